@@ -0,0 +1,78 @@
+//! Heap allocations per monitor read on the calling thread, with the frame built per call (`monitor_read`)
+//! and with the frame and the response buffer kept (`monitor_read_with`).
+//!
+//! `cargo bench --bench monitor_read`
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::time::Instant;
+use slmp::*;
+use slmp::testing::MockServer;
+
+const TICKS: usize = 1000;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts the allocations of each thread, so that those of the mock server (on other threads) are left out.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|x| x.set(x.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|x| x.set(x.get() + 1));
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|x| x.get())
+}
+
+fn main() {
+    // The server runs on a runtime of its own threads, the client on this thread.
+    let server_runtime = tokio::runtime::Runtime::new().unwrap();
+    let server = server_runtime.block_on(MockServer::start(CPU::R)).unwrap();
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+
+    runtime.block_on(async {
+        let client = SLMPClient::new(server.connection_props());
+        client.connect().await.unwrap();
+        let devices: Vec<TypedDevice> = (0..32)
+            .map(|i| TypedDevice { device: Device { device_type: DeviceType::D, address: i * 2 }, data_type: DataType::U32 })
+            .collect();
+        let monitor_list = client.monitor_register(&devices).await.unwrap();
+
+        let (start, count) = (Instant::now(), allocations());
+        for _ in 0..TICKS {
+            client.monitor_read(&monitor_list).await.unwrap();
+        }
+        report("monitor_read", start, allocations() - count);
+
+        let mut buffer = MonitorReadBuffer::new();
+        client.monitor_read_with(&monitor_list, &mut buffer).await.unwrap();
+        let (start, count) = (Instant::now(), allocations());
+        for _ in 0..TICKS {
+            client.monitor_read_with(&monitor_list, &mut buffer).await.unwrap();
+        }
+        report("monitor_read_with", start, allocations() - count);
+
+        client.close().await;
+    });
+}
+
+fn report(name: &str, start: Instant, allocations: usize) {
+    println!("{name:<20} {:>8.1} allocations/tick {:>10.1?}/tick", allocations as f64 / TICKS as f64, start.elapsed() / TICKS as u32);
+}
@@ -13,9 +13,10 @@ async fn main() {
         io_id: 0x03ff,
         area_id: 0x00,
         cpu_timer: 0x0010,
+        remote_password: None,
     };
 
-    let mut client = SLMPClient::new(connection_props);
+    let client = SLMPClient::new(connection_props);
     client.connect().await.unwrap();
 
     // Word data
@@ -1,4 +1,4 @@
-use slmp::{CPU, DataType, Device, DeviceType, MonitorRequest, SLMP4EConnectionProps, SLMPConnectionManager, TypedDevice};
+use slmp::{CPU, ChangeFilter, DataType, Device, DeviceType, MonitorRequest, SLMP4EConnectionProps, SLMPConnectionManager, TypedDevice};
 
 
 #[tokio::main]
@@ -14,6 +14,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         io_id: 0x03ff,
         area_id: 0x00,
         cpu_timer: 0x0010,
+        remote_password: None,
     };
 
     let manager = SLMPConnectionManager::new();
@@ -35,24 +36,32 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             monitor_device: TypedDevice {
                 device: Device { device_type: DeviceType::D, address: 4001 },
                 data_type: DataType::U16
-            }
+            },
+            change_filter: ChangeFilter::Always,
+            cycle_ms: None,
         },
         MonitorRequest {
             connection_props: &connection_props,
             monitor_device: TypedDevice {
                 device: Device { device_type: DeviceType::D, address: 4002 },
                 data_type: DataType::U16
-            }
+            },
+            change_filter: ChangeFilter::Always,
+            cycle_ms: None,
         },
         MonitorRequest {
             connection_props: &connection_props,
             monitor_device: TypedDevice {
                 device: Device { device_type: DeviceType::D, address: 4003 },
                 data_type: DataType::U16
-            }
+            },
+            change_filter: ChangeFilter::Always,
+            cycle_ms: None,
         },
     ];
-    manager.register_monitor_targets(&target_devices).await?;
+    for ret in manager.register_monitor_targets(&target_devices).await? {
+        ret.monitored_devices?;
+    }
 
     tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
 
@@ -2,6 +2,7 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::task::JoinHandle;
 use std::error::Error;
+use slmp::frame::{SlmpCommandPacket, SlmpReturnPacket};
 
 const PROXY_LISTEN_ADDR: &str = "127.0.0.1:8000";
 const TARGET_ADDR: &str = "192.168.3.10:5007";
@@ -96,186 +97,3 @@ fn proxy_connection(
     })
 }
 
-
-#[derive(Debug)]
-pub struct SlmpParseError(String);
-
-impl std::fmt::Display for SlmpParseError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "SLMP parse error: {}", self.0)
-    }
-}
-
-impl std::error::Error for SlmpParseError {}
-
-pub struct SlmpCommandPacket {
-    pub request_code: u16,
-    pub serial_id: u16,
-    pub network_id: u8,
-    pub pc_id: u8,
-    pub io_id: u16,
-    pub area_id: u8,
-    pub data_len: u16,
-    pub cpu_timer: u16,
-    pub command: u16,
-    pub subcommand: u16,
-    pub data: Vec<u8>,
-}
-
-impl TryFrom<&[u8]> for SlmpCommandPacket {
-
-    type Error = SlmpParseError;
-
-    fn try_from(data: &[u8]) -> Result<Self, SlmpParseError> {
-
-        const FIXED_FRAME_LEN: usize = 13;
-        const CMDFRAME_PREFIX_FIXED_LEN: usize = 19;
-
-        let packet_len = data.len();
-
-        if packet_len < FIXED_FRAME_LEN {
-            return Err(SlmpParseError("Data too short for SLMP header".to_string()));
-        }
-
-        let data_len = u16::from_le_bytes([data[11], data[12]]);
-
-        if data_len as usize != packet_len - FIXED_FRAME_LEN {
-            return Err(SlmpParseError("Received Invalid Data Frame".to_string()));
-        }
-
-        let request_code = u16::from_le_bytes([data[0], data[1]]);
-        let serial_id = u16::from_le_bytes([data[2], data[3]]);
-        let network_id = data[6];
-        let pc_id = data[7];
-        let io_id = u16::from_le_bytes([data[8], data[9]]);
-        let area_id = data[10];
-        let cpu_timer = u16::from_le_bytes([data[13], data[14]]);
-        let command: u16 = u16::from_le_bytes([data[15], data[16]]);
-        let subcommand: u16 = u16::from_le_bytes([data[17], data[18]]);
-        let data = data[CMDFRAME_PREFIX_FIXED_LEN..].to_vec();
-
-        Ok(SlmpCommandPacket {
-            request_code,
-            serial_id,
-            network_id,
-            pc_id,
-            io_id,
-            area_id,
-            data_len,
-            cpu_timer,
-            command,
-            subcommand,
-            data,
-        })
-    }
-}
-
-impl std::fmt::Display for SlmpCommandPacket {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f,
-            "\
-                Request_code: 0x{:04X}\n\
-                Serial ID: 0x{:04X}\n\
-                Network ID: 0x{:02X}\n\
-                PC ID: 0x{:X}\n\
-                Area ID: 0x{:X}\n\
-                IO ID: 0x{:04X}\n\
-                Data Length: 0x{:04X}\n\
-                Command: 0x{:04X}\n\
-                Subcommand: 0x{:04X}\n\
-                Data: {:02X?}\
-            ",
-            self.request_code,
-            self.serial_id,
-            self.network_id,
-            self.pc_id,
-            self.io_id,
-            self.area_id,
-            self.data_len,
-            self.command,
-            self.subcommand,
-            self.data
-        )
-    }
-}
-
-pub struct SlmpReturnPacket {
-    pub request_code: u16,
-    pub serial_id: u16,
-    pub network_id: u8,
-    pub pc_id: u8,
-    pub io_id: u16,
-    pub area_id: u8,
-    pub data_len: u16,
-    pub error: u16,
-    pub data: Vec<u8>,
-}
-
-impl TryFrom<&[u8]> for SlmpReturnPacket {
-
-    type Error = SlmpParseError;
-
-    fn try_from(data: &[u8]) -> Result<Self, SlmpParseError> {
-
-        const FIXED_FRAME_LEN: usize = 13;
-        const RECVFRAME_PREFIX_FIXED_LEN: usize = 15;
-
-        let packet_len = data.len();
-
-        if packet_len < FIXED_FRAME_LEN {
-            return Err(SlmpParseError("Data too short for SLMP header".to_string()));
-        }
-
-        let data_len = u16::from_le_bytes([data[11], data[12]]);
-        if data_len as usize != packet_len - FIXED_FRAME_LEN {
-            return Err(SlmpParseError("Received Invalid Data Frame".to_string()));
-        }
-
-        let request_code = u16::from_le_bytes([data[0], data[1]]);
-        let serial_id = u16::from_le_bytes([data[2], data[3]]);
-        let network_id = data[6];
-        let pc_id = data[7];
-        let io_id = u16::from_le_bytes([data[8], data[9]]);
-        let area_id = data[10];
-        let error = u16::from_le_bytes([data[13], data[14]]);
-        let data = data[RECVFRAME_PREFIX_FIXED_LEN..].to_vec();
-
-        Ok(SlmpReturnPacket {
-            request_code,
-            serial_id,
-            network_id,
-            pc_id,
-            io_id,
-            area_id,
-            data_len,
-            error,
-            data,
-        })
-    }
-}
-
-impl std::fmt::Display for SlmpReturnPacket {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f,
-            "\
-                Request_code: 0x{:04X}\n\
-                Serial ID: 0x{:04X}\n\
-                Network ID: 0x{:02X}\n\
-                PC ID: 0x{:X}\n\
-                Area ID: 0x{:X}\n\
-                IO ID: 0x{:04X}\n\
-                Data Length: 0x{:04X}\n\
-                Error: 0x{:02x}\n\
-                Data: {:02X?}\
-            ",
-            self.request_code,
-            self.serial_id,
-            self.network_id,
-            self.pc_id,
-            self.io_id,
-            self.area_id,
-            self.data_len,
-            self.error, self.data
-        )
-    }
-}
@@ -0,0 +1,213 @@
+//! HTTP/WebSocket gateway on `SLMPConnectionManager`, speaking the JSON of the feature `json-api`.
+//!
+//! - `POST /connections` connects a PLC and registers its monitor devices:
+//!   `{"name": "line1", "connectionProps": {...}, "cycleMs": 100, "monitor": [{"device": {"deviceType": "D", "address": 100}, "dataType": "U16"}]}`
+//! - `GET /read?plc=line1&device=D100&type=U16` reads a device and returns a `slmp::json::ReadResponse`.
+//! - `POST /write` writes a `slmp::json::WriteRequest` with the name of the connection: `{"plc": "line1", "type": "Random", "data": [...]}`
+//! - `GET /monitor` is a WebSocket sending the `PLCData` of the cyclic reads, a JSON text message each.
+//!
+//! Reads and writes go through `operate_worker`, so they run between the monitor reads of the connection.
+//! Errors are answered with an HTTP status and `{"error": "...", "endCode": "..."}`.
+//!
+//! `cargo run --example http_gateway`, then e.g. `curl 'localhost:3000/read?plc=line1&device=D100&type=U16'`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use slmp::json::{ReadRequest, ReadResponse, WriteRequest};
+use slmp::{ChangeFilter, DataType, Device, MonitorRequest, NotConnectedYet, PLCData, SLMP4EConnectionProps, SLMPConnectionManager, SLMPEndCode, TypedDevice};
+use tokio::sync::{RwLock, broadcast};
+
+const LISTEN_ADDR: &str = "0.0.0.0:3000";
+/// `PLCData` kept for WebSocket clients that fall behind; older values are skipped.
+const MONITOR_CAPACITY: usize = 1024;
+
+struct Gateway {
+    manager: SLMPConnectionManager,
+    /// Connections by the name given in `POST /connections`.
+    connections: RwLock<HashMap<String, SLMP4EConnectionProps>>,
+    monitor: broadcast::Sender<PLCData>,
+}
+
+impl Gateway {
+    async fn connection_props(&self, name: &str) -> Result<SLMP4EConnectionProps, ApiError> {
+        self.connections.read().await.get(name).cloned()
+            .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, format!("No connection named {name}"), None))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConnectionRequest {
+    name: String,
+    connection_props: SLMP4EConnectionProps,
+    #[serde(default = "default_cycle_ms")]
+    cycle_ms: u64,
+    #[serde(default)]
+    monitor: Vec<TypedDevice>,
+}
+
+const fn default_cycle_ms() -> u64 {
+    100
+}
+
+#[derive(Deserialize)]
+struct ReadQuery {
+    plc: String,
+    /// e.g. `D100`, `X1F`
+    device: String,
+    /// e.g. `U16`, `F32`, `String(10)`
+    #[serde(rename = "type")]
+    data_type: String,
+}
+
+#[derive(Deserialize)]
+struct WriteBody {
+    plc: String,
+    #[serde(flatten)]
+    request: WriteRequest,
+}
+
+/// Status, message and the name of the end code if the PLC answered with one.
+struct ApiError(StatusCode, String, Option<&'static str>);
+
+impl From<std::io::Error> for ApiError {
+    fn from(error: std::io::Error) -> Self {
+        use std::io::ErrorKind;
+
+        let end_code = SLMPEndCode::from_io_error(&error);
+        let status = if NotConnectedYet::from_io_error(&error).is_some() {
+            StatusCode::SERVICE_UNAVAILABLE
+        } else if end_code.is_some() {
+            StatusCode::BAD_GATEWAY
+        } else {
+            match error.kind() {
+                ErrorKind::InvalidInput => StatusCode::BAD_REQUEST,
+                ErrorKind::AddrNotAvailable => StatusCode::NOT_FOUND,
+                ErrorKind::AlreadyExists => StatusCode::CONFLICT,
+                ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+                ErrorKind::TimedOut => StatusCode::GATEWAY_TIMEOUT,
+                ErrorKind::InvalidData => StatusCode::BAD_GATEWAY,
+                ErrorKind::ConnectionRefused | ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::NotConnected
+                    | ErrorKind::BrokenPipe | ErrorKind::HostUnreachable | ErrorKind::NetworkUnreachable => StatusCode::SERVICE_UNAVAILABLE,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        };
+        Self(status, error.to_string(), end_code.map(|x| x.name()))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let ApiError(status, message, end_code) = self;
+        (status, Json(serde_json::json!({ "error": message, "endCode": end_code }))).into_response()
+    }
+}
+
+async fn connect(State(gateway): State<Arc<Gateway>>, Json(request): Json<ConnectionRequest>) -> Result<StatusCode, ApiError> {
+    let sender = gateway.monitor.clone();
+    let cyclic_task = move |data: Vec<PLCData>| {
+        let sender = sender.clone();
+        async move {
+            // No WebSocket client is not an error.
+            for x in data {
+                let _ = sender.send(x);
+            }
+            Ok::<(), std::io::Error>(())
+        }
+    };
+    gateway.manager.connect_named(&request.name, &request.connection_props, cyclic_task, request.cycle_ms).await?;
+    gateway.connections.write().await.insert(request.name.clone(), request.connection_props.clone());
+
+    let targets: Vec<MonitorRequest> = request.monitor.iter()
+        .map(|&monitor_device| MonitorRequest {
+            connection_props: &request.connection_props,
+            monitor_device,
+            change_filter: ChangeFilter::OnChange { deadband: 0.0 },
+            cycle_ms: None,
+        })
+        .collect();
+    if !targets.is_empty() {
+        for ret in gateway.manager.register_monitor_targets(&targets).await? {
+            ret.monitored_devices?;
+        }
+    }
+
+    Ok(StatusCode::CREATED)
+}
+
+async fn read(State(gateway): State<Arc<Gateway>>, Query(query): Query<ReadQuery>) -> Result<Json<ReadResponse>, ApiError> {
+    let start_device: Device = query.device.parse()?;
+    let data_type: DataType = query.data_type.parse()?;
+    let connection_props = gateway.connection_props(&query.plc).await?;
+
+    let request = ReadRequest::Bulk { start_device, device_num: 1, data_type };
+    let response = gateway.manager
+        .operate_worker(&connection_props, async move |client| client.execute_read(&request).await)
+        .await?;
+    Ok(Json(response))
+}
+
+async fn write(State(gateway): State<Arc<Gateway>>, Json(body): Json<WriteBody>) -> Result<StatusCode, ApiError> {
+    let connection_props = gateway.connection_props(&body.plc).await?;
+    let request = body.request;
+    gateway.manager
+        .operate_worker(&connection_props, async move |client| client.execute_write(&request).await)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn monitor(State(gateway): State<Arc<Gateway>>, ws: WebSocketUpgrade) -> Response {
+    let receiver = gateway.monitor.subscribe();
+    ws.on_upgrade(move |socket| stream_monitor(socket, receiver))
+}
+
+async fn stream_monitor(mut socket: WebSocket, mut receiver: broadcast::Receiver<PLCData>) {
+    loop {
+        let data = match receiver.recv().await {
+            Ok(data) => data,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        let Ok(text) = serde_json::to_string(&data) else {
+            continue;
+        };
+        if socket.send(Message::Text(text.into())).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+
+    let gateway = Arc::new(Gateway {
+        manager: SLMPConnectionManager::new(),
+        connections: RwLock::new(HashMap::new()),
+        monitor: broadcast::channel(MONITOR_CAPACITY).0,
+    });
+
+    let app = Router::new()
+        .route("/connections", post(connect))
+        .route("/read", get(read))
+        .route("/write", post(write))
+        .route("/monitor", get(monitor))
+        .with_state(gateway.clone());
+
+    let listener = tokio::net::TcpListener::bind(LISTEN_ADDR).await?;
+    println!("Listening on {LISTEN_ADDR}");
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async { tokio::signal::ctrl_c().await.unwrap_or_default() })
+        .await?;
+
+    gateway.manager.clear().await;
+
+    Ok(())
+}
@@ -13,9 +13,10 @@ async fn main() {
         io_id: 0x03ff,
         area_id: 0x00,
         cpu_timer: 0x0010,
+        remote_password: None,
     };
 
-    let mut client = SLMPClient::new(connection_props);
+    let client = SLMPClient::new(connection_props);
     client.connect().await.unwrap();
 
     // Word data
@@ -34,10 +35,10 @@ async fn main() {
     ];
 
     let wr_data = [
-        DeviceData{device: devices[0], data: data[0]},
-        DeviceData{device: devices[1], data: data[1]},
-        DeviceData{device: devices[2], data: data[2]},
-        DeviceData{device: devices[3], data: data[3]},
+        DeviceData{device: devices[0], data: data[0].clone()},
+        DeviceData{device: devices[1], data: data[1].clone()},
+        DeviceData{device: devices[2], data: data[2].clone()},
+        DeviceData{device: devices[3], data: data[3].clone()},
     ];
     client.random_write(&wr_data).await.unwrap();
 
@@ -0,0 +1,194 @@
+//! Bridge PLC devices to an MQTT broker with rumqttc, publishing the values that changed.
+//!
+//! - `plc/<addr>/<device>` (e.g. `plc/192.168.3.10:5007/D100`): `{"timestamp": <ms>, "data": {"type": "U16", "value": 1}}` for each changed value.
+//! - `plc/<addr>/status` (retained): `online` (birth) when the connection is up, `offline` (death) when it is lost, from the `ManagerEvent`s.
+//! - `plc/bridge/status` (retained): `online` while the bridge is connected to the broker, `offline` as its last will.
+//!
+//! A PLC offline at start is tried again until it is connected; after that the keep-alive reconnects it.
+//! rumqttc reconnects to the broker on the next poll after an error, and the births are published again then.
+//! While the broker is away, the oldest reads are dropped instead of holding the monitor loops (see `set_task_queue`).
+//!
+//! `cargo r --example mqtt_bridge -- mqtt_bridge.json`, with a configuration such as
+//!
+//! ```json
+//! {
+//!   "mqtt": {"host": "localhost", "port": 1883, "clientId": "slmp-bridge"},
+//!   "connections": [{
+//!     "name": "line1",
+//!     "connectionProps": {"ip": "192.168.3.10", "port": 5007, "cpu": "R", "serialId": 1, "networkId": 0, "pcId": 255, "ioId": 1023, "areaId": 0, "cpuTimer": 16},
+//!     "cycleMs": 100,
+//!     "monitorDevices": ["D100:U16", "D200:F32", "M0:Bool"]
+//!   }]
+//! }
+//! ```
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use rumqttc::{AsyncClient, Event, EventLoop, LastWill, MqttOptions, Packet, QoS};
+use serde::Deserialize;
+use slmp::{BackPressure, ChangeFilter, ConnectionConfig, ManagerEvent, MonitorRequest, PLCData, SLMPConnectionManager, TypedDevice};
+use tokio::sync::broadcast;
+
+const BRIDGE_STATUS_TOPIC: &str = "plc/bridge/status";
+/// Requests waiting for the MQTT event loop.
+const MQTT_CAPACITY: usize = 1024;
+const MQTT_KEEP_ALIVE: Duration = Duration::from_secs(5);
+/// Reads of a connection waiting to be published.
+const TASK_QUEUE_CAPACITY: usize = 64;
+/// Echo to a PLC after this idle time, which detects a lost link and reconnects.
+const PLC_KEEP_ALIVE: Duration = Duration::from_secs(1);
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+/// Changes of F32/F64 values within it are not published.
+const DEADBAND: f64 = 0.01;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BridgeConfig {
+    mqtt: MqttConfig,
+    connections: Vec<ConnectionConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MqttConfig {
+    host: String,
+    port: u16,
+    client_id: String,
+}
+
+fn status_topic(socket_addr: SocketAddr) -> String {
+    format!("plc/{socket_addr}/status")
+}
+
+/// Queue a retained status without waiting, as the MQTT event loop itself publishes them.
+fn publish_status(mqtt: &AsyncClient, topic: &str, online: bool) {
+    let payload = if online { "online" } else { "offline" };
+    if let Err(e) = mqtt.try_publish(topic, QoS::AtLeastOnce, true, payload) {
+        eprintln!("MQTT: {topic} not published: {e}");
+    }
+}
+
+async fn publish_values(mqtt: &AsyncClient, data: Vec<PLCData>) -> std::io::Result<()> {
+    let timestamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_millis();
+    for x in data {
+        let topic = format!("plc/{}/{}", x.socket_addr, x.device_data.device);
+        let payload = serde_json::json!({ "timestamp": timestamp, "data": x.device_data.data });
+        mqtt.publish(topic, QoS::AtLeastOnce, false, payload.to_string()).await.map_err(std::io::Error::other)?;
+    }
+    Ok(())
+}
+
+/// Drive the MQTT connection. After each (re)connection, the bridge and the PLC connections announce their state again.
+async fn mqtt_loop(mut event_loop: EventLoop, mqtt: AsyncClient, manager: Arc<SLMPConnectionManager>) {
+    loop {
+        match event_loop.poll().await {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                println!("MQTT: connected");
+                publish_status(&mqtt, BRIDGE_STATUS_TOPIC, true);
+                for (key, status) in manager.status_all().await {
+                    publish_status(&mqtt, &status_topic(key.socket_addr), status.connected);
+                }
+            },
+            Ok(_) => {},
+            Err(e) => {
+                eprintln!("MQTT: {e}");
+                tokio::time::sleep(RETRY_INTERVAL).await;
+            },
+        }
+    }
+}
+
+/// Connect a PLC, trying again while it is offline, and register its devices to be published on change.
+async fn connect_plc(manager: Arc<SLMPConnectionManager>, mqtt: AsyncClient, connection: ConnectionConfig) {
+    let devices: Vec<TypedDevice> = match connection.monitor_devices.iter().map(|x| x.parse()).collect() {
+        Ok(devices) => devices,
+        Err(e) => return eprintln!("{}: {e}", connection.name),
+    };
+
+    let cyclic_task = move |data: Vec<PLCData>| {
+        let mqtt = mqtt.clone();
+        async move { publish_values(&mqtt, data).await }
+    };
+
+    let props = &connection.connection_props;
+    while let Err(e) = manager.connect_with_keep_alive(props, cyclic_task.clone(), connection.cycle_ms, PLC_KEEP_ALIVE).await {
+        eprintln!("{}: {e}, retrying in {RETRY_INTERVAL:?}", connection.name);
+        tokio::time::sleep(RETRY_INTERVAL).await;
+    }
+    if let Err(e) = manager.set_name(&connection.name, props).await {
+        eprintln!("{}: {e}", connection.name);
+    }
+
+    let targets: Vec<MonitorRequest> = devices
+        .into_iter()
+        .map(|monitor_device| MonitorRequest {
+            connection_props: props,
+            monitor_device,
+            change_filter: ChangeFilter::OnChange { deadband: DEADBAND },
+            cycle_ms: None,
+        })
+        .collect();
+    let ret = match manager.register_monitor_targets(&targets).await {
+        Ok(ret) => ret,
+        Err(e) => return eprintln!("{}: {e}", connection.name),
+    };
+    for x in ret {
+        if let Err(e) = x.monitored_devices {
+            eprintln!("{}: {e}", connection.name);
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+
+    let path = std::env::args().nth(1).unwrap_or_else(|| String::from("mqtt_bridge.json"));
+    let config: BridgeConfig = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+
+    let mut options = MqttOptions::new(&config.mqtt.client_id, &config.mqtt.host, config.mqtt.port);
+    options.set_keep_alive(MQTT_KEEP_ALIVE);
+    options.set_last_will(LastWill::new(BRIDGE_STATUS_TOPIC, "offline", QoS::AtLeastOnce, true));
+    let (mqtt, event_loop) = AsyncClient::new(options, MQTT_CAPACITY);
+
+    let manager = Arc::new(SLMPConnectionManager::new());
+    manager.set_task_queue(TASK_QUEUE_CAPACITY, BackPressure::DropOldest);
+    // Subscribed before connecting, not to miss the first `Connected`.
+    let mut events = manager.events();
+
+    tokio::spawn(mqtt_loop(event_loop, mqtt.clone(), manager.clone()));
+    for connection in config.connections {
+        tokio::spawn(connect_plc(manager.clone(), mqtt.clone(), connection));
+    }
+
+    // Birth and death of the PLC connections.
+    loop {
+        let event = tokio::select! {
+            event = events.recv() => event,
+            _ = tokio::signal::ctrl_c() => break,
+        };
+        match event {
+            Ok(ManagerEvent::Connected { key } | ManagerEvent::Reconnected { key }) => {
+                println!("{}: online", key.socket_addr);
+                publish_status(&mqtt, &status_topic(key.socket_addr), true);
+            },
+            Ok(ManagerEvent::Disconnected { key, reason }) => {
+                println!("{}: offline ({reason})", key.socket_addr);
+                publish_status(&mqtt, &status_topic(key.socket_addr), false);
+            },
+            Ok(_) => {},
+            Err(broadcast::error::RecvError::Lagged(n)) => eprintln!("{n} events lost"),
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+
+    for key in manager.status_all().await.into_keys() {
+        publish_status(&mqtt, &status_topic(key.socket_addr), false);
+    }
+    publish_status(&mqtt, BRIDGE_STATUS_TOPIC, false);
+    manager.clear().await;
+    mqtt.disconnect().await?;
+
+    Ok(())
+}
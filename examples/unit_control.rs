@@ -13,9 +13,10 @@ async fn main() {
         io_id: 0x03ff,
         area_id: 0x00,
         cpu_timer: 0x0010,
+        remote_password: None,
     };
 
-    let mut client = SLMPClient::new(connection_props);
+    let client = SLMPClient::new(connection_props);
     client.connect().await.unwrap();
 
     #[allow(unused)]
@@ -23,8 +24,8 @@ async fn main() {
         tokio::time::sleep(tokio::time::Duration::from_millis(500)).await
     };
 
-    let cpu_type = client.get_cpu_type().await.unwrap();
-    println!("cpu type: {cpu_type}");
+    let cpu_type = client.verify_cpu_type().await.unwrap();
+    println!("cpu type: {} (model code 0x{:04X})", cpu_type.name, cpu_type.code);
 
     client.echo().await.unwrap();
     println!("Echo succeess");
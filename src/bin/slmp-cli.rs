@@ -0,0 +1,294 @@
+//! Ad-hoc reads, writes and diagnostics of a PLC from the command line (feature `cli`).
+//!
+//! ```text
+//! slmp-cli --ip 192.168.3.10 --cpu R read D100..D120 --type u16
+//! slmp-cli --ip 192.168.3.10 --cpu R write M100 true
+//! slmp-cli --ip 192.168.3.10 --cpu R --json status
+//! slmp-cli --ip 192.168.3.10 --cpu Q list-files 4:
+//! ```
+//!
+//! With `--json`, read results are a `slmp::json::ReadResponse`, and errors `{"error": "...", "endCode": "..."}`.
+//! Exit status: 0 on success, 1 on an error, 2 on invalid arguments, 3 when the PLC answered with an end code.
+
+use std::process::ExitCode;
+use std::time::{Instant, SystemTime};
+
+use clap::{Parser, Subcommand};
+use slmp::json::{ReadRequest, WriteRequest};
+use slmp::{CPU, DataType, Device, FileDrive, FileInfo, PLCDateTime, PLCString, RemotePassword, SLMP4EConnectionProps, SLMPClient, SLMPEndCode, TypedData};
+
+/// Exit status on invalid arguments, e.g. a device string that does not parse.
+const EXIT_INVALID_INPUT: u8 = 2;
+/// Exit status when the PLC answered with an end code.
+const EXIT_END_CODE: u8 = 3;
+
+#[derive(Parser)]
+#[command(name = "slmp-cli", version, about = "Read, write and diagnose a PLC over SLMP (4E frame)")]
+struct Cli {
+    /// IP address or host name of the CPU
+    #[arg(long)]
+    ip: String,
+    #[arg(long, default_value_t = 5007)]
+    port: u16,
+    /// CPU series: Q, R, L, F or A
+    #[arg(long, default_value = "R")]
+    cpu: CPU,
+    #[arg(long, default_value_t = 0x00)]
+    network_id: u8,
+    #[arg(long, default_value_t = 0xff)]
+    pc_id: u8,
+    #[arg(long, default_value_t = 0x03ff)]
+    io_id: u16,
+    /// Remote password, unlocked on connection and locked again on exit
+    #[arg(long)]
+    password: Option<String>,
+    /// Print the results in JSON
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Read a device (`D100`) or a range of devices (`D100..D120`, `D100..=D119`)
+    Read {
+        devices: String,
+        /// e.g. u16, f32, string(10) [default: bool for bit devices, u16 for word devices]
+        #[arg(long = "type", short = 't')]
+        data_type: Option<DataType>,
+    },
+    /// Write values to consecutive devices from a device, e.g. `write D100 1 -2 3 --type i16`
+    Write {
+        device: Device,
+        #[arg(required = true, allow_negative_numbers = true)]
+        values: Vec<String>,
+        /// e.g. u16, f32, string(10) [default: bool for bit devices, u16 for word devices]
+        #[arg(long = "type", short = 't')]
+        data_type: Option<DataType>,
+    },
+    /// Model, clock and self-diagnostic errors of the CPU
+    Status {
+        /// Error history entries to read (R/F; Q/L report the latest error only)
+        #[arg(long, default_value_t = 16)]
+        errors: usize,
+    },
+    /// Send an echo and print the round-trip time
+    Echo,
+    /// Model name and code of the CPU
+    CpuType,
+    /// Files in the root directory of a drive, e.g. `4:`
+    ListFiles {
+        drive: String,
+        #[arg(long, default_value_t = 256)]
+        count: usize,
+    },
+}
+
+impl Cli {
+    fn connection_props(&self) -> SLMP4EConnectionProps {
+        SLMP4EConnectionProps {
+            ip: self.ip.clone(),
+            port: self.port,
+            cpu: self.cpu,
+            serial_id: 0x0001,
+            network_id: self.network_id,
+            pc_id: self.pc_id,
+            io_id: self.io_id,
+            area_id: 0x00,
+            cpu_timer: 0x0010,
+            remote_password: self.password.clone().map(RemotePassword::new),
+        }
+    }
+}
+
+fn invalid_input(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
+
+fn default_type(device: Device) -> DataType {
+    if device.device_type.is_bit_device() { DataType::Bool } else { DataType::U16 }
+}
+
+/// `D100` (a value), `D100..D120` (D120 excluded) or `D100..=D119`, as a bulk read of `data_type`.
+fn parse_devices(s: &str, data_type: Option<DataType>) -> std::io::Result<ReadRequest> {
+    let Some((start, end)) = s.split_once("..") else {
+        let start_device: Device = s.parse()?;
+        let data_type = data_type.unwrap_or(default_type(start_device));
+        return Ok(ReadRequest::Bulk { start_device, device_num: 1, data_type });
+    };
+
+    let (end, inclusive) = match end.strip_prefix('=') {
+        Some(end) => (end, true),
+        None => (end, false),
+    };
+    let start_device: Device = start.parse()?;
+    let end_device: Device = end.parse()?;
+    if end_device.device_type != start_device.device_type {
+        return Err(invalid_input(format!("{start_device} and {end_device} are not of the same device")));
+    }
+    let count: usize = (end_device.address + inclusive as usize).saturating_sub(start_device.address);
+
+    let data_type = data_type.unwrap_or(default_type(start_device));
+    let device_num: usize = Device::range(start_device, count).typed_devices(data_type).len();
+    if device_num == 0 {
+        return Err(invalid_input(format!("{s} holds no {data_type}")));
+    }
+    Ok(ReadRequest::Bulk { start_device, device_num, data_type })
+}
+
+fn parse_value(s: &str, data_type: DataType) -> std::io::Result<TypedData> {
+    let invalid = || invalid_input(format!("Invalid {data_type} value: {s}"));
+
+    if let Some((device_size, encoding)) = data_type.as_string() {
+        return Ok(TypedData::String(PLCString::encode(s, device_size, encoding)?));
+    }
+    let value = match data_type {
+        DataType::Bool => match s.to_ascii_lowercase().as_str() {
+            "true" | "on" | "1" => TypedData::Bool(true),
+            "false" | "off" | "0" => TypedData::Bool(false),
+            _ => return Err(invalid()),
+        },
+        DataType::BitArray16 => {
+            let word: u16 = s.parse().map_err(|_| invalid())?;
+            TypedData::BitArray16(core::array::from_fn(|i| word >> i & 1 == 1))
+        },
+        DataType::U16 => TypedData::U16(s.parse().map_err(|_| invalid())?),
+        DataType::I16 => TypedData::I16(s.parse().map_err(|_| invalid())?),
+        DataType::U32 => TypedData::U32(s.parse().map_err(|_| invalid())?),
+        DataType::I32 => TypedData::I32(s.parse().map_err(|_| invalid())?),
+        DataType::U64 => TypedData::U64(s.parse().map_err(|_| invalid())?),
+        DataType::I64 => TypedData::I64(s.parse().map_err(|_| invalid())?),
+        DataType::F32 => TypedData::F32(s.parse().map_err(|_| invalid())?),
+        DataType::F64 => TypedData::F64(s.parse().map_err(|_| invalid())?),
+        DataType::Bcd16 => TypedData::Bcd16(s.parse().map_err(|_| invalid())?),
+        DataType::Bcd32 => TypedData::Bcd32(s.parse().map_err(|_| invalid())?),
+        DataType::String(_) | DataType::AsciiString(_) | DataType::WString(_) => unreachable!(),
+    };
+    value.check_range()?;
+    Ok(value)
+}
+
+/// The drive number with or without the colon, e.g. `4:`.
+fn parse_drive(s: &str, cpu: &CPU) -> std::io::Result<FileDrive> {
+    let drive_no: u16 = s.trim_end_matches(':').parse().map_err(|_| invalid_input(format!("Invalid drive: {s}")))?;
+    FileDrive::from_drive_no(cpu, drive_no).ok_or_else(|| invalid_input(format!("No drive {drive_no}: on {cpu:?} CPU")))
+}
+
+fn format_date_time(x: &PLCDateTime) -> String {
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", x.year, x.month, x.day, x.hour, x.minute, x.second)
+}
+
+fn file_json(x: &FileInfo) -> serde_json::Value {
+    let modified: u64 = x.modified.duration_since(SystemTime::UNIX_EPOCH).map_or(0, |x| x.as_secs());
+    serde_json::json!({
+        "name": x.name,
+        "size": x.size,
+        "modified": modified,
+        "readOnly": x.is_read_only(),
+        "directory": x.is_directory(),
+    })
+}
+
+async fn run(cli: &Cli, client: &SLMPClient) -> std::io::Result<()> {
+    match &cli.command {
+        Command::Read { devices, data_type } => {
+            let request = parse_devices(devices, *data_type)?;
+            let response = client.execute_read(&request).await?;
+            if cli.json {
+                println!("{}", serde_json::to_string(&response)?);
+            } else {
+                for x in response.data {
+                    println!("{}\t{}", x.device, x.data);
+                }
+            }
+        },
+        Command::Write { device, values, data_type } => {
+            let data_type = data_type.unwrap_or(default_type(*device));
+            let data: Vec<TypedData> = values.iter().map(|x| parse_value(x, data_type)).collect::<std::io::Result<_>>()?;
+            client.execute_write(&WriteRequest::Bulk { start_device: *device, data }).await?;
+        },
+        Command::Status { errors } => {
+            let cpu_type = client.get_cpu_type().await?;
+            let clock = client.read_clock().await?;
+            let errors = client.read_error_history(*errors).await?;
+            if cli.json {
+                println!("{}", serde_json::json!({ "cpuType": cpu_type, "clock": clock, "errors": errors }));
+            } else {
+                println!("Model:  {} (0x{:04X})", cpu_type.name, cpu_type.code);
+                println!("Clock:  {}", format_date_time(&clock));
+                println!("Errors: {}", errors.len());
+                for x in &errors {
+                    let occurred_at = x.occurred_at.as_ref().map_or_else(|| String::from("-"), format_date_time);
+                    println!("  0x{:04X}  {occurred_at}", x.code);
+                }
+            }
+        },
+        Command::Echo => {
+            let start = Instant::now();
+            client.echo().await?;
+            let elapsed = start.elapsed();
+            if cli.json {
+                println!("{}", serde_json::json!({ "elapsedMs": elapsed.as_secs_f64() * 1000.0 }));
+            } else {
+                println!("Echo returned in {elapsed:?}");
+            }
+        },
+        Command::CpuType => {
+            let cpu_type = client.get_cpu_type().await?;
+            if cli.json {
+                println!("{}", serde_json::to_string(&cpu_type)?);
+            } else {
+                println!("{} (0x{:04X})", cpu_type.name, cpu_type.code);
+            }
+            cpu_type.check(&cli.cpu)?;
+        },
+        Command::ListFiles { drive, count } => {
+            let drive = parse_drive(drive, &cli.cpu)?;
+            let files = client.list_files(drive, 0, *count).await?;
+            if cli.json {
+                println!("{}", serde_json::Value::Array(files.iter().map(file_json).collect()));
+            } else {
+                for x in &files {
+                    let kind = if x.is_directory() { "<DIR>" } else if x.is_read_only() { "R" } else { "" };
+                    println!("{:>10}  {kind:<5}  {}", x.size, x.name);
+                }
+            }
+        },
+    }
+    Ok(())
+}
+
+fn report(error: &std::io::Error, json: bool) -> ExitCode {
+    let end_code = SLMPEndCode::from_io_error(error);
+    if json {
+        eprintln!("{}", serde_json::json!({ "error": error.to_string(), "endCode": end_code.map(|x| x.name()) }));
+    } else {
+        match end_code {
+            Some(x) => eprintln!("error: {} (0x{:04X})", x.name(), x.0),
+            None => eprintln!("error: {error}"),
+        }
+    }
+    match (end_code, error.kind()) {
+        (Some(_), _) => ExitCode::from(EXIT_END_CODE),
+        (None, std::io::ErrorKind::InvalidInput) => ExitCode::from(EXIT_INVALID_INPUT),
+        (None, _) => ExitCode::FAILURE,
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let client = SLMPClient::new(cli.connection_props());
+    if let Err(e) = client.connect().await {
+        return report(&e, cli.json);
+    }
+    let ret = run(&cli, &client).await;
+    client.close().await;
+
+    match ret {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => report(&e, cli.json),
+    }
+}
@@ -0,0 +1,520 @@
+//! Client without an async runtime (`blocking` feature), e.g. for a few reads at startup.
+//!
+//! ```rust,no_run
+//! use slmp::{CPU, DataType, Device, DeviceType, SLMP4EConnectionProps};
+//! use slmp::blocking::SLMPClient;
+//!
+//! # fn run(connection_props: SLMP4EConnectionProps) -> std::io::Result<()> {
+//! let mut client = SLMPClient::new(connection_props);
+//! client.connect()?;
+//! let data = client.bulk_read(Device { device_type: DeviceType::D, address: 100 }, 4, DataType::U16)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream};
+use std::time::{Duration, Instant};
+
+use crate::commands::unit_control;
+use crate::frame_1e::Frames1E;
+use crate::requests::{self, RECVFRAME_PREFIX_FIXED_LEN};
+use crate::{BUFSIZE, CONNECT_TIMEOUT, DEFAULT_RECV_TIMEOUT_SEC, DEFAULT_SEND_TIMEOUT_SEC, u16_to_bits};
+use crate::{BlockedDeviceData, BlockedDeviceDataOwned, CPU, CpuTypeInfo, DataType, Device, DeviceBlock, DeviceData, DeviceLimits, DeviceSnapshot, ReadCoalescing, RetriesExhausted, RetryPolicy, SLMP4EConnectionProps, SlmpReadable, SlmpWritable, TypedData, TypedDevice, TypedDeviceBlock, WordOrder};
+use crate::read_plan::ReadPlan;
+use crate::retry::RetryAction;
+use crate::snapshot::{self, SnapshotRead};
+use crate::verify::Verification;
+
+/// Same requests as `slmp::SLMPClient` over `std::net::TcpStream`. The requests take `&mut self`, one at a time.
+pub struct SLMPClient {
+    connection_props: SLMP4EConnectionProps,
+    stream: Option<TcpStream>,
+    /// Bytes received but not taken as a frame yet.
+    pending: Vec<u8>,
+    /// Translation into the 1E frame for `CPU::A`, as in `slmp::TcpTransport`.
+    frames_1e: Option<Frames1E>,
+    serial_id: u16,
+    send_timeout: Duration,
+    recv_timeout: Duration,
+    word_order: WordOrder,
+    device_limits: DeviceLimits,
+    retry_policy: RetryPolicy,
+    read_coalescing: ReadCoalescing,
+    read_only: bool,
+}
+
+impl SLMPClient {
+    pub fn new(connection_props: SLMP4EConnectionProps) -> Self {
+        Self {
+            serial_id: connection_props.serial_id,
+            device_limits: DeviceLimits::new(connection_props.cpu),
+            frames_1e: (connection_props.cpu == CPU::A).then(Frames1E::new),
+            connection_props,
+            stream: None,
+            pending: Vec::with_capacity(BUFSIZE),
+            send_timeout: DEFAULT_SEND_TIMEOUT_SEC,
+            recv_timeout: DEFAULT_RECV_TIMEOUT_SEC,
+            word_order: WordOrder::LowHigh,
+            retry_policy: RetryPolicy::NONE,
+            read_coalescing: ReadCoalescing::DEFAULT,
+            read_only: false,
+        }
+    }
+
+    pub fn set_send_timeout(&mut self, dur: Duration) {
+        self.send_timeout = dur;
+    }
+
+    pub fn set_recv_timeout(&mut self, dur: Duration) {
+        self.recv_timeout = dur;
+    }
+
+    /// Word order of 32/64-bit values in device reads and writes. See `slmp::SLMPClient::set_word_order`.
+    pub fn set_word_order(&mut self, order: WordOrder) {
+        self.word_order = order;
+    }
+
+    /// Largest addresses checked before a request is sent. See `slmp::SLMPClient::set_device_limits`.
+    pub fn set_device_limits(&mut self, limits: DeviceLimits) {
+        self.device_limits = limits;
+    }
+
+    /// Refuse the requests changing the CPU. See `slmp::SLMPClient::set_read_only`.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    pub const fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Retries on transient failures. See `slmp::SLMPClient::set_retry_policy`.
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = policy;
+    }
+
+    /// Limits of `optimized_read`. See `slmp::SLMPClient::set_read_coalescing`.
+    pub fn set_read_coalescing(&mut self, coalescing: ReadCoalescing) {
+        self.read_coalescing = coalescing;
+    }
+
+    /// Open the connection, and unlock the `remote_password` of the props if any. See `slmp::SLMPClient::connect`.
+    pub fn connect(&mut self) -> std::io::Result<()> {
+        self.shutdown();
+
+        let socket_addr: SocketAddr = SocketAddr::try_from(&self.connection_props)?;
+        let stream: TcpStream = TcpStream::connect_timeout(&socket_addr, CONNECT_TIMEOUT)?;
+        stream.set_write_timeout(Some(self.send_timeout))?;
+
+        self.stream = Some(stream);
+        self.pending.clear();
+
+        if let Some(password) = &self.connection_props.remote_password {
+            let cmd = unit_control::unlock_cpu(&self.connection_props.cpu, password.expose());
+            if let Err(e) = cmd.and_then(|cmd| self.request_response_once(&cmd)) {
+                self.shutdown();
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Lock the `remote_password` of the props if any, and close the connection.
+    pub fn close(&mut self) {
+        if self.stream.is_some()
+            && let Some(password) = &self.connection_props.remote_password
+            && let Ok(cmd) = unit_control::lock_cpu(&self.connection_props.cpu, password.expose())
+        {
+            let _ = self.request_response_once(&cmd);
+        }
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        if let Some(frames_1e) = &mut self.frames_1e {
+            frames_1e.clear();
+        }
+    }
+
+    fn request_response(&mut self, msg: &[u8]) -> std::io::Result<Vec<u8>> {
+        requests::check_read_only(self.read_only, msg)?;
+        let mut attempts: u32 = 1;
+        let mut result = self.request_response_once(msg);
+
+        while let Err(error) = &result {
+            let Some(action) = self.retry_policy.action(error, attempts) else {
+                break;
+            };
+            std::thread::sleep(self.retry_policy.backoff);
+            attempts += 1;
+
+            result = match action {
+                RetryAction::Resend => self.request_response_once(msg),
+                RetryAction::Reconnect => self.connect().and_then(|()| self.request_response_once(msg)),
+            };
+        }
+
+        match result {
+            Err(e) if attempts > 1 => Err(RetriesExhausted::into_io_error(attempts, e)),
+            result => result,
+        }
+    }
+
+    fn request_response_once(&mut self, msg: &[u8]) -> std::io::Result<Vec<u8>> {
+        let stream = self.stream.as_mut().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotConnected, "Not Connected"))?;
+
+        let serial_id: u16 = self.serial_id;
+        self.serial_id = self.serial_id.wrapping_add(1);
+        let mut send_msg: Vec<u8> = requests::request_frame(&self.connection_props, serial_id, msg)?;
+        if let Some(frames_1e) = &mut self.frames_1e {
+            send_msg = frames_1e.request(&send_msg)?;
+        }
+
+        stream.set_write_timeout(Some(self.send_timeout))?;
+        if let Err(e) = stream.write_all(&send_msg) {
+            // A frame sent halfway cannot be completed; the CPU would take the next request as its rest.
+            self.shutdown();
+            return Err(match e.kind() {
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => std::io::Error::new(std::io::ErrorKind::TimedOut, "Send Failed (Timeout)"),
+                _ => e,
+            });
+        }
+
+        // Responses to other serial Nos. are late responses of requests that timed out, and are skipped.
+        let deadline = Instant::now() + self.recv_timeout;
+        let mut frame = loop {
+            let frame = self.read_frame(deadline)?;
+            if requests::frame_serial_id(&frame) == serial_id {
+                break frame;
+            }
+        };
+
+        requests::validate_response(&self.connection_props, &frame, serial_id)?;
+
+        frame.drain(..RECVFRAME_PREFIX_FIXED_LEN);
+        Ok(frame)
+    }
+
+    /// Read one response frame by `deadline`. Bytes received before a timeout are kept for the next request.
+    fn read_frame(&mut self, deadline: Instant) -> std::io::Result<Vec<u8>> {
+        let mut buf = [0u8; BUFSIZE];
+
+        loop {
+            let frame_len: Option<usize> = match &self.frames_1e {
+                Some(frames_1e) => frames_1e.frame_len(&self.pending)?,
+                None => requests::frame_len(&self.pending, BUFSIZE)?,
+            };
+            if let Some(frame_len) = frame_len
+                && self.pending.len() >= frame_len
+            {
+                let frame: Vec<u8> = self.pending.drain(..frame_len).collect();
+                return match &mut self.frames_1e {
+                    Some(frames_1e) => frames_1e.response(&frame),
+                    None => Ok(frame),
+                };
+            }
+
+            let stream = self.stream.as_mut().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotConnected, "Not Connected"))?;
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(self.timed_out());
+            }
+            stream.set_read_timeout(Some(remaining))?;
+
+            match stream.read(&mut buf) {
+                Ok(0) => return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Connection Closed")),
+                Ok(n) => self.pending.extend_from_slice(&buf[..n]),
+                Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => return Err(self.timed_out()),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {},
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// A 1E response has no serial No., so the connection is closed for a late one not to be taken for the response of the next request.
+    fn timed_out(&mut self) -> std::io::Error {
+        if self.frames_1e.is_some() {
+            self.shutdown();
+        }
+        std::io::Error::new(std::io::ErrorKind::TimedOut, "Read Failed (Timeout)")
+    }
+
+    /* Unit Control */
+
+    pub fn run_cpu(&mut self) -> std::io::Result<()> {
+        const COMMAND: [u8; 8] = unit_control::remote_run();
+        self.request_response(&COMMAND).map(|_| ())
+    }
+
+    pub fn stop_cpu(&mut self) -> std::io::Result<()> {
+        const COMMAND: [u8; 6] = unit_control::remote_stop();
+        self.request_response(&COMMAND).map(|_| ())
+    }
+
+    pub fn pause_cpu(&mut self) -> std::io::Result<()> {
+        const COMMAND: [u8; 6] = unit_control::remote_pause();
+        self.request_response(&COMMAND).map(|_| ())
+    }
+
+    pub fn clear_latch(&mut self) -> std::io::Result<()> {
+        const COMMAND: [u8; 6] = unit_control::remote_latch_clear();
+        self.request_response(&COMMAND).map(|_| ())
+    }
+
+    pub fn reset_cpu(&mut self) -> std::io::Result<()> {
+        const COMMAND: [u8; 6] = unit_control::remote_reset();
+        self.request_response(&COMMAND).map(|_| ())
+    }
+
+    pub fn get_cpu_type(&mut self) -> std::io::Result<CpuTypeInfo> {
+        const COMMAND: [u8; 4] = unit_control::get_cpu_type();
+        let ret = self.request_response(&COMMAND)?;
+        unit_control::parse_cpu_type(&ret)
+    }
+
+    /// See `slmp::SLMPClient::verify_cpu_type`.
+    pub fn verify_cpu_type(&mut self) -> std::io::Result<CpuTypeInfo> {
+        let info = self.get_cpu_type()?;
+        info.check(&self.connection_props.cpu)?;
+        Ok(info)
+    }
+
+    pub fn lock_cpu(&mut self, password: &str) -> std::io::Result<()> {
+        let cmd = unit_control::lock_cpu(&self.connection_props.cpu, password)?;
+        self.request_response(&cmd).map(|_| ())
+    }
+
+    pub fn unlock_cpu(&mut self, password: &str) -> std::io::Result<()> {
+        let cmd = unit_control::unlock_cpu(&self.connection_props.cpu, password)?;
+        self.request_response(&cmd).map(|_| ())
+    }
+
+    pub fn echo(&mut self) -> std::io::Result<()> {
+        const COMMAND: [u8; 10] = unit_control::echo();
+        let recv = self.request_response(&COMMAND)?;
+        unit_control::check_echo(&recv)
+    }
+
+    /// See `slmp::SLMPClient::loopback`.
+    pub fn loopback(&mut self, data: Option<&[u8]>) -> std::io::Result<Duration> {
+        let message: &[u8] = data.unwrap_or(&unit_control::ECHO_MESSAGE);
+        let cmd = unit_control::loopback(message)?;
+        let start = std::time::Instant::now();
+        let recv = self.request_response(&cmd)?;
+        let elapsed = start.elapsed();
+        unit_control::check_loopback(&recv, message)?;
+        Ok(elapsed)
+    }
+
+    /* Device Access */
+
+    pub fn bulk_write(&mut self, start_device: Device, data: &[TypedData]) -> std::io::Result<()> {
+        let cmd = requests::bulk_write(&self.connection_props.cpu, &self.device_limits, start_device, data, self.word_order)?;
+        self.request_response(&cmd).map(|_| ())
+    }
+
+    pub fn random_write(&mut self, data: &[DeviceData]) -> std::io::Result<()> {
+        for cmd in requests::random_write(&self.connection_props.cpu, &self.device_limits, data, self.word_order)? {
+            self.request_response(&cmd)?;
+        }
+        Ok(())
+    }
+
+    pub fn block_write<'a>(&mut self, data: &'a [BlockedDeviceData<'a>]) -> std::io::Result<()> {
+        let cmd = requests::block_write(&self.connection_props.cpu, &self.device_limits, data, self.word_order)?;
+        self.request_response(&cmd).map(|_| ())
+    }
+
+    /// See `slmp::SLMPClient::block_write_owned`.
+    pub fn block_write_owned(&mut self, data: &[BlockedDeviceDataOwned]) -> std::io::Result<()> {
+        let data: Vec<BlockedDeviceData> = data.iter().map(BlockedDeviceData::from).collect();
+        self.block_write(&data)
+    }
+
+    /// See `slmp::SLMPClient::block_write_split`.
+    pub fn block_write_split<'a>(&mut self, data: &'a [BlockedDeviceData<'a>]) -> std::io::Result<()> {
+        for cmd in requests::split_block_write(&self.connection_props.cpu, &self.device_limits, data, self.word_order)? {
+            self.request_response(&cmd)?;
+        }
+        Ok(())
+    }
+
+    /// Write and read back. See `slmp::SLMPClient::bulk_write_verified`.
+    pub fn bulk_write_verified(&mut self, start_device: Device, data: &[TypedData]) -> std::io::Result<()> {
+        let verification = Verification::bulk(&self.connection_props.cpu, &self.device_limits, start_device, data, self.word_order)?;
+        self.bulk_write(start_device, data)?;
+        self.verify(verification)
+    }
+
+    pub fn random_write_verified(&mut self, data: &[DeviceData]) -> std::io::Result<()> {
+        let verification = Verification::random(&self.connection_props.cpu, &self.device_limits, data, self.word_order)?;
+        self.random_write(data)?;
+        self.verify(verification)
+    }
+
+    pub fn block_write_verified<'a>(&mut self, data: &'a [BlockedDeviceData<'a>]) -> std::io::Result<()> {
+        let verification = Verification::block(&self.connection_props.cpu, &self.device_limits, data, self.word_order)?;
+        self.block_write(data)?;
+        self.verify(verification)
+    }
+
+    /// See `slmp::SLMPClient::fill`.
+    pub fn fill(&mut self, start_device: Device, count: usize, value: TypedData) -> std::io::Result<()> {
+        self.fill_with_progress(start_device, count, value, |_, _| ())
+    }
+
+    pub fn fill_with_progress<F: FnMut(usize, usize)>(&mut self, start_device: Device, count: usize, value: TypedData, mut progress: F) -> std::io::Result<()> {
+        let request = requests::Fill::new(&self.connection_props.cpu, &self.device_limits, start_device, count, &value, self.word_order)?;
+        for (written, cmd) in request.commands(self.connection_props.cpu) {
+            self.request_response(&cmd)?;
+            progress(written, count);
+        }
+        Ok(())
+    }
+
+    pub fn clear_bits(&mut self, start_device: Device, count: usize) -> std::io::Result<()> {
+        self.fill(start_device, count, TypedData::Bool(false))
+    }
+
+    fn verify(&mut self, verification: Verification) -> std::io::Result<()> {
+        let responses = verification.commands().iter()
+            .map(|cmd| self.request_response(cmd))
+            .collect::<std::io::Result<Vec<Vec<u8>>>>()?;
+        verification.check(&responses)
+    }
+
+    pub fn bulk_read(&mut self, start_device: Device, device_num: usize, data_type: DataType) -> std::io::Result<Vec<DeviceData>> {
+        let request = requests::BulkRead { start_device, device_num, data_type };
+        let cmd = request.command(&self.connection_props.cpu, &self.device_limits)?;
+        let recv = self.request_response(&cmd)?;
+        Ok(requests::ordered_device_data(request.parse(&recv)?, self.word_order))
+    }
+
+    /// It fails with `InvalidInput` for no devices or over `monitor_max_access_points` of the CPU.
+    pub fn random_read(&mut self, devices: &[TypedDevice]) -> std::io::Result<Vec<DeviceData>> {
+        let (cmd, monitor_list) = requests::random_read(&self.connection_props.cpu, &self.device_limits, devices)?;
+        let recv = self.request_response(&cmd)?;
+        Ok(requests::ordered_device_data(monitor_list.parse(&recv)?, self.word_order))
+    }
+
+    /// See `slmp::SLMPClient::optimized_read`.
+    pub fn optimized_read(&mut self, devices: &[TypedDevice]) -> std::io::Result<Vec<DeviceData>> {
+        let plan = ReadPlan::new(&self.connection_props.cpu, &self.device_limits, &self.read_coalescing, devices)?;
+        let responses = plan.commands(&self.connection_props.cpu, &self.device_limits)?.iter()
+            .map(|cmd| self.request_response(cmd))
+            .collect::<std::io::Result<Vec<Vec<u8>>>>()?;
+        Ok(requests::ordered_device_data(plan.parse(&responses)?, self.word_order))
+    }
+
+    pub fn block_read(&mut self, device_blocks: &[DeviceBlock]) -> std::io::Result<Vec<DeviceData>> {
+        let request = requests::BlockRead::new(&self.connection_props.cpu, &self.device_limits, device_blocks)?;
+        self.block_read_impl(request)
+    }
+
+    /// See `slmp::SLMPClient::block_read_split`.
+    pub fn block_read_split(&mut self, device_blocks: &[DeviceBlock]) -> std::io::Result<Vec<DeviceData>> {
+        let mut ret: Vec<DeviceData> = Vec::new();
+        for request in requests::BlockRead::split(&self.connection_props.cpu, &self.device_limits, device_blocks)? {
+            ret.extend(self.block_read_impl(request)?);
+        }
+        Ok(ret)
+    }
+
+    /// See `slmp::SLMPClient::block_read_typed`.
+    pub fn block_read_typed(&mut self, device_blocks: &[TypedDeviceBlock]) -> std::io::Result<Vec<DeviceData>> {
+        let request = requests::BlockRead::typed(&self.connection_props.cpu, &self.device_limits, device_blocks)?;
+        self.block_read_impl(request)
+    }
+
+    fn block_read_impl(&mut self, request: requests::BlockRead) -> std::io::Result<Vec<DeviceData>> {
+        let cmd = request.command(&self.connection_props.cpu);
+        let recv = self.request_response(&cmd)?;
+        Ok(requests::ordered_device_data(request.parse(&recv)?, self.word_order))
+    }
+
+    pub fn bulk_read_words(&mut self, start_device: Device, word_num: usize) -> std::io::Result<Vec<u16>> {
+        let recv = self.bulk_read_bytes(start_device, word_num)?;
+        Ok(requests::bytes_to_words(&recv))
+    }
+
+    pub fn bulk_read_bytes(&mut self, start_device: Device, word_num: usize) -> std::io::Result<Vec<u8>> {
+        let request = requests::WordRead { start_device, word_num };
+        let cmd = request.command(&self.connection_props.cpu, &self.device_limits)?;
+        let recv = self.request_response(&cmd)?;
+        request.check(&recv)?;
+        Ok(recv)
+    }
+
+    pub fn bulk_write_words(&mut self, start_device: Device, data: &[u16]) -> std::io::Result<()> {
+        let cmd = requests::bulk_write_words(&self.connection_props.cpu, &self.device_limits, start_device, data)?;
+        self.request_response(&cmd).map(|_| ())
+    }
+
+    /// See `slmp::SLMPClient::snapshot`.
+    pub fn snapshot(&mut self, blocks: &[DeviceBlock]) -> std::io::Result<DeviceSnapshot> {
+        let request = SnapshotRead::new(&self.connection_props.cpu, &self.device_limits, blocks)?;
+        let responses = request.commands().iter()
+            .map(|cmd| self.request_response(cmd))
+            .collect::<std::io::Result<Vec<Vec<u8>>>>()?;
+        request.parse(self.connection_props.cpu, &responses)
+    }
+
+    pub fn restore(&mut self, snapshot: &DeviceSnapshot) -> std::io::Result<()> {
+        for cmd in snapshot::restore(&self.connection_props.cpu, &self.device_limits, snapshot)? {
+            self.request_response(&cmd)?;
+        }
+        Ok(())
+    }
+
+    /// See `slmp::SLMPClient::modify_word`.
+    pub fn modify_word(&mut self, device: Device, and_mask: u16, or_mask: u16) -> std::io::Result<[bool; 16]> {
+        let previous: u16 = self.bulk_read_words(device, 1)?[0];
+        self.bulk_write_words(device, &[(previous & and_mask) | or_mask])?;
+        Ok(u16_to_bits(previous))
+    }
+
+    /// See `slmp::SLMPClient::write_bits16`.
+    pub fn write_bits16(&mut self, device: Device, bits: [bool; 16]) -> std::io::Result<()> {
+        self.bulk_write(device, &[TypedData::BitArray16(bits)])
+    }
+
+    pub fn set_word_bit(&mut self, device: Device, bit_index: u8, value: bool) -> std::io::Result<[bool; 16]> {
+        let mask: u16 = requests::word_bit_mask(bit_index)?;
+        self.modify_word(device, !mask, if value { mask } else { 0 })
+    }
+
+    pub fn read_string(&mut self, device: Device, word_len: u8) -> std::io::Result<String> {
+        let request = requests::StringRead { device, word_len };
+        let cmd = request.command(&self.connection_props.cpu, &self.device_limits)?;
+        let recv = self.request_response(&cmd)?;
+        request.parse(&recv)
+    }
+
+    pub fn write_string(&mut self, device: Device, text: &str, word_len: u8) -> std::io::Result<()> {
+        let cmd = requests::string_write(&self.connection_props.cpu, &self.device_limits, device, text, word_len)?;
+        self.request_response(&cmd).map(|_| ())
+    }
+
+    pub fn read_struct<S: SlmpReadable>(&mut self, start_device: Device) -> std::io::Result<S> {
+        let request = requests::StructRead { start_device, fields: S::FIELDS };
+        let cmd = request.command(&self.connection_props.cpu, &self.device_limits)?;
+        let recv = self.request_response(&cmd)?;
+        let fields: Vec<TypedData> = request.parse(&recv)?.into_iter().map(|x| x.with_word_order(self.word_order)).collect();
+        S::from_fields(fields)
+    }
+
+    pub fn write_struct<S: SlmpWritable>(&mut self, start_device: Device, value: &S) -> std::io::Result<()> {
+        let cmd = requests::struct_write(&self.connection_props.cpu, &self.device_limits, start_device, S::FIELDS, value.to_fields(), self.word_order)?;
+        self.request_response(&cmd).map(|_| ())
+    }
+}
+
+impl Drop for SLMPClient {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
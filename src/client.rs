@@ -0,0 +1,1132 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU64, Ordering};
+use tokio::sync::Mutex;
+use tokio::time::Duration;
+
+use crate::commands::device_access::read::*;
+use crate::commands::{clock, diagnostics, file_control, label_access, unit_control};
+use crate::requests::RECVFRAME_PREFIX_FIXED_LEN;
+use crate::{BlockedDeviceData, BlockedDeviceDataOwned, CPU, CpuTypeInfo, DataType, Device, DeviceBlock, DeviceData, DeviceLimits, DeviceSnapshot, FileDrive, FileInfo, MonitorList, PLCDateTime, PLCErrorRecord, SLMP4EConnectionProps, SLMPEndCode, SlmpReadable, SlmpTransport, SlmpWritable, TcpTransport, TypedData, TypedDevice, TypedDeviceBlock, WordOrder};
+use crate::{DEFAULT_RECV_TIMEOUT_SEC, DEFAULT_SEND_TIMEOUT_SEC, DryRun, ReadCoalescing, RetriesExhausted, RetryPolicy, requests, u16_to_bits};
+use crate::read_plan::ReadPlan;
+use crate::retry::RetryAction;
+use crate::snapshot::{self, SnapshotRead};
+use crate::verify::Verification;
+#[cfg(feature = "json-api")]
+use crate::json::{ErrorResponse, LabelResult, ReadRequest, ReadResponse, Request, Response, WriteRequest};
+
+macro_rules! invalidDataError {
+    ($msg:expr) => {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, $msg)
+    };
+}
+
+/// State shared by the clones of a client.
+/// A request holds `transport` from sending to its response, so the clones take turns on the link.
+struct SharedState<T> {
+    transport: Mutex<T>,
+    serial_id: AtomicU16,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    read_only: AtomicBool,
+}
+
+/// Lock of `unlock_scope` left to a task of its own when the scope is dropped before it locks.
+struct Relock<T: SlmpTransport> {
+    client: Option<SLMPClient<T>>,
+    password: String,
+}
+
+impl<T: SlmpTransport> Drop for Relock<T> {
+    fn drop(&mut self) {
+        if let Some(client) = self.client.take()
+            && let Ok(runtime) = tokio::runtime::Handle::try_current()
+        {
+            let password: String = std::mem::take(&mut self.password);
+            runtime.spawn(async move {
+                let _ = client.lock_cpu(&password).await;
+            });
+        }
+    }
+}
+
+/// A clone shares the connection and the read-only mode with the original; the timeouts, `cpu_timer`, word order, device limits and read coalescing are set per clone.
+///
+/// The requests go over TCP by default. `with_transport` takes another link, see `SlmpTransport`.
+pub struct SLMPClient<T: SlmpTransport = TcpTransport> {
+    connection_props: SLMP4EConnectionProps,
+    shared: Arc<SharedState<T>>,
+    send_timeout: Duration,
+    recv_timeout: Duration,
+    word_order: WordOrder,
+    device_limits: DeviceLimits,
+    retry_policy: RetryPolicy,
+    read_coalescing: ReadCoalescing,
+}
+
+impl<T: SlmpTransport> Clone for SLMPClient<T> {
+    fn clone(&self) -> Self {
+        Self {
+            connection_props: self.connection_props.clone(),
+            shared: self.shared.clone(),
+            send_timeout: self.send_timeout,
+            recv_timeout: self.recv_timeout,
+            word_order: self.word_order,
+            device_limits: self.device_limits,
+            retry_policy: self.retry_policy,
+            read_coalescing: self.read_coalescing,
+        }
+    }
+}
+
+impl SLMPClient {
+    /// Create a client without checking `cpu_timer` against the receive timeout. See `try_new`.
+    pub fn new(connection_props: SLMP4EConnectionProps) -> Self {
+        let transport = TcpTransport::new(&connection_props);
+        Self::with_transport(connection_props, transport)
+    }
+
+    /// Create a client, failing if the monitoring timer (`cpu_timer`) is longer than the default receive timeout.
+    /// Use `SLMP4EConnectionProps::with_timeouts` to derive a consistent `cpu_timer`.
+    pub fn try_new(connection_props: SLMP4EConnectionProps) -> std::io::Result<Self> {
+        let client = Self::new(connection_props);
+        client.validate_timeouts()?;
+        Ok(client)
+    }
+}
+
+impl<T: SlmpTransport> SLMPClient<T> {
+    /// Create a client sending the frames built from `connection_props` over `transport`.
+    /// `ip` and `port` of `connection_props` are up to the transport.
+    /// For `CPU::A`, the requests fail with `Unsupported` unless the transport `supports_a_series`.
+    pub fn with_transport(connection_props: SLMP4EConnectionProps, transport: T) -> Self {
+        Self {
+            shared: Arc::new(SharedState {
+                transport: Mutex::new(transport),
+                serial_id: AtomicU16::new(connection_props.serial_id),
+                bytes_sent: AtomicU64::new(0),
+                bytes_received: AtomicU64::new(0),
+                read_only: AtomicBool::new(false),
+            }),
+            device_limits: DeviceLimits::new(connection_props.cpu),
+            connection_props,
+            send_timeout: DEFAULT_SEND_TIMEOUT_SEC,
+            recv_timeout: DEFAULT_RECV_TIMEOUT_SEC,
+            word_order: WordOrder::LowHigh,
+            retry_policy: RetryPolicy::NONE,
+            read_coalescing: ReadCoalescing::DEFAULT,
+        }
+    }
+
+    /// Check that the CPU gives up a request before the client does.
+    /// Otherwise the client times out while the CPU is still legitimately processing the request.
+    pub fn validate_timeouts(&self) -> std::io::Result<()> {
+        match self.connection_props.cpu_timer_duration() {
+            Some(cpu_timer) if cpu_timer <= self.recv_timeout => Ok(()),
+            cpu_timer => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Monitoring timer ({cpu_timer:?}) exceeds the receive timeout ({:?})", self.recv_timeout)
+            )),
+        }
+    }
+
+    /// CPU series of the connection properties.
+    pub const fn cpu(&self) -> CPU {
+        self.connection_props.cpu
+    }
+
+    pub const fn cpu_timer(&self) -> u16 {
+        self.connection_props.cpu_timer
+    }
+
+    /// Change the monitoring timer (in 250 ms units) of the following requests,
+    /// e.g. a longer one for file access than for cyclic reads.
+    pub fn set_cpu_timer(&mut self, cpu_timer: u16) {
+        self.connection_props.cpu_timer = cpu_timer;
+    }
+
+    /// Lock the `remote_password` of the props if any, and close the connection.
+    pub async fn close(&self) {
+        if let Some(password) = &self.connection_props.remote_password
+            && let Ok(cmd) = unit_control::lock_cpu(&self.connection_props.cpu, password.expose())
+        {
+            let _ = self.request_once(&cmd).await;
+        }
+        self.shared.transport.lock().await.close().await;
+    }
+
+    pub fn set_send_timeout(&mut self, dur: Duration) {
+        self.send_timeout = dur;
+    }
+
+    pub fn set_recv_timeout(&mut self, dur: Duration) {
+        self.recv_timeout = dur;
+    }
+
+    pub const fn word_order(&self) -> WordOrder {
+        self.word_order
+    }
+
+    /// Change the word order of 32/64-bit values in the following device reads and writes of this clone,
+    /// e.g. a clone with `WordOrder::HighLow` for values stored upper word first.
+    pub fn set_word_order(&mut self, order: WordOrder) {
+        self.word_order = order;
+    }
+
+    pub const fn device_limits(&self) -> &DeviceLimits {
+        &self.device_limits
+    }
+
+    /// Change the largest addresses checked before the following requests of this clone are sent,
+    /// e.g. the file register size set by the CPU parameters. The default is `DeviceLimits::new` of the CPU.
+    pub fn set_device_limits(&mut self, limits: DeviceLimits) {
+        self.device_limits = limits;
+    }
+
+    pub const fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Retry the following requests of this clone on transient failures (default: `RetryPolicy::NONE`).
+    /// When the retries are exhausted, the error carries `RetriesExhausted` with the attempt count and the last error.
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = policy;
+    }
+
+    pub const fn read_coalescing(&self) -> &ReadCoalescing {
+        &self.read_coalescing
+    }
+
+    /// Change the limits of the following `optimized_read` of this clone (default: `ReadCoalescing::DEFAULT`).
+    pub fn set_read_coalescing(&mut self, coalescing: ReadCoalescing) {
+        self.read_coalescing = coalescing;
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.shared.read_only.load(Ordering::Acquire)
+    }
+
+    /// Refuse every request changing the CPU (device, label and file writes, remote operations, clock set and error clear)
+    /// of this client and its clones with `PermissionDenied` before it is built; reads work as usual.
+    /// It can be turned off only by a client without clones, so that the holder of a clone
+    /// (e.g. a closure of `SLMPConnectionManager::operate_worker`) cannot lift it.
+    pub fn set_read_only(&mut self, read_only: bool) -> std::io::Result<()> {
+        if !read_only && Arc::strong_count(&self.shared) > 1 {
+            return Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "Read-only mode is shared with other clones"));
+        }
+        self.shared.read_only.store(read_only, Ordering::Release);
+        Ok(())
+    }
+
+    /// Total bytes sent and received by this client and its clones, frame headers included.
+    pub fn transferred_bytes(&self) -> (u64, u64) {
+        (self.shared.bytes_sent.load(Ordering::Relaxed), self.shared.bytes_received.load(Ordering::Relaxed))
+    }
+
+    /// Frames of the requests of this client, built without sending them (e.g. `client.dry_run().fill(d0, 2000, value)`).
+    pub fn dry_run(&self) -> DryRun {
+        DryRun::new(self.connection_props.clone(), self.shared.serial_id.load(Ordering::Relaxed), self.device_limits, self.word_order, self.read_coalescing)
+    }
+
+    /// Open the connection, and unlock the `remote_password` of the props if any. The connection is closed again if the unlock fails.
+    pub async fn connect(&self) -> std::io::Result<()> {
+        let mut transport = self.shared.transport.lock().await;
+        self.check_transport(&transport)?;
+        transport.connect().await?;
+        drop(transport);
+
+        if let Some(password) = &self.connection_props.remote_password {
+            let unlocked = match unit_control::unlock_cpu(&self.connection_props.cpu, password.expose()) {
+                Ok(cmd) => self.request_once(&cmd).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = unlocked {
+                self.shared.transport.lock().await.close().await;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Send a request once, regardless of the retry policy (which may reconnect).
+    async fn request_once(&self, msg: &[u8]) -> std::io::Result<()> {
+        let mut frame: Vec<u8> = requests::request_frame(&self.connection_props, 0, msg)?;
+        let mut response: Vec<u8> = Vec::new();
+        self.exchange_once(&mut frame, None, &mut response).await
+    }
+
+    async fn request_response(&self, msg: &[u8]) -> std::io::Result<Vec<u8>> {
+        self.request_response_with_timeout(msg, None).await
+    }
+
+    /// Send a request under the retry policy.
+    /// `timeout` overrides both of the client-level send/receive timeouts if it is given.
+    async fn request_response_with_timeout(&self, msg: &[u8], timeout: Option<Duration>) -> std::io::Result<Vec<u8>> {
+        requests::check_read_only(self.is_read_only(), msg)?;
+        let mut frame: Vec<u8> = requests::request_frame(&self.connection_props, 0, msg)?;
+        let mut response: Vec<u8> = Vec::new();
+        self.exchange(&mut frame, timeout, &mut response).await?;
+        response.drain(..RECVFRAME_PREFIX_FIXED_LEN);
+        Ok(response)
+    }
+
+    /// Send `frame` (numbered here) under the retry policy and receive the whole response frame into `response`.
+    async fn exchange(&self, frame: &mut [u8], timeout: Option<Duration>, response: &mut Vec<u8>) -> std::io::Result<()> {
+        let mut attempts: u32 = 1;
+        let mut result = self.exchange_once(frame, timeout, response).await;
+
+        while let Err(error) = &result {
+            let Some(action) = self.retry_policy.action(error, attempts) else {
+                break;
+            };
+            tokio::time::sleep(self.retry_policy.backoff).await;
+            attempts += 1;
+
+            result = match action {
+                RetryAction::Resend => self.exchange_once(frame, timeout, response).await,
+                RetryAction::Reconnect => match self.connect().await {
+                    Ok(()) => self.exchange_once(frame, timeout, response).await,
+                    Err(e) => Err(e),
+                },
+            };
+        }
+
+        match result {
+            Err(e) if attempts > 1 => Err(RetriesExhausted::into_io_error(attempts, e)),
+            result => result,
+        }
+    }
+
+    async fn exchange_once(&self, frame: &mut [u8], timeout: Option<Duration>, response: &mut Vec<u8>) -> std::io::Result<()> {
+        let send_timeout: Duration = timeout.unwrap_or(self.send_timeout);
+        let recv_timeout: Duration = timeout.unwrap_or(self.recv_timeout);
+
+        let mut transport = self.shared.transport.lock().await;
+        self.check_transport(&transport)?;
+
+        let serial_id: u16 = self.shared.serial_id.fetch_add(1, Ordering::Relaxed);
+        frame[2..4].copy_from_slice(&serial_id.to_le_bytes());
+
+        match tokio::time::timeout(send_timeout, transport.send(frame)).await {
+            Ok(ret) => ret?,
+            Err(_) => {
+                // A frame sent halfway cannot be completed; the CPU would take the next request as its rest.
+                transport.close().await;
+                return Err(std::io::Error::new(std::io::ErrorKind::TimedOut,"Send Failed (Timeout)"));
+            },
+        }
+        self.shared.bytes_sent.fetch_add(frame.len() as u64, Ordering::Relaxed);
+
+        // Responses to other serial Nos. are late responses of requests that timed out, and are skipped.
+        let deadline = tokio::time::Instant::now() + recv_timeout;
+        loop {
+            let Ok(received) = tokio::time::timeout_at(deadline, transport.recv_into(response)).await else {
+                // A 1E response has no serial No., so a late one could be taken for the response of the next request.
+                if self.connection_props.cpu == CPU::A {
+                    transport.close().await;
+                }
+                return Err(std::io::Error::new(std::io::ErrorKind::TimedOut,"Read Failed (Timeout)"));
+            };
+            received?;
+            self.shared.bytes_received.fetch_add(response.len() as u64, Ordering::Relaxed);
+
+            if requests::frame_serial_id(response) == serial_id {
+                break;
+            }
+        }
+
+        requests::validate_response(&self.connection_props, response, serial_id)
+    }
+
+    fn check_transport(&self, transport: &T) -> std::io::Result<()> {
+        if self.connection_props.cpu == CPU::A && !transport.supports_a_series() {
+            return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Transport does not translate to the 1E frame of CPU::A"));
+        }
+        Ok(())
+    }
+
+    /* Unit Control */
+
+    pub async fn run_cpu(&self) -> std::io::Result<()> {
+        const COMMAND: [u8; 8] = unit_control::remote_run();
+        self.request_response(&COMMAND).await.map(|_| ())
+    }
+
+    pub async fn stop_cpu(&self) -> std::io::Result<()> {
+        const COMMAND: [u8; 6] = unit_control::remote_stop();
+        self.request_response(&COMMAND).await.map(|_| ())
+    }
+
+    pub async fn pause_cpu(&self) -> std::io::Result<()> {
+        const COMMAND: [u8; 6] = unit_control::remote_pause();
+        self.request_response(&COMMAND).await.map(|_| ())
+    }
+
+    pub async fn clear_latch(&self) -> std::io::Result<()> {
+        const COMMAND: [u8; 6] = unit_control::remote_latch_clear();
+        self.request_response(&COMMAND).await.map(|_| ())
+    }
+
+    pub async fn reset_cpu(&self) -> std::io::Result<()> {
+        const COMMAND: [u8; 6] = unit_control::remote_reset();
+        self.request_response(&COMMAND).await.map(|_| ())
+    }
+
+    pub async fn get_cpu_type(&self) -> std::io::Result<CpuTypeInfo> {
+        const COMMAND: [u8; 4] = unit_control::get_cpu_type();
+        let ret = self.request_response(&COMMAND).await?;
+        unit_control::parse_cpu_type(&ret)
+    }
+
+    /// `get_cpu_type`, failing with `InvalidInput` when the PLC reports a family other than `connection_props.cpu`.
+    pub async fn verify_cpu_type(&self) -> std::io::Result<CpuTypeInfo> {
+        let info = self.get_cpu_type().await?;
+        info.check(&self.connection_props.cpu)?;
+        Ok(info)
+    }
+
+    pub async fn lock_cpu(&self, password: &str) -> std::io::Result<()> {
+        let cmd = unit_control::lock_cpu(&self.connection_props.cpu, password)?;
+        self.request_response(&cmd).await.map(|_| ())
+    }
+
+    pub async fn unlock_cpu(&self, password: &str) -> std::io::Result<()> {
+        let cmd = unit_control::unlock_cpu(&self.connection_props.cpu, password)?;
+        self.request_response(&cmd).await.map(|_| ())
+    }
+
+    /// Unlock the remote password, run `f` and lock it again, also when `f` fails.
+    /// If the returned future is dropped halfway (e.g. on a timeout), the lock is sent from a task of its own.
+    /// The error of `f` goes before that of the lock.
+    pub async fn unlock_scope<R, F>(&self, password: &str, f: F) -> std::io::Result<R>
+        where F: AsyncFnOnce(&Self) -> std::io::Result<R>
+    {
+        let mut relock = Relock { client: Some(self.clone()), password: password.to_string() };
+        if let Err(e) = self.unlock_cpu(password).await {
+            relock.client = None;
+            return Err(e);
+        }
+
+        let ret = f(self).await;
+        relock.client = None;
+        let locked = self.lock_cpu(password).await;
+        ret.and_then(|x| locked.map(|()| x))
+    }
+
+    pub async fn echo(&self) -> std::io::Result<()> {
+        const COMMAND: [u8; 10] = unit_control::echo();
+        let recv: &[u8] = &self.request_response(&COMMAND).await
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::NetworkDown, "Echo response did not return in time"))?;
+
+        unit_control::check_echo(recv)
+    }
+
+    /// Loop back `data` (1~960 ASCII printable characters, default: the message of `echo`),
+    /// check that it comes back unchanged and return the round-trip time.
+    pub async fn loopback(&self, data: Option<&[u8]>) -> std::io::Result<Duration> {
+        let message: &[u8] = data.unwrap_or(&unit_control::ECHO_MESSAGE);
+        let cmd = unit_control::loopback(message)?;
+        let start = std::time::Instant::now();
+        let recv = self.request_response(&cmd).await?;
+        let elapsed = start.elapsed();
+        unit_control::check_loopback(&recv, message)?;
+        Ok(elapsed)
+    }
+
+    /* Diagnostics */
+
+    /// Read the self-diagnostic errors of the CPU, latest first.
+    /// Q/L type CPU reports only the latest error (with detail information),
+    /// R type CPU reports the latest error and the other errors currently occurring (up to 16).
+    pub async fn read_error_history(&self, max_entries: usize) -> std::io::Result<Vec<PLCErrorRecord>> {
+        let cpu: CPU = self.connection_props.cpu;
+        let cmd = diagnostics::read_error_info(&cpu);
+        let recv: &[u8] = &self.request_response(&cmd).await?;
+        diagnostics::parse_error_info(&cpu, recv, max_entries)
+    }
+
+    /// Clear the continuation errors of the CPU.
+    /// Stop errors cannot be cleared without resetting the CPU.
+    pub async fn clear_error(&self) -> std::io::Result<()> {
+        let cpu: CPU = self.connection_props.cpu;
+
+        if matches!(cpu, CPU::Q | CPU::L) {
+            let latest = self.read_error_history(1).await?;
+            let Some(latest) = latest.first() else {
+                return Ok(());
+            };
+            let cmd = diagnostics::write_error_reset_code(&cpu, latest.code);
+            self.request_response(&cmd).await?;
+        }
+
+        let cmd = diagnostics::error_reset_request(&cpu, false);
+        self.request_response(&cmd).await?;
+        let cmd = diagnostics::error_reset_request(&cpu, true);
+        self.request_response(&cmd).await.map(|_| ())
+    }
+
+    /* Clock */
+
+    pub async fn read_clock(&self) -> std::io::Result<PLCDateTime> {
+        let cpu: CPU = self.connection_props.cpu;
+        let cmd = clock::read_clock(&cpu);
+        let recv: &[u8] = &self.request_response(&cmd).await?;
+        clock::parse_clock(&cpu, recv)
+    }
+
+    /// Set the PLC clock. The new value takes effect at the END processing of the CPU.
+    pub async fn set_clock(&self, datetime: PLCDateTime) -> std::io::Result<()> {
+        let cmd = clock::write_clock(&self.connection_props.cpu, &datetime)?;
+        self.request_response(&cmd).await?;
+
+        let cmd = clock::set_clock_request(&self.connection_props.cpu, false);
+        self.request_response(&cmd).await?;
+        let cmd = clock::set_clock_request(&self.connection_props.cpu, true);
+        self.request_response(&cmd).await.map(|_| ())
+    }
+
+    /* File Control */
+
+    /// Download a whole file from the CPU.
+    /// The file is opened in read mode, read in chunks until EOF, and then closed even if reading fails.
+    pub async fn download_file(&self, drive: FileDrive, file_name: &str) -> std::io::Result<Vec<u8>> {
+        let file_pointer = self.open_file(drive, file_name, file_control::FileOpenMode::Read).await?;
+
+        let ret = self.read_file_to_end(file_pointer).await;
+        let closed = self.close_file(file_pointer).await;
+
+        let data = ret?;
+        closed?;
+
+        Ok(data)
+    }
+
+    /// Upload a whole file to the CPU.
+    /// If the file already exists, it is replaced when `overwrite` is true, otherwise the end code is returned.
+    /// The file is written in chunks and then closed even if writing fails.
+    pub async fn upload_file(&self, drive: FileDrive, file_name: &str, data: &[u8], overwrite: bool) -> std::io::Result<()> {
+        let file_size: u32 = u32::try_from(data.len())
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "File size exceeds the addressable offset"))?;
+
+        match self.create_file(drive, file_name, file_size).await {
+            Ok(()) => (),
+            Err(e) if overwrite && SLMPEndCode::from_io_error(&e) == Some(SLMPEndCode::FILE_ALREADY_EXISTS) => {
+                self.delete_file(drive, file_name).await?;
+                self.create_file(drive, file_name, file_size).await?;
+            },
+            Err(e) => return Err(e),
+        }
+
+        let file_pointer = self.open_file(drive, file_name, file_control::FileOpenMode::Write).await?;
+
+        let ret = self.write_file_all(file_pointer, data).await;
+        let closed = self.close_file(file_pointer).await;
+
+        ret?;
+        closed?;
+
+        Ok(())
+    }
+
+    /// List files in the root directory of the drive, starting from `start_file_no`.
+    /// Requests are repeated internally until `count` entries are collected or the directory ends.
+    pub async fn list_files(&self, drive: FileDrive, start_file_no: u32, count: usize) -> std::io::Result<Vec<FileInfo>> {
+        let cpu: CPU = self.connection_props.cpu;
+        let mut ret: Vec<FileInfo> = Vec::with_capacity(count);
+        let mut next_file_no: u32 = start_file_no;
+
+        while ret.len() < count {
+            let request_len: u16 = (count - ret.len()).min(file_control::FILE_INFO_MAX_POINTS as usize) as u16;
+            let cmd = file_control::read_directory(&cpu, &drive, next_file_no, request_len)?;
+            let recv: &[u8] = &self.request_response(&cmd).await?;
+
+            let files = file_control::parse_directory(&cpu, recv)?;
+            let received_len: usize = files.len();
+            ret.extend(files);
+
+            if received_len < request_len as usize {
+                break;
+            }
+            next_file_no += received_len as u32;
+        }
+
+        Ok(ret)
+    }
+
+    /// Delete a file.
+    /// If the file does not exist, the error carries `SLMPEndCode::FILE_NOT_FOUND` (kind: `NotFound`).
+    pub async fn delete_file(&self, drive: FileDrive, file_name: &str) -> std::io::Result<()> {
+        let cmd = file_control::delete_file(&self.connection_props.cpu, &drive, file_name)?;
+        self.request_response(&cmd).await.map(|_| ())
+    }
+
+    /// Copy a file within the CPU, possibly across drives.
+    pub async fn copy_file(&self, src_drive: FileDrive, src_file_name: &str, dst_drive: FileDrive, dst_file_name: &str) -> std::io::Result<()> {
+        let cmd = file_control::copy_file(&self.connection_props.cpu, &src_drive, src_file_name, &dst_drive, dst_file_name)?;
+        self.request_response(&cmd).await.map(|_| ())
+    }
+
+    async fn create_file(&self, drive: FileDrive, file_name: &str, file_size: u32) -> std::io::Result<()> {
+        let cmd = file_control::create_file(&self.connection_props.cpu, &drive, file_name, file_size)?;
+        self.request_response(&cmd).await.map(|_| ())
+    }
+
+
+    async fn write_file_all(&self, file_pointer: u16, data: &[u8]) -> std::io::Result<()> {
+        let mut offset: u32 = 0;
+
+        for chunk in data.chunks(file_control::FILE_ACCESS_MAX_BYTELEN) {
+            let cmd = file_control::write_file(file_pointer, offset, chunk);
+            let recv: &[u8] = &self.request_response(&cmd).await?;
+
+            let written_len: usize = file_control::parse_written_len(recv)?;
+            if written_len != chunk.len() {
+                return Err(invalidDataError!(format!("File write incomplete, requested: {}, written: {}", chunk.len(), written_len)));
+            }
+
+            offset += chunk.len() as u32;
+        }
+
+        Ok(())
+    }
+
+    async fn open_file(&self, drive: FileDrive, file_name: &str, mode: file_control::FileOpenMode) -> std::io::Result<u16> {
+        let cmd = file_control::open_file(&self.connection_props.cpu, &drive, file_name, mode)?;
+        let recv: &[u8] = &self.request_response(&cmd).await?;
+        file_control::parse_file_pointer(recv)
+    }
+
+    async fn read_file_to_end(&self, file_pointer: u16) -> std::io::Result<Vec<u8>> {
+        let mut data: Vec<u8> = Vec::new();
+
+        loop {
+            let offset: u32 = u32::try_from(data.len())
+                .map_err(|_| invalidDataError!("File size exceeds the addressable offset"))?;
+            let cmd = file_control::read_file(file_pointer, offset, file_control::FILE_ACCESS_MAX_BYTELEN as u16);
+            let recv: &[u8] = &self.request_response(&cmd).await?;
+
+            let chunk: &[u8] = file_control::parse_read_file(recv)?;
+            data.extend_from_slice(chunk);
+
+            if chunk.len() < file_control::FILE_ACCESS_MAX_BYTELEN {
+                break;
+            }
+        }
+
+        Ok(data)
+    }
+
+    async fn close_file(&self, file_pointer: u16) -> std::io::Result<()> {
+        let cmd = file_control::close_file(file_pointer);
+        self.request_response(&cmd).await.map(|_| ())
+    }
+
+    /* Label Access */
+
+    /// Read global labels by name.
+    /// Each value is decoded with the data type reported by the CPU.
+    /// If the CPU rejects the batch (e.g. a label does not exist), labels are read one by one
+    /// so that only the failing labels carry the error.
+    pub async fn read_labels(&self, labels: &[&str]) -> std::io::Result<Vec<(String, std::io::Result<TypedData>)>> {
+        let cmd = label_access::read_labels(labels)?;
+
+        let values = match self.request_response(&cmd).await {
+            Ok(recv) => label_access::parse_labels(&recv)?,
+            Err(e) if SLMPEndCode::from_io_error(&e).is_some() && labels.len() == 1 => vec![Err(e)],
+            Err(e) if SLMPEndCode::from_io_error(&e).is_some() => {
+                let mut values = Vec::with_capacity(labels.len());
+                for label in labels {
+                    let cmd = label_access::read_labels(&[label])?;
+                    let value = match self.request_response(&cmd).await {
+                        Ok(recv) => label_access::parse_labels(&recv)?.pop().unwrap_or_else(|| Err(invalidDataError!("Received Invalid Label Data"))),
+                        Err(e) if SLMPEndCode::from_io_error(&e).is_some() => Err(e),
+                        Err(e) => return Err(e),
+                    };
+                    values.push(value);
+                }
+                values
+            },
+            Err(e) => return Err(e),
+        };
+
+        if values.len() != labels.len() {
+            return Err(invalidDataError!("Received Invalid Label Points"));
+        }
+
+        Ok(labels.iter().map(|x| x.to_string()).zip(values).collect())
+    }
+
+    /// Write global labels by name, in one request.
+    /// The labels are not retried one by one when the CPU rejects the request, so that a rejected batch is not applied in part;
+    /// the end code is returned for the whole batch.
+    pub async fn write_labels(&self, labels: &[(&str, TypedData)]) -> std::io::Result<()> {
+        let cmd = label_access::write_labels(labels)?;
+        self.request_response(&cmd).await.map(|_| ())
+    }
+
+    /* Device Access */
+    // Requests without devices (empty slices, `device_num` 0, `DeviceBlock { size: 0 }`) and `DataType::String(0)`
+    // fail with `InvalidInput` before anything is sent.
+
+    pub async fn bulk_write(&self, start_device: Device, data: &[TypedData]) -> std::io::Result<()>
+    {
+        self.bulk_write_impl(start_device, data, None).await
+    }
+
+    /// Same as `bulk_write`, but the send/receive timeouts are replaced by `timeout` only for this call.
+    pub async fn bulk_write_with_timeout(&self, start_device: Device, data: &[TypedData], timeout: Duration) -> std::io::Result<()>
+    {
+        self.bulk_write_impl(start_device, data, Some(timeout)).await
+    }
+
+    async fn bulk_write_impl(&self, start_device: Device, data: &[TypedData], timeout: Option<Duration>) -> std::io::Result<()>
+    {
+        let cmd = requests::bulk_write(&self.connection_props.cpu, &self.device_limits, start_device, data, self.word_order)?;
+        self.request_response_with_timeout(&cmd, timeout).await.map(|_| ())
+    }
+
+
+    pub async fn random_write(&self, data: &[DeviceData]) -> std::io::Result<()>
+    {
+        self.random_write_impl(data, None).await
+    }
+
+    /// Same as `random_write`, but the send/receive timeouts are replaced by `timeout` only for this call.
+    pub async fn random_write_with_timeout(&self, data: &[DeviceData], timeout: Duration) -> std::io::Result<()>
+    {
+        self.random_write_impl(data, Some(timeout)).await
+    }
+
+    async fn random_write_impl(&self, data: &[DeviceData], timeout: Option<Duration>) -> std::io::Result<()>
+    {
+        for cmd in requests::random_write(&self.connection_props.cpu, &self.device_limits, data, self.word_order)? {
+            self.request_response_with_timeout(&cmd, timeout).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn block_write<'a>(&self, data: &'a [BlockedDeviceData<'a>]) -> std::io::Result<()>
+    {
+        self.block_write_impl(data, None).await
+    }
+
+    /// Same as `block_write`, but the send/receive timeouts are replaced by `timeout` only for this call.
+    pub async fn block_write_with_timeout<'a>(&self, data: &'a [BlockedDeviceData<'a>], timeout: Duration) -> std::io::Result<()>
+    {
+        self.block_write_impl(data, Some(timeout)).await
+    }
+
+    /// Same as `block_write`, but the blocks are split across as many requests as the limits on blocks and word points take.
+    /// Every request is checked before the first is sent; the requests are not atomic as a whole.
+    pub async fn block_write_split<'a>(&self, data: &'a [BlockedDeviceData<'a>]) -> std::io::Result<()>
+    {
+        for cmd in requests::split_block_write(&self.connection_props.cpu, &self.device_limits, data, self.word_order)? {
+            self.request_response(&cmd).await?;
+        }
+        Ok(())
+    }
+
+    /// Same as `block_write`, taking blocks which own their data.
+    pub async fn block_write_owned(&self, data: &[BlockedDeviceDataOwned]) -> std::io::Result<()>
+    {
+        let data: Vec<BlockedDeviceData> = data.iter().map(BlockedDeviceData::from).collect();
+        self.block_write_impl(&data, None).await
+    }
+
+    async fn block_write_impl<'a>(&self, data: &'a [BlockedDeviceData<'a>], timeout: Option<Duration>) -> std::io::Result<()>
+    {
+        let cmd = requests::block_write(&self.connection_props.cpu, &self.device_limits, data, self.word_order)?;
+        self.request_response_with_timeout(&cmd, timeout).await.map(|_| ())
+    }
+
+    /// Same as `bulk_write`, but the devices are read back and compared with `data` after the write.
+    /// It fails with `VerificationFailed` listing every mismatching device. See also `random_write_verified` and `block_write_verified`.
+    pub async fn bulk_write_verified(&self, start_device: Device, data: &[TypedData]) -> std::io::Result<()>
+    {
+        let verification = Verification::bulk(&self.connection_props.cpu, &self.device_limits, start_device, data, self.word_order)?;
+        self.bulk_write_impl(start_device, data, None).await?;
+        self.verify(verification).await
+    }
+
+    /// Same as `random_write`, followed by random reads of the devices (in words, so timer and counter contacts cannot be verified).
+    pub async fn random_write_verified(&self, data: &[DeviceData]) -> std::io::Result<()>
+    {
+        let verification = Verification::random(&self.connection_props.cpu, &self.device_limits, data, self.word_order)?;
+        self.random_write_impl(data, None).await?;
+        self.verify(verification).await
+    }
+
+    /// Same as `block_write`, followed by a block read of the blocks.
+    pub async fn block_write_verified<'a>(&self, data: &'a [BlockedDeviceData<'a>]) -> std::io::Result<()>
+    {
+        let verification = Verification::block(&self.connection_props.cpu, &self.device_limits, data, self.word_order)?;
+        self.block_write_impl(data, None).await?;
+        self.verify(verification).await
+    }
+
+    /// Write `value` to `count` consecutive points from `start_device`, in as many bulk writes as the write limit takes.
+    /// `Bool` is written in bits, other types in words. The whole range is checked before anything is sent.
+    pub async fn fill(&self, start_device: Device, count: usize, value: TypedData) -> std::io::Result<()>
+    {
+        self.fill_with_progress(start_device, count, value, |_, _| ()).await
+    }
+
+    /// Same as `fill`, calling `progress(written, count)` after each bulk write.
+    pub async fn fill_with_progress<F>(&self, start_device: Device, count: usize, value: TypedData, mut progress: F) -> std::io::Result<()>
+    where F: FnMut(usize, usize)
+    {
+        let request = requests::Fill::new(&self.connection_props.cpu, &self.device_limits, start_device, count, &value, self.word_order)?;
+        for (written, cmd) in request.commands(self.connection_props.cpu) {
+            self.request_response(&cmd).await?;
+            progress(written, count);
+        }
+        Ok(())
+    }
+
+    /// Reset `count` bits (e.g. of M or B) from `start_device`.
+    pub async fn clear_bits(&self, start_device: Device, count: usize) -> std::io::Result<()>
+    {
+        self.fill(start_device, count, TypedData::Bool(false)).await
+    }
+
+    async fn verify(&self, verification: Verification) -> std::io::Result<()> {
+        let mut responses: Vec<Vec<u8>> = Vec::with_capacity(verification.commands().len());
+        for cmd in verification.commands() {
+            responses.push(self.request_response(cmd).await?);
+        }
+        verification.check(&responses)
+    }
+
+    pub async fn bulk_read(&self, start_device: Device, device_num: usize, data_type: DataType) -> std::io::Result<Vec<DeviceData>>
+    {
+        self.bulk_read_impl(start_device, device_num, data_type, None).await
+    }
+
+    /// Same as `bulk_read`, but the send/receive timeouts are replaced by `timeout` only for this call.
+    pub async fn bulk_read_with_timeout(&self, start_device: Device, device_num: usize, data_type: DataType, timeout: Duration) -> std::io::Result<Vec<DeviceData>>
+    {
+        self.bulk_read_impl(start_device, device_num, data_type, Some(timeout)).await
+    }
+
+    async fn bulk_read_impl(&self, start_device: Device, device_num: usize, data_type: DataType, timeout: Option<Duration>) -> std::io::Result<Vec<DeviceData>>
+    {
+        let request = requests::BulkRead { start_device, device_num, data_type };
+        let cmd = request.command(&self.connection_props.cpu, &self.device_limits)?;
+        let recv: &[u8] = &self.request_response_with_timeout(&cmd, timeout).await?;
+        Ok(requests::ordered_device_data(request.parse(recv)?, self.word_order))
+    }
+
+    /// It fails with `InvalidInput` for no devices or over `monitor_max_access_points` of the CPU.
+    pub async fn random_read(&self, devices: &[TypedDevice]) -> std::io::Result<Vec<DeviceData>>
+    {
+        self.random_read_impl(devices, None).await
+    }
+
+    /// Same as `random_read`, but the send/receive timeouts are replaced by `timeout` only for this call.
+    pub async fn random_read_with_timeout(&self, devices: &[TypedDevice], timeout: Duration) -> std::io::Result<Vec<DeviceData>>
+    {
+        self.random_read_impl(devices, Some(timeout)).await
+    }
+
+    async fn random_read_impl(&self, devices: &[TypedDevice], timeout: Option<Duration>) -> std::io::Result<Vec<DeviceData>>
+    {
+        let (cmd, monitor_list) = requests::random_read(&self.connection_props.cpu, &self.device_limits, devices)?;
+        let recv: &[u8] = &self.request_response_with_timeout(&cmd, timeout).await?;
+        Ok(requests::ordered_device_data(monitor_list.parse(recv)?, self.word_order))
+    }
+
+    /// Same as `random_read` for any number of devices, in as few requests as `read_coalescing` allows:
+    /// word devices within `max_gap` words of each other are read by a bulk read of the range around them,
+    /// and the others by random reads. The values are in the order of `devices`.
+    /// The requests are not atomic; a failed one fails the whole read.
+    pub async fn optimized_read(&self, devices: &[TypedDevice]) -> std::io::Result<Vec<DeviceData>>
+    {
+        let plan = ReadPlan::new(&self.connection_props.cpu, &self.device_limits, &self.read_coalescing, devices)?;
+        let commands = plan.commands(&self.connection_props.cpu, &self.device_limits)?;
+        let mut responses: Vec<Vec<u8>> = Vec::with_capacity(commands.len());
+        for cmd in &commands {
+            responses.push(self.request_response(cmd).await?);
+        }
+        Ok(requests::ordered_device_data(plan.parse(&responses)?, self.word_order))
+    }
+
+    pub async fn block_read(&self, device_blocks: &[DeviceBlock]) -> std::io::Result<Vec<DeviceData>>
+    {
+        self.block_read_impl(requests::BlockRead::new(&self.connection_props.cpu, &self.device_limits, device_blocks)?, None).await
+    }
+
+    /// Same as `block_read`, but the send/receive timeouts are replaced by `timeout` only for this call.
+    pub async fn block_read_with_timeout(&self, device_blocks: &[DeviceBlock], timeout: Duration) -> std::io::Result<Vec<DeviceData>>
+    {
+        self.block_read_impl(requests::BlockRead::new(&self.connection_props.cpu, &self.device_limits, device_blocks)?, Some(timeout)).await
+    }
+
+    /// Same as `block_read`, but the blocks are split across as many requests as the limits on blocks and word points take.
+    /// Every request is checked before the first is sent, and the values come back in the order of a single `block_read`.
+    pub async fn block_read_split(&self, device_blocks: &[DeviceBlock]) -> std::io::Result<Vec<DeviceData>>
+    {
+        let mut ret: Vec<DeviceData> = Vec::new();
+        for request in requests::BlockRead::split(&self.connection_props.cpu, &self.device_limits, device_blocks)? {
+            ret.extend(self.block_read_impl(request, None).await?);
+        }
+        Ok(ret)
+    }
+
+    /// Same as `block_read`, but the words of a block with a data type are decoded as that type, one value per `data_type.byte_size()`.
+    pub async fn block_read_typed(&self, device_blocks: &[TypedDeviceBlock]) -> std::io::Result<Vec<DeviceData>>
+    {
+        self.block_read_impl(requests::BlockRead::typed(&self.connection_props.cpu, &self.device_limits, device_blocks)?, None).await
+    }
+
+    async fn block_read_impl(&self, request: requests::BlockRead, timeout: Option<Duration>) -> std::io::Result<Vec<DeviceData>>
+    {
+        let cmd = request.command(&self.connection_props.cpu);
+        let recv: &[u8] = &self.request_response_with_timeout(&cmd, timeout).await?;
+        Ok(requests::ordered_device_data(request.parse(recv)?, self.word_order))
+    }
+
+    /// Run a read of `slmp::json`.
+    #[cfg(feature = "json-api")]
+    pub async fn execute_read(&self, request: &ReadRequest) -> std::io::Result<ReadResponse>
+    {
+        let data = match request {
+            ReadRequest::Bulk { start_device, device_num, data_type } => self.bulk_read(*start_device, *device_num, *data_type).await?,
+            ReadRequest::Random { devices } => self.random_read(devices).await?,
+            ReadRequest::Block { blocks } => self.block_read_typed(blocks).await?,
+        };
+        Ok(ReadResponse { data })
+    }
+
+    /// Run a write of `slmp::json`.
+    #[cfg(feature = "json-api")]
+    pub async fn execute_write(&self, request: &WriteRequest) -> std::io::Result<()>
+    {
+        match request {
+            WriteRequest::Bulk { start_device, data } => self.bulk_write(*start_device, data).await,
+            WriteRequest::Random { data } => self.random_write(data).await,
+            WriteRequest::Block { blocks } => self.block_write_owned(blocks).await,
+        }
+    }
+
+    /// Run a request of `slmp::json`. A failed request is answered with `Response::Error`.
+    #[cfg(feature = "json-api")]
+    pub async fn execute(&self, request: &Request) -> Response
+    {
+        self.execute_impl(request).await.unwrap_or_else(|e| Response::Error(ErrorResponse::from(&e)))
+    }
+
+    #[cfg(feature = "json-api")]
+    async fn execute_impl(&self, request: &Request) -> std::io::Result<Response>
+    {
+        let done = |_| Response::Done;
+        match request {
+            Request::Read { request } => self.execute_read(request).await.map(Response::Read),
+            Request::Write { request } => self.execute_write(request).await.map(done),
+            Request::ReadLabels { labels } => {
+                let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+                let values = self.read_labels(&labels).await?;
+                Ok(Response::Labels { labels: values.into_iter().map(LabelResult::from).collect() })
+            },
+            Request::WriteLabels { labels } => {
+                let labels: Vec<(&str, TypedData)> = labels.iter().map(|x| (x.name.as_str(), x.data.clone())).collect();
+                self.write_labels(&labels).await.map(done)
+            },
+            Request::ReadString { device, word_len } => self.read_string(*device, *word_len).await.map(|text| Response::String { text }),
+            Request::WriteString { device, text, word_len } => self.write_string(*device, text, *word_len).await.map(done),
+            Request::ReadStruct { start_device, fields } => self.read_fields(*start_device, fields).await.map(|fields| Response::Struct { fields }),
+            Request::WriteStruct { start_device, fields } => {
+                let data_types: Vec<DataType> = fields.iter().map(TypedData::get_type).collect();
+                self.write_fields(*start_device, &data_types, fields.clone()).await.map(done)
+            },
+            Request::ReadClock => self.read_clock().await.map(|clock| Response::Clock { clock }),
+            Request::SetClock { clock } => self.set_clock(*clock).await.map(done),
+            Request::GetCpuType => self.get_cpu_type().await.map(Response::CpuType),
+            Request::ReadErrorHistory { max_entries } => self.read_error_history(*max_entries).await.map(|errors| Response::ErrorHistory { errors }),
+            Request::ClearError => self.clear_error().await.map(done),
+            Request::RunCpu => self.run_cpu().await.map(done),
+            Request::StopCpu => self.stop_cpu().await.map(done),
+            Request::PauseCpu => self.pause_cpu().await.map(done),
+            Request::ClearLatch => self.clear_latch().await.map(done),
+            Request::ResetCpu => self.reset_cpu().await.map(done),
+            Request::ListFiles { drive, start_file_no, count } => self.list_files(*drive, *start_file_no, *count).await.map(|files| Response::Files { files }),
+            Request::DownloadFile { drive, file_name } => self.download_file(*drive, file_name).await.map(|data| Response::File { data }),
+            Request::UploadFile { drive, file_name, data, overwrite } => self.upload_file(*drive, file_name, data, *overwrite).await.map(done),
+            Request::DeleteFile { drive, file_name } => self.delete_file(*drive, file_name).await.map(done),
+            Request::CopyFile { src_drive, src_file_name, dst_drive, dst_file_name } => {
+                self.copy_file(*src_drive, src_file_name, *dst_drive, dst_file_name).await.map(done)
+            },
+        }
+    }
+
+    /// Read `word_num` words from `start_device` as they are, without the conversion into `DeviceData`.
+    pub async fn bulk_read_words(&self, start_device: Device, word_num: usize) -> std::io::Result<Vec<u16>>
+    {
+        let recv = self.bulk_read_bytes(start_device, word_num).await?;
+        Ok(requests::bytes_to_words(&recv))
+    }
+
+    /// Read `word_num` words from `start_device` as the bytes of the response (`2 * word_num`, the lower byte of each word first).
+    pub async fn bulk_read_bytes(&self, start_device: Device, word_num: usize) -> std::io::Result<Vec<u8>>
+    {
+        let request = requests::WordRead { start_device, word_num };
+        let cmd = request.command(&self.connection_props.cpu, &self.device_limits)?;
+        let recv = self.request_response(&cmd).await?;
+        request.check(&recv)?;
+        Ok(recv)
+    }
+
+    /// Write words from `start_device` as they are.
+    pub async fn bulk_write_words(&self, start_device: Device, data: &[u16]) -> std::io::Result<()>
+    {
+        let cmd = requests::bulk_write_words(&self.connection_props.cpu, &self.device_limits, start_device, data)?;
+        self.request_response(&cmd).await.map(|_| ())
+    }
+
+    /// Read the raw words of `blocks` (bit blocks in points, word blocks in words) in as many bulk reads as they take.
+    pub async fn snapshot(&self, blocks: &[DeviceBlock]) -> std::io::Result<DeviceSnapshot>
+    {
+        let request = SnapshotRead::new(&self.connection_props.cpu, &self.device_limits, blocks)?;
+        let mut responses: Vec<Vec<u8>> = Vec::with_capacity(request.commands().len());
+        for cmd in request.commands() {
+            responses.push(self.request_response(cmd).await?);
+        }
+        request.parse(self.connection_props.cpu, &responses)
+    }
+
+    /// Write a snapshot back in as many bulk writes as it takes.
+    /// Every area is checked against the device limits of this client before the first write, so that a snapshot
+    /// of another CPU series is rejected with `InvalidInput` as a whole. The areas are not written atomically.
+    pub async fn restore(&self, snapshot: &DeviceSnapshot) -> std::io::Result<()>
+    {
+        for cmd in snapshot::restore(&self.connection_props.cpu, &self.device_limits, snapshot)? {
+            self.request_response(&cmd).await?;
+        }
+        Ok(())
+    }
+
+    /// Write `(word & and_mask) | or_mask` to the word of `device`, returning the bits read before the write.
+    /// The word is read and written in two requests: a change by the PLC program in between is overwritten.
+    pub async fn modify_word(&self, device: Device, and_mask: u16, or_mask: u16) -> std::io::Result<[bool; 16]>
+    {
+        let previous: u16 = self.bulk_read_words(device, 1).await?[0];
+        self.bulk_write_words(device, &[(previous & and_mask) | or_mask]).await?;
+        Ok(u16_to_bits(previous))
+    }
+
+    /// Write 16 bits to the word of `device` (bit 0: the least significant), e.g. a word of D or 16 points of M from a multiple of 16.
+    pub async fn write_bits16(&self, device: Device, bits: [bool; 16]) -> std::io::Result<()>
+    {
+        self.bulk_write(device, &[TypedData::BitArray16(bits)]).await
+    }
+
+    /// Set bit `bit_index` (0 to 15) of the word of `device`, leaving the other bits (see `modify_word`).
+    pub async fn set_word_bit(&self, device: Device, bit_index: u8, value: bool) -> std::io::Result<[bool; 16]>
+    {
+        let mask: u16 = requests::word_bit_mask(bit_index)?;
+        self.modify_word(device, !mask, if value { mask } else { 0 }).await
+    }
+
+    /// Read a Shift-JIS string stored in `word_len` words from `device`, up to the first NUL.
+    pub async fn read_string(&self, device: Device, word_len: u8) -> std::io::Result<String>
+    {
+        let request = requests::StringRead { device, word_len };
+        let cmd = request.command(&self.connection_props.cpu, &self.device_limits)?;
+        let recv: &[u8] = &self.request_response(&cmd).await?;
+        request.parse(recv)
+    }
+
+    /// Write `text` in Shift-JIS to `word_len` words from `device`, padded with NULs.
+    /// It fails with `InvalidInput` if the text does not fit or is not representable in Shift-JIS.
+    pub async fn write_string(&self, device: Device, text: &str, word_len: u8) -> std::io::Result<()>
+    {
+        let cmd = requests::string_write(&self.connection_props.cpu, &self.device_limits, device, text, word_len)?;
+        self.request_response(&cmd).await.map(|_| ())
+    }
+
+    /// Read a struct laid out in consecutive word devices from `start_device` (see `device_struct!`).
+    pub async fn read_struct<S: SlmpReadable>(&self, start_device: Device) -> std::io::Result<S>
+    {
+        S::from_fields(self.read_fields(start_device, S::FIELDS).await?)
+    }
+
+    /// Write a struct to consecutive word devices from `start_device` in one request.
+    pub async fn write_struct<S: SlmpWritable>(&self, start_device: Device, value: &S) -> std::io::Result<()>
+    {
+        self.write_fields(start_device, S::FIELDS, value.to_fields()).await
+    }
+
+    async fn read_fields(&self, start_device: Device, fields: &[DataType]) -> std::io::Result<Vec<TypedData>>
+    {
+        let request = requests::StructRead { start_device, fields };
+        let cmd = request.command(&self.connection_props.cpu, &self.device_limits)?;
+        let recv: &[u8] = &self.request_response(&cmd).await?;
+        Ok(request.parse(recv)?.into_iter().map(|x| x.with_word_order(self.word_order)).collect())
+    }
+
+    async fn write_fields(&self, start_device: Device, fields: &[DataType], values: Vec<TypedData>) -> std::io::Result<()>
+    {
+        let cmd = requests::struct_write(&self.connection_props.cpu, &self.device_limits, start_device, fields, values, self.word_order)?;
+        self.request_response(&cmd).await.map(|_| ())
+    }
+
+    /// Register devices for `monitor_read`. It fails with `InvalidInput` for no devices or over `monitor_max_access_points` of the CPU (with a `MonitorListFull`).
+    pub async fn monitor_register(&self, devices: &[TypedDevice]) -> std::io::Result<MonitorList>
+    {
+        let (cmd, monitor_list) = requests::monitor_register(&self.connection_props.cpu, &self.device_limits, devices)?;
+        self.request_response(&cmd).await?;
+
+        Ok(monitor_list)
+    }
+
+    /// Release the devices registered by `monitor_register`, by registering no devices.
+    /// `monitor_read` fails with an end code until devices are registered again.
+    pub async fn monitor_clear(&self) -> std::io::Result<()>
+    {
+        let cmd = requests::monitor_clear(&self.connection_props.cpu);
+        self.request_response(&cmd).await.map(|_| ())
+    }
+
+    pub async fn monitor_read(&self, monitor_list: &MonitorList) -> std::io::Result<Vec<DeviceData>>
+    {
+        const COMMAND: SLMPMonitorReadCommand = SLMPMonitorReadCommand::new();
+        let recv: &[u8] = &self.request_response(&COMMAND).await?;
+
+        Ok(requests::ordered_device_data(monitor_list.parse(recv)?, self.word_order))
+    }
+
+    /// Same as `monitor_read`, reusing the request frame, the response buffer and the values of `buffer` from the previous call,
+    /// for cyclic reads at short cycles. The values are left in `buffer` until the next call.
+    pub async fn monitor_read_with<'a>(&self, monitor_list: &MonitorList, buffer: &'a mut MonitorReadBuffer) -> std::io::Result<&'a [DeviceData]>
+    {
+        const COMMAND: SLMPMonitorReadCommand = SLMPMonitorReadCommand::new();
+        if buffer.frame.is_empty() {
+            buffer.frame = requests::request_frame(&self.connection_props, 0, &COMMAND)?;
+        }
+        self.exchange(&mut buffer.frame, None, &mut buffer.response).await?;
+
+        monitor_list.parse_into(&buffer.response[RECVFRAME_PREFIX_FIXED_LEN..], &mut buffer.values)?;
+        requests::order_device_data(&mut buffer.values, self.word_order);
+        Ok(&buffer.values)
+    }
+
+}
+
+/// Request frame, response buffer and values of `SLMPClient::monitor_read_with`, kept between cyclic reads.
+/// The frame is built on the first read; a buffer is for the client it was first used with.
+#[derive(Clone, Debug, Default)]
+pub struct MonitorReadBuffer {
+    frame: Vec<u8>,
+    response: Vec<u8>,
+    values: Vec<DeviceData>,
+}
+
+impl MonitorReadBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop the frame and the buffer, e.g. after the monitor list changed, so that the next read builds them again.
+    pub fn invalidate(&mut self) {
+        *self = Self::default();
+    }
+}
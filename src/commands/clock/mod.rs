@@ -0,0 +1,166 @@
+/*
+SLMP has no dedicated clock command; the CPU exposes its clock through special registers.
+
+Q/L (BCD):
+SD210: year (lower 2 digits) | month
+SD211: day | hour
+SD212: minute | second
+SD213: year (upper 2 digits) | day of week
+
+R (binary):
+SD210: year, SD211: month, SD212: day, SD213: hour, SD214: minute, SD215: second, SD216: day of week
+
+The clock is set by writing SD210~ and turning SM210 (clock data set request) on.
+*/
+
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::commands::device_access::{read::*, write::*};
+use crate::{CPU, DataType, Device, DeviceType, TypedData};
+
+const CLOCK_DATA_DEVICE: Device = Device { device_type: DeviceType::SD, address: 210 };
+const CLOCK_SET_REQUEST_DEVICE: Device = Device { device_type: DeviceType::SM, address: 210 };
+
+const YEAR_MIN: u16 = 1980;
+const YEAR_MAX: u16 = 2079;
+
+/// Date and time of the PLC real-time clock.
+/// day_of_week: 0 (Sunday) ~ 6 (Saturday)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PLCDateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub day_of_week: u8,
+}
+
+impl PLCDateTime {
+    /// Check the range of each field. The year must be within the range the CPU accepts (1980~2079).
+    pub fn validate(&self) -> crate::io::Result<()> {
+        let in_range = (YEAR_MIN..=YEAR_MAX).contains(&self.year)
+            && (1..=12).contains(&self.month)
+            && (1..=31).contains(&self.day)
+            && self.hour < 24
+            && self.minute < 60
+            && self.second < 60
+            && self.day_of_week < 7;
+
+        if in_range {
+            Ok(())
+        } else {
+            Err(crate::io::Error::new(
+                crate::io::ErrorKind::InvalidInput,
+                format!("Invalid clock data {self:?} (year must be {YEAR_MIN}~{YEAR_MAX})")
+            ))
+        }
+    }
+}
+
+#[inline(always)]
+const fn clock_data_len(cpu: &CPU) -> usize {
+    match cpu {
+        CPU::Q | CPU::L | CPU::A => 4,
+        CPU::R | CPU::F => 7,
+    }
+}
+
+#[inline(always)]
+pub(crate) const fn bcd_to_u8(n: u8) -> Option<u8> {
+    let (high, low) = (n >> 4, n & 0x0F);
+    if high > 9 || low > 9 { None } else { Some(high * 10 + low) }
+}
+
+#[inline(always)]
+const fn u8_to_bcd(n: u8) -> u8 {
+    ((n / 10) << 4) | (n % 10)
+}
+
+pub(crate) fn read_clock(cpu: &CPU) -> Vec<u8> {
+    let query = SLMPBulkReadQuery {
+        cpu,
+        start_device: CLOCK_DATA_DEVICE,
+        device_num: clock_data_len(cpu),
+        data_type: DataType::U16,
+    };
+    SLMPBulkReadCommand::from(query).0
+}
+
+pub(crate) fn parse_clock(cpu: &CPU, data: &[u8]) -> crate::io::Result<PLCDateTime> {
+    let invalid = || crate::io::Error::new(crate::io::ErrorKind::InvalidData, "Received Invalid Clock Data");
+
+    let words: Vec<u16> = data.chunks_exact(2).map(|x| u16::from_le_bytes([x[0], x[1]])).collect();
+    if words.len() < clock_data_len(cpu) {
+        return Err(invalid());
+    }
+
+    let datetime = match cpu {
+        CPU::Q | CPU::L | CPU::A => {
+            let bytes: Vec<u8> = words.iter().flat_map(|x| x.to_be_bytes()).collect();
+            let digits: Vec<u8> = bytes.iter().map(|&x| bcd_to_u8(x)).collect::<Option<Vec<u8>>>().ok_or_else(invalid)?;
+            PLCDateTime {
+                year: digits[6] as u16 * 100 + digits[0] as u16,
+                month: digits[1],
+                day: digits[2],
+                hour: digits[3],
+                minute: digits[4],
+                second: digits[5],
+                day_of_week: bytes[7] & 0x0F,
+            }
+        },
+        CPU::R | CPU::F => PLCDateTime {
+            year: words[0],
+            month: words[1] as u8,
+            day: words[2] as u8,
+            hour: words[3] as u8,
+            minute: words[4] as u8,
+            second: words[5] as u8,
+            day_of_week: words[6] as u8,
+        },
+    };
+
+    Ok(datetime)
+}
+
+pub(crate) fn write_clock(cpu: &CPU, datetime: &PLCDateTime) -> crate::io::Result<Vec<u8>> {
+    datetime.validate()?;
+
+    let words: Vec<u16> = match cpu {
+        CPU::Q | CPU::L | CPU::A => vec![
+            u16::from_be_bytes([u8_to_bcd((datetime.year % 100) as u8), u8_to_bcd(datetime.month)]),
+            u16::from_be_bytes([u8_to_bcd(datetime.day), u8_to_bcd(datetime.hour)]),
+            u16::from_be_bytes([u8_to_bcd(datetime.minute), u8_to_bcd(datetime.second)]),
+            u16::from_be_bytes([u8_to_bcd((datetime.year / 100) as u8), datetime.day_of_week]),
+        ],
+        CPU::R | CPU::F => vec![
+            datetime.year,
+            datetime.month as u16,
+            datetime.day as u16,
+            datetime.hour as u16,
+            datetime.minute as u16,
+            datetime.second as u16,
+            datetime.day_of_week as u16,
+        ],
+    };
+    let data: Vec<TypedData> = words.into_iter().map(TypedData::U16).collect();
+
+    let query = SLMPBulkWriteQuery {
+        cpu,
+        start_device: CLOCK_DATA_DEVICE,
+        data: &data,
+    };
+    Ok(SLMPBulkWriteCommand::from(query).0)
+}
+
+/// The clock is updated at the rising edge of SM210.
+pub(crate) fn set_clock_request(cpu: &CPU, value: bool) -> Vec<u8> {
+    let query = SLMPBulkWriteQuery {
+        cpu,
+        start_device: CLOCK_SET_REQUEST_DEVICE,
+        data: &[TypedData::Bool(value)],
+    };
+    SLMPBulkWriteCommand::from(query).0
+}
@@ -1,2 +1,63 @@
 pub(crate) mod read;
 pub(crate) mod write;
+
+use crate::prelude::*;
+use crate::CPU;
+
+/* Maximum access points of a request (SLMP reference manual). Larger requests fail before a frame is built. */
+
+/// Bulk read/write (0x0401/0x1401) in word units.
+pub(crate) const BULK_MAX_WORD_POINTS: usize = 960;
+/// Bulk read/write (0x0401/0x1401) in bit units.
+pub(crate) const BULK_MAX_BIT_POINTS: usize = 7168;
+/// Batch read/write of the 1E frame (MELSEC-A), in words and in bits alike.
+pub(crate) const BULK_MAX_POINTS_1E: usize = 256;
+/// Bulk read/write in word units on `cpu`.
+pub(crate) const fn bulk_max_word_points(cpu: &CPU) -> usize {
+    match cpu {
+        CPU::Q | CPU::L | CPU::F | CPU::R => BULK_MAX_WORD_POINTS,
+        CPU::A => BULK_MAX_POINTS_1E,
+    }
+}
+/// Bulk read/write in bit units on `cpu`.
+pub(crate) const fn bulk_max_bit_points(cpu: &CPU) -> usize {
+    match cpu {
+        CPU::Q | CPU::L | CPU::F | CPU::R => BULK_MAX_BIT_POINTS,
+        CPU::A => BULK_MAX_POINTS_1E,
+    }
+}
+/// Random write (0x1402) in bit units, halved for the longer device specification of iQ-R.
+pub(crate) const fn random_write_max_bit_points(cpu: &CPU) -> usize {
+    match cpu {
+        CPU::Q | CPU::L | CPU::F | CPU::A => 188,
+        CPU::R => 94,
+    }
+}
+/// Random write (0x1402) in word units: word points x 12 + double word points x 14 within this,
+/// halved for the longer device specification of iQ-R.
+pub(crate) const fn random_write_max_word_size(cpu: &CPU) -> usize {
+    match cpu {
+        CPU::Q | CPU::L | CPU::F | CPU::A => 1920,
+        CPU::R => 960,
+    }
+}
+/// Access points of a random write (0x1402) in word units with double words only, which any mix of word sizes fits in.
+pub(crate) const fn random_write_max_access_points(cpu: &CPU) -> usize {
+    random_write_max_word_size(cpu) / 14
+}
+/// Block read/write (0x0406/0x1406): word blocks + bit blocks, halved for the longer device specification of iQ-R.
+pub(crate) const fn block_max_blocks(cpu: &CPU) -> usize {
+    match cpu {
+        CPU::Q | CPU::L | CPU::F | CPU::A => 120,
+        CPU::R => 60,
+    }
+}
+/// Block read/write (0x0406/0x1406): words of all the blocks.
+pub(crate) const BLOCK_MAX_POINTS: usize = 960;
+
+pub(crate) fn check_limit(what: &str, points: usize, max: usize) -> crate::io::Result<()> {
+    if points > max {
+        return Err(crate::io::Error::new(crate::io::ErrorKind::InvalidInput, format!("Too many {what}: {points} > {max}")));
+    }
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{AccessType, CPU, DataType, Device};
 use crate::commands::COMMAND_BYTELEN;
 
@@ -11,7 +12,7 @@ pub(crate) struct SLMPBulkReadQuery<'a> {
 }
 
 pub(crate) struct SLMPBulkReadCommand(pub Vec<u8>);
-impl std::ops::Deref for SLMPBulkReadCommand {
+impl core::ops::Deref for SLMPBulkReadCommand {
     type Target = Vec<u8>;
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -35,11 +36,11 @@ fn construct_frame (query: SLMPBulkReadQuery) -> Vec<u8> {
     const COMMAND: [u8; 2] = COMMAND_BULK_READ.to_le_bytes();
     let subcommand: [u8; 2] = match access_type {
         AccessType::Bit => match query.cpu {
-            CPU::Q | CPU::L => [0x01, 0x00],
+            CPU::Q | CPU::L | CPU::F | CPU::A => [0x01, 0x00],
             CPU::R => [0x03, 0x00],
         },
         AccessType::Word => match query.cpu {
-            CPU::Q | CPU::L => [0x00, 0x00],
+            CPU::Q | CPU::L | CPU::F | CPU::A => [0x00, 0x00],
             CPU::R => [0x02, 0x00],
         }
     };
@@ -50,7 +51,7 @@ fn construct_frame (query: SLMPBulkReadQuery) -> Vec<u8> {
     let device_addr_len: u8 = Device::addr_code_len(query.cpu);
     let data_packet_len = device_addr_len as usize + 2;
 
-    let mut data_packet: Vec<u8> = Vec::with_capacity(data_packet_len as usize);
+    let mut data_packet: Vec<u8> = Vec::with_capacity(data_packet_len);
     data_packet.extend_from_slice(&start_address);
     data_packet.extend_from_slice(&device_size_code);
 
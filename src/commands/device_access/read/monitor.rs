@@ -1,3 +1,5 @@
+use crate::prelude::*;
+use crate::device::DeviceSize;
 use crate::{CPU, Device, MonitorList};
 use crate::commands::COMMAND_BYTELEN;
 
@@ -10,7 +12,7 @@ pub(crate) struct SLMPMonitorRegisterQuery<'a>{
 }
 
 pub(crate) struct SLMPMonitorRegisterCommand(pub Vec<u8>);
-impl std::ops::Deref for SLMPMonitorRegisterCommand {
+impl core::ops::Deref for SLMPMonitorRegisterCommand {
     type Target = Vec<u8>;
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -30,19 +32,30 @@ fn construct_frame (query: SLMPMonitorRegisterQuery) -> Vec<u8> {
 
     const COMMAND: [u8; 2] = COMMAND_REGISTER_MONITOR.to_le_bytes();
     let subcommand: [u8; 2] = match query.cpu {
-        CPU::Q | CPU::L => [0x00, 0x00],
+        CPU::Q | CPU::L | CPU::F | CPU::A => [0x00, 0x00],
         CPU::R => [0x02, 0x00],
     };
 
     let device_addr_bytelen: usize = Device::addr_code_len(query.cpu) as usize;
-    let total_access_points: usize = (query.monitor_list.single_word_access_points + query.monitor_list.double_word_access_points) as usize;
+    let total_access_points: usize = query.monitor_list.access_points();
 
     let data_packet_len: usize = ACCESS_POINTS_BYTELEN + (total_access_points * device_addr_bytelen);
     let mut data_packet: Vec<u8> = Vec::with_capacity(data_packet_len);
 
-    data_packet.extend([query.monitor_list.single_word_access_points, query.monitor_list.double_word_access_points,]);
+    // The counts fit in a byte as the list is validated before a frame is built.
+    data_packet.extend([query.monitor_list.single_word_access_points as u8, query.monitor_list.double_word_access_points as u8]);
+    // A multi-word device is registered as consecutive single-word devices, the same as random read.
     for device in &query.monitor_list.sorted_devices {
-        data_packet.extend(device.1.device.serialize(query.cpu));
+        match device.1.data_type.device_size() {
+            DeviceSize::MultiWord(n) => {
+                let mut target_device = device.1.device;
+                for _ in 0..n {
+                    data_packet.extend(target_device.serialize(query.cpu));
+                    target_device.address += 1;
+                }
+            },
+            _ => data_packet.extend(device.1.device.serialize(query.cpu)),
+        };
     }
 
     let mut packet: Vec<u8> = Vec::with_capacity(COMMAND_BYTELEN + data_packet_len);
@@ -55,7 +68,7 @@ fn construct_frame (query: SLMPMonitorRegisterQuery) -> Vec<u8> {
 
 
 pub(crate) struct SLMPMonitorReadCommand(pub [u8; 4]);
-impl std::ops::Deref for SLMPMonitorReadCommand {
+impl core::ops::Deref for SLMPMonitorReadCommand {
     type Target = [u8];
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::device::DeviceSize;
 use crate::{CPU, Device, MonitorList};
 use crate::commands::COMMAND_BYTELEN;
@@ -10,7 +11,7 @@ pub(crate) struct SLMPRandomReadQuery<'a>{
 }
 
 pub(crate) struct SLMPRandomReadCommand(pub Vec<u8>);
-impl std::ops::Deref for SLMPRandomReadCommand {
+impl core::ops::Deref for SLMPRandomReadCommand {
     type Target = Vec<u8>;
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -30,17 +31,18 @@ fn construct_frame (query: SLMPRandomReadQuery) -> Vec<u8> {
 
     const COMMAND: [u8; 2] = COMMAND_RANDOM_READ.to_le_bytes();
     let subcommand: [u8; 2] = match query.cpu {
-        CPU::Q | CPU::L => [0x00, 0x00],
+        CPU::Q | CPU::L | CPU::F | CPU::A => [0x00, 0x00],
         CPU::R => [0x02, 0x00]
     };
 
     let device_addr_bytelen: usize = Device::addr_code_len(query.cpu) as usize;
-    let total_access_points: usize = (query.monitor_list.single_word_access_points + query.monitor_list.double_word_access_points) as usize;
+    let total_access_points: usize = query.monitor_list.access_points();
 
     let data_packet_len: usize = ACCESS_POINTS_BYTELEN + (total_access_points * device_addr_bytelen);
     let mut data_packet: Vec<u8> = Vec::with_capacity(data_packet_len);
 
-    data_packet.extend([query.monitor_list.single_word_access_points, query.monitor_list.double_word_access_points,]);
+    // The counts fit in a byte as the list is validated before a frame is built.
+    data_packet.extend([query.monitor_list.single_word_access_points as u8, query.monitor_list.double_word_access_points as u8]);
 
     // The devices "sorted_device" is in the order of single-word, multi-word, and double-word.
     // A multi-word read-request is to be decomposed to single-word read-requests.
@@ -50,7 +52,7 @@ fn construct_frame (query: SLMPRandomReadQuery) -> Vec<u8> {
                 let mut target_device = device.1.device;
                 for _ in 0..n {
                     data_packet.extend(target_device.serialize(query.cpu));
-                    target_device.address += 1 as usize;
+                    target_device.address += 1;
                 }
             },
             _ => data_packet.extend(device.1.device.serialize(query.cpu)),
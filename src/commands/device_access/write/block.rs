@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{AccessType, BlockedDeviceData, CPU, TypedData, bits_to_u8, div_ceil};
 use crate::commands::COMMAND_BYTELEN;
 
@@ -11,7 +12,7 @@ pub(crate) struct SLMPBlockWriteQuery<'a> {
 }
 
 pub(crate) struct SLMPBlockWriteCommand(pub Vec<u8>);
-impl std::ops::Deref for SLMPBlockWriteCommand {
+impl core::ops::Deref for SLMPBlockWriteCommand {
     type Target = Vec<u8>;
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -29,7 +30,7 @@ fn construct_frame(query: SLMPBlockWriteQuery) -> Vec<u8> {
 
     const COMMAND: [u8; 2] = COMMAND_BLOCK_WRITE.to_le_bytes();
     let subcommand: [u8; 2] = match query.cpu {
-        CPU::Q | CPU::L => [0x00, 0x00],
+        CPU::Q | CPU::L | CPU::F | CPU::A => [0x00, 0x00],
         CPU::R => [0x02, 0x00],
     };
 
@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::{AccessType, CPU, Device, TypedData, div_ceil};
 use crate::commands::COMMAND_BYTELEN;
 
@@ -10,7 +11,7 @@ pub(crate) struct SLMPBulkWriteQuery<'a> {
 }
 
 pub(crate) struct SLMPBulkWriteCommand(pub Vec<u8>);
-impl std::ops::Deref for SLMPBulkWriteCommand {
+impl core::ops::Deref for SLMPBulkWriteCommand {
     type Target = Vec<u8>;
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -24,6 +25,51 @@ impl<'a> From<SLMPBulkWriteQuery<'a>> for SLMPBulkWriteCommand {
     }
 }
 
+/// Words written as they are, without the conversion of `TypedData`.
+pub(crate) struct SLMPBulkWriteWordsQuery<'a> {
+    pub cpu: &'a CPU,
+    pub start_device: Device,
+    pub data: &'a [u16],
+}
+
+impl<'a> From<SLMPBulkWriteWordsQuery<'a>> for SLMPBulkWriteCommand {
+    fn from(value: SLMPBulkWriteWordsQuery) -> Self {
+        let data_code: Vec<u8> = value.data.iter().flat_map(|x| x.to_le_bytes()).collect();
+        let cmd = construct_packet(value.cpu, value.start_device, AccessType::Word, value.data.len(), &data_code);
+        Self(cmd)
+    }
+}
+
+/// `count` points of one value: the encoded value (a word image, or a bit) repeated.
+pub(crate) struct SLMPBulkFillQuery<'a> {
+    pub cpu: &'a CPU,
+    pub start_device: Device,
+    pub count: usize,
+    pub value: &'a [u8],
+    pub access_type: AccessType,
+}
+
+impl<'a> From<SLMPBulkFillQuery<'a>> for SLMPBulkWriteCommand {
+    fn from(value: SLMPBulkFillQuery) -> Self {
+        let cmd = match value.access_type {
+            AccessType::Word => {
+                let data_code: Vec<u8> = value.value.repeat(value.count);
+                construct_packet(value.cpu, value.start_device, AccessType::Word, data_code.len() / 2, &data_code)
+            },
+            AccessType::Bit => {
+                // Two points per byte; an odd count leaves the lower nibble of the last byte empty.
+                let bit = value.value[0] & 0x01;
+                let mut data_code: Vec<u8> = vec![(bit << 4) | bit; div_ceil(value.count, 2)];
+                if value.count % 2 == 1 && let Some(last) = data_code.last_mut() {
+                    *last = bit << 4;
+                }
+                construct_packet(value.cpu, value.start_device, AccessType::Bit, value.count, &data_code)
+            },
+        };
+        Self(cmd)
+    }
+}
+
 fn construct_frame(query: SLMPBulkWriteQuery) -> Vec<u8> {
 
     let access_type: AccessType = match query.data.iter().all(|x| matches!(x, TypedData::Bool(_))) {
@@ -31,23 +77,6 @@ fn construct_frame(query: SLMPBulkWriteQuery) -> Vec<u8> {
         false => AccessType::Word
     };
 
-    const COMMAND: [u8; 2] = COMMAND_BULK_WRITE.to_le_bytes();
-    let subcommand: [u8; 2] = match access_type {
-        AccessType::Bit => match query.cpu {
-            CPU::Q | CPU::L => [0x01, 0x00],
-            CPU::R => [0x03, 0x00],
-        },
-        AccessType::Word => match query.cpu {
-            CPU::Q | CPU::L => [0x00, 0x00],
-            CPU::R => [0x02, 0x00],
-        }
-    };
-
-    let start_address: Box<[u8]> = query.start_device.serialize(query.cpu);
-
-    let mut data_packet: Vec<u8> = vec![];
-
-    data_packet.extend(start_address);
     match access_type {
         AccessType::Word => {
             let mut data_code: Vec<u8> = vec![];
@@ -55,10 +84,7 @@ fn construct_frame(query: SLMPBulkWriteQuery) -> Vec<u8> {
                 data_code.extend(x.to_bytes());
             }
             let word_size: usize = data_code.len() / 2;
-            let device_size_code: [u8; 2] = (word_size as u16).to_le_bytes();
-
-            data_packet.extend(device_size_code);
-            data_packet.extend(data_code);
+            construct_packet(query.cpu, query.start_device, access_type, word_size, &data_code)
         }
         AccessType::Bit => {
             let byte_size = div_ceil(query.data.len(), 2);
@@ -67,20 +93,37 @@ fn construct_frame(query: SLMPBulkWriteQuery) -> Vec<u8> {
                 bit_array[i] = matches!(x, TypedData::Bool(true));
             }
 
+            // Two points per byte, the first point in the upper nibble.
             let data_code: Vec<u8> = bit_array.chunks_exact(2)
-                    .map(|x| (x[1] as u8) + ((x[0] as u8) << 4))
+                    .map(|x| ((x[0] as u8) << 4) | (x[1] as u8))
                     .collect();
-            let device_size_code: [u8; 2] = (query.data.len() as u16).to_le_bytes();
-
-            data_packet.extend(device_size_code);
-            data_packet.extend(data_code);
+            construct_packet(query.cpu, query.start_device, access_type, query.data.len(), &data_code)
         }
     }
+}
+
+fn construct_packet(cpu: &CPU, start_device: Device, access_type: AccessType, device_num: usize, data_code: &[u8]) -> Vec<u8> {
+    const COMMAND: [u8; 2] = COMMAND_BULK_WRITE.to_le_bytes();
+    let subcommand: [u8; 2] = match access_type {
+        AccessType::Bit => match cpu {
+            CPU::Q | CPU::L | CPU::F | CPU::A => [0x01, 0x00],
+            CPU::R => [0x03, 0x00],
+        },
+        AccessType::Word => match cpu {
+            CPU::Q | CPU::L | CPU::F | CPU::A => [0x00, 0x00],
+            CPU::R => [0x02, 0x00],
+        }
+    };
+
+    let start_address: Box<[u8]> = start_device.serialize(cpu);
+    let device_size_code: [u8; 2] = (device_num as u16).to_le_bytes();
 
-    let mut packet: Vec<u8> = Vec::with_capacity(COMMAND_BYTELEN + data_packet.len());
+    let mut packet: Vec<u8> = Vec::with_capacity(COMMAND_BYTELEN + start_address.len() + 2 + data_code.len());
     packet.extend(COMMAND);
     packet.extend(subcommand);
-    packet.extend(data_packet);
+    packet.extend(start_address);
+    packet.extend(device_size_code);
+    packet.extend(data_code);
 
     packet
 }
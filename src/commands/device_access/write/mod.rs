@@ -1,6 +1,6 @@
 
 mod bulk;
-pub(crate)  use bulk::{SLMPBulkWriteCommand, SLMPBulkWriteQuery};
+pub(crate)  use bulk::{SLMPBulkFillQuery, SLMPBulkWriteCommand, SLMPBulkWriteQuery, SLMPBulkWriteWordsQuery};
 
 mod random;
 pub(crate) use random::{SLMPRandomWriteCommand, SLMPRandomWriteQuery};
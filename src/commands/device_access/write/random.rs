@@ -1,3 +1,4 @@
+use crate::prelude::*;
 use crate::device::DeviceSize;
 use crate::{AccessType, CPU, Device, DeviceData, TypedData};
 use crate::commands::COMMAND_BYTELEN;
@@ -14,7 +15,7 @@ pub(crate) struct SLMPRandomWriteQuery<'a> {
 }
 
 pub(crate) struct SLMPRandomWriteCommand(pub Vec<u8>);
-impl std::ops::Deref for SLMPRandomWriteCommand {
+impl core::ops::Deref for SLMPRandomWriteCommand {
     type Target = Vec<u8>;
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -30,40 +31,40 @@ impl<'a> From<SLMPRandomWriteQuery<'a>> for SLMPRandomWriteCommand {
 
 fn construct_frame(query: SLMPRandomWriteQuery) -> Vec<u8> {
 
-    const SINGLE_WORD_BYTELEN: u8 = 2;
-    const DOUBLE_WORD_BYTELEN: u8 = 4;
+    const SINGLE_WORD_BYTELEN: usize = 2;
+    const DOUBLE_WORD_BYTELEN: usize = 4;
 
     let bit_bytelen = match query.cpu {
-        CPU::Q | CPU::L => 1,
+        CPU::Q | CPU::L | CPU::F | CPU::A => 1,
         CPU::R => 2,
     };
 
-    let device_addr_bytelen: u8 = Device::addr_code_len(query.cpu);
-    let bit_wreq_bytelen: u8 = device_addr_bytelen + bit_bytelen;
-    let single_word_wreq_bytelen: u8 = device_addr_bytelen + SINGLE_WORD_BYTELEN;
-    let double_word_wreq_bytelen: u8 = device_addr_bytelen + DOUBLE_WORD_BYTELEN;
+    let device_addr_bytelen: usize = Device::addr_code_len(query.cpu) as usize;
+    let bit_wreq_bytelen: usize = device_addr_bytelen + bit_bytelen;
+    let single_word_wreq_bytelen: usize = device_addr_bytelen + SINGLE_WORD_BYTELEN;
+    let double_word_wreq_bytelen: usize = device_addr_bytelen + DOUBLE_WORD_BYTELEN;
 
     const COMMAND: [u8; 2] = COMMAND_RANDOM_WRITE.to_le_bytes();
     let subcommand: [u8; 2] = match (query.access_type, query.cpu) {
-        (AccessType::Bit, CPU::Q | CPU::L) => [0x01, 0x00],
+        (AccessType::Bit, CPU::Q | CPU::L | CPU::F | CPU::A) => [0x01, 0x00],
         (AccessType::Bit, CPU::R) => [0x03, 0x00],
-        (AccessType::Word, CPU::Q | CPU::L) => [0x00, 0x00],
+        (AccessType::Word, CPU::Q | CPU::L | CPU::F | CPU::A) => [0x00, 0x00],
         (AccessType::Word, CPU::R) => [0x02, 0x00],
     };
 
     let data_packet_len = match query.access_type {
         AccessType::Word => {
-            const LENGTH_SPECIFIER_BYTELEN: u8 = 2;
-            let single_words_wreq_bytelen: u8 = query.single_word_access_points * single_word_wreq_bytelen;
-            let double_words_wreq_bytelen: u8 = query.double_word_access_points * double_word_wreq_bytelen;
+            const LENGTH_SPECIFIER_BYTELEN: usize = 2;
+            let single_words_wreq_bytelen: usize = query.single_word_access_points as usize * single_word_wreq_bytelen;
+            let double_words_wreq_bytelen: usize = query.double_word_access_points as usize * double_word_wreq_bytelen;
             LENGTH_SPECIFIER_BYTELEN + single_words_wreq_bytelen + double_words_wreq_bytelen
         },
         AccessType::Bit => {
-            const LENGTH_SPECIFIER_BYTELEN: u8 = 1;
-            let bits_wreq_bytelen: u8 = query.bit_access_points * bit_wreq_bytelen;
+            const LENGTH_SPECIFIER_BYTELEN: usize = 1;
+            let bits_wreq_bytelen: usize = query.bit_access_points as usize * bit_wreq_bytelen;
             LENGTH_SPECIFIER_BYTELEN + bits_wreq_bytelen
         }
-    } as usize;
+    };
 
     let mut data_packet: Vec<u8> = Vec::with_capacity(data_packet_len);
 
@@ -77,11 +78,12 @@ fn construct_frame(query: SLMPRandomWriteQuery) -> Vec<u8> {
                     DeviceSize::MultiWord(n) => {
                         let mut target_device = x.device;
                         let bytelen = n as usize * 2;
-                        let data = &x.data.to_bytes()[..bytelen];
-                        for word_data in data.chunks_exact(SINGLE_WORD_BYTELEN as usize) {
+                        let data = x.data.to_bytes();
+                        let data = &data[..bytelen];
+                        for word_data in data.chunks_exact(SINGLE_WORD_BYTELEN) {
                             data_packet.extend(target_device.serialize(query.cpu));
                             data_packet.extend(word_data);
-                            target_device.address += 1 as usize;
+                            target_device.address += 1;
                         }
                     },
                     _ => {
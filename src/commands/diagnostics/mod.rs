@@ -0,0 +1,149 @@
+/*
+The CPU exposes its self-diagnostic errors through special registers.
+
+Q/L:
+SD0: diagnostic error code
+SD1~SD3: occurrence time (BCD, year (lower 2 digits) | month, day | hour, minute | second)
+SD4~SD15: error information category and individual information
+SD50: error code to be reset by SM50
+
+R:
+SD0: latest self-diagnostic error code
+SD1~SD7: occurrence time (binary, year, month, day, hour, minute, second, day of week)
+SD10~SD25: codes of the errors currently occurring (up to 16)
+
+Errors are cleared at the rising edge of SM50.
+*/
+
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::commands::clock::bcd_to_u8;
+use crate::commands::device_access::{read::*, write::*};
+use crate::{CPU, DataType, Device, DeviceType, PLCDateTime, TypedData};
+
+const ERROR_INFO_DEVICE: Device = Device { device_type: DeviceType::SD, address: 0 };
+const ERROR_RESET_CODE_DEVICE: Device = Device { device_type: DeviceType::SD, address: 50 };
+const ERROR_RESET_REQUEST_DEVICE: Device = Device { device_type: DeviceType::SM, address: 50 };
+
+const QL_DETAIL_RANGE: core::ops::Range<usize> = 4..16;
+const R_ERROR_CODES_RANGE: core::ops::Range<usize> = 10..26;
+
+/// Self-diagnostic error reported by the CPU.
+/// `detail` holds the raw individual information registers (Q/L only).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PLCErrorRecord {
+    pub code: u16,
+    pub occurred_at: Option<PLCDateTime>,
+    pub detail: Vec<u16>,
+}
+
+#[inline(always)]
+const fn error_info_len(cpu: &CPU) -> usize {
+    match cpu {
+        CPU::Q | CPU::L | CPU::A => QL_DETAIL_RANGE.end,
+        CPU::R | CPU::F => R_ERROR_CODES_RANGE.end,
+    }
+}
+
+/// Day of week (0: Sunday) by Sakamoto's method.
+const fn day_of_week(year: u16, month: u8, day: u8) -> u8 {
+    const OFFSET: [u16; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    let y = if month < 3 { year - 1 } else { year };
+    ((y + y / 4 - y / 100 + y / 400 + OFFSET[(month as usize + 11) % 12] + day as u16) % 7) as u8
+}
+
+pub(crate) fn read_error_info(cpu: &CPU) -> Vec<u8> {
+    let query = SLMPBulkReadQuery {
+        cpu,
+        start_device: ERROR_INFO_DEVICE,
+        device_num: error_info_len(cpu),
+        data_type: DataType::U16,
+    };
+    SLMPBulkReadCommand::from(query).0
+}
+
+pub(crate) fn parse_error_info(cpu: &CPU, data: &[u8], max_entries: usize) -> crate::io::Result<Vec<PLCErrorRecord>> {
+    let words: Vec<u16> = data.chunks_exact(2).map(|x| u16::from_le_bytes([x[0], x[1]])).collect();
+    if words.len() < error_info_len(cpu) {
+        return Err(crate::io::Error::new(crate::io::ErrorKind::InvalidData, "Received Invalid Error Information"));
+    }
+
+    let latest_code: u16 = words[0];
+    let mut ret: Vec<PLCErrorRecord> = Vec::new();
+
+    match cpu {
+        CPU::Q | CPU::L | CPU::A => {
+            if latest_code != 0 {
+                let bytes: Vec<u8> = words[1..4].iter().flat_map(|x| x.to_be_bytes()).collect();
+                let occurred_at = bytes.iter()
+                    .map(|&x| bcd_to_u8(x))
+                    .collect::<Option<Vec<u8>>>()
+                    .map(|digits| {
+                        let year: u16 = if digits[0] < 80 { 2000 } else { 1900 } + digits[0] as u16;
+                        PLCDateTime {
+                            year,
+                            month: digits[1],
+                            day: digits[2],
+                            hour: digits[3],
+                            minute: digits[4],
+                            second: digits[5],
+                            day_of_week: day_of_week(year, digits[1], digits[2]),
+                        }
+                    });
+
+                ret.push(PLCErrorRecord {
+                    code: latest_code,
+                    occurred_at,
+                    detail: words[QL_DETAIL_RANGE].to_vec(),
+                });
+            }
+        },
+        CPU::R | CPU::F => {
+            if latest_code != 0 {
+                ret.push(PLCErrorRecord {
+                    code: latest_code,
+                    occurred_at: Some(PLCDateTime {
+                        year: words[1],
+                        month: words[2] as u8,
+                        day: words[3] as u8,
+                        hour: words[4] as u8,
+                        minute: words[5] as u8,
+                        second: words[6] as u8,
+                        day_of_week: words[7] as u8,
+                    }),
+                    detail: Vec::new(),
+                });
+            }
+
+            for &code in &words[R_ERROR_CODES_RANGE] {
+                if code != 0 && !ret.iter().any(|x| x.code == code) {
+                    ret.push(PLCErrorRecord { code, occurred_at: None, detail: Vec::new() });
+                }
+            }
+        }
+    }
+
+    ret.truncate(max_entries);
+
+    Ok(ret)
+}
+
+/// Q/L type CPU resets the error whose code is stored in SD50.
+pub(crate) fn write_error_reset_code(cpu: &CPU, code: u16) -> Vec<u8> {
+    let query = SLMPBulkWriteQuery {
+        cpu,
+        start_device: ERROR_RESET_CODE_DEVICE,
+        data: &[TypedData::U16(code)],
+    };
+    SLMPBulkWriteCommand::from(query).0
+}
+
+pub(crate) fn error_reset_request(cpu: &CPU, value: bool) -> Vec<u8> {
+    let query = SLMPBulkWriteQuery {
+        cpu,
+        start_device: ERROR_RESET_REQUEST_DEVICE,
+        data: &[TypedData::Bool(value)],
+    };
+    SLMPBulkWriteCommand::from(query).0
+}
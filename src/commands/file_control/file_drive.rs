@@ -1,3 +1,7 @@
+use serde::{Deserialize, Serialize};
+use crate::CPU;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FileDriveForR {
     Device,
     SDMemory,
@@ -5,7 +9,7 @@ pub enum FileDriveForR {
 }
 
 impl FileDriveForR {
-    pub(crate) const fn to_drive_code(&self) -> [u8; 2] {
+    pub(crate) const fn to_drive_code(self) -> [u8; 2] {
         match self {
             Self::DataMemory => 0x0001u16,
             Self::SDMemory => 0x0002u16,
@@ -14,6 +18,7 @@ impl FileDriveForR {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FileDriveForQL {
     ProgramMemory,
     SRAMCard,
@@ -23,24 +28,40 @@ pub enum FileDriveForQL {
 }
 
 impl FileDriveForQL {
-    pub(crate) const fn to_drive_code(&self) -> [u8; 2] {
+    pub(crate) const fn to_drive_code(self) -> [u8; 2] {
         match self {
             Self::ProgramMemory => 0x0000u16,
             Self::SRAMCard => 0x0001u16,
             Self::SDMemory => 0x0002u16,
             Self::DefaultRAM => 0x0003u16,
-            Self::DefaultROM => 0x0003u16,
+            Self::DefaultROM => 0x0004u16,
         }.to_le_bytes()
     }
 }
 
+/// Serialized as `{"R": "SDMemory"}` or `{"QL": "ProgramMemory"}`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FileDrive {
     R(FileDriveForR),
     QL(FileDriveForQL)
 }
 
 impl FileDrive {
-    pub(crate) const fn to_drive_code(&self) -> [u8; 2] {
+    /// The drive of `cpu` numbered `drive_no`, e.g. 2 for the SD memory card.
+    /// `None` for a number the CPU does not have, and for iQ-F and MELSEC-A CPUs, which have no file access.
+    pub fn from_drive_no(cpu: &CPU, drive_no: u16) -> Option<Self> {
+        let drives: &[Self] = match cpu {
+            CPU::R => &[Self::R(FileDriveForR::DataMemory), Self::R(FileDriveForR::SDMemory), Self::R(FileDriveForR::Device)],
+            CPU::Q | CPU::L => &[
+                Self::QL(FileDriveForQL::ProgramMemory), Self::QL(FileDriveForQL::SRAMCard), Self::QL(FileDriveForQL::SDMemory),
+                Self::QL(FileDriveForQL::DefaultRAM), Self::QL(FileDriveForQL::DefaultROM),
+            ],
+            CPU::F | CPU::A => &[],
+        };
+        drives.iter().copied().find(|x| x.to_drive_code() == drive_no.to_le_bytes())
+    }
+
+    pub(crate) const fn to_drive_code(self) -> [u8; 2] {
         match self {
             Self::R(drive) => drive.to_drive_code(),
             Self::QL(drive) => drive.to_drive_code()
@@ -48,13 +69,16 @@ impl FileDrive {
     }
 }
 
+#[allow(dead_code, clippy::upper_case_acronyms)]
 pub enum FileExtension { DAT, PRG, QPG, PFB, QCD, DCM, QDI, DID }
 
+#[allow(dead_code)]
 pub enum FileAttribute {
     ReadOnly(bool),
     ReadWrite(bool),
 }
 
+#[allow(dead_code)]
 impl FileAttribute {
     pub(crate) const fn to_attribute_code(&self) -> [u8; 2] {
         match self {
@@ -66,11 +90,13 @@ impl FileAttribute {
     }
 }
 
+#[allow(dead_code)]
 pub enum FolderAttribute {
     ReadOnly(bool),
     ReadWrite(bool),
 }
 
+#[allow(dead_code)]
 impl FolderAttribute {
     pub(crate) const fn to_attribute_code(&self) -> [u8; 2] {
         match self {
@@ -82,10 +108,11 @@ impl FolderAttribute {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum FileOpenMode {Read, Write}
 
 impl FileOpenMode {
-    pub(crate) const fn to_mode_code(&self) -> [u8; 2] {
+    pub(crate) const fn to_mode_code(self) -> [u8; 2] {
         match self {
             Self::Read => [0x00, 0x00],
             Self::Write => [0x00, 0x01],
@@ -1,102 +1,411 @@
 mod file_drive;
+pub use file_drive::{FileDrive, FileDriveForQL, FileDriveForR, FileOpenMode};
+
+use crate::prelude::*;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::SystemTime;
+use encoding_rs::SHIFT_JIS;
+use serde::{Deserialize, Serialize};
+use crate::CPU;
+
+/// Maximum number of bytes transferred by a single read_file/write_file request.
+pub(crate) const FILE_ACCESS_MAX_BYTELEN: usize = 1920;
+
+/// Maximum number of file information entries returned by a single request.
+pub(crate) const FILE_INFO_MAX_POINTS: u16 = 36;
+
+const FILE_NAME_MAX_LEN: usize = 252;
+const QL_PASSWORD_LEN: usize = 4;
+
+#[inline(always)]
+const fn file_subcommand(cpu: &CPU) -> [u8; 2] {
+    match cpu {
+        CPU::Q | CPU::A => [0x00, 0x00],
+        CPU::L => [0x04, 0x00],
+        CPU::R | CPU::F => [0x40, 0x00],
+    }
+}
 
-use crate::{CPU, SLMP4EConnectionProps, commands::COMMAND_PREFIX_BYTELEN};
-use file_drive::FileDriveForQL;
+fn validate_drive(cpu: &CPU, drive: &FileDrive) -> crate::io::Result<()> {
+    match (cpu, drive) {
+        (CPU::F, _) => Err(crate::io::Error::new(crate::io::ErrorKind::Unsupported, "F type CPU (iQ-F) does not support file access by SLMP")),
+        (CPU::A, _) => Err(crate::io::Error::new(crate::io::ErrorKind::Unsupported, "A type CPU (1E frame) does not support file access")),
+        (CPU::R, FileDrive::R(_)) | (CPU::Q | CPU::L, FileDrive::QL(_)) => Ok(()),
+        (CPU::R, FileDrive::QL(_)) => Err(crate::io::Error::new(crate::io::ErrorKind::InvalidInput, "R type CPU requires FileDrive::R")),
+        (CPU::Q | CPU::L, FileDrive::R(_)) => Err(crate::io::Error::new(crate::io::ErrorKind::InvalidInput, "Q/L type CPU requires FileDrive::QL")),
+    }
+}
 
-pub(crate) const fn read_file_and_folder_props_for_ql(
-    connection_props: &SLMP4EConnectionProps,
-    drive: FileDriveForQL,
-    start_file_no: u16,
-    request_file_len: u16,
-    request_folder_len: u16
-) -> [u8; 31] {
+/// File password field. No password is expressed as blank for Q/L and zero-length for R.
+fn encode_no_password(cpu: &CPU) -> Vec<u8> {
+    match cpu {
+        CPU::Q | CPU::L | CPU::A => vec![0x20; QL_PASSWORD_LEN],
+        CPU::R | CPU::F => vec![0x00, 0x00],
+    }
+}
 
-    const DATA_BYTELEN: u16 = 12;
-    const COMMAND_LEN: u16 = COMMAND_PREFIX_BYTELEN as u16 + DATA_BYTELEN;
+/// File name field: name length (2 bytes) + name.
+/// R type CPU requires UTF-16LE, Q/L type CPU requires Shift-JIS.
+fn encode_file_name(cpu: &CPU, file_name: &str) -> crate::io::Result<Vec<u8>> {
+    let (name_len, name): (usize, Vec<u8>) = match cpu {
+        CPU::Q | CPU::L | CPU::A => {
+            let (shift_jis_bytes, _, had_errors) = SHIFT_JIS.encode(file_name);
+            if had_errors {
+                return Err(crate::io::Error::new(crate::io::ErrorKind::InvalidInput, "File name contains characters not representable in Shift-JIS"));
+            }
+            (shift_jis_bytes.len(), shift_jis_bytes.into_owned())
+        },
+        CPU::R | CPU::F => {
+            let utf16: Vec<u16> = file_name.encode_utf16().collect();
+            (utf16.len(), utf16.iter().flat_map(|x| x.to_le_bytes()).collect())
+        }
+    };
 
-    const COMMAND: [u8; 2] = 0x1810u16.to_le_bytes();
-    let subcommand: [u8; 2] = match connection_props.cpu {
-        CPU::Q | CPU::L => [0x00, 0x00],
-        CPU::R => [0x40, 0x00],
+    if name_len == 0 || name_len > FILE_NAME_MAX_LEN {
+        return Err(crate::io::Error::new(crate::io::ErrorKind::InvalidInput, format!("File name length must be between 1 and {FILE_NAME_MAX_LEN}")));
+    }
+
+    let mut ret: Vec<u8> = Vec::with_capacity(2 + name.len());
+    ret.extend((name_len as u16).to_le_bytes());
+    ret.extend(name);
+
+    Ok(ret)
+}
+
+pub(crate) fn create_file(cpu: &CPU, drive: &FileDrive, file_name: &str, file_size: u32) -> crate::io::Result<Vec<u8>> {
+    const COMMAND: [u8; 2] = 0x1820u16.to_le_bytes();
+    let subcommand: [u8; 2] = match cpu {
+        CPU::Q | CPU::L | CPU::A => [0x00, 0x00],
+        CPU::R | CPU::F => [0x40, 0x00],
     };
 
-    const CONSTANT: [u8; 4] = [0x30, 0x30, 0x30, 0x30];
+    validate_drive(cpu, drive)?;
 
-    let drive: [u8; 2] = drive.to_drive_code();
-    let start_file: [u8; 2] = start_file_no.to_le_bytes();
-    let request_file_len: [u8; 2] = request_file_len.to_le_bytes();
-    let request_folder_len: [u8; 2] = request_folder_len.to_le_bytes();
+    let password: Vec<u8> = encode_no_password(cpu);
+    let file_name: Vec<u8> = encode_file_name(cpu, file_name)?;
 
-    let header: [u8; 15] = connection_props.generate_header(COMMAND_LEN);
+    let mut packet: Vec<u8> = Vec::with_capacity(10 + password.len() + file_name.len());
+    packet.extend_from_slice(&COMMAND);
+    packet.extend_from_slice(&subcommand);
+    packet.extend_from_slice(&password);
+    packet.extend_from_slice(&drive.to_drive_code());
+    packet.extend_from_slice(&file_size.to_le_bytes());
+    packet.extend_from_slice(&file_name);
+
+    Ok(packet)
+}
+
+pub(crate) fn delete_file(cpu: &CPU, drive: &FileDrive, file_name: &str) -> crate::io::Result<Vec<u8>> {
+    const COMMAND: [u8; 2] = 0x1822u16.to_le_bytes();
+    let subcommand: [u8; 2] = file_subcommand(cpu);
+
+    validate_drive(cpu, drive)?;
+
+    let password: Vec<u8> = encode_no_password(cpu);
+    let file_name: Vec<u8> = encode_file_name(cpu, file_name)?;
+
+    let mut packet: Vec<u8> = Vec::with_capacity(6 + password.len() + file_name.len());
+    packet.extend_from_slice(&COMMAND);
+    packet.extend_from_slice(&subcommand);
+    packet.extend_from_slice(&password);
+    packet.extend_from_slice(&drive.to_drive_code());
+    packet.extend_from_slice(&file_name);
+
+    Ok(packet)
+}
+
+pub(crate) fn copy_file(cpu: &CPU, src_drive: &FileDrive, src_file_name: &str, dst_drive: &FileDrive, dst_file_name: &str) -> crate::io::Result<Vec<u8>> {
+    const COMMAND: [u8; 2] = 0x1824u16.to_le_bytes();
+    let subcommand: [u8; 2] = file_subcommand(cpu);
+
+    validate_drive(cpu, src_drive)?;
+    validate_drive(cpu, dst_drive)?;
+
+    let password: Vec<u8> = encode_no_password(cpu);
+    let src_file_name: Vec<u8> = encode_file_name(cpu, src_file_name)?;
+    let dst_file_name: Vec<u8> = encode_file_name(cpu, dst_file_name)?;
+
+    let mut packet: Vec<u8> = Vec::with_capacity(8 + 2 * password.len() + src_file_name.len() + dst_file_name.len());
+    packet.extend_from_slice(&COMMAND);
+    packet.extend_from_slice(&subcommand);
+
+    // Copy destination comes first, then copy source.
+    packet.extend_from_slice(&password);
+    packet.extend_from_slice(&dst_drive.to_drive_code());
+    packet.extend_from_slice(&dst_file_name);
+    packet.extend_from_slice(&password);
+    packet.extend_from_slice(&src_drive.to_drive_code());
+    packet.extend_from_slice(&src_file_name);
+
+    Ok(packet)
+}
+
+pub(crate) fn open_file(cpu: &CPU, drive: &FileDrive, file_name: &str, mode: FileOpenMode) -> crate::io::Result<Vec<u8>> {
+    const COMMAND: [u8; 2] = 0x1827u16.to_le_bytes();
+    let subcommand: [u8; 2] = file_subcommand(cpu);
+
+    validate_drive(cpu, drive)?;
+
+    let password: Vec<u8> = encode_no_password(cpu);
+    let file_name: Vec<u8> = encode_file_name(cpu, file_name)?;
+
+    let mut packet: Vec<u8> = Vec::with_capacity(8 + password.len() + file_name.len());
+    packet.extend_from_slice(&COMMAND);
+    packet.extend_from_slice(&subcommand);
+    packet.extend_from_slice(&password);
+    packet.extend_from_slice(&mode.to_mode_code());
+    packet.extend_from_slice(&drive.to_drive_code());
+    packet.extend_from_slice(&file_name);
+
+    Ok(packet)
+}
+
+pub(crate) const fn read_file(file_pointer: u16, offset: u32, bytelen: u16) -> [u8; 12] {
+    const COMMAND: [u8; 2] = 0x1828u16.to_le_bytes();
+    const SUBCOMMAND: [u8; 2] = [0x00, 0x00];
+
+    let file_pointer: [u8; 2] = file_pointer.to_le_bytes();
+    let offset: [u8; 4] = offset.to_le_bytes();
+    let bytelen: [u8; 2] = bytelen.to_le_bytes();
 
     [
-        header[0], header[1], header[2], header[3],
-        header[4], header[5], header[6], header[7],
-        header[8], header[9], header[10], header[11],
-        header[12], header[13], header[14],
         COMMAND[0], COMMAND[1],
-        subcommand[0], subcommand[1],
-        CONSTANT[0], CONSTANT[1], CONSTANT[2], CONSTANT[3],
-        drive[0], drive[1],
-        start_file[0], start_file[1],
-        request_file_len[0], request_file_len[1],
-        request_folder_len[0], request_folder_len[1]
+        SUBCOMMAND[0], SUBCOMMAND[1],
+        file_pointer[0], file_pointer[1],
+        offset[0], offset[1], offset[2], offset[3],
+        bytelen[0], bytelen[1],
     ]
 }
 
+pub(crate) fn write_file(file_pointer: u16, offset: u32, data: &[u8]) -> Vec<u8> {
+    const COMMAND: [u8; 2] = 0x1829u16.to_le_bytes();
+    const SUBCOMMAND: [u8; 2] = [0x00, 0x00];
 
-// pub(crate) fn search_file(connection_props: &SLMP4EConnectionProps) -> Vec<u8> {
+    let bytelen: [u8; 2] = (data.len() as u16).to_le_bytes();
 
-//     const COMMAND: [u8; 2] = 0x1811u16.to_le_bytes();
-//     let subcommand: [u8; 2] = match connection_props.cpu {
-//         CPU::Q | CPU::L => [0x00, 0x00],
-//         CPU::R => [0x40, 0x00],
-//     };
+    let mut packet: Vec<u8> = Vec::with_capacity(12 + data.len());
+    packet.extend_from_slice(&COMMAND);
+    packet.extend_from_slice(&SUBCOMMAND);
+    packet.extend_from_slice(&file_pointer.to_le_bytes());
+    packet.extend_from_slice(&offset.to_le_bytes());
+    packet.extend_from_slice(&bytelen);
+    packet.extend_from_slice(data);
 
-//     vec![
-//         COMMAND[0], COMMAND[1],
-//         subcommand[0], subcommand[1]
-//     ]
-// }
+    packet
+}
 
+pub(crate) const fn close_file(file_pointer: u16) -> [u8; 8] {
+    const COMMAND: [u8; 2] = 0x182Au16.to_le_bytes();
+    const SUBCOMMAND: [u8; 2] = [0x00, 0x00];
+    const CLOSE_TYPE: [u8; 2] = [0x00, 0x00];
 
-// pub(crate) fn create_file(connection_props: &SLMP4EConnectionProps) -> Vec<u8> {
+    let file_pointer: [u8; 2] = file_pointer.to_le_bytes();
 
-//     const COMMAND: [u8; 2] = 0x1820u16.to_le_bytes();
-//     let subcommand: [u8; 2] = match connection_props.cpu {
-//         CPU::Q | CPU::L => [0x00, 0x00],
-//         CPU::R => [0x40, 0x00],
-//     };
+    [
+        COMMAND[0], COMMAND[1],
+        SUBCOMMAND[0], SUBCOMMAND[1],
+        file_pointer[0], file_pointer[1],
+        CLOSE_TYPE[0], CLOSE_TYPE[1],
+    ]
+}
 
-//     vec![
-//         COMMAND[0], COMMAND[1],
-//         subcommand[0], subcommand[1]
-//     ]
-// }
 
+/// File pointer in the response of `open_file`.
+pub(crate) fn parse_file_pointer(recv: &[u8]) -> crate::io::Result<u16> {
+    match recv {
+        [a, b, ..] => Ok(u16::from_le_bytes([*a, *b])),
+        _ => Err(crate::io::Error::new(crate::io::ErrorKind::InvalidData, "Received Invalid File Pointer")),
+    }
+}
 
-// pub(crate) fn delete_file(connection_props: &SLMP4EConnectionProps) -> Vec<u8> {
+/// Data in the response of `read_file`, after its length.
+pub(crate) fn parse_read_file(recv: &[u8]) -> crate::io::Result<&[u8]> {
+    const BYTELEN_SPECIFIER_LEN: usize = 2;
 
-//     const COMMAND: [u8; 2] = 0x1822u16.to_le_bytes();
-//     let subcommand: [u8; 2] = match connection_props.cpu {
-//         CPU::Q => [0x00, 0x00],
-//         CPU::L => [0x04, 0x00],
-//         CPU::R => [0x40, 0x00],
-//     };
+    let invalid = || crate::io::Error::new(crate::io::ErrorKind::InvalidData, "Received Invalid File Data");
+    let read_len: usize = match recv {
+        [a, b, ..] => u16::from_le_bytes([*a, *b]) as usize,
+        _ => return Err(invalid()),
+    };
+    recv.get(BYTELEN_SPECIFIER_LEN..(BYTELEN_SPECIFIER_LEN + read_len)).ok_or_else(invalid)
+}
 
-//     vec![
-//         COMMAND[0], COMMAND[1],
-//         subcommand[0], subcommand[1]
-//     ]
-// }
+/// Bytes written, in the response of `write_file`.
+pub(crate) fn parse_written_len(recv: &[u8]) -> crate::io::Result<usize> {
+    match recv {
+        [a, b, ..] => Ok(u16::from_le_bytes([*a, *b]) as usize),
+        _ => Err(crate::io::Error::new(crate::io::ErrorKind::InvalidData, "Received Invalid Written Length")),
+    }
+}
+
+pub(crate) fn read_directory(cpu: &CPU, drive: &FileDrive, start_file_no: u32, request_len: u16) -> crate::io::Result<Vec<u8>> {
+    const COMMAND: [u8; 2] = 0x1810u16.to_le_bytes();
+    const ROOT_DIRECTORY_LEN: [u8; 2] = [0x00, 0x00];
+
+    validate_drive(cpu, drive)?;
+
+    let mut packet: Vec<u8> = Vec::with_capacity(16);
+    packet.extend_from_slice(&COMMAND);
+
+    match cpu {
+        CPU::Q | CPU::L | CPU::A => {
+            const SUBCOMMAND: [u8; 2] = [0x00, 0x00];
+            const CONSTANT: [u8; 4] = [0x30, 0x30, 0x30, 0x30];
+
+            let start_file_no: u16 = u16::try_from(start_file_no)
+                .map_err(|_| crate::io::Error::new(crate::io::ErrorKind::InvalidInput, "Q/L type CPU requires file No. within u16"))?;
+
+            packet.extend_from_slice(&SUBCOMMAND);
+            packet.extend_from_slice(&CONSTANT);
+            packet.extend_from_slice(&drive.to_drive_code());
+            packet.extend_from_slice(&start_file_no.to_le_bytes());
+            packet.extend_from_slice(&request_len.to_le_bytes());
+            packet.extend_from_slice(&ROOT_DIRECTORY_LEN);
+        },
+        CPU::R | CPU::F => {
+            const SUBCOMMAND: [u8; 2] = [0x40, 0x00];
+
+            packet.extend_from_slice(&SUBCOMMAND);
+            packet.extend_from_slice(&drive.to_drive_code());
+            packet.extend_from_slice(&start_file_no.to_le_bytes());
+            packet.extend_from_slice(&request_len.to_le_bytes());
+            packet.extend_from_slice(&ROOT_DIRECTORY_LEN);
+        }
+    }
+
+    Ok(packet)
+}
+
+/// Properties of a file or a folder stored in the CPU.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileInfo {
+    pub name: String,
+    pub size: u32,
+    #[cfg(feature = "std")]
+    pub modified: SystemTime,
+    /// Time since the Unix epoch (no `SystemTime` without std).
+    #[cfg(not(feature = "std"))]
+    pub modified: Duration,
+    pub attribute: u16,
+}
 
+impl FileInfo {
+    const ATTRIBUTE_READ_ONLY: u16 = 0x01;
+    const ATTRIBUTE_DIRECTORY: u16 = 0x10;
 
-// pub(crate) fn copy_file(connection_props: &SLMP4EConnectionProps) -> Vec<u8> {
+    pub const fn is_read_only(&self) -> bool {
+        self.attribute & Self::ATTRIBUTE_READ_ONLY != 0
+    }
 
-//     const COMMAND: [u8; 2] = 0x1824u16.to_le_bytes();
+    pub const fn is_directory(&self) -> bool {
+        self.attribute & Self::ATTRIBUTE_DIRECTORY != 0
+    }
+}
+
+/// Parse the response of read_directory.
+///
+/// Q/L type CPU returns fixed 32-byte entries (Shift-JIS 8.3 name, 1-byte attribute),
+/// R type CPU returns variable-length entries (UTF-16LE name with length prefix, 2-byte attribute).
+pub(crate) fn parse_directory(cpu: &CPU, data: &[u8]) -> crate::io::Result<Vec<FileInfo>> {
+    let invalid = || crate::io::Error::new(crate::io::ErrorKind::InvalidData, "Received Invalid File Information");
+    let read_u16 = |pos: usize| -> crate::io::Result<u16> {
+        data.get(pos..pos + 2).map(|x| u16::from_le_bytes([x[0], x[1]])).ok_or_else(invalid)
+    };
+    let read_u32 = |pos: usize| -> crate::io::Result<u32> {
+        data.get(pos..pos + 4).map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]])).ok_or_else(invalid)
+    };
+
+    let entry_num: usize = read_u16(0)? as usize;
+    let mut ret: Vec<FileInfo> = Vec::with_capacity(entry_num);
+    let mut pos: usize = 2;
+
+    for _ in 0..entry_num {
+        match cpu {
+            CPU::Q | CPU::L | CPU::A => {
+                const ENTRY_BYTELEN: usize = 32;
+                let entry = data.get(pos..pos + ENTRY_BYTELEN).ok_or_else(invalid)?;
+
+                let trim = |x: &[u8]| {
+                    let len = x.iter().rposition(|&b| b != 0x20 && b != 0x00).map_or(0, |p| p + 1);
+                    SHIFT_JIS.decode(&x[..len]).0.into_owned()
+                };
+                let base_name = trim(&entry[0..8]);
+                let extension = trim(&entry[8..11]);
+                let name = if extension.is_empty() { base_name } else { format!("{base_name}.{extension}") };
+
+                ret.push(FileInfo {
+                    name,
+                    attribute: entry[11] as u16,
+                    modified: fat_timestamp(read_u16(pos + 24)?, read_u16(pos + 22)?),
+                    size: read_u32(pos + 28)?,
+                });
+                pos += ENTRY_BYTELEN;
+            },
+            CPU::R | CPU::F => {
+                let name_len: usize = read_u16(pos)? as usize;
+                pos += 2;
+
+                let name: Vec<u16> = data.get(pos..pos + 2 * name_len).ok_or_else(invalid)?
+                    .chunks_exact(2)
+                    .map(|x| u16::from_le_bytes([x[0], x[1]]))
+                    .collect();
+                pos += 2 * name_len;
+
+                ret.push(FileInfo {
+                    name: String::from_utf16_lossy(&name),
+                    attribute: read_u16(pos)?,
+                    modified: fat_timestamp(read_u16(pos + 4)?, read_u16(pos + 2)?),
+                    size: read_u32(pos + 6)?,
+                });
+                pos += 10;
+            }
+        }
+    }
+
+    Ok(ret)
+}
+
+/// Convert a FAT-style date/time pair into SystemTime.
+#[cfg(feature = "std")]
+fn fat_timestamp(date: u16, time: u16) -> SystemTime {
+    SystemTime::UNIX_EPOCH + fat_since_epoch(date, time)
+}
+
+#[cfg(not(feature = "std"))]
+fn fat_timestamp(date: u16, time: u16) -> Duration {
+    fat_since_epoch(date, time)
+}
+
+/// Time since the Unix epoch of a FAT-style date/time pair.
+/// date: (year - 1980) << 9 | month << 5 | day, time: hour << 11 | minute << 5 | second / 2
+fn fat_since_epoch(date: u16, time: u16) -> Duration {
+    let year: i64 = 1980 + (date >> 9) as i64;
+    let month: i64 = ((date >> 5) & 0x0F).clamp(1, 12) as i64;
+    let day: i64 = (date & 0x1F).max(1) as i64;
+
+    let hour: u64 = (time >> 11) as u64;
+    let minute: u64 = ((time >> 5) & 0x3F) as u64;
+    let second: u64 = ((time & 0x1F) * 2) as u64;
+
+    // Days from 1970-01-01 (civil calendar)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = (era * 146097 + doe - 719468) as u64;
+
+    Duration::from_secs(days * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+
+// pub(crate) fn search_file(connection_props: &SLMP4EConnectionProps) -> Vec<u8> {
+
+//     const COMMAND: [u8; 2] = 0x1811u16.to_le_bytes();
 //     let subcommand: [u8; 2] = match connection_props.cpu {
-//         CPU::Q => [0x00, 0x00],
-//         CPU::L => [0x04, 0x00],
+//         CPU::Q | CPU::L | CPU::A => [0x00, 0x00],
 //         CPU::R => [0x40, 0x00],
 //     };
 
@@ -137,54 +446,3 @@ pub(crate) const fn read_file_and_folder_props_for_ql(
 //     ]
 // }
 
-
-// pub(crate) fn open_file(connection_props: &SLMP4EConnectionProps) -> Vec<u8> {
-
-//     const COMMAND: [u8; 2] = 0x1827u16.to_le_bytes();
-//     let subcommand: [u8; 2] = match connection_props.cpu {
-//         CPU::Q => [0x00, 0x00],
-//         CPU::L => [0x04, 0x00],
-//         CPU::R => [0x40, 0x00],
-//     };
-
-//     vec![
-//         COMMAND[0], COMMAND[1],
-//         subcommand[0], subcommand[1]
-//     ]
-// }
-
-
-// pub(crate) fn read_file(connection_props: &SLMP4EConnectionProps) -> Vec<u8> {
-
-//     const COMMAND: [u8; 2] = 0x1828u16.to_le_bytes();
-//     const SUBCOMMAND: [u8; 2] = [0x00, 0x00];
-
-//     vec![
-//         COMMAND[0], COMMAND[1],
-//         SUBCOMMAND[0], SUBCOMMAND[1]
-//     ]
-// }
-
-
-// pub(crate) fn write_file(connection_props: &SLMP4EConnectionProps) -> Vec<u8> {
-
-//     const COMMAND: [u8; 2] = 0x1829u16.to_le_bytes();
-//     const SUBCOMMAND: [u8; 2] = [0x00, 0x00];
-
-//     vec![
-//         COMMAND[0], COMMAND[1],
-//         SUBCOMMAND[0], SUBCOMMAND[1]
-//     ]
-// }
-
-
-// pub(crate) fn close_file(connection_props: &SLMP4EConnectionProps) -> Vec<u8> {
-
-//     const COMMAND: [u8; 2] = 0x182Au16.to_le_bytes();
-//     const SUBCOMMAND: [u8; 2] = [0x00, 0x00];
-
-//     vec![
-//         COMMAND[0], COMMAND[1],
-//         SUBCOMMAND[0], SUBCOMMAND[1]
-//     ]
-// }
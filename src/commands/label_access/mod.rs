@@ -0,0 +1,178 @@
+/*
+Label names are UTF-16LE strings with a length prefix (number of characters).
+Array elements and structure members can be specified in the name itself (e.g. "Recipe[3].Speed").
+
+Random label read (0x041C):
+Request: Label points (2) + Abbreviation points (2) + [Name length (2) + Name] * points
+Response: Label points (2) + [Data type ID (1) + Spare (1) + Data length (2) + Data] * points
+
+Array label write (0x141A):
+Request: Label points (2) + Abbreviation points (2) + [Name length (2) + Name + Unit (1) + Fixed (1) + Data length (2) + Data] * points
+*/
+
+use crate::prelude::*;
+use crate::{DataType, TypedData};
+
+const COMMAND_RANDOM_LABEL_READ: u16 = 0x041C;
+const COMMAND_ARRAY_LABEL_WRITE: u16 = 0x141A;
+
+const LABEL_NAME_MAX_LEN: usize = 64;
+
+const UNIT_BIT: u8 = 0x00;
+const UNIT_BYTE: u8 = 0x01;
+
+fn encode_label_name(label: &str) -> crate::io::Result<Vec<u8>> {
+    let utf16: Vec<u16> = label.encode_utf16().collect();
+    if utf16.is_empty() || utf16.len() > LABEL_NAME_MAX_LEN {
+        return Err(crate::io::Error::new(
+            crate::io::ErrorKind::InvalidInput,
+            format!("Label name length must be between 1 and {LABEL_NAME_MAX_LEN}: {label}")
+        ));
+    }
+
+    let mut ret: Vec<u8> = Vec::with_capacity(2 + 2 * utf16.len());
+    ret.extend((utf16.len() as u16).to_le_bytes());
+    ret.extend(utf16.iter().flat_map(|x| x.to_le_bytes()));
+
+    Ok(ret)
+}
+
+// Data type IDs of the labels in the response of random label read.
+pub(crate) const TYPE_ID_BIT: u8 = 0x01;
+/// Unsigned word and 16-bit bit string.
+pub(crate) const TYPE_ID_WORD: u8 = 0x02;
+/// Unsigned double word and 32-bit bit string.
+pub(crate) const TYPE_ID_DOUBLE_WORD: u8 = 0x03;
+pub(crate) const TYPE_ID_FLOAT: u8 = 0x04;
+pub(crate) const TYPE_ID_DOUBLE: u8 = 0x05;
+pub(crate) const TYPE_ID_STRING: u8 = 0x06;
+pub(crate) const TYPE_ID_WSTRING: u8 = 0x07;
+pub(crate) const TYPE_ID_SIGNED_WORD: u8 = 0x08;
+pub(crate) const TYPE_ID_SIGNED_DOUBLE_WORD: u8 = 0x09;
+/// Timers, counters, retentive timers and their long variants, which are structures.
+const TYPE_IDS_TIMER_COUNTER: core::ops::RangeInclusive<u8> = 0x0A..=0x0F;
+
+/// Map a label data type ID and the data length in the response to DataType.
+/// A string takes the words of its data length; a length its type cannot have fails with `InvalidData`.
+fn data_type_from_id(id: u8, bytelen: usize) -> crate::io::Result<DataType> {
+    let invalid_len = || crate::io::Error::new(
+        crate::io::ErrorKind::InvalidData,
+        format!("Invalid data length {bytelen} for label data type ID 0x{id:02X}")
+    );
+    let string_words = |words: usize| u8::try_from(words).ok().filter(|&n| n > 0).ok_or_else(invalid_len);
+
+    let data_type = match id {
+        TYPE_ID_BIT => DataType::Bool,
+        TYPE_ID_WORD => DataType::U16,
+        TYPE_ID_DOUBLE_WORD => DataType::U32,
+        TYPE_ID_FLOAT => DataType::F32,
+        TYPE_ID_DOUBLE => DataType::F64,
+        TYPE_ID_SIGNED_WORD => DataType::I16,
+        TYPE_ID_SIGNED_DOUBLE_WORD => DataType::I32,
+        TYPE_ID_STRING => DataType::String(string_words(bytelen.div_ceil(2))?),
+        TYPE_ID_WSTRING if bytelen.is_multiple_of(2) => DataType::WString(string_words(bytelen / 2)?),
+        TYPE_ID_WSTRING => return Err(invalid_len()),
+        x if TYPE_IDS_TIMER_COUNTER.contains(&x) => return Err(crate::io::Error::new(
+            crate::io::ErrorKind::Unsupported,
+            format!("Unsupported label data type ID: 0x{id:02X} (timer or counter)")
+        )),
+        _ => return Err(crate::io::Error::new(crate::io::ErrorKind::InvalidData, format!("Unknown label data type ID: 0x{id:02X}"))),
+    };
+
+    let fits: bool = match data_type {
+        DataType::Bool => matches!(bytelen, 1 | 2),
+        DataType::String(_) | DataType::WString(_) => true,
+        _ => bytelen == data_type.byte_size(),
+    };
+    if !fits {
+        return Err(invalid_len());
+    }
+    Ok(data_type)
+}
+
+pub(crate) fn read_labels(labels: &[&str]) -> crate::io::Result<Vec<u8>> {
+    const COMMAND: [u8; 2] = COMMAND_RANDOM_LABEL_READ.to_le_bytes();
+    const SUBCOMMAND: [u8; 2] = [0x00, 0x00];
+    const ABBREVIATION_POINTS: [u8; 2] = [0x00, 0x00];
+
+    let label_points: [u8; 2] = u16::try_from(labels.len())
+        .map_err(|_| crate::io::Error::new(crate::io::ErrorKind::InvalidInput, "Too many labels"))?
+        .to_le_bytes();
+
+    let mut packet: Vec<u8> = Vec::new();
+    packet.extend_from_slice(&COMMAND);
+    packet.extend_from_slice(&SUBCOMMAND);
+    packet.extend_from_slice(&label_points);
+    packet.extend_from_slice(&ABBREVIATION_POINTS);
+    for label in labels {
+        packet.extend(encode_label_name(label)?);
+    }
+
+    Ok(packet)
+}
+
+/// Each label is decoded independently so that an unsupported data type fails only that label.
+pub(crate) fn parse_labels(data: &[u8]) -> crate::io::Result<Vec<crate::io::Result<TypedData>>> {
+    let invalid = || crate::io::Error::new(crate::io::ErrorKind::InvalidData, "Received Invalid Label Data");
+
+    let points: usize = data.get(0..2).map(|x| u16::from_le_bytes([x[0], x[1]])).ok_or_else(invalid)? as usize;
+    let mut ret: Vec<crate::io::Result<TypedData>> = Vec::with_capacity(points);
+    let mut pos: usize = 2;
+
+    for _ in 0..points {
+        let header = data.get(pos..pos + 4).ok_or_else(invalid)?;
+        let type_id: u8 = header[0];
+        let bytelen: usize = u16::from_le_bytes([header[2], header[3]]) as usize;
+        pos += 4;
+
+        let value = data.get(pos..pos + bytelen).ok_or_else(invalid)?;
+        pos += bytelen;
+
+        // A bit and a string of an odd length are padded to words.
+        let typed = data_type_from_id(type_id, bytelen).and_then(|data_type| {
+            let mut bytes: Vec<u8> = value.to_vec();
+            bytes.resize(data_type.byte_size().max(2), 0x00);
+            TypedData::decode(&bytes, data_type)
+        });
+        ret.push(typed);
+    }
+
+    Ok(ret)
+}
+
+pub(crate) fn write_labels(labels: &[(&str, TypedData)]) -> crate::io::Result<Vec<u8>> {
+    const COMMAND: [u8; 2] = COMMAND_ARRAY_LABEL_WRITE.to_le_bytes();
+    const SUBCOMMAND: [u8; 2] = [0x00, 0x00];
+    const ABBREVIATION_POINTS: [u8; 2] = [0x00, 0x00];
+    const FIXED_VALUE: u8 = 0x00;
+
+    let label_points: [u8; 2] = u16::try_from(labels.len())
+        .map_err(|_| crate::io::Error::new(crate::io::ErrorKind::InvalidInput, "Too many labels"))?
+        .to_le_bytes();
+
+    let mut packet: Vec<u8> = Vec::new();
+    packet.extend_from_slice(&COMMAND);
+    packet.extend_from_slice(&SUBCOMMAND);
+    packet.extend_from_slice(&label_points);
+    packet.extend_from_slice(&ABBREVIATION_POINTS);
+
+    for (label, data) in labels {
+        packet.extend(encode_label_name(label)?);
+
+        let bytes: Vec<u8> = data.to_bytes();
+        match data {
+            // Bit unit: the data length is given in bits, the data is packed in words.
+            TypedData::Bool(_) => {
+                packet.extend([UNIT_BIT, FIXED_VALUE]);
+                packet.extend(1u16.to_le_bytes());
+            },
+            _ => {
+                packet.extend([UNIT_BYTE, FIXED_VALUE]);
+                packet.extend((bytes.len() as u16).to_le_bytes());
+            }
+        }
+        packet.extend_from_slice(&bytes);
+    }
+
+    Ok(packet)
+}
@@ -12,7 +12,24 @@ Error Response
 Header (Autoset) + Subheader + Access route + Data length + End code + Error
 */
 
+pub(crate) mod clock;
 pub(crate) mod device_access;
+pub(crate) mod diagnostics;
+pub(crate) mod file_control;
+pub(crate) mod label_access;
 pub(crate) mod unit_control;
 
 const COMMAND_BYTELEN: usize = 4;
+
+/// Commands changing the CPU: remote operations, device and label writes, error clear and file writes.
+const WRITE_COMMANDS: [u16; 17] = [
+    0x1001, 0x1002, 0x1003, 0x1005, 0x1006,
+    0x1401, 0x1402, 0x1406, 0x141A, 0x141B,
+    0x1617,
+    0x1820, 0x1822, 0x1824, 0x1825, 0x1826, 0x1829,
+];
+
+/// Whether a request (command, subcommand and data) changes the CPU.
+pub(crate) fn is_write_command(msg: &[u8]) -> bool {
+    msg.get(..2).is_some_and(|x| WRITE_COMMANDS.contains(&u16::from_le_bytes([x[0], x[1]])))
+}
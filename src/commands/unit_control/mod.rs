@@ -1,3 +1,5 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
 use crate::CPU;
 
 pub(crate) const fn remote_run() -> [u8; 8] {
@@ -75,23 +77,24 @@ pub(crate) const fn get_cpu_type() -> [u8; 4] {
     ]
 }
 
-fn validate_password(cpu: &CPU, password: &str) -> std::io::Result<()> {
+fn validate_password(cpu: &CPU, password: &str) -> crate::io::Result<()> {
     let len = password.len();
     match cpu {
         CPU::Q | CPU::L => if len != 4 {
-            return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Q/L type CPU requires password length of 4"));
+            Err(crate::io::Error::new(crate::io::ErrorKind::Unsupported, "Q/L type CPU requires password length of 4"))
         } else { Ok(()) },
-        CPU::R => if !(6..=32).contains(&len) {
-            return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "R type CPU requires password length of 6~32"));
-        } else { Ok(()) }
+        CPU::R | CPU::F => if !(6..=32).contains(&len) {
+            Err(crate::io::Error::new(crate::io::ErrorKind::Unsupported, format!("{cpu:?} type CPU requires password length of 6~32")))
+        } else { Ok(()) },
+        CPU::A => Err(crate::io::Error::new(crate::io::ErrorKind::Unsupported, "A type CPU (1E frame) has no remote password")),
     }
 }
 
-pub(crate) fn unlock_cpu(cpu: &CPU, password: &str) -> std::io::Result<Vec<u8>> {
+pub(crate) fn unlock_cpu(cpu: &CPU, password: &str) -> crate::io::Result<Vec<u8>> {
     const COMMAND: [u8; 2] = 0x1630u16.to_le_bytes();
     const SUBCOMMAND: [u8; 2] = [0x00, 0x00];
 
-    validate_password(cpu, &password)?;
+    validate_password(cpu, password)?;
 
     let password = password.as_bytes();
     let password_len = password.len();
@@ -107,11 +110,11 @@ pub(crate) fn unlock_cpu(cpu: &CPU, password: &str) -> std::io::Result<Vec<u8>>
     Ok(packet)
 }
 
-pub(crate) fn lock_cpu(cpu: &CPU, password: &str) -> std::io::Result<Vec<u8>> {
+pub(crate) fn lock_cpu(cpu: &CPU, password: &str) -> crate::io::Result<Vec<u8>> {
     const COMMAND: [u8; 2] = 0x1631u16.to_le_bytes();
     const SUBCOMMAND: [u8; 2] = [0x00, 0x00];
 
-    validate_password(cpu, &password)?;
+    validate_password(cpu, password)?;
 
     let password = password.as_bytes();
     let password_len = password.len();
@@ -129,10 +132,133 @@ pub(crate) fn lock_cpu(cpu: &CPU, password: &str) -> std::io::Result<Vec<u8>> {
 
 pub(crate) const ECHO_MESSAGE: [u8; 4] = [0x41, 0x31, 0x47, 0x35];
 
+/// Model name and model code reported by `get_cpu_type`.
+/// `family` is derived from the model code, and is `None` for a code outside the known ranges.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CpuTypeInfo {
+    pub name: String,
+    pub code: u16,
+    pub family: Option<CPU>,
+}
+
+impl CpuTypeInfo {
+    pub fn new(name: impl Into<String>, code: u16) -> Self {
+        Self { name: name.into(), code, family: cpu_family(code) }
+    }
+
+    /// Fail with `InvalidInput` when the PLC reports a family other than the configured one,
+    /// since the subcommands of the configured family would be rejected or misread.
+    pub fn check(&self, configured: &CPU) -> crate::io::Result<()> {
+        match self.family {
+            Some(family) if family != *configured => Err(crate::io::Error::new(
+                crate::io::ErrorKind::InvalidInput,
+                format!("Configured CPU {:?} does not match {} ({:?}, model code 0x{:04X}) reported by the PLC", configured, self.name, family, self.code)
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Model codes of known CPUs, answered by the mock server. iQ-L CPUs share the iQ-R subcommands and belong to `CPU::R`.
+/// The one-byte model code of MELSEC-A is shared by several models.
+#[cfg(feature = "mock-server")]
+pub(crate) const MODEL_CODES: [(&str, u16); 52] = [
+    ("Q02CPU", 0x0041), ("Q06HCPU", 0x0042), ("Q12HCPU", 0x0043), ("Q25HCPU", 0x0044),
+    ("Q00JCPU", 0x0250), ("Q00CPU", 0x0251), ("Q01CPU", 0x0252),
+    ("Q00UJCPU", 0x0260), ("Q00UCPU", 0x0261), ("Q01UCPU", 0x0262), ("Q02UCPU", 0x0263),
+    ("Q10UDEHCPU", 0x0266), ("Q20UDEHCPU", 0x0267), ("Q03UDECPU", 0x0268), ("Q04UDEHCPU", 0x0269),
+    ("Q06UDEHCPU", 0x026A), ("Q13UDEHCPU", 0x026B), ("Q26UDEHCPU", 0x026C), ("Q50UDEHCPU", 0x026D), ("Q100UDEHCPU", 0x026E),
+    ("Q03UDVCPU", 0x0366), ("Q04UDVCPU", 0x0367), ("Q06UDVCPU", 0x0368), ("Q13UDVCPU", 0x036A), ("Q26UDVCPU", 0x036C),
+    ("L02CPU", 0x0541), ("L26CPU-BT", 0x0542), ("L02SCPU", 0x0543), ("L06CPU", 0x0544), ("L26CPU", 0x0545),
+    ("L02CPU-P", 0x0641), ("L26CPU-PBT", 0x0642),
+    ("R04CPU", 0x4800), ("R08CPU", 0x4801), ("R16CPU", 0x4802), ("R32CPU", 0x4803), ("R120CPU", 0x4804),
+    ("R04ENCPU", 0x4805), ("R08ENCPU", 0x4806), ("R16ENCPU", 0x4807), ("R32ENCPU", 0x4808), ("R120ENCPU", 0x4809),
+    ("R00CPU", 0x4842), ("R01CPU", 0x4843), ("R02CPU", 0x4844),
+    ("L04HCPU", 0x48C0), ("L08HCPU", 0x48C1), ("L16HCPU", 0x48C2),
+    ("FX5U-32MR/ES", 0x4A21), ("FX5U-32MT/ES", 0x4A23), ("FX5UC-32MT/D", 0x4A91),
+    ("A2USHCPU-S1", 0x0083),
+];
+
+/// Family of a model code by its range.
+pub(crate) const fn cpu_family(code: u16) -> Option<CPU> {
+    match code {
+        0x4800..=0x48FF => Some(CPU::R),
+        0x4A00..=0x4BFF => Some(CPU::F),
+        0x0541..=0x0545 | 0x0641..=0x0642 => Some(CPU::L),
+        0x0041..=0x0044 | 0x0250..=0x036F => Some(CPU::Q),
+        0x0080..=0x00FF => Some(CPU::A),
+        _ => None,
+    }
+}
+
+/// Model name (16 characters padded with spaces) followed by the model code.
+pub(crate) fn parse_cpu_type(recv: &[u8]) -> crate::io::Result<CpuTypeInfo> {
+    const NAME_LEN: usize = 16;
+    const PADDING: u8 = 0x20;
+    if recv.len() < NAME_LEN + 2 {
+        return Err(crate::io::Error::new(
+            crate::io::ErrorKind::InvalidData,
+            format!("CPU type response of {} bytes, expected {}", recv.len(), NAME_LEN + 2)
+        ));
+    }
+    let name = &recv[..NAME_LEN];
+    let end_pos = name.iter().position(|&b| b == PADDING || b == 0x00).unwrap_or(NAME_LEN);
+    let code = u16::from_le_bytes([recv[NAME_LEN], recv[NAME_LEN + 1]]);
+    Ok(CpuTypeInfo::new(String::from_utf8_lossy(&name[..end_pos]), code))
+}
+
+/// The response of `echo` holds the message length and the message sent.
+pub(crate) fn check_echo(recv: &[u8]) -> crate::io::Result<()> {
+    check_loopback(recv, &ECHO_MESSAGE)
+}
+
+/// The response of a loopback test holds the message length and exactly the message sent.
+pub(crate) fn check_loopback(recv: &[u8], message: &[u8]) -> crate::io::Result<()> {
+    let len = recv.get(..2).map(|x| u16::from_le_bytes([x[0], x[1]]) as usize);
+    let received = recv.get(2..).unwrap_or(recv);
+    if len == Some(message.len()) && received == message {
+        Ok(())
+    } else {
+        Err(crate::io::Error::new(
+            crate::io::ErrorKind::InvalidData,
+            format!("Echo mismatch, send: {:02x?}, received: {:02x?}", message, received)
+        ))
+    }
+}
+
+pub(crate) const LOOPBACK_MAX_LEN: usize = 960;
+
+/// Loopback test of 1~960 ASCII printable characters.
+pub(crate) fn loopback(message: &[u8]) -> crate::io::Result<Vec<u8>> {
+    const COMMAND: [u8; 2] = 0x0619u16.to_le_bytes();
+    const SUBCOMMAND: [u8; 2] = [0x00, 0x00];
+
+    if message.is_empty() || message.len() > LOOPBACK_MAX_LEN {
+        return Err(crate::io::Error::new(
+            crate::io::ErrorKind::InvalidInput,
+            format!("Loopback data must be 1~{} bytes, got {}", LOOPBACK_MAX_LEN, message.len())
+        ));
+    }
+    if let Some(pos) = message.iter().position(|b| !(0x20..=0x7E).contains(b)) {
+        return Err(crate::io::Error::new(
+            crate::io::ErrorKind::InvalidInput,
+            format!("Loopback data must be ASCII printable, got 0x{:02X} at {}", message[pos], pos)
+        ));
+    }
+
+    let mut packet: Vec<u8> = Vec::with_capacity(6 + message.len());
+    packet.extend_from_slice(&COMMAND);
+    packet.extend_from_slice(&SUBCOMMAND);
+    packet.extend_from_slice(&(message.len() as u16).to_le_bytes());
+    packet.extend_from_slice(message);
+    Ok(packet)
+}
+
 pub(crate) const fn echo() -> [u8; 10] {
     const COMMAND: [u8; 2] = 0x0619u16.to_le_bytes();
     const SUBCOMMAND: [u8; 2] = [0x00, 0x00];
-    const MESSAGE_LEN: [u8; 2] = (4 as u16).to_le_bytes();
+    const MESSAGE_LEN: [u8; 2] = 4_u16.to_le_bytes();
 
     [
         COMMAND[0], COMMAND[1],
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
+
+use crate::{ChangeFilter, MonitorRequest, PLCData, SLMP4EConnectionProps, SLMPConnectionManager, TypedDevice};
+
+/// Connections of a `SLMPConnectionManager`, e.g. deserialized from a JSON/TOML file.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManagerConfig {
+    pub connections: Vec<ConnectionConfig>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionConfig {
+    /// Name to look the connection up with `get_by_name`.
+    pub name: String,
+    pub connection_props: SLMP4EConnectionProps,
+    pub cycle_ms: u64,
+    /// Devices monitored from the start, in the form of `TypedDevice` (e.g. "D100:U16", "X1F:Bool").
+    #[serde(default)]
+    pub monitor_devices: Vec<String>,
+}
+
+impl SLMPConnectionManager {
+    /// Connect all connections of `config` concurrently and register their monitor devices.
+    /// The result of each connection is returned by name, in the order of `config`.
+    pub async fn from_config<T, F, Fut>(config: &ManagerConfig, cyclic_task: F) -> (Self, Vec<(String, std::io::Result<()>)>)
+        where
+            T: 'static,
+            F: Fn(Vec<PLCData>) -> Fut + Clone + std::marker::Send + std::marker::Sync + 'static,
+            Fut: std::future::Future<Output = std::io::Result<T>> + std::marker::Send,
+    {
+        let manager = Arc::new(Self::new());
+
+        // A task which panicked has no result of its own, so the results are placed by the index of their task.
+        let mut tasks: JoinSet<std::io::Result<()>> = JoinSet::new();
+        let mut indices: HashMap<tokio::task::Id, usize> = HashMap::with_capacity(config.connections.len());
+        for (i, connection) in config.connections.iter().enumerate() {
+            let manager = manager.clone();
+            let connection = connection.clone();
+            let cyclic_task = cyclic_task.clone();
+            let handle = tasks.spawn(async move { manager.connect_config(&connection, cyclic_task).await });
+            indices.insert(handle.id(), i);
+        }
+
+        let mut results: Vec<Option<std::io::Result<()>>> = (0..config.connections.len()).map(|_| None).collect();
+        while let Some(ret) = tasks.join_next_with_id().await {
+            let (id, ret) = match ret {
+                Ok((id, ret)) => (id, ret),
+                Err(e) => (e.id(), Err(std::io::Error::other(e))),
+            };
+            results[indices[&id]] = Some(ret);
+        }
+
+        let results = config.connections
+            .iter()
+            .zip(results)
+            .map(|(connection, ret)| (connection.name.clone(), ret.unwrap_or_else(|| Err(std::io::Error::other("Connection task lost")))))
+            .collect();
+
+        // Every task holding the manager has finished.
+        let manager = Arc::try_unwrap(manager).unwrap_or_else(|_| unreachable!());
+        (manager, results)
+    }
+
+    async fn connect_config<T, F, Fut>(&self, connection: &ConnectionConfig, cyclic_task: F) -> std::io::Result<()>
+        where
+            F: Fn(Vec<PLCData>) -> Fut + std::marker::Send + 'static,
+            Fut: std::future::Future<Output = std::io::Result<T>> + std::marker::Send,
+    {
+        let devices: Vec<TypedDevice> = connection.monitor_devices
+            .iter()
+            .map(|x| x.parse())
+            .collect::<std::io::Result<_>>()?;
+
+        self.connect_named(&connection.name, &connection.connection_props, cyclic_task, connection.cycle_ms).await?;
+
+        if !devices.is_empty() {
+            let targets: Vec<MonitorRequest> = devices
+                .into_iter()
+                .map(|monitor_device| MonitorRequest {
+                    connection_props: &connection.connection_props,
+                    monitor_device,
+                    change_filter: ChangeFilter::Always,
+                    cycle_ms: None,
+                })
+                .collect();
+            self.add_monitor_targets(&targets).await?;
+        }
+
+        Ok(())
+    }
+}
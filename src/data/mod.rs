@@ -1,13 +1,14 @@
+use crate::prelude::*;
 use serde::{Serialize, Deserialize};
 use crate::{bits_to_u16, device::DeviceSize, u16_to_bits};
 
 pub(crate) mod string;
-use string::PLCString;
+use string::{PLCString, StringEncoding};
 
 /// Available data type for SLMP communication.
 #[repr(u8)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-#[cfg_attr(feature = "json-api", serde(rename_all = "PascalCase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
 pub enum DataType {
     Bool = 1,
     BitArray16 = 2,
@@ -15,40 +16,130 @@ pub enum DataType {
     I16 = 4,
     U32 = 7,
     I32 = 8,
+    U64 = 12,
+    I64 = 13,
+    /// 4-digit BCD in a word, e.g. 0x1234 for 1234.
+    Bcd16 = 14,
+    /// 8-digit BCD in two words (lower digits first), as DBCD stores it.
+    Bcd32 = 15,
     F32 = 9,
     F64 = 5,
     /// You should provide a word size to be accessed.
     String(u8) = 6,
+    /// `String` in ASCII.
+    AsciiString(u8) = 10,
+    /// `String` in UTF-16LE (WSTRING); the word size counts UTF-16 code units.
+    WString(u8) = 11,
 }
 
 impl DataType {
+    /// The string type of `encoding` in `device_size` words.
+    pub const fn string(device_size: u8, encoding: StringEncoding) -> Self {
+        match encoding {
+            StringEncoding::ShiftJis => Self::String(device_size),
+            StringEncoding::Ascii => Self::AsciiString(device_size),
+            StringEncoding::Utf16Le => Self::WString(device_size),
+        }
+    }
+
+    /// Word size and encoding of a string type.
+    pub const fn as_string(&self) -> Option<(u8, StringEncoding)> {
+        match *self {
+            Self::String(n) => Some((n, StringEncoding::ShiftJis)),
+            Self::AsciiString(n) => Some((n, StringEncoding::Ascii)),
+            Self::WString(n) => Some((n, StringEncoding::Utf16Le)),
+            _ => None,
+        }
+    }
+
     #[inline(always)]
     pub(crate) const fn byte_size(&self) -> usize {
         match self {
             DataType::Bool => 1,
-            DataType::BitArray16 | DataType::U16 | DataType::I16 => 2,
-            DataType::U32 | DataType::I32 | DataType::F32 => 4,
-            DataType::F64 => 8,
-            DataType::String(n) => *n as usize * 2  // n: device size -> (2 * n): byte size
+            DataType::BitArray16 | DataType::U16 | DataType::I16 | DataType::Bcd16 => 2,
+            DataType::U32 | DataType::I32 | DataType::F32 | DataType::Bcd32 => 4,
+            DataType::U64 | DataType::I64 | DataType::F64 => 8,
+            DataType::String(n) | DataType::AsciiString(n) | DataType::WString(n) => *n as usize * 2  // n: device size -> (2 * n): byte size
         }
     }
 
+    /// Sort key of the devices of random and monitor requests: single words, multi-word values (sent as single words), and then double words.
+    /// The discriminants do not follow it, as types such as U64 and Bcd16 were added later.
+    pub(crate) const fn access_order(&self) -> (u8, Self) {
+        let class: u8 = match self.device_size() {
+            DeviceSize::Bit | DeviceSize::SingleWord => 0,
+            DeviceSize::MultiWord(_) => 1,
+            DeviceSize::DoubleWord => 2,
+        };
+        (class, *self)
+    }
+
     #[inline(always)]
     pub(crate) const fn device_size(&self) -> DeviceSize {
         match self {
             DataType::Bool => DeviceSize::Bit,
-            DataType::BitArray16 | DataType::U16 | DataType::I16 => DeviceSize::SingleWord,
-            DataType::U32 | DataType::I32 | DataType::F32 => DeviceSize::DoubleWord,
-            DataType::F64 => DeviceSize::MultiWord(4),
-            DataType::String(n) => DeviceSize::MultiWord(*n)
+            DataType::BitArray16 | DataType::U16 | DataType::I16 | DataType::Bcd16 => DeviceSize::SingleWord,
+            DataType::U32 | DataType::I32 | DataType::F32 | DataType::Bcd32 => DeviceSize::DoubleWord,
+            DataType::U64 | DataType::I64 | DataType::F64 => DeviceSize::MultiWord(4),
+            DataType::String(n) | DataType::AsciiString(n) | DataType::WString(n) => DeviceSize::MultiWord(*n)
         }
     }
 }
 
+impl core::fmt::Display for DataType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(self, f)
+    }
+}
+
+/// The names of the variants, e.g. `U16`, `F64`, `String(10)`, `WString(10)` (case-insensitive).
+impl core::str::FromStr for DataType {
+    type Err = crate::io::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || crate::io::Error::new(crate::io::ErrorKind::InvalidInput, format!("Invalid data type: {s}"));
+
+        match s.trim().to_ascii_uppercase().as_str() {
+            "BOOL" => Ok(Self::Bool),
+            "BITARRAY16" => Ok(Self::BitArray16),
+            "U16" => Ok(Self::U16),
+            "I16" => Ok(Self::I16),
+            "U32" => Ok(Self::U32),
+            "I32" => Ok(Self::I32),
+            "F32" => Ok(Self::F32),
+            "U64" => Ok(Self::U64),
+            "I64" => Ok(Self::I64),
+            "F64" => Ok(Self::F64),
+            "BCD16" => Ok(Self::Bcd16),
+            "BCD32" => Ok(Self::Bcd32),
+            x => {
+                let (name, size) = x.strip_suffix(')').and_then(|x| x.split_once('(')).ok_or_else(invalid)?;
+                let size: u8 = size.trim().parse().map_err(|_| invalid())?;
+                match name.trim() {
+                    "STRING" => Ok(Self::String(size)),
+                    "ASCIISTRING" => Ok(Self::AsciiString(size)),
+                    "WSTRING" => Ok(Self::WString(size)),
+                    _ => Err(invalid()),
+                }
+            },
+        }
+    }
+}
+
+/// Order of the words of 32/64-bit values (U32, I32, F32, Bcd32, U64, I64, F64) in devices.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum WordOrder {
+    /// Lower word first, as DMOV/EMOV store the values.
+    #[default]
+    LowHigh,
+    /// Upper word first.
+    HighLow,
+}
+
 /// Available typed-data for SLMP communication.
 /// It is used for all of write requests.
-#[derive(Clone, Copy, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
-#[cfg_attr(feature = "json-api", serde(rename_all = "PascalCase"))]
+#[derive(Clone, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
 #[serde(tag = "type", content = "value")]
 pub enum TypedData {
     Bool(bool),
@@ -57,10 +148,16 @@ pub enum TypedData {
     I16(i16),
     U32(u32),
     I32(i32),
+    U64(u64),
+    I64(i64),
     F32(f32),
     F64(f64),
-    ///If you send string request with json, is should expressed in the form {type: String, value: `${device_size}${BOUNDSTRING_QUERY_SPLITTER}${text}`}.
-    /// BOUNDSTRING_QUERY_SPLITTER is publicly available on this crate.
+    /// Value of a 4-digit BCD (0 to 9999).
+    Bcd16(u16),
+    /// Value of an 8-digit BCD (0 to 99999999).
+    Bcd32(u32),
+    /// In JSON, `{"type": "String", "value": {"text": "ABC", "deviceSize": 10, "encoding": "ShiftJis"}}` (`encoding` is optional).
+    /// The value may also be a query string `"10#|#ABC"` (see `PLCSTRING_QUERY_SPLITTER`).
     String(PLCString),
 }
 
@@ -75,28 +172,210 @@ impl From<(&[u8], DataType)> for TypedData {
             DataType::U32 => Self::U32(u32::from_le_bytes([value.0[0], value.0[1], value.0[2], value.0[3]])),
             DataType::I32 => Self::I32(i32::from_le_bytes([value.0[0], value.0[1], value.0[2], value.0[3]])),
             DataType::F32 => Self::F32(f32::from_le_bytes([value.0[0], value.0[1], value.0[2], value.0[3]])),
+            DataType::U64 => Self::U64(u64::from_le_bytes([value.0[0], value.0[1], value.0[2], value.0[3], value.0[4], value.0[5], value.0[6], value.0[7]])),
+            DataType::I64 => Self::I64(i64::from_le_bytes([value.0[0], value.0[1], value.0[2], value.0[3], value.0[4], value.0[5], value.0[6], value.0[7]])),
             DataType::F64 => Self::F64(f64::from_le_bytes([value.0[0], value.0[1], value.0[2], value.0[3], value.0[4], value.0[5], value.0[6], value.0[7]])),
+            DataType::Bcd16 => Self::Bcd16(bcd_to_binary(u16::from_le_bytes([value.0[0], value.0[1]]) as u32) as u16),
+            DataType::Bcd32 => Self::Bcd32(bcd_to_binary(u32::from_le_bytes([value.0[0], value.0[1], value.0[2], value.0[3]]))),
             DataType::String(n) => Self::String(PLCString::from_shift_jis_bytes(value.0, n)),
+            DataType::AsciiString(n) => Self::String(PLCString::from_bytes(value.0, n, StringEncoding::Ascii)),
+            DataType::WString(n) => Self::String(PLCString::from_bytes(value.0, n, StringEncoding::Utf16Le)),
         }
     }
 }
 
+/// Bytes stored in `WordOrder`. `(bytes, data_type)` is the same as `WordOrder::LowHigh`.
+impl From<(&[u8], DataType, WordOrder)> for TypedData {
+    fn from(value: (&[u8], DataType, WordOrder)) -> Self {
+        Self::from((value.0, value.1)).with_word_order(value.2)
+    }
+}
+
 impl TypedData {
-    #[inline(always)]
-    pub fn to_bytes(&self) -> &[u8] {
-        unsafe {
-            match self {
-                TypedData::Bool(true)  => &[1, 0],
-                TypedData::Bool(false) => &[0, 0],
-                TypedData::BitArray16(v) => std::slice::from_raw_parts(bits_to_u16(*v) as *const u16 as *const u8, 2),
-                TypedData::U16(v) => std::slice::from_raw_parts(v as *const u16 as *const u8, 2),
-                TypedData::I16(v) => std::slice::from_raw_parts(v as *const i16 as *const u8, 2),
-                TypedData::U32(v) => std::slice::from_raw_parts(v as *const u32 as *const u8, 4),
-                TypedData::I32(v) => std::slice::from_raw_parts(v as *const i32 as *const u8, 4),
-                TypedData::F32(v) => std::slice::from_raw_parts(v as *const f32 as *const u8, 4),
-                TypedData::F64(v) => std::slice::from_raw_parts(v as *const f64 as *const u8, 8),
-                TypedData::String(v) => v.as_bytes(),
-            }
+    /// Same as `From<(&[u8], DataType)>`, but a BCD with a digit over 9 fails with `InvalidData`
+    /// instead of being read digit by digit.
+    pub fn decode(bytes: &[u8], data_type: DataType) -> crate::io::Result<Self> {
+        let raw: Option<u32> = match data_type {
+            DataType::Bcd16 => Some(u16::from_le_bytes([bytes[0], bytes[1]]) as u32),
+            DataType::Bcd32 => Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+            _ => None,
+        };
+        if let Some(raw) = raw && !is_bcd(raw) {
+            return Err(crate::io::Error::new(crate::io::ErrorKind::InvalidData, format!("Invalid BCD for {data_type}: {raw:#x}")));
+        }
+        Ok(Self::from((bytes, data_type)))
+    }
+
+    /// Fail with `InvalidInput` for a value its type cannot store, i.e. a BCD over 9999 (`Bcd16`) or 99999999 (`Bcd32`).
+    pub fn check_range(&self) -> crate::io::Result<()> {
+        let (value, max): (u32, u32) = match *self {
+            Self::Bcd16(v) => (v as u32, 9_999),
+            Self::Bcd32(v) => (v, 99_999_999),
+            _ => return Ok(()),
+        };
+        if value > max {
+            return Err(crate::io::Error::new(crate::io::ErrorKind::InvalidInput, format!("{self:?} exceeds {max}")));
+        }
+        Ok(())
+    }
+
+    /// The value with its words reversed for `WordOrder::HighLow` (32/64-bit values only).
+    /// Reversing is its own inverse, so it converts a value to be written as well as a value read with `LowHigh`.
+    pub fn with_word_order(self, order: WordOrder) -> Self {
+        match (order, &self) {
+            (WordOrder::HighLow, Self::U32(_) | Self::I32(_) | Self::F32(_) | Self::U64(_) | Self::I64(_) | Self::F64(_) | Self::Bcd32(_)) => {
+                let bytes: Vec<u8> = self.to_bytes().chunks_exact(2).rev().flatten().copied().collect();
+                Self::from((&bytes[..], self.get_type()))
+            },
+            _ => self,
+        }
+    }
+
+    /// Same as `to_bytes`, with the words of 32/64-bit values in `order`.
+    pub fn to_ordered_bytes(&self, order: WordOrder) -> Vec<u8> {
+        self.clone().with_word_order(order).to_bytes()
+    }
+
+    /* Accessors */
+    // Each accessor takes the variants whose values convert without loss, and is `None` for the others.
+
+    pub const fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Self::Bool(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub const fn as_u16(&self) -> Option<u16> {
+        match *self {
+            Self::U16(v) | Self::Bcd16(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub const fn as_i16(&self) -> Option<i16> {
+        match *self {
+            Self::I16(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub const fn as_u32(&self) -> Option<u32> {
+        match *self {
+            Self::U16(v) | Self::Bcd16(v) => Some(v as u32),
+            Self::U32(v) | Self::Bcd32(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub const fn as_i32(&self) -> Option<i32> {
+        match *self {
+            Self::U16(v) | Self::Bcd16(v) => Some(v as i32),
+            Self::I16(v) => Some(v as i32),
+            Self::I32(v) => Some(v),
+            Self::Bcd32(v) => Some(v as i32), // 99999999 at most
+            _ => None,
+        }
+    }
+
+    pub const fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Self::U16(v) | Self::Bcd16(v) => Some(v as u64),
+            Self::U32(v) | Self::Bcd32(v) => Some(v as u64),
+            Self::U64(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub const fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Self::U16(v) | Self::Bcd16(v) => Some(v as i64),
+            Self::I16(v) => Some(v as i64),
+            Self::U32(v) | Self::Bcd32(v) => Some(v as i64),
+            Self::I32(v) => Some(v as i64),
+            Self::I64(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub const fn as_f32(&self) -> Option<f32> {
+        match *self {
+            Self::U16(v) | Self::Bcd16(v) => Some(v as f32),
+            Self::I16(v) => Some(v as f32),
+            Self::F32(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub const fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Self::U16(v) | Self::Bcd16(v) => Some(v as f64),
+            Self::I16(v) => Some(v as f64),
+            Self::U32(v) | Self::Bcd32(v) => Some(v as f64),
+            Self::I32(v) => Some(v as f64),
+            Self::F32(v) => Some(v as f64),
+            Self::F64(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<alloc::borrow::Cow<'_, str>> {
+        match self {
+            Self::String(v) => Some(v.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Any numeric value as f64, e.g. for plotting: `Bool` is 0 or 1, `BitArray16` its word,
+    /// and 64-bit integers are rounded to the nearest f64. `None` for strings.
+    pub fn value_as_f64(&self) -> Option<f64> {
+        match *self {
+            Self::Bool(v) => Some(v as u8 as f64),
+            Self::BitArray16(v) => Some(bits_to_u16(v) as f64),
+            Self::U64(v) => Some(v as f64),
+            Self::I64(v) => Some(v as f64),
+            Self::String(_) => None,
+            _ => self.as_f64(),
+        }
+    }
+
+    /// Total order like `f64::total_cmp`: F32/F64 are compared by `total_cmp` (a NaN equals the same NaN),
+    /// strings by text, and values of different types by the order of the variants.
+    pub fn total_cmp(&self, other: &Self) -> core::cmp::Ordering {
+        match (self, other) {
+            (Self::F32(a), Self::F32(b)) => a.total_cmp(b),
+            (Self::F64(a), Self::F64(b)) => a.total_cmp(b),
+            // The other values are ordered without NaN.
+            (a, b) => a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal),
+        }
+    }
+
+    /// Hash consistent with `total_cmp`.
+    pub(crate) fn hash_total<H: core::hash::Hasher>(&self, state: &mut H) {
+        use core::hash::Hash;
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Self::String(v) => v.as_str().hash(state),
+            v => v.to_bytes().hash(state),
+        }
+    }
+
+    /// Little-endian bytes as sent to a CPU. Bool takes a word, a string its `device_size` words.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            TypedData::Bool(v) => (*v as u16).to_le_bytes().to_vec(),
+            TypedData::BitArray16(v) => bits_to_u16(*v).to_le_bytes().to_vec(),
+            TypedData::U16(v) => v.to_le_bytes().to_vec(),
+            TypedData::I16(v) => v.to_le_bytes().to_vec(),
+            TypedData::U32(v) => v.to_le_bytes().to_vec(),
+            TypedData::I32(v) => v.to_le_bytes().to_vec(),
+            TypedData::F32(v) => v.to_le_bytes().to_vec(),
+            TypedData::U64(v) => v.to_le_bytes().to_vec(),
+            TypedData::I64(v) => v.to_le_bytes().to_vec(),
+            TypedData::F64(v) => v.to_le_bytes().to_vec(),
+            TypedData::Bcd16(v) => (binary_to_bcd(*v as u32) as u16).to_le_bytes().to_vec(),
+            TypedData::Bcd32(v) => binary_to_bcd(*v).to_le_bytes().to_vec(),
+            TypedData::String(v) => v.as_bytes().to_vec(),
         }
     }
 
@@ -110,8 +389,31 @@ impl TypedData {
             TypedData::U32(_) => DataType::U32,
             TypedData::I32(_) => DataType::I32,
             TypedData::F32(_) => DataType::F32,
+            TypedData::U64(_) => DataType::U64,
+            TypedData::I64(_) => DataType::I64,
             TypedData::F64(_) => DataType::F64,
-            TypedData::String(v) => DataType::String(v.device_size)
+            TypedData::Bcd16(_) => DataType::Bcd16,
+            TypedData::Bcd32(_) => DataType::Bcd32,
+            TypedData::String(v) => DataType::string(v.device_size, v.encoding())
+        }
+    }
+}
+
+/// The value only, e.g. `true`, `-1`, `0.5`, the text of a string, and `BitArray16` as 16 binary digits (bit 15 first).
+impl core::fmt::Display for TypedData {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Bool(v) => write!(f, "{v}"),
+            Self::BitArray16(v) => write!(f, "{:016b}", bits_to_u16(*v)),
+            Self::U16(v) | Self::Bcd16(v) => write!(f, "{v}"),
+            Self::I16(v) => write!(f, "{v}"),
+            Self::U32(v) | Self::Bcd32(v) => write!(f, "{v}"),
+            Self::I32(v) => write!(f, "{v}"),
+            Self::U64(v) => write!(f, "{v}"),
+            Self::I64(v) => write!(f, "{v}"),
+            Self::F32(v) => write!(f, "{v}"),
+            Self::F64(v) => write!(f, "{v}"),
+            Self::String(v) => write!(f, "{v}"),
         }
     }
 }
@@ -122,6 +424,12 @@ impl From<(&str, u8)> for TypedData {
     }
 }
 
+impl From<(&str, u8, StringEncoding)> for TypedData {
+    fn from(value: (&str, u8, StringEncoding)) -> Self {
+        Self::String(PLCString::from(value))
+    }
+}
+
 impl From<bool> for TypedData {
     fn from(value: bool) -> Self {
         Self::Bool(value)
@@ -164,8 +472,104 @@ impl From<f32> for TypedData {
     }
 }
 
+impl From<u64> for TypedData {
+    fn from(value: u64) -> Self {
+        Self::U64(value)
+    }
+}
+
+impl From<i64> for TypedData {
+    fn from(value: i64) -> Self {
+        Self::I64(value)
+    }
+}
+
 impl From<f64> for TypedData {
     fn from(value: f64) -> Self {
         Self::F64(value)
     }
 }
+
+impl From<PLCString> for TypedData {
+    fn from(value: PLCString) -> Self {
+        Self::String(value)
+    }
+}
+
+/// The text of a string, failing with `InvalidData` for another variant.
+impl TryFrom<TypedData> for String {
+    type Error = crate::io::Error;
+    fn try_from(value: TypedData) -> Result<Self, Self::Error> {
+        match value {
+            TypedData::String(v) => Ok(v.as_str().into_owned()),
+            x => Err(crate::io::Error::new(crate::io::ErrorKind::InvalidData, format!("{} is not String", x.get_type()))),
+        }
+    }
+}
+
+/// Take the value out of a `TypedData`, failing with `InvalidData` for another variant.
+macro_rules! impl_try_from_typed_data {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl TryFrom<TypedData> for $ty {
+                type Error = crate::io::Error;
+                fn try_from(value: TypedData) -> Result<Self, Self::Error> {
+                    match value {
+                        TypedData::$variant(v) => Ok(v),
+                        x => Err(crate::io::Error::new(
+                            crate::io::ErrorKind::InvalidData,
+                            format!("{} is not {}", x.get_type(), stringify!($variant))
+                        )),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_typed_data! {
+    bool => Bool,
+    [bool; 16] => BitArray16,
+    u16 => U16,
+    i16 => I16,
+    u32 => U32,
+    i32 => I32,
+    f32 => F32,
+    u64 => U64,
+    i64 => I64,
+    f64 => F64,
+    PLCString => String,
+}
+
+const fn is_bcd(raw: u32) -> bool {
+    let mut i = 0;
+    while i < 8 {
+        if (raw >> (4 * i)) & 0x0f > 9 {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Digits of a BCD to the value; nibbles over 9 are weighted as they are.
+const fn bcd_to_binary(raw: u32) -> u32 {
+    let mut ret: u32 = 0;
+    let mut i = 8;
+    while i > 0 {
+        i -= 1;
+        ret = ret.wrapping_mul(10).wrapping_add((raw >> (4 * i)) & 0x0f);
+    }
+    ret
+}
+
+const fn binary_to_bcd(mut value: u32) -> u32 {
+    let mut ret: u32 = 0;
+    let mut i = 0;
+    while i < 8 {
+        ret |= (value % 10) << (4 * i);
+        value /= 10;
+        i += 1;
+    }
+    ret
+}
@@ -1,51 +1,162 @@
+use crate::prelude::*;
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
 use encoding_rs::SHIFT_JIS;
 
-const PLCSTRING_MAX_BYTES: usize = 64;
-const PLCSTRING_MAX_DEVICE_SIZE: usize = PLCSTRING_MAX_BYTES / 2;
-
-const SHIFT_JIS_NULL_CODE: u8 = 0x00;
+const NULL_CODE: u8 = 0x00;
 
 pub const PLCSTRING_QUERY_SPLITTER: &str = "#|#";
 
-/// String is stored as u8 array (max: 64 byte).
-/// Character code is Shift-JIS.
-#[derive(Clone, Copy)]
+/// Character code of a string in devices.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum StringEncoding {
+    /// STRING of GX Works (`DataType::String`).
+    #[default]
+    ShiftJis,
+    /// 7-bit ASCII (`DataType::AsciiString`). Other bytes are read as U+FFFD.
+    Ascii,
+    /// WSTRING of GX Works3 (`DataType::WString`), a character or surrogate per word. It ends at a NUL word.
+    Utf16Le,
+}
+
+impl core::str::FromStr for StringEncoding {
+    type Err = crate::io::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "SHIFTJIS" | "SHIFT_JIS" => Ok(Self::ShiftJis),
+            "ASCII" => Ok(Self::Ascii),
+            "UTF16LE" | "UTF-16LE" => Ok(Self::Utf16Le),
+            _ => Err(crate::io::Error::new(crate::io::ErrorKind::InvalidInput, format!("Invalid string encoding: {s}"))),
+        }
+    }
+}
+
+impl StringEncoding {
+    /// Bytes of `text`, or `None` if a character is not representable.
+    fn encode(self, text: &str) -> Option<Vec<u8>> {
+        match self {
+            Self::ShiftJis => match SHIFT_JIS.encode(text) {
+                (_, _, true) => None,
+                (bytes, _, false) => Some(bytes.into_owned()),
+            },
+            Self::Ascii => text.is_ascii().then(|| text.as_bytes().to_vec()),
+            Self::Utf16Le => Some(text.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+        }
+    }
+
+    /// Length of the text in `bytes`, up to the first NUL (a NUL word for UTF-16).
+    fn text_len(self, bytes: &[u8]) -> usize {
+        match self {
+            Self::ShiftJis | Self::Ascii => bytes.iter().position(|&b| b == NULL_CODE).unwrap_or(bytes.len()),
+            Self::Utf16Le => bytes.chunks(2).position(|x| x == [NULL_CODE, NULL_CODE]).map_or(bytes.len() & !1, |x| x * 2),
+        }
+    }
+}
+
+/// String is stored as the bytes of `device_size` words (max: 255 words, 510 bytes).
+/// Character code is Shift-JIS unless another `StringEncoding` is given.
+#[derive(Clone)]
 pub struct PLCString {
-    pub data: [u8; PLCSTRING_MAX_BYTES],
-    pub(crate) effective_len: u8,
+    /// `device_size * 2` bytes, padded with NULs after the text.
+    data: Box<[u8]>,
+    pub(crate) effective_len: usize,
     pub(crate) device_size: u8,
+    encoding: StringEncoding,
 }
 
 impl PLCString {
 
+    /// The text is up to the first NUL in `bytes`, and is cut at `device_size` words.
     pub fn from_shift_jis_bytes(bytes: &[u8], device_size: u8) -> Self {
-        let nul_pos = bytes.iter().position(|&b| b == SHIFT_JIS_NULL_CODE).unwrap_or(bytes.len());
+        Self::from_bytes(bytes, device_size, StringEncoding::ShiftJis)
+    }
 
-        let effective_len = nul_pos.min(device_size as usize * 2);
-        let device_size = device_size.min(PLCSTRING_MAX_DEVICE_SIZE as u8);
+    /// Same as `from_shift_jis_bytes` for the text in `encoding`.
+    pub fn from_bytes(bytes: &[u8], device_size: u8, encoding: StringEncoding) -> Self {
+        let bytes = &bytes[..bytes.len().min(device_size as usize * 2)];
+        let effective_len = encoding.text_len(bytes);
 
-        let mut data = [SHIFT_JIS_NULL_CODE; PLCSTRING_MAX_BYTES];
+        let mut data = vec![NULL_CODE; device_size as usize * 2].into_boxed_slice();
         data[..effective_len].copy_from_slice(&bytes[..effective_len]);
 
-        PLCString { data, effective_len: effective_len as u8, device_size}
+        PLCString { data, effective_len, device_size, encoding }
+    }
+
+    /// Encode `text` in Shift-JIS for `device_size` words.
+    /// Unlike `From<(&str, u8)>`, it fails with `InvalidInput` instead of truncating a text that does not fit.
+    pub fn try_from_str(text: &str, device_size: u8) -> crate::io::Result<Self> {
+        Self::encode(text, device_size, StringEncoding::ShiftJis)
+    }
+
+    /// Same as `try_from_str` for the text in `encoding`.
+    pub fn encode(text: &str, device_size: u8, encoding: StringEncoding) -> crate::io::Result<Self> {
+        let invalid_input = |msg: String| crate::io::Error::new(crate::io::ErrorKind::InvalidInput, msg);
+
+        Self::check_device_size(device_size)?;
+
+        let bytes = encoding.encode(text)
+            .ok_or_else(|| invalid_input(format!("Contains characters not representable in {encoding:?}")))?;
+
+        let required_byte_size = bytes.len();
+        let allowed_byte_size = device_size as usize * 2;
+
+        if required_byte_size > allowed_byte_size {
+            return Err(invalid_input(format!(
+                "Device size is too small to store {:?} string: Specified size: {}, Required: {})",
+                encoding, device_size, required_byte_size
+            )));
+        }
+
+        Ok(Self::from_bytes(&bytes, device_size, encoding))
+    }
+
+    pub(crate) fn check_device_size(device_size: u8) -> crate::io::Result<()> {
+        if device_size == 0 {
+            return Err(crate::io::Error::new(
+                crate::io::ErrorKind::InvalidInput,
+                format!("device_size must be between 1 and {}", u8::MAX)
+            ));
+        }
+        Ok(())
+    }
+
+    /// The same text fitted to `device_size` words.
+    pub(crate) fn resized(&self, device_size: u8) -> Self {
+        Self::from_bytes(&self.data[..self.effective_len], device_size, self.encoding)
     }
 
     pub fn as_bytes(&self) -> &[u8] {
-        let bytes = self.device_size as usize * 2;
-        &self.data[..bytes]
+        &self.data
     }
 
-    pub fn as_str(&self) -> std::borrow::Cow<'_, str> {
-        let bytes = &self.data[..self.effective_len as usize];
-        let (cow, _, _) = encoding_rs::SHIFT_JIS.decode(bytes);
-        cow
+    pub fn as_str(&self) -> alloc::borrow::Cow<'_, str> {
+        let bytes = &self.data[..self.effective_len];
+        match self.encoding {
+            StringEncoding::ShiftJis => SHIFT_JIS.decode(bytes).0,
+            StringEncoding::Ascii => bytes.iter()
+                .map(|&b| if b.is_ascii() { b as char } else { char::REPLACEMENT_CHARACTER })
+                .collect::<String>()
+                .into(),
+            StringEncoding::Utf16Le => {
+                let units = bytes.chunks_exact(2).map(|x| u16::from_le_bytes([x[0], x[1]]));
+                char::decode_utf16(units)
+                    .map(|x| x.unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .collect::<String>()
+                    .into()
+            },
+        }
     }
 
+    /// The text as an owned `String`, the same as `Display` gives. Kept as an inherent method of the public API.
+    #[allow(clippy::inherent_to_string_shadow_display)]
     pub fn to_string(&self) -> String {
         self.as_str().into_owned()
     }
 
+    pub const fn encoding(&self) -> StringEncoding {
+        self.encoding
+    }
+
     pub fn is_empty(&self) -> bool {
         self.effective_len == 0
     }
@@ -53,19 +164,31 @@ impl PLCString {
 
 impl From<(&str, u8)> for PLCString {
     fn from(s: (&str, u8)) -> Self {
-        let (shift_jis_bytes, _, _) = SHIFT_JIS.encode(s.0);
-        Self::from_shift_jis_bytes(&shift_jis_bytes, s.1)
+        Self::from((s.0, s.1, StringEncoding::ShiftJis))
     }
 }
 
-impl std::fmt::Display for PLCString {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// Characters not representable in the encoding are left out, and the text is cut at the device size.
+impl From<(&str, u8, StringEncoding)> for PLCString {
+    fn from(s: (&str, u8, StringEncoding)) -> Self {
+        let (text, device_size, encoding) = s;
+        let bytes: Vec<u8> = match encoding {
+            StringEncoding::ShiftJis => SHIFT_JIS.encode(text).0.into_owned(),
+            StringEncoding::Ascii => text.bytes().filter(u8::is_ascii).collect(),
+            StringEncoding::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+        };
+        Self::from_bytes(&bytes, device_size, encoding)
+    }
+}
+
+impl core::fmt::Display for PLCString {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.as_str())
     }
 }
 
-impl std::fmt::Debug for PLCString {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Debug for PLCString {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_tuple("PLCString")
             .field(&self.as_str())
             .finish()
@@ -81,28 +204,52 @@ impl PartialEq for PLCString {
 impl Eq for PLCString {}
 
 impl PartialOrd for PLCString {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
 
 impl Ord for PLCString {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
         self.as_str().cmp(&other.as_str())
     }
 }
 
 
+/// Fields of the serialized form, e.g. `{"text": "ABC", "deviceSize": 10, "encoding": "ShiftJis"}`.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PLCStringFields {
+    text: String,
+    device_size: u8,
+    #[serde(default)]
+    encoding: StringEncoding,
+}
+
+/// Either form `Deserialize` takes.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PLCStringInput {
+    Query(String),
+    Fields(PLCStringFields),
+}
+
+/// The text with its device size and encoding, so that it deserializes back to the same string.
 impl Serialize for PLCString {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        serializer.serialize_str(&self.as_str().into_owned())
+        PLCStringFields { text: self.as_str().into_owned(), device_size: self.device_size, encoding: self.encoding }.serialize(serializer)
     }
 }
 
+/// The fields written by `Serialize`, or a query string:
+/// `device_size#|#text` is a Shift-JIS string; `device_size:encoding#|#text` (e.g. `10:Utf16Le#|#text`) selects another encoding.
 impl<'de> Deserialize<'de> for PLCString {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
 
-        let combined = String::deserialize(deserializer)?;
+        let combined = match PLCStringInput::deserialize(deserializer)? {
+            PLCStringInput::Fields(x) => return Self::encode(&x.text, x.device_size, x.encoding).map_err(serde::de::Error::custom),
+            PLCStringInput::Query(x) => x,
+        };
         let parts: Vec<&str> = combined.splitn(2, PLCSTRING_QUERY_SPLITTER).collect();
 
         if parts.len() != 2 {
@@ -112,7 +259,10 @@ impl<'de> Deserialize<'de> for PLCString {
             )));
         }
 
-        let device_size_str = parts[0].trim();
+        let (device_size_str, encoding) = match parts[0].split_once(':') {
+            Some((device_size_str, encoding)) => (device_size_str.trim(), encoding.parse().map_err(serde::de::Error::custom)?),
+            None => (parts[0].trim(), StringEncoding::ShiftJis),
+        };
         let text = parts[1].trim().to_string();
 
         let device_size: u8 = device_size_str.parse().map_err(|_| {
@@ -122,29 +272,6 @@ impl<'de> Deserialize<'de> for PLCString {
             ))
         })?;
 
-        if !(1..=PLCSTRING_MAX_DEVICE_SIZE as u8).contains(&device_size) {
-            return Err(serde::de::Error::custom(format!(
-                "device_size must be between 1 and {}",
-                PLCSTRING_MAX_DEVICE_SIZE
-            )));
-        }
-
-        let (shift_jis_bytes, _, had_errors) = SHIFT_JIS.encode(&text);
-
-        if had_errors {
-            return Err(serde::de::Error::custom("Contains characters not representable in Shift-JIS"));
-        }
-
-        let required_byte_size = shift_jis_bytes.len();
-        let allowed_byte_size = device_size as usize * 2;
-
-        if required_byte_size > allowed_byte_size {
-            return Err(serde::de::Error::custom(format!(
-                "Device size is too small to store Shift-JIS string: Specified size: {}, Required: {})",
-                device_size, required_byte_size
-            )));
-        }
-
-        Ok(Self::from_shift_jis_bytes(&shift_jis_bytes, device_size))
+        Self::encode(&text, device_size, encoding).map_err(serde::de::Error::custom)
     }
 }
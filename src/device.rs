@@ -1,10 +1,11 @@
-use std::net::SocketAddr;
+use crate::prelude::*;
+use core::net::SocketAddr;
 
 use serde::{Deserialize, Serialize};
-use crate::{CPU, DataType, TypedData};
+use crate::{CPU, DataType, Route, TypedData};
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-#[cfg_attr(feature = "json-api", serde(rename_all = "PascalCase"))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
 pub enum AccessType {
     Bit = 2,
     Word = 1,
@@ -12,7 +13,7 @@ pub enum AccessType {
 
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-#[cfg_attr(feature = "json-api", serde(rename_all = "PascalCase"))]
+#[serde(rename_all = "PascalCase")]
 pub(crate) enum DeviceSize {
     Bit = 1,
     SingleWord = 2,
@@ -36,8 +37,8 @@ impl From<DeviceSize> for usize {
 /// Device type used in Mitsubishi PLC.
 ///
 /// Available devices: X, Y, M, L, F, V, B, D, W, S, Z, R, TS, TC, TN, SS, SC, SN, CS, CC, CN, SB, SD, SM, SW, DX, DY, ZR,
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-#[cfg_attr(feature = "json-api", serde(rename_all = "PascalCase"))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
 pub enum DeviceType {
     X,
     Y,
@@ -103,11 +104,90 @@ impl DeviceType {
             Self::ZR => 0xb0,
         }
     }
+
+    /// Devices numbered in hexadecimal (e.g. X1F).
+    pub(crate) const fn has_hex_address(&self) -> bool {
+        matches!(self, Self::X | Self::Y | Self::B | Self::W | Self::SB | Self::SW | Self::DX | Self::DY)
+    }
+
+    /// Devices of bit points. A word of them holds 16 points.
+    pub const fn is_bit_device(&self) -> bool {
+        matches!(self,
+            Self::X | Self::Y | Self::M | Self::L | Self::F | Self::V |
+            Self::B | Self::S | Self::TS | Self::TC | Self::SS | Self::SC |
+            Self::CS | Self::CC | Self::SB | Self::SM | Self::DX | Self::DY
+        )
+    }
+
+    /// Devices of words (D, W, R, ZR, current values of timers and counters, ...). They take no bit access.
+    pub const fn is_word_device(&self) -> bool {
+        !self.is_bit_device()
+    }
+
+    /// Contacts and coils of timers and counters, accessed in bits only.
+    pub(crate) const fn is_bit_only(&self) -> bool {
+        matches!(self, Self::TS | Self::TC | Self::SS | Self::SC | Self::CS | Self::CC)
+    }
+
+    /// Addresses a value of `data_type` takes from its device, e.g. 32 points of `U32` on M, 2 words on D.
+    pub(crate) const fn points(&self, data_type: DataType) -> usize {
+        let words: usize = if data_type.byte_size() < 2 { 1 } else { data_type.byte_size() / 2 };
+        match (self.is_bit_device(), data_type) {
+            (true, DataType::Bool) => 1,
+            (true, _) => words * 16,
+            (false, _) => words,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        const DEVICE_TYPES: [DeviceType; 28] = [
+            DeviceType::X, DeviceType::Y, DeviceType::M, DeviceType::L, DeviceType::F, DeviceType::V, DeviceType::B,
+            DeviceType::D, DeviceType::W, DeviceType::S, DeviceType::Z, DeviceType::R, DeviceType::TS, DeviceType::TC,
+            DeviceType::TN, DeviceType::SS, DeviceType::SC, DeviceType::SN, DeviceType::CS, DeviceType::CC, DeviceType::CN,
+            DeviceType::SB, DeviceType::SD, DeviceType::SM, DeviceType::SW, DeviceType::DX, DeviceType::DY, DeviceType::ZR,
+        ];
+        DEVICE_TYPES.into_iter().find(|x| format!("{x:?}") == name)
+    }
+
+    /// Convert a byte code of the SLMP communication into a device_type, the reverse of `to_code`.
+    pub const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0x9c => Some(Self::X),
+            0x9d => Some(Self::Y),
+            0x90 => Some(Self::M),
+            0x92 => Some(Self::L),
+            0x93 => Some(Self::F),
+            0x94 => Some(Self::V),
+            0xa0 => Some(Self::B),
+            0xa8 => Some(Self::D),
+            0xb4 => Some(Self::W),
+            0x98 => Some(Self::S),
+            0xcc => Some(Self::Z),
+            0xaf => Some(Self::R),
+            0xc1 => Some(Self::TS),
+            0xc0 => Some(Self::TC),
+            0xc2 => Some(Self::TN),
+            0xc7 => Some(Self::SS),
+            0xc6 => Some(Self::SC),
+            0xc8 => Some(Self::SN),
+            0xc4 => Some(Self::CS),
+            0xc3 => Some(Self::CC),
+            0xc5 => Some(Self::CN),
+            0xa1 => Some(Self::SB),
+            0xa9 => Some(Self::SD),
+            0x91 => Some(Self::SM),
+            0xb5 => Some(Self::SW),
+            0xa2 => Some(Self::DX),
+            0xa3 => Some(Self::DY),
+            0xb0 => Some(Self::ZR),
+            _ => None,
+        }
+    }
 }
 
 /// It works as a device pointer.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-#[cfg_attr(feature = "json-api", serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Device {
     pub device_type: DeviceType,
     pub address: usize,
@@ -118,26 +198,258 @@ impl Device {
     pub fn serialize(&self, cpu: &CPU) -> Box<[u8]> {
         let device_code: u8 = self.device_type.to_code();
         let address: [u8; 8] = self.address.to_le_bytes();
-        let ret = match cpu {
-            CPU::Q | CPU::L => [address[0], address[1], address[2], device_code].into(),
+
+        match cpu {
+            CPU::Q | CPU::L | CPU::F | CPU::A => [address[0], address[1], address[2], device_code].into(),
             CPU::R => [address[0], address[1], address[2], 0x00, device_code, 0x00].into()
-        };
-        ret
+        }
+    }
+
+    /// Check the device against the default `DeviceLimits` of `cpu`.
+    pub fn validate(&self, cpu: &CPU) -> crate::io::Result<()> {
+        DeviceLimits::new(*cpu).check(*self, 1)
     }
 
     pub const fn addr_code_len(cpu: &CPU) -> u8 {
         match cpu {
-            CPU::Q | CPU::L => 4,
+            CPU::Q | CPU::L | CPU::F | CPU::A => 4,
             CPU::R => 6,
         }
     }
+
+    /// Largest address a request can carry (3 bytes).
+    pub const MAX_ADDRESS: usize = 0xff_ffff;
+
+    /// The device `n` addresses after this one, or `None` past `MAX_ADDRESS`.
+    pub const fn offset(&self, n: usize) -> Option<Device> {
+        match self.address.checked_add(n) {
+            Some(address) if address <= Self::MAX_ADDRESS => Some(Device { device_type: self.device_type, address }),
+            _ => None,
+        }
+    }
+
+    /// `count` consecutive devices from `start`. It stops at `MAX_ADDRESS`.
+    pub const fn range(start: Device, count: usize) -> DeviceRange {
+        DeviceRange { start, count }
+    }
+}
+
+/// Largest address of each device type, checked before a request is sent.
+///
+/// `DeviceLimits::new` takes the largest numbers of the CPU series. Devices whose size is set by parameters
+/// (file registers ZR and R, and most devices of iQ-R) may have fewer; set them with `set_max_address`.
+/// Device types the CPU series does not have (V, DX, DY and ZR of iQ-F, and those without a 1E device code on MELSEC-A) are rejected by `check`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeviceLimits {
+    max_addresses: [usize; 28],
+    unavailable: u32,
+}
+
+/// Device types without a device code in the 1E frame of MELSEC-A.
+const A_UNAVAILABLE: u32 = {
+    const TYPES: [DeviceType; 14] = [
+        DeviceType::L, DeviceType::V, DeviceType::S, DeviceType::Z, DeviceType::SS, DeviceType::SC, DeviceType::SN,
+        DeviceType::SB, DeviceType::SD, DeviceType::SM, DeviceType::SW, DeviceType::DX, DeviceType::DY, DeviceType::ZR,
+    ];
+    let mut ret: u32 = 0;
+    let mut i: usize = 0;
+    while i < TYPES.len() {
+        ret |= 1 << TYPES[i] as u32;
+        i += 1;
+    }
+    ret
+};
+
+impl DeviceLimits {
+    pub const fn new(cpu: CPU) -> Self {
+        let max_addresses: [usize; 28] = match cpu {
+            CPU::Q | CPU::L => [
+                // X, Y, M, L, F, V, B
+                0x1fff, 0x1fff, 61439, 32767, 32767, 32767, 0xefff,
+                // D, W, S, Z, R
+                4_910_079, 0x4a_efff, 8191, 19, 32767,
+                // TS, TC, TN, SS, SC, SN, CS, CC, CN
+                32767, 32767, 32767, 32767, 32767, 32767, 32767, 32767, 32767,
+                // SB, SD, SM, SW, DX, DY, ZR
+                0x7fff, 2047, 2047, 0x7fff, 0x1fff, 0x1fff, 4_849_663,
+            ],
+            CPU::R => {
+                let max = Device::MAX_ADDRESS;
+                [
+                    // X, Y, M, L, F, V, B
+                    0x2fff, 0x2fff, max, max, max, max, max,
+                    // D, W, S, Z, R
+                    max, max, 16383, 23, 32767,
+                    // TS, TC, TN, SS, SC, SN, CS, CC, CN
+                    max, max, max, max, max, max, max, max, max,
+                    // SB, SD, SM, SW, DX, DY, ZR
+                    max, 4095, 4095, max, 0x2fff, 0x2fff, max,
+                ]
+            },
+            // X and Y are numbered in octal: X1777 is address 1023.
+            CPU::F => [
+                // X, Y, M, L, F, V, B
+                1023, 1023, 32767, 32767, 32767, 0, 0x7fff,
+                // D, W, S, Z, R
+                7999, 0x7fff, 4095, 23, 32767,
+                // TS, TC, TN, SS, SC, SN, CS, CC, CN
+                1023, 1023, 1023, 1023, 1023, 1023, 1023, 1023, 1023,
+                // SB, SD, SM, SW, DX, DY, ZR
+                0x7fff, 11999, 9999, 0x7fff, 0, 0, 0,
+            ],
+            // M and D include the special relays M9000~ and registers D9000~. L and S are numbered within M.
+            CPU::A => [
+                // X, Y, M, L, F, V, B
+                0xfff, 0xfff, 9255, 0, 2047, 0, 0x1fff,
+                // D, W, S, Z, R
+                9255, 0x1fff, 0, 0, 8191,
+                // TS, TC, TN, SS, SC, SN, CS, CC, CN
+                2047, 2047, 2047, 0, 0, 0, 1023, 1023, 1023,
+                // SB, SD, SM, SW, DX, DY, ZR
+                0, 0, 0, 0, 0, 0, 0,
+            ],
+        };
+        let unavailable: u32 = match cpu {
+            CPU::F => 1 << DeviceType::V as u32 | 1 << DeviceType::DX as u32 | 1 << DeviceType::DY as u32 | 1 << DeviceType::ZR as u32,
+            CPU::A => A_UNAVAILABLE,
+            CPU::Q | CPU::L | CPU::R => 0,
+        };
+        Self { max_addresses, unavailable }
+    }
+
+    pub const fn max_address(&self, device_type: DeviceType) -> usize {
+        self.max_addresses[device_type as usize]
+    }
+
+    pub const fn is_available(&self, device_type: DeviceType) -> bool {
+        self.unavailable & 1 << device_type as u32 == 0
+    }
+
+    /// Capped at `Device::MAX_ADDRESS`, the largest address a request can carry. It makes the device type available.
+    pub fn set_max_address(&mut self, device_type: DeviceType, max_address: usize) {
+        self.max_addresses[device_type as usize] = max_address.min(Device::MAX_ADDRESS);
+        self.unavailable &= !(1 << device_type as u32);
+    }
+
+    /// Reject the device type, e.g. ZR on a model without file registers.
+    pub fn set_unavailable(&mut self, device_type: DeviceType) {
+        self.unavailable |= 1 << device_type as u32;
+    }
+
+    /// Same as `set_max_address`, e.g. `DeviceLimits::new(CPU::Q).with_max_address(DeviceType::ZR, 65535)`.
+    pub fn with_max_address(mut self, device_type: DeviceType, max_address: usize) -> Self {
+        self.set_max_address(device_type, max_address);
+        self
+    }
+
+    /// Check the `points` addresses from `start` (at least the start itself).
+    pub fn check(&self, start: Device, points: usize) -> crate::io::Result<()> {
+        if !self.is_available(start.device_type) {
+            return Err(crate::io::Error::new(
+                crate::io::ErrorKind::InvalidInput,
+                format!("{:?} is not available on this CPU", start.device_type)
+            ));
+        }
+        let max = Device { device_type: start.device_type, address: self.max_address(start.device_type) };
+        match start.address.checked_add(points.max(1) - 1) {
+            Some(end) if end <= max.address => Ok(()),
+            Some(end) if end > start.address => Err(crate::io::Error::new(
+                crate::io::ErrorKind::InvalidInput,
+                format!("{start} to {} is out of the device range (max: {max})", Device { address: end, ..start })
+            )),
+            _ => Err(crate::io::Error::new(
+                crate::io::ErrorKind::InvalidInput,
+                format!("{start} is out of the device range (max: {max})")
+            )),
+        }
+    }
+}
+
+/// It panics past `Device::MAX_ADDRESS`; use `Device::offset` to check.
+impl core::ops::Add<usize> for Device {
+    type Output = Device;
+    fn add(self, rhs: usize) -> Self::Output {
+        self.offset(rhs).unwrap_or_else(|| panic!("{self} + {rhs} exceeds the device address range"))
+    }
+}
+
+/// Consecutive devices of a type, e.g. D100 to D119. See `Device::range`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeviceRange {
+    start: Device,
+    count: usize,
+}
+
+impl DeviceRange {
+    /// Devices of `data_type` filling the range, e.g. D100, D102, ... for `U32`.
+    /// A value takes its words (16 points each on bit devices); one that does not fit in the range at its end is left out.
+    pub fn typed_devices(&self, data_type: DataType) -> Vec<TypedDevice> {
+        let stride: usize = self.start.device_type.points(data_type);
+        let count: usize = self.len() / stride;
+
+        (0..count)
+            .map(|i| TypedDevice { device: self.start + i * stride, data_type })
+            .collect()
+    }
+}
+
+impl Iterator for DeviceRange {
+    type Item = Device;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.count == 0 || self.start.address > Device::MAX_ADDRESS {
+            return None;
+        }
+        let ret = self.start;
+        self.count -= 1;
+        // Past `MAX_ADDRESS`, the next call ends the iteration.
+        self.start.address += 1;
+        Some(ret)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len: usize = self.count.min((Device::MAX_ADDRESS + 1).saturating_sub(self.start.address));
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for DeviceRange {}
+
+/// GX Works notation, e.g. `D100`, `X1F` (hexadecimal for X, Y, B, W, SB, SW, DX and DY).
+impl core::fmt::Display for Device {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.device_type.has_hex_address() {
+            write!(f, "{:?}{:X}", self.device_type, self.address)
+        } else {
+            write!(f, "{:?}{}", self.device_type, self.address)
+        }
+    }
+}
+
+impl core::str::FromStr for Device {
+    type Err = crate::io::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || crate::io::Error::new(crate::io::ErrorKind::InvalidInput, format!("Invalid device: {s}"));
+        let s = s.trim().to_ascii_uppercase();
+
+        // Two-letter device names first (e.g. "SD" before "S").
+        let (device_type, address) = [2, 1]
+            .into_iter()
+            .filter_map(|n| Some((DeviceType::from_name(s.get(..n)?)?, s.get(n..)?)))
+            .next()
+            .ok_or_else(invalid)?;
+
+        let radix = if device_type.has_hex_address() { 16 } else { 10 };
+        let address = usize::from_str_radix(address, radix).map_err(|_| invalid())?;
+        Ok(Self { device_type, address })
+    }
 }
 
 /// Device pointer with type annotation.
 /// It is used for random-read request.
 /// Results of random-read are typed as requested.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-#[cfg_attr(feature = "json-api", serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TypedDevice {
     pub device: Device,
     pub data_type: DataType,
@@ -146,38 +458,170 @@ pub struct TypedDevice {
 /// Block unit of the device pointer.
 /// It is used for block-read request.
 /// Multiple blocks are acceptable for block-read request.
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
-#[cfg_attr(feature = "json-api", serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct DeviceBlock {
     pub access_type: AccessType,
     pub start_device: Device,
     pub size: usize
 }
 
+impl DeviceBlock {
+    /// Start device and words of the request. A bit block is read in words of 16 points
+    /// from its start rounded down to a multiple of 16, and trimmed to its points afterwards.
+    pub(crate) const fn request_words(&self) -> (Device, usize) {
+        match self.access_type {
+            AccessType::Word => (self.start_device, self.size),
+            AccessType::Bit => {
+                let offset: usize = self.start_device.address % 16;
+                let start_device = Device { device_type: self.start_device.device_type, address: self.start_device.address - offset };
+                (start_device, crate::div_ceil(offset + self.size, 16))
+            },
+        }
+    }
+}
+
+/// Block of `block_read_typed`, whose words are decoded as `data_type` (`None`: U16 as `DeviceBlock`).
+/// `size` counts words as `DeviceBlock::size` does, and must be a multiple of the words of `data_type`.
+/// Only word blocks take a data type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedDeviceBlock {
+    pub access_type: AccessType,
+    pub start_device: Device,
+    pub size: usize,
+    #[serde(default)]
+    pub data_type: Option<DataType>,
+}
+
+impl TypedDeviceBlock {
+    pub const fn block(&self) -> DeviceBlock {
+        DeviceBlock { access_type: self.access_type, start_device: self.start_device, size: self.size }
+    }
+}
+
+impl From<DeviceBlock> for TypedDeviceBlock {
+    fn from(value: DeviceBlock) -> Self {
+        Self { access_type: value.access_type, start_device: value.start_device, size: value.size, data_type: None }
+    }
+}
+
 /// Data of the specified device.
 /// It is used for random-write request and all of read requests.
 ///
 /// Results of the read requets are unified in the form of this struct.
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
-#[cfg_attr(feature = "json-api", serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct DeviceData {
     pub device: Device,
     pub data: TypedData,
 }
 
+/// `DeviceData` with `Eq`, `Ord` and `Hash`, e.g. for a `HashSet` of read values.
+/// Values are compared by `TypedData::total_cmp`, so a NaN equals the same NaN.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct OrderedDeviceData(pub DeviceData);
+
+impl PartialEq for OrderedDeviceData {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for OrderedDeviceData {}
+
+impl PartialOrd for OrderedDeviceData {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedDeviceData {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.0.device.cmp(&other.0.device).then_with(|| self.0.data.total_cmp(&other.0.data))
+    }
+}
+
+impl core::hash::Hash for OrderedDeviceData {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.0.device.hash(state);
+        self.0.data.hash_total(state);
+    }
+}
+
+impl From<DeviceData> for OrderedDeviceData {
+    fn from(value: DeviceData) -> Self {
+        Self(value)
+    }
+}
+
+impl From<OrderedDeviceData> for DeviceData {
+    fn from(value: OrderedDeviceData) -> Self {
+        value.0
+    }
+}
+
 /// Blocked data used for block-write request.
 /// Multiple blocks are acceptable for block-write request.
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Serialize)]
-#[cfg_attr(feature = "json-api", serde(rename_all = "camelCase"))]
+#[serde(rename_all = "camelCase")]
 pub struct BlockedDeviceData<'a> {
     pub access_type: AccessType,
     pub start_device: Device,
     pub data: &'a [TypedData],
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
-#[cfg_attr(feature = "json-api", serde(rename_all = "camelCase"))]
+/// `BlockedDeviceData` owning its data, e.g. to receive a block write by the json-api or to keep prepared writes.
+/// See `SLMPClient::block_write_owned`.
+#[derive(Clone, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockedDeviceDataOwned {
+    pub access_type: AccessType,
+    pub start_device: Device,
+    pub data: Vec<TypedData>,
+}
+
+impl BlockedDeviceDataOwned {
+    pub fn as_blocked(&self) -> BlockedDeviceData<'_> {
+        BlockedDeviceData { access_type: self.access_type, start_device: self.start_device, data: &self.data }
+    }
+}
+
+impl<'a> From<&'a BlockedDeviceDataOwned> for BlockedDeviceData<'a> {
+    fn from(value: &'a BlockedDeviceDataOwned) -> Self {
+        value.as_blocked()
+    }
+}
+
+impl From<BlockedDeviceData<'_>> for BlockedDeviceDataOwned {
+    fn from(value: BlockedDeviceData<'_>) -> Self {
+        Self { access_type: value.access_type, start_device: value.start_device, data: value.data.to_vec() }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PLCData {
     pub socket_addr: SocketAddr,
+    /// Route of the connection, telling apart the connections to one address.
+    #[serde(default)]
+    pub route: Route,
     pub device_data: DeviceData,
 }
+
+/// `D100:U16`, `D200:String(10)`
+impl core::fmt::Display for TypedDevice {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}:{}", self.device, self.data_type)
+    }
+}
+
+impl core::str::FromStr for TypedDevice {
+    type Err = crate::io::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (device, data_type) = s.split_once(':')
+            .ok_or_else(|| crate::io::Error::new(crate::io::ErrorKind::InvalidInput, format!("Invalid typed device (expected e.g. D100:U16): {s}")))?;
+        Ok(Self { device: device.parse()?, data_type: data_type.parse()? })
+    }
+}
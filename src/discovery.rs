@@ -0,0 +1,265 @@
+//! Node Search (0x0E30) and IP Address Set (0x0E31) over UDP, to find SLMP devices on a line and give them addresses
+//! before any connection is set up.
+//!
+//! The frames are 4E binary frames to the own station (network 0, station 0xFF, I/O 0x03FF); the addresses and MAC addresses
+//! are carried in reversed byte order, as the other multi-byte fields of binary frames.
+//!
+//! ```rust,no_run
+//! # async fn run() -> std::io::Result<()> {
+//! use slmp::discovery;
+//!
+//! let nodes = discovery::search("192.168.3.1".parse().unwrap(), std::time::Duration::from_secs(1)).await?;
+//! for node in &nodes {
+//!     println!("{:02X?} {} (vendor {:04X}, model {:08X})", node.mac, node.ip, node.vendor_code, node.model_code);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tokio::net::UdpSocket;
+
+use crate::requests;
+use crate::{BUFSIZE, CPU, SLMP4EConnectionProps};
+
+/// UDP port the nodes listen to for Node Search and IP Address Set.
+pub const NODE_SEARCH_PORT: u16 = 45237;
+
+const COMMAND_NODE_SEARCH: u16 = 0x0E30;
+const COMMAND_IP_ADDRESS_SET: u16 = 0x0E31;
+const IPV4_ADDRESS_SIZE: u8 = 4;
+
+/// Protocol of the SLMP port of a node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum NodeProtocol {
+    Tcp,
+    Udp,
+    Unknown(u8),
+}
+
+impl From<u8> for NodeProtocol {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::Tcp,
+            2 => Self::Udp,
+            x => Self::Unknown(x),
+        }
+    }
+}
+
+/// A node answering Node Search.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeInfo {
+    pub mac: [u8; 6],
+    pub ip: Ipv4Addr,
+    pub subnet_mask: Ipv4Addr,
+    pub default_gateway: Ipv4Addr,
+    pub host_name: String,
+    pub vendor_code: u16,
+    pub model_code: u32,
+    pub equipment_version: u16,
+    /// Status of the node, 0 in normal operation.
+    pub status: u16,
+    /// SLMP port of the node.
+    pub port: u16,
+    pub protocol: NodeProtocol,
+}
+
+impl NodeInfo {
+    /// Connection props to the SLMP port of the node, with the default route to its own CPU.
+    pub fn connection_props(&self, cpu: CPU) -> SLMP4EConnectionProps {
+        SLMP4EConnectionProps { ip: self.ip.to_string(), port: self.port, ..search_props(cpu) }
+    }
+}
+
+/// Addresses given by IP Address Set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IpSettings {
+    pub ip: Ipv4Addr,
+    pub subnet_mask: Ipv4Addr,
+    pub default_gateway: Ipv4Addr,
+}
+
+/// Broadcast Node Search from `interface_addr` and collect the nodes answering within `timeout`, once per MAC address.
+pub async fn search(interface_addr: Ipv4Addr, timeout: Duration) -> std::io::Result<Vec<NodeInfo>> {
+    search_at(interface_addr, SocketAddrV4::new(Ipv4Addr::BROADCAST, NODE_SEARCH_PORT).into(), timeout).await
+}
+
+/// Same as `search` to `destination`, e.g. a directed broadcast address or a single node.
+pub async fn search_at(interface_addr: Ipv4Addr, destination: SocketAddr, timeout: Duration) -> std::io::Result<Vec<NodeInfo>> {
+    let socket = bind(interface_addr).await?;
+    let props = search_props(CPU::R);
+    socket.send_to(&build_search_request(&props, interface_addr)?, destination).await?;
+
+    let mut nodes: Vec<NodeInfo> = Vec::new();
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut buf = [0u8; BUFSIZE];
+    while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+        let (len, _) = received?;
+        // Other traffic on the port is skipped.
+        if let Ok(node) = parse_search_response(&props, &buf[..len])
+            && nodes.iter().all(|x| x.mac != node.mac)
+        {
+            nodes.push(node);
+        }
+    }
+    Ok(nodes)
+}
+
+/// Broadcast IP Address Set from `interface_addr` to the node of `mac`, waiting for its answer within `timeout`.
+/// It fails with `TimedOut` if the node does not answer, or with the end code it returns.
+pub async fn set_ip_address(interface_addr: Ipv4Addr, mac: [u8; 6], settings: IpSettings, timeout: Duration) -> std::io::Result<()> {
+    set_ip_address_at(interface_addr, SocketAddrV4::new(Ipv4Addr::BROADCAST, NODE_SEARCH_PORT).into(), mac, settings, timeout).await
+}
+
+/// Same as `set_ip_address` to `destination`.
+pub async fn set_ip_address_at(interface_addr: Ipv4Addr, destination: SocketAddr, mac: [u8; 6], settings: IpSettings, timeout: Duration) -> std::io::Result<()> {
+    let socket = bind(interface_addr).await?;
+    let props = search_props(CPU::R);
+    socket.send_to(&build_ip_address_set_request(&props, interface_addr, mac, &settings)?, destination).await?;
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut buf = [0u8; BUFSIZE];
+    loop {
+        let (len, _) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "IP Address Set Failed (Timeout)"))??;
+        // Other traffic on the port is skipped; an end code fails.
+        if len < requests::RECVFRAME_PREFIX_FIXED_LEN || requests::frame_serial_id(&buf[..len]) != props.serial_id {
+            continue;
+        }
+        return requests::response_data(&props, &buf[..len], props.serial_id).map(|_| ());
+    }
+}
+
+async fn bind(interface_addr: Ipv4Addr) -> std::io::Result<UdpSocket> {
+    let socket = UdpSocket::bind(SocketAddrV4::new(interface_addr, 0)).await?;
+    socket.set_broadcast(true)?;
+    Ok(socket)
+}
+
+fn search_props(cpu: CPU) -> SLMP4EConnectionProps {
+    SLMP4EConnectionProps {
+        ip: String::new(),
+        port: NODE_SEARCH_PORT,
+        cpu,
+        serial_id: 0x0001,
+        network_id: 0x00,
+        pc_id: 0xff,
+        io_id: 0x03ff,
+        area_id: 0x00,
+        cpu_timer: 0x0010,
+        remote_password: None,
+    }
+}
+
+/// Node Search request from `client_ip`. The client MAC address is sent as zeros.
+pub fn build_search_request(connection_props: &SLMP4EConnectionProps, client_ip: Ipv4Addr) -> std::io::Result<Vec<u8>> {
+    let mut cmd: Vec<u8> = command(COMMAND_NODE_SEARCH);
+    put_mac(&mut cmd, [0; 6]);
+    put_sized_ip(&mut cmd, client_ip);
+    requests::request_frame(connection_props, connection_props.serial_id, &cmd)
+}
+
+/// IP Address Set request from `client_ip` to the node of `mac`.
+pub fn build_ip_address_set_request(connection_props: &SLMP4EConnectionProps, client_ip: Ipv4Addr, mac: [u8; 6], settings: &IpSettings) -> std::io::Result<Vec<u8>> {
+    let mut cmd: Vec<u8> = command(COMMAND_IP_ADDRESS_SET);
+    put_mac(&mut cmd, [0; 6]);
+    put_sized_ip(&mut cmd, client_ip);
+    put_mac(&mut cmd, mac);
+    put_sized_ip(&mut cmd, settings.ip);
+    put_ip(&mut cmd, settings.subnet_mask);
+    put_ip(&mut cmd, settings.default_gateway);
+    requests::request_frame(connection_props, connection_props.serial_id, &cmd)
+}
+
+/// Node of a Node Search response. It fails with `InvalidData` for a malformed response, or with the end code.
+pub fn parse_search_response(connection_props: &SLMP4EConnectionProps, frame: &[u8]) -> std::io::Result<NodeInfo> {
+    let data = requests::response_data(connection_props, frame, connection_props.serial_id)?;
+    parse_node(data).ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "Received Invalid Node Search Data"))
+}
+
+/// The response data after the end code: the client MAC and IP address, and then the fields of the node.
+fn parse_node(data: &[u8]) -> Option<NodeInfo> {
+    let mut reader = Reader { data };
+    reader.mac()?;
+    reader.sized_ip()?;
+    let mac = reader.mac()?;
+    let ip = reader.sized_ip()?;
+    let subnet_mask = reader.ip()?;
+    let default_gateway = reader.ip()?;
+    let host_name_len: usize = reader.take(1)?[0] as usize;
+    let host_name: String = String::from_utf8_lossy(reader.take(host_name_len)?).into_owned();
+    let vendor_code = reader.u16()?;
+    let model_code = u32::from_le_bytes(reader.take(4)?.try_into().ok()?);
+    let equipment_version = reader.u16()?;
+    // Address and port of the client the node is connected with, if any.
+    reader.sized_ip()?;
+    reader.u16()?;
+    let status = reader.u16()?;
+    let port = reader.u16()?;
+    let protocol = NodeProtocol::from(reader.take(1)?[0]);
+
+    Some(NodeInfo { mac, ip, subnet_mask, default_gateway, host_name, vendor_code, model_code, equipment_version, status, port, protocol })
+}
+
+fn command(command: u16) -> Vec<u8> {
+    let mut cmd: Vec<u8> = Vec::with_capacity(48);
+    cmd.extend(command.to_le_bytes());
+    cmd.extend(0x0000u16.to_le_bytes());
+    cmd
+}
+
+fn put_mac(cmd: &mut Vec<u8>, mac: [u8; 6]) {
+    cmd.extend(mac.iter().rev());
+}
+
+fn put_ip(cmd: &mut Vec<u8>, ip: Ipv4Addr) {
+    cmd.extend(ip.octets().iter().rev());
+}
+
+fn put_sized_ip(cmd: &mut Vec<u8>, ip: Ipv4Addr) {
+    cmd.push(IPV4_ADDRESS_SIZE);
+    put_ip(cmd, ip);
+}
+
+/// Cursor over the response data. Every read returns None when the data is too short.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let (head, tail) = self.data.split_at_checked(len)?;
+        self.data = tail;
+        Some(head)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.take(2)?.try_into().ok()?))
+    }
+
+    fn mac(&mut self) -> Option<[u8; 6]> {
+        let mut mac: [u8; 6] = self.take(6)?.try_into().ok()?;
+        mac.reverse();
+        Some(mac)
+    }
+
+    fn ip(&mut self) -> Option<Ipv4Addr> {
+        let mut octets: [u8; 4] = self.take(4)?.try_into().ok()?;
+        octets.reverse();
+        Some(Ipv4Addr::from(octets))
+    }
+
+    /// An address after its size; only IPv4 (4 bytes) is taken.
+    fn sized_ip(&mut self) -> Option<Ipv4Addr> {
+        match self.take(1)?[0] {
+            IPV4_ADDRESS_SIZE => self.ip(),
+            _ => None,
+        }
+    }
+}
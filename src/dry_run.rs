@@ -0,0 +1,241 @@
+use crate::commands::device_access::read::SLMPMonitorReadCommand;
+use crate::commands::{clock, diagnostics, label_access, unit_control};
+use crate::frame::SlmpCommandPacket;
+use crate::read_plan::ReadPlan;
+use crate::requests;
+use crate::snapshot::{self, SnapshotRead};
+use crate::verify::Verification;
+use crate::{BlockedDeviceData, CPU, DataType, Device, DeviceBlock, DeviceData, DeviceLimits, DeviceSnapshot, PLCDateTime, ReadCoalescing, SLMP4EConnectionProps, SlmpReadable, SlmpWritable, TypedData, TypedDevice, WordOrder};
+
+/// Request frames of a `DryRun` call, in the order the client would send them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DryRunFrames {
+    pub frames: Vec<Vec<u8>>,
+}
+
+impl DryRunFrames {
+    /// The frames decoded by `slmp::frame`.
+    pub fn packets(&self) -> Vec<SlmpCommandPacket> {
+        self.frames.iter().filter_map(|x| SlmpCommandPacket::try_from(&x[..]).ok()).collect()
+    }
+}
+
+impl std::fmt::Display for DryRunFrames {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, packet) in self.packets().iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "Frame {} of {}", i + 1, self.frames.len())?;
+            write!(f, "{packet}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Frames the requests of `SLMPClient` would send, built with its CPU, device limits and word order without touching the link
+/// (see `SLMPClient::dry_run`). The frames are numbered from the next serial No. of the client.
+///
+/// Requests whose frames depend on a response (file access, `clear_error` of Q/L CPUs, `modify_word`,
+/// and the retries of `read_labels`) are not covered.
+#[derive(Clone, Debug)]
+pub struct DryRun {
+    connection_props: SLMP4EConnectionProps,
+    device_limits: DeviceLimits,
+    word_order: WordOrder,
+    read_coalescing: ReadCoalescing,
+}
+
+impl DryRun {
+    pub(crate) fn new(connection_props: SLMP4EConnectionProps, serial_id: u16, device_limits: DeviceLimits, word_order: WordOrder, read_coalescing: ReadCoalescing) -> Self {
+        Self { connection_props: SLMP4EConnectionProps { serial_id, ..connection_props }, device_limits, word_order, read_coalescing }
+    }
+
+    fn cpu(&self) -> &CPU {
+        &self.connection_props.cpu
+    }
+
+    fn frames<I, C>(&self, commands: I) -> std::io::Result<DryRunFrames>
+    where I: IntoIterator<Item = C>, C: AsRef<[u8]>
+    {
+        let frames = commands.into_iter().enumerate()
+            .map(|(i, cmd)| requests::request_frame(&self.connection_props, self.connection_props.serial_id.wrapping_add(i as u16), cmd.as_ref()))
+            .collect::<std::io::Result<Vec<Vec<u8>>>>()?;
+        Ok(DryRunFrames { frames })
+    }
+
+    /* Unit Control */
+
+    pub fn run_cpu(&self) -> std::io::Result<DryRunFrames> {
+        self.frames([unit_control::remote_run()])
+    }
+
+    pub fn stop_cpu(&self) -> std::io::Result<DryRunFrames> {
+        self.frames([unit_control::remote_stop()])
+    }
+
+    pub fn pause_cpu(&self) -> std::io::Result<DryRunFrames> {
+        self.frames([unit_control::remote_pause()])
+    }
+
+    pub fn clear_latch(&self) -> std::io::Result<DryRunFrames> {
+        self.frames([unit_control::remote_latch_clear()])
+    }
+
+    pub fn reset_cpu(&self) -> std::io::Result<DryRunFrames> {
+        self.frames([unit_control::remote_reset()])
+    }
+
+    pub fn get_cpu_type(&self) -> std::io::Result<DryRunFrames> {
+        self.frames([unit_control::get_cpu_type()])
+    }
+
+    pub fn lock_cpu(&self, password: &str) -> std::io::Result<DryRunFrames> {
+        self.frames([unit_control::lock_cpu(self.cpu(), password)?])
+    }
+
+    pub fn unlock_cpu(&self, password: &str) -> std::io::Result<DryRunFrames> {
+        self.frames([unit_control::unlock_cpu(self.cpu(), password)?])
+    }
+
+    pub fn echo(&self) -> std::io::Result<DryRunFrames> {
+        self.frames([unit_control::echo()])
+    }
+
+    /* Diagnostics and Clock */
+
+    pub fn read_error_history(&self) -> std::io::Result<DryRunFrames> {
+        self.frames([diagnostics::read_error_info(self.cpu())])
+    }
+
+    pub fn read_clock(&self) -> std::io::Result<DryRunFrames> {
+        self.frames([clock::read_clock(self.cpu())])
+    }
+
+    pub fn set_clock(&self, datetime: PLCDateTime) -> std::io::Result<DryRunFrames> {
+        let cmd = clock::write_clock(self.cpu(), &datetime)?;
+        self.frames([cmd, clock::set_clock_request(self.cpu(), false), clock::set_clock_request(self.cpu(), true)])
+    }
+
+    /* Label Access */
+
+    pub fn read_labels(&self, labels: &[&str]) -> std::io::Result<DryRunFrames> {
+        self.frames([label_access::read_labels(labels)?])
+    }
+
+    pub fn write_labels(&self, labels: &[(&str, TypedData)]) -> std::io::Result<DryRunFrames> {
+        self.frames([label_access::write_labels(labels)?])
+    }
+
+    /* Device Access */
+
+    pub fn bulk_write(&self, start_device: Device, data: &[TypedData]) -> std::io::Result<DryRunFrames> {
+        self.frames([requests::bulk_write(self.cpu(), &self.device_limits, start_device, data, self.word_order)?])
+    }
+
+    /// Bit and word devices are written in separate requests.
+    pub fn random_write(&self, data: &[DeviceData]) -> std::io::Result<DryRunFrames> {
+        self.frames(requests::random_write(self.cpu(), &self.device_limits, data, self.word_order)?)
+    }
+
+    pub fn block_write<'a>(&self, data: &'a [BlockedDeviceData<'a>]) -> std::io::Result<DryRunFrames> {
+        self.frames([requests::block_write(self.cpu(), &self.device_limits, data, self.word_order)?])
+    }
+
+    /// The write followed by the reads back.
+    pub fn bulk_write_verified(&self, start_device: Device, data: &[TypedData]) -> std::io::Result<DryRunFrames> {
+        let verification = Verification::bulk(self.cpu(), &self.device_limits, start_device, data, self.word_order)?;
+        let cmd = requests::bulk_write(self.cpu(), &self.device_limits, start_device, data, self.word_order)?;
+        self.frames(std::iter::once(&cmd).chain(verification.commands()))
+    }
+
+    pub fn random_write_verified(&self, data: &[DeviceData]) -> std::io::Result<DryRunFrames> {
+        let verification = Verification::random(self.cpu(), &self.device_limits, data, self.word_order)?;
+        let commands = requests::random_write(self.cpu(), &self.device_limits, data, self.word_order)?;
+        self.frames(commands.iter().chain(verification.commands()))
+    }
+
+    pub fn block_write_verified<'a>(&self, data: &'a [BlockedDeviceData<'a>]) -> std::io::Result<DryRunFrames> {
+        let verification = Verification::block(self.cpu(), &self.device_limits, data, self.word_order)?;
+        let cmd = requests::block_write(self.cpu(), &self.device_limits, data, self.word_order)?;
+        self.frames(std::iter::once(&cmd).chain(verification.commands()))
+    }
+
+    /// A bulk write per chunk of the write limit.
+    pub fn fill(&self, start_device: Device, count: usize, value: TypedData) -> std::io::Result<DryRunFrames> {
+        let request = requests::Fill::new(&self.connection_props.cpu, &self.device_limits, start_device, count, &value, self.word_order)?;
+        self.frames(request.commands(*self.cpu()).map(|(_, cmd)| cmd))
+    }
+
+    pub fn clear_bits(&self, start_device: Device, count: usize) -> std::io::Result<DryRunFrames> {
+        self.fill(start_device, count, TypedData::Bool(false))
+    }
+
+    pub fn bulk_read(&self, start_device: Device, device_num: usize, data_type: DataType) -> std::io::Result<DryRunFrames> {
+        let request = requests::BulkRead { start_device, device_num, data_type };
+        self.frames([request.command(self.cpu(), &self.device_limits)?])
+    }
+
+    pub fn random_read(&self, devices: &[TypedDevice]) -> std::io::Result<DryRunFrames> {
+        self.frames([requests::random_read(self.cpu(), &self.device_limits, devices)?.0])
+    }
+
+    pub fn optimized_read(&self, devices: &[TypedDevice]) -> std::io::Result<DryRunFrames> {
+        let plan = ReadPlan::new(&self.connection_props.cpu, &self.device_limits, &self.read_coalescing, devices)?;
+        self.frames(plan.commands(self.cpu(), &self.device_limits)?)
+    }
+
+    pub fn block_read(&self, device_blocks: &[DeviceBlock]) -> std::io::Result<DryRunFrames> {
+        let request = requests::BlockRead::new(self.cpu(), &self.device_limits, device_blocks)?;
+        self.frames([request.command(self.cpu())])
+    }
+
+    pub fn bulk_read_words(&self, start_device: Device, word_num: usize) -> std::io::Result<DryRunFrames> {
+        let request = requests::WordRead { start_device, word_num };
+        self.frames([request.command(self.cpu(), &self.device_limits)?])
+    }
+
+    pub fn bulk_write_words(&self, start_device: Device, data: &[u16]) -> std::io::Result<DryRunFrames> {
+        self.frames([requests::bulk_write_words(self.cpu(), &self.device_limits, start_device, data)?])
+    }
+
+    pub fn read_string(&self, device: Device, word_len: u8) -> std::io::Result<DryRunFrames> {
+        let request = requests::StringRead { device, word_len };
+        self.frames([request.command(self.cpu(), &self.device_limits)?])
+    }
+
+    pub fn write_string(&self, device: Device, text: &str, word_len: u8) -> std::io::Result<DryRunFrames> {
+        self.frames([requests::string_write(self.cpu(), &self.device_limits, device, text, word_len)?])
+    }
+
+    pub fn read_struct<S: SlmpReadable>(&self, start_device: Device) -> std::io::Result<DryRunFrames> {
+        let request = requests::StructRead { start_device, fields: S::FIELDS };
+        self.frames([request.command(self.cpu(), &self.device_limits)?])
+    }
+
+    pub fn write_struct<S: SlmpWritable>(&self, start_device: Device, value: &S) -> std::io::Result<DryRunFrames> {
+        self.frames([requests::struct_write(self.cpu(), &self.device_limits, start_device, S::FIELDS, value.to_fields(), self.word_order)?])
+    }
+
+    pub fn snapshot(&self, blocks: &[DeviceBlock]) -> std::io::Result<DryRunFrames> {
+        let request = SnapshotRead::new(self.cpu(), &self.device_limits, blocks)?;
+        self.frames(request.commands())
+    }
+
+    pub fn restore(&self, snapshot: &DeviceSnapshot) -> std::io::Result<DryRunFrames> {
+        self.frames(snapshot::restore(self.cpu(), &self.device_limits, snapshot)?)
+    }
+
+    pub fn monitor_register(&self, devices: &[TypedDevice]) -> std::io::Result<DryRunFrames> {
+        self.frames([requests::monitor_register(self.cpu(), &self.device_limits, devices)?.0])
+    }
+
+    pub fn monitor_clear(&self) -> std::io::Result<DryRunFrames> {
+        self.frames([requests::monitor_clear(self.cpu())])
+    }
+
+    pub fn monitor_read(&self) -> std::io::Result<DryRunFrames> {
+        const COMMAND: SLMPMonitorReadCommand = SLMPMonitorReadCommand::new();
+        self.frames([&COMMAND[..]])
+    }
+}
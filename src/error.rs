@@ -0,0 +1,99 @@
+/// End code returned from the SLMP server when a request is rejected.
+///
+/// Requests fail with `std::io::Error` (`slmp::io::Error` without std) carrying this value,
+/// so that the specific end code can be checked with `SLMPEndCode::from_io_error`.
+/// The error kind is `NotFound`/`AlreadyExists` for file end codes and `InvalidData` otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SLMPEndCode(pub u16);
+
+impl SLMPEndCode {
+    pub const WRONG_COMMAND: Self = Self(0xC059);
+    pub const WRONG_FORMAT: Self = Self(0xC05C);
+    /// Monitor read (0x0802) without devices registered by monitor register (0x0801):
+    /// "Monitor registration is not performed" in the end code list of the Ethernet interface manuals
+    /// (e.g. Q Corresponding Ethernet Interface Module User's Manual (Basic), SH-080009).
+    pub const MONITOR_NOT_REGISTERED: Self = Self(0xC05D);
+    pub const WRONG_LENGTH: Self = Self(0xC061);
+    pub const BUSY: Self = Self(0xCEE0);
+    pub const EXCEED_REQ_LENGTH: Self = Self(0xCEE1);
+    pub const EXCEED_RESP_LENGTH: Self = Self(0xCEE2);
+    pub const SERVER_NOT_FOUND: Self = Self(0xCF10);
+    pub const WRONG_CONFIG_ITEM: Self = Self(0xCF20);
+    pub const PRM_ID_NOT_FOUND: Self = Self(0xCF30);
+    pub const NOT_START_EXCLUSIVE_WRITE: Self = Self(0xCF31);
+    pub const RELAY_FAILURE: Self = Self(0xCF70);
+    pub const TIMEOUT_ERROR: Self = Self(0xCF71);
+    pub const FILE_NOT_FOUND: Self = Self(0x4030);
+    pub const FILE_ALREADY_EXISTS: Self = Self(0x4066);
+
+    pub const fn name(&self) -> &'static str {
+        match *self {
+            Self::WRONG_COMMAND => "WrongCommand",
+            Self::WRONG_FORMAT => "WrongFormat",
+            Self::MONITOR_NOT_REGISTERED => "MonitorNotRegistered",
+            Self::WRONG_LENGTH => "WrongLength",
+            Self::BUSY => "Busy",
+            Self::EXCEED_REQ_LENGTH => "ExceedReqLength",
+            Self::EXCEED_RESP_LENGTH => "ExceedRespLength",
+            Self::SERVER_NOT_FOUND => "ServerNotFound",
+            Self::WRONG_CONFIG_ITEM => "WrongConfigItem",
+            Self::PRM_ID_NOT_FOUND => "PrmIDNotFound",
+            Self::NOT_START_EXCLUSIVE_WRITE => "NotStartExclusiveWrite",
+            Self::RELAY_FAILURE => "RelayFailure",
+            Self::TIMEOUT_ERROR => "TimeoutError",
+            Self::FILE_NOT_FOUND => "FileNotFound",
+            Self::FILE_ALREADY_EXISTS => "FileAlreadyExists",
+            _ => "Unknown Error",
+        }
+    }
+
+    /// Take the end code out of an error returned from `SLMPClient`.
+    /// It returns `None` if the error was not caused by an end code.
+    #[cfg(feature = "std")]
+    pub fn from_io_error(error: &std::io::Error) -> Option<Self> {
+        if let Some(retries) = crate::RetriesExhausted::from_io_error(error) {
+            return Self::from_io_error(&retries.last_error);
+        }
+        if let Some(relay) = crate::RelayError::from_io_error(error) {
+            return Some(relay.end_code);
+        }
+        error.get_ref()?.downcast_ref::<Self>().copied()
+    }
+
+    /// Take the end code out of an error returned from `SLMPClient`.
+    /// It returns `None` if the error was not caused by an end code.
+    #[cfg(not(feature = "std"))]
+    pub fn from_io_error(error: &crate::io::Error) -> Option<Self> {
+        match error {
+            crate::io::Error::EndCode(end_code) => Some(*end_code),
+            _ => None,
+        }
+    }
+}
+
+impl core::fmt::Display for SLMPEndCode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "SLMP Returns Error: {} (0x{:X})", self.name(), self.0)
+    }
+}
+
+impl core::error::Error for SLMPEndCode {}
+
+#[cfg(feature = "std")]
+impl From<SLMPEndCode> for std::io::Error {
+    fn from(value: SLMPEndCode) -> Self {
+        let kind = match value {
+            SLMPEndCode::FILE_NOT_FOUND => std::io::ErrorKind::NotFound,
+            SLMPEndCode::FILE_ALREADY_EXISTS => std::io::ErrorKind::AlreadyExists,
+            _ => std::io::ErrorKind::InvalidData,
+        };
+        std::io::Error::new(kind, value)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl From<SLMPEndCode> for crate::io::Error {
+    fn from(value: SLMPEndCode) -> Self {
+        Self::EndCode(value)
+    }
+}
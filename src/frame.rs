@@ -0,0 +1,381 @@
+//! Decoder of SLMP 4E binary frames for logging and protocol tests.
+//!
+//! ```rust
+//! use slmp::frame::SlmpCommandPacket;
+//!
+//! let data: [u8; 27] = [
+//!     0x54, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0x03, 0x00, 0x0e, 0x00,
+//!     0x10, 0x00, 0x01, 0x04, 0x02, 0x00, 0x64, 0x00, 0x00, 0x00, 0xa8, 0x00, 0x0a, 0x00,
+//! ];
+//! let packet = SlmpCommandPacket::try_from(&data[..]).unwrap();
+//! assert_eq!(packet.command_name(), "BulkRead");
+//! println!("{packet}");
+//! ```
+
+use crate::prelude::*;
+use crate::{Device, DeviceType, SLMPEndCode};
+
+const FIXED_FRAME_LEN: usize = 13;
+
+#[derive(Debug)]
+pub struct SlmpParseError(String);
+
+impl core::fmt::Display for SlmpParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "SLMP parse error: {}", self.0)
+    }
+}
+
+impl core::error::Error for SlmpParseError {}
+
+/// Name of a command code. It returns "Unknown" for commands not defined in SLMP.
+pub const fn command_name(command: u16) -> &'static str {
+    match command {
+        0x0401 => "BulkRead",
+        0x1401 => "BulkWrite",
+        0x0403 => "RandomRead",
+        0x1402 => "RandomWrite",
+        0x0406 => "BlockRead",
+        0x1406 => "BlockWrite",
+        0x0801 => "MonitorRegister",
+        0x0802 => "MonitorRead",
+        0x041A => "ArrayLabelRead",
+        0x141A => "ArrayLabelWrite",
+        0x041C => "RandomLabelRead",
+        0x141B => "RandomLabelWrite",
+        0x0601 => "BufferMemoryRead",
+        0x1601 => "BufferMemoryWrite",
+        0x0613 => "IntelligentModuleRead",
+        0x1613 => "IntelligentModuleWrite",
+        0x1001 => "RemoteRun",
+        0x1002 => "RemoteStop",
+        0x1003 => "RemotePause",
+        0x1005 => "RemoteLatchClear",
+        0x1006 => "RemoteReset",
+        0x0101 => "ReadCPUType",
+        0x1630 => "RemoteUnlock",
+        0x1631 => "RemoteLock",
+        0x0619 => "SelfTest",
+        0x1617 => "ClearError",
+        0x1810 => "ReadDirectory",
+        0x1811 => "SearchDirectory",
+        0x1820 => "NewFile",
+        0x1822 => "DeleteFile",
+        0x1824 => "CopyFile",
+        0x1825 => "ChangeFileAttribute",
+        0x1826 => "ChangeFileDate",
+        0x1827 => "OpenFile",
+        0x1828 => "ReadFile",
+        0x1829 => "WriteFile",
+        0x182A => "CloseFile",
+        0x0E30 => "NodeSearch",
+        0x0E31 => "IPAddressSet",
+        _ => "Unknown",
+    }
+}
+
+/// Device notation used in GX Works. X, Y, B, W, SB, SW, DX and DY are numbered in hexadecimal.
+/// Device specified in the data section of a device access command.
+/// `points` is the number of points from `device` (in words for word units, in bits for bit units).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeviceSpecification {
+    pub device: Device,
+    pub points: usize,
+}
+
+impl core::fmt::Display for DeviceSpecification {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} ({} points)", self.device, self.points)
+    }
+}
+
+/// Cursor over the data section. Every read returns None when the data is too short.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+        let ret = self.data.get(self.pos..self.pos + n)?;
+        self.pos += n;
+        Some(ret)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|x| x[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2).map(|x| u16::from_le_bytes([x[0], x[1]]))
+    }
+
+    fn device(&mut self, r_format: bool) -> Option<Device> {
+        let (address, code) = if r_format {
+            let x = self.bytes(6)?;
+            (u32::from_le_bytes([x[0], x[1], x[2], x[3]]), x[4])
+        } else {
+            let x = self.bytes(4)?;
+            (u32::from_le_bytes([x[0], x[1], x[2], 0]), x[3])
+        };
+        Some(Device { device_type: DeviceType::from_code(code)?, address: address as usize })
+    }
+}
+
+pub struct SlmpCommandPacket {
+    pub request_code: u16,
+    pub serial_id: u16,
+    pub network_id: u8,
+    pub pc_id: u8,
+    pub io_id: u16,
+    pub area_id: u8,
+    pub data_len: u16,
+    pub cpu_timer: u16,
+    pub command: u16,
+    pub subcommand: u16,
+    pub data: Vec<u8>,
+}
+
+impl SlmpCommandPacket {
+    pub const fn command_name(&self) -> &'static str {
+        command_name(self.command)
+    }
+
+    /// Decode the devices specified in a device access command.
+    /// It returns None for other commands or when the data section cannot be decoded.
+    pub fn devices(&self) -> Option<Vec<DeviceSpecification>> {
+        let bit_unit: bool = self.subcommand & 0x0001 != 0;
+        let r_format: bool = self.subcommand & 0x0002 != 0;
+        let mut reader = Reader { data: &self.data, pos: 0 };
+        let mut ret: Vec<DeviceSpecification> = Vec::new();
+
+        match self.command {
+            // Bulk read/write: device + points (+ data)
+            0x0401 | 0x1401 => {
+                let device = reader.device(r_format)?;
+                let points: usize = reader.u16()? as usize;
+                ret.push(DeviceSpecification { device, points });
+            },
+            // Random read, monitor register: single-word points + double-word points + devices
+            0x0403 | 0x0801 => {
+                let single_word_points: usize = reader.u8()? as usize;
+                let double_word_points: usize = reader.u8()? as usize;
+                for i in 0..(single_word_points + double_word_points) {
+                    let points: usize = if i < single_word_points { 1 } else { 2 };
+                    ret.push(DeviceSpecification { device: reader.device(r_format)?, points });
+                }
+            },
+            // Random write: (device + value) for each point
+            0x1402 if bit_unit => {
+                let bit_points: usize = reader.u8()? as usize;
+                for _ in 0..bit_points {
+                    ret.push(DeviceSpecification { device: reader.device(r_format)?, points: 1 });
+                    reader.bytes(if r_format { 2 } else { 1 })?;
+                }
+            },
+            0x1402 => {
+                let single_word_points: usize = reader.u8()? as usize;
+                let double_word_points: usize = reader.u8()? as usize;
+                for i in 0..(single_word_points + double_word_points) {
+                    let points: usize = if i < single_word_points { 1 } else { 2 };
+                    ret.push(DeviceSpecification { device: reader.device(r_format)?, points });
+                    reader.bytes(2 * points)?;
+                }
+            },
+            // Block read/write: word blocks + bit blocks, each of device + size in words (+ data)
+            0x0406 | 0x1406 => {
+                let word_blocks: usize = reader.u8()? as usize;
+                let bit_blocks: usize = reader.u8()? as usize;
+                for i in 0..(word_blocks + bit_blocks) {
+                    let device = reader.device(r_format)?;
+                    let size: usize = reader.u16()? as usize;
+                    let points: usize = if i < word_blocks { size } else { 16 * size };
+                    ret.push(DeviceSpecification { device, points });
+                    if self.command == 0x1406 {
+                        reader.bytes(2 * size)?;
+                    }
+                }
+            },
+            _ => return None,
+        }
+
+        Some(ret)
+    }
+}
+
+impl TryFrom<&[u8]> for SlmpCommandPacket {
+
+    type Error = SlmpParseError;
+
+    fn try_from(data: &[u8]) -> Result<Self, SlmpParseError> {
+
+        const CMDFRAME_PREFIX_FIXED_LEN: usize = 19;
+
+        let packet_len = data.len();
+
+        if packet_len < CMDFRAME_PREFIX_FIXED_LEN {
+            return Err(SlmpParseError("Data too short for SLMP header".to_string()));
+        }
+
+        let data_len = u16::from_le_bytes([data[11], data[12]]);
+
+        if data_len as usize != packet_len - FIXED_FRAME_LEN {
+            return Err(SlmpParseError("Received Invalid Data Frame".to_string()));
+        }
+
+        let request_code = u16::from_le_bytes([data[0], data[1]]);
+        let serial_id = u16::from_le_bytes([data[2], data[3]]);
+        let network_id = data[6];
+        let pc_id = data[7];
+        let io_id = u16::from_le_bytes([data[8], data[9]]);
+        let area_id = data[10];
+        let cpu_timer = u16::from_le_bytes([data[13], data[14]]);
+        let command: u16 = u16::from_le_bytes([data[15], data[16]]);
+        let subcommand: u16 = u16::from_le_bytes([data[17], data[18]]);
+        let data = data[CMDFRAME_PREFIX_FIXED_LEN..].to_vec();
+
+        Ok(SlmpCommandPacket {
+            request_code,
+            serial_id,
+            network_id,
+            pc_id,
+            io_id,
+            area_id,
+            data_len,
+            cpu_timer,
+            command,
+            subcommand,
+            data,
+        })
+    }
+}
+
+/// The remote password of unlock/lock requests (0x1630/0x1631) is shown masked.
+impl core::fmt::Display for SlmpCommandPacket {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut data: Vec<u8> = self.data.clone();
+        crate::requests::mask_password(self.command, &mut data);
+
+        write!(f,
+            "\
+                Request_code: 0x{:04X}\n\
+                Serial ID: 0x{:04X}\n\
+                Network ID: 0x{:02X}\n\
+                PC ID: 0x{:X}\n\
+                Area ID: 0x{:X}\n\
+                IO ID: 0x{:04X}\n\
+                Data Length: 0x{:04X}\n\
+                CPU Timer: 0x{:04X}\n\
+                Command: 0x{:04X} ({})\n\
+                Subcommand: 0x{:04X}\n\
+                Data: {:02X?}\
+            ",
+            self.request_code,
+            self.serial_id,
+            self.network_id,
+            self.pc_id,
+            self.area_id,
+            self.io_id,
+            self.data_len,
+            self.cpu_timer,
+            self.command, self.command_name(),
+            self.subcommand,
+            data
+        )?;
+
+        if let Some(devices) = self.devices() {
+            write!(f, "\nDevices:")?;
+            for device in devices {
+                write!(f, "\n  {device}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct SlmpReturnPacket {
+    pub request_code: u16,
+    pub serial_id: u16,
+    pub network_id: u8,
+    pub pc_id: u8,
+    pub io_id: u16,
+    pub area_id: u8,
+    pub data_len: u16,
+    pub error: u16,
+    pub data: Vec<u8>,
+}
+
+impl TryFrom<&[u8]> for SlmpReturnPacket {
+
+    type Error = SlmpParseError;
+
+    fn try_from(data: &[u8]) -> Result<Self, SlmpParseError> {
+
+        const RECVFRAME_PREFIX_FIXED_LEN: usize = 15;
+
+        let packet_len = data.len();
+
+        if packet_len < RECVFRAME_PREFIX_FIXED_LEN {
+            return Err(SlmpParseError("Data too short for SLMP header".to_string()));
+        }
+
+        let data_len = u16::from_le_bytes([data[11], data[12]]);
+        if data_len as usize != packet_len - FIXED_FRAME_LEN {
+            return Err(SlmpParseError("Received Invalid Data Frame".to_string()));
+        }
+
+        let request_code = u16::from_le_bytes([data[0], data[1]]);
+        let serial_id = u16::from_le_bytes([data[2], data[3]]);
+        let network_id = data[6];
+        let pc_id = data[7];
+        let io_id = u16::from_le_bytes([data[8], data[9]]);
+        let area_id = data[10];
+        let error = u16::from_le_bytes([data[13], data[14]]);
+        let data = data[RECVFRAME_PREFIX_FIXED_LEN..].to_vec();
+
+        Ok(SlmpReturnPacket {
+            request_code,
+            serial_id,
+            network_id,
+            pc_id,
+            io_id,
+            area_id,
+            data_len,
+            error,
+            data,
+        })
+    }
+}
+
+impl core::fmt::Display for SlmpReturnPacket {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let error_name: &str = match self.error {
+            0 => "Success",
+            code => SLMPEndCode(code).name(),
+        };
+
+        write!(f,
+            "\
+                Request_code: 0x{:04X}\n\
+                Serial ID: 0x{:04X}\n\
+                Network ID: 0x{:02X}\n\
+                PC ID: 0x{:X}\n\
+                Area ID: 0x{:X}\n\
+                IO ID: 0x{:04X}\n\
+                Data Length: 0x{:04X}\n\
+                Error: 0x{:04X} ({})\n\
+                Data: {:02X?}\
+            ",
+            self.request_code,
+            self.serial_id,
+            self.network_id,
+            self.pc_id,
+            self.area_id,
+            self.io_id,
+            self.data_len,
+            self.error, error_name,
+            self.data
+        )
+    }
+}
@@ -0,0 +1,275 @@
+//! A-compatible 1E frames (binary) of MELSEC-A CPUs (`CPU::A`), which do not speak the 4E frame.
+//!
+//! The clients build 4E frames for every CPU. For `CPU::A`, the link translates each request into a 1E frame
+//! and each 1E response back into a 4E response, so that serial No. matching, end code checks and parsing stay as they are.
+//! A 1E frame carries neither a serial No. nor a length: the responses come in the order of the requests,
+//! and the length of a response follows from its request.
+//!
+//! Request: command, PC No., monitoring timer (2 bytes), request data.
+//! Response: command | 0x80, completion code, response data (0x5B is followed by an abnormal code instead).
+
+use crate::prelude::*;
+use alloc::collections::VecDeque;
+
+use crate::requests::SUBHEADER_LEN;
+use crate::{DeviceType, SLMPEndCode};
+
+const BATCH_READ_BIT: u8 = 0x00;
+const BATCH_READ_WORD: u8 = 0x01;
+const BATCH_WRITE_BIT: u8 = 0x02;
+const BATCH_WRITE_WORD: u8 = 0x03;
+const REMOTE_RUN: u8 = 0x13;
+const REMOTE_STOP: u8 = 0x14;
+const READ_MODEL: u8 = 0x15;
+const LOOPBACK: u8 = 0x16;
+
+const RESPONSE_FLAG: u8 = 0x80;
+/// Completion code followed by an abnormal code.
+const COMPLETION_ABNORMAL: u8 = 0x5B;
+/// Loopback data of the 1E frame: 1~254 bytes.
+const LOOPBACK_MAX_LEN: usize = 254;
+/// Model name in the 4E response of `get_cpu_type`; the 1E frame reports a model code only.
+const MODEL_NAME: &[u8; 16] = b"ACPU            ";
+
+/// Device code of `device_type` in the 1E frame (two ASCII characters, e.g. "D "), or `None` for devices MELSEC-A does not have.
+/// L and S are numbered within M.
+pub(crate) const fn device_code(device_type: DeviceType) -> Option<u16> {
+    match device_type {
+        DeviceType::X => Some(0x5820),
+        DeviceType::Y => Some(0x5920),
+        DeviceType::M => Some(0x4D20),
+        DeviceType::F => Some(0x4620),
+        DeviceType::B => Some(0x4220),
+        DeviceType::D => Some(0x4420),
+        DeviceType::W => Some(0x5720),
+        DeviceType::R => Some(0x5220),
+        DeviceType::TS => Some(0x5453),
+        DeviceType::TC => Some(0x5443),
+        DeviceType::TN => Some(0x544E),
+        DeviceType::CS => Some(0x4353),
+        DeviceType::CC => Some(0x4343),
+        DeviceType::CN => Some(0x434E),
+        _ => None,
+    }
+}
+
+/// The device type of a 1E device code.
+#[cfg(feature = "mock-server")]
+pub(crate) const fn device_type(code: u16) -> Option<DeviceType> {
+    match code {
+        0x5820 => Some(DeviceType::X),
+        0x5920 => Some(DeviceType::Y),
+        0x4D20 => Some(DeviceType::M),
+        0x4620 => Some(DeviceType::F),
+        0x4220 => Some(DeviceType::B),
+        0x4420 => Some(DeviceType::D),
+        0x5720 => Some(DeviceType::W),
+        0x5220 => Some(DeviceType::R),
+        0x5453 => Some(DeviceType::TS),
+        0x5443 => Some(DeviceType::TC),
+        0x544E => Some(DeviceType::TN),
+        0x4353 => Some(DeviceType::CS),
+        0x4343 => Some(DeviceType::CC),
+        0x434E => Some(DeviceType::CN),
+        _ => None,
+    }
+}
+
+/// End code standing for a 1E completion code: the completion code itself, or 0x5B00 + the abnormal code.
+pub(crate) const fn end_code(completion_code: u8, abnormal_code: u8) -> SLMPEndCode {
+    match completion_code {
+        COMPLETION_ABNORMAL => SLMPEndCode(u16::from_be_bytes([COMPLETION_ABNORMAL, abnormal_code])),
+        code => SLMPEndCode(code as u16),
+    }
+}
+
+fn unsupported(msg: String) -> crate::io::Error {
+    crate::io::Error::new(crate::io::ErrorKind::Unsupported, msg)
+}
+
+fn invalid_input(msg: String) -> crate::io::Error {
+    crate::io::Error::new(crate::io::ErrorKind::InvalidInput, msg)
+}
+
+fn invalid_data(msg: &str) -> crate::io::Error {
+    crate::io::Error::new(crate::io::ErrorKind::InvalidData, msg)
+}
+
+/// What the response data of a request turns into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ResponseData {
+    /// Read data, the same in both frames.
+    Read(usize),
+    None,
+    /// Model code (1 byte) into the model name and code.
+    Model,
+    /// Loopback data with a 1-byte length into a 2-byte length.
+    Loopback(usize),
+}
+
+impl ResponseData {
+    const fn len(&self) -> usize {
+        match *self {
+            Self::Read(len) => len,
+            Self::None => 0,
+            Self::Model => 1,
+            Self::Loopback(len) => 1 + len,
+        }
+    }
+}
+
+/// A request sent as a 1E frame, waiting for its response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Expected {
+    /// 4E response header up to the area ID (subheader, serial No. and route of the request).
+    header: [u8; 11],
+    command: u8,
+    data: ResponseData,
+}
+
+/// Translation of the 4E frames of a client into 1E frames, for `CPU::A`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Frames1E {
+    /// Requests sent, oldest first.
+    expected: VecDeque<Expected>,
+}
+
+impl Frames1E {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget the requests sent, e.g. on a new connection.
+    pub(crate) fn clear(&mut self) {
+        self.expected.clear();
+    }
+
+    /// The 1E frame of a 4E request frame. It fails with `Unsupported` for the requests the 1E frame does not have.
+    /// The response is expected after the responses of the requests translated before.
+    pub(crate) fn request(&mut self, frame: &[u8]) -> crate::io::Result<Vec<u8>> {
+        let (request, expected) = translate_request(frame)?;
+        self.expected.push_back(expected);
+        Ok(request)
+    }
+
+    /// Length of the first 1E response in `pending`, or `None` until it can be told.
+    pub(crate) fn frame_len(&self, pending: &[u8]) -> crate::io::Result<Option<usize>> {
+        let Some(&[subheader, completion_code]) = pending.get(..2) else {
+            return Ok(None);
+        };
+        let expected = self.expected.front().ok_or_else(|| invalid_data("Received a 1E Frame without a Request"))?;
+        if subheader != expected.command | RESPONSE_FLAG {
+            return Err(invalid_data("Received Invalid Response Data"));
+        }
+        Ok(Some(match completion_code {
+            0 => 2 + expected.data.len(),
+            COMPLETION_ABNORMAL => 3,
+            _ => 2,
+        }))
+    }
+
+    /// The 4E response frame of a whole 1E response taken by `frame_len`.
+    pub(crate) fn response(&mut self, frame: &[u8]) -> crate::io::Result<Vec<u8>> {
+        let expected = self.expected.pop_front().ok_or_else(|| invalid_data("Received a 1E Frame without a Request"))?;
+
+        let (end_code, data): (SLMPEndCode, Vec<u8>) = match frame[1] {
+            0 => {
+                let data: &[u8] = &frame[2..];
+                let data: Vec<u8> = match expected.data {
+                    ResponseData::Read(_) | ResponseData::None => data.to_vec(),
+                    ResponseData::Model => {
+                        let mut ret: Vec<u8> = MODEL_NAME.to_vec();
+                        ret.extend((data[0] as u16).to_le_bytes());
+                        ret
+                    },
+                    ResponseData::Loopback(_) => {
+                        let mut ret: Vec<u8> = (data[0] as u16).to_le_bytes().to_vec();
+                        ret.extend(&data[1..]);
+                        ret
+                    },
+                };
+                (SLMPEndCode(0), data)
+            },
+            code => (end_code(code, frame.get(2).copied().unwrap_or(0)), Vec::new()),
+        };
+
+        let mut ret: Vec<u8> = Vec::with_capacity(expected.header.len() + 4 + data.len());
+        ret.extend(expected.header);
+        ret.extend(((2 + data.len()) as u16).to_le_bytes());
+        ret.extend(end_code.0.to_le_bytes());
+        ret.extend(data);
+        Ok(ret)
+    }
+}
+
+/// A device and points of a Q/L format 4E device access, as a 1E head device and points.
+fn device_points(data: &[u8], max_points: usize) -> crate::io::Result<([u8; 8], usize)> {
+    let Some(&[a0, a1, a2, code, p0, p1]) = data.get(..6) else {
+        return Err(invalid_input(String::from("Device access without a device")));
+    };
+    let device_type: DeviceType = DeviceType::from_code(code).ok_or_else(|| invalid_input(format!("Invalid device code 0x{code:02X}")))?;
+    let device_code: u16 = device_code(device_type)
+        .ok_or_else(|| unsupported(format!("{device_type:?} has no device code in the 1E frame (A type CPU)")))?;
+    let points: usize = u16::from_le_bytes([p0, p1]) as usize;
+    if points == 0 || points > max_points {
+        return Err(invalid_input(format!("1E frame takes 1~{max_points} points, got {points}")));
+    }
+
+    let code: [u8; 2] = device_code.to_le_bytes();
+    // 256 points are sent as 0.
+    Ok(([a0, a1, a2, 0x00, code[0], code[1], points as u8, 0x00], points))
+}
+
+fn translate_request(frame: &[u8]) -> crate::io::Result<(Vec<u8>, Expected)> {
+    use crate::commands::device_access::BULK_MAX_POINTS_1E;
+
+    if frame.len() < SUBHEADER_LEN + 4 {
+        return Err(invalid_input(String::from("Request frame too short")));
+    }
+    let command = u16::from_le_bytes([frame[SUBHEADER_LEN], frame[SUBHEADER_LEN + 1]]);
+    let subcommand = u16::from_le_bytes([frame[SUBHEADER_LEN + 2], frame[SUBHEADER_LEN + 3]]);
+    let data: &[u8] = &frame[SUBHEADER_LEN + 4..];
+
+    let (command_1e, request_data, response_data): (u8, Vec<u8>, ResponseData) = match (command, subcommand) {
+        (0x0401, 0x0001) => {
+            let (head, points) = device_points(data, BULK_MAX_POINTS_1E)?;
+            (BATCH_READ_BIT, head.to_vec(), ResponseData::Read(points.div_ceil(2)))
+        },
+        (0x0401, 0x0000) => {
+            let (head, points) = device_points(data, BULK_MAX_POINTS_1E)?;
+            (BATCH_READ_WORD, head.to_vec(), ResponseData::Read(2 * points))
+        },
+        (0x1401, 0x0001 | 0x0000) => {
+            let (head, _) = device_points(data, BULK_MAX_POINTS_1E)?;
+            let command_1e: u8 = if subcommand == 0x0001 { BATCH_WRITE_BIT } else { BATCH_WRITE_WORD };
+            (command_1e, [&head[..], &data[6..]].concat(), ResponseData::None)
+        },
+        (0x1001, _) => (REMOTE_RUN, Vec::new(), ResponseData::None),
+        (0x1002, _) => (REMOTE_STOP, Vec::new(), ResponseData::None),
+        (0x0101, _) => (READ_MODEL, Vec::new(), ResponseData::Model),
+        (0x0619, _) => {
+            let message: &[u8] = data.get(2..).unwrap_or_default();
+            if message.is_empty() || message.len() > LOOPBACK_MAX_LEN {
+                return Err(invalid_input(format!("1E frame takes loopback data of 1~{LOOPBACK_MAX_LEN} bytes, got {}", message.len())));
+            }
+            let mut request_data: Vec<u8> = vec![message.len() as u8];
+            request_data.extend(message);
+            (LOOPBACK, request_data, ResponseData::Loopback(message.len()))
+        },
+        (command, subcommand) => return Err(unsupported(format!(
+            "{} (0x{command:04X}, subcommand 0x{subcommand:04X}) is not available in the 1E frame (A type CPU)",
+            crate::frame::command_name(command)
+        ))),
+    };
+
+    // PC No. and monitoring timer of the 4E request.
+    let mut request: Vec<u8> = Vec::with_capacity(4 + request_data.len());
+    request.extend([command_1e, frame[7], frame[13], frame[14]]);
+    request.extend(request_data);
+
+    let mut header = [0u8; 11];
+    header.copy_from_slice(&frame[..11]);
+    header[0] = 0xD4;
+
+    Ok((request, Expected { header, command: command_1e, data: response_data }))
+}
@@ -0,0 +1,330 @@
+//! Request frames and response parsing without a socket, e.g. to generate frames on one machine
+//! and send them from a gateway without tokio. `SLMPClient` builds and parses its frames with the same functions.
+//! This module builds without the default `std` feature (`no_std` + `alloc`); the errors are then `slmp::io::Error`.
+//!
+//! A frame carries `serial_id` of the connection props, and a response is checked against it.
+//! Requests of several frames (`build_random_write`, `build_clear_error`, `build_set_clock`) number them from `serial_id`;
+//! check each response with the serial No. of its frame.
+//! Devices are checked against `DeviceLimits::new` of the CPU, and 32/64-bit values are in `WordOrder::LowHigh`.
+//! The frames are 4E frames for `CPU::A` too; only `TcpTransport` and the blocking client translate them into 1E frames.
+//!
+//! ```rust
+//! use slmp::*;
+//!
+//! let props = SLMP4EConnectionProps {
+//!     ip: String::from("192.168.3.10"),
+//!     port: 5007,
+//!     cpu: CPU::R,
+//!     serial_id: 0x0001,
+//!     network_id: 0x00,
+//!     pc_id: 0xff,
+//!     io_id: 0x03ff,
+//!     area_id: 0x00,
+//!     cpu_timer: 0x0010,
+//!     remote_password: None,
+//! };
+//! let d100 = Device { device_type: DeviceType::D, address: 100 };
+//! let frame: Vec<u8> = frames::build_bulk_read(&props, d100, 2, DataType::U16).unwrap();
+//! assert_eq!(&frame[..2], [0x54, 0x00]);
+//!
+//! let response: [u8; 19] = [
+//!     0xd4, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0x03, 0x00, 0x06, 0x00,
+//!     0x00, 0x00, 0x0a, 0x00, 0x14, 0x00,
+//! ];
+//! let values = frames::parse_bulk_read_response(&props, d100, 2, DataType::U16, &response).unwrap();
+//! assert_eq!(values[1].data, TypedData::U16(20));
+//! ```
+
+use crate::prelude::*;
+use crate::commands::device_access::read::SLMPMonitorReadCommand;
+use crate::commands::file_control::{self, FileOpenMode};
+use crate::commands::{clock, diagnostics, label_access, unit_control};
+use crate::requests;
+use crate::{BlockedDeviceData, CPU, CpuTypeInfo, DataType, Device, DeviceBlock, DeviceData, DeviceLimits, FileDrive, FileInfo, MonitorList, PLCDateTime, PLCErrorRecord, SLMP4EConnectionProps, TypedData, TypedDevice, WordOrder};
+
+fn frame(connection_props: &SLMP4EConnectionProps, command: &[u8]) -> crate::io::Result<Vec<u8>> {
+    requests::request_frame(connection_props, connection_props.serial_id, command)
+}
+
+fn numbered_frames<C: AsRef<[u8]>>(connection_props: &SLMP4EConnectionProps, commands: Vec<C>) -> crate::io::Result<Vec<Vec<u8>>> {
+    commands.iter()
+        .zip(0u16..)
+        .map(|(command, i)| requests::request_frame(connection_props, connection_props.serial_id.wrapping_add(i), command.as_ref()))
+        .collect()
+}
+
+fn limits(connection_props: &SLMP4EConnectionProps) -> DeviceLimits {
+    DeviceLimits::new(connection_props.cpu)
+}
+
+/// Response data after the end code. It fails with the end code (see `SLMPEndCode::from_io_error`),
+/// or with `InvalidData` for a malformed response or a response to another request.
+pub fn response_data<'a>(connection_props: &SLMP4EConnectionProps, frame: &'a [u8]) -> crate::io::Result<&'a [u8]> {
+    requests::response_data(connection_props, frame, connection_props.serial_id)
+}
+
+/// Length of the first frame in `received`, or `None` until its header has been received,
+/// to split a byte stream into frames. It fails with `InvalidData` for a frame longer than `max_len`.
+pub fn frame_len(received: &[u8], max_len: usize) -> crate::io::Result<Option<usize>> {
+    requests::frame_len(received, max_len)
+}
+
+/// Check the response of a request returning no data, e.g. a write or a remote operation.
+pub fn check_response(connection_props: &SLMP4EConnectionProps, frame: &[u8]) -> crate::io::Result<()> {
+    response_data(connection_props, frame).map(|_| ())
+}
+
+/* Device Access */
+
+pub fn build_bulk_read(connection_props: &SLMP4EConnectionProps, start_device: Device, device_num: usize, data_type: DataType) -> crate::io::Result<Vec<u8>> {
+    let request = requests::BulkRead { start_device, device_num, data_type };
+    frame(connection_props, &request.command(&connection_props.cpu, &limits(connection_props))?)
+}
+
+pub fn parse_bulk_read_response(connection_props: &SLMP4EConnectionProps, start_device: Device, device_num: usize, data_type: DataType, frame: &[u8]) -> crate::io::Result<Vec<DeviceData>> {
+    let request = requests::BulkRead { start_device, device_num, data_type };
+    request.parse(response_data(connection_props, frame)?)
+}
+
+pub fn build_bulk_write(connection_props: &SLMP4EConnectionProps, start_device: Device, data: &[TypedData]) -> crate::io::Result<Vec<u8>> {
+    let cmd = requests::bulk_write(&connection_props.cpu, &limits(connection_props), start_device, data, WordOrder::LowHigh)?;
+    frame(connection_props, &cmd)
+}
+
+pub fn build_random_read(connection_props: &SLMP4EConnectionProps, devices: &[TypedDevice]) -> crate::io::Result<Vec<u8>> {
+    let (cmd, _) = requests::random_read(&connection_props.cpu, &limits(connection_props), devices)?;
+    frame(connection_props, &cmd)
+}
+
+/// Values in the order of `devices`.
+pub fn parse_random_read_response(connection_props: &SLMP4EConnectionProps, devices: &[TypedDevice], frame: &[u8]) -> crate::io::Result<Vec<DeviceData>> {
+    MonitorList::try_from(devices)?.parse(response_data(connection_props, frame)?)
+}
+
+/// Word devices and bit devices are written by separate frames, numbered from `serial_id`.
+pub fn build_random_write(connection_props: &SLMP4EConnectionProps, data: &[DeviceData]) -> crate::io::Result<Vec<Vec<u8>>> {
+    let cmds = requests::random_write(&connection_props.cpu, &limits(connection_props), data, WordOrder::LowHigh)?;
+    numbered_frames(connection_props, cmds)
+}
+
+pub fn build_block_read(connection_props: &SLMP4EConnectionProps, device_blocks: &[DeviceBlock]) -> crate::io::Result<Vec<u8>> {
+    let request = requests::BlockRead::new(&connection_props.cpu, &limits(connection_props), device_blocks)?;
+    frame(connection_props, &request.command(&connection_props.cpu))
+}
+
+pub fn parse_block_read_response(connection_props: &SLMP4EConnectionProps, device_blocks: &[DeviceBlock], frame: &[u8]) -> crate::io::Result<Vec<DeviceData>> {
+    let request = requests::BlockRead::new(&connection_props.cpu, &limits(connection_props), device_blocks)?;
+    request.parse(response_data(connection_props, frame)?)
+}
+
+pub fn build_block_write<'a>(connection_props: &SLMP4EConnectionProps, data: &'a [BlockedDeviceData<'a>]) -> crate::io::Result<Vec<u8>> {
+    let cmd = requests::block_write(&connection_props.cpu, &limits(connection_props), data, WordOrder::LowHigh)?;
+    frame(connection_props, &cmd)
+}
+
+pub fn build_monitor_register(connection_props: &SLMP4EConnectionProps, devices: &[TypedDevice]) -> crate::io::Result<Vec<u8>> {
+    let (cmd, _) = requests::monitor_register(&connection_props.cpu, &limits(connection_props), devices)?;
+    frame(connection_props, &cmd)
+}
+
+/// Monitor register of no devices, which releases the registered ones.
+pub fn build_monitor_clear(connection_props: &SLMP4EConnectionProps) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &requests::monitor_clear(&connection_props.cpu))
+}
+
+pub fn build_monitor_read(connection_props: &SLMP4EConnectionProps) -> crate::io::Result<Vec<u8>> {
+    const COMMAND: SLMPMonitorReadCommand = SLMPMonitorReadCommand::new();
+    frame(connection_props, &COMMAND)
+}
+
+/// Values in the order of the `devices` registered by `build_monitor_register`.
+pub fn parse_monitor_read_response(connection_props: &SLMP4EConnectionProps, devices: &[TypedDevice], frame: &[u8]) -> crate::io::Result<Vec<DeviceData>> {
+    MonitorList::try_from(devices)?.parse(response_data(connection_props, frame)?)
+}
+
+/* Unit Control */
+
+pub fn build_remote_run(connection_props: &SLMP4EConnectionProps) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &unit_control::remote_run())
+}
+
+pub fn build_remote_stop(connection_props: &SLMP4EConnectionProps) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &unit_control::remote_stop())
+}
+
+pub fn build_remote_pause(connection_props: &SLMP4EConnectionProps) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &unit_control::remote_pause())
+}
+
+pub fn build_remote_latch_clear(connection_props: &SLMP4EConnectionProps) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &unit_control::remote_latch_clear())
+}
+
+pub fn build_remote_reset(connection_props: &SLMP4EConnectionProps) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &unit_control::remote_reset())
+}
+
+pub fn build_get_cpu_type(connection_props: &SLMP4EConnectionProps) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &unit_control::get_cpu_type())
+}
+
+pub fn parse_get_cpu_type_response(connection_props: &SLMP4EConnectionProps, frame: &[u8]) -> crate::io::Result<CpuTypeInfo> {
+    unit_control::parse_cpu_type(response_data(connection_props, frame)?)
+}
+
+pub fn build_lock_cpu(connection_props: &SLMP4EConnectionProps, password: &str) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &unit_control::lock_cpu(&connection_props.cpu, password)?)
+}
+
+pub fn build_unlock_cpu(connection_props: &SLMP4EConnectionProps, password: &str) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &unit_control::unlock_cpu(&connection_props.cpu, password)?)
+}
+
+pub fn build_echo(connection_props: &SLMP4EConnectionProps) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &unit_control::echo())
+}
+
+/// Check that the echo came back as sent.
+pub fn parse_echo_response(connection_props: &SLMP4EConnectionProps, frame: &[u8]) -> crate::io::Result<()> {
+    unit_control::check_echo(response_data(connection_props, frame)?)
+}
+
+pub fn build_loopback(connection_props: &SLMP4EConnectionProps, data: &[u8]) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &unit_control::loopback(data)?)
+}
+
+/// Check that the loopback data came back unchanged.
+pub fn parse_loopback_response(connection_props: &SLMP4EConnectionProps, frame: &[u8], data: &[u8]) -> crate::io::Result<()> {
+    unit_control::check_loopback(response_data(connection_props, frame)?, data)
+}
+
+/* Diagnostics */
+
+pub fn build_read_error_history(connection_props: &SLMP4EConnectionProps) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &diagnostics::read_error_info(&connection_props.cpu))
+}
+
+/// Errors latest first. See `SLMPClient::read_error_history`.
+pub fn parse_read_error_history_response(connection_props: &SLMP4EConnectionProps, max_entries: usize, frame: &[u8]) -> crate::io::Result<Vec<PLCErrorRecord>> {
+    diagnostics::parse_error_info(&connection_props.cpu, response_data(connection_props, frame)?, max_entries)
+}
+
+/// Frames clearing the continuation errors, to be sent in order.
+/// Q/L type CPU takes the code of the latest error (from `parse_read_error_history_response`); no frames are built without it.
+pub fn build_clear_error(connection_props: &SLMP4EConnectionProps, latest_code: Option<u16>) -> crate::io::Result<Vec<Vec<u8>>> {
+    let cpu: CPU = connection_props.cpu;
+    let mut cmds: Vec<Vec<u8>> = Vec::with_capacity(3);
+
+    if matches!(cpu, CPU::Q | CPU::L) {
+        let Some(code) = latest_code else {
+            return Ok(Vec::new());
+        };
+        cmds.push(diagnostics::write_error_reset_code(&cpu, code));
+    }
+    cmds.push(diagnostics::error_reset_request(&cpu, false));
+    cmds.push(diagnostics::error_reset_request(&cpu, true));
+
+    numbered_frames(connection_props, cmds)
+}
+
+/* Clock */
+
+pub fn build_read_clock(connection_props: &SLMP4EConnectionProps) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &clock::read_clock(&connection_props.cpu))
+}
+
+pub fn parse_read_clock_response(connection_props: &SLMP4EConnectionProps, frame: &[u8]) -> crate::io::Result<PLCDateTime> {
+    clock::parse_clock(&connection_props.cpu, response_data(connection_props, frame)?)
+}
+
+/// Frames setting the clock, to be sent in order.
+pub fn build_set_clock(connection_props: &SLMP4EConnectionProps, datetime: PLCDateTime) -> crate::io::Result<Vec<Vec<u8>>> {
+    let cpu: CPU = connection_props.cpu;
+    let cmds: Vec<Vec<u8>> = vec![
+        clock::write_clock(&cpu, &datetime)?,
+        clock::set_clock_request(&cpu, false),
+        clock::set_clock_request(&cpu, true),
+    ];
+    numbered_frames(connection_props, cmds)
+}
+
+/* File Control */
+
+/// Largest number of bytes a frame of `build_read_file` or `build_write_file` carries.
+pub const FILE_ACCESS_MAX_BYTELEN: usize = file_control::FILE_ACCESS_MAX_BYTELEN;
+
+pub fn build_open_file(connection_props: &SLMP4EConnectionProps, drive: FileDrive, file_name: &str, mode: FileOpenMode) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &file_control::open_file(&connection_props.cpu, &drive, file_name, mode)?)
+}
+
+/// File pointer for `build_read_file`, `build_write_file` and `build_close_file`.
+pub fn parse_open_file_response(connection_props: &SLMP4EConnectionProps, frame: &[u8]) -> crate::io::Result<u16> {
+    file_control::parse_file_pointer(response_data(connection_props, frame)?)
+}
+
+/// Read up to `FILE_ACCESS_MAX_BYTELEN` bytes at `offset`.
+pub fn build_read_file(connection_props: &SLMP4EConnectionProps, file_pointer: u16, offset: u32, bytelen: u16) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &file_control::read_file(file_pointer, offset, bytelen))
+}
+
+/// Bytes read; fewer than requested at the end of the file.
+pub fn parse_read_file_response<'a>(connection_props: &SLMP4EConnectionProps, frame: &'a [u8]) -> crate::io::Result<&'a [u8]> {
+    file_control::parse_read_file(response_data(connection_props, frame)?)
+}
+
+/// Write up to `FILE_ACCESS_MAX_BYTELEN` bytes at `offset`.
+pub fn build_write_file(connection_props: &SLMP4EConnectionProps, file_pointer: u16, offset: u32, data: &[u8]) -> crate::io::Result<Vec<u8>> {
+    if data.len() > FILE_ACCESS_MAX_BYTELEN {
+        return Err(crate::io::Error::new(
+            crate::io::ErrorKind::InvalidInput,
+            format!("File write of {} bytes (max {FILE_ACCESS_MAX_BYTELEN})", data.len())
+        ));
+    }
+    frame(connection_props, &file_control::write_file(file_pointer, offset, data))
+}
+
+/// Bytes written.
+pub fn parse_write_file_response(connection_props: &SLMP4EConnectionProps, frame: &[u8]) -> crate::io::Result<usize> {
+    file_control::parse_written_len(response_data(connection_props, frame)?)
+}
+
+pub fn build_close_file(connection_props: &SLMP4EConnectionProps, file_pointer: u16) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &file_control::close_file(file_pointer))
+}
+
+pub fn build_create_file(connection_props: &SLMP4EConnectionProps, drive: FileDrive, file_name: &str, file_size: u32) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &file_control::create_file(&connection_props.cpu, &drive, file_name, file_size)?)
+}
+
+pub fn build_delete_file(connection_props: &SLMP4EConnectionProps, drive: FileDrive, file_name: &str) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &file_control::delete_file(&connection_props.cpu, &drive, file_name)?)
+}
+
+pub fn build_copy_file(connection_props: &SLMP4EConnectionProps, src_drive: FileDrive, src_file_name: &str, dst_drive: FileDrive, dst_file_name: &str) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &file_control::copy_file(&connection_props.cpu, &src_drive, src_file_name, &dst_drive, dst_file_name)?)
+}
+
+/// Read up to `count` entries of the root directory (at most 36 per frame), from `start_file_no`.
+pub fn build_read_directory(connection_props: &SLMP4EConnectionProps, drive: FileDrive, start_file_no: u32, count: u16) -> crate::io::Result<Vec<u8>> {
+    let count: u16 = count.min(file_control::FILE_INFO_MAX_POINTS);
+    frame(connection_props, &file_control::read_directory(&connection_props.cpu, &drive, start_file_no, count)?)
+}
+
+pub fn parse_read_directory_response(connection_props: &SLMP4EConnectionProps, frame: &[u8]) -> crate::io::Result<Vec<FileInfo>> {
+    file_control::parse_directory(&connection_props.cpu, response_data(connection_props, frame)?)
+}
+
+/* Label Access */
+
+pub fn build_read_labels(connection_props: &SLMP4EConnectionProps, labels: &[&str]) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &label_access::read_labels(labels)?)
+}
+
+/// Values in the order of the labels, each decoded with the data type reported by the CPU.
+pub fn parse_read_labels_response(connection_props: &SLMP4EConnectionProps, frame: &[u8]) -> crate::io::Result<Vec<crate::io::Result<TypedData>>> {
+    label_access::parse_labels(response_data(connection_props, frame)?)
+}
+
+pub fn build_write_labels(connection_props: &SLMP4EConnectionProps, labels: &[(&str, TypedData)]) -> crate::io::Result<Vec<u8>> {
+    frame(connection_props, &label_access::write_labels(labels)?)
+}
@@ -0,0 +1,64 @@
+//! Error type of the frame builders and parsers.
+//!
+//! With the `std` feature (default), these are the `std::io` types, as returned by the clients.
+//! Without it, the crate is `no_std` + `alloc` and `Error` is a small enum with the same error kinds.
+
+#[cfg(feature = "std")]
+pub use std::io::{Error, ErrorKind, Result};
+
+#[cfg(not(feature = "std"))]
+pub use self::no_std::{Error, ErrorKind, Result};
+
+#[cfg(not(feature = "std"))]
+mod no_std {
+    use alloc::string::String;
+    use crate::SLMPEndCode;
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// Subset of `std::io::ErrorKind` used by the crate.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    #[non_exhaustive]
+    pub enum ErrorKind {
+        InvalidInput,
+        InvalidData,
+        Unsupported,
+        NotFound,
+        AlreadyExists,
+        PermissionDenied,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum Error {
+        /// Rejected locally, e.g. a malformed request or response.
+        Message(ErrorKind, String),
+        /// End code returned from the SLMP server.
+        EndCode(SLMPEndCode),
+    }
+
+    impl Error {
+        pub fn new<M: Into<String>>(kind: ErrorKind, message: M) -> Self {
+            Self::Message(kind, message.into())
+        }
+
+        pub fn kind(&self) -> ErrorKind {
+            match self {
+                Self::Message(kind, _) => *kind,
+                Self::EndCode(SLMPEndCode::FILE_NOT_FOUND) => ErrorKind::NotFound,
+                Self::EndCode(SLMPEndCode::FILE_ALREADY_EXISTS) => ErrorKind::AlreadyExists,
+                Self::EndCode(_) => ErrorKind::InvalidData,
+            }
+        }
+    }
+
+    impl core::fmt::Display for Error {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            match self {
+                Self::Message(_, message) => f.write_str(message),
+                Self::EndCode(end_code) => end_code.fmt(f),
+            }
+        }
+    }
+
+    impl core::error::Error for Error {}
+}
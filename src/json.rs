@@ -0,0 +1,147 @@
+//! Requests and responses of the client in JSON, e.g. for a web gateway. See `SLMPClient::execute`.
+//!
+//! Struct fields are in camelCase and enum variants keep their names (`"U16"`, `"Word"`, `"D"`).
+//! A request names its operation in `"type"`, the same way `TypedData` does, and so does a response:
+//!
+//! ```json
+//! {"type": "ReadClock"}
+//! {"type": "Read", "request": {"type": "Bulk", "startDevice": {"deviceType": "D", "address": 100}, "deviceNum": 2, "dataType": "U16"}}
+//! {"type": "Write", "request": {"type": "Random", "data": [{"device": {"deviceType": "M", "address": 5}, "data": {"type": "Bool", "value": true}}]}}
+//! ```
+//!
+//! Device access alone goes through `ReadRequest` and `WriteRequest` (`SLMPClient::execute_read` and `SLMPClient::execute_write`).
+//! A string value is `{"type": "String", "value": {"text": "ABC", "deviceSize": 10, "encoding": "ShiftJis"}}`.
+
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::{BlockedDeviceDataOwned, CpuTypeInfo, DataType, Device, DeviceData, FileDrive, FileInfo, PLCDateTime, PLCErrorRecord, TypedData, TypedDevice, TypedDeviceBlock};
+
+/// A read of `bulk_read`, `random_read` or `block_read_typed`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all_fields = "camelCase")]
+pub enum ReadRequest {
+    Bulk { start_device: Device, device_num: usize, data_type: DataType },
+    Random { devices: Vec<TypedDevice> },
+    Block { blocks: Vec<TypedDeviceBlock> },
+}
+
+/// A write of `bulk_write`, `random_write` or `block_write_owned`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all_fields = "camelCase")]
+pub enum WriteRequest {
+    Bulk { start_device: Device, data: Vec<TypedData> },
+    Random { data: Vec<DeviceData> },
+    Block { blocks: Vec<BlockedDeviceDataOwned> },
+}
+
+/// Values of a `ReadRequest`, in the order the read returns them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadResponse {
+    pub data: Vec<DeviceData>,
+}
+
+/// An operation of the client, named in `"type"` after the method it runs. See `Response` for what each returns.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all_fields = "camelCase")]
+pub enum Request {
+    /// `Response::Read`.
+    Read { request: ReadRequest },
+    Write { request: WriteRequest },
+    /// `Response::Labels`.
+    ReadLabels { labels: Vec<String> },
+    WriteLabels { labels: Vec<LabelData> },
+    /// `Response::String`.
+    ReadString { device: Device, word_len: u8 },
+    WriteString { device: Device, text: String, word_len: u8 },
+    /// The fields of a struct laid out from `start_device` (see `device_struct!`), in `Response::Struct`.
+    ReadStruct { start_device: Device, fields: Vec<DataType> },
+    /// The fields are written as their types.
+    WriteStruct { start_device: Device, fields: Vec<TypedData> },
+    /// `Response::Clock`.
+    ReadClock,
+    SetClock { clock: PLCDateTime },
+    /// `Response::CpuType`.
+    GetCpuType,
+    /// `Response::ErrorHistory`.
+    ReadErrorHistory { max_entries: usize },
+    ClearError,
+    RunCpu,
+    StopCpu,
+    PauseCpu,
+    ClearLatch,
+    ResetCpu,
+    /// `Response::Files`.
+    ListFiles { drive: FileDrive, start_file_no: u32, count: usize },
+    /// `Response::File`.
+    DownloadFile { drive: FileDrive, file_name: String },
+    UploadFile { drive: FileDrive, file_name: String, data: Vec<u8>, overwrite: bool },
+    DeleteFile { drive: FileDrive, file_name: String },
+    CopyFile { src_drive: FileDrive, src_file_name: String, dst_drive: FileDrive, dst_file_name: String },
+}
+
+/// The result of a `Request`, named in `"type"`. The requests which return nothing are answered with `Done`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all_fields = "camelCase")]
+pub enum Response {
+    Done,
+    Read(ReadResponse),
+    /// In the order of the labels requested.
+    Labels { labels: Vec<LabelResult> },
+    String { text: String },
+    Struct { fields: Vec<TypedData> },
+    Clock { clock: PLCDateTime },
+    CpuType(CpuTypeInfo),
+    /// Latest first.
+    ErrorHistory { errors: Vec<PLCErrorRecord> },
+    Files { files: Vec<FileInfo> },
+    File { data: Vec<u8> },
+    Error(ErrorResponse),
+}
+
+/// A label to write with its value.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LabelData {
+    pub name: String,
+    pub data: TypedData,
+}
+
+/// A label read: its value, or the error of the label alone (e.g. the CPU does not have it).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LabelResult {
+    pub name: String,
+    pub data: Option<TypedData>,
+    pub error: Option<ErrorResponse>,
+}
+
+#[cfg(feature = "std")]
+impl From<(String, std::io::Result<TypedData>)> for LabelResult {
+    fn from(value: (String, std::io::Result<TypedData>)) -> Self {
+        match value.1 {
+            Ok(data) => Self { name: value.0, data: Some(data), error: None },
+            Err(e) => Self { name: value.0, data: None, error: Some(ErrorResponse::from(&e)) },
+        }
+    }
+}
+
+/// A request which failed: the message, the `ErrorKind` (e.g. `"TimedOut"`) and the end code if the CPU answered with one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorResponse {
+    pub error: String,
+    pub kind: String,
+    pub end_code: Option<u16>,
+}
+
+#[cfg(feature = "std")]
+impl From<&std::io::Error> for ErrorResponse {
+    fn from(value: &std::io::Error) -> Self {
+        Self {
+            error: value.to_string(),
+            kind: format!("{:?}", value.kind()),
+            end_code: crate::SLMPEndCode::from_io_error(value).map(|x| x.0),
+        }
+    }
+}
@@ -1,562 +1,220 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+// Helpers of the clients and the manager are left unused by the frame builders alone.
+#![cfg_attr(not(feature = "std"), allow(dead_code))]
+
+extern crate alloc;
+
 mod commands;
 mod data;
 mod device;
-mod manager;
+mod error;
+pub mod frame;
+mod frame_1e;
+pub mod frames;
+pub mod io;
+#[cfg(feature = "json-api")]
+pub mod json;
+mod mapping;
 mod monitor;
+mod prelude;
+mod read_plan;
+mod requests;
+mod route;
+mod snapshot;
+
+#[cfg(feature = "std")]
+mod client;
+#[cfg(feature = "std")]
+mod config;
+#[cfg(feature = "std")]
+pub mod discovery;
+#[cfg(feature = "std")]
+mod dry_run;
+#[cfg(feature = "std")]
+mod manager;
+#[cfg(feature = "std")]
+mod on_demand;
+#[cfg(feature = "std")]
+mod replay;
+#[cfg(feature = "std")]
+mod retry;
+#[cfg(feature = "std")]
+mod transport;
+#[cfg(feature = "std")]
+mod verify;
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
+#[cfg(feature = "mock-server")]
+pub mod testing;
 
-use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
-use std::sync::Arc;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{TcpStream};
-use tokio::sync::Mutex;
-use tokio::time::{timeout, Duration};
-use serde::{Deserialize, Serialize};
 
-use crate::commands::device_access::{read::*, write::*};
-use crate::commands::unit_control;
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
 
-use device::DeviceSize;
+use crate::prelude::*;
 
 // Public
-pub use data::{DataType, TypedData, string::{PLCString, PLCSTRING_QUERY_SPLITTER}};
-pub use device::{AccessType, Device, DeviceType, DeviceData, DeviceBlock, BlockedDeviceData, TypedDevice, PLCData};
-pub use monitor::{MonitorList, MonitorRequest, MonitoredDevice};
-pub use manager::{SLMPConnectionManager, SLMPWorker};
+pub use commands::clock::PLCDateTime;
+pub use commands::diagnostics::PLCErrorRecord;
+pub use commands::unit_control::CpuTypeInfo;
+pub use commands::file_control::{FileDrive, FileDriveForQL, FileDriveForR, FileInfo, FileOpenMode};
+pub use data::{DataType, TypedData, WordOrder, string::{PLCString, PLCSTRING_QUERY_SPLITTER, StringEncoding}};
+pub use error::SLMPEndCode;
+pub use device::{AccessType, Device, DeviceLimits, DeviceRange, DeviceType, DeviceData, OrderedDeviceData, DeviceBlock, TypedDeviceBlock, BlockedDeviceData, BlockedDeviceDataOwned, TypedDevice, PLCData};
+pub use monitor::{MONITOR_MAX_ACCESS_POINTS, monitor_max_access_points, ChangeFilter, MonitorList, MonitorListFull, MonitorRequest, MonitoredDevice};
+pub use mapping::{SlmpLayout, SlmpReadable, SlmpWritable};
+pub use read_plan::ReadCoalescing;
+pub use route::{CpuNo, Route};
+pub use snapshot::{DeviceChange, DeviceSnapshot, SnapshotArea};
+
+#[cfg(feature = "std")]
+pub use client::{MonitorReadBuffer, SLMPClient};
+#[cfg(feature = "std")]
+pub use config::{ConnectionConfig, ManagerConfig};
+#[cfg(feature = "std")]
+pub use route::RelayError;
+#[cfg(feature = "std")]
+pub use retry::{RetriesExhausted, RetryOn, RetryPolicy};
+#[cfg(feature = "std")]
+pub use verify::{DeviceMismatch, VerificationFailed};
+#[cfg(feature = "std")]
+pub use dry_run::{DryRun, DryRunFrames};
+#[cfg(feature = "std")]
+pub use transport::{SlmpTransport, TcpTransport};
+#[cfg(feature = "std")]
+pub use on_demand::{OnDemandData, OnDemandProtocol, OnDemandReceiver, SLMPOnDemandServer, parse_on_demand_frame};
+#[cfg(feature = "std")]
+pub use replay::{RecordedExchange, RecordingTransport, ReplayMatch, ReplayTransport, SessionLog};
+#[cfg(feature = "std")]
+pub use manager::{BackPressure, ConnectionKey, ConnectionStatus, ManagerEvent, MonitorError, MonitorReceiver, NotConnectedYet, RegistrationResult, SLMPConnectionManager, SLMPWorker, WorkerStats};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::vec;
+    pub use alloc::vec::Vec;
+}
 
 // Constants
+#[cfg(feature = "std")]
 const BUFSIZE: usize = 2048;
+#[cfg(feature = "std")]
 const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+#[cfg(feature = "std")]
 const DEFAULT_SEND_TIMEOUT_SEC: Duration = Duration::from_secs(1);
+#[cfg(feature = "std")]
 const DEFAULT_RECV_TIMEOUT_SEC: Duration = Duration::from_secs(1);
 
-const SUBHEADER_LEN: usize = 15;
-
-macro_rules! invalidDataError {
-    ($msg:expr) => {
-        std::io::Error::new(std::io::ErrorKind::InvalidData, $msg)
-    };
-}
-macro_rules! check {
-    ($data:expr, $idx:expr, $expected:expr, $msg:expr) => {
-        if $data[$idx] != $expected {
-            return Err(invalidDataError!($msg));
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+/// CPU series. `F` is the iQ-F series (FX5), which takes the Q/L device specification;
+/// its X and Y are numbered in octal, so X17 is `Device { device_type: DeviceType::X, address: 0o17 }`.
+/// `A` is the MELSEC-A series (e.g. A2USHCPU-S1 through an Ethernet interface module), which speaks only the 1E frame;
+/// bulk read/write, `get_cpu_type`, `run_cpu`/`stop_cpu` and `echo`/`loopback` are available, and other requests fail with `Unsupported`.
+pub enum CPU {Q, R, L, F, A}
+
+/// The names of the variants, `Q`, `R`, `L`, `F` and `A` (case-insensitive).
+impl core::str::FromStr for CPU {
+    type Err = io::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "Q" => Ok(Self::Q),
+            "R" => Ok(Self::R),
+            "L" => Ok(Self::L),
+            "F" => Ok(Self::F),
+            "A" => Ok(Self::A),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid CPU: {s}"))),
         }
-    };
+    }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-#[cfg_attr(feature = "json-api", serde(rename_all = "PascalCase"))]
-pub enum CPU {Q, R, L}
-
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-#[cfg_attr(feature = "json-api", serde(rename_all = "camelCase"))]
+#[serde(rename_all = "camelCase")]
 pub struct SLMP4EConnectionProps {
+    /// IP address or host name of the CPU.
     pub ip: String,
     pub port : u16,
     pub cpu: CPU,
+    /// Serial No. of the first request. The client increments it per request to match the responses.
     pub serial_id: u16,
     pub network_id: u8,
     pub pc_id: u8,
     pub io_id: u16,
     pub area_id: u8,
     pub cpu_timer: u16,
+    /// Remote password unlocked by `SLMPClient::connect` and locked again by `SLMPClient::close` (default: none).
+    #[serde(default, skip_serializing)]
+    pub remote_password: Option<RemotePassword>,
 }
 
-impl<'a> TryFrom<&'a SLMP4EConnectionProps> for SocketAddr {
-    type Error = std::io::Error;
-    fn try_from(value: &'a SLMP4EConnectionProps) -> Result<Self, Self::Error> {
-        let ip: IpAddr = value.ip.parse::<IpAddr>()
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
-        let port: u16 = value.port;
-        Ok(SocketAddr::new(ip, port))
-    }
-}
-
-#[inline(always)]
-const fn create_subheader(connection_props: &SLMP4EConnectionProps, command_len: usize) -> [u8; SUBHEADER_LEN] {
-    const BLANK_CODE: u8 = 0x00;
-    const REQUEST_CODE: [u8; 2] = [0x54, 0x00];
-    const CPUTIMER_LEN: usize = 2;
-
-    let serial_id: [u8; 2] = connection_props.serial_id.to_le_bytes();
-    let io_id: [u8; 2] = connection_props.io_id.to_le_bytes();
-    let cpu_timer: [u8; 2] = connection_props.cpu_timer.to_le_bytes();
-
-    // "Command length" counts the packet from cpu_timer
-    let command_len: [u8; 2] = ((command_len + CPUTIMER_LEN) as u16).to_le_bytes();
-
-    [
-        REQUEST_CODE[0], REQUEST_CODE[1],
-        serial_id[0], serial_id[1],
-        BLANK_CODE, BLANK_CODE,
-        connection_props.network_id,
-        connection_props.pc_id,
-        io_id[0], io_id[1],
-        connection_props.area_id,
-        command_len[0], command_len[1],
-        cpu_timer[0], cpu_timer[1],
-    ]
-}
-
-#[derive(Clone)]
-pub struct SLMPClient {
-    connection_props: SLMP4EConnectionProps,
-    stream: Arc<Mutex<Option<TcpStream>>>,
-    send_timeout: Duration,
-    recv_timeout: Duration,
-    buffer: [u8; BUFSIZE],
-}
-
-impl SLMPClient {
-    pub fn new(connection_props: SLMP4EConnectionProps) -> Self {
-        Self {
-            connection_props,
-            stream: Arc::new(Mutex::new(None)),
-            send_timeout: DEFAULT_SEND_TIMEOUT_SEC,
-            recv_timeout: DEFAULT_RECV_TIMEOUT_SEC,
-            buffer: [0; BUFSIZE],
-        }
-    }
+/// Remote password of a CPU. It is left out of `Debug` and never serialized.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(transparent)]
+pub struct RemotePassword(String);
 
-    pub async fn close(&self) {
-        let mut lock = self.stream.lock().await;
-        if let Some(mut stream) = lock.take() {
-            let _ = stream.shutdown().await;
-        }
+impl RemotePassword {
+    pub fn new(password: impl Into<String>) -> Self {
+        Self(password.into())
     }
 
-    #[allow(dead_code)]
-    pub fn set_send_timeout(&mut self, dur: Duration) {
-        self.send_timeout = dur;
-    }
-
-    #[allow(dead_code)]
-    pub fn set_recv_timeout(&mut self, dur: Duration) {
-        self.recv_timeout = dur;
-    }
-
-    pub async fn connect(&self) -> std::io::Result<()> {
-        self.close().await;
-
-        let addr: (&str, u16) = (&self.connection_props.ip, self.connection_props.port);
-        let socket_addr: SocketAddr = addr
-            .to_socket_addrs()?
-            .next()
-            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "resolve failed"))?;
-
-        let stream: TcpStream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(socket_addr))
-            .await.map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut,"Connect Failed (Timeout)"))
-            .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut,"Connect Failed (Timeout)"))??;
-
-        let mut lock = self.stream.lock().await;
-        *lock = Some(stream);
-
-        Ok(())
-    }
-
-    async fn request_response(&mut self, msg: &[u8]) -> std::io::Result<&[u8]> {
-        const RECVFRAME_PREFIX_FIXED_LEN: usize = 15;
-
-        let msg_len: usize = msg.len();
-        let subheader: [u8; SUBHEADER_LEN] = create_subheader(&self.connection_props, msg_len);
-
-        let mut send_msg: Vec<u8> = Vec::with_capacity(SUBHEADER_LEN + msg_len);
-        send_msg.extend(&subheader);
-        send_msg.extend(msg);
-
-        let mut stream = self.stream.lock().await;
-        let stream = stream.as_mut().ok_or(std::io::Error::new(std::io::ErrorKind::NotConnected, "Not Connected"))?;
-
-        timeout(self.send_timeout, stream.write_all(&send_msg)).await
-            .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut,"Send Failed (Timeout)"))??;
-
-        let bytes_read = timeout(self.recv_timeout, stream.read(&mut self.buffer)).await
-            .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut,"Read Failed (Timeout)"))??;
-
-        self.validate_response(&self.buffer[..bytes_read])?;
-
-        Ok(&self.buffer[RECVFRAME_PREFIX_FIXED_LEN..bytes_read])
-    }
-
-    fn validate_response(&self, data: &[u8]) -> std::io::Result<()> {
-        const FIXED_FRAME_LEN: usize = 13;
-        const RESPONSE_CODE: [u8; 2] = [0xD4, 0x00];
-        const BLANK_CODE: u8 = 0x00;
-
-        let data_len: usize = data.len();
-        if data_len < FIXED_FRAME_LEN {
-            return Err(invalidDataError!("Received Invalid Length Data"));
-        }
-
-        let data_block_len: usize = u16::from_le_bytes([data[11], data[12]]) as usize;
-        if data_block_len != data_len - FIXED_FRAME_LEN {
-            return Err(invalidDataError!("Received Invalid Data Frame"));
-        }
-
-        let error = u16::from_le_bytes([data[13], data[14]]);
-        if error != 0 {
-            let error_msg = match error {
-                0xC059 => "WrongCommand",
-                0xC05C => "WrongFormat",
-                0xC061 => "WrongLength",
-                0xCEE0 => "Busy",
-                0xCEE1 => "ExceedReqLength",
-                0xCEE2 => "ExceedRespLength",
-                0xCF10 => "ServerNotFound",
-                0xCF20 => "WrongConfigItem",
-                0xCF30 => "PrmIDNotFound",
-                0xCF31 => "NotStartExclusiveWrite",
-                0xCF70 => "RelayFailure",
-                0xCF71 => "TimeoutError",
-                _ => "Unknown Error",
-            };
-            return Err(invalidDataError!(format!("SLMP Returns Error: {error_msg} (0x{error:X})")));
-        }
-
-        check!(data, 0..2, RESPONSE_CODE, "Received Invalid Response Data");
-        check!(data, 2..4, self.connection_props.serial_id.to_le_bytes(), "Received Invalid Serial ID");
-        check!(data, 4..6, [BLANK_CODE; 2], "Received Invalid Blank Code");
-        check!(data, 6, self.connection_props.network_id, "Received Invalid Network ID");
-        check!(data, 7, self.connection_props.pc_id, "Received Invalid PC ID");
-        check!(data, 8..10, self.connection_props.io_id.to_le_bytes(), "Received Invalid IO ID");
-        check!(data,10, self.connection_props.area_id, "Received Invalid Area ID");
-
-        Ok(())
-    }
-
-    /* Unit Control */
-
-    pub async fn run_cpu(&mut self) -> std::io::Result<()> {
-        const COMMAND: [u8; 8] = unit_control::remote_run();
-        self.request_response(&COMMAND).await.map(|_| ())
+    pub fn expose(&self) -> &str {
+        &self.0
     }
+}
 
-    pub async fn stop_cpu(&mut self) -> std::io::Result<()> {
-        const COMMAND: [u8; 6] = unit_control::remote_stop();
-        self.request_response(&COMMAND).await.map(|_| ())
-    }
-
-    pub async fn pause_cpu(&mut self) -> std::io::Result<()> {
-        const COMMAND: [u8; 6] = unit_control::remote_pause();
-        self.request_response(&COMMAND).await.map(|_| ())
-    }
-
-    pub async fn clear_latch(&mut self) -> std::io::Result<()> {
-        const COMMAND: [u8; 6] = unit_control::remote_latch_clear();
-        self.request_response(&COMMAND).await.map(|_| ())
-    }
-
-    pub async fn reset_cpu(&mut self) -> std::io::Result<()> {
-        const COMMAND: [u8; 6] = unit_control::remote_reset();
-        self.request_response(&COMMAND).await.map(|_| ())
-    }
-
-    pub async fn get_cpu_type(&mut self) -> std::io::Result<String> {
-        const COMMAND: [u8; 4] = unit_control::get_cpu_type();
-        let ret = self.request_response(&COMMAND).await?;
-
-        const END_CODE: u8 = 0x20;
-        let end_pos = ret.iter().position(|&b| b == END_CODE).unwrap_or(ret.len());
-        let cpu_type = String::from_utf8_lossy(&ret[..end_pos]).into_owned();
-
-        Ok(cpu_type)
-    }
-
-    pub async fn lock_cpu(&mut self, password: &str) -> std::io::Result<()> {
-        let cmd = unit_control::lock_cpu(&self.connection_props.cpu, password)?;
-        self.request_response(&cmd).await.map(|_| ())
-    }
-
-    pub async fn unlock_cpu(&mut self, password: &str) -> std::io::Result<()> {
-        let cmd = unit_control::unlock_cpu(&self.connection_props.cpu, password)?;
-        self.request_response(&cmd).await.map(|_| ())
-    }
-
-    pub async fn echo(&mut self) -> std::io::Result<()> {
-        const COMMAND: [u8; 10] = unit_control::echo();
-        let recv = self.request_response(&COMMAND).await
-            .map_err(|_| std::io::Error::new(std::io::ErrorKind::NetworkDown, "Echo response did not return in time"))?;
-
-        if &recv[2..6] ==  unit_control::ECHO_MESSAGE {
-            Ok(())
-        } else {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("Echo mismatch, send: {:02x?}, received: {:02x?}", unit_control::ECHO_MESSAGE, &recv[2..6])
-            ))
-        }
-    }
-
-    /* File Control */
-
-    /* Device Access */
-
-    pub async fn bulk_write<'a>(&mut self, start_device: Device, data: &'a [TypedData]) -> std::io::Result<()>
-    {
-        if data.len() > 0 {
-            let query = SLMPBulkWriteQuery {
-                cpu: &self.connection_props.cpu,
-                start_device,
-                data,
-            };
-            let cmd: SLMPBulkWriteCommand = query.into();
-
-            self.request_response(&cmd).await.map(|_| ())?;
-        }
-
-        Ok(())
-    }
-
-
-    pub async fn random_write<'a>(&mut self, data: &'a [DeviceData]) -> std::io::Result<()>
-    {
-        // Word access
-        let mut sorted_word_data: Vec<DeviceData> = data.iter()
-            .filter(|x| !matches!(x.data, TypedData::Bool(_)))
-            .copied()
-            .collect();
-        sorted_word_data.sort_by_key(|p| p.device.address);
-        sorted_word_data.sort_by_key(|p| p.data.get_type());
-
-        // Bit access
-        let mut sorted_bit_data: Vec<DeviceData> = data.iter()
-            .filter(|x| matches!(x.data, TypedData::Bool(_)))
-            .copied()
-            .collect();
-        sorted_bit_data.sort_by_key(|p| p.device.address);
-
-        let single_word_access_points_for_multi_word_communication = sorted_word_data
-            .iter()
-            .filter(|x| matches!(x.data.get_type().device_size(), DeviceSize::MultiWord(_)))
-            .fold(0, |a, b| {
-                if let DeviceSize::MultiWord(n) = b.data.get_type().device_size() { a + n } else { a }
-            });
-
-        let single_word_access_points: u8 = sorted_word_data
-            .iter()
-            .filter(|x| x.data.get_type().device_size() == DeviceSize::SingleWord)
-            .count() as u8 + single_word_access_points_for_multi_word_communication;
-
-        let double_word_access_points: u8 = sorted_word_data
-            .iter()
-            .filter(|x| x.data.get_type().device_size() == DeviceSize::DoubleWord)
-            .count() as u8;
-
-        let bit_access_points: u8 = sorted_bit_data
-            .iter()
-            .filter(|x| x.data.get_type().device_size() == DeviceSize::Bit).count() as u8;
-
-        if single_word_access_points + double_word_access_points > 0 {
-            let query = SLMPRandomWriteQuery {
-                cpu: &self.connection_props.cpu,
-                sorted_data: &sorted_word_data,
-                access_type: AccessType::Word,
-                bit_access_points: 0,
-                single_word_access_points,
-                double_word_access_points,
-            };
-            let cmd: SLMPRandomWriteCommand = query.into();
-
-            self.request_response(&cmd).await.map(|_| ())?;
-        }
-
-        if bit_access_points > 0 {
-            let query = SLMPRandomWriteQuery {
-                cpu: &self.connection_props.cpu,
-                sorted_data: &sorted_bit_data,
-                access_type: AccessType::Bit,
-                bit_access_points,
-                single_word_access_points: 0,
-                double_word_access_points: 0,
-            };
-            let cmd: SLMPRandomWriteCommand = query.into();
-
-            self.request_response(&cmd).await.map(|_| ())?;
-        }
-
-        Ok(())
-    }
-
-    pub async fn block_write<'a>(&mut self, data: &'a [BlockedDeviceData<'a>]) -> std::io::Result<()>
-    {
-        let mut sorted_data = data.to_vec();
-        sorted_data.sort_by_key(|p| p.access_type);
-
-        let word_access_points: u8 = sorted_data.iter().filter(|x| x.access_type == AccessType::Word).count() as u8;
-        let bit_access_points: u8 = sorted_data.iter().filter(|x| x.access_type == AccessType::Bit).count() as u8;
-
-        if word_access_points + bit_access_points > 0 {
-            let query = SLMPBlockWriteQuery {
-                cpu: &self.connection_props.cpu,
-                sorted_data: &sorted_data,
-                word_access_points,
-                bit_access_points
-            };
-            let cmd: SLMPBlockWriteCommand = query.into();
-
-            self.request_response(&cmd).await.map(|_| ())?;
-        }
-
-        Ok(())
-    }
-
-    pub async fn bulk_read(&mut self, start_device: Device, device_num: usize, data_type: DataType) -> std::io::Result<Vec<DeviceData>>
-    {
-        let query = SLMPBulkReadQuery {
-            cpu: &self.connection_props.cpu,
-            start_device,
-            device_num,
-            data_type,
-        };
-        let cmd: SLMPBulkReadCommand = query.into();
-
-        let recv: &[u8] = &(self.request_response(&cmd).await?);
-
-        match data_type {
-            DataType::Bool => {
-                let device_type = start_device.device_type;
-                let start_address = start_device.address;
-
-                let mut ret: Vec<DeviceData> = Vec::with_capacity(device_num);
-                for (i, data) in recv.iter().flat_map(|&x| [(x >> 4) & 0x01, x & 0x01]).enumerate() {
-                    if i < device_num {
-                        ret.push(DeviceData {
-                            device: Device {device_type, address: start_address + i},
-                            data: TypedData::Bool(if data == 1 { true } else { false })
-                        })
-                    }
-                }
-                Ok(ret)
-            }
-            _ => {
-                let chunk_size = data_type.byte_size();
-                let skip_address = chunk_size / 2;
-                let device_type = start_device.device_type;
-                let start_address = start_device.address;
-
-                let mut ret: Vec<DeviceData> = Vec::with_capacity(device_num);
-                for (i, data) in recv.chunks_exact(chunk_size).enumerate() {
-                    ret.push(DeviceData {
-                        device: Device {device_type, address: start_address + skip_address * i},
-                        data: TypedData::from((data, data_type))
-                    });
-                }
-
-                Ok(ret)
-            }
-        }
-    }
-
-    pub async fn random_read(&mut self, devices: &[TypedDevice]) -> std::io::Result<Vec<DeviceData>>
-    {
-        let monitor_list = MonitorList::from(devices);
-
-        let query = SLMPRandomReadQuery {
-            cpu: &self.connection_props.cpu,
-            monitor_list: &monitor_list
-        };
-        let cmd: SLMPRandomReadCommand = query.into();
-
-        let recv: &[u8] = &(self.request_response(&cmd).await?);
-
-        Ok(monitor_list.parse(&recv))
+impl core::fmt::Debug for RemotePassword {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("RemotePassword(***)")
     }
+}
 
+impl SLMP4EConnectionProps {
+    /// Unit of `cpu_timer` (monitoring timer).
+    pub const CPU_TIMER_UNIT: Duration = Duration::from_millis(250);
 
-    pub async fn block_read(&mut self, device_blocks: &[DeviceBlock]) -> std::io::Result<Vec<DeviceData>>
-    {
-        const WORD_RESPONSE_BYTEELEN: usize = 2;
-        const BIT_RESPONSE_BYTEELEN: usize = 1;
-
-        let mut sorted_block = device_blocks.to_vec();
-        sorted_block.sort_by_key(|p| p.start_device.address);
-        sorted_block.sort_by_key(|p| p.access_type);
-
-        let word_access_points: u8 = sorted_block.iter().filter(|x| x.access_type == AccessType::Word).count() as u8;
-        let bit_access_points: u8 = sorted_block.iter().filter(|x| x.access_type == AccessType::Bit).count() as u8;
-
-        let query = SLMPBlockReadQuery {
-            cpu: &self.connection_props.cpu,
-            sorted_block: &sorted_block,
-            word_access_points,
-            bit_access_points,
-        };
-        let cmd: SLMPBlockReadCommand = query.into();
-
-        let recv: &[u8] = &(self.request_response(&cmd).await?);
-
-        let data_num = sorted_block.iter().fold(0, |a, b| a + b.size);
-        let mut ret: Vec<DeviceData> = Vec::with_capacity(data_num);
-
-        let mut read_addr = 0;
-
-        for block in &sorted_block {
-            let start_address = block.start_device.address;
-            let device_type = block.start_device.device_type;
-            let block_bytelen = match block.access_type {
-                AccessType::Word => WORD_RESPONSE_BYTEELEN * block.size,
-                AccessType::Bit => BIT_RESPONSE_BYTEELEN * div_ceil(block.size, 8)
-            };
-            let blocked_data = &recv[read_addr..(read_addr + block_bytelen)];
-            read_addr += block_bytelen;
-
-            match block.access_type {
-                AccessType::Word => {
-                    for (i, x) in blocked_data.chunks_exact(WORD_RESPONSE_BYTEELEN).enumerate() {
-                        ret.push(DeviceData{
-                            device: Device {device_type, address: start_address + i},
-                            data: TypedData::from((x, DataType::U16)),
-                        });
-                    }
-                },
-                AccessType::Bit => {
-                    for (i, x) in blocked_data.chunks_exact(BIT_RESPONSE_BYTEELEN).enumerate() {
-                        for (j, y) in u8_to_bits(x[0]).into_iter().enumerate() {
-                            let bit_index = 8 * i + j;
-                            if bit_index < block.size {
-                                ret.push(DeviceData{
-                                    device: Device {device_type, address: start_address + bit_index},
-                                    data: TypedData::Bool(y),
-                                });
-                            }
-                        }
-                    }
-                }
-            }
+    /// Time the CPU waits for the processing of a request before returning an end code.
+    /// `None` means the CPU waits infinitely (`cpu_timer` = 0).
+    pub const fn cpu_timer_duration(&self) -> Option<Duration> {
+        match self.cpu_timer {
+            0 => None,
+            n => Some(Duration::from_millis(n as u64 * Self::CPU_TIMER_UNIT.as_millis() as u64)),
         }
-
-        Ok(ret)
     }
 
-    pub async fn monitor_register(&mut self, devices: &[TypedDevice]) -> std::io::Result<MonitorList>
-    {
-        let monitor_list = MonitorList::from(devices);
-        let query = SLMPMonitorRegisterQuery {
-            cpu: &self.connection_props.cpu,
-            monitor_list: &monitor_list
+    /// Set `cpu_timer` to the longest monitoring timer within `recv_timeout`,
+    /// so that the CPU gives up (and returns an end code) before the client does.
+    pub fn with_timeouts(mut self, recv_timeout: Duration) -> io::Result<Self> {
+        let units: u128 = recv_timeout.as_millis() / Self::CPU_TIMER_UNIT.as_millis();
+        self.cpu_timer = match u16::try_from(units) {
+            Ok(0) => return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Receive timeout must be {:?} or longer", Self::CPU_TIMER_UNIT)
+            )),
+            Ok(n) => n,
+            Err(_) => u16::MAX,
         };
-        let cmd: SLMPMonitorRegisterCommand = query.into();
-        self.request_response(&cmd).await?;
-
-        Ok(monitor_list)
+        Ok(self)
     }
+}
 
-    pub async fn monitor_read(&mut self, monitor_list: &MonitorList) -> std::io::Result<Vec<DeviceData>>
-    {
-        const COMMAND: SLMPMonitorReadCommand = SLMPMonitorReadCommand::new();
-        let recv: &[u8] = &(self.request_response(&COMMAND).await?);
-
-        Ok(monitor_list.parse(&recv))
+#[cfg(feature = "std")]
+impl<'a> TryFrom<&'a SLMP4EConnectionProps> for std::net::SocketAddr {
+    type Error = std::io::Error;
+    fn try_from(value: &'a SLMP4EConnectionProps) -> Result<Self, Self::Error> {
+        // A host name is resolved to its first address.
+        let addr: (&str, u16) = (&value.ip, value.port);
+        std::net::ToSocketAddrs::to_socket_addrs(&addr)?
+            .next()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "resolve failed"))
     }
-
 }
 
 
 #[inline(always)]
 pub(crate) const fn div_ceil(a: usize, b: usize) -> usize {
-    (a + b - 1) / b
+    a.div_ceil(b)
 }
 
 #[inline(always)]
@@ -566,7 +224,7 @@ pub(crate) const fn u8_to_bits(n: u8) -> [bool; 8] {
 
 #[inline(always)]
 pub(crate) const fn bits_to_u8(bits: [bool; 8]) -> u8 {
-    ((bits[0] as u8) << 0) |
+    (bits[0] as u8) |
     ((bits[1] as u8) << 1) |
     ((bits[2] as u8) << 2) |
     ((bits[3] as u8) << 3) |
@@ -597,5 +255,5 @@ pub(crate) const fn bits_to_u16(bits: [bool; 16]) -> u16 {
     let high_byte = bits_to_u8(high_bits);
     let low_byte = bits_to_u8(low_bits);
 
-    u16::from_be_bytes([low_byte, high_byte])
+    u16::from_le_bytes([low_byte, high_byte])
 }
@@ -1,138 +1,1762 @@
-use std::collections::{HashSet, hash_map::Entry};
-use std::sync::Arc;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering}};
 use std::net::SocketAddr;
 use std::time::SystemTime;
 use std::collections::HashMap;
 
-use tokio::sync::{Mutex, RwLock, mpsc::{unbounded_channel, UnboundedSender}};
+use tokio::sync::{Mutex, Notify, RwLock, Semaphore, broadcast, oneshot, watch, mpsc::{self, unbounded_channel, UnboundedReceiver, UnboundedSender}};
+use tokio::time::MissedTickBehavior;
 use tokio_util::sync::CancellationToken;
+use serde::{Deserialize, Serialize};
 
 use crate::*;
-use tokio::task::JoinHandle;
+use crate::commands::device_access::random_write_max_access_points;
+use tokio::task::{JoinHandle, JoinSet};
 
 type SharedResource<T> = Arc<Mutex<T>>;
-type ConnectionMap = HashMap<SocketAddr, Arc<SLMPWorker>>;
+type ConnectionMap = HashMap<ConnectionKey, Arc<SLMPWorker>>;
+type MonitorTargets = Vec<MonitorTarget>;
+/// Result of a write of the write queue, shared by the writes of a batch.
+type WriteReply = oneshot::Sender<Result<(), Arc<std::io::Error>>>;
+/// Subscribed devices of a worker with their type and value.
+type Subscriptions = HashMap<Device, (DataType, Arc<watch::Sender<TypedData>>)>;
+/// Last value read of each device of a worker, with the time it was read.
+type LatestValues = HashMap<TypedDevice, (TypedData, SystemTime)>;
+/// Bulk writes (start device and values) and random writes of an output image.
+type OutputWrites = (Vec<(Device, Vec<TypedData>)>, Vec<Vec<DeviceData>>);
+/// An ad-hoc request run by the monitor loop between cyclic reads.
+type ClientRequest = Box<dyn FnOnce(Arc<SLMPClient>) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + std::marker::Send>> + std::marker::Send>;
+
+/// Pending error events per connection. Slow subscribers lose the oldest ones.
+const ERROR_CHANNEL_CAPACITY: usize = 64;
+
+/// Pending `ManagerEvent`s of a manager. Slow receivers lose the oldest ones.
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// Batches of cyclic reads waiting for the cyclic task, by default.
+const DEFAULT_TASK_QUEUE_CAPACITY: usize = 16;
+
+
+/// How long `close` waits for the monitor loop before aborting it.
+const DEFAULT_CLOSE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+
+/// First and longest waits between the connection attempts of `connect_lazy`, by default.
+const DEFAULT_CONNECT_BACKOFF: (std::time::Duration, std::time::Duration) = (std::time::Duration::from_millis(100), std::time::Duration::from_secs(10));
+
+/// Identity of a connection: the address and the route (network No., station No., I/O No. and multidrop station No.).
+/// Connections to one address with different routes (e.g. CPU No.1 and No.2 of a multiple CPU system) are kept apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionKey {
+    pub socket_addr: SocketAddr,
+    pub network_id: u8,
+    pub pc_id: u8,
+    pub io_id: u16,
+    pub area_id: u8,
+}
+
+/// Resolves the host name with the blocking resolver of the standard library; the manager resolves it once on connect instead.
+impl TryFrom<&SLMP4EConnectionProps> for ConnectionKey {
+    type Error = std::io::Error;
+    fn try_from(value: &SLMP4EConnectionProps) -> Result<Self, Self::Error> {
+        Ok(Self {
+            socket_addr: SocketAddr::try_from(value)?,
+            network_id: value.network_id,
+            pc_id: value.pc_id,
+            io_id: value.io_id,
+            area_id: value.area_id,
+        })
+    }
+}
+
+impl ConnectionKey {
+    pub const fn new(socket_addr: SocketAddr, route: Route) -> Self {
+        Self { socket_addr, network_id: route.network_id(), pc_id: route.pc_id(), io_id: route.io_id(), area_id: route.area_id() }
+    }
+
+    pub const fn route(&self) -> Route {
+        Route::unchecked(self.network_id, self.pc_id, self.io_id, self.area_id)
+    }
+}
+
+/// The host, port and route of `SLMP4EConnectionProps` as given, before the host is resolved.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct HostKey {
+    host: String,
+    port: u16,
+    route: Route,
+}
+
+impl HostKey {
+    fn of(connection_props: &SLMP4EConnectionProps) -> Self {
+        Self { host: connection_props.ip.clone(), port: connection_props.port, route: Route::of(connection_props) }
+    }
+}
+
+/// The key of a connection, with the host resolved to its first address.
+async fn resolve(connection_props: &SLMP4EConnectionProps) -> std::io::Result<ConnectionKey> {
+    let socket_addr: SocketAddr = tokio::net::lookup_host((connection_props.ip.as_str(), connection_props.port)).await?
+        .next()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "resolve failed"))?;
+    Ok(ConnectionKey::new(socket_addr, Route::of(connection_props)))
+}
 
 impl<'a> TryFrom<&MonitorRequest<'a>> for MonitoredDevice {
     type Error = std::io::Error;
     fn try_from(value: &MonitorRequest) -> Result<Self, Self::Error> {
         Ok(Self {
             socket_addr: SocketAddr::try_from(value.connection_props)?,
+            route: Route::of(value.connection_props),
             monitor_device: value.monitor_device
         })
     }
-}
+}
+
+impl MonitoredDevice {
+    /// The connection of the device.
+    pub const fn key(&self) -> ConnectionKey {
+        ConnectionKey::new(self.socket_addr, self.route)
+    }
+}
+
+
+/// Link health of a connection, updated from inside the monitor loop.
+/// `connected` turns false when a request fails without an end code (the link itself failed),
+/// and turns true again on the next successful request.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionStatus {
+    pub connected: bool,
+    pub last_success: Option<SystemTime>,
+    pub last_error: Option<(SystemTime, String)>,
+    pub registered_monitor_points: usize,
+    pub poll_cycle_ms: u64,
+    pub consecutive_failures: u32,
+}
+
+/// Request statistics of the monitor loop of a connection (cyclic reads only).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerStats {
+    pub total_requests: u64,
+    pub failed_requests: u64,
+    pub timeouts: u64,
+    pub consecutive_failures: u32,
+    /// Exponential moving average of the round trip of successful requests (1/8 weight per sample).
+    pub average_latency: Option<std::time::Duration>,
+    pub min_latency: Option<std::time::Duration>,
+    pub max_latency: Option<std::time::Duration>,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// Batches of cyclic reads discarded because the cyclic task fell behind (see `SLMPConnectionManager::set_task_queue`).
+    pub dropped_batches: u64,
+}
+
+impl WorkerStats {
+    fn record<T>(&mut self, latency: std::time::Duration, result: &std::io::Result<T>, transferred_bytes: (u64, u64)) {
+        self.total_requests += 1;
+        self.bytes_sent += transferred_bytes.0;
+        self.bytes_received += transferred_bytes.1;
+
+        match result {
+            Ok(_) => {
+                self.consecutive_failures = 0;
+                self.average_latency = Some(self.average_latency.map_or(latency, |x| (x * 7 + latency) / 8));
+                self.min_latency = Some(self.min_latency.map_or(latency, |x| x.min(latency)));
+                self.max_latency = Some(self.max_latency.map_or(latency, |x| x.max(latency)));
+            },
+            Err(e) => {
+                self.failed_requests += 1;
+                self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+                if e.kind() == std::io::ErrorKind::TimedOut {
+                    self.timeouts += 1;
+                }
+            },
+        }
+    }
+}
+
+/// Result of `register_monitor_targets` for a connection.
+#[derive(Debug)]
+pub struct RegistrationResult {
+    pub key: ConnectionKey,
+    /// The effective targets, or the error of monitor register.
+    pub monitored_devices: std::io::Result<Vec<MonitoredDevice>>,
+}
+
+/// A failed request in the monitor loop of a connection.
+#[derive(Clone, Debug)]
+pub struct MonitorError {
+    pub socket_addr: SocketAddr,
+    pub route: Route,
+    pub error: Arc<std::io::Error>,
+    /// Failures in a row including this one. Reset by the next successful request.
+    pub consecutive_failures: u32,
+}
+
+/// A change of the state of a connection, see `SLMPConnectionManager::events`.
+#[derive(Clone, Debug)]
+pub enum ManagerEvent {
+    /// A connection was made by `connect`, or established by `connect_lazy`.
+    Connected { key: ConnectionKey },
+    /// The link failed while it was up, or the connection was closed (by `disconnect`, `clear` or before `SLMPWorker::reconnect`).
+    Disconnected { key: ConnectionKey, reason: String },
+    /// A reconnection by the keep-alive or `SLMPWorker::reconnect`, numbered from 1 since the link was last up.
+    ReconnectAttempt { key: ConnectionKey, attempt: u32 },
+    /// The link is up again after it failed.
+    Reconnected { key: ConnectionKey },
+    /// The monitor targets were registered, also again after a reconnection. `points` is the number of targets.
+    MonitorRegistered { key: ConnectionKey, points: usize },
+    /// A request of the monitor loop failed (see `SLMPWorker::subscribe_errors`).
+    CyclicError { key: ConnectionKey, error: Arc<std::io::Error> },
+    /// A write of the output image failed (see `SLMPWorker::register_output_image`).
+    OutputError { key: ConnectionKey, error: Arc<std::io::Error> },
+}
+
+/// Sender of the `ManagerEvent`s of a worker, which records the state of the link in its status along with them.
+/// A worker not made by a manager has none.
+#[derive(Clone, Default)]
+struct WorkerEvents {
+    sender: Option<(ConnectionKey, broadcast::Sender<ManagerEvent>)>,
+    /// Reconnections tried since the link was last up.
+    reconnect_attempts: Arc<AtomicU32>,
+    /// Notified when the link is up again after it failed, so that the output image is written at once.
+    reconnected: Arc<Notify>,
+}
+
+impl WorkerEvents {
+    fn emit(&self, event: impl FnOnce(ConnectionKey) -> ManagerEvent) {
+        // No receiver is not an error.
+        if let Some((key, sender)) = &self.sender {
+            let _ = sender.send(event(*key));
+        }
+    }
+
+    async fn record_success(&self, status: &RwLock<ConnectionStatus>) {
+        let mut status = status.write().await;
+        let was_down = !status.connected;
+        status.record_success();
+        if was_down {
+            self.reconnect_attempts.store(0, Ordering::Relaxed);
+            self.emit(|key| ManagerEvent::Reconnected { key });
+            self.reconnected.notify_one();
+        }
+    }
+
+    async fn record_error(&self, status: &RwLock<ConnectionStatus>, context: &str, error: &std::io::Error) -> u32 {
+        let mut status = status.write().await;
+        let was_up = status.connected;
+        let consecutive_failures = status.record_error(context, error);
+        if was_up && !status.connected {
+            self.emit(|key| ManagerEvent::Disconnected { key, reason: format!("{context}: {error}") });
+        }
+        consecutive_failures
+    }
+
+    async fn record_disconnect(&self, status: &RwLock<ConnectionStatus>, reason: &str) {
+        let mut status = status.write().await;
+        if status.connected {
+            status.connected = false;
+            self.emit(|key| ManagerEvent::Disconnected { key, reason: reason.to_string() });
+        }
+    }
+}
+
+/// Error of a request to a connection of `connect_lazy` which is not established yet, carried by the returned
+/// `std::io::Error` (of the kind `NotConnected`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotConnectedYet;
+
+impl NotConnectedYet {
+    fn into_io_error() -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::NotConnected, Self)
+    }
+
+    /// Whether an error returned from a worker is this one.
+    pub fn from_io_error(error: &std::io::Error) -> Option<&Self> {
+        error.get_ref()?.downcast_ref::<Self>()
+    }
+}
+
+impl std::fmt::Display for NotConnectedYet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Not connected yet")
+    }
+}
+
+impl std::error::Error for NotConnectedYet {}
+
+impl ConnectionStatus {
+    fn record_success(&mut self) {
+        self.connected = true;
+        self.last_success = Some(SystemTime::now());
+        self.consecutive_failures = 0;
+    }
+
+    fn record_error(&mut self, context: &str, error: &std::io::Error) -> u32 {
+        if SLMPEndCode::from_io_error(error).is_none() {
+            self.connected = false;
+        }
+        self.last_error = Some((SystemTime::now(), format!("{context}: {error}")));
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        self.consecutive_failures
+    }
+}
+
+/// What the monitor loop does when the cyclic task (or the receiver of `connect_stream`) does not keep up with it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackPressure {
+    /// The monitor loop waits until there is room, slowing the polling.
+    Block,
+    /// The oldest pending data is discarded.
+    DropOldest,
+    /// The new data is discarded.
+    DropNewest,
+}
+
+/// Batches of the monitor loop waiting for the cyclic task, which runs on a task of its own
+/// so that a slow task does not stretch the cycle. There is one producer and one consumer.
+struct TaskQueue {
+    batches: std::sync::Mutex<VecDeque<Vec<PLCData>>>,
+    capacity: usize,
+    back_pressure: BackPressure,
+    pushed: Notify,
+    popped: Notify,
+}
+
+impl TaskQueue {
+    fn new(capacity: usize, back_pressure: BackPressure) -> Self {
+        Self {
+            batches: std::sync::Mutex::new(VecDeque::with_capacity(capacity.max(1))),
+            capacity: capacity.max(1),
+            back_pressure,
+            pushed: Notify::new(),
+            popped: Notify::new(),
+        }
+    }
+
+    /// Queue `batch` and return whether a batch was discarded. With `BackPressure::Block` it waits for room instead.
+    async fn push(&self, batch: Vec<PLCData>) -> bool {
+        loop {
+            {
+                let mut batches = self.batches.lock().unwrap();
+                if batches.len() < self.capacity {
+                    batches.push_back(batch);
+                    self.pushed.notify_one();
+                    return false;
+                }
+                match self.back_pressure {
+                    BackPressure::Block => {},
+                    BackPressure::DropOldest => {
+                        batches.pop_front();
+                        batches.push_back(batch);
+                        self.pushed.notify_one();
+                        return true;
+                    },
+                    BackPressure::DropNewest => return true,
+                }
+            }
+            // A pop in between leaves a permit, so it is not missed.
+            self.popped.notified().await;
+        }
+    }
+
+    async fn pop(&self) -> Vec<PLCData> {
+        loop {
+            if let Some(batch) = self.batches.lock().unwrap().pop_front() {
+                self.popped.notify_one();
+                return batch;
+            }
+            self.pushed.notified().await;
+        }
+    }
+}
+
+enum MonitorReceiverInner {
+    Queue(mpsc::Receiver<Vec<PLCData>>),
+    DropOldest(broadcast::Receiver<Vec<PLCData>>),
+}
+
+/// Receiving end of `connect_stream`. Each item is the result of one cyclic monitor read.
+pub struct MonitorReceiver {
+    inner: MonitorReceiverInner,
+}
+
+impl MonitorReceiver {
+    /// Wait for the next data. `None` after the connection is closed.
+    pub async fn recv(&mut self) -> Option<Vec<PLCData>> {
+        match &mut self.inner {
+            MonitorReceiverInner::Queue(receiver) => receiver.recv().await,
+            MonitorReceiverInner::DropOldest(receiver) => loop {
+                match receiver.recv().await {
+                    Ok(data) => break Some(data),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break None,
+                }
+            },
+        }
+    }
+}
+
+/// Run `f` on `workers` in tasks of their own, at most `concurrency_limit` at a time.
+async fn for_each_worker<T, F, Fut>(workers: HashMap<ConnectionKey, Arc<SLMPWorker>>, concurrency_limit: usize, f: F) -> HashMap<ConnectionKey, T>
+    where
+        F: Fn(ConnectionKey, Arc<SLMPWorker>) -> Fut + std::marker::Send + Sync + 'static,
+        Fut: std::future::Future<Output = T> + std::marker::Send + 'static,
+        T: std::marker::Send + 'static,
+{
+    let semaphore = Arc::new(Semaphore::new(concurrency_limit.clamp(1, Semaphore::MAX_PERMITS)));
+    let f = Arc::new(f);
+    let mut tasks: JoinSet<(ConnectionKey, T)> = JoinSet::new();
+    for (key, worker) in workers {
+        let (semaphore, f) = (semaphore.clone(), f.clone());
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            (key, f(key, worker).await)
+        });
+    }
+
+    let mut ret = HashMap::new();
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok((key, output)) = joined {
+            ret.insert(key, output);
+        }
+    }
+    ret
+}
+
+/// A monitor target sent to the monitor loop.
+#[derive(Clone)]
+struct MonitorTarget {
+    device: TypedDevice,
+    change_filter: ChangeFilter,
+    cycle_ms: Option<u64>,
+}
+
+impl From<&MonitorRequest<'_>> for MonitorTarget {
+    fn from(value: &MonitorRequest<'_>) -> Self {
+        Self { device: value.monitor_device, change_filter: value.change_filter, cycle_ms: value.cycle_ms }
+    }
+}
+
+enum TargetUpdate {
+    Replace(MonitorTargets),
+    Add(MonitorTargets),
+    Remove(Vec<TypedDevice>),
+    /// A device read for `SLMPWorker::subscribe`, apart from the monitor targets.
+    Subscribe(TypedDevice),
+    Unsubscribe(TypedDevice),
+}
+
+impl TargetUpdate {
+    /// The monitor targets and the subscribed devices after the update.
+    fn apply(self, current: &[MonitorTarget], subscribed: &[TypedDevice]) -> (MonitorTargets, Vec<TypedDevice>) {
+        match self {
+            Self::Subscribe(device) => {
+                let mut ret: Vec<TypedDevice> = subscribed.to_vec();
+                if !ret.contains(&device) {
+                    ret.push(device);
+                }
+                (current.to_vec(), ret)
+            },
+            Self::Unsubscribe(device) => (current.to_vec(), subscribed.iter().filter(|&&x| x != device).copied().collect()),
+            update => (update.apply_targets(current), subscribed.to_vec()),
+        }
+    }
+
+    fn apply_targets(self, current: &[MonitorTarget]) -> MonitorTargets {
+        match self {
+            Self::Subscribe(_) | Self::Unsubscribe(_) => current.to_vec(),
+            // A device given more than once is a single target, as a device added again.
+            Self::Replace(targets) => Self::Add(targets).apply_targets(&[]),
+            Self::Add(targets) => {
+                // A device already registered takes the new filter and interval.
+                let mut ret: MonitorTargets = current.to_vec();
+                let mut index: HashMap<TypedDevice, usize> = ret.iter().enumerate().map(|(i, x)| (x.device, i)).collect();
+                for target in targets {
+                    match index.get(&target.device) {
+                        Some(&i) => ret[i] = target,
+                        None => {
+                            index.insert(target.device, ret.len());
+                            ret.push(target);
+                        },
+                    }
+                }
+                ret
+            },
+            Self::Remove(devices) => {
+                let devices: HashSet<TypedDevice> = devices.into_iter().collect();
+                current.iter().filter(|x| !devices.contains(&x.device)).cloned().collect()
+            },
+        }
+    }
+}
+
+/// A change of the monitor targets. The effective targets are replied if `reply` is given.
+struct TargetRequest {
+    update: TargetUpdate,
+    reply: Option<oneshot::Sender<std::io::Result<Vec<TypedDevice>>>>,
+}
+
+/// A write queued by `SLMPWorker::queue_write`, or a flush of the writes queued before it.
+enum WriteCommand {
+    Write(DeviceData, WriteReply),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Whether two writes touch a common point, so that they must not go in one random write.
+fn overlaps(a: &DeviceData, b: &DeviceData) -> bool {
+    let span = |x: &DeviceData| x.device.address..x.device.address + x.device.device_type.points(x.data.get_type());
+    let (a_span, b_span) = (span(a), span(b));
+    a.device.device_type == b.device.device_type && a_span.start < b_span.end && b_span.start < a_span.end
+}
+
+/// Send the queued writes in batches through the ad-hoc requests of the monitor loop.
+/// A batch is sent when `window` has passed since its first write, when it reaches `max_points` access points,
+/// on a flush, or before a write overlapping one in it, so that the writes to a device are done in order.
+async fn write_queue(mut receiver: UnboundedReceiver<WriteCommand>, requests: UnboundedSender<ClientRequest>, settings: Arc<std::sync::Mutex<(std::time::Duration, usize)>>) {
+    let mut next: Option<WriteCommand> = None;
+    let mut closed = false;
+
+    while !closed {
+        let Some(mut command) = next.take() else {
+            match receiver.recv().await {
+                Some(command) => next = Some(command),
+                None => break,
+            }
+            continue;
+        };
+
+        let (window, max_points) = *settings.lock().unwrap();
+        let deadline = tokio::time::Instant::now() + window;
+        let mut batch: Vec<(DeviceData, WriteReply)> = Vec::new();
+        let mut points: usize = 0;
+        let mut flushed: Option<oneshot::Sender<()>> = None;
+
+        loop {
+            match command {
+                WriteCommand::Write(data, reply) if batch.iter().any(|(x, _)| overlaps(x, &data)) => {
+                    next = Some(WriteCommand::Write(data, reply));
+                    break;
+                },
+                WriteCommand::Write(data, reply) => {
+                    points += monitor::access_points(&[TypedDevice { device: data.device, data_type: data.data.get_type() }]);
+                    batch.push((data, reply));
+                    if points >= max_points {
+                        break;
+                    }
+                },
+                WriteCommand::Flush(done) => {
+                    flushed = Some(done);
+                    break;
+                },
+            }
+
+            // Past the window, only the writes already queued join the batch (`timeout_at` polls the receiver first).
+            match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                Ok(Some(received)) => command = received,
+                Ok(None) => {
+                    closed = true;
+                    break;
+                },
+                Err(_) => break,
+            }
+        }
+
+        if !batch.is_empty() {
+            let data: Vec<DeviceData> = batch.iter().map(|(x, _)| x.clone()).collect();
+            let (reply, receiver) = oneshot::channel();
+            let request: ClientRequest = Box::new(move |client| Box::pin(async move {
+                let _ = reply.send(client.random_write(&data).await);
+            }));
+            let worker_closed = || std::io::Error::new(std::io::ErrorKind::NotConnected, "Worker closed");
+            let ret = match requests.send(request) {
+                Ok(()) => receiver.await.unwrap_or_else(|_| Err(worker_closed())),
+                Err(_) => Err(worker_closed()),
+            };
+            let ret = ret.map_err(Arc::new);
+            for (_, reply) in batch {
+                let _ = reply.send(ret.clone());
+            }
+        }
+        if let Some(done) = flushed {
+            let _ = done.send(());
+        }
+    }
+}
+
+/// Devices written as a whole every `period` by `output_refresh`, with the values of `SLMPWorker::update_output`.
+struct OutputImage {
+    period: std::time::Duration,
+    data: Vec<DeviceData>,
+}
+
+/// The writes of an output image: bulk writes of the runs of adjacent word devices, and random writes of the others
+/// within the access points of a random write with double words only each.
+fn output_writes(cpu: &CPU, data: &[DeviceData]) -> OutputWrites {
+    let words = |x: &DeviceData| crate::mapping::field_words(x.data.get_type());
+
+    let mut sorted: Vec<&DeviceData> = data.iter().collect();
+    sorted.sort_by_key(|x| (x.device.device_type as usize, x.device.address));
+
+    let mut runs: Vec<(Device, usize, Vec<&DeviceData>)> = Vec::new();
+    for x in sorted {
+        let word_data: bool = x.device.device_type.is_word_device() && !matches!(x.data, TypedData::Bool(_));
+        match runs.last_mut() {
+            Some((start, end, run)) if word_data
+                && start.device_type == x.device.device_type
+                && *end == x.device.address
+                && *end + words(x) - start.address <= crate::commands::device_access::BULK_MAX_WORD_POINTS =>
+            {
+                *end += words(x);
+                run.push(x);
+            },
+            _ => runs.push((x.device, if word_data { x.device.address + words(x) } else { usize::MAX }, vec![x])),
+        }
+    }
+
+    // A run of a single device costs a request of its own; it joins the random writes instead.
+    let (bulk, random): (Vec<_>, Vec<_>) = runs.into_iter().partition(|x| x.2.len() > 1);
+    let bulk = bulk.into_iter()
+        .map(|(start, _, run)| (start, run.into_iter().map(|x| x.data.clone()).collect()))
+        .collect();
+    let random: Vec<DeviceData> = random.into_iter().flat_map(|x| x.2).cloned().collect();
+    let devices: Vec<TypedDevice> = random.iter().map(|x| TypedDevice { device: x.device, data_type: x.data.get_type() }).collect();
+    let random = monitor::split_batches(&devices, random_write_max_access_points(cpu))
+        .into_iter()
+        .map(|batch| random[batch].to_vec())
+        .collect();
+    (bulk, random)
+}
+
+/// Write the output image through the ad-hoc requests of the monitor loop: every period, at once when it is registered,
+/// and at once when the link is up again after it failed. The failures go to the `ManagerEvent`s.
+async fn output_refresh(cpu: CPU, image: Arc<std::sync::Mutex<Option<OutputImage>>>, changed: Arc<Notify>, requests: UnboundedSender<ClientRequest>, status: Arc<RwLock<ConnectionStatus>>, events: WorkerEvents, missed_tick_behavior: MissedTickBehavior) {
+    let mut interval: Option<tokio::time::Interval> = None;
+
+    loop {
+        tokio::select! {
+            _ = changed.notified() => {
+                // The first tick of the new interval is at once.
+                interval = image.lock().unwrap().as_ref().map(|x| {
+                    let mut interval = tokio::time::interval(x.period);
+                    interval.set_missed_tick_behavior(missed_tick_behavior);
+                    interval
+                });
+                continue;
+            },
+            _ = events.reconnected.notified() => {},
+            Some(_) = async { interval.as_mut()?.tick().await.into() } => {},
+        }
+
+        let Some((bulk, random)) = image.lock().unwrap().as_ref().map(|x| output_writes(&cpu, &x.data)) else {
+            continue;
+        };
+        let (reply, receiver) = oneshot::channel();
+        let request: ClientRequest = Box::new(move |client| Box::pin(async move {
+            let ret = async {
+                for (start_device, data) in &bulk {
+                    client.bulk_write(*start_device, data).await?;
+                }
+                for data in &random {
+                    client.random_write(data).await?;
+                }
+                Ok(())
+            };
+            let _ = reply.send(ret.await);
+        }));
+        if requests.send(request).is_err() {
+            break;
+        }
+
+        match receiver.await {
+            Ok(Ok(())) => events.record_success(&status).await,
+            Ok(Err(e)) => {
+                events.record_error(&status, "Output write failed", &e).await;
+                let error = Arc::new(e);
+                events.emit(|key| ManagerEvent::OutputError { key, error });
+            },
+            Err(_) => break,
+        }
+    }
+}
+
+/// Monitor targets polled at the same interval, in the order of registration.
+/// The devices are read in batches within `monitor_max_access_points` of the CPU.
+/// The first batch of the group at the connection cycle is read by monitor (0x0801/0x0802), the others by random read (0x0403).
+struct MonitorGroup {
+    period: std::time::Duration,
+    next_due: tokio::time::Instant,
+    monitored: bool,
+    batches: Vec<std::ops::Range<usize>>,
+    devices: Vec<TypedDevice>,
+    /// `None` for a device read only for the subscriptions, which is not passed to the cyclic task.
+    change_filters: Vec<Option<ChangeFilter>>,
+    last_values: Vec<Option<TypedData>>,
+    cycle: u32,
+    /// Monitor read frame and response buffer, built again with the group when the devices are registered.
+    monitor_read_buffer: MonitorReadBuffer,
+    /// Values of the last read, in the order of `devices`.
+    values: Vec<DeviceData>,
+}
+
+impl MonitorGroup {
+    /// Group targets by interval, and the subscribed devices which are not targets at the connection cycle.
+    /// Intervals already polled keep their schedule.
+    fn build(cpu: &CPU, targets: &[MonitorTarget], subscribed: &[TypedDevice], cycle: std::time::Duration, previous: &[MonitorGroup]) -> Vec<Self> {
+        let now = tokio::time::Instant::now();
+        let mut groups: Vec<Self> = Vec::new();
+
+        let subscribed_only = subscribed.iter()
+            .filter(|&&device| !targets.iter().any(|x| x.device == device))
+            .map(|&device| (device, None, None));
+        let devices = targets.iter().map(|x| (x.device, Some(x.change_filter), x.cycle_ms)).chain(subscribed_only);
+
+        for (device, change_filter, cycle_ms) in devices {
+            // A zero period would never leave the schedule loop.
+            let period = cycle_ms
+                .map(std::time::Duration::from_millis)
+                .unwrap_or(cycle)
+                .max(std::time::Duration::from_millis(1));
+
+            let group = match groups.iter().position(|x| x.period == period) {
+                Some(i) => &mut groups[i],
+                None => {
+                    let next_due = previous.iter().find(|x| x.period == period).map_or(now, |x| x.next_due);
+                    groups.push(Self {
+                        period,
+                        next_due,
+                        monitored: period == cycle,
+                        batches: Vec::new(),
+                        devices: Vec::new(),
+                        change_filters: Vec::new(),
+                        last_values: Vec::new(),
+                        cycle: 0,
+                        monitor_read_buffer: MonitorReadBuffer::new(),
+                        values: Vec::new(),
+                    });
+                    groups.last_mut().unwrap()
+                },
+            };
+            group.devices.push(device);
+            group.change_filters.push(change_filter);
+            group.last_values.push(None);
+        }
+
+        for group in &mut groups {
+            group.batches = monitor::split_batches(&group.devices, monitor_max_access_points(cpu));
+        }
+
+        groups
+    }
+
+    /// Devices registered by monitor register.
+    fn monitored_devices(&self) -> &[TypedDevice] {
+        match (self.monitored, self.batches.first()) {
+            (true, Some(batch)) => &self.devices[batch.clone()],
+            _ => &[],
+        }
+    }
+
+    /// Read the devices into `values`. The monitor read of a group of a single batch allocates nothing
+    /// (but for `String` values) after the first cycle; the batches read by random read do.
+    async fn read(&mut self, client: &SLMPClient, monitor_target: &RwLock<MonitorList>, stats: &std::sync::Mutex<WorkerStats>) -> std::io::Result<()> {
+        self.values.clear();
+        self.values.reserve(self.devices.len());
+
+        for (i, batch) in self.batches.iter().enumerate() {
+            let (sent, received) = client.transferred_bytes();
+            let start = tokio::time::Instant::now();
+
+            let ret = if self.monitored && i == 0 {
+                client.monitor_read_with(&*monitor_target.read().await, &mut self.monitor_read_buffer).await
+                    .map(|values| self.values.extend_from_slice(values))
+            } else {
+                client.random_read(&self.devices[batch.clone()]).await
+                    .map(|values| self.values.extend(values))
+            };
+
+            let transferred = client.transferred_bytes();
+            stats.lock().unwrap().record(start.elapsed(), &ret, (transferred.0 - sent, transferred.1 - received));
+            ret?;
+        }
+
+        Ok(())
+    }
+
+    fn schedule_next(&mut self, now: tokio::time::Instant, missed_tick_behavior: MissedTickBehavior) {
+        match missed_tick_behavior {
+            MissedTickBehavior::Burst => self.next_due += self.period,
+            MissedTickBehavior::Delay => self.next_due = now + self.period,
+            _ => while self.next_due <= now {
+                self.next_due += self.period;
+            },
+        }
+    }
+
+    /// The values of the last read passing their change filters (all of them on a snapshot cycle), as the data of `key`.
+    /// The returned vector is the only allocation, and none if no value passes.
+    fn filter(&mut self, snapshot_cycles: u32, key: ConnectionKey) -> Vec<PLCData> {
+        let snapshot = snapshot_cycles != 0 && self.cycle.is_multiple_of(snapshot_cycles);
+        self.cycle = self.cycle.wrapping_add(1);
+
+        let route = key.route();
+        self.values
+            .iter()
+            .zip(&self.change_filters)
+            .zip(&mut self.last_values)
+            .filter_map(|((device_data, change_filter), last_value)| {
+                let change_filter = change_filter.as_ref()?;
+                let passes = snapshot || change_filter.passes(last_value.as_ref(), &device_data.data);
+                passes.then(|| {
+                    *last_value = Some(device_data.data.clone());
+                    PLCData { socket_addr: key.socket_addr, route, device_data: device_data.clone() }
+                })
+            })
+            .collect()
+    }
+
+    /// Keep the values of the last read as the latest ones, read at `at`. The lock is held only to copy them in.
+    fn cache(&self, latest: &std::sync::RwLock<LatestValues>, at: SystemTime) {
+        let mut latest = latest.write().unwrap();
+        for (device, device_data) in self.devices.iter().zip(&self.values) {
+            latest.insert(*device, (device_data.data.clone(), at));
+        }
+    }
+
+    /// Send the values of the last read to their subscribers, if they changed.
+    fn publish(&self, subscriptions: &std::sync::Mutex<Subscriptions>) {
+        let subscriptions = subscriptions.lock().unwrap();
+        if subscriptions.is_empty() {
+            return;
+        }
+        for device_data in &self.values {
+            if let Some((data_type, sender)) = subscriptions.get(&device_data.device)
+                && *data_type == device_data.data.get_type()
+            {
+                sender.send_if_modified(|x| {
+                    let modified = *x != device_data.data;
+                    if modified {
+                        *x = device_data.data.clone();
+                    }
+                    modified
+                });
+            }
+        }
+    }
+}
+
+pub struct SLMPWorker {
+    client: Arc<SLMPClient>,
+    /// Time of the last (re)connection.
+    connected_at: Arc<std::sync::Mutex<SystemTime>>,
+    status: Arc<RwLock<ConnectionStatus>>,
+    stats: Arc<std::sync::Mutex<WorkerStats>>,
+    error_sender: broadcast::Sender<MonitorError>,
+    monitor_handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+    monitor_target: Arc<RwLock<MonitorList>>,
+    targets: Arc<RwLock<MonitorTargets>>,
+    sender_targets: Arc<Mutex<Option<UnboundedSender<TargetRequest>>>>,
+    sender_requests: Arc<Mutex<Option<UnboundedSender<ClientRequest>>>>,
+    sender_writes: Arc<Mutex<Option<UnboundedSender<WriteCommand>>>>,
+    /// Window and access points of a batch of `queue_write`.
+    write_coalescing: Arc<std::sync::Mutex<(std::time::Duration, usize)>>,
+    subscriptions: Arc<std::sync::Mutex<Subscriptions>>,
+    latest: Arc<std::sync::RwLock<LatestValues>>,
+    output_image: Arc<std::sync::Mutex<Option<OutputImage>>>,
+    /// Notified when the output image is registered or removed.
+    output_changed: Arc<Notify>,
+    /// Set while a connection of `connect_lazy` is being established.
+    connecting: Arc<AtomicBool>,
+    events: WorkerEvents,
+    snapshot_cycles: Arc<AtomicU32>,
+    cancel_token: CancellationToken,
+}
+
+impl SLMPWorker {
+    pub fn new(client: Arc<SLMPClient>) -> Self{
+        // Writes of a batch go in a random write, so that any mix of word sizes fits in one by default.
+        let write_batch_points: usize = random_write_max_access_points(&client.cpu());
+        Self {
+            client,
+            connected_at: Arc::new(std::sync::Mutex::new(SystemTime::now())),
+            status: Arc::new(RwLock::new(ConnectionStatus {
+                connected: true,
+                last_success: None,
+                last_error: None,
+                registered_monitor_points: 0,
+                poll_cycle_ms: 0,
+                consecutive_failures: 0,
+            })),
+            stats: Arc::new(std::sync::Mutex::new(WorkerStats::default())),
+            error_sender: broadcast::channel(ERROR_CHANNEL_CAPACITY).0,
+            monitor_handle: Arc::new(Mutex::new(None)),
+            monitor_target: Arc::new(RwLock::new(MonitorList::new())),
+            targets: Arc::new(RwLock::new(Vec::new())),
+            sender_targets: Arc::new(Mutex::new(None)),
+            sender_requests: Arc::new(Mutex::new(None)),
+            sender_writes: Arc::new(Mutex::new(None)),
+            write_coalescing: Arc::new(std::sync::Mutex::new((std::time::Duration::ZERO, write_batch_points))),
+            subscriptions: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            latest: Arc::new(std::sync::RwLock::new(HashMap::new())),
+            output_image: Arc::new(std::sync::Mutex::new(None)),
+            output_changed: Arc::new(Notify::new()),
+            connecting: Arc::new(AtomicBool::new(false)),
+            events: WorkerEvents::default(),
+            snapshot_cycles: Arc::new(AtomicU32::new(0)),
+            cancel_token: CancellationToken::new(),
+        }
+    }
+
+    pub async fn close(&self) {
+        self.close_with_timeout(DEFAULT_CLOSE_TIMEOUT).await
+    }
+
+    /// Stop the monitor loop, aborting it if it does not finish within `timeout` (e.g. waiting on a dead socket),
+    /// and close the connection.
+    pub async fn close_with_timeout(&self, timeout: std::time::Duration) {
+        // Stop a spawned thread and release resources
+        self.cancel_token.cancel();
+        if let Some(mut handle) = self.monitor_handle.lock().await.take()
+            && tokio::time::timeout(timeout, &mut handle).await.is_err()
+        {
+            handle.abort();
+            let _ = handle.await;
+        }
+
+        // Close a connection. It waits for a request of another holder of the client, within `timeout`.
+        let _ = tokio::time::timeout(timeout, self.client.close()).await;
+
+        // Drop sender_targets, sender_requests and sender_writes
+        let mut sender = self.sender_targets.lock().await;
+        *sender = None;
+        let mut sender = self.sender_requests.lock().await;
+        *sender = None;
+        let mut sender = self.sender_writes.lock().await;
+        *sender = None;
+    }
+
+    /// False after the link failed (e.g. a keep-alive failed), until the connection is reestablished.
+    pub async fn is_healthy(&self) -> bool {
+        self.status.read().await.connected
+    }
+
+    pub async fn status(&self) -> ConnectionStatus {
+        self.status.read().await.clone()
+    }
+
+    pub fn stats(&self) -> WorkerStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// When the connection was established, or last reestablished by a keep-alive or `reconnect`.
+    /// Until a connection of `connect_lazy` is established, when the worker was made.
+    pub fn connected_at(&self) -> SystemTime {
+        *self.connected_at.lock().unwrap()
+    }
+
+    /// The effective monitor targets, in the order they were registered. Subscribed devices are not included.
+    pub async fn monitor_devices(&self) -> Vec<TypedDevice> {
+        self.targets.read().await.iter().map(|x| x.device).collect()
+    }
+
+    /// Whether the monitor loop is running: it was started and neither closed nor stopped.
+    pub async fn is_running(&self) -> bool {
+        !self.cancel_token.is_cancelled()
+            && self.monitor_handle.lock().await.as_ref().is_some_and(|x| !x.is_finished())
+    }
+
+    /// Close the connection, connect again and register the monitor targets again, between cyclic reads.
+    /// The result is also recorded in the `status`.
+    pub async fn reconnect(&self) -> std::io::Result<()> {
+        let monitor_target = self.monitor_target.clone();
+        let status = self.status.clone();
+        let connected_at = self.connected_at.clone();
+        let events = self.events.clone();
+        self.request(async move |client| {
+            client.close().await;
+            events.record_disconnect(&status, "Reconnect requested").await;
+            reconnect(&client, &monitor_target, &status, &connected_at, &events).await
+        }).await
+    }
+
+    /// Pass every device to the cyclic task once in `cycles` reads regardless of its `ChangeFilter`,
+    /// so that late consumers get the current values. 0 disables it (default).
+    pub fn set_snapshot_interval(&self, cycles: u32) {
+        self.snapshot_cycles.store(cycles, Ordering::Relaxed);
+    }
+
+    async fn update_targets(&self, update: TargetUpdate) -> std::io::Result<Vec<TypedDevice>> {
+        let closed = || std::io::Error::new(std::io::ErrorKind::NotConnected, "Worker closed");
+
+        let sender = self.sender_targets.lock().await.clone().ok_or_else(closed)?;
+        let (reply, receiver) = oneshot::channel();
+        sender.send(TargetRequest { update, reply: Some(reply) }).map_err(|_| closed())?;
+        receiver.await.map_err(|_| closed())?
+    }
+
+    /// Add devices to the monitor targets and register them again. The effective targets are returned.
+    /// A device already registered takes the new change filter and interval.
+    /// The `connection_props` of the requests are not used.
+    pub async fn add_monitor_targets(&self, targets: &[MonitorRequest<'_>]) -> std::io::Result<Vec<TypedDevice>> {
+        self.update_targets(TargetUpdate::Add(targets.iter().map(MonitorTarget::from).collect())).await
+    }
+
+    /// Remove devices from the monitor targets. Devices not registered are ignored.
+    pub async fn remove_monitor_targets(&self, devices: &[TypedDevice]) -> std::io::Result<Vec<TypedDevice>> {
+        self.update_targets(TargetUpdate::Remove(devices.to_vec())).await
+    }
+
+    pub async fn clear_monitor_targets(&self) -> std::io::Result<()> {
+        self.update_targets(TargetUpdate::Replace(Vec::new())).await.map(|_| ())
+    }
+
+    /// Run `task` on the client in the monitor loop, between cyclic reads.
+    /// Requests are taken after each group read, so one waits for about one read rather than contending for the client.
+    /// It fails with `NotConnectedYet` until a connection of `connect_lazy` is established.
+    pub async fn request<T, F, Fut>(&self, task: F) -> std::io::Result<T>
+        where
+            T: std::marker::Send + 'static,
+            F: FnOnce(Arc<SLMPClient>) -> Fut + std::marker::Send + 'static,
+            Fut: std::future::Future<Output = std::io::Result<T>> + std::marker::Send + 'static,
+    {
+        let closed = || std::io::Error::new(std::io::ErrorKind::NotConnected, "Worker closed");
+
+        let sender = self.sender_requests.lock().await.clone().ok_or_else(closed)?;
+        if self.connecting.load(Ordering::Acquire) {
+            return Err(NotConnectedYet::into_io_error());
+        }
+        let (reply, receiver) = oneshot::channel();
+        let request: ClientRequest = Box::new(move |client| Box::pin(async move {
+            let _ = reply.send(task(client).await);
+        }));
+        sender.send(request).map_err(|_| closed())?;
+        receiver.await.map_err(|_| closed())?
+    }
+
+    /// Read `device` on the connection of the monitor loop at the next point between cyclic reads (as `request`),
+    /// for a value fresher than `latest`. It fails with `TimedOut` if the value is not read within `timeout`;
+    /// the read is then skipped if it has not started yet.
+    pub async fn read_now(&self, device: TypedDevice, timeout: std::time::Duration) -> std::io::Result<TypedData> {
+        let closed = || std::io::Error::new(std::io::ErrorKind::NotConnected, "Worker closed");
+
+        let sender = self.sender_requests.lock().await.clone().ok_or_else(closed)?;
+        if self.connecting.load(Ordering::Acquire) {
+            return Err(NotConnectedYet::into_io_error());
+        }
+        let (reply, receiver) = oneshot::channel();
+        let request: ClientRequest = Box::new(move |client| Box::pin(async move {
+            // The caller gave up waiting.
+            if reply.is_closed() {
+                return;
+            }
+            let ret = client.random_read(&[device]).await
+                .and_then(|mut x| x.pop().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "No value read")));
+            let _ = reply.send(ret);
+        }));
+        sender.send(request).map_err(|_| closed())?;
+
+        match tokio::time::timeout(timeout, receiver).await {
+            Ok(ret) => ret.map_err(|_| closed())?.map(|x| x.data),
+            Err(_) => Err(std::io::Error::new(std::io::ErrorKind::TimedOut, format!("{} was not read within {timeout:?}", device.device))),
+        }
+    }
+
+    /// Queue a write to be sent with the other writes queued within the window of `set_write_coalescing`, as one `random_write`
+    /// (split into bit and word requests as it takes) between cyclic reads. It fails at once for a write a random write rejects.
+    /// The receiver gets the result of the batch; it is closed without a result if the worker closes first.
+    /// Writes to a device are done in the order they are queued.
+    pub async fn queue_write(&self, data: DeviceData) -> std::io::Result<oneshot::Receiver<Result<(), Arc<std::io::Error>>>> {
+        let closed = || std::io::Error::new(std::io::ErrorKind::NotConnected, "Worker closed");
+
+        if self.client.is_read_only() {
+            return Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "Write refused by a read-only client"));
+        }
+        self.client.dry_run().random_write(std::slice::from_ref(&data))?;
+        let sender = self.sender_writes.lock().await.clone().ok_or_else(closed)?;
+        let (reply, receiver) = oneshot::channel();
+        sender.send(WriteCommand::Write(data, reply)).map_err(|_| closed())?;
+        Ok(receiver)
+    }
+
+    /// Send the writes queued so far without waiting for the window, and wait until they are done.
+    pub async fn flush(&self) -> std::io::Result<()> {
+        let closed = || std::io::Error::new(std::io::ErrorKind::NotConnected, "Worker closed");
+
+        let sender = self.sender_writes.lock().await.clone().ok_or_else(closed)?;
+        let (done, receiver) = oneshot::channel();
+        sender.send(WriteCommand::Flush(done)).map_err(|_| closed())?;
+        receiver.await.map_err(|_| closed())
+    }
+
+    /// Gather the writes of `queue_write` for up to `window` from the first one, or until they take `max_points` access points
+    /// (default: no window, so that only the writes queued meanwhile go together, and the access points of a random write
+    /// with double words only: 137, 68 on iQ-R).
+    pub fn set_write_coalescing(&self, window: std::time::Duration, max_points: usize) {
+        *self.write_coalescing.lock().unwrap() = (window, max_points.max(1));
+    }
 
+    pub fn write_coalescing(&self) -> (std::time::Duration, usize) {
+        *self.write_coalescing.lock().unwrap()
+    }
 
-pub struct SLMPWorker {
-    client: SharedResource<SLMPClient>,
-    connected_at: SystemTime,
-    monitor_handle: Arc<Mutex<Option<JoinHandle<()>>>>,
-    monitor_target: Arc<RwLock<MonitorList>>,
-    sender_targets: Arc<Mutex<Option<UnboundedSender<Vec<TypedDevice>>>>>,
-    cancel_token: CancellationToken,
-}
+    /// Write `devices` as a whole every `cycle_ms`, replacing the output image registered before (an I/O refresh of outputs).
+    /// The image starts with the values read at once, so that nothing changes until `update_output`;
+    /// it is written right away, and again as soon as the link is up after it failed. Runs of adjacent word devices go
+    /// by bulk write and the others by random write, between cyclic reads. The failures are sent as `ManagerEvent::OutputError`.
+    /// Overlapping devices are rejected with `InvalidInput`. No devices removes the image.
+    pub async fn register_output_image(&self, devices: &[TypedDevice], cycle_ms: u64) -> std::io::Result<()> {
+        if devices.is_empty() {
+            *self.output_image.lock().unwrap() = None;
+            self.output_changed.notify_one();
+            return Ok(());
+        }
+        if self.client.is_read_only() {
+            return Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "Write refused by a read-only client"));
+        }
 
-impl SLMPWorker {
-    pub fn new(client: SharedResource<SLMPClient>) -> Self{
-        Self {
-            client,
-            connected_at: SystemTime::now(),
-            monitor_handle: Arc::new(Mutex::new(None)),
-            monitor_target: Arc::new(RwLock::new(MonitorList::new())),
-            sender_targets: Arc::new(Mutex::new(None)),
-            cancel_token: CancellationToken::new(),
+        let devices: Vec<TypedDevice> = devices.to_vec();
+        let data: Vec<DeviceData> = self.request(async move |client| client.optimized_read(&devices).await).await?;
+        for (i, x) in data.iter().enumerate() {
+            if let Some(y) = data[..i].iter().find(|y| overlaps(x, y)) {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("{} overlaps {} in the output image", x.device, y.device)));
+            }
         }
+        let dry_run = self.client.dry_run();
+        data.iter().try_for_each(|x| dry_run.random_write(std::slice::from_ref(x)).map(|_| ()))?;
+
+        // A zero period would write without pause.
+        let period = std::time::Duration::from_millis(cycle_ms).max(std::time::Duration::from_millis(1));
+        *self.output_image.lock().unwrap() = Some(OutputImage { period, data });
+        self.output_changed.notify_one();
+        Ok(())
     }
 
-    pub async fn close(&self) {
-        // Stop a spawned thread and release resources
-        self.cancel_token.cancel();
-        if let Some(handle) = self.monitor_handle.lock().await.take() {
-            let _ = handle.await;
+    /// Set the value of a device of the output image, written on the next cycle.
+    /// A device not in the image, or of another data type, is rejected with `InvalidInput`.
+    pub fn update_output(&self, data: DeviceData) -> std::io::Result<()> {
+        let mut image = self.output_image.lock().unwrap();
+        let entry = image.as_mut()
+            .and_then(|x| x.data.iter_mut().find(|x| x.device == data.device))
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("{} is not in the output image", data.device)))?;
+        if entry.data.get_type() != data.data.get_type() {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput,
+                format!("{} is {:?} in the output image, not {:?}", data.device, entry.data.get_type(), data.data.get_type())));
+        }
+        entry.data = data.data;
+        Ok(())
+    }
+
+    /// The values of the output image, in the order of registration.
+    pub fn output_image(&self) -> Vec<DeviceData> {
+        self.output_image.lock().unwrap().as_ref().map_or_else(Vec::new, |x| x.data.clone())
+    }
+
+    /// Follow the value of `device`, read at the connection cycle (or the interval of a monitor target of the same device),
+    /// without passing it to the cyclic task. The receiver starts with a value read at once, and changes when the value does.
+    ///
+    /// The subscribers of a device share its reads; it stops being read when the last receiver is dropped.
+    /// A device already subscribed with another data type is rejected with `InvalidInput`.
+    /// The reads go on after a reconnect; the senders are dropped when the worker is closed.
+    pub async fn subscribe(&self, device: TypedDevice) -> std::io::Result<watch::Receiver<TypedData>> {
+        if let Some(receiver) = subscriber(&self.subscriptions.lock().unwrap(), device)? {
+            return Ok(receiver);
         }
 
-        // Close a connection
-        let client = self.client.lock().await;
-        client.close().await;
-        drop(client);
+        let devices = [device];
+        let initial = self.request(async move |client| client.random_read(&devices).await).await?
+            .pop()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "No value read"))?;
 
-        // Drop sender_targets
-        let mut sender = self.sender_targets.lock().await;
-        *sender = None;
+        let (sender, receiver) = {
+            let mut subscriptions = self.subscriptions.lock().unwrap();
+            if let Some(receiver) = subscriber(&subscriptions, device)? {
+                return Ok(receiver);
+            }
+            let (sender, receiver) = watch::channel(initial.data);
+            let sender = Arc::new(sender);
+            subscriptions.insert(device.device, (device.data_type, sender.clone()));
+            (sender, receiver)
+        };
+
+        if let Err(e) = self.update_targets(TargetUpdate::Subscribe(device)).await {
+            self.subscriptions.lock().unwrap().retain(|_, (_, x)| !Arc::ptr_eq(x, &sender));
+            return Err(e);
+        }
+        self.watch_subscribers(device, sender).await;
+        Ok(receiver)
+    }
+
+    /// Stop reading `device` once its receivers are dropped, unless another subscriber came meanwhile.
+    async fn watch_subscribers(&self, device: TypedDevice, sender: Arc<watch::Sender<TypedData>>) {
+        let Some(sender_targets) = self.sender_targets.lock().await.clone() else {
+            return;
+        };
+        let subscriptions = self.subscriptions.clone();
+        let cancel_token = self.cancel_token.clone();
+
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = cancel_token.cancelled() => {},
+                _ = sender.closed() => {
+                    let mut subscriptions = subscriptions.lock().unwrap();
+                    let unused = subscriptions.get(&device.device).is_some_and(|(_, x)| Arc::ptr_eq(x, &sender) && x.receiver_count() == 0);
+                    if unused {
+                        subscriptions.remove(&device.device);
+                        // Sent under the lock, so that it goes before the registration of a new subscriber.
+                        let _ = sender_targets.send(TargetRequest { update: TargetUpdate::Unsubscribe(device), reply: None });
+                    }
+                },
+            }
+        });
+    }
+
+    /// The value of `device` at its last cyclic read (as a monitor target or a subscribed device), and when it was read.
+    /// It is kept after the read fails, so the time tells how old it is. `None` for a device which is not read,
+    /// or not read yet; a device removed from the targets is forgotten.
+    pub fn latest(&self, device: &TypedDevice) -> Option<(TypedData, SystemTime)> {
+        self.latest.read().unwrap().get(device).cloned()
+    }
+
+    /// `latest` of every device read.
+    pub fn latest_all(&self) -> HashMap<TypedDevice, (TypedData, SystemTime)> {
+        self.latest.read().unwrap().clone()
+    }
+
+    /// Number of failed requests in a row. 0 after a successful request.
+    pub async fn consecutive_failures(&self) -> u32 {
+        self.status.read().await.consecutive_failures
+    }
+
+    /// Receive the errors of the monitor loop (failed monitor register/read) from now on.
+    pub fn subscribe_errors(&self) -> broadcast::Receiver<MonitorError> {
+        self.error_sender.subscribe()
+    }
+}
+
+/// A new receiver of a subscribed device, `None` if it is not subscribed.
+fn subscriber(subscriptions: &Subscriptions, device: TypedDevice) -> std::io::Result<Option<watch::Receiver<TypedData>>> {
+    match subscriptions.get(&device.device) {
+        Some((data_type, sender)) if *data_type == device.data_type => Ok(Some(sender.subscribe())),
+        Some((data_type, _)) => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput,
+            format!("{} is subscribed as {data_type:?}, not {:?}", device.device, device.data_type))),
+        None => Ok(None),
+    }
+}
+
+/// Record a failure of the monitor loop and notify the subscribers.
+async fn report_error(status: &RwLock<ConnectionStatus>, error_sender: &broadcast::Sender<MonitorError>, events: &WorkerEvents, key: ConnectionKey, context: &str, error: std::io::Error) {
+    let consecutive_failures = events.record_error(status, context, &error).await;
+    let error = Arc::new(error);
+    events.emit(|key| ManagerEvent::CyclicError { key, error: error.clone() });
+    // No subscriber is not an error.
+    let _ = error_sender.send(MonitorError { socket_addr: key.socket_addr, route: key.route(), error, consecutive_failures });
+}
+
+/// Send an echo and reconnect if it fails.
+/// Monitor targets are registered again on the new connection since the CPU forgets them with the old one.
+async fn keep_alive(client: &SLMPClient, monitor_target: &RwLock<MonitorList>, status: &RwLock<ConnectionStatus>, connected_at: &std::sync::Mutex<SystemTime>, events: &WorkerEvents) {
+
+    match client.echo().await {
+        Ok(()) => {
+            events.record_success(status).await;
+            return;
+        },
+        Err(e) => {
+            events.record_error(status, "Keep-alive failed", &e).await;
+        },
+    }
+
+    let _ = reconnect(client, monitor_target, status, connected_at, events).await;
+}
+
+/// Connect and register the monitor targets again, recording the result in `status`.
+async fn reconnect(client: &SLMPClient, monitor_target: &RwLock<MonitorList>, status: &RwLock<ConnectionStatus>, connected_at: &std::sync::Mutex<SystemTime>, events: &WorkerEvents) -> std::io::Result<()> {
+    let attempt: u32 = events.reconnect_attempts.fetch_add(1, Ordering::Relaxed).saturating_add(1);
+    events.emit(|key| ManagerEvent::ReconnectAttempt { key, attempt });
+
+    let reconnected = match client.connect().await {
+        Ok(()) => {
+            *connected_at.lock().unwrap() = SystemTime::now();
+            register_again(client, monitor_target).await
+        },
+        Err(e) => Err(e),
+    };
+
+    match &reconnected {
+        Ok(registered) => {
+            events.record_success(status).await;
+            if *registered {
+                let points: usize = status.read().await.registered_monitor_points;
+                events.emit(|key| ManagerEvent::MonitorRegistered { key, points });
+            }
+        },
+        Err(e) => {
+            events.record_error(status, "Reconnect failed", e).await;
+        },
+    }
+    reconnected.map(|_| ())
+}
+
+/// Register the devices of `monitor_target` again in the order of registration, as the CPU forgot them.
+/// False if there was nothing to register.
+async fn register_again(client: &SLMPClient, monitor_target: &RwLock<MonitorList>) -> std::io::Result<bool> {
+    let devices: Vec<TypedDevice> = monitor_target.read().await.devices();
+    if devices.is_empty() {
+        return Ok(false);
     }
+
+    *monitor_target.write().await = client.monitor_register(&devices).await?;
+    Ok(true)
 }
 
 pub struct SLMPConnectionManager {
-    pub connections: SharedResource<ConnectionMap>,
+    /// Read-locked by the lookups and write-locked only to insert or remove a worker; neither is held across a request.
+    pub connections: Arc<RwLock<ConnectionMap>>,
+    /// Monitor targets of closed connections, registered again when they connect.
+    saved_targets: SharedResource<HashMap<ConnectionKey, MonitorTargets>>,
+    /// Names given to connections by `set_name`.
+    names: SharedResource<HashMap<String, ConnectionKey>>,
+    /// Keys of the connections by the host, port and route they were connected with.
+    /// A host name is resolved once on connect, so that the lookups do not depend on the name service afterwards.
+    addresses: SharedResource<HashMap<HostKey, ConnectionKey>>,
+    preserve_monitor_targets: AtomicBool,
+    close_timeout_ms: AtomicU64,
+    retry_policy: std::sync::Mutex<RetryPolicy>,
+    task_queue: std::sync::Mutex<(usize, BackPressure)>,
+    missed_tick_behavior: std::sync::Mutex<MissedTickBehavior>,
+    connect_backoff: std::sync::Mutex<(std::time::Duration, std::time::Duration)>,
+    read_only: AtomicBool,
+    events: broadcast::Sender<ManagerEvent>,
+}
+
+impl Default for SLMPConnectionManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SLMPConnectionManager {
     pub fn new() -> Self {
         Self {
-            connections: Arc::new(Mutex::new(HashMap::new())),
+            connections: Arc::new(RwLock::new(HashMap::new())),
+            saved_targets: Arc::new(Mutex::new(HashMap::new())),
+            names: Arc::new(Mutex::new(HashMap::new())),
+            addresses: Arc::new(Mutex::new(HashMap::new())),
+            preserve_monitor_targets: AtomicBool::new(true),
+            close_timeout_ms: AtomicU64::new(DEFAULT_CLOSE_TIMEOUT.as_millis() as u64),
+            retry_policy: std::sync::Mutex::new(RetryPolicy::NONE),
+            task_queue: std::sync::Mutex::new((DEFAULT_TASK_QUEUE_CAPACITY, BackPressure::Block)),
+            missed_tick_behavior: std::sync::Mutex::new(MissedTickBehavior::Skip),
+            connect_backoff: std::sync::Mutex::new(DEFAULT_CONNECT_BACKOFF),
+            read_only: AtomicBool::new(false),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+        }
+    }
+
+    /// Receive the `ManagerEvent`s of all connections from now on. A receiver which falls behind loses the oldest events
+    /// (`RecvError::Lagged`); the monitor loops never wait for it.
+    pub fn events(&self) -> broadcast::Receiver<ManagerEvent> {
+        self.events.subscribe()
+    }
+
+    /// How long `disconnect` and `clear` wait for each monitor loop before aborting it (default: 3 s).
+    pub fn set_close_timeout(&self, timeout: std::time::Duration) {
+        self.close_timeout_ms.store(timeout.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn close_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.close_timeout_ms.load(Ordering::Relaxed))
+    }
+
+    /// Retry policy of the clients of the following connections, for the monitor loop and the requests through the workers
+    /// (default: `RetryPolicy::NONE`). See `SLMPClient::set_retry_policy`.
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        *self.retry_policy.lock().unwrap() = policy;
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        *self.retry_policy.lock().unwrap()
+    }
+
+    /// Make the clients of the following connections read-only (default: false), see `SLMPClient::set_read_only`.
+    /// The writes through the manager and its workers are refused with `PermissionDenied`, and so are those of `operate_worker`.
+    pub fn set_read_only(&self, read_only: bool) {
+        self.read_only.store(read_only, Ordering::Relaxed);
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
+    /// Queue between the monitor loop and the cyclic task of the following connections: up to `capacity` batches of cyclic reads
+    /// wait for the task, and `back_pressure` applies beyond it (default: 16, `BackPressure::Block`).
+    /// Discarded batches are counted in `WorkerStats::dropped_batches`.
+    pub fn set_task_queue(&self, capacity: usize, back_pressure: BackPressure) {
+        *self.task_queue.lock().unwrap() = (capacity, back_pressure);
+    }
+
+    pub fn task_queue(&self) -> (usize, BackPressure) {
+        *self.task_queue.lock().unwrap()
+    }
+
+    /// How the cyclic reads and the keep-alive of the following connections catch up after a late cycle
+    /// (default: `MissedTickBehavior::Skip`, missed cycles are not read).
+    pub fn set_missed_tick_behavior(&self, behavior: MissedTickBehavior) {
+        *self.missed_tick_behavior.lock().unwrap() = behavior;
+    }
+
+    pub fn missed_tick_behavior(&self) -> MissedTickBehavior {
+        *self.missed_tick_behavior.lock().unwrap()
+    }
+
+    /// Wait after the first failed attempt of the following `connect_lazy`, doubled after each failure up to `max`
+    /// (default: 100 ms and 10 s).
+    pub fn set_connect_backoff(&self, initial: std::time::Duration, max: std::time::Duration) {
+        *self.connect_backoff.lock().unwrap() = (initial, max.max(initial));
+    }
+
+    pub fn connect_backoff(&self) -> (std::time::Duration, std::time::Duration) {
+        *self.connect_backoff.lock().unwrap()
+    }
+
+    /// Whether the monitor targets of a connection are kept after `disconnect` and registered again on `connect` (default: true).
+    /// Turning it off also forgets the targets kept so far.
+    pub async fn set_preserve_monitor_targets(&self, preserve: bool) {
+        self.preserve_monitor_targets.store(preserve, Ordering::Relaxed);
+        if !preserve {
+            self.saved_targets.lock().await.clear();
+        }
+    }
+
+    async fn save_targets(&self, key: ConnectionKey, worker: &SLMPWorker) {
+        if self.preserve_monitor_targets.load(Ordering::Relaxed) {
+            let targets = worker.targets.read().await.clone();
+            self.saved_targets.lock().await.insert(key, targets);
+        }
+    }
+
+    pub async fn connect<T, F, Fut>(&self, connection_props: &SLMP4EConnectionProps, cyclic_task: F, cycle_ms: u64) -> std::io::Result<()>
+        where
+            F: Fn(Vec<PLCData>) -> Fut + std::marker::Send + 'static,
+            Fut: std::future::Future<Output = std::io::Result<T>> + std::marker::Send,
+    {
+        self.connect_worker(connection_props, cyclic_task, cycle_ms, None, false).await
+    }
+
+    /// Same as `connect`, but the connection is made in the background, so that it succeeds while the PLC is offline.
+    /// The connection is tried again after each failure (see `set_connect_backoff`) until it is established or closed,
+    /// and the monitor loop starts then. Meanwhile the status is not connected, the failures go to `subscribe_errors`,
+    /// the monitor targets are kept to be registered once connected, and requests fail with `NotConnectedYet`.
+    pub async fn connect_lazy<T, F, Fut>(&self, connection_props: &SLMP4EConnectionProps, cyclic_task: F, cycle_ms: u64) -> std::io::Result<()>
+        where
+            F: Fn(Vec<PLCData>) -> Fut + std::marker::Send + 'static,
+            Fut: std::future::Future<Output = std::io::Result<T>> + std::marker::Send,
+    {
+        self.connect_worker(connection_props, cyclic_task, cycle_ms, None, true).await
+    }
+
+    /// Same as `connect`, but an echo is sent when the connection has been idle for `keep_alive`.
+    /// If the echo fails, the worker is marked unhealthy and reconnects (see `SLMPWorker::is_healthy`).
+    pub async fn connect_with_keep_alive<T, F, Fut>(&self, connection_props: &SLMP4EConnectionProps, cyclic_task: F, cycle_ms: u64, keep_alive: std::time::Duration) -> std::io::Result<()>
+        where
+            F: Fn(Vec<PLCData>) -> Fut + std::marker::Send + 'static,
+            Fut: std::future::Future<Output = std::io::Result<T>> + std::marker::Send,
+    {
+        self.connect_worker(connection_props, cyclic_task, cycle_ms, Some(keep_alive), false).await
+    }
+
+    /// Same as `connect`, but the data is delivered to the returned receiver instead of a closure.
+    /// `capacity` is the number of pending reads held for the receiver.
+    pub async fn connect_stream(&self, connection_props: &SLMP4EConnectionProps, cycle_ms: u64, capacity: usize, back_pressure: BackPressure) -> std::io::Result<MonitorReceiver> {
+        let closed = || std::io::Error::new(std::io::ErrorKind::BrokenPipe, "Receiver dropped");
+
+        match back_pressure {
+            BackPressure::Block => {
+                let (sender, receiver) = mpsc::channel::<Vec<PLCData>>(capacity);
+                let cyclic_task = move |data| {
+                    let sender = sender.clone();
+                    async move { sender.send(data).await.map_err(|_| closed()) }
+                };
+                self.connect_worker(connection_props, cyclic_task, cycle_ms, None, false).await?;
+                Ok(MonitorReceiver { inner: MonitorReceiverInner::Queue(receiver) })
+            },
+            BackPressure::DropNewest => {
+                let (sender, receiver) = mpsc::channel::<Vec<PLCData>>(capacity);
+                let cyclic_task = move |data| {
+                    let ret = match sender.try_send(data) {
+                        Err(mpsc::error::TrySendError::Closed(_)) => Err(closed()),
+                        _ => Ok(()),
+                    };
+                    async move { ret }
+                };
+                self.connect_worker(connection_props, cyclic_task, cycle_ms, None, false).await?;
+                Ok(MonitorReceiver { inner: MonitorReceiverInner::Queue(receiver) })
+            },
+            BackPressure::DropOldest => {
+                let (sender, receiver) = broadcast::channel::<Vec<PLCData>>(capacity);
+                let cyclic_task = move |data| {
+                    let ret = sender.send(data).map(|_| ()).map_err(|_| closed());
+                    async move { ret }
+                };
+                self.connect_worker(connection_props, cyclic_task, cycle_ms, None, false).await?;
+                Ok(MonitorReceiver { inner: MonitorReceiverInner::DropOldest(receiver) })
+            },
         }
     }
 
-    pub async fn connect<'a, T, F, Fut>(&self, connection_props: &'a SLMP4EConnectionProps, cyclic_task: F, cycle_ms: u64) -> std::io::Result<()>
+    async fn connect_worker<T, F, Fut>(&self, connection_props: &SLMP4EConnectionProps, cyclic_task: F, cycle_ms: u64, keep_alive_interval: Option<std::time::Duration>, lazy: bool) -> std::io::Result<()>
         where
             F: Fn(Vec<PLCData>) -> Fut + std::marker::Send + 'static,
             Fut: std::future::Future<Output = std::io::Result<T>> + std::marker::Send,
     {
-        let socket_addr: SocketAddr = SocketAddr::try_from(connection_props)?;
+        let key = resolve(connection_props).await?;
 
         // Once close a channel if exist and then wait
-        if self.disconnect(connection_props).await? == true {
+        let mut closed = self.disconnect_key(key).await;
+        if let Some(previous) = self.key_of(connection_props).await.ok().filter(|x| *x != key) {
+            closed |= self.disconnect_key(previous).await;
+        }
+        if closed {
             tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
         };
 
-        let client = SLMPClient::new(connection_props.clone());
-        client.connect().await?;
+        let mut client = SLMPClient::new(connection_props.clone());
+        client.set_retry_policy(self.retry_policy());
+        client.set_read_only(self.is_read_only())?;
+        if !lazy {
+            client.connect().await?;
+        }
 
-        let mut worker = SLMPWorker::new(Arc::new(tokio::sync::Mutex::new(client)));
+        let mut worker = SLMPWorker::new(Arc::new(client));
+        worker.events.sender = Some((key, self.events.clone()));
+        if lazy {
+            worker.connecting.store(true, Ordering::Release);
+            worker.status.write().await.connected = false;
+        }
 
-        let (sender_targets, mut receiver_targets) = unbounded_channel::<Vec<TypedDevice>>();
+        let (sender_targets, mut receiver_targets) = unbounded_channel::<TargetRequest>();
+        let (sender_requests, mut receiver_requests) = unbounded_channel::<ClientRequest>();
+        let (sender_writes, receiver_writes) = unbounded_channel::<WriteCommand>();
 
         let client = worker.client.clone();
         let monitor_target = worker.monitor_target.clone();
         let cancel_token = worker.cancel_token.clone();
+        worker.status.write().await.poll_cycle_ms = cycle_ms;
+        let status = worker.status.clone();
+        let connected_at = worker.connected_at.clone();
+        let events = worker.events.clone();
+        let error_sender = worker.error_sender.clone();
+        let snapshot_cycles = worker.snapshot_cycles.clone();
+        let worker_targets = worker.targets.clone();
+        let stats = worker.stats.clone();
+        let (queue_capacity, back_pressure) = self.task_queue();
+        let missed_tick_behavior = self.missed_tick_behavior();
+        let write_coalescing = worker.write_coalescing.clone();
+        let write_requests = sender_requests.clone();
+        let subscriptions = worker.subscriptions.clone();
+        let latest = worker.latest.clone();
+        let output_image = worker.output_image.clone();
+        let output_changed = worker.output_changed.clone();
+        let output_requests = sender_requests.clone();
+        let connecting = worker.connecting.clone();
+        let connect_backoff = lazy.then(|| self.connect_backoff());
+        let sender_registration = sender_targets.clone();
+        let cpu: CPU = connection_props.cpu;
 
         let monitor_handle = {
 
             tokio::spawn(async move {
-                let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(cycle_ms));
+                let cycle = std::time::Duration::from_millis(cycle_ms);
+                let mut keep_alive_interval = keep_alive_interval.map(|period| {
+                    let mut interval = tokio::time::interval(period);
+                    interval.set_missed_tick_behavior(missed_tick_behavior);
+                    interval
+                });
+                let mut last_activity = tokio::time::Instant::now();
+                let mut groups: Vec<MonitorGroup> = Vec::new();
+                let mut current_targets: MonitorTargets = Vec::new();
+                let mut subscribed: Vec<TypedDevice> = Vec::new();
+
+                // The cyclic task and the write queue run apart from the reads, and stop with the monitor loop as the join set is dropped.
+                let queue = Arc::new(TaskQueue::new(queue_capacity, back_pressure));
+                let mut consumer: JoinSet<()> = JoinSet::new();
+                consumer.spawn({
+                    let queue = queue.clone();
+                    async move {
+                        loop {
+                            let batch = queue.pop().await;
+                            let _ = cyclic_task(batch).await;
+                        }
+                    }
+                });
+                consumer.spawn(write_queue(receiver_writes, write_requests, write_coalescing));
+                consumer.spawn(output_refresh(cpu, output_image, output_changed, output_requests, status.clone(), events.clone(), missed_tick_behavior));
+
+                // A connection of `connect_lazy` is made here. The targets changed meanwhile are registered once connected.
+                if let Some((initial, max)) = connect_backoff {
+                    let mut backoff = initial;
+                    loop {
+                        let connected = tokio::select! {
+                            _ = cancel_token.cancelled() => return,
+                            ret = client.connect() => ret,
+                        };
+                        match connected {
+                            Ok(()) => break,
+                            Err(e) => report_error(&status, &error_sender, &events, key, "Connect failed", e).await,
+                        }
+
+                        let retry_at = tokio::time::Instant::now() + backoff;
+                        backoff = (backoff * 2).min(max);
+                        loop {
+                            tokio::select! {
+                                _ = cancel_token.cancelled() => return,
+                                _ = tokio::time::sleep_until(retry_at) => break,
+                                Some(request) = receiver_targets.recv() => {
+                                    (current_targets, subscribed) = request.update.apply(&current_targets, &subscribed);
+                                    *worker_targets.write().await = current_targets.clone();
+                                    if let Some(reply) = request.reply {
+                                        let _ = reply.send(Ok(current_targets.iter().map(|x| x.device).collect()));
+                                    }
+                                },
+                            }
+                        }
+                    }
+
+                    *connected_at.lock().unwrap() = SystemTime::now();
+                    status.write().await.record_success();
+                    connecting.store(false, Ordering::Release);
+                    events.emit(|key| ManagerEvent::Connected { key });
+                    if !current_targets.is_empty() {
+                        let targets: MonitorTargets = std::mem::take(&mut current_targets);
+                        let _ = sender_registration.send(TargetRequest { update: TargetUpdate::Replace(targets), reply: None });
+                    }
+                }
+                drop(sender_registration);
+
+                'monitor: loop {
+                    let next_due = groups.iter().map(|x| x.next_due).min().unwrap_or_else(tokio::time::Instant::now);
 
-                loop {
                     tokio::select! {
                         _ = cancel_token.cancelled() => {
                             break;
                         }
 
-                        Some(targets) = receiver_targets.recv() => {
-                            let monitor_list = {
-                                let mut client = client.lock().await;
-                                client.monitor_register(&targets).await
+                        Some(now) = async { keep_alive_interval.as_mut()?.tick().await.into() } => {
+                            let idle: bool = keep_alive_interval.as_ref().is_some_and(|x| now.duration_since(last_activity) >= x.period());
+                            if idle {
+                                keep_alive(&client, &monitor_target, &status, &connected_at, &events).await;
+                                last_activity = tokio::time::Instant::now();
+                            }
+                        }
+
+                        Some(request) = receiver_requests.recv() => {
+                            request(client.clone()).await;
+                            // Requests on a failed link (e.g. the writes of the output image) do not hold off the keep-alive which reconnects.
+                            if status.read().await.connected {
+                                last_activity = tokio::time::Instant::now();
+                            }
+                        }
+
+                        Some(request) = receiver_targets.recv() => {
+                            let (targets, new_subscribed) = request.update.apply(&current_targets, &subscribed);
+                            let new_groups = MonitorGroup::build(&cpu, &targets, &new_subscribed, cycle, &groups);
+
+                            // Only the first batch of the group at the connection cycle is registered on the CPU.
+                            let registered: &[TypedDevice] = new_groups
+                                .iter()
+                                .find(|x| x.monitored)
+                                .map_or(&[], |x| x.monitored_devices());
+
+                            let monitor_list = if registered.is_empty() {
+                                Ok(MonitorList::new())
+                            } else {
+                                client.monitor_register(registered).await
+                            };
+
+                            let ret = match monitor_list {
+                                Ok(monitor_list) => {
+                                    events.record_success(&status).await;
+                                    status.write().await.registered_monitor_points = targets.len();
+                                    let points: usize = targets.len();
+                                    events.emit(|key| ManagerEvent::MonitorRegistered { key, points });
+                                    let mut monitor_target = monitor_target.write().await;
+                                    *monitor_target = monitor_list;
+                                    groups = new_groups;
+                                    latest.write().unwrap().retain(|device, _| groups.iter().any(|x| x.devices.contains(device)));
+                                    current_targets = targets;
+                                    subscribed = new_subscribed;
+                                    *worker_targets.write().await = current_targets.clone();
+                                    last_activity = tokio::time::Instant::now();
+                                    Ok(current_targets.iter().map(|x| x.device).collect())
+                                },
+                                Err(e) => Err(e),
                             };
 
-                            if let Ok(monitor_list) = monitor_list {
-                                let mut monitor_target = monitor_target.write().await;
-                                *monitor_target = monitor_list;
+                            // A caller waiting for the result gets the error itself; otherwise it goes to the subscribers.
+                            match (request.reply, ret) {
+                                (Some(reply), ret) => {
+                                    if let Err(e) = &ret {
+                                        events.record_error(&status, "Monitor register failed", e).await;
+                                    }
+                                    let _ = reply.send(ret);
+                                },
+                                (None, Err(e)) => report_error(&status, &error_sender, &events, key, "Monitor register failed", e).await,
+                                (None, Ok(_)) => {},
                             }
                         }
 
-                        _ = interval.tick() => {
+                        _ = tokio::time::sleep_until(next_due), if !groups.is_empty() => {
+                            let now = tokio::time::Instant::now();
+                            let snapshot_cycles = snapshot_cycles.load(Ordering::Relaxed);
 
-                            let target_devices = monitor_target.read().await;
+                            for group in groups.iter_mut().filter(|x| x.next_due <= now) {
+                                group.schedule_next(now, missed_tick_behavior);
 
+                                let ret = group.read(&client, &monitor_target, &stats).await;
 
-                            if target_devices.sorted_devices.len() != 0 {
-                                let ret = {
-                                    let mut client = client.lock().await;
-                                    client.monitor_read(&target_devices).await
-                                };
-                                if let Ok(values) = ret {
-                                    let data: Vec<PLCData> = values.clone().into_iter().map(|device_data| PLCData {socket_addr, device_data} ).collect();
-                                    let _ = cyclic_task(data).await;
+                                // Ad-hoc requests do not wait for all the groups nor the cyclic task.
+                                while let Ok(request) = receiver_requests.try_recv() {
+                                    request(client.clone()).await;
+                                }
+
+                                match ret {
+                                    Ok(()) => {
+                                        events.record_success(&status).await;
+                                        last_activity = tokio::time::Instant::now();
+                                        group.publish(&subscriptions);
+                                        group.cache(&latest, SystemTime::now());
+
+                                        let data: Vec<PLCData> = group.filter(snapshot_cycles, key);
+
+                                        if data.is_empty() {
+                                            continue;
+                                        }
+
+                                        // The queue may be full with `BackPressure::Block`; closing the worker must not wait for it.
+                                        tokio::select! {
+                                            _ = cancel_token.cancelled() => break 'monitor,
+                                            dropped = queue.push(data) => if dropped {
+                                                stats.lock().unwrap().dropped_batches += 1;
+                                            }
+                                        }
+                                    },
+                                    Err(e) => {
+                                        // After a power cycle the CPU has no devices registered and answers monitor read with `MONITOR_NOT_REGISTERED`.
+                                        let lost = group.monitored && SLMPEndCode::from_io_error(&e) == Some(SLMPEndCode::MONITOR_NOT_REGISTERED);
+                                        report_error(&status, &error_sender, &events, key, "Monitor read failed", e).await;
+
+                                        if lost {
+                                            match register_again(&client, &monitor_target).await {
+                                                Ok(_) => {
+                                                    let points: usize = status.read().await.registered_monitor_points;
+                                                    events.emit(|key| ManagerEvent::MonitorRegistered { key, points });
+                                                },
+                                                Err(e) => report_error(&status, &error_sender, &events, key, "Monitor register failed", e).await,
+                                            }
+                                        }
+                                    },
                                 }
                             }
                         }
@@ -142,103 +1766,352 @@ impl SLMPConnectionManager {
         };
 
         worker.monitor_handle = Arc::new(Mutex::new(Some(monitor_handle)));
+        // Registered before the first cyclic read, as there is nothing to read until then.
+        // Nothing is kept when preserving is turned off.
+        if let Some(targets) = self.saved_targets.lock().await.get(&key) {
+            let _ = sender_targets.send(TargetRequest { update: TargetUpdate::Replace(targets.clone()), reply: None });
+        }
         worker.sender_targets = Arc::new(Mutex::new(Some(sender_targets)));
+        worker.sender_requests = Arc::new(Mutex::new(Some(sender_requests)));
+        worker.sender_writes = Arc::new(Mutex::new(Some(sender_writes)));
 
-        let mut map = self.connections.lock().await;
-        map.insert(socket_addr, Arc::new(worker));
+        self.connections.write().await.insert(key, Arc::new(worker));
+        self.addresses.lock().await.insert(HostKey::of(connection_props), key);
+        if !lazy {
+            let _ = self.events.send(ManagerEvent::Connected { key });
+        }
 
         Ok(())
     }
 
-    pub async fn disconnect<'a>(&self, connection_props: &'a SLMP4EConnectionProps) -> std::io::Result<bool> {
-        let socket_addr: SocketAddr = SocketAddr::try_from(connection_props)?;
-
-        let mut map = self.connections.lock().await;
-
-        if let Entry::Occupied(entry) = map.entry(socket_addr) {
-            let worker = entry.get();
-            worker.close().await;
-            entry.remove();
-            Ok(true)
-        } else {
-            Ok(false)
+    pub async fn disconnect(&self, connection_props: &SLMP4EConnectionProps) -> std::io::Result<bool> {
+        match self.key_of(connection_props).await {
+            Ok(key) => Ok(self.disconnect_key(key).await),
+            Err(_) => Ok(false),
         }
     }
 
+    async fn disconnect_key(&self, key: ConnectionKey) -> bool {
+        // Taken out of the map first, so that the other connections are not held up while it closes.
+        let Some(worker) = self.connections.write().await.remove(&key) else {
+            return false;
+        };
+        self.names.lock().await.retain(|_, x| *x != key);
+        self.addresses.lock().await.retain(|_, x| *x != key);
+        worker.close_with_timeout(self.close_timeout()).await;
+        self.save_targets(key, &worker).await;
+        let _ = self.events.send(ManagerEvent::Disconnected { key, reason: "Closed".to_string() });
+        true
+    }
+
+    /// Close all connections concurrently.
     pub async fn clear(&self) {
-        let mut map = self.connections.lock().await;
-        for (_, worker) in map.drain() {
-            worker.close().await;
+        let timeout = self.close_timeout();
+        let workers: HashMap<ConnectionKey, Arc<SLMPWorker>> = self.connections.write().await.drain().collect();
+        self.names.lock().await.retain(|_, x| !workers.contains_key(x));
+        self.addresses.lock().await.retain(|_, x| !workers.contains_key(x));
+
+        let closed = for_each_worker(workers, usize::MAX, move |_, worker| async move {
+            worker.close_with_timeout(timeout).await;
+            worker
+        }).await;
+        for (key, worker) in closed {
+            self.save_targets(key, &worker).await;
+            let _ = self.events.send(ManagerEvent::Disconnected { key, reason: "Closed".to_string() });
         }
     }
 
-    pub async fn register_monitor_targets<'a>(&self, targets: &'a [MonitorRequest<'a>]) -> std::io::Result<Vec<MonitoredDevice>> {
+    /// Run `f` on every connection, at most `concurrency_limit` at a time (`usize::MAX` for all at once),
+    /// and return the outputs by connection. The map is not locked while `f` runs, so that `f` may use the manager;
+    /// connections added meanwhile are left out. The output of a task that panicked is missing.
+    pub async fn for_each_connection<T, F, Fut>(&self, concurrency_limit: usize, f: F) -> HashMap<ConnectionKey, T>
+        where
+            F: Fn(ConnectionKey, Arc<SLMPWorker>) -> Fut + std::marker::Send + Sync + 'static,
+            Fut: std::future::Future<Output = T> + std::marker::Send + 'static,
+            T: std::marker::Send + 'static,
+    {
+        let workers: HashMap<ConnectionKey, Arc<SLMPWorker>> = self.connections.read().await.clone();
+        for_each_worker(workers, concurrency_limit, f).await
+    }
+
+    /// Replace the monitor targets of the connections involved, waiting for each registration.
+    /// The result of each connection is returned in the order of `targets`, with its effective targets.
+    /// It fails before registering anything if a connection is not found.
+    pub async fn register_monitor_targets<'a>(&self, targets: &'a [MonitorRequest<'a>]) -> std::io::Result<Vec<RegistrationResult>> {
 
-        let mut socket_addrs: Vec<SocketAddr> = targets
-            .iter()
-            .map(|x| SocketAddr::try_from(x.connection_props))
-            .collect::<Result<Vec<SocketAddr>, std::io::Error>>()?;
+        let mut target_keys: Vec<ConnectionKey> = Vec::with_capacity(targets.len());
+        for target in targets {
+            target_keys.push(self.key_of(target.connection_props).await?);
+        }
 
+        let mut keys: Vec<ConnectionKey> = target_keys.clone();
         let mut seen = HashSet::new();
-        socket_addrs.retain(|item| seen.insert(*item));
+        keys.retain(|item| seen.insert(*item));
 
-        let map = self.connections.lock().await;
+        let workers: Vec<(ConnectionKey, Arc<SLMPWorker>)> = {
+            let map = self.connections.read().await;
+            keys.iter()
+                .map(|key| map.get(key)
+                    .map(|worker| (*key, worker.clone()))
+                    .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::AddrNotAvailable, "Connection not found")))
+                .collect::<std::io::Result<_>>()?
+        };
 
-        for socket_addr in &socket_addrs {
-            if !map.contains_key(socket_addr) {
-                return Err(std::io::Error::new(std::io::ErrorKind::AddrNotAvailable, "Connection not found"))
-            }
+        let mut ret: Vec<RegistrationResult> = Vec::with_capacity(workers.len());
+        for (key, worker) in workers {
+            let targets: MonitorTargets = targets
+                .iter()
+                .zip(&target_keys)
+                .filter(|(_, x)| **x == key)
+                .map(|(x, _)| MonitorTarget::from(x))
+                .collect();
+
+            let monitored_devices = worker.update_targets(TargetUpdate::Replace(targets)).await
+                .map(|devices| devices
+                    .into_iter()
+                    .map(|monitor_device| MonitoredDevice { socket_addr: key.socket_addr, route: key.route(), monitor_device })
+                    .collect());
+            ret.push(RegistrationResult { key, monitored_devices });
         }
 
-        for socket_addr in &socket_addrs {
-            let worker = map.get(&socket_addr)
-                .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::AddrNotAvailable, "Connection not found"))?
-                .clone();
+        Ok(ret)
+    }
 
-            let targets: Vec<TypedDevice> = targets
-                .iter()
-                .filter(|&x| if let Ok(x) = SocketAddr::try_from(x.connection_props) { &x == socket_addr } else { false })
-                .map(|x| x.monitor_device)
-                .collect();
+    /// The key of the connection made with `connection_props`, without resolving its host again.
+    async fn key_of(&self, connection_props: &SLMP4EConnectionProps) -> std::io::Result<ConnectionKey> {
+        self.addresses.lock().await
+            .get(&HostKey::of(connection_props))
+            .copied()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::AddrNotAvailable, "Connection not found"))
+    }
+
+    async fn worker_by_key(&self, key: &ConnectionKey) -> std::io::Result<Arc<SLMPWorker>> {
+        let map = self.connections.read().await;
+        map.get(key)
+            .cloned()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::AddrNotAvailable, "Connection not found"))
+    }
 
-            let sender = worker.sender_targets.lock().await;
+    /// Name a connection, e.g. after a line in the configuration. A name already given to another connection is rejected.
+    /// The name is released when the connection is closed.
+    pub async fn set_name(&self, name: &str, connection_props: &SLMP4EConnectionProps) -> std::io::Result<()> {
+        let key = self.key_of(connection_props).await?;
+        self.worker_by_key(&key).await?;
 
-            if let Some(sender) = sender.clone() {
-                let _ = sender.send(targets.to_vec());
-            };
+        let mut names = self.names.lock().await;
+        match names.get(name) {
+            Some(x) if *x != key => Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, format!("Connection name already in use: {name}"))),
+            _ => {
+                names.insert(name.to_string(), key);
+                Ok(())
+            },
         }
+    }
 
-        let monitored_devices: Vec<MonitoredDevice> = targets
-            .iter()
-            .map(|x| MonitoredDevice::try_from(x))
-            .collect::<Result<Vec<_>, _>>()?;
+    /// Same as `connect`, and name the connection (see `set_name`).
+    pub async fn connect_named<T, F, Fut>(&self, name: &str, connection_props: &SLMP4EConnectionProps, cyclic_task: F, cycle_ms: u64) -> std::io::Result<()>
+        where
+            F: Fn(Vec<PLCData>) -> Fut + std::marker::Send + 'static,
+            Fut: std::future::Future<Output = std::io::Result<T>> + std::marker::Send,
+    {
+        let key = self.key_of(connection_props).await.ok();
+        if self.names.lock().await.get(name).is_some_and(|x| Some(*x) != key) {
+            return Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, format!("Connection name already in use: {name}")));
+        }
 
-        Ok(monitored_devices)
+        self.connect(connection_props, cyclic_task, cycle_ms).await?;
+        self.set_name(name, connection_props).await
     }
 
-    pub async fn get_connections_with_elapsed_time(&self) -> HashMap<SocketAddr, std::time::Duration> {
-        let map = self.connections.lock().await;
+    pub async fn get_by_name(&self, name: &str) -> Option<Arc<SLMPWorker>> {
+        let key = *self.names.lock().await.get(name)?;
+        self.worker_by_key(&key).await.ok()
+    }
+
+    pub async fn get(&self, connection_props: &SLMP4EConnectionProps) -> Option<Arc<SLMPWorker>> {
+        let key = self.key_of(connection_props).await.ok()?;
+        self.worker_by_key(&key).await.ok()
+    }
+
+    /// The worker of a connection, for its status, monitor targets and `reconnect` (same as `get`).
+    pub async fn worker(&self, connection_props: &SLMP4EConnectionProps) -> Option<Arc<SLMPWorker>> {
+        self.get(connection_props).await
+    }
+
+    /// All connections to `socket_addr`. There are several if they differ in route.
+    pub async fn get_by_socket_addr(&self, socket_addr: &SocketAddr) -> Vec<Arc<SLMPWorker>> {
+        let map = self.connections.read().await;
         map.iter()
-            .filter_map(|(&addr, worker)| {
-                worker.connected_at.elapsed().ok().map(|d| (addr, d))
-            })
+            .filter(|(key, _)| key.socket_addr == *socket_addr)
+            .map(|(_, worker)| worker.clone())
+            .collect()
+    }
+
+    /// Add devices to the monitor targets of their connections, keeping the ones already registered.
+    /// The effective targets of the connections involved are returned.
+    pub async fn add_monitor_targets<'a>(&self, targets: &'a [MonitorRequest<'a>]) -> std::io::Result<Vec<MonitoredDevice>> {
+        let mut requests: Vec<(ConnectionKey, Vec<MonitorRequest<'a>>)> = Vec::new();
+        for target in targets {
+            let key = self.key_of(target.connection_props).await?;
+            match requests.iter_mut().find(|x| x.0 == key) {
+                Some(x) => x.1.push(target.clone()),
+                None => requests.push((key, vec![target.clone()])),
+            }
+        }
+
+        let mut ret = Vec::new();
+        for (key, targets) in requests {
+            let devices = self.worker_by_key(&key).await?.add_monitor_targets(&targets).await?;
+            ret.extend(devices.into_iter().map(|monitor_device| MonitoredDevice { socket_addr: key.socket_addr, route: key.route(), monitor_device }));
+        }
+        Ok(ret)
+    }
+
+    /// Remove devices from the monitor targets of their connections. Devices not registered are ignored.
+    /// The effective targets of the connections involved are returned.
+    pub async fn remove_monitor_targets(&self, devices: &[MonitoredDevice]) -> std::io::Result<Vec<MonitoredDevice>> {
+        let mut requests: Vec<(ConnectionKey, Vec<TypedDevice>)> = Vec::new();
+        for device in devices {
+            match requests.iter_mut().find(|x| x.0 == device.key()) {
+                Some(x) => x.1.push(device.monitor_device),
+                None => requests.push((device.key(), vec![device.monitor_device])),
+            }
+        }
+
+        let mut ret = Vec::new();
+        for (key, devices) in requests {
+            let devices = self.worker_by_key(&key).await?.remove_monitor_targets(&devices).await?;
+            ret.extend(devices.into_iter().map(|monitor_device| MonitoredDevice { socket_addr: key.socket_addr, route: key.route(), monitor_device }));
+        }
+        Ok(ret)
+    }
+
+    pub async fn clear_monitor_targets(&self, connection_props: &SLMP4EConnectionProps) -> std::io::Result<()> {
+        let key = self.key_of(connection_props).await?;
+        self.worker_by_key(&key).await?.clear_monitor_targets().await
+    }
+
+    /// Connections sharing an address with different routes appear once.
+    pub async fn get_connections_with_elapsed_time(&self) -> HashMap<SocketAddr, std::time::Duration> {
+        self.for_each_connection(usize::MAX, |_, worker| async move { worker.connected_at().elapsed().ok() })
+            .await
+            .into_iter()
+            .filter_map(|(key, elapsed)| elapsed.map(|d| (key.socket_addr, d)))
             .collect()
     }
 
-    pub async fn operate_worker<'a, T, F, Fut>(&self, connection_props: &'a SLMP4EConnectionProps, task: F) -> std::io::Result<T>
+    pub async fn status(&self, connection_props: &SLMP4EConnectionProps) -> std::io::Result<ConnectionStatus> {
+        let key = self.key_of(connection_props).await?;
+        Ok(self.worker_by_key(&key).await?.status().await)
+    }
+
+    pub async fn stats(&self, connection_props: &SLMP4EConnectionProps) -> std::io::Result<WorkerStats> {
+        let key = self.key_of(connection_props).await?;
+        Ok(self.worker_by_key(&key).await?.stats())
+    }
+
+    pub async fn status_all(&self) -> HashMap<ConnectionKey, ConnectionStatus> {
+        self.for_each_connection(usize::MAX, |_, worker| async move { worker.status().await }).await
+    }
+
+    /// See `SLMPWorker::set_snapshot_interval`.
+    pub async fn set_snapshot_interval(&self, connection_props: &SLMP4EConnectionProps, cycles: u32) -> std::io::Result<()> {
+        let key = self.key_of(connection_props).await?;
+        let worker = self.worker_by_key(&key).await?;
+
+        worker.set_snapshot_interval(cycles);
+        Ok(())
+    }
+
+    /// Subscribe to the errors of the monitor loop of a connection (see `SLMPWorker::subscribe_errors`).
+    pub async fn subscribe_errors(&self, connection_props: &SLMP4EConnectionProps) -> std::io::Result<broadcast::Receiver<MonitorError>> {
+        let key = self.key_of(connection_props).await?;
+        let worker = self.worker_by_key(&key).await?;
+
+        Ok(worker.subscribe_errors())
+    }
+
+    /// Follow the value of a device of a connection (see `SLMPWorker::subscribe`).
+    pub async fn subscribe(&self, connection_props: &SLMP4EConnectionProps, device: TypedDevice) -> std::io::Result<watch::Receiver<TypedData>> {
+        let key = self.key_of(connection_props).await?;
+        self.worker_by_key(&key).await?.subscribe(device).await
+    }
+
+    /// The last value read of a device of a connection (see `SLMPWorker::latest`). `None` if the connection is not found either.
+    pub async fn latest(&self, connection_props: &SLMP4EConnectionProps, device: &TypedDevice) -> Option<(TypedData, SystemTime)> {
+        self.get(connection_props).await?.latest(device)
+    }
+
+    /// The last values read of the devices of a connection (see `SLMPWorker::latest_all`).
+    pub async fn latest_all(&self, connection_props: &SLMP4EConnectionProps) -> std::io::Result<HashMap<TypedDevice, (TypedData, SystemTime)>> {
+        let key = self.key_of(connection_props).await?;
+        Ok(self.worker_by_key(&key).await?.latest_all())
+    }
+
+    /// Read a device of a connection between cyclic reads, within `timeout` (see `SLMPWorker::read_now`).
+    pub async fn read_now(&self, connection_props: &SLMP4EConnectionProps, device: TypedDevice, timeout: std::time::Duration) -> std::io::Result<TypedData> {
+        let key = self.key_of(connection_props).await?;
+        self.worker_by_key(&key).await?.read_now(device, timeout).await
+    }
+
+    /// Write devices of a connection every `cycle_ms` from an output image (see `SLMPWorker::register_output_image`).
+    pub async fn register_output_image(&self, connection_props: &SLMP4EConnectionProps, devices: &[TypedDevice], cycle_ms: u64) -> std::io::Result<()> {
+        let key = self.key_of(connection_props).await?;
+        self.worker_by_key(&key).await?.register_output_image(devices, cycle_ms).await
+    }
+
+    /// Set a value of the output image of a connection, written on its next cycle (see `SLMPWorker::update_output`).
+    pub async fn update_output(&self, connection_props: &SLMP4EConnectionProps, data: DeviceData) -> std::io::Result<()> {
+        let key = self.key_of(connection_props).await?;
+        self.worker_by_key(&key).await?.update_output(data)
+    }
+
+    /// Whether the connection is alive as far as the monitor loop and the keep-alive know.
+    pub async fn is_healthy(&self, connection_props: &SLMP4EConnectionProps) -> std::io::Result<bool> {
+        let key = self.key_of(connection_props).await?;
+        Ok(self.worker_by_key(&key).await?.is_healthy().await)
+    }
+
+    /// Random write on a connection, run by the monitor loop between monitor reads.
+    pub async fn write(&self, connection_props: &SLMP4EConnectionProps, data: &[DeviceData]) -> std::io::Result<()> {
+        let data: Vec<DeviceData> = data.to_vec();
+        self.operate_worker(connection_props, async move |client| client.random_write(&data).await).await
+    }
+
+    /// Bulk write on a connection, run by the monitor loop between monitor reads.
+    pub async fn bulk_write(&self, connection_props: &SLMP4EConnectionProps, start_device: Device, data: &[TypedData]) -> std::io::Result<()> {
+        let data: Vec<TypedData> = data.to_vec();
+        self.operate_worker(connection_props, async move |client| client.bulk_write(start_device, &data).await).await
+    }
+
+    /// `SLMPClient::modify_word` on a connection, run by the monitor loop between monitor reads.
+    /// Other requests through the manager do not come between the read and the write, but the PLC program may.
+    pub async fn modify_word(&self, connection_props: &SLMP4EConnectionProps, device: Device, and_mask: u16, or_mask: u16) -> std::io::Result<[bool; 16]> {
+        self.operate_worker(connection_props, async move |client| client.modify_word(device, and_mask, or_mask).await).await
+    }
+
+    /// `SLMPClient::set_word_bit` on a connection, run by the monitor loop between monitor reads.
+    pub async fn set_word_bit(&self, connection_props: &SLMP4EConnectionProps, device: Device, bit_index: u8, value: bool) -> std::io::Result<[bool; 16]> {
+        crate::requests::word_bit_mask(bit_index)?;
+        self.operate_worker(connection_props, async move |client| client.set_word_bit(device, bit_index, value).await).await
+    }
+
+    /// Random read on a connection, run by the monitor loop between monitor reads.
+    pub async fn read(&self, connection_props: &SLMP4EConnectionProps, devices: &[TypedDevice]) -> std::io::Result<Vec<DeviceData>> {
+        let devices: Vec<TypedDevice> = devices.to_vec();
+        self.operate_worker(connection_props, async move |client| client.random_read(&devices).await).await
+    }
+
+    /// Run `task` on the client of a connection. It is queued to the monitor loop (see `SLMPWorker::request`).
+    pub async fn operate_worker<T, F, Fut>(&self, connection_props: &SLMP4EConnectionProps, task: F) -> std::io::Result<T>
         where
-            F: FnOnce(Arc<Mutex<SLMPClient>>) -> Fut,
-            Fut: std::future::Future<Output = std::io::Result<T>>,
+            T: std::marker::Send + 'static,
+            F: FnOnce(Arc<SLMPClient>) -> Fut + std::marker::Send + 'static,
+            Fut: std::future::Future<Output = std::io::Result<T>> + std::marker::Send + 'static,
     {
-        let socket_addr: SocketAddr = SocketAddr::try_from(connection_props)?;
-
-        let worker = {
-            let map = self.connections.lock().await;
-            map.get(&socket_addr)
-                .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::AddrNotAvailable, "Connection not found"))?
-                .clone()
-        };
+        let key = self.key_of(connection_props).await?;
+        let worker = self.worker_by_key(&key).await?;
 
-        task(worker.client.clone()).await
+        worker.request(task).await
     }
 }
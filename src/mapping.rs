@@ -0,0 +1,96 @@
+use crate::prelude::*;
+use crate::{DataType, TypedData};
+
+/// Layout of a struct in consecutive word devices, e.g. a recipe block from D100.
+///
+/// The fields follow each other without gaps: a field takes the words of its type (`U32` two, `String(n)` n),
+/// and `Bool` takes a whole word (bit 0).
+pub trait SlmpLayout {
+    /// Types of the fields in device order.
+    const FIELDS: &'static [DataType];
+
+    /// Words the struct takes, e.g. to place the next one after it.
+    fn word_size() -> usize {
+        Self::FIELDS.iter().map(|&x| field_words(x)).sum()
+    }
+
+    /// Word offset of each field from the start device.
+    fn word_offsets() -> Vec<usize> {
+        Self::FIELDS.iter()
+            .scan(0, |offset, &x| {
+                let ret = *offset;
+                *offset += field_words(x);
+                Some(ret)
+            })
+            .collect()
+    }
+}
+
+/// Struct read by `SLMPClient::read_struct`.
+pub trait SlmpReadable: SlmpLayout + Sized {
+    /// Build the struct from the values of `FIELDS`, in the same order.
+    fn from_fields(fields: Vec<TypedData>) -> crate::io::Result<Self>;
+}
+
+/// Struct written by `SLMPClient::write_struct`.
+pub trait SlmpWritable: SlmpLayout {
+    /// Values of `FIELDS`, in the same order.
+    fn to_fields(&self) -> Vec<TypedData>;
+}
+
+pub(crate) const fn field_words(data_type: DataType) -> usize {
+    match data_type {
+        DataType::Bool => 1,
+        x => x.byte_size() / 2,
+    }
+}
+
+/// Declare a struct laid out in consecutive word devices, implementing `SlmpReadable` and `SlmpWritable`.
+/// Each field names its `DataType` variant after `=>`; the field type converts from and into `TypedData`.
+///
+/// ```rust
+/// slmp::device_struct! {
+///     #[derive(Clone, Debug, PartialEq)]
+///     pub struct Recipe {
+///         pub speed: u16 => U16,        // D100
+///         pub temperature: f32 => F32,  // D101-D102
+///         pub name: slmp::PLCString => String(10), // D103-D112
+///         pub enabled: bool => Bool,    // D113
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! device_struct {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident : $ty:ty => $data_type:ident $(($size:expr))?),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($(#[$field_meta])* $field_vis $field: $ty,)*
+        }
+
+        impl $crate::SlmpLayout for $name {
+            const FIELDS: &'static [$crate::DataType] = &[$($crate::DataType::$data_type $(($size))?),*];
+        }
+
+        impl $crate::SlmpReadable for $name {
+            fn from_fields(fields: $crate::__private::Vec<$crate::TypedData>) -> $crate::io::Result<Self> {
+                let mut fields = fields.into_iter();
+                Ok(Self {
+                    $($field: <$ty as ::core::convert::TryFrom<$crate::TypedData>>::try_from(
+                        fields.next().ok_or_else(|| $crate::io::Error::new($crate::io::ErrorKind::InvalidData, "missing field"))?
+                    )?,)*
+                })
+            }
+        }
+
+        impl $crate::SlmpWritable for $name {
+            fn to_fields(&self) -> $crate::__private::Vec<$crate::TypedData> {
+                $crate::__private::vec![$($crate::TypedData::from(::core::clone::Clone::clone(&self.$field))),*]
+            }
+        }
+    };
+}
@@ -1,61 +1,135 @@
-use std::net::SocketAddr;
+use crate::prelude::*;
+use core::net::SocketAddr;
 use serde::{Deserialize, Serialize};
-use crate::{DeviceData, SLMP4EConnectionProps, TypedData, TypedDevice, device::DeviceSize};
+use crate::{CPU, DeviceData, Route, SLMP4EConnectionProps, TypedData, TypedDevice, device::DeviceSize};
+
+/// Maximum word access points + double word access points of a monitor register (0x0801) and random read (0x0403),
+/// the largest among the CPU series. See `monitor_max_access_points` for the limit of a CPU.
+pub const MONITOR_MAX_ACCESS_POINTS: usize = 192;
+
+/// Word access points + double word access points of a monitor register and random read on `cpu`,
+/// halved for the longer device specification of iQ-R (subcommand 0x0002).
+pub const fn monitor_max_access_points(cpu: &CPU) -> usize {
+    match cpu {
+        CPU::Q | CPU::L | CPU::F | CPU::A => MONITOR_MAX_ACCESS_POINTS,
+        CPU::R => 96,
+    }
+}
+
+const fn default_max_access_points() -> usize {
+    MONITOR_MAX_ACCESS_POINTS
+}
+
+/// Access points the devices occupy in a monitor register. Multi-word devices take one point per word.
+pub(crate) fn access_points(devices: &[TypedDevice]) -> usize {
+    devices
+        .iter()
+        .map(|x| match x.data_type.device_size() {
+            DeviceSize::MultiWord(n) => n as usize,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Split devices into consecutive ranges within `max_points` access points each.
+pub(crate) fn split_batches(devices: &[TypedDevice], max_points: usize) -> Vec<core::ops::Range<usize>> {
+    let mut ret = Vec::new();
+    let mut start = 0;
+    let mut points = 0;
+
+    for (i, device) in devices.iter().enumerate() {
+        let device_points = access_points(core::slice::from_ref(device));
+        if i > start && points + device_points > max_points {
+            ret.push(start..i);
+            start = i;
+            points = 0;
+        }
+        points += device_points;
+    }
+
+    if start < devices.len() {
+        ret.push(start..devices.len());
+    }
+    ret
+}
 
 /// Mitsubishi PLC allow only the signle-word access and double-word access.
 /// Multi-word access which used for f64 and string is not supported by default.
 /// This library supporrts the multi-word access using signle-word access.
+///
+/// A device listed more than once with the same data type is registered once, and its value is returned for each entry.
+/// The same device with different data types (e.g. D100 as U16 and as F32) are different entries, each read in its type.
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
-#[cfg_attr(feature = "json-api", serde(rename_all = "camelCase"))]
+#[serde(rename_all = "camelCase")]
 pub struct MonitorList {
+    /// The registered devices with their index among the distinct devices, in the order of the request.
     pub sorted_devices: Vec<(usize, TypedDevice)>,
-    pub(crate) single_word_access_points: u8,
-    pub(crate) double_word_access_points: u8,
-    pub(crate) multi_word_access_points: u8,
-    pub(crate) single_word_access_points_for_multi_word_communication: u8,
-}
-
-impl From<&[TypedDevice]> for MonitorList {
-    fn from(value: &[TypedDevice]) -> Self {
-        let mut sorted_devices: Vec<(usize, TypedDevice)> = value
-            .into_iter()
-            .enumerate()
-            .map(|(i, typed_device)| (i, typed_device.clone()))
-            .collect();
-
-        // Sort by data_type make the order of devices in the following order; single-word device, multi-word-device, and double-word device.
-        // This is defined by the definition of DataType.
-        sorted_devices.sort_by_key(|p| p.1.device.address);
-        sorted_devices.sort_by_key(|p| p.1.data_type);
-
-        let multi_word_devices = sorted_devices
-            .iter()
-            .filter(|x| matches!(x.1.data_type.device_size(), DeviceSize::MultiWord(_)));
-
-        let multi_word_access_points = multi_word_devices.clone().count() as u8;
-
-        let single_word_access_points_for_multi_word_communication = multi_word_devices
-            .fold(0, |a, b| {
-                if let DeviceSize::MultiWord(n) = b.1.data_type.device_size() { a + n } else { a }
-            });
+    /// Index among the distinct devices of each device given, if any of them is repeated (empty otherwise).
+    #[serde(default)]
+    pub(crate) origins: Vec<usize>,
+    pub(crate) single_word_access_points: usize,
+    pub(crate) double_word_access_points: usize,
+    pub(crate) multi_word_access_points: usize,
+    pub(crate) single_word_access_points_for_multi_word_communication: usize,
+    /// Access points the list takes at most (`MONITOR_MAX_ACCESS_POINTS`, or the limit of a CPU with `for_cpu`).
+    #[serde(skip, default = "default_max_access_points")]
+    pub(crate) max_access_points: usize,
+}
 
-        let single_word_access_points: u8 = sorted_devices
-            .iter()
-            .filter(|x| matches!(x.1.data_type.device_size(), DeviceSize::SingleWord | DeviceSize::Bit))
-            .count() as u8 + single_word_access_points_for_multi_word_communication;
+impl TryFrom<&[TypedDevice]> for MonitorList {
+    type Error = MonitorListFull;
 
-        let double_word_access_points: u8 = sorted_devices
-            .iter()
-            .filter(|x| matches!(x.1.data_type.device_size(), DeviceSize::DoubleWord))
-            .count() as u8;
+    fn try_from(value: &[TypedDevice]) -> Result<Self, Self::Error> {
+        let mut ret = Self::new();
+        ret.try_extend(value.iter().copied())?;
+        Ok(ret)
+    }
+}
 
-        Self {
-            sorted_devices,
-            single_word_access_points,
-            double_word_access_points,
-            multi_word_access_points,
-            single_word_access_points_for_multi_word_communication
-        }
+/// A device which would take a monitor list past its limit (`monitor_max_access_points`).
+/// It is carried by the `InvalidInput` error of `monitor_register` and `random_read`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MonitorListFull {
+    pub device: TypedDevice,
+    /// Access points the list would take with the device.
+    pub access_points: usize,
+    /// Access points the list takes at most.
+    pub max_access_points: usize,
+}
+
+impl MonitorListFull {
+    /// Take the detail out of an error returned from `SLMPClient`.
+    #[cfg(feature = "std")]
+    pub fn from_io_error(error: &std::io::Error) -> Option<&Self> {
+        error.get_ref()?.downcast_ref::<Self>()
+    }
+}
+
+impl core::fmt::Display for MonitorListFull {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Too many monitor points: {} > {} with {} as {:?}", self.access_points, self.max_access_points, self.device.device, self.device.data_type)
+    }
+}
+
+impl core::error::Error for MonitorListFull {}
+
+#[cfg(feature = "std")]
+impl From<MonitorListFull> for std::io::Error {
+    fn from(value: MonitorListFull) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, value)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl From<MonitorListFull> for crate::io::Error {
+    fn from(value: MonitorListFull) -> Self {
+        crate::io::Error::new(crate::io::ErrorKind::InvalidInput, value.to_string())
+    }
+}
+
+impl Default for MonitorList {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -64,78 +138,197 @@ impl MonitorList {
         const MAX_MONITOR_LIST: usize = 256;
         Self {
             sorted_devices: Vec::with_capacity(MAX_MONITOR_LIST),
+            origins: Vec::new(),
             single_word_access_points: 0,
             double_word_access_points: 0,
             multi_word_access_points: 0,
             single_word_access_points_for_multi_word_communication: 0,
+            max_access_points: MONITOR_MAX_ACCESS_POINTS,
         }
     }
 
-    pub fn parse(&self, data: &[u8]) -> Vec<DeviceData> {
+    /// An empty list within the limit of `cpu` (`new` takes `MONITOR_MAX_ACCESS_POINTS`).
+    pub fn for_cpu(cpu: &CPU) -> Self {
+        Self { max_access_points: monitor_max_access_points(cpu), ..Self::new() }
+    }
 
-        const SINGLE_WORD_BYTELEN: usize = 2;
-        const DOUBLE_WORD_BYTELEN: usize = 4;
+    /// The devices as given to the list, repeated ones included.
+    pub fn devices(&self) -> Vec<TypedDevice> {
+        let mut distinct: Vec<(usize, TypedDevice)> = self.sorted_devices.clone();
+        distinct.sort_by_key(|x| x.0);
+        if self.origins.is_empty() {
+            distinct.into_iter().map(|x| x.1).collect()
+        } else {
+            self.origins.iter().map(|&i| distinct[i].1).collect()
+        }
+    }
 
-        let total_access_points: usize = self.single_word_access_points as usize + self.double_word_access_points as usize;
-        let single_word_data_byte: usize = (self.single_word_access_points as usize) * SINGLE_WORD_BYTELEN;    // It include single-word data and multi-word data
-        let multi_word_data_byte: usize = (self.single_word_access_points_for_multi_word_communication as usize) * SINGLE_WORD_BYTELEN;
+    /// Word access points + double word access points. Multi-word devices take one point per word.
+    pub const fn access_points(&self) -> usize {
+        self.single_word_access_points + self.double_word_access_points
+    }
 
-        let single_word_data: &[u8] = &data[..(single_word_data_byte - multi_word_data_byte)];
-        let multi_word_data: &[u8] = &data[(single_word_data_byte - multi_word_data_byte)..single_word_data_byte];
-        let double_word_data: &[u8] = &data[single_word_data_byte..];
+    /// Add a device, in the order the devices are sent (single-word devices, multi-word devices read as single words,
+    /// and double-word devices, each by address). A device already listed with the same data type takes no points.
+    /// The counts are sent as a single byte each, so a device taking the list over the limit is refused.
+    pub fn try_push(&mut self, device: TypedDevice) -> Result<(), MonitorListFull> {
+        if let Some(&(i, _)) = self.sorted_devices.iter().find(|x| x.1 == device) {
+            if self.origins.is_empty() {
+                self.origins = (0..self.sorted_devices.len()).collect();
+            }
+            self.origins.push(i);
+            return Ok(());
+        }
 
-        let mut ret: Vec<(usize, DeviceData)> = Vec::with_capacity(total_access_points);
+        let (single, double, multi) = match device.data_type.device_size() {
+            DeviceSize::Bit | DeviceSize::SingleWord => (1, 0, 0),
+            DeviceSize::DoubleWord => (0, 1, 0),
+            DeviceSize::MultiWord(n) => (n as usize, 0, 1),
+        };
+        let access_points: usize = self.access_points() + single + double;
+        if access_points > self.max_access_points {
+            return Err(MonitorListFull { device, access_points, max_access_points: self.max_access_points });
+        }
 
-        let mut i = 0;
+        self.single_word_access_points += single;
+        self.double_word_access_points += double;
+        self.multi_word_access_points += multi;
+        if multi > 0 {
+            self.single_word_access_points_for_multi_word_communication += single;
+        }
 
-        for x in single_word_data.chunks_exact(SINGLE_WORD_BYTELEN) {
-            ret.push((self.sorted_devices[i].0, DeviceData {
-                device: self.sorted_devices[i].1.device,
-                data: TypedData::from((x, self.sorted_devices[i].1.data_type)),
-            }));
-            i += 1;
+        let index: usize = self.sorted_devices.len();
+        if !self.origins.is_empty() {
+            self.origins.push(index);
         }
+        let key = |x: &TypedDevice| (x.data_type.access_order(), x.device.address);
+        let position: usize = self.sorted_devices.partition_point(|x| key(&x.1) <= key(&device));
+        self.sorted_devices.insert(position, (index, device));
+        Ok(())
+    }
 
-        let mut buffer_start_addr = 0;
-        for _ in 0..self.multi_word_access_points {
-            let dev = self.sorted_devices[i];
-            let buffer_next_addr = buffer_start_addr + dev.1.data_type.byte_size();
-            let data = &multi_word_data[buffer_start_addr..buffer_next_addr];
-            ret.push((dev.0, DeviceData {
-                device: dev.1.device,
-                data: TypedData::from((data, dev.1.data_type))
-            }));
-            buffer_start_addr = buffer_next_addr;
-            i += 1;
+    /// `try_push` of every device, or none of them if one does not fit.
+    pub fn try_extend(&mut self, devices: impl IntoIterator<Item = TypedDevice>) -> Result<(), MonitorListFull> {
+        let mut ret: Self = self.clone();
+        for device in devices {
+            ret.try_push(device)?;
         }
+        *self = ret;
+        Ok(())
+    }
+
+    /// Parse a response of monitor read or random read. It fails with `InvalidData` if the length does not match the list.
+    pub fn parse(&self, data: &[u8]) -> crate::io::Result<Vec<DeviceData>> {
+        let mut ret: Vec<DeviceData> = Vec::with_capacity(self.sorted_devices.len());
+        self.parse_into(data, &mut ret)?;
+        Ok(ret)
+    }
+
+    /// Same as `parse` into `out`, replacing its content, so that cyclic reads reuse its allocation
+    /// (no allocation but those of `String` values once `out` has the capacity, unless a device is repeated).
+    /// `out` is left unspecified on an error.
+    pub fn parse_into(&self, data: &[u8], out: &mut Vec<DeviceData>) -> crate::io::Result<()> {
+        const SINGLE_WORD_BYTELEN: usize = 2;
+
+        let invalid = || crate::io::Error::new(crate::io::ErrorKind::InvalidData,
+            format!("Monitor response of {} bytes does not match {} devices", data.len(), self.sorted_devices.len()));
 
-        for x in double_word_data.chunks_exact(DOUBLE_WORD_BYTELEN) {
-            ret.push((self.sorted_devices[i].0, DeviceData {
-                device: self.sorted_devices[i].1.device,
-                data: TypedData::from((x, self.sorted_devices[i].1.data_type)),
-            }));
-            i += 1;
+        // The response holds the devices in the order of `sorted_devices`;
+        // single-word devices, multi-word devices (read as single words), and double-word devices.
+        out.clear();
+        out.reserve(self.sorted_devices.len());
+        let mut rest: &[u8] = data;
+
+        for (_, typed_device) in &self.sorted_devices {
+            let bytelen = typed_device.data_type.byte_size().max(SINGLE_WORD_BYTELEN);
+            let (x, tail) = rest.split_at_checked(bytelen).ok_or_else(invalid)?;
+            out.push(DeviceData {
+                device: typed_device.device,
+                data: TypedData::decode(x, typed_device.data_type)?,
+            });
+            rest = tail;
+        }
+
+        if !rest.is_empty() {
+            return Err(invalid());
         }
 
-        ret.sort_by_key(|x| x.0);
+        self.restore_order(out);
+        if !self.origins.is_empty() {
+            let distinct: Vec<DeviceData> = core::mem::take(out);
+            out.extend(self.origins.iter().map(|&i| distinct[i].clone()));
+        }
+        Ok(())
+    }
 
-        let ret: Vec<DeviceData> = ret.into_iter().map(|x| x.1).collect();
+    /// Move the values from the order of `sorted_devices` to the order of registration in place,
+    /// following each cycle of the permutation once (from its smallest index). A list in registration order moves nothing.
+    fn restore_order(&self, values: &mut [DeviceData]) {
+        let destination = |k: usize| self.sorted_devices[k].0;
+        for start in 0..values.len() {
+            let mut k = destination(start);
+            while k > start {
+                k = destination(k);
+            }
+            if k < start {
+                continue;
+            }
 
-        ret
+            let mut k = destination(start);
+            while k != start {
+                values.swap(start, k);
+                k = destination(k);
+            }
+        }
     }
 }
 
 
-#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize)]
-#[cfg_attr(feature = "json-api", serde(rename_all = "camelCase"))]
+/// Which reads of a monitored device are passed to the cyclic task.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", rename_all_fields = "camelCase")]
+pub enum ChangeFilter {
+    /// Every read.
+    #[default]
+    Always,
+    /// Only reads that differ from the last value passed.
+    /// For F32/F64, changes not larger than `deadband` are suppressed. It is ignored for other types.
+    OnChange { deadband: f64 },
+}
+
+impl ChangeFilter {
+    pub(crate) fn passes(&self, previous: Option<&TypedData>, current: &TypedData) -> bool {
+        let deadband = match (self, previous) {
+            (Self::Always, _) | (_, None) => return true,
+            (Self::OnChange { deadband }, _) => *deadband,
+        };
+
+        // A change to or from NaN is a change, and a NaN read again is not.
+        match (previous, current) {
+            (Some(TypedData::F32(a)), TypedData::F32(b)) if !a.is_nan() && !b.is_nan() => (*b as f64 - *a as f64).abs() > deadband,
+            (Some(TypedData::F64(a)), TypedData::F64(b)) if !a.is_nan() && !b.is_nan() => (b - a).abs() > deadband,
+            (Some(a), b) => a.total_cmp(b).is_ne(),
+            (None, _) => true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct MonitorRequest<'a> {
     pub connection_props: &'a SLMP4EConnectionProps,
-    pub monitor_device: TypedDevice
+    pub monitor_device: TypedDevice,
+    pub change_filter: ChangeFilter,
+    /// Polling interval of this device. `None` polls it at the cycle of the connection.
+    pub cycle_ms: Option<u64>,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
-#[cfg_attr(feature = "json-api", serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct MonitoredDevice {
     pub socket_addr: SocketAddr,
+    /// Route of the connection, telling apart the connections to one address.
+    #[serde(default)]
+    pub route: Route,
     pub monitor_device: TypedDevice
 }
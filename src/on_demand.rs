@@ -0,0 +1,199 @@
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::mapping::field_words;
+use crate::requests::{self, FIXED_FRAME_LEN};
+use crate::{BUFSIZE, DataType, TypedData, WordOrder};
+
+const COMMAND_ON_DEMAND: u16 = 0x2101;
+/// Header of an on-demand frame up to the subcommand: the end code field holds 0xFFFF.
+const ON_DEMAND_PREFIX_LEN: usize = 19;
+
+/// Link of `SLMPOnDemandServer`, as set in the Ethernet parameters of the CPU.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OnDemandProtocol {
+    Tcp,
+    Udp,
+}
+
+/// Data pushed by the ON DEMAND instruction of a CPU.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OnDemandData {
+    /// Address the frame came from.
+    pub source: SocketAddr,
+    pub serial_id: u16,
+    pub network_id: u8,
+    pub pc_id: u8,
+    pub io_id: u16,
+    pub area_id: u8,
+    pub words: Vec<u16>,
+}
+
+impl OnDemandData {
+    /// The words as the values of `schema` laid out one after another (a `Bool` taking a word).
+    /// It fails with `InvalidData` if the words do not match the schema.
+    pub fn decode(&self, schema: &[DataType], order: WordOrder) -> std::io::Result<Vec<TypedData>> {
+        let words: usize = schema.iter().map(|&x| field_words(x)).sum();
+        if words != self.words.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("On-demand data of {} words does not match the schema of {words} words", self.words.len())
+            ));
+        }
+
+        let bytes: Vec<u8> = self.words.iter().flat_map(|x| x.to_le_bytes()).collect();
+        let mut rest: &[u8] = &bytes;
+        schema.iter()
+            .map(|&data_type| {
+                let (field, next) = rest.split_at(field_words(data_type) * 2);
+                rest = next;
+                TypedData::decode(field, data_type).map(|x| x.with_word_order(order))
+            })
+            .collect()
+    }
+}
+
+/// Check an on-demand frame (4E binary, command 0x2101) and take its data.
+/// It fails with `InvalidData` for other frames or an odd number of data bytes.
+pub fn parse_on_demand_frame(source: SocketAddr, frame: &[u8]) -> std::io::Result<OnDemandData> {
+    let invalid = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, msg.to_string());
+
+    if frame.len() < ON_DEMAND_PREFIX_LEN || frame[0..2] != [0xD4, 0x00] {
+        return Err(invalid("Received Invalid On-Demand Frame"));
+    }
+    if u16::from_le_bytes([frame[11], frame[12]]) as usize != frame.len() - FIXED_FRAME_LEN {
+        return Err(invalid("Received Invalid Data Frame"));
+    }
+    if frame[13..15] != [0xFF, 0xFF] || frame[15..17] != COMMAND_ON_DEMAND.to_le_bytes() || frame[17..19] != [0x00, 0x00] {
+        return Err(invalid("Received Invalid On-Demand Command"));
+    }
+    let data: &[u8] = &frame[ON_DEMAND_PREFIX_LEN..];
+    if !data.len().is_multiple_of(2) {
+        return Err(invalid("Received Invalid On-Demand Data Length"));
+    }
+
+    Ok(OnDemandData {
+        source,
+        serial_id: requests::frame_serial_id(frame),
+        network_id: frame[6],
+        pc_id: frame[7],
+        io_id: u16::from_le_bytes([frame[8], frame[9]]),
+        area_id: frame[10],
+        words: requests::bytes_to_words(data),
+    })
+}
+
+/// Acknowledgment of an on-demand frame: a response of its header with end code 0 and no data.
+fn acknowledgment(frame: &[u8]) -> Vec<u8> {
+    let mut ack: Vec<u8> = frame[..FIXED_FRAME_LEN].to_vec();
+    ack[11..13].copy_from_slice(&2u16.to_le_bytes());
+    ack.extend([0x00, 0x00]);
+    ack
+}
+
+/// Receiving end of `SLMPOnDemandServer`.
+pub struct OnDemandReceiver {
+    receiver: mpsc::Receiver<OnDemandData>,
+}
+
+impl OnDemandReceiver {
+    /// Wait for the next data. `None` after the server is closed.
+    pub async fn recv(&mut self) -> Option<OnDemandData> {
+        self.receiver.recv().await
+    }
+}
+
+/// Server receiving on-demand data pushed by CPUs, on a port of its own, so that it runs beside
+/// `SLMPConnectionManager` and clients polling the same CPU.
+///
+/// Frames which are not on-demand frames are skipped. With `acknowledge`, each frame is answered with end code 0;
+/// the ON DEMAND instruction does not wait for it, so it is off unless the other end expects it.
+pub struct SLMPOnDemandServer {
+    local_addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl SLMPOnDemandServer {
+    /// Listen on `addr`. The data is queued up to `capacity` frames; the server waits for the receiver beyond it.
+    pub async fn bind(addr: SocketAddr, protocol: OnDemandProtocol, capacity: usize, acknowledge: bool) -> std::io::Result<(Self, OnDemandReceiver)> {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        let (local_addr, task) = match protocol {
+            OnDemandProtocol::Tcp => {
+                let listener = TcpListener::bind(addr).await?;
+                (listener.local_addr()?, tokio::spawn(serve_tcp(listener, sender, acknowledge)))
+            },
+            OnDemandProtocol::Udp => {
+                let socket = UdpSocket::bind(addr).await?;
+                (socket.local_addr()?, tokio::spawn(serve_udp(socket, sender, acknowledge)))
+            },
+        };
+        Ok((Self { local_addr, task }, OnDemandReceiver { receiver }))
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop listening and close the connections; the receiver returns `None` after the queued data.
+    pub fn close(&self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for SLMPOnDemandServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve_udp(socket: UdpSocket, sender: mpsc::Sender<OnDemandData>, acknowledge: bool) {
+    let mut buf = [0u8; BUFSIZE];
+    while let Ok((len, source)) = socket.recv_from(&mut buf).await {
+        let Ok(data) = parse_on_demand_frame(source, &buf[..len]) else {
+            continue;
+        };
+        if acknowledge {
+            let _ = socket.send_to(&acknowledgment(&buf[..len]), source).await;
+        }
+        if sender.send(data).await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn serve_tcp(listener: TcpListener, sender: mpsc::Sender<OnDemandData>, acknowledge: bool) {
+    let mut connections = tokio::task::JoinSet::new();
+    while let Ok((stream, source)) = listener.accept().await {
+        connections.spawn(serve_connection(stream, source, sender.clone(), acknowledge));
+    }
+}
+
+/// Frames of a connection until it is closed or sends a header of an overlong frame.
+async fn serve_connection(mut stream: TcpStream, source: SocketAddr, sender: mpsc::Sender<OnDemandData>, acknowledge: bool) {
+    let mut pending: Vec<u8> = Vec::with_capacity(BUFSIZE);
+    loop {
+        match requests::frame_len(&pending, BUFSIZE) {
+            Ok(Some(frame_len)) if pending.len() >= frame_len => {
+                let frame: Vec<u8> = pending.drain(..frame_len).collect();
+                let Ok(data) = parse_on_demand_frame(source, &frame) else {
+                    continue;
+                };
+                if acknowledge && stream.write_all(&acknowledgment(&frame)).await.is_err() {
+                    return;
+                }
+                if sender.send(data).await.is_err() {
+                    return;
+                }
+            },
+            Ok(_) => {
+                if !matches!(stream.read_buf(&mut pending).await, Ok(1..)) {
+                    return;
+                }
+            },
+            Err(_) => return,
+        }
+    }
+}
@@ -0,0 +1,7 @@
+//! Items of the std prelude which are in `alloc`, so that the pure modules build without std.
+
+pub(crate) use alloc::boxed::Box;
+pub(crate) use alloc::format;
+pub(crate) use alloc::string::{String, ToString};
+pub(crate) use alloc::vec;
+pub(crate) use alloc::vec::Vec;
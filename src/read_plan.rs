@@ -0,0 +1,130 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::commands::device_access::BULK_MAX_WORD_POINTS;
+use crate::mapping::field_words;
+use crate::monitor::{self, MONITOR_MAX_ACCESS_POINTS, monitor_max_access_points};
+use crate::requests::{self, WordRead};
+use crate::{CPU, DeviceData, DeviceLimits, MonitorList, TypedData, TypedDevice};
+
+/// Limits of `SLMPClient::optimized_read`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadCoalescing {
+    /// Unrequested words read through between two devices of a bulk read. 0 merges only adjacent (or overlapping) devices.
+    pub max_gap: usize,
+    /// Words per bulk read, capped at 960.
+    pub max_words: usize,
+    /// Access points per random read of the other devices, capped at `monitor_max_access_points` of the CPU.
+    pub max_random_points: usize,
+}
+
+impl ReadCoalescing {
+    pub const DEFAULT: Self = Self { max_gap: 16, max_words: BULK_MAX_WORD_POINTS, max_random_points: MONITOR_MAX_ACCESS_POINTS };
+}
+
+impl Default for ReadCoalescing {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Word range of a bulk read and the devices in it: their index in the request and word offset.
+struct CoalescedRange {
+    read: WordRead,
+    devices: Vec<(usize, usize)>,
+}
+
+impl CoalescedRange {
+    /// Address next to the last word.
+    fn end(&self) -> usize {
+        self.read.start_device.address + self.read.word_num
+    }
+}
+
+/// Requests of `SLMPClient::optimized_read`: bulk reads of the word devices in runs, then random reads of the rest
+/// (bit devices, and word devices with no neighbour in reach), each batch with the indices of its devices.
+pub(crate) struct ReadPlan {
+    devices: Vec<TypedDevice>,
+    ranges: Vec<CoalescedRange>,
+    random: Vec<Vec<usize>>,
+}
+
+impl ReadPlan {
+    /// Every device is checked as in a random read before anything is planned.
+    pub fn new(cpu: &CPU, limits: &DeviceLimits, coalescing: &ReadCoalescing, devices: &[TypedDevice]) -> crate::io::Result<Self> {
+        requests::check_devices(limits, devices, "Optimized read")?;
+        let max_words: usize = coalescing.max_words.clamp(1, BULK_MAX_WORD_POINTS);
+        let max_points: usize = coalescing.max_random_points.clamp(1, monitor_max_access_points(cpu));
+
+        let mut word_devices: Vec<usize> = (0..devices.len())
+            .filter(|&i| devices[i].device.device_type.is_word_device() && field_words(devices[i].data_type) <= max_words)
+            .collect();
+        word_devices.sort_by_key(|&i| (devices[i].device.device_type as usize, devices[i].device.address, field_words(devices[i].data_type)));
+
+        let mut ranges: Vec<CoalescedRange> = Vec::new();
+        for i in word_devices {
+            let TypedDevice { device, data_type } = devices[i];
+            let end: usize = device.address + field_words(data_type);
+            match ranges.last_mut() {
+                Some(range) if range.read.start_device.device_type == device.device_type
+                    && device.address <= range.end() + coalescing.max_gap
+                    && end.max(range.end()) - range.read.start_device.address <= max_words =>
+                {
+                    let start: usize = range.read.start_device.address;
+                    range.read.word_num = end.max(range.end()) - start;
+                    range.devices.push((i, device.address - start));
+                },
+                _ => ranges.push(CoalescedRange { read: WordRead { start_device: device, word_num: end - device.address }, devices: vec![(i, 0)] }),
+            }
+        }
+
+        // A range of a single device costs a request of its own; it joins the random reads instead.
+        ranges.retain(|x| x.devices.len() > 1);
+        let mut coalesced: Vec<bool> = vec![false; devices.len()];
+        ranges.iter().flat_map(|x| &x.devices).for_each(|&(i, _)| coalesced[i] = true);
+        let random: Vec<usize> = (0..devices.len()).filter(|&i| !coalesced[i]).collect();
+
+        let random_devices: Vec<TypedDevice> = random.iter().map(|&i| devices[i]).collect();
+        let random = monitor::split_batches(&random_devices, max_points)
+            .into_iter()
+            .map(|batch| random[batch].to_vec())
+            .collect();
+        Ok(Self { devices: devices.to_vec(), ranges, random })
+    }
+
+    /// Bulk reads first, then random reads.
+    pub fn commands(&self, cpu: &CPU, limits: &DeviceLimits) -> crate::io::Result<Vec<Vec<u8>>> {
+        let bulk = self.ranges.iter().map(|x| x.read.command(cpu, limits));
+        let random = self.random.iter().map(|x| Ok(requests::random_read(cpu, limits, &self.batch(x))?.0));
+        bulk.chain(random).collect()
+    }
+
+    /// The values in the order of the request, as the responses of `commands` in the same order.
+    pub fn parse(&self, responses: &[Vec<u8>]) -> crate::io::Result<Vec<DeviceData>> {
+        let mut values: Vec<Option<DeviceData>> = vec![None; self.devices.len()];
+        let (bulk, random) = responses.split_at(self.ranges.len().min(responses.len()));
+
+        for (range, recv) in self.ranges.iter().zip(bulk) {
+            range.read.check(recv)?;
+            for &(i, offset) in &range.devices {
+                let TypedDevice { device, data_type } = self.devices[i];
+                let bytes: &[u8] = &recv[offset * 2..(offset + field_words(data_type)) * 2];
+                values[i] = Some(DeviceData { device, data: TypedData::decode(bytes, data_type)? });
+            }
+        }
+        for (batch, recv) in self.random.iter().zip(random) {
+            let parsed = MonitorList::try_from(&self.batch(batch)[..])?.parse(recv)?;
+            for (&i, device_data) in batch.iter().zip(parsed) {
+                values[i] = Some(device_data);
+            }
+        }
+
+        values.into_iter()
+            .map(|x| x.ok_or_else(|| crate::io::Error::new(crate::io::ErrorKind::InvalidData, "Missing response of an optimized read")))
+            .collect()
+    }
+
+    fn batch(&self, indices: &[usize]) -> Vec<TypedDevice> {
+        indices.iter().map(|&i| self.devices[i]).collect()
+    }
+}
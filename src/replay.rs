@@ -0,0 +1,157 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+
+use crate::SlmpTransport;
+use crate::requests::{self, SUBHEADER_LEN};
+
+/// A request frame and the response frame of the same serial No. `None` is a request which got no response.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedExchange {
+    pub request: Vec<u8>,
+    pub response: Option<Vec<u8>>,
+}
+
+/// Frames of a session recorded by `RecordingTransport`, in the order of the requests.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLog {
+    pub exchanges: Vec<RecordedExchange>,
+}
+
+/// Transport passing the frames to `inner` and recording them in a `SessionLog`, e.g. to replay a session with a real CPU
+/// in tests by `ReplayTransport`. The remote password of unlock/lock requests is recorded masked.
+pub struct RecordingTransport<T: SlmpTransport> {
+    inner: T,
+    log: Arc<Mutex<SessionLog>>,
+}
+
+impl<T: SlmpTransport> RecordingTransport<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner, log: Arc::new(Mutex::new(SessionLog::default())) }
+    }
+
+    /// The log being recorded. Take it before giving the transport to `SLMPClient::with_transport`.
+    pub fn log(&self) -> Arc<Mutex<SessionLog>> {
+        self.log.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SessionLog> {
+        self.log.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<T: SlmpTransport> SlmpTransport for RecordingTransport<T> {
+    async fn connect(&mut self) -> std::io::Result<()> {
+        self.inner.connect().await
+    }
+
+    async fn close(&mut self) {
+        self.inner.close().await
+    }
+
+    fn supports_a_series(&self) -> bool {
+        self.inner.supports_a_series()
+    }
+
+    async fn send(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        self.inner.send(frame).await?;
+        let mut request: Vec<u8> = frame.to_vec();
+        requests::mask_frame_password(&mut request);
+        self.lock().exchanges.push(RecordedExchange { request, response: None });
+        Ok(())
+    }
+
+    /// A response is recorded with the latest request of its serial No., so a late response finds its own request.
+    async fn recv(&mut self) -> std::io::Result<Vec<u8>> {
+        let frame = self.inner.recv().await?;
+        let serial_id: u16 = requests::frame_serial_id(&frame);
+        if let Some(exchange) = self.lock().exchanges.iter_mut().rev().find(|x| requests::frame_serial_id(&x.request) == serial_id && x.response.is_none()) {
+            exchange.response = Some(frame.clone());
+        }
+        Ok(frame)
+    }
+}
+
+/// How `ReplayTransport` finds the recorded exchange of a request.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ReplayMatch {
+    /// The whole frame.
+    #[default]
+    Exact,
+    /// The command, the subcommand and the request data (e.g. the devices and the values written),
+    /// so that the serial No., the destination and the monitoring timer may differ from the recording.
+    Command,
+}
+
+/// Transport answering requests with the responses of a `SessionLog`, without a CPU.
+///
+/// A request takes the first exchange not replayed yet whose request matches, so that a repeated request gets
+/// the recorded responses in turn. The response carries the serial No. of the request.
+/// A request not in the log fails with `InvalidInput`, and one recorded without a response gets none (the client times out).
+pub struct ReplayTransport {
+    exchanges: Vec<Option<RecordedExchange>>,
+    matching: ReplayMatch,
+    responses: VecDeque<Vec<u8>>,
+}
+
+impl ReplayTransport {
+    pub fn new(log: SessionLog, matching: ReplayMatch) -> Self {
+        Self { exchanges: log.exchanges.into_iter().map(Some).collect(), matching, responses: VecDeque::new() }
+    }
+
+    /// Exchanges of the log not replayed yet.
+    pub fn remaining(&self) -> usize {
+        self.exchanges.iter().flatten().count()
+    }
+
+    fn matches(&self, recorded: &[u8], frame: &[u8]) -> bool {
+        match self.matching {
+            ReplayMatch::Exact => recorded == frame,
+            ReplayMatch::Command => recorded.get(SUBHEADER_LEN..).is_some_and(|x| Some(x) == frame.get(SUBHEADER_LEN..)),
+        }
+    }
+}
+
+impl SlmpTransport for ReplayTransport {
+    async fn connect(&mut self) -> std::io::Result<()> {
+        self.responses.clear();
+        Ok(())
+    }
+
+    async fn close(&mut self) {
+        self.responses.clear();
+    }
+
+    async fn send(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        // Recorded with the password masked.
+        let mut masked: Vec<u8> = frame.to_vec();
+        requests::mask_frame_password(&mut masked);
+        let found = self.exchanges.iter().position(|x| x.as_ref().is_some_and(|x| self.matches(&x.request, &masked)));
+        let Some(exchange) = found.and_then(|i| self.exchanges[i].take()) else {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "Request not in the recorded session"));
+        };
+
+        if let Some(mut response) = exchange.response {
+            if response.len() >= 4 {
+                response[2..4].copy_from_slice(&frame[2..4]);
+            }
+            self.responses.push_back(response);
+        }
+        Ok(())
+    }
+
+    /// It waits forever for a request without a recorded response, like a CPU not answering.
+    async fn recv(&mut self) -> std::io::Result<Vec<u8>> {
+        match self.responses.pop_front() {
+            Some(response) => Ok(response),
+            None => std::future::pending().await,
+        }
+    }
+
+    /// The frames are recorded as the client sends and receives them, 4E frames for `CPU::A` as well.
+    fn supports_a_series(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,889 @@
+//! Request frames and response parsing, shared by the async client and the blocking client.
+//! The functions here only build and check bytes; sending them is up to the client.
+
+use crate::prelude::*;
+use alloc::borrow::Cow;
+
+use crate::commands::device_access::{read::*, write::*, check_limit, bulk_max_bit_points, bulk_max_word_points, random_write_max_bit_points, random_write_max_word_size, block_max_blocks, BLOCK_MAX_POINTS};
+use crate::device::DeviceSize;
+use crate::mapping::field_words;
+use crate::{AccessType, BlockedDeviceData, CPU, DataType, Device, DeviceBlock, DeviceData, DeviceLimits, MonitorList, PLCString, SLMP4EConnectionProps, SLMPEndCode, TypedData, TypedDevice, TypedDeviceBlock, WordOrder, div_ceil, u8_to_bits};
+
+pub(crate) const SUBHEADER_LEN: usize = 15;
+pub(crate) const FIXED_FRAME_LEN: usize = 13;
+/// Response header and end code, in front of the response data.
+pub(crate) const RECVFRAME_PREFIX_FIXED_LEN: usize = 15;
+
+fn invalid_data(msg: &str) -> crate::io::Error {
+    crate::io::Error::new(crate::io::ErrorKind::InvalidData, msg)
+}
+
+/// Header of a request frame up to the monitoring timer, for a command of `command_len` bytes.
+pub(crate) fn create_subheader(connection_props: &SLMP4EConnectionProps, serial_id: u16, command_len: usize) -> crate::io::Result<[u8; SUBHEADER_LEN]> {
+    const BLANK_CODE: u8 = 0x00;
+    const REQUEST_CODE: [u8; 2] = [0x54, 0x00];
+    const CPUTIMER_LEN: usize = 2;
+
+    let serial_id: [u8; 2] = serial_id.to_le_bytes();
+    let io_id: [u8; 2] = connection_props.io_id.to_le_bytes();
+    let cpu_timer: [u8; 2] = connection_props.cpu_timer.to_le_bytes();
+
+    // "Command length" counts the packet from cpu_timer
+    let command_len: [u8; 2] = command_len
+        .checked_add(CPUTIMER_LEN)
+        .and_then(|x| u16::try_from(x).ok())
+        .ok_or_else(|| crate::io::Error::new(crate::io::ErrorKind::InvalidInput, format!("Request too long: {command_len} bytes")))?
+        .to_le_bytes();
+
+    Ok([
+        REQUEST_CODE[0], REQUEST_CODE[1],
+        serial_id[0], serial_id[1],
+        BLANK_CODE, BLANK_CODE,
+        connection_props.network_id,
+        connection_props.pc_id,
+        io_id[0], io_id[1],
+        connection_props.area_id,
+        command_len[0], command_len[1],
+        cpu_timer[0], cpu_timer[1],
+    ])
+}
+
+/// A whole request frame: the header with `serial_id` and the command.
+/// Refuse a request changing the CPU on a read-only client, before its frame is built.
+pub(crate) fn check_read_only(read_only: bool, command: &[u8]) -> crate::io::Result<()> {
+    if read_only && crate::commands::is_write_command(command) {
+        return Err(crate::io::Error::new(crate::io::ErrorKind::PermissionDenied, "Write refused by a read-only client"));
+    }
+    Ok(())
+}
+
+pub(crate) fn request_frame(connection_props: &SLMP4EConnectionProps, serial_id: u16, command: &[u8]) -> crate::io::Result<Vec<u8>> {
+    let subheader: [u8; SUBHEADER_LEN] = create_subheader(connection_props, serial_id, command.len())?;
+
+    let mut frame: Vec<u8> = Vec::with_capacity(SUBHEADER_LEN + command.len());
+    frame.extend(&subheader);
+    frame.extend(command);
+    Ok(frame)
+}
+
+/// Response data after the end code, of a response passing `validate_response`.
+pub(crate) fn response_data<'a>(connection_props: &SLMP4EConnectionProps, frame: &'a [u8], serial_id: u16) -> crate::io::Result<&'a [u8]> {
+    validate_response(connection_props, frame, serial_id)?;
+    Ok(&frame[RECVFRAME_PREFIX_FIXED_LEN..])
+}
+
+/// Length of the first frame in `pending`, or `None` until its header has been received.
+pub(crate) fn frame_len(pending: &[u8], max_len: usize) -> crate::io::Result<Option<usize>> {
+    if pending.len() < FIXED_FRAME_LEN {
+        return Ok(None);
+    }
+    let frame_len: usize = FIXED_FRAME_LEN + u16::from_le_bytes([pending[11], pending[12]]) as usize;
+    if frame_len > max_len {
+        return Err(invalid_data("Received Too Long Data Frame"));
+    }
+    Ok(Some(frame_len))
+}
+
+/// Serial No. of a frame taken by `frame_len`.
+pub(crate) fn frame_serial_id(frame: &[u8]) -> u16 {
+    u16::from_le_bytes([frame[2], frame[3]])
+}
+
+/// Stands in for each byte of a remote password in the frames recorded or displayed.
+pub(crate) const PASSWORD_MASK: u8 = b'*';
+
+/// Replace the remote password of an unlock/lock request (0x1630/0x1631) with `PASSWORD_MASK`, keeping its length.
+/// `data` is the request data after the subcommand (the password length, then the password).
+pub(crate) fn mask_password(command: u16, data: &mut [u8]) {
+    if matches!(command, 0x1630 | 0x1631) && let Some(password) = data.get_mut(2..) {
+        password.fill(PASSWORD_MASK);
+    }
+}
+
+/// `mask_password` on a whole request frame.
+pub(crate) fn mask_frame_password(frame: &mut [u8]) {
+    let Some(&[command_low, command_high, _, _]) = frame.get(SUBHEADER_LEN..SUBHEADER_LEN + 4) else {
+        return;
+    };
+    mask_password(u16::from_le_bytes([command_low, command_high]), &mut frame[SUBHEADER_LEN + 4..]);
+}
+
+/// Check the header of a response frame and its end code.
+pub(crate) fn validate_response(connection_props: &SLMP4EConnectionProps, data: &[u8], serial_id: u16) -> crate::io::Result<()> {
+    const RESPONSE_CODE: [u8; 2] = [0xD4, 0x00];
+    const BLANK_CODE: u8 = 0x00;
+
+    let data_len: usize = data.len();
+    if data_len < FIXED_FRAME_LEN {
+        return Err(invalid_data("Received Invalid Length Data"));
+    }
+
+    let data_block_len: usize = u16::from_le_bytes([data[11], data[12]]) as usize;
+    if data_block_len != data_len - FIXED_FRAME_LEN {
+        return Err(invalid_data("Received Invalid Data Frame"));
+    }
+    // The data block starts with the end code.
+    if data_block_len < 2 {
+        return Err(invalid_data("Received Invalid Length Data"));
+    }
+
+    let checks: [(&[u8], &[u8], &str); 7] = [
+        (&data[0..2], &RESPONSE_CODE, "Received Invalid Response Data"),
+        (&data[2..4], &serial_id.to_le_bytes(), "Received Invalid Serial ID"),
+        (&data[4..6], &[BLANK_CODE; 2], "Received Invalid Blank Code"),
+        (&data[6..7], &[connection_props.network_id], "Received Invalid Network ID"),
+        (&data[7..8], &[connection_props.pc_id], "Received Invalid PC ID"),
+        (&data[8..10], &connection_props.io_id.to_le_bytes(), "Received Invalid IO ID"),
+        (&data[10..11], &[connection_props.area_id], "Received Invalid Area ID"),
+    ];
+    if let Some((_, _, msg)) = checks.iter().find(|(received, expected, _)| received != expected) {
+        return Err(invalid_data(msg));
+    }
+
+    let error = u16::from_le_bytes([data[13], data[14]]);
+    match SLMPEndCode(error) {
+        SLMPEndCode(0) => {},
+        #[cfg(feature = "std")]
+        end_code @ (SLMPEndCode::RELAY_FAILURE | SLMPEndCode::TIMEOUT_ERROR) => {
+            return Err(crate::RelayError { end_code, route: crate::Route::of(connection_props) }.into());
+        },
+        end_code => return Err(end_code.into()),
+    }
+
+    Ok(())
+}
+
+/// Requests without devices build frames without data, which CPUs answer inconsistently
+/// (an end code, garbage or a closed socket), so they are not sent.
+pub(crate) fn check_not_empty(len: usize, request: &str) -> crate::io::Result<()> {
+    if len == 0 {
+        return Err(crate::io::Error::new(crate::io::ErrorKind::InvalidInput, format!("{request} with no devices")));
+    }
+    Ok(())
+}
+
+/// Type and range of a value to be written.
+pub(crate) fn check_data(data: &TypedData) -> crate::io::Result<()> {
+    check_data_type(data.get_type())?;
+    data.check_range()
+}
+
+pub(crate) fn check_data_type(data_type: DataType) -> crate::io::Result<()> {
+    if let Some((0, _)) = data_type.as_string() {
+        return Err(crate::io::Error::new(crate::io::ErrorKind::InvalidInput, format!("DataType::{data_type} has no words to access")));
+    }
+    Ok(())
+}
+
+/// Bit access needs a bit device, and contacts and coils of timers and counters (TS, TC, SS, SC, CS, CC) take only bit access.
+/// CPUs answer these with an end code.
+pub(crate) fn check_access(device: Device, access_type: AccessType) -> crate::io::Result<()> {
+    let device_type = device.device_type;
+    match access_type {
+        AccessType::Bit if device_type.is_word_device() => Err(crate::io::Error::new(
+            crate::io::ErrorKind::InvalidInput,
+            format!("Bit access to {device}: {device_type:?} is a word device (access it in words, e.g. as U16)")
+        )),
+        AccessType::Word if device_type.is_bit_only() => Err(crate::io::Error::new(
+            crate::io::ErrorKind::InvalidInput,
+            format!("Word access to {device}: {device_type:?} is a timer or counter contact accessed in bits (Bool)")
+        )),
+        _ => Ok(()),
+    }
+}
+
+/* Word order */
+
+/// Values to be written in `order`. They are borrowed as they are for `WordOrder::LowHigh`.
+pub(crate) fn ordered_data(data: &[TypedData], order: WordOrder) -> Cow<'_, [TypedData]> {
+    match order {
+        WordOrder::LowHigh => Cow::Borrowed(data),
+        order => Cow::Owned(data.iter().map(|x| x.clone().with_word_order(order)).collect()),
+    }
+}
+
+/// Values written or read in `order`; the conversion is the same both ways.
+/// Same as `ordered_device_data` in place.
+pub(crate) fn order_device_data(data: &mut [DeviceData], order: WordOrder) {
+    if order != WordOrder::LowHigh {
+        for x in data {
+            x.data = core::mem::replace(&mut x.data, TypedData::Bool(false)).with_word_order(order);
+        }
+    }
+}
+
+pub(crate) fn ordered_device_data(data: Vec<DeviceData>, order: WordOrder) -> Vec<DeviceData> {
+    match order {
+        WordOrder::LowHigh => data,
+        order => data.into_iter()
+            .map(|x| DeviceData { device: x.device, data: x.data.with_word_order(order) })
+            .collect(),
+    }
+}
+
+/* Write */
+
+pub(crate) fn bulk_write(cpu: &CPU, limits: &DeviceLimits, start_device: Device, data: &[TypedData], order: WordOrder) -> crate::io::Result<Vec<u8>> {
+    let data: &[TypedData] = &ordered_data(data, order);
+    check_not_empty(data.len(), "Bulk write")?;
+    data.iter().try_for_each(check_data)?;
+    if data.iter().all(|x| matches!(x, TypedData::Bool(_))) {
+        check_limit("bulk write bit points", data.len(), bulk_max_bit_points(cpu))?;
+        check_access(start_device, AccessType::Bit)?;
+        limits.check(start_device, data.len())?;
+    } else {
+        check_access(start_device, AccessType::Word)?;
+        let words: usize = data.iter().map(|x| x.get_type().byte_size()).sum::<usize>() / 2;
+        check_limit("bulk write word points", words, bulk_max_word_points(cpu))?;
+        limits.check(start_device, words * start_device.device_type.points(DataType::U16))?;
+    }
+
+    let query = SLMPBulkWriteQuery {
+        cpu,
+        start_device,
+        data,
+    };
+    let cmd: SLMPBulkWriteCommand = query.into();
+    Ok(cmd.0)
+}
+
+/// Word devices and bit devices are written by separate requests; the ones with no devices are left out.
+pub(crate) fn random_write(cpu: &CPU, limits: &DeviceLimits, data: &[DeviceData], order: WordOrder) -> crate::io::Result<Vec<Vec<u8>>> {
+    let data: &[DeviceData] = &match order {
+        WordOrder::LowHigh => Cow::Borrowed(data),
+        order => Cow::Owned(ordered_device_data(data.to_vec(), order)),
+    };
+    check_not_empty(data.len(), "Random write")?;
+    data.iter().try_for_each(|x| check_data(&x.data))?;
+    data.iter().try_for_each(|x| check_access(x.device, match x.data {
+        TypedData::Bool(_) => AccessType::Bit,
+        _ => AccessType::Word,
+    }))?;
+    data.iter().try_for_each(|x| limits.check(x.device, x.device.device_type.points(x.data.get_type())))?;
+
+    // Word access
+    let mut sorted_word_data: Vec<DeviceData> = data.iter()
+        .filter(|x| !matches!(x.data, TypedData::Bool(_)))
+        .cloned()
+        .collect();
+    sorted_word_data.sort_by_key(|p| p.device.address);
+    sorted_word_data.sort_by_key(|p| p.data.get_type().access_order());
+
+    // Bit access
+    let mut sorted_bit_data: Vec<DeviceData> = data.iter()
+        .filter(|x| matches!(x.data, TypedData::Bool(_)))
+        .cloned()
+        .collect();
+    sorted_bit_data.sort_by_key(|p| p.device.address);
+
+    let single_word_access_points_for_multi_word_communication: usize = sorted_word_data
+        .iter()
+        .filter(|x| matches!(x.data.get_type().device_size(), DeviceSize::MultiWord(_)))
+        .fold(0, |a, b| {
+            if let DeviceSize::MultiWord(n) = b.data.get_type().device_size() { a + n as usize } else { a }
+        });
+
+    let single_word_access_points: usize = sorted_word_data
+        .iter()
+        .filter(|x| x.data.get_type().device_size() == DeviceSize::SingleWord)
+        .count() + single_word_access_points_for_multi_word_communication;
+
+    let double_word_access_points: usize = sorted_word_data
+        .iter()
+        .filter(|x| x.data.get_type().device_size() == DeviceSize::DoubleWord)
+        .count();
+
+    let bit_access_points: usize = sorted_bit_data
+        .iter()
+        .filter(|x| x.data.get_type().device_size() == DeviceSize::Bit).count();
+
+    // The counts are sent as a single byte each; within the limits they fit.
+    check_limit("random write word points (word x 12 + double word x 14)",
+        single_word_access_points * 12 + double_word_access_points * 14, random_write_max_word_size(cpu))?;
+    check_limit("random write bit points", bit_access_points, random_write_max_bit_points(cpu))?;
+    let (single_word_access_points, double_word_access_points, bit_access_points) =
+        (single_word_access_points as u8, double_word_access_points as u8, bit_access_points as u8);
+
+    let mut ret: Vec<Vec<u8>> = Vec::with_capacity(2);
+
+    if single_word_access_points + double_word_access_points > 0 {
+        let query = SLMPRandomWriteQuery {
+            cpu,
+            sorted_data: &sorted_word_data,
+            access_type: AccessType::Word,
+            bit_access_points: 0,
+            single_word_access_points,
+            double_word_access_points,
+        };
+        let cmd: SLMPRandomWriteCommand = query.into();
+        ret.push(cmd.0);
+    }
+
+    if bit_access_points > 0 {
+        let query = SLMPRandomWriteQuery {
+            cpu,
+            sorted_data: &sorted_bit_data,
+            access_type: AccessType::Bit,
+            bit_access_points,
+            single_word_access_points: 0,
+            double_word_access_points: 0,
+        };
+        let cmd: SLMPRandomWriteCommand = query.into();
+        ret.push(cmd.0);
+    }
+
+    Ok(ret)
+}
+
+pub(crate) fn block_write<'a>(cpu: &CPU, limits: &DeviceLimits, data: &'a [BlockedDeviceData<'a>], order: WordOrder) -> crate::io::Result<Vec<u8>> {
+    let ordered: Vec<Cow<[TypedData]>> = data.iter().map(|x| ordered_data(x.data, order)).collect();
+    let data: Vec<BlockedDeviceData> = data.iter().zip(&ordered).map(|(x, ordered)| BlockedDeviceData { data: ordered, ..*x }).collect();
+
+    check_not_empty(data.len(), "Block write")?;
+    for block in &data {
+        check_not_empty(block.data.len(), "Block write of a block")?;
+        block.data.iter().try_for_each(check_data)?;
+        if block.access_type == AccessType::Bit && let Some(x) = block.data.iter().find(|x| !matches!(x, TypedData::Bool(_))) {
+            return Err(crate::io::Error::new(
+                crate::io::ErrorKind::InvalidInput,
+                format!("Bit block of {} takes Bool only, got {:?} (write it in a word block)", block.start_device, x.get_type())
+            ));
+        }
+        check_access(block.start_device, block.access_type)?;
+        let points: usize = match block.access_type {
+            AccessType::Word => block.data.iter().map(|x| x.get_type().byte_size()).sum::<usize>() / 2 * block.start_device.device_type.points(DataType::U16),
+            AccessType::Bit => div_ceil(block.data.len(), 16) * 16,
+        };
+        limits.check(block.start_device, points)?;
+    }
+
+    let mut sorted_data = data;
+    sorted_data.sort_by_key(|p| p.access_type);
+
+    check_limit(&format!("block write blocks (word + bit) of {cpu:?} CPU"), sorted_data.len(), block_max_blocks(cpu))?;
+    let points: usize = sorted_data.iter().map(|x| match x.access_type {
+        AccessType::Word => x.data.iter().map(|y| y.get_type().byte_size()).sum::<usize>() / 2,
+        AccessType::Bit => div_ceil(x.data.len(), 16),
+    }).sum();
+    check_limit("block write word points", points, BLOCK_MAX_POINTS)?;
+
+    let word_access_points: u8 = sorted_data.iter().filter(|x| x.access_type == AccessType::Word).count() as u8;
+    let bit_access_points: u8 = sorted_data.iter().filter(|x| x.access_type == AccessType::Bit).count() as u8;
+
+    let query = SLMPBlockWriteQuery {
+        cpu,
+        sorted_data: &sorted_data,
+        word_access_points,
+        bit_access_points
+    };
+    let cmd: SLMPBlockWriteCommand = query.into();
+    Ok(cmd.0)
+}
+
+/// Word points of a block of a block read or write.
+const fn block_points(access_type: AccessType, words: usize, bits: usize) -> usize {
+    match access_type {
+        AccessType::Word => words,
+        AccessType::Bit => div_ceil(bits, 16),
+    }
+}
+
+/// Consecutive groups of `sorted` within the limits on blocks and word points of a block request.
+/// A block too large for a request is left in a group of its own, to fail the checks of that request.
+fn split_blocks<'a, T>(cpu: &CPU, sorted: &'a [T], points: impl Fn(&T) -> usize) -> Vec<&'a [T]> {
+    let max_blocks: usize = block_max_blocks(cpu);
+    let mut groups: Vec<&'a [T]> = Vec::new();
+    let (mut start, mut sum) = (0, 0);
+    for (i, x) in sorted.iter().enumerate() {
+        let points: usize = points(x);
+        if i > start && (i - start == max_blocks || sum + points > BLOCK_MAX_POINTS) {
+            groups.push(&sorted[start..i]);
+            (start, sum) = (i, 0);
+        }
+        sum += points;
+    }
+    if start < sorted.len() {
+        groups.push(&sorted[start..]);
+    }
+    groups
+}
+
+/// Block write split across requests, in the order a single block write takes the blocks (word blocks first).
+pub(crate) fn split_block_write(cpu: &CPU, limits: &DeviceLimits, data: &[BlockedDeviceData], order: WordOrder) -> crate::io::Result<Vec<Vec<u8>>> {
+    check_not_empty(data.len(), "Block write")?;
+    let mut sorted: Vec<BlockedDeviceData> = data.to_vec();
+    sorted.sort_by_key(|p| p.access_type);
+    let words = |x: &BlockedDeviceData| x.data.iter().map(|y| y.get_type().byte_size()).sum::<usize>() / 2;
+    split_blocks(cpu, &sorted, |x| block_points(x.access_type, words(x), x.data.len()))
+        .into_iter()
+        .map(|x| block_write(cpu, limits, x, order))
+        .collect()
+}
+
+/// Mask of bit `bit_index` (0: the least significant) of a word, failing with `InvalidInput` past 15.
+pub(crate) fn word_bit_mask(bit_index: u8) -> crate::io::Result<u16> {
+    if bit_index < 16 {
+        Ok(1 << bit_index)
+    } else {
+        Err(crate::io::Error::new(crate::io::ErrorKind::InvalidInput, format!("Bit index {bit_index} is out of a word (0 to 15)")))
+    }
+}
+
+/// Words as they are, without the conversion of `TypedData`.
+pub(crate) fn bulk_write_words(cpu: &CPU, limits: &DeviceLimits, start_device: Device, data: &[u16]) -> crate::io::Result<Vec<u8>> {
+    check_not_empty(data.len(), "Bulk write")?;
+    check_limit("bulk write word points", data.len(), bulk_max_word_points(cpu))?;
+    check_access(start_device, AccessType::Word)?;
+    limits.check(start_device, data.len() * start_device.device_type.points(DataType::U16))?;
+
+    let query = SLMPBulkWriteWordsQuery {
+        cpu,
+        start_device,
+        data,
+    };
+    let cmd: SLMPBulkWriteCommand = query.into();
+    Ok(cmd.0)
+}
+
+/// Bulk writes of one value to `count` consecutive points, as many as the write limit takes.
+/// The value is encoded once, in bits for `Bool` and in words otherwise.
+pub(crate) struct Fill {
+    start_device: Device,
+    count: usize,
+    value: Vec<u8>,
+    access_type: AccessType,
+    /// Values per request.
+    chunk: usize,
+}
+
+impl Fill {
+    pub fn new(cpu: &CPU, limits: &DeviceLimits, start_device: Device, count: usize, value: &TypedData, order: WordOrder) -> crate::io::Result<Self> {
+        check_not_empty(count, "Fill")?;
+        check_data(value)?;
+        let (access_type, chunk, points) = match value {
+            TypedData::Bool(_) => (AccessType::Bit, bulk_max_bit_points(cpu), count),
+            value => {
+                let words: usize = value.to_bytes().len() / 2;
+                (AccessType::Word, bulk_max_word_points(cpu) / words, count.saturating_mul(words).saturating_mul(start_device.device_type.points(DataType::U16)))
+            },
+        };
+        check_access(start_device, access_type)?;
+        limits.check(start_device, points)?;
+
+        Ok(Self { start_device, count, value: value.clone().with_word_order(order).to_bytes(), access_type, chunk })
+    }
+
+    /// Values written and the command of each request.
+    pub fn commands(&self, cpu: CPU) -> impl Iterator<Item = (usize, Vec<u8>)> + '_ {
+        let points_per_value: usize = match self.access_type {
+            AccessType::Bit => 1,
+            AccessType::Word => self.value.len() / 2 * self.start_device.device_type.points(DataType::U16),
+        };
+        (0..self.count).step_by(self.chunk).map(move |offset| {
+            let count: usize = self.chunk.min(self.count - offset);
+            let query = SLMPBulkFillQuery {
+                cpu: &cpu,
+                start_device: self.start_device + offset * points_per_value,
+                count,
+                value: &self.value,
+                access_type: self.access_type,
+            };
+            let cmd: SLMPBulkWriteCommand = query.into();
+            (offset + count, cmd.0)
+        })
+    }
+}
+
+/// A text in Shift-JIS, failing with `InvalidInput` if it does not fit in `word_len` words.
+pub(crate) fn string_write(cpu: &CPU, limits: &DeviceLimits, device: Device, text: &str, word_len: u8) -> crate::io::Result<Vec<u8>> {
+    let data = TypedData::String(PLCString::try_from_str(text, word_len)?);
+    bulk_write(cpu, limits, device, &[data], WordOrder::LowHigh)
+}
+
+/// Fields of a struct (`SlmpWritable`) as one word write. `Bool` fields are written as a word (0 or 1),
+/// and strings are fitted to the size in `fields`.
+pub(crate) fn struct_write(cpu: &CPU, limits: &DeviceLimits, start_device: Device, fields: &[DataType], values: Vec<TypedData>, order: WordOrder) -> crate::io::Result<Vec<u8>> {
+    if values.len() != fields.len() {
+        return Err(crate::io::Error::new(
+            crate::io::ErrorKind::InvalidInput,
+            format!("Struct write of {} values for {} fields", values.len(), fields.len())
+        ));
+    }
+
+    let data: Vec<TypedData> = fields.iter().zip(values).enumerate()
+        .map(|(i, (&data_type, value))| match (data_type, value) {
+            (DataType::Bool, TypedData::Bool(v)) => Ok(TypedData::U16(v as u16)),
+            (data_type, TypedData::String(v)) if let Some((n, encoding)) = data_type.as_string() && encoding == v.encoding() => Ok(TypedData::String(v.resized(n))),
+            (data_type, value) if value.get_type() == data_type => Ok(value),
+            (data_type, value) => Err(crate::io::Error::new(
+                crate::io::ErrorKind::InvalidInput,
+                format!("Field {i} of struct write is {} ({data_type} expected)", value.get_type())
+            )),
+        })
+        .collect::<crate::io::Result<_>>()?;
+
+    bulk_write(cpu, limits, start_device, &data, order)
+}
+
+/* Read */
+
+pub(crate) struct BulkRead {
+    pub start_device: Device,
+    pub device_num: usize,
+    pub data_type: DataType,
+}
+
+impl BulkRead {
+    pub fn command(&self, cpu: &CPU, limits: &DeviceLimits) -> crate::io::Result<Vec<u8>> {
+        let (device_num, data_type) = (self.device_num, self.data_type);
+
+        check_not_empty(device_num, "Bulk read")?;
+        check_data_type(data_type)?;
+        match data_type {
+            DataType::Bool => check_limit("bulk read bit points", device_num, bulk_max_bit_points(cpu))?,
+            _ => check_limit("bulk read word points", device_num.saturating_mul(data_type.byte_size() / 2), bulk_max_word_points(cpu))?,
+        }
+        check_access(self.start_device, match data_type {
+            DataType::Bool => AccessType::Bit,
+            _ => AccessType::Word,
+        })?;
+        limits.check(self.start_device, device_num * self.start_device.device_type.points(data_type))?;
+
+        let query = SLMPBulkReadQuery {
+            cpu,
+            start_device: self.start_device,
+            device_num,
+            data_type,
+        };
+        let cmd: SLMPBulkReadCommand = query.into();
+        Ok(cmd.0)
+    }
+
+    pub fn parse(&self, recv: &[u8]) -> crate::io::Result<Vec<DeviceData>> {
+        let (device_num, data_type) = (self.device_num, self.data_type);
+
+        // Bits are packed two points per byte; the other types take `byte_size` per device.
+        let expected_len: usize = match data_type {
+            DataType::Bool => device_num.div_ceil(2),
+            _ => device_num * data_type.byte_size(),
+        };
+        if recv.len() != expected_len {
+            return Err(crate::io::Error::new(
+                crate::io::ErrorKind::InvalidData,
+                format!("Bulk read of {device_num} x {data_type} returned {} bytes ({expected_len} bytes expected)", recv.len())
+            ));
+        }
+
+        let device_type = self.start_device.device_type;
+        let start_address = self.start_device.address;
+
+        match data_type {
+            DataType::Bool => {
+                // The first point is in the upper nibble, as written by `bulk_write`.
+                let ret: Vec<DeviceData> = recv
+                    .iter()
+                    .flat_map(|&x| [(x >> 4) & 0x01, x & 0x01])
+                    .take(device_num)
+                    .enumerate()
+                    .map(|(i, data)| DeviceData {
+                        device: Device {device_type, address: start_address + i},
+                        data: TypedData::Bool(data == 1)
+                    })
+                    .collect();
+                Ok(ret)
+            }
+            _ => {
+                // A value takes its words, 16 points each on a bit device: X0, X10, X20, ... for `U16` and X0, X20, ... for `U32`.
+                let stride: usize = device_type.points(data_type);
+
+                recv
+                    .chunks_exact(data_type.byte_size())
+                    .enumerate()
+                    .map(|(i, data)| Ok(DeviceData {
+                        device: Device {device_type, address: start_address + stride * i},
+                        data: TypedData::decode(data, data_type)?
+                    }))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// The command and the list to parse the response with.
+pub(crate) fn random_read(cpu: &CPU, limits: &DeviceLimits, devices: &[TypedDevice]) -> crate::io::Result<(Vec<u8>, MonitorList)> {
+    check_devices(limits, devices, "Random read")?;
+    let mut monitor_list = MonitorList::for_cpu(cpu);
+    monitor_list.try_extend(devices.iter().copied())?;
+
+    let query = SLMPRandomReadQuery {
+        cpu,
+        monitor_list: &monitor_list
+    };
+    let cmd: SLMPRandomReadCommand = query.into();
+    Ok((cmd.0, monitor_list))
+}
+
+/// Devices of a random read or a monitor registration.
+pub(crate) fn check_devices(limits: &DeviceLimits, devices: &[TypedDevice], request: &str) -> crate::io::Result<()> {
+    check_not_empty(devices.len(), request)?;
+    devices.iter().try_for_each(|x| check_data_type(x.data_type))?;
+    // Every device is read in words, `Bool` ones included.
+    devices.iter().try_for_each(|x| check_access(x.device, AccessType::Word))?;
+    devices.iter().try_for_each(|x| limits.check(x.device, x.device.device_type.points(x.data_type)))
+}
+
+/// The command and the list to parse the responses of monitor read with.
+pub(crate) fn monitor_register(cpu: &CPU, limits: &DeviceLimits, devices: &[TypedDevice]) -> crate::io::Result<(Vec<u8>, MonitorList)> {
+    check_devices(limits, devices, "Monitor register")?;
+    let mut monitor_list = MonitorList::for_cpu(cpu);
+    monitor_list.try_extend(devices.iter().copied())?;
+
+    let query = SLMPMonitorRegisterQuery {
+        cpu,
+        monitor_list: &monitor_list
+    };
+    let cmd: SLMPMonitorRegisterCommand = query.into();
+    Ok((cmd.0, monitor_list))
+}
+
+/// Monitor register of no devices, which releases the registered ones.
+pub(crate) fn monitor_clear(cpu: &CPU) -> Vec<u8> {
+    let query = SLMPMonitorRegisterQuery {
+        cpu,
+        monitor_list: &MonitorList::new()
+    };
+    let cmd: SLMPMonitorRegisterCommand = query.into();
+    cmd.0
+}
+
+/// Blocks in the order of the request, which the response follows.
+pub(crate) struct BlockRead {
+    sorted_block: Vec<DeviceBlock>,
+    data_types: Vec<Option<DataType>>,
+}
+
+impl BlockRead {
+    const WORD_RESPONSE_BYTEELEN: usize = 2;
+
+    pub fn new(cpu: &CPU, limits: &DeviceLimits, device_blocks: &[DeviceBlock]) -> crate::io::Result<Self> {
+        let blocks: Vec<TypedDeviceBlock> = device_blocks.iter().map(|&x| x.into()).collect();
+        Self::typed(cpu, limits, &blocks)
+    }
+
+    pub fn typed(cpu: &CPU, limits: &DeviceLimits, blocks: &[TypedDeviceBlock]) -> crate::io::Result<Self> {
+        blocks.iter().try_for_each(Self::check_data_type)?;
+        let device_blocks: &[DeviceBlock] = &blocks.iter().map(TypedDeviceBlock::block).collect::<Vec<_>>();
+        check_not_empty(device_blocks.len(), "Block read")?;
+        device_blocks.iter().try_for_each(|x| check_not_empty(x.size, "Block read of a block"))?;
+        device_blocks.iter().try_for_each(|x| check_access(x.start_device, x.access_type))?;
+        device_blocks.iter().try_for_each(|x| {
+            let (start_device, words) = x.request_words();
+            limits.check(start_device, words.saturating_mul(start_device.device_type.points(DataType::U16)))
+        })?;
+        check_limit(&format!("block read blocks (word + bit) of {cpu:?} CPU"), device_blocks.len(), block_max_blocks(cpu))?;
+        let points: usize = device_blocks.iter().map(|x| x.request_words().1).fold(0, usize::saturating_add);
+        check_limit("block read word points", points, BLOCK_MAX_POINTS)?;
+
+        let mut sorted_block = blocks.to_vec();
+        sorted_block.sort_by_key(|p| p.start_device.address);
+        sorted_block.sort_by_key(|p| p.access_type);
+
+        Ok(Self {
+            data_types: sorted_block.iter().map(|x| x.data_type).collect(),
+            sorted_block: sorted_block.iter().map(TypedDeviceBlock::block).collect(),
+        })
+    }
+
+    /// Block read split across requests, in the order a single block read takes the blocks (word blocks first, by address).
+    pub fn split(cpu: &CPU, limits: &DeviceLimits, device_blocks: &[DeviceBlock]) -> crate::io::Result<Vec<Self>> {
+        check_not_empty(device_blocks.len(), "Block read")?;
+        let mut sorted: Vec<DeviceBlock> = device_blocks.to_vec();
+        sorted.sort_by_key(|p| p.start_device.address);
+        sorted.sort_by_key(|p| p.access_type);
+        split_blocks(cpu, &sorted, |x| x.request_words().1)
+            .into_iter()
+            .map(|x| Self::new(cpu, limits, x))
+            .collect()
+    }
+
+    /// A data type is decoded from whole words of a word block.
+    fn check_data_type(block: &TypedDeviceBlock) -> crate::io::Result<()> {
+        let Some(data_type) = block.data_type else {
+            return Ok(());
+        };
+        check_data_type(data_type)?;
+        if block.access_type == AccessType::Bit || data_type == DataType::Bool {
+            return Err(crate::io::Error::new(crate::io::ErrorKind::InvalidInput, format!("Block read of {:?} decodes words, not bits", data_type)));
+        }
+        let words: usize = data_type.byte_size() / 2;
+        if !block.size.is_multiple_of(words) {
+            return Err(crate::io::Error::new(
+                crate::io::ErrorKind::InvalidInput,
+                format!("Block read of {} words does not divide into {:?} of {} words", block.size, data_type, words)
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn command(&self, cpu: &CPU) -> Vec<u8> {
+        let word_access_points: u8 = self.sorted_block.iter().filter(|x| x.access_type == AccessType::Word).count() as u8;
+        let bit_access_points: u8 = self.sorted_block.iter().filter(|x| x.access_type == AccessType::Bit).count() as u8;
+
+        let query = SLMPBlockReadQuery {
+            cpu,
+            sorted_block: &self.sorted_block,
+            word_access_points,
+            bit_access_points,
+        };
+        let cmd: SLMPBlockReadCommand = query.into();
+        cmd.0
+    }
+
+    /// A bit block is read in words of 16 points.
+    const fn block_bytelen(block: &DeviceBlock) -> usize {
+        Self::WORD_RESPONSE_BYTEELEN * block.request_words().1
+    }
+
+    pub fn parse(&self, recv: &[u8]) -> crate::io::Result<Vec<DeviceData>> {
+        let data_num = self.sorted_block.iter().fold(0, |a, b| a + b.size);
+        let recv_bytelen: usize = self.sorted_block.iter().map(Self::block_bytelen).sum();
+        if recv.len() != recv_bytelen {
+            return Err(crate::io::Error::new(crate::io::ErrorKind::InvalidData, format!("Block read response of {} bytes (expected {})", recv.len(), recv_bytelen)));
+        }
+
+        let mut ret: Vec<DeviceData> = Vec::with_capacity(data_num);
+
+        let mut read_addr = 0;
+
+        for (block, data_type) in self.sorted_block.iter().zip(&self.data_types) {
+            let start_address = block.start_device.address;
+            let device_type = block.start_device.device_type;
+            let bytelen = Self::block_bytelen(block);
+            let blocked_data = &recv[read_addr..(read_addr + bytelen)];
+            read_addr += bytelen;
+
+            match (block.access_type, data_type) {
+                (AccessType::Word, Some(data_type)) => {
+                    let points: usize = device_type.points(*data_type);
+                    for (i, x) in blocked_data.chunks_exact(data_type.byte_size()).enumerate() {
+                        ret.push(DeviceData{
+                            device: Device {device_type, address: start_address + i * points},
+                            data: TypedData::decode(x, *data_type)?,
+                        });
+                    }
+                },
+                (AccessType::Word, None) => {
+                    for (i, x) in blocked_data.chunks_exact(Self::WORD_RESPONSE_BYTEELEN).enumerate() {
+                        ret.push(DeviceData{
+                            device: Device {device_type, address: start_address + i},
+                            data: TypedData::from((x, DataType::U16)),
+                        });
+                    }
+                },
+                (AccessType::Bit, _) => {
+                    // The words start at the address rounded down to a multiple of 16, the lowest bit of the lower byte first.
+                    let (aligned, _) = block.request_words();
+                    let points = (start_address - aligned.address)..(start_address - aligned.address + block.size);
+                    let bits = blocked_data.iter().flat_map(|x| u8_to_bits(*x));
+                    for (bit_index, y) in bits.enumerate().filter(|(i, _)| points.contains(i)) {
+                        ret.push(DeviceData{
+                            device: Device {device_type, address: aligned.address + bit_index},
+                            data: TypedData::Bool(y),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(ret)
+    }
+}
+
+/// Read of a Shift-JIS string in `word_len` words.
+pub(crate) struct StringRead {
+    pub device: Device,
+    pub word_len: u8,
+}
+
+impl StringRead {
+    pub fn command(&self, cpu: &CPU, limits: &DeviceLimits) -> crate::io::Result<Vec<u8>> {
+        PLCString::check_device_size(self.word_len)?;
+        self.request().command(cpu, limits)
+    }
+
+    /// The text up to the first NUL.
+    pub fn parse(&self, recv: &[u8]) -> crate::io::Result<String> {
+        match self.request().parse(recv)?.pop().map(|x| x.data) {
+            Some(TypedData::String(v)) => Ok(v.as_str().into_owned()),
+            _ => Err(invalid_data("String read returned no string")),
+        }
+    }
+
+    fn request(&self) -> BulkRead {
+        BulkRead { start_device: self.device, device_num: 1, data_type: DataType::String(self.word_len) }
+    }
+}
+
+/// Word read returning the response data as it is (2 bytes per word, little endian).
+pub(crate) struct WordRead {
+    pub start_device: Device,
+    pub word_num: usize,
+}
+
+impl WordRead {
+    pub fn command(&self, cpu: &CPU, limits: &DeviceLimits) -> crate::io::Result<Vec<u8>> {
+        let request = BulkRead { start_device: self.start_device, device_num: self.word_num, data_type: DataType::U16 };
+        request.command(cpu, limits)
+    }
+
+    pub fn check(&self, recv: &[u8]) -> crate::io::Result<()> {
+        if recv.len() != self.word_num * 2 {
+            return Err(crate::io::Error::new(
+                crate::io::ErrorKind::InvalidData,
+                format!("Bulk read of {} words returned {} bytes", self.word_num, recv.len())
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn bytes_to_words(recv: &[u8]) -> Vec<u16> {
+    recv.chunks_exact(2).map(|x| u16::from_le_bytes([x[0], x[1]])).collect()
+}
+
+/// Word read of the fields of a struct (`SlmpReadable`) from `start_device`.
+pub(crate) struct StructRead<'a> {
+    pub start_device: Device,
+    pub fields: &'a [DataType],
+}
+
+impl StructRead<'_> {
+    pub fn command(&self, cpu: &CPU, limits: &DeviceLimits) -> crate::io::Result<Vec<u8>> {
+        self.fields.iter().try_for_each(|&x| check_data_type(x))?;
+        let request = BulkRead { start_device: self.start_device, device_num: self.word_size(), data_type: DataType::U16 };
+        request.command(cpu, limits)
+    }
+
+    pub fn parse(&self, recv: &[u8]) -> crate::io::Result<Vec<TypedData>> {
+        let word_size: usize = self.word_size();
+        if recv.len() != word_size * 2 {
+            return Err(crate::io::Error::new(
+                crate::io::ErrorKind::InvalidData,
+                format!("Struct read of {word_size} words returned {} bytes", recv.len())
+            ));
+        }
+
+        let mut rest: &[u8] = recv;
+        self.fields.iter()
+            .map(|&data_type| {
+                let (field, next) = rest.split_at(field_words(data_type) * 2);
+                rest = next;
+                TypedData::decode(field, data_type)
+            })
+            .collect()
+    }
+
+    fn word_size(&self) -> usize {
+        self.fields.iter().map(|&x| field_words(x)).sum()
+    }
+}
@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use crate::SLMPEndCode;
+
+/// Failures retried by `RetryPolicy`. Other end codes (e.g. WrongCommand, WrongFormat) and invalid requests are never retried.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RetryOn {
+    /// No response within the timeout. A late response to the first attempt is skipped by its serial No.
+    pub timeout: bool,
+    /// Busy end code (0xCEE0).
+    pub busy: bool,
+    /// Broken or closed link (e.g. broken pipe, connection reset, or closed after a send timeout):
+    /// the client reconnects before the retry.
+    pub disconnection: bool,
+}
+
+impl RetryOn {
+    pub const ALL: Self = Self { timeout: true, busy: true, disconnection: true };
+
+    fn action(&self, error: &std::io::Error) -> Option<RetryAction> {
+        use std::io::ErrorKind;
+        match error.kind() {
+            ErrorKind::TimedOut if self.timeout => Some(RetryAction::Resend),
+            ErrorKind::BrokenPipe | ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::ConnectionRefused
+            | ErrorKind::NotConnected | ErrorKind::UnexpectedEof if self.disconnection => Some(RetryAction::Reconnect),
+            _ if self.busy && SLMPEndCode::from_io_error(error) == Some(SLMPEndCode::BUSY) => Some(RetryAction::Resend),
+            _ => None,
+        }
+    }
+}
+
+impl Default for RetryOn {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RetryAction {
+    Resend,
+    Reconnect,
+}
+
+/// Retries of a request of `SLMPClient` on transient failures.
+/// Note that a write which timed out may have been done by the CPU, and is written again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RetryPolicy {
+    /// Attempts including the first one. 1 (or 0) disables the retries.
+    pub max_attempts: u32,
+    /// Wait before each retry.
+    pub backoff: Duration,
+    pub retry_on: RetryOn,
+}
+
+impl RetryPolicy {
+    /// No retries, the default of a client.
+    pub const NONE: Self = Self { max_attempts: 1, backoff: Duration::ZERO, retry_on: RetryOn::ALL };
+
+    /// Retry every transient failure up to `max_attempts` attempts in total.
+    pub const fn new(max_attempts: u32, backoff: Duration) -> Self {
+        Self { max_attempts, backoff, retry_on: RetryOn::ALL }
+    }
+
+    /// Whether `error` of the `attempts`-th attempt is retried, and how.
+    pub(crate) fn action(&self, error: &std::io::Error, attempts: u32) -> Option<RetryAction> {
+        if attempts >= self.max_attempts {
+            return None;
+        }
+        self.retry_on.action(error)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// Error of a request which failed on every attempt of its `RetryPolicy`, carried by the returned `std::io::Error`
+/// (of the kind of `last_error`). `SLMPEndCode::from_io_error` looks through it.
+#[derive(Debug)]
+pub struct RetriesExhausted {
+    pub attempts: u32,
+    pub last_error: std::io::Error,
+}
+
+impl RetriesExhausted {
+    pub(crate) fn into_io_error(attempts: u32, last_error: std::io::Error) -> std::io::Error {
+        std::io::Error::new(last_error.kind(), Self { attempts, last_error })
+    }
+
+    /// Take the detail out of an error returned from `SLMPClient`.
+    pub fn from_io_error(error: &std::io::Error) -> Option<&Self> {
+        error.get_ref()?.downcast_ref::<Self>()
+    }
+}
+
+impl std::fmt::Display for RetriesExhausted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (failed {} attempts)", self.last_error, self.attempts)
+    }
+}
+
+impl std::error::Error for RetriesExhausted {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.last_error)
+    }
+}
@@ -0,0 +1,185 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::SLMP4EConnectionProps;
+
+/// Module I/O No. of the CPU connected to (or the CPU of the station reached).
+const OWN_CPU_IO: u16 = 0x03FF;
+/// Module I/O No. of CPU No.1 of a multiple CPU system; No.2 to No.4 follow.
+const MULTI_CPU_IO: u16 = 0x03E0;
+/// Module I/O No. of the control CPU of a redundant system; the standby CPU, system A and system B follow.
+const REDUNDANT_CPU_IO: u16 = 0x03D0;
+/// Last module I/O No. of a multidrop link (start I/O No. of the module divided by 16).
+const MULTIDROP_IO_MAX: u16 = 0x01FF;
+const MULTIDROP_STATION_MAX: u8 = 0x1F;
+const NETWORK_NO_MAX: u8 = 0xEF;
+const STATION_NO_MAX: u8 = 0x78;
+/// Station No. of the assigned master station and of the current master station.
+const MASTER_STATIONS: [u8; 2] = [0x7D, 0x7E];
+const OWN_NETWORK: u8 = 0x00;
+const OWN_STATION: u8 = 0xFF;
+
+/// CPU No. of a multiple CPU system.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CpuNo {
+    No1,
+    No2,
+    No3,
+    No4,
+}
+
+/// Destination of the requests: the `network_id`, `pc_id`, `io_id` and `area_id` of `SLMP4EConnectionProps`,
+/// checked to be a destination a CPU can relay to. It is deserialized as is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Route {
+    network_id: u8,
+    pc_id: u8,
+    io_id: u16,
+    area_id: u8,
+}
+
+impl Route {
+    /// The CPU connected to.
+    pub const fn own_station() -> Self {
+        Self { network_id: OWN_NETWORK, pc_id: OWN_STATION, io_id: OWN_CPU_IO, area_id: 0x00 }
+    }
+
+    /// The CPU of station `station_no` (1 to 120, or 125/126 for the assigned/current master station)
+    /// on network `network_no` (1 to 239) of CC-Link IE, relayed by the CPU connected to.
+    pub fn cc_link_ie(network_no: u8, station_no: u8) -> crate::io::Result<Self> {
+        Self::new(network_no, station_no, OWN_CPU_IO, 0x00)
+    }
+
+    /// CPU `cpu_no` of the multiple CPU system connected to.
+    pub const fn multi_cpu(cpu_no: CpuNo) -> Self {
+        Self::own_station().with_cpu(cpu_no)
+    }
+
+    /// Station `station_no` (0 to 31) of the multidrop link of the serial communication module at `module_start_io`
+    /// (a multiple of 16 up to 0x1FF0) on the station connected to.
+    pub fn multidrop(module_start_io: u16, station_no: u8) -> crate::io::Result<Self> {
+        if !module_start_io.is_multiple_of(16) {
+            return Err(invalid_route(format!("Module start I/O 0x{module_start_io:04X} is not a multiple of 16")));
+        }
+        Self::new(OWN_NETWORK, OWN_STATION, module_start_io / 16, station_no)
+    }
+
+    /// Same station, CPU `cpu_no` of its multiple CPU system.
+    pub const fn with_cpu(self, cpu_no: CpuNo) -> Self {
+        Self { io_id: MULTI_CPU_IO + cpu_no as u16, area_id: 0x00, ..self }
+    }
+
+    /// Route of the raw values. It fails with `InvalidInput` for a combination no CPU relays to,
+    /// e.g. a station No. on the own network or a multidrop station of a CPU module.
+    pub fn new(network_id: u8, pc_id: u8, io_id: u16, area_id: u8) -> crate::io::Result<Self> {
+        let route = Self { network_id, pc_id, io_id, area_id };
+        match network_id {
+            OWN_NETWORK if pc_id != OWN_STATION => {
+                return Err(invalid_route(format!("Station No. {pc_id} requires a network No. ({route})")));
+            },
+            OWN_NETWORK => {},
+            1..=NETWORK_NO_MAX if (1..=STATION_NO_MAX).contains(&pc_id) || MASTER_STATIONS.contains(&pc_id) => {},
+            1..=NETWORK_NO_MAX => return Err(invalid_route(format!("Invalid station No. 0x{pc_id:02X} ({route})"))),
+            _ => return Err(invalid_route(format!("Invalid network No. 0x{network_id:02X} ({route})"))),
+        }
+        match io_id {
+            0..=MULTIDROP_IO_MAX if area_id <= MULTIDROP_STATION_MAX => {},
+            0..=MULTIDROP_IO_MAX => return Err(invalid_route(format!("Invalid multidrop station No. {area_id} ({route})"))),
+            _ if area_id != 0x00 => return Err(invalid_route(format!("Multidrop station No. {area_id} to a CPU module ({route})"))),
+            OWN_CPU_IO | MULTI_CPU_IO..=0x03E3 | REDUNDANT_CPU_IO..=0x03D3 => {},
+            _ => return Err(invalid_route(format!("Invalid module I/O No. 0x{io_id:04X} ({route})"))),
+        }
+        Ok(route)
+    }
+
+    /// The raw values of `connection_props`, unchecked.
+    pub(crate) const fn of(connection_props: &SLMP4EConnectionProps) -> Self {
+        Self::unchecked(connection_props.network_id, connection_props.pc_id, connection_props.io_id, connection_props.area_id)
+    }
+
+    pub(crate) const fn unchecked(network_id: u8, pc_id: u8, io_id: u16, area_id: u8) -> Self {
+        Self { network_id, pc_id, io_id, area_id }
+    }
+
+    pub const fn network_id(&self) -> u8 {
+        self.network_id
+    }
+
+    pub const fn pc_id(&self) -> u8 {
+        self.pc_id
+    }
+
+    pub const fn io_id(&self) -> u16 {
+        self.io_id
+    }
+
+    pub const fn area_id(&self) -> u8 {
+        self.area_id
+    }
+}
+
+impl Default for Route {
+    fn default() -> Self {
+        Self::own_station()
+    }
+}
+
+impl core::fmt::Display for Route {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "network 0x{:02X}, station 0x{:02X}, module I/O 0x{:04X}, multidrop station 0x{:02X}", self.network_id, self.pc_id, self.io_id, self.area_id)
+    }
+}
+
+impl SLMP4EConnectionProps {
+    /// The destination of the props, checked as `Route::new`.
+    pub fn route(&self) -> crate::io::Result<Route> {
+        Route::new(self.network_id, self.pc_id, self.io_id, self.area_id)
+    }
+
+    /// Send the requests to `route`.
+    pub const fn with_route(mut self, route: Route) -> Self {
+        self.network_id = route.network_id;
+        self.pc_id = route.pc_id;
+        self.io_id = route.io_id;
+        self.area_id = route.area_id;
+        self
+    }
+}
+
+fn invalid_route(message: String) -> crate::io::Error {
+    crate::io::Error::new(crate::io::ErrorKind::InvalidInput, message)
+}
+
+/// A relay end code (`SLMPEndCode::RELAY_FAILURE`, `SLMPEndCode::TIMEOUT_ERROR`) with the route of the request,
+/// so that the error tells which station could not be reached. `SLMPEndCode::from_io_error` takes the end code out of it.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RelayError {
+    pub end_code: crate::SLMPEndCode,
+    pub route: Route,
+}
+
+#[cfg(feature = "std")]
+impl RelayError {
+    /// Take the detail out of an error returned from `SLMPClient`.
+    pub fn from_io_error(error: &std::io::Error) -> Option<&Self> {
+        error.get_ref()?.downcast_ref::<Self>()
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for RelayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (route: {})", self.end_code, self.route)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RelayError {}
+
+#[cfg(feature = "std")]
+impl From<RelayError> for std::io::Error {
+    fn from(value: RelayError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, value)
+    }
+}
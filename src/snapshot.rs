@@ -0,0 +1,233 @@
+use crate::prelude::*;
+use alloc::collections::{BTreeMap, BTreeSet};
+use serde::{Deserialize, Serialize};
+use crate::commands::device_access::{BULK_MAX_BIT_POINTS, bulk_max_bit_points, bulk_max_word_points};
+use crate::requests::{self, BulkRead, WordRead};
+use crate::{AccessType, CPU, DataType, Device, DeviceBlock, DeviceLimits, TypedData, TypedDevice, WordOrder, bits_to_u16, div_ceil, u16_to_bits};
+
+/// Bit points per read of a snapshot, whose response (2 points per byte) fits in a received frame.
+const SNAPSHOT_MAX_BIT_POINTS: usize = BULK_MAX_BIT_POINTS / 2;
+
+/// Raw words of device areas, taken by `SLMPClient::snapshot` and written back by `SLMPClient::restore`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceSnapshot {
+    /// CPU series the snapshot was taken from.
+    pub cpu: CPU,
+    pub areas: Vec<SnapshotArea>,
+}
+
+/// Words of an area from `start_device`.
+/// A bit area packs 16 points in a word from the least significant bit, the rest of the last word being 0.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotArea {
+    pub access_type: AccessType,
+    pub start_device: Device,
+    /// Points of a bit area, words of a word area.
+    pub size: usize,
+    pub words: Vec<u16>,
+}
+
+/// A device whose value differs between two snapshots (see `DeviceSnapshot::diff`).
+/// `None` is a device outside the areas of that snapshot.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceChange {
+    pub device: Device,
+    pub before: Option<TypedData>,
+    pub after: Option<TypedData>,
+}
+
+impl DeviceChange {
+    /// Whether the device is in the areas of only one of the snapshots.
+    pub fn is_extent_change(&self) -> bool {
+        self.before.is_none() || self.after.is_none()
+    }
+}
+
+impl DeviceSnapshot {
+    /// Changes from `self` to `after` in device order: a word (`U16`) per device of the word areas, and a point (`Bool`) of the bit areas.
+    /// A device in the areas of only one of the snapshots is reported with `None` on the other side, whether or not its value changed.
+    pub fn diff(&self, after: &DeviceSnapshot) -> Vec<DeviceChange> {
+        let (before, after) = (self.raw_values(), after.raw_values());
+        let keys: BTreeSet<&(Device, AccessType)> = before.keys().chain(after.keys()).collect();
+        keys.into_iter()
+            .map(|key| (key.0, before.get(key), after.get(key)))
+            .filter(|(_, before, after)| before != after)
+            .map(|(device, before, after)| DeviceChange { device, before: before.cloned(), after: after.cloned() })
+            .collect()
+    }
+
+    /// Same as `diff` for the values of `devices`, so that a multi-word value which changed is a single change.
+    /// A value only partly in the areas of a snapshot, or not BCD for a BCD type, is `None` there. The values are compared bit-exact, as `TypedData::to_bytes`.
+    pub fn diff_typed(&self, after: &DeviceSnapshot, devices: &[TypedDevice], order: WordOrder) -> Vec<DeviceChange> {
+        devices.iter()
+            .filter_map(|&x| {
+                let (before, after) = (self.value(x, order), after.value(x, order));
+                let same: bool = match (&before, &after) {
+                    (Some(before), Some(after)) => before.get_type() == after.get_type() && before.to_bytes() == after.to_bytes(),
+                    (before, after) => before.is_none() && after.is_none(),
+                };
+                (!same).then_some(DeviceChange { device: x.device, before, after })
+            })
+            .collect()
+    }
+
+    /// Value of `device` in the areas, or `None` if a word (or the point of a `Bool`) is outside them
+    /// or the words are not a BCD value of its type.
+    pub fn value(&self, device: TypedDevice, order: WordOrder) -> Option<TypedData> {
+        let TypedDevice { device, data_type } = device;
+        if data_type == DataType::Bool {
+            return self.bit(device).map(TypedData::Bool);
+        }
+        let points: usize = device.device_type.points(DataType::U16);
+        let words: usize = data_type.byte_size() / 2;
+        let mut bytes: Vec<u8> = Vec::with_capacity(words * 2);
+        for i in 0..words {
+            let device = device.offset(i * points)?;
+            bytes.extend(self.word(device)?.to_le_bytes());
+        }
+        Some(TypedData::decode(&bytes, data_type).ok()?.with_word_order(order))
+    }
+
+    fn word(&self, device: Device) -> Option<u16> {
+        self.areas.iter()
+            .filter(|x| x.access_type == AccessType::Word && x.start_device.device_type == device.device_type)
+            .find_map(|x| {
+                let points: usize = device.device_type.points(DataType::U16);
+                let offset: usize = device.address.checked_sub(x.start_device.address)?;
+                offset.is_multiple_of(points).then(|| x.words.get(offset / points)).flatten().copied()
+            })
+    }
+
+    /// A point of the bit areas, or of a word area of a bit device.
+    fn bit(&self, device: Device) -> Option<bool> {
+        self.areas.iter()
+            .filter(|x| x.start_device.device_type == device.device_type)
+            .find_map(|x| {
+                let offset: usize = device.address.checked_sub(x.start_device.address)?;
+                let size: usize = match x.access_type {
+                    AccessType::Bit => x.size,
+                    AccessType::Word if device.device_type.is_bit_device() => x.size * 16,
+                    AccessType::Word => 0,
+                };
+                (offset < size).then(|| x.words.get(offset / 16).map(|&word| u16_to_bits(word)[offset % 16])).flatten()
+            })
+    }
+
+    fn raw_values(&self) -> BTreeMap<(Device, AccessType), TypedData> {
+        let mut values: BTreeMap<(Device, AccessType), TypedData> = BTreeMap::new();
+        for area in &self.areas {
+            match area.access_type {
+                AccessType::Bit => {
+                    let bits = area.words.iter().flat_map(|&x| u16_to_bits(x)).take(area.size);
+                    values.extend(bits.enumerate().filter_map(|(i, x)| Some(((area.start_device.offset(i)?, AccessType::Bit), TypedData::Bool(x)))));
+                },
+                AccessType::Word => {
+                    let points: usize = area.start_device.device_type.points(DataType::U16);
+                    values.extend(area.words.iter().enumerate().filter_map(|(i, &x)| Some(((area.start_device.offset(i * points)?, AccessType::Word), TypedData::U16(x)))));
+                },
+            }
+        }
+        values
+    }
+}
+
+/// Bulk reads of the blocks of a snapshot, as many as the read limits take.
+pub(crate) struct SnapshotRead {
+    blocks: Vec<DeviceBlock>,
+    /// Index of the block and the read of each request.
+    reads: Vec<(usize, BulkRead)>,
+    commands: Vec<Vec<u8>>,
+}
+
+impl SnapshotRead {
+    /// Every block is checked before anything is sent.
+    pub fn new(cpu: &CPU, limits: &DeviceLimits, blocks: &[DeviceBlock]) -> crate::io::Result<Self> {
+        requests::check_not_empty(blocks.len(), "Snapshot")?;
+        let mut reads: Vec<(usize, BulkRead)> = Vec::new();
+        for (i, block) in blocks.iter().enumerate() {
+            requests::check_not_empty(block.size, "Snapshot of a block")?;
+            let (chunk, data_type, points) = match block.access_type {
+                AccessType::Bit => (SNAPSHOT_MAX_BIT_POINTS.min(bulk_max_bit_points(cpu)), DataType::Bool, 1),
+                AccessType::Word => (bulk_max_word_points(cpu), DataType::U16, block.start_device.device_type.points(DataType::U16)),
+            };
+            limits.check(block.start_device, block.size.saturating_mul(points))?;
+            for offset in (0..block.size).step_by(chunk) {
+                let read = BulkRead { start_device: block.start_device + offset * points, device_num: chunk.min(block.size - offset), data_type };
+                reads.push((i, read));
+            }
+        }
+        let commands = reads.iter().map(|(_, x)| x.command(cpu, limits)).collect::<crate::io::Result<Vec<Vec<u8>>>>()?;
+        Ok(Self { blocks: blocks.to_vec(), reads, commands })
+    }
+
+    pub fn commands(&self) -> &[Vec<u8>] {
+        &self.commands
+    }
+
+    pub fn parse(&self, cpu: CPU, responses: &[Vec<u8>]) -> crate::io::Result<DeviceSnapshot> {
+        let mut areas: Vec<SnapshotArea> = self.blocks.iter()
+            .map(|x| SnapshotArea { access_type: x.access_type, start_device: x.start_device, size: x.size, words: Vec::new() })
+            .collect();
+        for ((i, read), recv) in self.reads.iter().zip(responses) {
+            let words = &mut areas[*i].words;
+            match read.data_type {
+                DataType::Bool => {
+                    let bits: Vec<bool> = read.parse(recv)?.into_iter().map(|x| matches!(x.data, TypedData::Bool(true))).collect();
+                    words.extend(bits.chunks(16).map(|x| {
+                        let mut word = [false; 16];
+                        word[..x.len()].copy_from_slice(x);
+                        bits_to_u16(word)
+                    }));
+                },
+                _ => {
+                    WordRead { start_device: read.start_device, word_num: read.device_num }.check(recv)?;
+                    words.extend(requests::bytes_to_words(recv));
+                },
+            }
+        }
+        Ok(DeviceSnapshot { cpu, areas })
+    }
+}
+
+/// Bulk writes of the areas of `snapshot`, as many as the write limits take.
+/// Every area is checked against `cpu` and `limits` (of the client, not of `snapshot.cpu`) before a command is returned.
+pub(crate) fn restore(cpu: &CPU, limits: &DeviceLimits, snapshot: &DeviceSnapshot) -> crate::io::Result<Vec<Vec<u8>>> {
+    requests::check_not_empty(snapshot.areas.len(), "Restore")?;
+    let mut commands: Vec<Vec<u8>> = Vec::new();
+    for area in &snapshot.areas {
+        let (word_num, points) = match area.access_type {
+            AccessType::Bit => (div_ceil(area.size, 16), area.size),
+            AccessType::Word => (area.size, area.size.saturating_mul(area.start_device.device_type.points(DataType::U16))),
+        };
+        if area.words.len() != word_num {
+            return Err(crate::io::Error::new(
+                crate::io::ErrorKind::InvalidInput,
+                format!("Snapshot area from {} of {} points holds {} words", area.start_device, area.size, area.words.len())
+            ));
+        }
+        requests::check_not_empty(area.size, "Restore of an area")?;
+        requests::check_access(area.start_device, area.access_type)?;
+        limits.check(area.start_device, points)?;
+
+        match area.access_type {
+            AccessType::Bit => {
+                let bits: Vec<TypedData> = area.words.iter().flat_map(|&x| u16_to_bits(x)).take(area.size).map(TypedData::Bool).collect();
+                let max_points: usize = bulk_max_bit_points(cpu);
+                for (i, chunk) in bits.chunks(max_points).enumerate() {
+                    commands.push(requests::bulk_write(cpu, limits, area.start_device + i * max_points, chunk, WordOrder::default())?);
+                }
+            },
+            AccessType::Word => {
+                let points: usize = area.start_device.device_type.points(DataType::U16);
+                let max_points: usize = bulk_max_word_points(cpu);
+                for (i, chunk) in area.words.chunks(max_points).enumerate() {
+                    commands.push(requests::bulk_write_words(cpu, limits, area.start_device + i * max_points * points, chunk)?);
+                }
+            },
+        }
+    }
+    Ok(commands)
+}
@@ -0,0 +1,707 @@
+/*
+Request handling of the mock server.
+
+Device access commands are decoded from the subcommand:
+bit 0 (0x0001) selects bit units and bit 1 (0x0002) selects the iQ-R device specification (6 bytes).
+File control commands use subcommand 0x0040 for iQ-R (UTF-16LE names, length-prefixed password).
+*/
+
+use std::collections::BTreeMap;
+use encoding_rs::SHIFT_JIS;
+
+use crate::commands::label_access::*;
+use crate::{CPU, DataType, Device, DeviceType, SLMPEndCode, StringEncoding, TypedData};
+use super::memory::{DEVICE_ERROR, DeviceMemory, is_bit_device};
+
+/// The number of points is out of range.
+pub(crate) const POINTS_OUT_OF_RANGE: SLMPEndCode = SLMPEndCode(0xC051);
+pub(crate) const PASSWORD_MISMATCH: SLMPEndCode = SLMPEndCode(0xC201);
+/// The specified label does not exist.
+pub(crate) const LABEL_NOT_FOUND: SLMPEndCode = SLMPEndCode(0x40C0);
+
+const BULK_WORD_MAX_POINTS: usize = 960;
+const BULK_BIT_MAX_POINTS: usize = 7168;
+const RANDOM_MAX_POINTS: usize = 192;
+const R_RANDOM_MAX_POINTS: usize = 96;
+const RANDOM_WRITE_MAX_BIT_POINTS: usize = 188;
+const R_RANDOM_WRITE_MAX_BIT_POINTS: usize = 94;
+/// Word points x 12 + double word points x 14.
+const RANDOM_WRITE_MAX_WORD_SIZE: usize = 1920;
+const R_RANDOM_WRITE_MAX_WORD_SIZE: usize = 960;
+const BLOCK_MAX_BLOCKS: usize = 120;
+const R_BLOCK_MAX_BLOCKS: usize = 60;
+const BLOCK_MAX_POINTS: usize = 960;
+
+const FILE_SUBCOMMAND_R: u16 = 0x0040;
+const QL_PASSWORD_LEN: usize = 4;
+
+/// Start device and size (words)
+type DeviceBlockSpec = (Device, usize);
+
+struct MockFile {
+    data: Vec<u8>,
+}
+
+pub(crate) struct MockState {
+    pub memory: DeviceMemory,
+    pub cpu_type: String,
+    pub response_delay: std::time::Duration,
+    monitor_list: Option<(Vec<Device>, Vec<Device>)>,
+    files: BTreeMap<(u16, String), MockFile>,
+    /// Global labels by name, with the data type of their values.
+    labels: BTreeMap<String, TypedData>,
+    open_files: BTreeMap<u16, (u16, String)>,
+    next_file_pointer: u16,
+    pub remote_password: Option<String>,
+    pub remote_unlocked: bool,
+}
+
+impl MockState {
+    pub fn new(cpu_type: &str) -> Self {
+        Self {
+            memory: DeviceMemory::default(),
+            cpu_type: cpu_type.to_string(),
+            response_delay: std::time::Duration::ZERO,
+            monitor_list: None,
+            files: BTreeMap::new(),
+            labels: BTreeMap::new(),
+            open_files: BTreeMap::new(),
+            next_file_pointer: 1,
+            remote_password: None,
+            remote_unlocked: false,
+        }
+    }
+
+    /// CPU series of the model, by its model code.
+    fn cpu_family(&self) -> Option<CPU> {
+        crate::commands::unit_control::MODEL_CODES.iter()
+            .find(|(model, _)| *model == self.cpu_type)
+            .and_then(|(_, code)| crate::commands::unit_control::cpu_family(*code))
+    }
+
+    pub fn file(&self, drive: u16, file_name: &str) -> Option<Vec<u8>> {
+        self.files.get(&(drive, file_name.to_string())).map(|x| x.data.clone())
+    }
+
+    pub fn insert_file(&mut self, drive: u16, file_name: &str, data: &[u8]) {
+        self.files.insert((drive, file_name.to_string()), MockFile { data: data.to_vec() });
+    }
+
+    pub fn label(&self, name: &str) -> Option<TypedData> {
+        self.labels.get(name).cloned()
+    }
+
+    /// It fails for values without a label data type (U64, I64).
+    pub fn set_label(&mut self, name: &str, value: TypedData) -> Result<(), TypedData> {
+        if label_type_id(&value).is_none() {
+            return Err(value);
+        }
+        self.labels.insert(name.to_string(), value);
+        Ok(())
+    }
+}
+
+/// Cursor over the request data. Running out of data means the request is shorter than the command requires.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    const fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], SLMPEndCode> {
+        let ret = self.data.get(self.pos..self.pos + n).ok_or(SLMPEndCode::WRONG_LENGTH)?;
+        self.pos += n;
+        Ok(ret)
+    }
+
+    fn u8(&mut self) -> Result<u8, SLMPEndCode> {
+        self.bytes(1).map(|x| x[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, SLMPEndCode> {
+        self.bytes(2).map(|x| u16::from_le_bytes([x[0], x[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, SLMPEndCode> {
+        self.bytes(4).map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+    }
+
+    fn device(&mut self, r_format: bool) -> Result<Device, SLMPEndCode> {
+        let (address, code) = if r_format {
+            let x = self.bytes(6)?;
+            (u32::from_le_bytes([x[0], x[1], x[2], x[3]]), x[4])
+        } else {
+            let x = self.bytes(4)?;
+            (u32::from_le_bytes([x[0], x[1], x[2], 0]), x[3])
+        };
+        let device_type = DeviceType::from_code(code).ok_or(SLMPEndCode::WRONG_FORMAT)?;
+        Ok(Device { device_type, address: address as usize })
+    }
+
+    fn finish(&self) -> Result<(), SLMPEndCode> {
+        if self.pos == self.data.len() { Ok(()) } else { Err(SLMPEndCode::WRONG_LENGTH) }
+    }
+}
+
+#[inline(always)]
+const fn offset(device: Device, n: usize) -> Device {
+    Device { device_type: device.device_type, address: device.address + n }
+}
+
+/// Process a request and return the response data (without the end code).
+pub(crate) fn handle(state: &mut MockState, command: u16, subcommand: u16, data: &[u8]) -> Result<Vec<u8>, SLMPEndCode> {
+    let mut reader = Reader::new(data);
+
+    let ret = match command {
+        0x0401 => bulk_read(state, subcommand, &mut reader)?,
+        0x1401 => bulk_write(state, subcommand, &mut reader)?,
+        0x0403 => random_read(state, subcommand, &mut reader)?,
+        0x1402 => random_write(state, subcommand, &mut reader)?,
+        0x0406 => block_read(state, subcommand, &mut reader)?,
+        0x1406 => block_write(state, subcommand, &mut reader)?,
+        0x0801 => monitor_register(state, subcommand, &mut reader)?,
+        0x0802 => monitor_read(state, subcommand)?,
+        0x1001 => { reader.bytes(4)?; Vec::new() },
+        0x1002 | 0x1003 | 0x1005 | 0x1006 => { reader.bytes(2)?; Vec::new() },
+        0x0101 => {
+            let mut name: Vec<u8> = state.cpu_type.bytes().take(16).collect();
+            name.resize(16, 0x20);
+            let code: u16 = crate::commands::unit_control::MODEL_CODES.iter()
+                .find(|(model, _)| *model == state.cpu_type)
+                .map_or(0, |(_, code)| *code);
+            name.extend(code.to_le_bytes());
+            name
+        },
+        0x0619 => {
+            let len: usize = reader.u16()? as usize;
+            let message = reader.bytes(len)?;
+            [&(len as u16).to_le_bytes(), message].concat()
+        },
+        0x1630 | 0x1631 => {
+            let len: usize = reader.u16()? as usize;
+            let password: &[u8] = reader.bytes(len)?;
+            if let Some(expected) = &state.remote_password && expected.as_bytes() != password {
+                return Err(PASSWORD_MISMATCH);
+            }
+            state.remote_unlocked = command == 0x1630;
+            Vec::new()
+        },
+        // iQ-F CPUs have no file access commands.
+        0x1810..=0x182A if state.cpu_family() == Some(CPU::F) => return Err(SLMPEndCode::WRONG_COMMAND),
+        0x1820 => create_file(state, subcommand, &mut reader)?,
+        0x1822 => delete_file(state, subcommand, &mut reader)?,
+        0x1824 => copy_file(state, subcommand, &mut reader)?,
+        0x1827 => open_file(state, subcommand, &mut reader)?,
+        0x1828 => read_file(state, &mut reader)?,
+        0x1829 => write_file(state, &mut reader)?,
+        0x182A => close_file(state, &mut reader)?,
+        0x1810 => read_directory(state, subcommand, &mut reader)?,
+        0x041C => read_labels(state, subcommand, &mut reader)?,
+        0x141A => write_labels(state, subcommand, &mut reader)?,
+        _ => return Err(SLMPEndCode::WRONG_COMMAND),
+    };
+
+    reader.finish()?;
+
+    Ok(ret)
+}
+
+/* Device Access */
+
+/// Returns (bit units, iQ-R device specification).
+fn device_subcommand(subcommand: u16, allow_bit: bool) -> Result<(bool, bool), SLMPEndCode> {
+    match subcommand {
+        0x0000 => Ok((false, false)),
+        0x0002 => Ok((false, true)),
+        0x0001 if allow_bit => Ok((true, false)),
+        0x0003 if allow_bit => Ok((true, true)),
+        _ => Err(SLMPEndCode::WRONG_COMMAND),
+    }
+}
+
+fn bulk_read(state: &MockState, subcommand: u16, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    let (bit_unit, r_format) = device_subcommand(subcommand, true)?;
+    let start = reader.device(r_format)?;
+    let points: usize = reader.u16()? as usize;
+
+    if bit_unit {
+        if points == 0 || points > BULK_BIT_MAX_POINTS {
+            return Err(POINTS_OUT_OF_RANGE);
+        }
+        let bits = (0..points).map(|i| state.memory.bit(offset(start, i))).collect::<Result<Vec<bool>, _>>()?;
+        // Two points per byte, the first point in the upper nibble.
+        Ok(bits.chunks(2).map(|x| ((x[0] as u8) << 4) | x.get(1).map_or(0, |&y| y as u8)).collect())
+    } else {
+        if points == 0 || points > BULK_WORD_MAX_POINTS {
+            return Err(POINTS_OUT_OF_RANGE);
+        }
+        Ok((0..points).flat_map(|i| state.memory.word(offset(start, i)).to_le_bytes()).collect())
+    }
+}
+
+fn bulk_write(state: &mut MockState, subcommand: u16, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    let (bit_unit, r_format) = device_subcommand(subcommand, true)?;
+    let start = reader.device(r_format)?;
+    let points: usize = reader.u16()? as usize;
+
+    if bit_unit {
+        if points == 0 || points > BULK_BIT_MAX_POINTS {
+            return Err(POINTS_OUT_OF_RANGE);
+        }
+        let data = reader.bytes(points.div_ceil(2))?;
+        for i in 0..points {
+            let nibble = if i % 2 == 0 { data[i / 2] >> 4 } else { data[i / 2] & 0x0F };
+            state.memory.set_bit(offset(start, i), nibble & 0x01 != 0)?;
+        }
+    } else {
+        if points == 0 || points > BULK_WORD_MAX_POINTS {
+            return Err(POINTS_OUT_OF_RANGE);
+        }
+        for i in 0..points {
+            let value = reader.u16()?;
+            state.memory.set_word(offset(start, i), value);
+        }
+    }
+
+    Ok(Vec::new())
+}
+
+/// Read the word and double-word device lists used by random read and monitor register.
+fn word_device_lists(subcommand: u16, reader: &mut Reader) -> Result<(Vec<Device>, Vec<Device>), SLMPEndCode> {
+    let (_, r_format) = device_subcommand(subcommand, false)?;
+    let single_word_points: usize = reader.u8()? as usize;
+    let double_word_points: usize = reader.u8()? as usize;
+
+    let max_points: usize = if r_format { R_RANDOM_MAX_POINTS } else { RANDOM_MAX_POINTS };
+    if single_word_points + double_word_points == 0 || single_word_points + double_word_points > max_points {
+        return Err(POINTS_OUT_OF_RANGE);
+    }
+
+    let single_words = (0..single_word_points).map(|_| reader.device(r_format)).collect::<Result<Vec<Device>, _>>()?;
+    let double_words = (0..double_word_points).map(|_| reader.device(r_format)).collect::<Result<Vec<Device>, _>>()?;
+
+    Ok((single_words, double_words))
+}
+
+fn read_word_device_lists(state: &MockState, single_words: &[Device], double_words: &[Device]) -> Vec<u8> {
+    let mut ret: Vec<u8> = Vec::with_capacity(2 * single_words.len() + 4 * double_words.len());
+    for &device in single_words {
+        ret.extend(state.memory.word(device).to_le_bytes());
+    }
+    for &device in double_words {
+        ret.extend(state.memory.word(device).to_le_bytes());
+        ret.extend(state.memory.word(offset(device, 1)).to_le_bytes());
+    }
+    ret
+}
+
+fn random_read(state: &MockState, subcommand: u16, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    let (single_words, double_words) = word_device_lists(subcommand, reader)?;
+    Ok(read_word_device_lists(state, &single_words, &double_words))
+}
+
+fn random_write(state: &mut MockState, subcommand: u16, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    let (bit_unit, r_format) = device_subcommand(subcommand, true)?;
+
+    if bit_unit {
+        let points: usize = reader.u8()? as usize;
+        let max_points: usize = if r_format { R_RANDOM_WRITE_MAX_BIT_POINTS } else { RANDOM_WRITE_MAX_BIT_POINTS };
+        if points == 0 || points > max_points {
+            return Err(POINTS_OUT_OF_RANGE);
+        }
+        for _ in 0..points {
+            let device = reader.device(r_format)?;
+            let value = reader.bytes(if r_format { 2 } else { 1 })?;
+            state.memory.set_bit(device, value[0] == 0x01)?;
+        }
+    } else {
+        let single_word_points: usize = reader.u8()? as usize;
+        let double_word_points: usize = reader.u8()? as usize;
+        let max_size: usize = if r_format { R_RANDOM_WRITE_MAX_WORD_SIZE } else { RANDOM_WRITE_MAX_WORD_SIZE };
+        if single_word_points + double_word_points == 0 || single_word_points * 12 + double_word_points * 14 > max_size {
+            return Err(POINTS_OUT_OF_RANGE);
+        }
+        for _ in 0..single_word_points {
+            let device = reader.device(r_format)?;
+            let value = reader.u16()?;
+            state.memory.set_word(device, value);
+        }
+        for _ in 0..double_word_points {
+            let device = reader.device(r_format)?;
+            let value = reader.u32()?;
+            state.memory.set_word(device, value as u16);
+            state.memory.set_word(offset(device, 1), (value >> 16) as u16);
+        }
+    }
+
+    Ok(Vec::new())
+}
+
+/// Read the block specifications. The size of a bit block is given in words (16 points).
+fn block_specs(subcommand: u16, reader: &mut Reader) -> Result<(Vec<DeviceBlockSpec>, Vec<DeviceBlockSpec>), SLMPEndCode> {
+    let (_, r_format) = device_subcommand(subcommand, false)?;
+    let word_blocks: usize = reader.u8()? as usize;
+    let bit_blocks: usize = reader.u8()? as usize;
+
+    let max_blocks: usize = if r_format { R_BLOCK_MAX_BLOCKS } else { BLOCK_MAX_BLOCKS };
+    if word_blocks + bit_blocks == 0 || word_blocks + bit_blocks > max_blocks {
+        return Err(POINTS_OUT_OF_RANGE);
+    }
+
+    let mut words: Vec<DeviceBlockSpec> = Vec::with_capacity(word_blocks);
+    for _ in 0..word_blocks {
+        words.push((reader.device(r_format)?, reader.u16()? as usize));
+    }
+
+    let mut bits: Vec<DeviceBlockSpec> = Vec::with_capacity(bit_blocks);
+    for _ in 0..bit_blocks {
+        let device = reader.device(r_format)?;
+        if !is_bit_device(device.device_type) {
+            return Err(DEVICE_ERROR);
+        }
+        bits.push((device, reader.u16()? as usize));
+    }
+
+    Ok((words, bits))
+}
+
+fn block_read(state: &MockState, subcommand: u16, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    let (word_blocks, bit_blocks) = block_specs(subcommand, reader)?;
+
+    let total_points: usize = word_blocks.iter().chain(&bit_blocks).map(|x| x.1).sum();
+    if total_points > BLOCK_MAX_POINTS {
+        return Err(POINTS_OUT_OF_RANGE);
+    }
+
+    let mut ret: Vec<u8> = Vec::with_capacity(2 * total_points);
+    for &(device, size) in &word_blocks {
+        for i in 0..size {
+            ret.extend(state.memory.word(offset(device, i)).to_le_bytes());
+        }
+    }
+    for &(device, size) in &bit_blocks {
+        for i in 0..size {
+            ret.extend(state.memory.word(offset(device, 16 * i)).to_le_bytes());
+        }
+    }
+
+    Ok(ret)
+}
+
+fn block_write(state: &mut MockState, subcommand: u16, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    let (_, r_format) = device_subcommand(subcommand, false)?;
+    let word_blocks: usize = reader.u8()? as usize;
+    let bit_blocks: usize = reader.u8()? as usize;
+
+    let max_blocks: usize = if r_format { R_BLOCK_MAX_BLOCKS } else { BLOCK_MAX_BLOCKS };
+    if word_blocks + bit_blocks == 0 || word_blocks + bit_blocks > max_blocks {
+        return Err(POINTS_OUT_OF_RANGE);
+    }
+
+    let mut total_points: usize = 0;
+    for i in 0..(word_blocks + bit_blocks) {
+        let device = reader.device(r_format)?;
+        let size: usize = reader.u16()? as usize;
+        total_points += size;
+        if total_points > BLOCK_MAX_POINTS {
+            return Err(POINTS_OUT_OF_RANGE);
+        }
+
+        let is_bit_block = i >= word_blocks;
+        if is_bit_block && !is_bit_device(device.device_type) {
+            return Err(DEVICE_ERROR);
+        }
+
+        for j in 0..size {
+            let value = reader.u16()?;
+            let target = if is_bit_block { offset(device, 16 * j) } else { offset(device, j) };
+            state.memory.set_word(target, value);
+        }
+    }
+
+    Ok(Vec::new())
+}
+
+/// No devices release the registration (`SLMPClient::monitor_clear`).
+fn monitor_register(state: &mut MockState, subcommand: u16, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    if reader.data[reader.pos..] == [0x00, 0x00] {
+        device_subcommand(subcommand, false)?;
+        reader.bytes(2)?;
+        state.monitor_list = None;
+        return Ok(Vec::new());
+    }
+    state.monitor_list = Some(word_device_lists(subcommand, reader)?);
+    Ok(Vec::new())
+}
+
+fn monitor_read(state: &MockState, subcommand: u16) -> Result<Vec<u8>, SLMPEndCode> {
+    if subcommand != 0x0000 {
+        return Err(SLMPEndCode::WRONG_COMMAND);
+    }
+    let (single_words, double_words) = state.monitor_list.as_ref().ok_or(SLMPEndCode::MONITOR_NOT_REGISTERED)?;
+    Ok(read_word_device_lists(state, single_words, double_words))
+}
+
+/* Label Access */
+
+/// Data type ID of a label holding `value` in the response of random label read.
+fn label_type_id(value: &TypedData) -> Option<u8> {
+    match value {
+        TypedData::Bool(_) => Some(TYPE_ID_BIT),
+        TypedData::U16(_) | TypedData::BitArray16(_) | TypedData::Bcd16(_) => Some(TYPE_ID_WORD),
+        TypedData::U32(_) | TypedData::Bcd32(_) => Some(TYPE_ID_DOUBLE_WORD),
+        TypedData::F32(_) => Some(TYPE_ID_FLOAT),
+        TypedData::F64(_) => Some(TYPE_ID_DOUBLE),
+        TypedData::I16(_) => Some(TYPE_ID_SIGNED_WORD),
+        TypedData::I32(_) => Some(TYPE_ID_SIGNED_DOUBLE_WORD),
+        TypedData::String(x) if x.encoding() == StringEncoding::Utf16Le => Some(TYPE_ID_WSTRING),
+        TypedData::String(_) => Some(TYPE_ID_STRING),
+        TypedData::U64(_) | TypedData::I64(_) => None,
+    }
+}
+
+/// Label points, then the names. Abbreviations are not supported.
+fn label_names(subcommand: u16, reader: &mut Reader) -> Result<Vec<String>, SLMPEndCode> {
+    if subcommand != 0x0000 {
+        return Err(SLMPEndCode::WRONG_COMMAND);
+    }
+    let points: usize = reader.u16()? as usize;
+    if reader.u16()? != 0 {
+        return Err(SLMPEndCode::WRONG_FORMAT);
+    }
+    (0..points).map(|_| file_name(reader, true)).collect()
+}
+
+fn read_labels(state: &MockState, subcommand: u16, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    let names = label_names(subcommand, reader)?;
+
+    let mut ret: Vec<u8> = Vec::new();
+    ret.extend((names.len() as u16).to_le_bytes());
+    for name in &names {
+        let value = state.labels.get(name).ok_or(LABEL_NOT_FOUND)?;
+        let bytes: Vec<u8> = value.to_bytes();
+        ret.extend([label_type_id(value).ok_or(SLMPEndCode::WRONG_FORMAT)?, 0x00]);
+        ret.extend((bytes.len() as u16).to_le_bytes());
+        ret.extend(bytes);
+    }
+    Ok(ret)
+}
+
+/// The labels are checked before any of them is written, so a rejected request changes nothing.
+fn write_labels(state: &mut MockState, subcommand: u16, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    if subcommand != 0x0000 {
+        return Err(SLMPEndCode::WRONG_COMMAND);
+    }
+    let points: usize = reader.u16()? as usize;
+    if reader.u16()? != 0 {
+        return Err(SLMPEndCode::WRONG_FORMAT);
+    }
+
+    let mut values: Vec<(String, TypedData)> = Vec::with_capacity(points);
+    for _ in 0..points {
+        let name = file_name(reader, true)?;
+        let bit_unit: bool = reader.u8()? == 0x00;
+        reader.u8()?;
+        let len: usize = reader.u16()? as usize;
+        // A bit unit gives the length in bits, packed in words.
+        let bytes = reader.bytes(if bit_unit { 2 * len.div_ceil(16) } else { len })?;
+
+        let current = state.labels.get(&name).ok_or(LABEL_NOT_FOUND)?;
+        let data_type: DataType = current.get_type();
+        if bit_unit != (data_type == DataType::Bool) || bytes.len() != current.to_bytes().len() {
+            return Err(SLMPEndCode::WRONG_LENGTH);
+        }
+        values.push((name, TypedData::from((bytes, data_type))));
+    }
+
+    state.labels.extend(values);
+    Ok(Vec::new())
+}
+
+/* File Control */
+
+fn file_subcommand(subcommand: u16, allowed: &[u16]) -> Result<bool, SLMPEndCode> {
+    if allowed.contains(&subcommand) {
+        Ok(subcommand == FILE_SUBCOMMAND_R)
+    } else {
+        Err(SLMPEndCode::WRONG_COMMAND)
+    }
+}
+
+fn skip_password(reader: &mut Reader, r_format: bool) -> Result<(), SLMPEndCode> {
+    if r_format {
+        let len: usize = reader.u16()? as usize;
+        reader.bytes(len)?;
+    } else {
+        reader.bytes(QL_PASSWORD_LEN)?;
+    }
+    Ok(())
+}
+
+fn file_name(reader: &mut Reader, r_format: bool) -> Result<String, SLMPEndCode> {
+    let len: usize = reader.u16()? as usize;
+    if r_format {
+        let utf16: Vec<u16> = reader.bytes(2 * len)?.chunks_exact(2).map(|x| u16::from_le_bytes([x[0], x[1]])).collect();
+        String::from_utf16(&utf16).map_err(|_| SLMPEndCode::WRONG_FORMAT)
+    } else {
+        Ok(SHIFT_JIS.decode(reader.bytes(len)?).0.into_owned())
+    }
+}
+
+fn create_file(state: &mut MockState, subcommand: u16, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    let r_format = file_subcommand(subcommand, &[0x0000, FILE_SUBCOMMAND_R])?;
+    skip_password(reader, r_format)?;
+    let drive = reader.u16()?;
+    let size: usize = reader.u32()? as usize;
+    let name = file_name(reader, r_format)?;
+
+    if state.files.contains_key(&(drive, name.clone())) {
+        return Err(SLMPEndCode::FILE_ALREADY_EXISTS);
+    }
+    state.files.insert((drive, name), MockFile { data: vec![0; size] });
+
+    Ok(Vec::new())
+}
+
+fn delete_file(state: &mut MockState, subcommand: u16, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    let r_format = file_subcommand(subcommand, &[0x0000, 0x0004, FILE_SUBCOMMAND_R])?;
+    skip_password(reader, r_format)?;
+    let drive = reader.u16()?;
+    let name = file_name(reader, r_format)?;
+
+    state.files.remove(&(drive, name)).ok_or(SLMPEndCode::FILE_NOT_FOUND)?;
+
+    Ok(Vec::new())
+}
+
+fn copy_file(state: &mut MockState, subcommand: u16, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    let r_format = file_subcommand(subcommand, &[0x0000, 0x0004, FILE_SUBCOMMAND_R])?;
+    skip_password(reader, r_format)?;
+    let dst_drive = reader.u16()?;
+    let dst_name = file_name(reader, r_format)?;
+    skip_password(reader, r_format)?;
+    let src_drive = reader.u16()?;
+    let src_name = file_name(reader, r_format)?;
+
+    let data = state.files.get(&(src_drive, src_name)).ok_or(SLMPEndCode::FILE_NOT_FOUND)?.data.clone();
+    if state.files.contains_key(&(dst_drive, dst_name.clone())) {
+        return Err(SLMPEndCode::FILE_ALREADY_EXISTS);
+    }
+    state.files.insert((dst_drive, dst_name), MockFile { data });
+
+    Ok(Vec::new())
+}
+
+fn open_file(state: &mut MockState, subcommand: u16, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    let r_format = file_subcommand(subcommand, &[0x0000, 0x0004, FILE_SUBCOMMAND_R])?;
+    skip_password(reader, r_format)?;
+    reader.u16()?;
+    let drive = reader.u16()?;
+    let name = file_name(reader, r_format)?;
+
+    if !state.files.contains_key(&(drive, name.clone())) {
+        return Err(SLMPEndCode::FILE_NOT_FOUND);
+    }
+
+    let file_pointer = state.next_file_pointer;
+    state.next_file_pointer = state.next_file_pointer.wrapping_add(1).max(1);
+    state.open_files.insert(file_pointer, (drive, name));
+
+    Ok(file_pointer.to_le_bytes().to_vec())
+}
+
+fn opened_file(state: &mut MockState, file_pointer: u16) -> Result<&mut MockFile, SLMPEndCode> {
+    let key = state.open_files.get(&file_pointer).ok_or(SLMPEndCode::WRONG_FORMAT)?;
+    state.files.get_mut(key).ok_or(SLMPEndCode::FILE_NOT_FOUND)
+}
+
+fn read_file(state: &mut MockState, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    let file_pointer = reader.u16()?;
+    let offset: usize = reader.u32()? as usize;
+    let len: usize = reader.u16()? as usize;
+
+    let file = opened_file(state, file_pointer)?;
+    let start = offset.min(file.data.len());
+    let end = (offset + len).min(file.data.len());
+    let chunk = &file.data[start..end];
+
+    Ok([&(chunk.len() as u16).to_le_bytes(), chunk].concat())
+}
+
+fn write_file(state: &mut MockState, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    let file_pointer = reader.u16()?;
+    let offset: usize = reader.u32()? as usize;
+    let len: usize = reader.u16()? as usize;
+    let data = reader.bytes(len)?;
+
+    let file = opened_file(state, file_pointer)?;
+    if file.data.len() < offset + len {
+        file.data.resize(offset + len, 0);
+    }
+    file.data[offset..offset + len].copy_from_slice(data);
+
+    Ok((len as u16).to_le_bytes().to_vec())
+}
+
+fn close_file(state: &mut MockState, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    let file_pointer = reader.u16()?;
+    reader.u16()?;
+
+    state.open_files.remove(&file_pointer).ok_or(SLMPEndCode::WRONG_FORMAT)?;
+
+    Ok(Vec::new())
+}
+
+/// File No. is the 0-based index of the file in name order. Timestamps are fixed to 1980-01-01 00:00:00.
+fn read_directory(state: &MockState, subcommand: u16, reader: &mut Reader) -> Result<Vec<u8>, SLMPEndCode> {
+    const FAT_DATE_1980_01_01: u16 = (1 << 5) | 1;
+
+    let r_format = file_subcommand(subcommand, &[0x0000, FILE_SUBCOMMAND_R])?;
+    if !r_format {
+        reader.bytes(4)?;
+    }
+    let drive = reader.u16()?;
+    let start_file_no: usize = if r_format { reader.u32()? as usize } else { reader.u16()? as usize };
+    let request_len: usize = reader.u16()? as usize;
+    let directory_len: usize = reader.u16()? as usize;
+    reader.bytes(if r_format { 2 * directory_len } else { directory_len })?;
+
+    let entries: Vec<(&String, &MockFile)> = state.files.iter()
+        .filter(|((x, _), _)| *x == drive)
+        .map(|((_, name), file)| (name, file))
+        .skip(start_file_no)
+        .take(request_len)
+        .collect();
+
+    let mut ret: Vec<u8> = Vec::new();
+    ret.extend((entries.len() as u16).to_le_bytes());
+
+    for (name, file) in entries {
+        if r_format {
+            let utf16: Vec<u16> = name.encode_utf16().collect();
+            ret.extend((utf16.len() as u16).to_le_bytes());
+            ret.extend(utf16.iter().flat_map(|x| x.to_le_bytes()));
+            ret.extend(0u16.to_le_bytes());
+            ret.extend(0u16.to_le_bytes());
+            ret.extend(FAT_DATE_1980_01_01.to_le_bytes());
+            ret.extend((file.data.len() as u32).to_le_bytes());
+        } else {
+            let (base_name, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+            let mut entry = [0x20u8; 32];
+            let base_name = SHIFT_JIS.encode(base_name).0;
+            let extension = SHIFT_JIS.encode(extension).0;
+            entry[0..base_name.len().min(8)].copy_from_slice(&base_name[..base_name.len().min(8)]);
+            entry[8..8 + extension.len().min(3)].copy_from_slice(&extension[..extension.len().min(3)]);
+            entry[11..32].fill(0);
+            entry[24..26].copy_from_slice(&FAT_DATE_1980_01_01.to_le_bytes());
+            entry[28..32].copy_from_slice(&(file.data.len() as u32).to_le_bytes());
+            ret.extend(entry);
+        }
+    }
+
+    Ok(ret)
+}
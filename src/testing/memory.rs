@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+use crate::{Device, DeviceType, SLMPEndCode};
+
+/// The CPU cannot read/write the specified device (e.g. bit access to a word device).
+pub(crate) const DEVICE_ERROR: SLMPEndCode = SLMPEndCode(0xC05B);
+
+const WORD_BIT_SIZE: usize = 16;
+
+#[inline(always)]
+pub(crate) const fn is_bit_device(device_type: DeviceType) -> bool {
+    device_type.is_bit_device()
+}
+
+/// Sparse device memory. Devices never written read as zero.
+/// Bit devices are stored per point, and word access to them packs 16 points into a word (LSB first).
+#[derive(Default)]
+pub(crate) struct DeviceMemory {
+    words: BTreeMap<(DeviceType, usize), u16>,
+    bits: BTreeMap<(DeviceType, usize), bool>,
+}
+
+impl DeviceMemory {
+    pub fn word(&self, device: Device) -> u16 {
+        if is_bit_device(device.device_type) {
+            (0..WORD_BIT_SIZE).fold(0, |a, i| {
+                let bit = self.bits.get(&(device.device_type, device.address + i)).copied().unwrap_or(false);
+                a | ((bit as u16) << i)
+            })
+        } else {
+            self.words.get(&(device.device_type, device.address)).copied().unwrap_or(0)
+        }
+    }
+
+    pub fn set_word(&mut self, device: Device, value: u16) {
+        if is_bit_device(device.device_type) {
+            for i in 0..WORD_BIT_SIZE {
+                self.bits.insert((device.device_type, device.address + i), value & (1 << i) != 0);
+            }
+        } else {
+            self.words.insert((device.device_type, device.address), value);
+        }
+    }
+
+    pub fn bit(&self, device: Device) -> Result<bool, SLMPEndCode> {
+        if !is_bit_device(device.device_type) {
+            return Err(DEVICE_ERROR);
+        }
+        Ok(self.bits.get(&(device.device_type, device.address)).copied().unwrap_or(false))
+    }
+
+    pub fn set_bit(&mut self, device: Device, value: bool) -> Result<(), SLMPEndCode> {
+        if !is_bit_device(device.device_type) {
+            return Err(DEVICE_ERROR);
+        }
+        self.bits.insert((device.device_type, device.address), value);
+        Ok(())
+    }
+}
@@ -0,0 +1,334 @@
+//! Mock SLMP server for testing without a physical PLC (`mock-server` feature).
+//!
+//! ```rust,no_run
+//! use slmp::{CPU, SLMPClient};
+//! use slmp::testing::MockServer;
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let server = MockServer::start(CPU::R).await?;
+//! let client = SLMPClient::new(server.connection_props());
+//! client.connect().await?;
+//!
+//! // The same in one call.
+//! let (server, client) = MockServer::start_connected(CPU::R).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod handler;
+mod memory;
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::{JoinHandle, JoinSet};
+
+use crate::{CPU, Device, FileDrive, SLMP4EConnectionProps, SLMPClient, SLMPEndCode, TypedData};
+use crate::frame_1e;
+use handler::MockState;
+
+const FIXED_FRAME_LEN: usize = 13;
+const REQUEST_CODE: [u8; 2] = [0x54, 0x00];
+const RESPONSE_CODE: [u8; 2] = [0xD4, 0x00];
+const CPUTIMER_LEN: usize = 2;
+const COMMAND_LEN: usize = 4;
+
+/// 1E frame: command, PC No. and monitoring timer.
+const HEADER_LEN_1E: usize = 4;
+/// 1E device access: head device (address 4 bytes + device code 2 bytes), points (0 for 256) and 0x00.
+const DEVICE_ACCESS_LEN_1E: usize = 8;
+const COMPLETION_WRONG_COMMAND: u8 = 0x50;
+const COMPLETION_DEVICE_ERROR: u8 = 0x56;
+const COMPLETION_POINTS_ERROR: u8 = 0x57;
+
+/// In-process SLMP server speaking 4E binary frames on a local TCP port (1E frames for `CPU::A`).
+///
+/// It keeps device memory, files and global labels in memory and answers device access (bulk, random, block, monitor),
+/// unit control, file control and label access commands.
+/// Malformed requests are answered with the end code a CPU would return
+/// (`WRONG_LENGTH` for a request shorter or longer than the command requires, `WRONG_COMMAND` for unknown commands).
+/// With `CPU::A` it answers batch read/write, remote RUN/STOP, model read and loopback of the 1E frame,
+/// and errors with completion code 0x50 (command), 0x56 (device) or 0x57 (points).
+/// The server stops when it is dropped.
+pub struct MockServer {
+    local_addr: SocketAddr,
+    cpu: CPU,
+    state: Arc<Mutex<MockState>>,
+    handle: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Start a server on a free port of the loopback interface.
+    pub async fn start(cpu: CPU) -> std::io::Result<Self> {
+        Self::bind("127.0.0.1:0", cpu).await
+    }
+
+    /// Start a server as `start`, and a client connected to it.
+    pub async fn start_connected(cpu: CPU) -> std::io::Result<(Self, SLMPClient)> {
+        let server = Self::start(cpu).await?;
+        let client = SLMPClient::new(server.connection_props());
+        client.connect().await?;
+        Ok((server, client))
+    }
+
+    pub async fn bind(addr: &str, cpu: CPU) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+
+        let cpu_type = match cpu {
+            CPU::Q => "Q03UDVCPU",
+            CPU::R => "R04CPU",
+            CPU::L => "L02CPU",
+            CPU::F => "FX5U-32MR/ES",
+            CPU::A => "A2USHCPU-S1",
+        };
+        let state = Arc::new(Mutex::new(MockState::new(cpu_type)));
+
+        let shared_state = state.clone();
+        let handle = tokio::spawn(async move {
+            // Connection tasks are aborted together with this task when the JoinSet is dropped.
+            let mut connections: JoinSet<()> = JoinSet::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                match cpu {
+                    CPU::A => connections.spawn(serve_1e(stream, shared_state.clone())),
+                    _ => connections.spawn(serve(stream, shared_state.clone())),
+                };
+            }
+        });
+
+        Ok(Self { local_addr, cpu, state, handle })
+    }
+
+    pub const fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Connection properties pointing to this server.
+    pub fn connection_props(&self) -> SLMP4EConnectionProps {
+        SLMP4EConnectionProps {
+            ip: self.local_addr.ip().to_string(),
+            port: self.local_addr.port(),
+            cpu: self.cpu,
+            serial_id: 0x0001,
+            network_id: 0x00,
+            pc_id: 0xff,
+            io_id: 0x03ff,
+            area_id: 0x00,
+            cpu_timer: 0x0010,
+            remote_password: None,
+        }
+    }
+
+    /// Read a word directly from the device memory. Bit devices are packed 16 points per word.
+    pub fn word(&self, device: Device) -> u16 {
+        self.state.lock().unwrap().memory.word(device)
+    }
+
+    pub fn set_word(&self, device: Device, value: u16) {
+        self.state.lock().unwrap().memory.set_word(device, value);
+    }
+
+    /// Read a bit directly from the device memory. It fails for word devices.
+    pub fn bit(&self, device: Device) -> std::io::Result<bool> {
+        self.state.lock().unwrap().memory.bit(device).map_err(std::io::Error::from)
+    }
+
+    pub fn set_bit(&self, device: Device, value: bool) -> std::io::Result<()> {
+        self.state.lock().unwrap().memory.set_bit(device, value).map_err(std::io::Error::from)
+    }
+
+    /// Set the model name returned by `get_cpu_type`.
+    pub fn set_cpu_type(&self, cpu_type: &str) {
+        self.state.lock().unwrap().cpu_type = cpu_type.to_string();
+    }
+
+    /// Delay every response to simulate a slow CPU or network.
+    pub fn set_response_delay(&self, delay: std::time::Duration) {
+        self.state.lock().unwrap().response_delay = delay;
+    }
+
+    /// Require a remote password for unlock and lock requests (default: any password is accepted).
+    pub fn set_remote_password(&self, password: Option<&str>) {
+        self.state.lock().unwrap().remote_password = password.map(str::to_string);
+    }
+
+    /// Whether the last unlock request has not been followed by a lock request.
+    pub fn is_remote_unlocked(&self) -> bool {
+        self.state.lock().unwrap().remote_unlocked
+    }
+
+    /// A global label, answered to random label read (0x041C) and array label write (0x141A).
+    pub fn label(&self, name: &str) -> Option<TypedData> {
+        self.state.lock().unwrap().label(name)
+    }
+
+    /// Define a global label with its data type and value. It fails with `InvalidInput` for U64 and I64, which have no label data type.
+    pub fn set_label(&self, name: &str, value: TypedData) -> std::io::Result<()> {
+        self.state.lock().unwrap().set_label(name, value)
+            .map_err(|x| std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("No label data type for {:?}", x.get_type())))
+    }
+
+    pub fn file(&self, drive: FileDrive, file_name: &str) -> Option<Vec<u8>> {
+        let drive = u16::from_le_bytes(drive.to_drive_code());
+        self.state.lock().unwrap().file(drive, file_name)
+    }
+
+    pub fn insert_file(&self, drive: FileDrive, file_name: &str, data: &[u8]) {
+        let drive = u16::from_le_bytes(drive.to_drive_code());
+        self.state.lock().unwrap().insert_file(drive, file_name, data);
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn serve(mut stream: TcpStream, state: Arc<Mutex<MockState>>) {
+    let mut header = [0u8; FIXED_FRAME_LEN];
+
+    loop {
+        if stream.read_exact(&mut header).await.is_err() {
+            return;
+        }
+
+        let data_len: usize = u16::from_le_bytes([header[11], header[12]]) as usize;
+        let mut data = vec![0u8; data_len];
+        if stream.read_exact(&mut data).await.is_err() {
+            return;
+        }
+
+        // A CPU does not answer frames other than 4E requests.
+        if header[0..2] != REQUEST_CODE {
+            continue;
+        }
+
+        let (result, response_delay) = {
+            let mut state = state.lock().unwrap();
+            let result = if data_len < CPUTIMER_LEN + COMMAND_LEN {
+                Err(SLMPEndCode::WRONG_LENGTH)
+            } else {
+                let command = u16::from_le_bytes([data[2], data[3]]);
+                let subcommand = u16::from_le_bytes([data[4], data[5]]);
+                handler::handle(&mut state, command, subcommand, &data[(CPUTIMER_LEN + COMMAND_LEN)..])
+            };
+            (result, state.response_delay)
+        };
+
+        if !response_delay.is_zero() {
+            tokio::time::sleep(response_delay).await;
+        }
+
+        let (end_code, body): (u16, Vec<u8>) = match result {
+            Ok(body) => (0, body),
+            Err(SLMPEndCode(code)) => {
+                // Error information: network, pc, io, area (same as the request) + command and subcommand.
+                let mut body: Vec<u8> = header[6..11].to_vec();
+                body.extend(data.get(CPUTIMER_LEN..(CPUTIMER_LEN + COMMAND_LEN)).unwrap_or(&[0; COMMAND_LEN]));
+                (code, body)
+            }
+        };
+
+        let response_len: [u8; 2] = ((2 + body.len()) as u16).to_le_bytes();
+        let mut response: Vec<u8> = Vec::with_capacity(FIXED_FRAME_LEN + 2 + body.len());
+        response.extend(RESPONSE_CODE);
+        response.extend(&header[2..11]);
+        response.extend(response_len);
+        response.extend(end_code.to_le_bytes());
+        response.extend(body);
+
+        if stream.write_all(&response).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Points of a 1E device access, 0 standing for 256.
+fn points_1e(data: &[u8]) -> usize {
+    match data[6] {
+        0 => 256,
+        n => n as usize,
+    }
+}
+
+async fn serve_1e(mut stream: TcpStream, state: Arc<Mutex<MockState>>) {
+    let mut header = [0u8; HEADER_LEN_1E];
+
+    loop {
+        if stream.read_exact(&mut header).await.is_err() {
+            return;
+        }
+        let command: u8 = header[0];
+
+        // A 1E frame has no length; it follows from the command and the points.
+        let mut data = vec![0u8; match command {
+            0x00..=0x03 => DEVICE_ACCESS_LEN_1E,
+            0x16 => 1,
+            _ => 0,
+        }];
+        if stream.read_exact(&mut data).await.is_err() {
+            return;
+        }
+        let mut written = vec![0u8; match command {
+            0x02 => points_1e(&data).div_ceil(2),
+            0x03 => 2 * points_1e(&data),
+            0x16 => data[0] as usize,
+            _ => 0,
+        }];
+        if stream.read_exact(&mut written).await.is_err() {
+            return;
+        }
+
+        let (result, response_delay) = {
+            let mut state = state.lock().unwrap();
+            (handle_1e(&mut state, command, &data, &written), state.response_delay)
+        };
+
+        if !response_delay.is_zero() {
+            tokio::time::sleep(response_delay).await;
+        }
+
+        let response: Vec<u8> = match result {
+            Ok(body) => [&[command | 0x80, 0x00][..], &body].concat(),
+            Err(completion_code) => vec![command | 0x80, completion_code],
+        };
+        if stream.write_all(&response).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Answer a 1E request by the 4E commands of `handler::handle`.
+fn handle_1e(state: &mut MockState, command: u8, data: &[u8], written: &[u8]) -> Result<Vec<u8>, u8> {
+    let completion_code = |x: SLMPEndCode| match x {
+        memory::DEVICE_ERROR => COMPLETION_DEVICE_ERROR,
+        handler::POINTS_OUT_OF_RANGE => COMPLETION_POINTS_ERROR,
+        _ => COMPLETION_WRONG_COMMAND,
+    };
+
+    match command {
+        0x00..=0x03 => {
+            let device_type = frame_1e::device_type(u16::from_le_bytes([data[4], data[5]])).ok_or(COMPLETION_DEVICE_ERROR)?;
+            let mut request: Vec<u8> = vec![data[0], data[1], data[2], device_type.to_code()];
+            request.extend((points_1e(data) as u16).to_le_bytes());
+            request.extend(written);
+            let (command, subcommand): (u16, u16) = match command {
+                0x00 => (0x0401, 0x0001),
+                0x01 => (0x0401, 0x0000),
+                0x02 => (0x1401, 0x0001),
+                _ => (0x1401, 0x0000),
+            };
+            handler::handle(state, command, subcommand, &request).map_err(completion_code)
+        },
+        0x13 | 0x14 => Ok(Vec::new()),
+        // The model name is not in the 1E frame; the model code is one byte.
+        0x15 => handler::handle(state, 0x0101, 0x0000, &[]).map(|x| vec![x[16]]).map_err(completion_code),
+        0x16 => {
+            let request: Vec<u8> = [&(written.len() as u16).to_le_bytes()[..], written].concat();
+            handler::handle(state, 0x0619, 0x0000, &request).map(|x| [&data[..1], &x[2..]].concat()).map_err(completion_code)
+        },
+        _ => Err(COMPLETION_WRONG_COMMAND),
+    }
+}
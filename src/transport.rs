@@ -0,0 +1,163 @@
+use std::future::Future;
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::{BUFSIZE, CONNECT_TIMEOUT, CPU, SLMP4EConnectionProps};
+use crate::frame_1e::Frames1E;
+use crate::requests;
+
+/// Link carrying 4E binary frames between `SLMPClient` and a CPU, e.g. TCP (`TcpTransport`),
+/// a serial communication module, or a bridge to another network.
+///
+/// The client builds the request frames, matches the responses by serial No. and checks them;
+/// a transport only moves whole frames.
+///
+/// A transport implements `connect`, `close`, `send` and `recv`; `recv_into`, `exchange` and `supports_a_series` have default implementations.
+/// The client does not go through `exchange`, which could not serve it: it sends a request once and receives until
+/// the response of its serial No. comes, skipping late responses of requests that timed out, and it drops `recv` on a timeout
+/// without sending again. `connect` and `close` let the client reconnect under its retry policy and after a send timed out halfway.
+pub trait SlmpTransport: Send + 'static {
+    /// Open the link, closing the current one if any.
+    fn connect(&mut self) -> impl Future<Output = std::io::Result<()>> + Send;
+
+    fn close(&mut self) -> impl Future<Output = ()> + Send;
+
+    /// Send a whole request frame. It fails with `NotConnected` before `connect`.
+    fn send(&mut self, frame: &[u8]) -> impl Future<Output = std::io::Result<()>> + Send;
+
+    /// Receive the next whole response frame.
+    /// It must be cancel safe, as the client drops it on a timeout and calls it again for the next request.
+    fn recv(&mut self) -> impl Future<Output = std::io::Result<Vec<u8>>> + Send;
+
+    /// Same as `recv` into `buf`, replacing its content. A transport may override it to reuse the allocation of `buf`.
+    fn recv_into(&mut self, buf: &mut Vec<u8>) -> impl Future<Output = std::io::Result<()>> + Send {
+        async move {
+            *buf = self.recv().await?;
+            Ok(())
+        }
+    }
+
+    /// Send a request frame and receive the next response frame.
+    fn exchange(&mut self, frame: &[u8]) -> impl Future<Output = std::io::Result<Vec<u8>>> + Send {
+        async move {
+            self.send(frame).await?;
+            self.recv().await
+        }
+    }
+
+    /// Whether the transport may carry the requests of `CPU::A`, which does not take 4E frames:
+    /// it translates them to the 1E frame as `TcpTransport` does, or it does not reach a CPU.
+    /// The client refuses the requests of `CPU::A` over the other transports with `Unsupported`.
+    fn supports_a_series(&self) -> bool {
+        false
+    }
+}
+
+/// SLMP over TCP, the transport of `SLMPClient::new`.
+///
+/// For `CPU::A` it speaks the 1E frame on the wire: the requests the 1E frame has are translated,
+/// and the others fail with `Unsupported` before anything is sent.
+pub struct TcpTransport {
+    ip: String,
+    port: u16,
+    stream: Option<TcpStream>,
+    /// Bytes received but not taken as a frame yet, e.g. the head of a response that arrived as a request timed out.
+    pending: Vec<u8>,
+    frames_1e: Option<Frames1E>,
+}
+
+impl TcpTransport {
+    /// Connect to `ip` and `port` of `connection_props` (on `connect`).
+    pub fn new(connection_props: &SLMP4EConnectionProps) -> Self {
+        Self {
+            ip: connection_props.ip.clone(),
+            port: connection_props.port,
+            stream: None,
+            pending: Vec::with_capacity(BUFSIZE),
+            frames_1e: (connection_props.cpu == CPU::A).then(Frames1E::new),
+        }
+    }
+
+    fn stream(&mut self) -> std::io::Result<&mut TcpStream> {
+        self.stream.as_mut().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotConnected, "Not Connected"))
+    }
+}
+
+impl SlmpTransport for TcpTransport {
+    async fn connect(&mut self) -> std::io::Result<()> {
+        self.close().await;
+
+        let socket_addr: SocketAddr = tokio::net::lookup_host((self.ip.as_str(), self.port)).await?
+            .next()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "resolve failed"))?;
+
+        let stream: TcpStream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(socket_addr))
+            .await
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut,"Connect Failed (Timeout)"))??;
+
+        self.stream = Some(stream);
+        self.pending.clear();
+
+        Ok(())
+    }
+
+    async fn close(&mut self) {
+        if let Some(mut stream) = self.stream.take() {
+            let _ = stream.shutdown().await;
+        }
+        if let Some(frames_1e) = &mut self.frames_1e {
+            frames_1e.clear();
+        }
+    }
+
+    async fn send(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        self.stream()?;
+        match &mut self.frames_1e {
+            Some(frames_1e) => {
+                let frame: Vec<u8> = frames_1e.request(frame)?;
+                self.stream()?.write_all(&frame).await
+            },
+            None => self.stream()?.write_all(frame).await,
+        }
+    }
+
+    /// Bytes received before a cancellation are kept, so the next call starts at a frame boundary.
+    async fn recv(&mut self) -> std::io::Result<Vec<u8>> {
+        let mut frame: Vec<u8> = Vec::new();
+        self.recv_into(&mut frame).await?;
+        Ok(frame)
+    }
+
+    async fn recv_into(&mut self, buf: &mut Vec<u8>) -> std::io::Result<()> {
+        loop {
+            let frame_len: Option<usize> = match &self.frames_1e {
+                Some(frames_1e) => frames_1e.frame_len(&self.pending)?,
+                None => requests::frame_len(&self.pending, BUFSIZE)?,
+            };
+            if let Some(frame_len) = frame_len
+                && self.pending.len() >= frame_len
+            {
+                buf.clear();
+                match &mut self.frames_1e {
+                    Some(frames_1e) => {
+                        let frame: Vec<u8> = self.pending.drain(..frame_len).collect();
+                        buf.extend(frames_1e.response(&frame)?);
+                    },
+                    None => buf.extend(self.pending.drain(..frame_len)),
+                }
+                return Ok(());
+            }
+
+            self.pending.reserve(BUFSIZE);
+            let stream = self.stream.as_mut().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotConnected, "Not Connected"))?;
+            if stream.read_buf(&mut self.pending).await? == 0 {
+                return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Connection Closed"));
+            }
+        }
+    }
+
+    fn supports_a_series(&self) -> bool {
+        self.frames_1e.is_some()
+    }
+}
@@ -0,0 +1,228 @@
+use crate::requests::{self, BlockRead, BulkRead, WordRead};
+use crate::{AccessType, BlockedDeviceData, CPU, DataType, Device, DeviceData, DeviceLimits, MonitorList, TypedData, TypedDevice, WordOrder};
+
+/// A device whose value read back differs from the value written.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeviceMismatch {
+    pub device: Device,
+    pub expected: TypedData,
+    pub actual: TypedData,
+}
+
+/// Error of a verified write (e.g. `SLMPClient::bulk_write_verified`) listing every mismatching device,
+/// carried by the returned `std::io::Error` (kind: `InvalidData`).
+/// The values are compared bit-exact in their register images, so that -0.0 differs from 0.0 and NaN matches itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VerificationFailed {
+    pub mismatches: Vec<DeviceMismatch>,
+}
+
+impl VerificationFailed {
+    /// Take the mismatches out of an error returned from `SLMPClient`.
+    pub fn from_io_error(error: &std::io::Error) -> Option<&Self> {
+        error.get_ref()?.downcast_ref::<Self>()
+    }
+}
+
+impl std::fmt::Display for VerificationFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Verification failed:")?;
+        for x in &self.mismatches {
+            write!(f, " {} (written: {:?}, read: {:?})", x.device, x.expected, x.actual)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for VerificationFailed {}
+
+/// How the responses of the read-back are decoded.
+enum ReadBack {
+    Bits(BulkRead),
+    /// Bytes of each expected value follow one another in the words read.
+    Words(WordRead),
+    Random(Vec<MonitorList>),
+    /// Word blocks are decoded as `Words`, bit blocks one point per device.
+    Block { request: BlockRead, sorted_blocks: Vec<(AccessType, usize)> },
+}
+
+/// Read-back of a write request, built (and checked) before the write is sent.
+pub(crate) struct Verification {
+    /// Written values in the word order of the client.
+    expected: Vec<DeviceData>,
+    commands: Vec<Vec<u8>>,
+    read_back: ReadBack,
+    order: WordOrder,
+}
+
+impl Verification {
+    pub fn bulk(cpu: &CPU, limits: &DeviceLimits, start_device: Device, data: &[TypedData], order: WordOrder) -> std::io::Result<Self> {
+        requests::check_not_empty(data.len(), "Bulk write")?;
+        if data.iter().all(|x| matches!(x, TypedData::Bool(_))) {
+            let request = BulkRead { start_device, device_num: data.len(), data_type: DataType::Bool };
+            let expected = data.iter().enumerate().map(|(i, x)| DeviceData { device: start_device + i, data: x.clone() }).collect();
+            Ok(Self { expected, commands: vec![request.command(cpu, limits)?], read_back: ReadBack::Bits(request), order })
+        } else {
+            let expected = word_devices(start_device, data);
+            let request = WordRead { start_device, word_num: words(data) };
+            Ok(Self { expected, commands: vec![request.command(cpu, limits)?], read_back: ReadBack::Words(request), order })
+        }
+    }
+
+    /// The devices are read back in as many random reads as their access points take.
+    pub fn random(cpu: &CPU, limits: &DeviceLimits, data: &[DeviceData], order: WordOrder) -> std::io::Result<Self> {
+        requests::check_not_empty(data.len(), "Random write")?;
+        let devices: Vec<TypedDevice> = data.iter().map(|x| TypedDevice { device: x.device, data_type: raw_type(x.data.get_type()) }).collect();
+
+        let mut chunks: Vec<&[TypedDevice]> = Vec::new();
+        let mut rest: &[TypedDevice] = &devices;
+        while !rest.is_empty() {
+            let mut len: usize = 1;
+            while len < rest.len() && crate::monitor::access_points(&rest[..=len]) <= crate::monitor_max_access_points(cpu) {
+                len += 1;
+            }
+            let (chunk, tail) = rest.split_at(len);
+            chunks.push(chunk);
+            rest = tail;
+        }
+
+        let (commands, lists) = chunks.into_iter()
+            .map(|x| requests::random_read(cpu, limits, x))
+            .collect::<std::io::Result<(Vec<_>, Vec<_>)>>()?;
+        Ok(Self { expected: data.to_vec(), commands, read_back: ReadBack::Random(lists), order })
+    }
+
+    pub fn block(cpu: &CPU, limits: &DeviceLimits, data: &[BlockedDeviceData], order: WordOrder) -> std::io::Result<Self> {
+        requests::check_not_empty(data.len(), "Block write")?;
+        let blocks: Vec<crate::DeviceBlock> = data.iter()
+            .map(|x| crate::DeviceBlock {
+                access_type: x.access_type,
+                start_device: x.start_device,
+                size: match x.access_type {
+                    AccessType::Word => words(x.data),
+                    AccessType::Bit => x.data.len(),
+                },
+            })
+            .collect();
+        let request = BlockRead::new(cpu, limits, &blocks)?;
+
+        // The response holds the blocks in the order `BlockRead` sorts them: word blocks first, by address.
+        let mut sorted: Vec<usize> = (0..data.len()).collect();
+        sorted.sort_by_key(|&i| (data[i].access_type, data[i].start_device.address));
+
+        let mut expected: Vec<DeviceData> = Vec::new();
+        let mut sorted_blocks: Vec<(AccessType, usize)> = Vec::with_capacity(data.len());
+        for i in sorted {
+            let block = &data[i];
+            match block.access_type {
+                AccessType::Word => expected.extend(word_devices(block.start_device, block.data)),
+                AccessType::Bit => expected.extend(block.data.iter().enumerate().map(|(j, x)| DeviceData { device: block.start_device + j, data: x.clone() })),
+            }
+            sorted_blocks.push((block.access_type, block.data.len()));
+        }
+
+        Ok(Self { expected, commands: vec![request.command(cpu)], read_back: ReadBack::Block { request, sorted_blocks }, order })
+    }
+
+    pub fn commands(&self) -> &[Vec<u8>] {
+        &self.commands
+    }
+
+    /// Compare the responses of `commands` with the values written.
+    pub fn check(&self, responses: &[Vec<u8>]) -> std::io::Result<()> {
+        let actual: Vec<TypedData> = match &self.read_back {
+            ReadBack::Bits(request) => request.parse(&responses[0])?.into_iter().map(|x| x.data).collect(),
+            ReadBack::Words(request) => {
+                request.check(&responses[0])?;
+                self.decode_words(&self.expected, &responses[0])?
+            },
+            ReadBack::Random(lists) => {
+                let mut actual: Vec<TypedData> = Vec::with_capacity(self.expected.len());
+                for (list, recv) in lists.iter().zip(responses) {
+                    actual.extend(list.parse(recv)?.into_iter().map(|x| x.data));
+                }
+                actual.iter().zip(&self.expected)
+                    .map(|(raw, expected)| TypedData::decode(&raw.to_bytes(), expected.data.get_type()).map(|x| x.with_word_order(self.order)))
+                    .collect::<std::io::Result<_>>()?
+            },
+            ReadBack::Block { request, sorted_blocks } => {
+                let read: Vec<DeviceData> = request.parse(&responses[0])?;
+                let mut read = read.into_iter().map(|x| x.data);
+                let mut expected: &[DeviceData] = &self.expected;
+                let mut actual: Vec<TypedData> = Vec::with_capacity(expected.len());
+                for &(access_type, len) in sorted_blocks {
+                    let (block, tail) = expected.split_at(len);
+                    expected = tail;
+                    match access_type {
+                        AccessType::Word => {
+                            let bytes: Vec<u8> = read.by_ref().take(words_of(block)).flat_map(|x| x.to_bytes()).collect();
+                            actual.extend(self.decode_words(block, &bytes)?);
+                        },
+                        AccessType::Bit => actual.extend(read.by_ref().take(len)),
+                    }
+                }
+                actual
+            },
+        };
+
+        if actual.len() != self.expected.len() {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Received Invalid Verification Data"));
+        }
+
+        // Bit-exact: the register images are compared, not the values.
+        let mismatches: Vec<DeviceMismatch> = self.expected.iter().zip(actual)
+            .filter(|(expected, actual)| expected.data.get_type() != actual.get_type() || expected.data.to_bytes() != actual.to_bytes())
+            .map(|(expected, actual)| DeviceMismatch { device: expected.device, expected: expected.data.clone(), actual })
+            .collect();
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(std::io::Error::new(std::io::ErrorKind::InvalidData, VerificationFailed { mismatches }))
+        }
+    }
+
+    /// Values of the types of `expected` laid out one after another in `bytes`.
+    /// A BCD value with a digit over 9 fails with `InvalidData`, as it cannot be compared with the value written.
+    fn decode_words(&self, expected: &[DeviceData], bytes: &[u8]) -> std::io::Result<Vec<TypedData>> {
+        let mut rest: &[u8] = bytes;
+        expected.iter()
+            .map(|x| {
+                let (value, tail) = rest.split_at(x.data.to_bytes().len());
+                rest = tail;
+                TypedData::decode(value, x.data.get_type()).map(|x| x.with_word_order(self.order))
+            })
+            .collect()
+    }
+}
+
+/// Words of the values written one after another, a `Bool` taking a word.
+fn words(data: &[TypedData]) -> usize {
+    data.iter().map(|x| x.to_bytes().len() / 2).sum()
+}
+
+fn words_of(data: &[DeviceData]) -> usize {
+    data.iter().map(|x| x.data.to_bytes().len() / 2).sum()
+}
+
+/// Devices of the values written in words from `start_device`. A word of a bit device holds 16 points.
+fn word_devices(start_device: Device, data: &[TypedData]) -> Vec<DeviceData> {
+    let points: usize = start_device.device_type.points(DataType::U16);
+    let mut offset: usize = 0;
+    data.iter()
+        .map(|x| {
+            let device = start_device + offset * points;
+            offset += x.to_bytes().len() / 2;
+            DeviceData { device, data: x.clone() }
+        })
+        .collect()
+}
+
+/// Type read back for a written value: BCD is read raw, so that a broken BCD is a mismatch rather than a read error.
+fn raw_type(data_type: DataType) -> DataType {
+    match data_type {
+        DataType::Bcd16 => DataType::U16,
+        DataType::Bcd32 => DataType::U32,
+        x => x,
+    }
+}
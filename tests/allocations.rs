@@ -0,0 +1,98 @@
+//! Heap allocations of the monitor hot path, counted per thread by a global allocator.
+//! After a warm-up, a cyclic read of a fixed list allocates nothing.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use slmp::*;
+use slmp::testing::MockServer;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts the allocations of each thread, so that those of other tests and of the mock server are left out.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|x| x.set(x.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|x| x.set(x.get() + 1));
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|x| x.get())
+}
+
+/// Double words registered before single words, so that the response order differs from the registration order.
+fn devices() -> Vec<TypedDevice> {
+    let device = |address, data_type| TypedDevice { device: Device { device_type: DeviceType::D, address }, data_type };
+    vec![
+        device(0, DataType::U32),
+        device(2, DataType::U16),
+        device(4, DataType::F32),
+        device(6, DataType::I16),
+        device(8, DataType::U64),
+        device(12, DataType::Bool),
+        device(13, DataType::I32),
+    ]
+}
+
+#[test]
+fn parse_into() {
+    let devices = devices();
+    let list = MonitorList::try_from(&devices[..]).unwrap();
+    // 4 + 2 + 4 + 2 + 8 + 2 (a word for Bool) + 4
+    let data: Vec<u8> = (0..26).collect();
+
+    let mut values: Vec<DeviceData> = Vec::new();
+    list.parse_into(&data, &mut values).unwrap();
+    assert_eq!(values, list.parse(&data).unwrap());
+    assert_eq!(values.iter().map(|x| x.device).collect::<Vec<_>>(), devices.iter().map(|x| x.device).collect::<Vec<_>>());
+
+    let count = allocations();
+    for _ in 0..100 {
+        list.parse_into(&data, &mut values).unwrap();
+    }
+    assert_eq!(allocations() - count, 0);
+    assert!(list.parse_into(&data[1..], &mut values).is_err());
+}
+
+#[test]
+fn monitor_read_with() {
+    // The server runs on a runtime of its own threads, the client on this thread.
+    let server_runtime = tokio::runtime::Runtime::new().unwrap();
+    let server = server_runtime.block_on(MockServer::start(CPU::R)).unwrap();
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+
+    runtime.block_on(async {
+        let client = SLMPClient::new(server.connection_props());
+        client.connect().await.unwrap();
+        let devices = devices();
+        let list = client.monitor_register(&devices).await.unwrap();
+
+        let mut buffer = MonitorReadBuffer::new();
+        let expected: Vec<DeviceData> = client.monitor_read_with(&list, &mut buffer).await.unwrap().to_vec();
+        assert_eq!(expected, client.monitor_read(&list).await.unwrap());
+
+        let count = allocations();
+        for _ in 0..100 {
+            client.monitor_read_with(&list, &mut buffer).await.unwrap();
+        }
+        assert_eq!(allocations() - count, 0);
+
+        client.close().await;
+    });
+}
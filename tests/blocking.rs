@@ -0,0 +1,142 @@
+//! The blocking client against the mock server, run on a thread outside of the runtime.
+
+use std::time::Duration;
+use slmp::*;
+use slmp::testing::MockServer;
+
+async fn run_blocking<T, F>(f: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+{
+    tokio::task::spawn_blocking(f).await.unwrap()
+}
+
+#[tokio::test]
+async fn blocking_device_access() {
+    for cpu in [CPU::Q, CPU::R] {
+        let server = MockServer::start(cpu).await.unwrap();
+        let props = server.connection_props();
+        let d = |address| Device { device_type: DeviceType::D, address };
+        let m = |address| Device { device_type: DeviceType::M, address };
+        server.set_word(d(10), 0x1234);
+
+        let ret = run_blocking(move || {
+            let mut client = slmp::blocking::SLMPClient::new(props);
+            client.connect()?;
+
+            client.bulk_write(d(0), &[TypedData::U16(1), TypedData::U32(0x0003_0002)])?;
+            client.bulk_write(m(0), &[true, false, true].map(TypedData::Bool))?;
+            client.random_write(&[
+                DeviceData { device: d(20), data: TypedData::I16(-1) },
+                DeviceData { device: m(5), data: TypedData::Bool(true) },
+            ])?;
+            let words = [TypedData::U16(7), TypedData::U16(8)];
+            client.block_write(&[BlockedDeviceData { access_type: AccessType::Word, start_device: d(30), data: &words }])?;
+
+            let bulk = client.bulk_read(d(0), 3, DataType::U16)?;
+            let bits = client.bulk_read(m(0), 3, DataType::Bool)?;
+            let random = client.random_read(&[
+                TypedDevice { device: d(10), data_type: DataType::U16 },
+                TypedDevice { device: d(20), data_type: DataType::I16 },
+                TypedDevice { device: m(5), data_type: DataType::Bool },
+            ])?;
+            let block = client.block_read(&[DeviceBlock { access_type: AccessType::Word, start_device: d(30), size: 2 }])?;
+
+            client.bulk_write_words(d(40), &[5, 6])?;
+            let raw = client.bulk_read_words(d(40), 2)?;
+
+            client.echo()?;
+            let cpu_type = client.get_cpu_type()?;
+
+            std::io::Result::Ok((bulk, bits, random, block, raw, cpu_type))
+        }).await;
+
+        let (bulk, bits, random, block, raw, cpu_type) = ret.unwrap();
+        assert_eq!(bulk.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), [1, 2, 3].map(TypedData::U16));
+        assert_eq!(bits.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), [true, false, true].map(TypedData::Bool));
+        assert_eq!(random.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), [TypedData::U16(0x1234), TypedData::I16(-1), TypedData::Bool(true)]);
+        assert_eq!(block.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), [7, 8].map(TypedData::U16));
+        assert_eq!(raw, [5, 6]);
+        assert_eq!(cpu_type.family, Some(cpu));
+    }
+}
+
+#[tokio::test]
+async fn blocking_errors() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    server.set_response_delay(Duration::from_millis(200));
+
+    let ret = run_blocking(move || {
+        let mut client = slmp::blocking::SLMPClient::new(props);
+        let not_connected = client.echo().unwrap_err();
+        client.connect().unwrap();
+
+        let invalid = client.bulk_read(Device { device_type: DeviceType::D, address: 0 }, 0, DataType::U16).unwrap_err();
+
+        client.set_recv_timeout(Duration::from_millis(50));
+        let timed_out = client.echo().unwrap_err();
+
+        // The late response is skipped.
+        client.set_recv_timeout(Duration::from_secs(1));
+        client.echo().unwrap();
+
+        (not_connected.kind(), invalid.kind(), timed_out.kind())
+    }).await;
+
+    assert_eq!(ret, (std::io::ErrorKind::NotConnected, std::io::ErrorKind::InvalidInput, std::io::ErrorKind::TimedOut));
+}
+
+#[tokio::test]
+async fn blocking_verified_writes() {
+    let server = MockServer::start(CPU::Q).await.unwrap();
+    let props = server.connection_props();
+    let d = |address| Device { device_type: DeviceType::D, address };
+
+    run_blocking(move || {
+        let mut client = slmp::blocking::SLMPClient::new(props);
+        client.connect()?;
+        client.bulk_write_verified(d(0), &[TypedData::F32(-0.0), TypedData::U16(3)])?;
+        client.random_write_verified(&[DeviceData { device: d(20), data: TypedData::U64(u64::MAX) }])?;
+        let words = [TypedData::I16(-5)];
+        client.block_write_verified(&[BlockedDeviceData { access_type: AccessType::Word, start_device: d(30), data: &words }])
+    }).await.unwrap();
+    assert_eq!(server.word(d(2)), 3);
+}
+
+#[tokio::test]
+async fn blocking_read_only() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+    server.set_word(d0, 7);
+
+    let (denied, read) = run_blocking(move || {
+        let mut client = slmp::blocking::SLMPClient::new(props);
+        client.connect().unwrap();
+        client.set_read_only(true);
+        let denied = client.bulk_write(d0, &[TypedData::U16(1)]).unwrap_err().kind();
+        (denied, client.bulk_read(d0, 1, DataType::U16).unwrap())
+    }).await;
+    assert_eq!(denied, std::io::ErrorKind::PermissionDenied);
+    assert_eq!(read[0].data, TypedData::U16(7));
+    assert_eq!(server.word(d0), 7);
+}
+
+#[tokio::test]
+async fn blocking_remote_password() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    server.set_remote_password(Some("secret"));
+    let mut props = server.connection_props();
+    props.remote_password = Some(RemotePassword::new("secret"));
+
+    let client = run_blocking(move || {
+        let mut client = slmp::blocking::SLMPClient::new(props);
+        client.connect().unwrap();
+        client
+    }).await;
+    assert!(server.is_remote_unlocked());
+    run_blocking(move || drop(client)).await;
+    assert!(!server.is_remote_unlocked());
+}
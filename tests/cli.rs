@@ -0,0 +1,33 @@
+#![cfg(feature = "cli")]
+
+use std::process::Output;
+use slmp::*;
+use slmp::testing::MockServer;
+
+async fn slmp_cli(server: &MockServer, args: &[&str]) -> Output {
+    let props = server.connection_props();
+    tokio::process::Command::new(env!("CARGO_BIN_EXE_slmp-cli"))
+        .args(["--ip", &props.ip, "--port", &props.port.to_string(), "--cpu", "R"])
+        .args(args)
+        .output().await.unwrap()
+}
+
+#[tokio::test]
+async fn exit_status() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    server.set_word(Device { device_type: DeviceType::D, address: 100 }, 7);
+
+    let output = slmp_cli(&server, &["read", "D100"]).await;
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "D100\t7\n");
+
+    // Invalid arguments found after connecting exit with 2 as well as the ones clap rejects.
+    for args in [&["read", "Q100"][..], &["read", "D100..M110"], &["write", "Q100", "1"], &["write", "D100", "x"]] {
+        let output = slmp_cli(&server, args).await;
+        assert_eq!(output.status.code(), Some(2), "{args:?}");
+    }
+
+    let output = slmp_cli(&server, &["--json", "read", "Q100"]).await;
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["endCode"], serde_json::Value::Null);
+}
@@ -0,0 +1,60 @@
+use slmp::*;
+use slmp::testing::MockServer;
+
+fn sd(address: usize) -> Device {
+    Device { device_type: DeviceType::SD, address }
+}
+
+const SM210: Device = Device { device_type: DeviceType::SM, address: 210 };
+
+#[tokio::test]
+async fn set_and_read_clock() {
+    let datetime = PLCDateTime { year: 2026, month: 10, day: 16, hour: 9, minute: 5, second: 30, day_of_week: 5 };
+    for cpu in [CPU::Q, CPU::R] {
+        let (server, client) = MockServer::start_connected(cpu).await.unwrap();
+        client.set_clock(datetime).await.unwrap();
+        assert!(server.bit(SM210).unwrap());
+        assert_eq!(client.read_clock().await.unwrap(), datetime);
+    }
+}
+
+#[tokio::test]
+async fn clock_encoding() {
+    let last = PLCDateTime { year: 2079, month: 12, day: 31, hour: 23, minute: 59, second: 59, day_of_week: 0 };
+
+    // Q/L: two BCD digits per byte, upper byte first, and the century with the day of week in SD213.
+    let (server, client) = MockServer::start_connected(CPU::Q).await.unwrap();
+    client.set_clock(last).await.unwrap();
+    let words: Vec<u16> = (210..214).map(|x| server.word(sd(x))).collect();
+    assert_eq!(words, [0x7912, 0x3123, 0x5959, 0x2000]);
+    assert_eq!(client.read_clock().await.unwrap(), last);
+
+    // A BCD digit over 9 is not a clock.
+    server.set_word(sd(211), 0x3A23);
+    assert_eq!(client.read_clock().await.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+
+    // R: a field per word in binary.
+    let (server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    client.set_clock(last).await.unwrap();
+    let words: Vec<u16> = (210..217).map(|x| server.word(sd(x))).collect();
+    assert_eq!(words, [2079, 12, 31, 23, 59, 59, 0]);
+    assert_eq!(client.read_clock().await.unwrap(), last);
+}
+
+#[tokio::test]
+async fn clock_year_range() {
+    for cpu in [CPU::Q, CPU::R] {
+        let (server, client) = MockServer::start_connected(cpu).await.unwrap();
+        let sent = client.transferred_bytes().0;
+        for year in [1979, 2080] {
+            let datetime = PLCDateTime { year, month: 1, day: 1, hour: 0, minute: 0, second: 0, day_of_week: 1 };
+            assert_eq!(client.set_clock(datetime).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+        }
+        assert_eq!(client.transferred_bytes().0, sent);
+        assert!(!server.bit(SM210).unwrap());
+
+        let first = PLCDateTime { year: 1980, month: 1, day: 1, hour: 0, minute: 0, second: 0, day_of_week: 2 };
+        client.set_clock(first).await.unwrap();
+        assert_eq!(client.read_clock().await.unwrap(), first);
+    }
+}
@@ -0,0 +1,466 @@
+//! Encoding of `TypedData` and parsing of monitor responses. No I/O, so it also runs under Miri: `cargo +nightly miri test --test data`.
+
+use slmp::*;
+
+#[test]
+fn typed_data_bytes() {
+    let mut bits = [false; 16];
+    bits[0] = true;
+    bits[9] = true;
+
+    let cases: [(TypedData, &[u8]); 13] = [
+        (TypedData::Bool(true), &[0x01, 0x00]),
+        (TypedData::Bool(false), &[0x00, 0x00]),
+        (TypedData::BitArray16(bits), &[0x01, 0x02]),
+        (TypedData::U16(0x1234), &[0x34, 0x12]),
+        (TypedData::I16(-2), &[0xfe, 0xff]),
+        (TypedData::U32(0x1234_5678), &[0x78, 0x56, 0x34, 0x12]),
+        (TypedData::I32(-2), &[0xfe, 0xff, 0xff, 0xff]),
+        (TypedData::F32(1.5), &[0x00, 0x00, 0xc0, 0x3f]),
+        (TypedData::F64(-2.0), &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0]),
+        (TypedData::U64(0x0123_4567_89ab_cdef), &[0xef, 0xcd, 0xab, 0x89, 0x67, 0x45, 0x23, 0x01]),
+        (TypedData::I64(-2), &[0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]),
+        (TypedData::from(("AB", 2)), &[0x41, 0x42, 0x00, 0x00]),
+        (TypedData::from(("ABC", 2)), &[0x41, 0x42, 0x43, 0x00]),
+    ];
+
+    for (data, bytes) in cases {
+        assert_eq!(data.to_bytes(), bytes, "{data:?}");
+        assert_eq!(TypedData::from((&data.to_bytes()[..], data.get_type())), data);
+    }
+}
+
+#[test]
+fn monitor_parse_never_panics() {
+    let device = |address, data_type| TypedDevice { device: Device { device_type: DeviceType::D, address }, data_type };
+    let devices = [
+        device(0, DataType::U32),
+        device(2, DataType::Bool),
+        device(3, DataType::String(5)),
+        device(8, DataType::F64),
+        device(12, DataType::U16),
+    ];
+    let list = MonitorList::try_from(&devices[..]).unwrap();
+    // 4 + 2 (a word for Bool) + 10 + 8 + 2
+    let bytelen = 26;
+
+    // A simple LCG, enough to vary the bytes.
+    let mut seed: u32 = 0x1234_5678;
+    let mut next = || { seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345); (seed >> 16) as u8 };
+
+    for len in 0..64 {
+        for _ in 0..16 {
+            let data: Vec<u8> = (0..len).map(|_| next()).collect();
+            match list.parse(&data) {
+                Ok(ret) => {
+                    assert_eq!(len, bytelen);
+                    assert_eq!(ret.iter().map(|x| x.device).collect::<Vec<_>>(), devices.map(|x| x.device));
+                }
+                Err(e) => {
+                    assert_ne!(len, bytelen);
+                    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+                }
+            }
+        }
+    }
+
+    // Any list, including an empty one
+    for n in 0..devices.len() {
+        let list = MonitorList::try_from(&devices[..n]).unwrap();
+        for len in 0..32 {
+            let data: Vec<u8> = (0..len).map(|_| next()).collect();
+            let _ = list.parse(&data);
+        }
+    }
+}
+
+#[test]
+fn monitor_list_capacity() {
+    let device = |address, data_type| TypedDevice { device: Device { device_type: DeviceType::D, address }, data_type };
+
+    // Pushed one by one, the list is the same as the one built at once.
+    let devices = [device(8, DataType::F64), device(0, DataType::U32), device(12, DataType::U16), device(0, DataType::U32), device(3, DataType::String(5))];
+    let mut list = MonitorList::new();
+    for x in devices {
+        list.try_push(x).unwrap();
+    }
+    assert_eq!(list, MonitorList::try_from(&devices[..]).unwrap());
+    assert_eq!(list.devices(), devices);
+    // U16 + String(5) as 5 words + F64 as 4 words + U32
+    assert_eq!(list.access_points(), 11);
+
+    let mut list = MonitorList::try_from(&[device(0, DataType::F64)][..]).unwrap();
+    let words: Vec<TypedDevice> = (0..MONITOR_MAX_ACCESS_POINTS - 4).map(|i| device(100 + i, DataType::U16)).collect();
+    list.try_extend(words.iter().copied()).unwrap();
+    assert_eq!(list.access_points(), MONITOR_MAX_ACCESS_POINTS);
+
+    // A repeated device takes no points.
+    list.try_push(words[0]).unwrap();
+    let e = list.try_push(device(1000, DataType::U16)).unwrap_err();
+    assert_eq!(e, MonitorListFull { device: device(1000, DataType::U16), access_points: MONITOR_MAX_ACCESS_POINTS + 1, max_access_points: MONITOR_MAX_ACCESS_POINTS });
+
+    // A failed extension leaves the list as it was.
+    let before = list.clone();
+    assert!(list.try_extend([device(0, DataType::U32)]).is_err());
+    assert_eq!(list, before);
+
+    let too_many: Vec<TypedDevice> = (0..=MONITOR_MAX_ACCESS_POINTS).map(|i| device(i, DataType::U16)).collect();
+    let e: std::io::Error = MonitorList::try_from(&too_many[..]).unwrap_err().into();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(MonitorListFull::from_io_error(&e).map(|x| x.access_points), Some(MONITOR_MAX_ACCESS_POINTS + 1));
+}
+
+#[test]
+fn long_plc_string() {
+    let text: String = "0123456789".repeat(11);
+
+    let data = TypedData::from((text.as_str(), 60));
+    assert_eq!(data.get_type(), DataType::String(60));
+    assert_eq!(data.to_bytes().len(), 120);
+    assert_eq!(TypedData::from((&data.to_bytes()[..], DataType::String(60))), data);
+
+    let TypedData::String(s) = data else { unreachable!() };
+    assert_eq!(s.as_str(), text);
+    assert_eq!(&s.as_bytes()[110..], &[0; 10]);
+
+    assert!(PLCString::try_from_str(&"x".repeat(510), 255).is_ok());
+    assert_eq!(PLCString::try_from_str(&text, 54).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(PLCString::try_from_str("", 0).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+    // The query form of json payloads.
+    use serde::Deserialize;
+    let de = serde::de::value::StrDeserializer::<serde::de::value::Error>::new;
+    let query = format!("60{PLCSTRING_QUERY_SPLITTER}{text}");
+    assert_eq!(PLCString::deserialize(de(&query)).unwrap().as_str(), text);
+    assert_eq!(PLCString::deserialize(de("2#|#AB")).unwrap().as_bytes(), b"AB\0\0");
+    assert!(PLCString::deserialize(de("2#|#ABCDE")).is_err());
+    assert!(PLCString::deserialize(de("0#|#")).is_err());
+}
+
+#[test]
+fn string_encodings() {
+    let cases: [(&str, StringEncoding, DataType, &[u8]); 5] = [
+        ("aあ", StringEncoding::ShiftJis, DataType::String(3), &[0x61, 0x82, 0xa0, 0x00, 0x00, 0x00]),
+        ("AB~", StringEncoding::Ascii, DataType::AsciiString(2), &[0x41, 0x42, 0x7e, 0x00]),
+        ("Aé", StringEncoding::Utf16Le, DataType::WString(3), &[0x41, 0x00, 0xe9, 0x00, 0x00, 0x00]),
+        // A surrogate pair takes two words.
+        ("\u{1F600}", StringEncoding::Utf16Le, DataType::WString(2), &[0x3d, 0xd8, 0x00, 0xde]),
+        // A NUL byte inside a character does not end a WSTRING.
+        ("Ā", StringEncoding::Utf16Le, DataType::WString(1), &[0x00, 0x01]),
+    ];
+
+    for (text, encoding, data_type, bytes) in cases {
+        let (device_size, _) = data_type.as_string().unwrap();
+        let data = TypedData::from((text, device_size, encoding));
+        assert_eq!(data.get_type(), data_type, "{text}");
+        assert_eq!(data.to_bytes(), bytes, "{text}");
+
+        let TypedData::String(read) = TypedData::from((bytes, data_type)) else { unreachable!() };
+        assert_eq!(read.as_str(), text);
+        assert_eq!(read.encoding(), encoding);
+        assert_eq!(PLCString::encode(text, device_size, encoding).unwrap().as_bytes(), bytes);
+    }
+
+    // Default encoding.
+    assert_eq!(TypedData::from(("A", 1)).get_type(), DataType::String(1));
+    assert_eq!("wstring(10)".parse::<DataType>().unwrap(), DataType::WString(10));
+    assert_eq!("AsciiString(4)".parse::<DataType>().unwrap(), DataType::AsciiString(4));
+    assert!("Foo(4)".parse::<DataType>().is_err());
+
+    let invalid_input = |ret: std::io::Result<PLCString>| ret.unwrap_err().kind() == std::io::ErrorKind::InvalidInput;
+    assert!(invalid_input(PLCString::encode("é", 2, StringEncoding::Ascii)));
+    assert!(invalid_input(PLCString::encode("abc", 1, StringEncoding::Utf16Le)));
+
+    // Bytes outside ASCII are not guessed.
+    let TypedData::String(read) = TypedData::from((&[0x41, 0xe9][..], DataType::AsciiString(1))) else { unreachable!() };
+    assert_eq!(read.as_str(), "A\u{FFFD}");
+
+    use serde::Deserialize;
+    let de = serde::de::value::StrDeserializer::<serde::de::value::Error>::new;
+    let wstring = PLCString::deserialize(de("2:Utf16Le#|#Aé")).unwrap();
+    assert_eq!((wstring.as_str().as_ref(), wstring.encoding()), ("Aé", StringEncoding::Utf16Le));
+    assert!(PLCString::deserialize(de("2:Latin1#|#A")).is_err());
+}
+
+#[test]
+fn word_order_bytes() {
+    let cases: [(TypedData, &[u8], &[u8]); 6] = [
+        (TypedData::U32(0x1234_5678), &[0x78, 0x56, 0x34, 0x12], &[0x34, 0x12, 0x78, 0x56]),
+        (TypedData::I32(-2), &[0xfe, 0xff, 0xff, 0xff], &[0xff, 0xff, 0xfe, 0xff]),
+        (TypedData::F32(1.5), &[0x00, 0x00, 0xc0, 0x3f], &[0xc0, 0x3f, 0x00, 0x00]),
+        (TypedData::F64(-2.0), &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0], &[0x00, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+        (TypedData::I64(-2), &[0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff], &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0xff]),
+        // Single words are not changed.
+        (TypedData::U16(0x1234), &[0x34, 0x12], &[0x34, 0x12]),
+    ];
+
+    for (data, low_high, high_low) in cases {
+        assert_eq!(data.to_ordered_bytes(WordOrder::LowHigh), low_high, "{data:?}");
+        assert_eq!(data.to_ordered_bytes(WordOrder::HighLow), high_low, "{data:?}");
+        assert_eq!(TypedData::from((low_high, data.get_type(), WordOrder::LowHigh)), data);
+        assert_eq!(TypedData::from((high_low, data.get_type(), WordOrder::HighLow)), data);
+        assert_eq!(data.clone().with_word_order(WordOrder::HighLow).with_word_order(WordOrder::HighLow), data);
+    }
+}
+
+#[test]
+fn bcd() {
+    let cases: [(TypedData, &[u8]); 4] = [
+        (TypedData::Bcd16(1234), &[0x34, 0x12]),
+        (TypedData::Bcd16(9999), &[0x99, 0x99]),
+        (TypedData::Bcd32(12_345_678), &[0x78, 0x56, 0x34, 0x12]),
+        (TypedData::Bcd32(0), &[0x00, 0x00, 0x00, 0x00]),
+    ];
+    for (data, bytes) in cases {
+        assert_eq!(data.to_bytes(), bytes, "{data:?}");
+        assert_eq!(TypedData::decode(bytes, data.get_type()).unwrap(), data);
+        assert!(data.check_range().is_ok());
+    }
+
+    // Each nibble over 9, in the lowest and the highest digit.
+    for bytes in [[0x0a, 0x00], [0x00, 0xf0], [0x5b, 0x12]] {
+        assert_eq!(TypedData::decode(&bytes, DataType::Bcd16).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+    assert_eq!(TypedData::decode(&[0x00, 0x00, 0x00, 0xa0], DataType::Bcd32).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+
+    assert_eq!(TypedData::Bcd16(10_000).check_range().unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(TypedData::Bcd32(100_000_000).check_range().unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!("bcd32".parse::<DataType>().unwrap(), DataType::Bcd32);
+}
+
+#[test]
+fn typed_data_accessors() {
+    type Row = (Option<bool>, Option<u16>, Option<i16>, Option<u32>, Option<i32>, Option<u64>, Option<i64>, Option<f32>, Option<f64>, Option<f64>);
+    let accessors = |x: &TypedData| -> Row {
+        (x.as_bool(), x.as_u16(), x.as_i16(), x.as_u32(), x.as_i32(), x.as_u64(), x.as_i64(), x.as_f32(), x.as_f64(), x.value_as_f64())
+    };
+    let mut bits = [false; 16];
+    bits[1] = true;
+
+    let cases: [(TypedData, Row); 13] = [
+        (TypedData::Bool(true), (Some(true), None, None, None, None, None, None, None, None, Some(1.0))),
+        (TypedData::BitArray16(bits), (None, None, None, None, None, None, None, None, None, Some(2.0))),
+        (TypedData::U16(65535), (None, Some(65535), None, Some(65535), Some(65535), Some(65535), Some(65535), Some(65535.0), Some(65535.0), Some(65535.0))),
+        (TypedData::I16(-2), (None, None, Some(-2), None, Some(-2), None, Some(-2), Some(-2.0), Some(-2.0), Some(-2.0))),
+        (TypedData::U32(4_000_000_000), (None, None, None, Some(4_000_000_000), None, Some(4_000_000_000), Some(4_000_000_000), None, Some(4e9), Some(4e9))),
+        (TypedData::I32(-70_000), (None, None, None, None, Some(-70_000), None, Some(-70_000), None, Some(-70_000.0), Some(-70_000.0))),
+        (TypedData::U64(u64::MAX), (None, None, None, None, None, Some(u64::MAX), None, None, None, Some(u64::MAX as f64))),
+        (TypedData::I64(-2), (None, None, None, None, None, None, Some(-2), None, None, Some(-2.0))),
+        (TypedData::F32(1.5), (None, None, None, None, None, None, None, Some(1.5), Some(1.5), Some(1.5))),
+        (TypedData::F64(-2.5), (None, None, None, None, None, None, None, None, Some(-2.5), Some(-2.5))),
+        (TypedData::Bcd16(9999), (None, Some(9999), None, Some(9999), Some(9999), Some(9999), Some(9999), Some(9999.0), Some(9999.0), Some(9999.0))),
+        (TypedData::Bcd32(99_999_999), (None, None, None, Some(99_999_999), Some(99_999_999), Some(99_999_999), Some(99_999_999), None, Some(99_999_999.0), Some(99_999_999.0))),
+        (TypedData::from(("AB", 2)), (None, None, None, None, None, None, None, None, None, None)),
+    ];
+
+    for (data, row) in cases {
+        assert_eq!(accessors(&data), row, "{data:?}");
+        assert_eq!(data.as_str().is_some(), matches!(data, TypedData::String(_)));
+    }
+
+    // TryFrom takes only its own variant.
+    assert_eq!(u16::try_from(TypedData::U16(7)).unwrap(), 7);
+    assert_eq!(i64::try_from(TypedData::I64(-7)).unwrap(), -7);
+    assert_eq!(String::try_from(TypedData::from(("AB", 2))).unwrap(), "AB");
+    assert!(bool::try_from(TypedData::Bool(false)).is_ok_and(|x| !x));
+    let e = u32::try_from(TypedData::U16(7)).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(e.to_string(), "U16 is not U32");
+    assert_eq!(String::try_from(TypedData::F32(1.0)).unwrap_err().to_string(), "F32 is not String");
+    assert!(f64::try_from(TypedData::F32(1.0)).is_err());
+}
+
+#[test]
+fn device_ranges() {
+    let d = |address| Device { device_type: DeviceType::D, address };
+    let m = |address| Device { device_type: DeviceType::M, address };
+
+    assert_eq!(d(100) + 20, d(120));
+    assert_eq!(d(100).offset(5), Some(d(105)));
+    assert_eq!(d(Device::MAX_ADDRESS).offset(1), None);
+    assert_eq!(d(0).offset(usize::MAX), None);
+    assert!(std::panic::catch_unwind(|| d(Device::MAX_ADDRESS) + 1).is_err());
+
+    assert_eq!(Device::range(d(100), 3).collect::<Vec<_>>(), [d(100), d(101), d(102)]);
+    assert_eq!(Device::range(d(100), 0).count(), 0);
+    // Iteration stops at the last address instead of wrapping.
+    assert_eq!(Device::range(d(Device::MAX_ADDRESS - 1), 5).collect::<Vec<_>>(), [d(Device::MAX_ADDRESS - 1), d(Device::MAX_ADDRESS)]);
+    assert_eq!(Device::range(d(Device::MAX_ADDRESS - 1), 5).len(), 2);
+
+    let typed = |range: DeviceRange, data_type| range.typed_devices(data_type).into_iter().map(|x| (x.device, x.data_type)).collect::<Vec<_>>();
+    assert_eq!(typed(Device::range(d(100), 3), DataType::U16), [(d(100), DataType::U16), (d(101), DataType::U16), (d(102), DataType::U16)]);
+    // D100-D101 and D102-D103; D104 alone does not hold a U32.
+    assert_eq!(typed(Device::range(d(100), 5), DataType::U32), [(d(100), DataType::U32), (d(102), DataType::U32)]);
+    assert_eq!(typed(Device::range(d(0), 8), DataType::F64), [(d(0), DataType::F64), (d(4), DataType::F64)]);
+    assert_eq!(typed(Device::range(d(0), 10), DataType::String(4)), [(d(0), DataType::String(4)), (d(4), DataType::String(4))]);
+    // A word of a bit device holds 16 points.
+    assert_eq!(typed(Device::range(m(0), 64), DataType::U32), [(m(0), DataType::U32), (m(32), DataType::U32)]);
+    assert_eq!(typed(Device::range(m(0), 2), DataType::Bool), [(m(0), DataType::Bool), (m(1), DataType::Bool)]);
+    assert!(typed(Device::range(m(0), 15), DataType::U16).is_empty());
+}
+
+#[test]
+fn device_limits() {
+    let d = |address| Device { device_type: DeviceType::D, address };
+
+    assert!(d(4_910_079).validate(&CPU::Q).is_ok());
+    let e = d(9_000_000).validate(&CPU::Q).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(e.to_string(), "D9000000 is out of the device range (max: D4910079)");
+    assert!(d(9_000_000).validate(&CPU::R).is_ok());
+
+    let limits = DeviceLimits::new(CPU::Q);
+    assert_eq!(limits.max_address(DeviceType::X), 0x1fff);
+    assert!(limits.check(d(4_910_078), 2).is_ok());
+    assert!(limits.check(d(4_910_078), 3).is_err());
+    assert!(limits.check(d(0), usize::MAX).is_err());
+    // Hexadecimal devices are shown as such.
+    let x = Device { device_type: DeviceType::X, address: 0x1ff0 };
+    assert_eq!(limits.check(x, 32).unwrap_err().to_string(), "X1FF0 to X200F is out of the device range (max: X1FFF)");
+
+    let limits = limits.with_max_address(DeviceType::ZR, 65535);
+    assert_eq!(limits.max_address(DeviceType::ZR), 65535);
+    // Capped at the largest address a request can carry.
+    assert_eq!(limits.with_max_address(DeviceType::ZR, usize::MAX).max_address(DeviceType::ZR), Device::MAX_ADDRESS);
+}
+
+#[test]
+fn device_type_codes() {
+    let device_types = [
+        DeviceType::X, DeviceType::Y, DeviceType::M, DeviceType::L, DeviceType::F, DeviceType::V, DeviceType::B,
+        DeviceType::D, DeviceType::W, DeviceType::S, DeviceType::Z, DeviceType::R, DeviceType::TS, DeviceType::TC,
+        DeviceType::TN, DeviceType::SS, DeviceType::SC, DeviceType::SN, DeviceType::CS, DeviceType::CC, DeviceType::CN,
+        DeviceType::SB, DeviceType::SD, DeviceType::SM, DeviceType::SW, DeviceType::DX, DeviceType::DY, DeviceType::ZR,
+    ];
+    for x in device_types {
+        assert_eq!(DeviceType::from_code(x.to_code()), Some(x));
+        assert_ne!(x.is_bit_device(), x.is_word_device());
+    }
+    assert_eq!(DeviceType::from_code(0x00), None);
+
+    assert!(DeviceType::M.is_bit_device());
+    assert!(DeviceType::TS.is_bit_device());
+    assert!(DeviceType::TN.is_word_device());
+    assert!(DeviceType::ZR.is_word_device());
+}
+
+#[test]
+fn hashable_devices() {
+    use std::collections::{HashMap, HashSet};
+
+    let d = |address| Device { device_type: DeviceType::D, address };
+
+    let mut latest: HashMap<Device, TypedData> = HashMap::new();
+    latest.insert(d(0), TypedData::U16(1));
+    latest.insert(d(0), TypedData::U16(2));
+    assert_eq!(latest.len(), 1);
+    assert_eq!(latest[&d(0)], TypedData::U16(2));
+
+    let typed: HashSet<TypedDevice> = [TypedDevice { device: d(0), data_type: DataType::U16 }, TypedDevice { device: d(0), data_type: DataType::U32 }].into();
+    assert_eq!(typed.len(), 2);
+    let addr: std::net::SocketAddr = "127.0.0.1:5000".parse().unwrap();
+    let monitored_device = MonitoredDevice { socket_addr: addr, route: Route::own_station(), monitor_device: TypedDevice { device: d(0), data_type: DataType::U16 } };
+    let monitored: HashSet<MonitoredDevice> = [monitored_device.clone(), monitored_device].into();
+    assert_eq!(monitored.len(), 1);
+
+    // Floats compare by their bits, so a NaN equals itself and -0.0 differs from 0.0.
+    assert!(TypedData::F32(f32::NAN).total_cmp(&TypedData::F32(f32::NAN)).is_eq());
+    assert!(TypedData::F64(0.0).total_cmp(&TypedData::F64(-0.0)).is_gt());
+    assert!(TypedData::U16(1).total_cmp(&TypedData::U16(2)).is_lt());
+    // Strings compare by text, whatever their sizes.
+    assert!(TypedData::from(("AB", 2)).total_cmp(&TypedData::from(("AB", 4))).is_eq());
+
+    let values: HashSet<OrderedDeviceData> = [
+        DeviceData { device: d(0), data: TypedData::F32(f32::NAN) },
+        DeviceData { device: d(0), data: TypedData::F32(f32::NAN) },
+        DeviceData { device: d(2), data: TypedData::from(("AB", 2)) },
+        DeviceData { device: d(2), data: TypedData::from(("AB", 4)) },
+        DeviceData { device: d(4), data: TypedData::U16(1) },
+    ].into_iter().map(OrderedDeviceData::from).collect();
+    assert_eq!(values.len(), 3);
+
+    let mut sorted: Vec<OrderedDeviceData> = values.into_iter().collect();
+    sorted.sort();
+    assert_eq!(sorted.into_iter().map(|x| DeviceData::from(x).device).collect::<Vec<_>>(), [d(0), d(2), d(4)]);
+}
+
+#[test]
+fn snapshot_diff() {
+    let d = |address| Device { device_type: DeviceType::D, address };
+    let m = |address| Device { device_type: DeviceType::M, address };
+    let word_area = |address, words: &[u16]| SnapshotArea { access_type: AccessType::Word, start_device: d(address), size: words.len(), words: words.to_vec() };
+
+    let before = DeviceSnapshot {
+        cpu: CPU::R,
+        areas: vec![
+            word_area(0, &[1, 2, 0x0000, 0x3f80]),
+            SnapshotArea { access_type: AccessType::Bit, start_device: m(0), size: 20, words: vec![0x0001, 0x0000] },
+        ],
+    };
+    let after = DeviceSnapshot {
+        cpu: CPU::R,
+        areas: vec![
+            word_area(1, &[2, 0x0000, 0x4000, 9]),
+            SnapshotArea { access_type: AccessType::Bit, start_device: m(0), size: 20, words: vec![0x0000, 0x0008] },
+        ],
+    };
+
+    // Raw words and points in device order (M before D); D0 and D4 are in only one of the snapshots.
+    assert_eq!(before.diff(&after), [
+        DeviceChange { device: m(0), before: Some(TypedData::Bool(true)), after: Some(TypedData::Bool(false)) },
+        DeviceChange { device: m(19), before: Some(TypedData::Bool(false)), after: Some(TypedData::Bool(true)) },
+        DeviceChange { device: d(0), before: Some(TypedData::U16(1)), after: None },
+        DeviceChange { device: d(3), before: Some(TypedData::U16(0x3f80)), after: Some(TypedData::U16(0x4000)) },
+        DeviceChange { device: d(4), before: None, after: Some(TypedData::U16(9)) },
+    ]);
+    assert_eq!(before.diff(&after).iter().filter(|x| x.is_extent_change()).count(), 2);
+    assert!(before.diff(&before).is_empty());
+
+    // D2 and D3 as a single F32.
+    let devices = [
+        TypedDevice { device: d(0), data_type: DataType::U32 },
+        TypedDevice { device: d(1), data_type: DataType::U16 },
+        TypedDevice { device: d(2), data_type: DataType::F32 },
+        TypedDevice { device: d(100), data_type: DataType::U16 },
+        TypedDevice { device: m(19), data_type: DataType::Bool },
+    ];
+    assert_eq!(before.diff_typed(&after, &devices, WordOrder::LowHigh), [
+        DeviceChange { device: d(0), before: Some(TypedData::U32(0x0002_0001)), after: None },
+        DeviceChange { device: d(2), before: Some(TypedData::F32(1.0)), after: Some(TypedData::F32(2.0)) },
+        DeviceChange { device: m(19), before: Some(TypedData::Bool(false)), after: Some(TypedData::Bool(true)) },
+    ]);
+    assert_eq!(before.value(TypedDevice { device: d(2), data_type: DataType::F32 }, WordOrder::HighLow), Some(TypedData::F32(f32::from_bits(0x0000_3f80))));
+    assert_eq!(before.value(TypedDevice { device: d(3), data_type: DataType::U32 }, WordOrder::LowHigh), None);
+
+    // A word that is not BCD has no BCD value.
+    assert_eq!(before.value(TypedDevice { device: d(1), data_type: DataType::Bcd16 }, WordOrder::LowHigh), Some(TypedData::Bcd16(2)));
+    assert_eq!(before.value(TypedDevice { device: d(3), data_type: DataType::Bcd16 }, WordOrder::LowHigh), None);
+}
+
+#[test]
+fn cpu_series_from_config() {
+    for cpu in [CPU::Q, CPU::R, CPU::L, CPU::F, CPU::A] {
+        assert_eq!(serde_json::from_str::<CPU>(&serde_json::to_string(&cpu).unwrap()).unwrap(), cpu);
+    }
+    assert_eq!(serde_json::from_str::<CPU>("\"A\"").unwrap(), CPU::A);
+    let e = serde_json::from_str::<CPU>("\"QnA\"").unwrap_err();
+    assert!(e.to_string().contains("unknown variant `QnA`"));
+
+    // Command line arguments take the same names.
+    assert_eq!("f".parse::<CPU>().unwrap(), CPU::F);
+    assert_eq!("a".parse::<CPU>().unwrap(), CPU::A);
+    assert_eq!("QnA".parse::<CPU>().unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn typed_data_display() {
+    assert_eq!(TypedData::Bool(true).to_string(), "true");
+    assert_eq!(TypedData::I16(-5).to_string(), "-5");
+    assert_eq!(TypedData::Bcd16(1234).to_string(), "1234");
+    assert_eq!(TypedData::F32(0.5).to_string(), "0.5");
+    assert_eq!(TypedData::from(("ABC", 3)).to_string(), "ABC");
+    let mut bits = [false; 16];
+    bits[0] = true;
+    bits[15] = true;
+    assert_eq!(TypedData::BitArray16(bits).to_string(), "1000000000000001");
+}
@@ -0,0 +1,135 @@
+use slmp::*;
+use slmp::frame::SlmpCommandPacket;
+use slmp::testing::MockServer;
+
+fn sd(address: usize) -> Device {
+    Device { device_type: DeviceType::SD, address }
+}
+
+const SM50: Device = Device { device_type: DeviceType::SM, address: 50 };
+
+#[tokio::test]
+async fn no_error() {
+    for cpu in [CPU::Q, CPU::R] {
+        let (_server, client) = MockServer::start_connected(cpu).await.unwrap();
+        assert!(client.read_error_history(16).await.unwrap().is_empty());
+    }
+}
+
+#[tokio::test]
+async fn ql_latest_error() {
+    let (server, client) = MockServer::start_connected(CPU::Q).await.unwrap();
+    // 2026-10-16 09:05:30 in BCD, then the error information category and the individual information.
+    let words: [u16; 16] = [0x1810, 0x2610, 0x1609, 0x0530, 0x0001, 0x0002, 0x0100, 0x0200, 0x0300, 0, 0, 0, 0, 0, 0, 0x0fff];
+    for (i, &x) in words.iter().enumerate() {
+        server.set_word(sd(i), x);
+    }
+    // Registers past SD15 are not part of the Q/L error information.
+    server.set_word(sd(16), 0x2000);
+
+    // Q/L keep the latest error only, however many entries are requested.
+    let errors = client.read_error_history(16).await.unwrap();
+    assert_eq!(errors, [PLCErrorRecord {
+        code: 0x1810,
+        occurred_at: Some(PLCDateTime { year: 2026, month: 10, day: 16, hour: 9, minute: 5, second: 30, day_of_week: 5 }),
+        detail: words[4..16].to_vec(),
+    }]);
+
+    // Years 80..99 are in the 1900s.
+    server.set_word(sd(1), 0x9912);
+    server.set_word(sd(2), 0x3123);
+    let errors = client.read_error_history(16).await.unwrap();
+    assert_eq!(errors[0].occurred_at.unwrap().year, 1999);
+    assert_eq!(errors[0].occurred_at.unwrap().day_of_week, 5);
+
+    // A time that is not BCD leaves the error without its time.
+    server.set_word(sd(2), 0x3A23);
+    let errors = client.read_error_history(16).await.unwrap();
+    assert_eq!(errors[0].code, 0x1810);
+    assert_eq!(errors[0].occurred_at, None);
+
+    assert!(client.read_error_history(0).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn r_error_history() {
+    let (server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let occurred_at = PLCDateTime { year: 2026, month: 10, day: 16, hour: 9, minute: 5, second: 30, day_of_week: 5 };
+    let words: [u16; 8] = [0x2220, 2026, 10, 16, 9, 5, 30, 5];
+    for (i, &x) in words.iter().enumerate() {
+        server.set_word(sd(i), x);
+    }
+    // The errors occurring, the latest among them. Zeros are empty slots.
+    for (i, x) in [0x1810, 0x2220, 0, 0x3300].into_iter().enumerate() {
+        server.set_word(sd(10 + i), x);
+    }
+    server.set_word(sd(25), 0x4400);
+
+    let errors = client.read_error_history(16).await.unwrap();
+    let codes: Vec<u16> = errors.iter().map(|x| x.code).collect();
+    assert_eq!(codes, [0x2220, 0x1810, 0x3300, 0x4400]);
+    assert_eq!(errors[0].occurred_at, Some(occurred_at));
+    assert!(errors[1..].iter().all(|x| x.occurred_at.is_none()));
+    assert!(errors.iter().all(|x| x.detail.is_empty()));
+
+    let errors = client.read_error_history(2).await.unwrap();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[1].code, 0x1810);
+}
+
+/// Device accesses of the requests sent by `client.clear_error()`, as (command, device, data written).
+async fn clear_error_requests(cpu: CPU, server: &MockServer) -> Vec<(u16, Device, Vec<u8>)> {
+    let props = server.connection_props();
+    let transport = RecordingTransport::new(TcpTransport::new(&props));
+    let log = transport.log();
+    let client = SLMPClient::with_transport(props, transport);
+    client.connect().await.unwrap();
+    client.clear_error().await.unwrap();
+
+    let device_len: usize = if cpu == CPU::R { 6 } else { 4 };
+    let log = log.lock().unwrap().clone();
+    log.exchanges.iter()
+        .map(|x| SlmpCommandPacket::try_from(&x.request[..]).unwrap())
+        .map(|x| (x.command, x.devices().unwrap()[0].device, x.data[device_len + 2..].to_vec()))
+        .collect()
+}
+
+#[tokio::test]
+async fn clear_ql_error() {
+    let (server, client) = MockServer::start_connected(CPU::Q).await.unwrap();
+    server.set_word(sd(0), 0x1810);
+    server.set_bit(SM50, true).unwrap();
+
+    // The latest error code goes to SD50, and SM50 is turned OFF and ON for the rising edge.
+    let requests = clear_error_requests(CPU::Q, &server).await;
+    assert_eq!(requests, [
+        (0x0401, sd(0), vec![]),
+        (0x1401, sd(50), vec![0x10, 0x18]),
+        (0x1401, SM50, vec![0x00]),
+        (0x1401, SM50, vec![0x10]),
+    ]);
+    assert_eq!(server.word(sd(50)), 0x1810);
+    assert!(server.bit(SM50).unwrap());
+
+    // Nothing to clear without an error.
+    server.set_word(sd(0), 0);
+    server.set_word(sd(50), 0);
+    client.clear_error().await.unwrap();
+    assert_eq!(server.word(sd(50)), 0);
+    assert_eq!(clear_error_requests(CPU::Q, &server).await.len(), 1);
+}
+
+#[tokio::test]
+async fn clear_r_error() {
+    let (server, _client) = MockServer::start_connected(CPU::R).await.unwrap();
+    server.set_word(sd(0), 0x2220);
+
+    // R/F clear every continuation error by SM50 alone.
+    let requests = clear_error_requests(CPU::R, &server).await;
+    assert_eq!(requests, [
+        (0x1401, SM50, vec![0x00]),
+        (0x1401, SM50, vec![0x10]),
+    ]);
+    assert_eq!(server.word(sd(50)), 0);
+    assert!(server.bit(SM50).unwrap());
+}
@@ -0,0 +1,121 @@
+//! Node Search and IP Address Set against a node simulated on a UDP socket of the loopback.
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use slmp::discovery::{self, IpSettings, NodeInfo, NodeProtocol};
+use slmp::frame::SlmpCommandPacket;
+use tokio::net::UdpSocket;
+
+const MAC: [u8; 6] = [0x00, 0x26, 0x92, 0x01, 0x02, 0x03];
+
+fn reversed(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().rev().copied().collect()
+}
+
+/// Response frame of the serial No. of `request` with `data` after the end code.
+fn response(request: &SlmpCommandPacket, end_code: u16, data: &[u8]) -> Vec<u8> {
+    let mut frame: Vec<u8> = vec![0xD4, 0x00];
+    frame.extend(request.serial_id.to_le_bytes());
+    frame.extend([0x00, 0x00, request.network_id, request.pc_id]);
+    frame.extend(request.io_id.to_le_bytes());
+    frame.push(request.area_id);
+    frame.extend(((data.len() + 2) as u16).to_le_bytes());
+    frame.extend(end_code.to_le_bytes());
+    frame.extend(data);
+    frame
+}
+
+fn node_search_data(request: &SlmpCommandPacket) -> Vec<u8> {
+    // Client MAC and IP address as requested.
+    let mut data: Vec<u8> = request.data[..11].to_vec();
+    data.extend(reversed(&MAC));
+    data.push(4);
+    data.extend(reversed(&[192, 168, 3, 39]));
+    data.extend(reversed(&[255, 255, 255, 0]));
+    data.extend(reversed(&[192, 168, 3, 254]));
+    data.push(4);
+    data.extend(b"PLC1");
+    data.extend(0x5A4Du16.to_le_bytes());
+    data.extend(0x0000_4800u32.to_le_bytes());
+    data.extend(0x0003u16.to_le_bytes());
+    // Not connected with a client.
+    data.push(4);
+    data.extend([0; 4]);
+    data.extend(0u16.to_le_bytes());
+    data.extend(0u16.to_le_bytes());
+    data.extend(5007u16.to_le_bytes());
+    data.push(1);
+    data
+}
+
+/// A node answering one request, passing the request to `check`.
+async fn node<F>(answer: F) -> SocketAddr
+where F: FnOnce(SlmpCommandPacket) -> Vec<Vec<u8>> + Send + 'static
+{
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = socket.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut buf = [0u8; 2048];
+        let (len, client) = socket.recv_from(&mut buf).await.unwrap();
+        let request = SlmpCommandPacket::try_from(&buf[..len]).unwrap();
+        for frame in answer(request) {
+            socket.send_to(&frame, client).await.unwrap();
+        }
+    });
+    addr
+}
+
+#[tokio::test]
+async fn node_search() {
+    let addr = node(|request| {
+        assert_eq!(request.command_name(), "NodeSearch");
+        assert_eq!(&request.data[6..11], [4, 1, 0, 0, 127]);
+        let data = node_search_data(&request);
+        // Once more, and garbage: a node is listed once.
+        vec![response(&request, 0, &data), vec![0x00; 3], response(&request, 0, &data)]
+    }).await;
+
+    let nodes = discovery::search_at(Ipv4Addr::LOCALHOST, addr, Duration::from_millis(200)).await.unwrap();
+    assert_eq!(nodes, [NodeInfo {
+        mac: MAC,
+        ip: Ipv4Addr::new(192, 168, 3, 39),
+        subnet_mask: Ipv4Addr::new(255, 255, 255, 0),
+        default_gateway: Ipv4Addr::new(192, 168, 3, 254),
+        host_name: String::from("PLC1"),
+        vendor_code: 0x5A4D,
+        model_code: 0x4800,
+        equipment_version: 3,
+        status: 0,
+        port: 5007,
+        protocol: NodeProtocol::Tcp,
+    }]);
+    let props = nodes[0].connection_props(slmp::CPU::R);
+    assert_eq!((props.ip.as_str(), props.port), ("192.168.3.39", 5007));
+
+    // No nodes.
+    let silent = node(|_| Vec::new()).await;
+    assert!(discovery::search_at(Ipv4Addr::LOCALHOST, silent, Duration::from_millis(50)).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn ip_address_set() {
+    let settings = IpSettings { ip: Ipv4Addr::new(192, 168, 3, 40), subnet_mask: Ipv4Addr::new(255, 255, 255, 0), default_gateway: Ipv4Addr::new(192, 168, 3, 254) };
+
+    let addr = node(|request| {
+        assert_eq!(request.command_name(), "IPAddressSet");
+        assert_eq!(&request.data[11..17], reversed(&MAC));
+        assert_eq!(&request.data[17..22], [4, 40, 3, 168, 192]);
+        assert_eq!(&request.data[22..30], [0, 255, 255, 255, 254, 3, 168, 192]);
+        let data = request.data[..6].to_vec();
+        vec![response(&request, 0, &data)]
+    }).await;
+    discovery::set_ip_address_at(Ipv4Addr::LOCALHOST, addr, MAC, settings, Duration::from_millis(200)).await.unwrap();
+
+    let addr = node(|request| vec![response(&request, 0xC059, &[])]).await;
+    let err = discovery::set_ip_address_at(Ipv4Addr::LOCALHOST, addr, MAC, settings, Duration::from_millis(200)).await.unwrap_err();
+    assert_eq!(slmp::SLMPEndCode::from_io_error(&err), Some(slmp::SLMPEndCode(0xC059)));
+
+    let silent = node(|_| Vec::new()).await;
+    let err = discovery::set_ip_address_at(Ipv4Addr::LOCALHOST, silent, MAC, settings, Duration::from_millis(50)).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+}
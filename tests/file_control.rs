@@ -0,0 +1,268 @@
+use std::sync::{Arc, Mutex};
+use slmp::*;
+use slmp::frame::SlmpCommandPacket;
+use slmp::testing::MockServer;
+
+#[tokio::test]
+async fn upload_download_round_trip() {
+    for (cpu, drive) in [
+        (CPU::R, FileDrive::R(FileDriveForR::SDMemory)),
+        (CPU::Q, FileDrive::QL(FileDriveForQL::SDMemory)),
+    ] {
+        let (server, client) = MockServer::start_connected(cpu).await.unwrap();
+
+        // Larger than a single read/write request
+        let data: Vec<u8> = (0..5000).map(|i| (i % 251) as u8).collect();
+        client.upload_file(drive, "RECIPE.CSV", &data, false).await.unwrap();
+        assert_eq!(server.file(drive, "RECIPE.CSV").unwrap(), data);
+
+        let downloaded = client.download_file(drive, "RECIPE.CSV").await.unwrap();
+        assert_eq!(downloaded, data);
+
+        // Exactly one request of data
+        let data = vec![0x55; 1920];
+        client.upload_file(drive, "FULL.BIN", &data, false).await.unwrap();
+        assert_eq!(client.download_file(drive, "FULL.BIN").await.unwrap(), data);
+    }
+}
+
+#[tokio::test]
+async fn no_file_access_on_fx5() {
+    let (_server, client) = MockServer::start_connected(CPU::F).await.unwrap();
+    for drive in [FileDrive::R(FileDriveForR::SDMemory), FileDrive::QL(FileDriveForQL::SDMemory)] {
+        let e = client.upload_file(drive, "RECIPE.CSV", b"data", false).await.unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::Unsupported);
+        assert_eq!(client.list_files(drive, 0, 10).await.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+    }
+    assert_eq!(client.transferred_bytes().0, 0);
+}
+
+#[test]
+fn drives_by_number() {
+    assert_eq!(FileDrive::from_drive_no(&CPU::R, 2), Some(FileDrive::R(FileDriveForR::SDMemory)));
+    assert_eq!(FileDrive::from_drive_no(&CPU::Q, 4), Some(FileDrive::QL(FileDriveForQL::DefaultROM)));
+    assert_eq!(FileDrive::from_drive_no(&CPU::L, 0), Some(FileDrive::QL(FileDriveForQL::ProgramMemory)));
+    assert_eq!(FileDrive::from_drive_no(&CPU::R, 4), None);
+    assert_eq!(FileDrive::from_drive_no(&CPU::F, 2), None);
+}
+
+#[tokio::test]
+async fn upload_overwrite() {
+    let drive = FileDrive::R(FileDriveForR::DataMemory);
+    let (server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    server.insert_file(drive, "設定.DAT", b"old");
+
+    let err = client.upload_file(drive, "設定.DAT", b"new data", false).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+    assert_eq!(SLMPEndCode::from_io_error(&err), Some(SLMPEndCode::FILE_ALREADY_EXISTS));
+
+    client.upload_file(drive, "設定.DAT", b"new data", true).await.unwrap();
+    assert_eq!(client.download_file(drive, "設定.DAT").await.unwrap(), b"new data");
+}
+
+#[tokio::test]
+async fn list_copy_delete() {
+    for (cpu, drive) in [
+        (CPU::R, FileDrive::R(FileDriveForR::SDMemory)),
+        (CPU::Q, FileDrive::QL(FileDriveForQL::SDMemory)),
+    ] {
+        let (_server, client) = MockServer::start_connected(cpu).await.unwrap();
+
+        for i in 0..40 {
+            client.upload_file(drive, &format!("F{i:02}.DAT"), &[i; 3], false).await.unwrap();
+        }
+
+        let files = client.list_files(drive, 0, 100).await.unwrap();
+        assert_eq!(files.len(), 40);
+        assert_eq!(files[0].name, "F00.DAT");
+        assert_eq!(files[39].name, "F39.DAT");
+        assert_eq!(files[39].size, 3);
+
+        client.copy_file(drive, "F01.DAT", drive, "COPY.DAT").await.unwrap();
+        assert_eq!(client.download_file(drive, "COPY.DAT").await.unwrap(), [1; 3]);
+
+        client.delete_file(drive, "COPY.DAT").await.unwrap();
+        let err = client.download_file(drive, "COPY.DAT").await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        let err = client.delete_file(drive, "COPY.DAT").await.unwrap_err();
+        assert_eq!(SLMPEndCode::from_io_error(&err), Some(SLMPEndCode::FILE_NOT_FOUND));
+    }
+}
+
+#[tokio::test]
+async fn download_file_requests() {
+    let drive = FileDrive::R(FileDriveForR::SDMemory);
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let data: Vec<u8> = (0..2 * 1920 + 10).map(|i| (i % 251) as u8).collect();
+    server.insert_file(drive, "RECIPE.CSV", &data);
+    server.insert_file(drive, "EMPTY.CSV", &[]);
+
+    let props = server.connection_props();
+    let transport = RecordingTransport::new(TcpTransport::new(&props));
+    let log = transport.log();
+    let client = SLMPClient::with_transport(props, transport);
+    client.connect().await.unwrap();
+
+    assert_eq!(client.download_file(drive, "RECIPE.CSV").await.unwrap(), data);
+    assert!(client.download_file(drive, "EMPTY.CSV").await.unwrap().is_empty());
+
+    // Open, read by 1920 bytes until a short chunk, and close by the file pointer opened.
+    let log = log.lock().unwrap().clone();
+    let requests: Vec<SlmpCommandPacket> = log.exchanges.iter().map(|x| SlmpCommandPacket::try_from(&x.request[..]).unwrap()).collect();
+    let commands: Vec<u16> = requests.iter().map(|x| x.command).collect();
+    assert_eq!(commands, [0x1827, 0x1828, 0x1828, 0x1828, 0x182A, 0x1827, 0x1828, 0x182A]);
+
+    let opened = log.exchanges[0].response.as_ref().unwrap();
+    let file_pointer = &opened[opened.len() - 2..];
+    let reads: Vec<(&[u8], u32, u16)> = requests[1..4].iter()
+        .map(|x| (&x.data[0..2], u32::from_le_bytes(x.data[2..6].try_into().unwrap()), u16::from_le_bytes([x.data[6], x.data[7]])))
+        .collect();
+    assert_eq!(reads, [(file_pointer, 0, 1920), (file_pointer, 1920, 1920), (file_pointer, 3840, 1920)]);
+    assert_eq!(&requests[4].data[..2], file_pointer);
+}
+
+/// A server answering each request by `respond`, which gives the end code and the response data of a command.
+/// The commands received are kept in the returned list.
+async fn canned_server<F>(cpu: CPU, respond: F) -> (SLMPClient, Arc<Mutex<Vec<u16>>>)
+    where F: Fn(u16) -> (u16, Vec<u8>) + Send + 'static
+{
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let commands = received.clone();
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut header = [0u8; 13];
+        while stream.read_exact(&mut header).await.is_ok() {
+            let mut data = vec![0u8; u16::from_le_bytes([header[11], header[12]]) as usize];
+            stream.read_exact(&mut data).await.unwrap();
+            let command = u16::from_le_bytes([data[2], data[3]]);
+            commands.lock().unwrap().push(command);
+
+            let (end_code, body) = respond(command);
+            let mut response = header[..11].to_vec();
+            response[0] = 0xD4;
+            response.extend((2 + body.len() as u16).to_le_bytes());
+            response.extend(end_code.to_le_bytes());
+            response.extend(body);
+            stream.write_all(&response).await.unwrap();
+        }
+    });
+
+    let props = SLMP4EConnectionProps {
+        ip: addr.ip().to_string(),
+        port: addr.port(),
+        cpu,
+        serial_id: 0x0001,
+        network_id: 0x00,
+        pc_id: 0xff,
+        io_id: 0x03ff,
+        area_id: 0x00,
+        cpu_timer: 0x0010,
+        remote_password: None,
+    };
+    let client = SLMPClient::new(props);
+    client.connect().await.unwrap();
+    (client, received)
+}
+
+#[tokio::test]
+async fn download_file_closes_on_error() {
+    // Opens file pointer 7, fails every read with an end code, and closes.
+    let (client, received) = canned_server(CPU::R, |command| match command {
+        0x1827 => (0, vec![0x07, 0x00]),
+        0x1828 => (0xC05C, vec![]),
+        _ => (0, vec![]),
+    }).await;
+
+    let err = client.download_file(FileDrive::R(FileDriveForR::SDMemory), "RECIPE.CSV").await.unwrap_err();
+    assert_eq!(SLMPEndCode::from_io_error(&err), Some(SLMPEndCode(0xC05C)));
+    assert_eq!(*received.lock().unwrap(), [0x1827, 0x1828, 0x182A]);
+}
+
+/// 2026-10-16 09:05:30 as FAT time and date.
+const FAT_TIME: [u8; 2] = 0x48AFu16.to_le_bytes();
+const FAT_DATE: [u8; 2] = 0x5D50u16.to_le_bytes();
+
+fn modified() -> std::time::SystemTime {
+    std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_792_141_530)
+}
+
+async fn list_canned_directory(cpu: CPU, drive: FileDrive, directory: Vec<u8>) -> std::io::Result<Vec<FileInfo>> {
+    let (client, _) = canned_server(cpu, move |_| (0, directory.clone())).await;
+    client.list_files(drive, 0, 16).await
+}
+
+/// Q/L directory entry: 8.3 name in Shift-JIS padded with spaces, attribute, time, date and size in 32 bytes.
+fn ql_entry(name: &[u8; 8], extension: &[u8; 3], attribute: u8, size: u32) -> Vec<u8> {
+    let mut ret: Vec<u8> = [&name[..], extension, &[attribute]].concat();
+    ret.extend([0; 10]);
+    ret.extend(FAT_TIME);
+    ret.extend(FAT_DATE);
+    ret.extend([0; 2]);
+    ret.extend(size.to_le_bytes());
+    ret
+}
+
+/// R directory entry: name length in characters, UTF-16LE name, attribute, time, date and size.
+fn r_entry(name: &str, attribute: u16, size: u32) -> Vec<u8> {
+    let name: Vec<u16> = name.encode_utf16().collect();
+    let mut ret: Vec<u8> = (name.len() as u16).to_le_bytes().to_vec();
+    ret.extend(name.iter().flat_map(|x| x.to_le_bytes()));
+    ret.extend(attribute.to_le_bytes());
+    ret.extend(FAT_TIME);
+    ret.extend(FAT_DATE);
+    ret.extend(size.to_le_bytes());
+    ret
+}
+
+#[tokio::test]
+async fn parse_ql_directory() {
+    let drive = FileDrive::QL(FileDriveForQL::SDMemory);
+    let entries = [ql_entry(b"RECIPE  ", b"CSV", 0x01, 5000), ql_entry(b"LOGS    ", b"   ", 0x10, 0)];
+    let directory: Vec<u8> = [&2u16.to_le_bytes()[..], &entries[0], &entries[1]].concat();
+    assert_eq!(directory.len(), 2 + 2 * 32);
+
+    let files = list_canned_directory(CPU::Q, drive, directory.clone()).await.unwrap();
+    assert_eq!(files, [
+        FileInfo { name: String::from("RECIPE.CSV"), size: 5000, modified: modified(), attribute: 0x01 },
+        FileInfo { name: String::from("LOGS"), size: 0, modified: modified(), attribute: 0x10 },
+    ]);
+    assert!(files[0].is_read_only() && !files[0].is_directory());
+    assert!(files[1].is_directory());
+
+    // An entry cut short, or fewer entries than counted.
+    for len in [directory.len() - 1, 2 + 32 + 12, 2 + 32] {
+        let e = list_canned_directory(CPU::Q, drive, directory[..len].to_vec()).await.unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData, "{len}");
+    }
+}
+
+#[tokio::test]
+async fn parse_r_directory() {
+    let drive = FileDrive::R(FileDriveForR::SDMemory);
+    let entries = [r_entry("設定.DAT", 0x20, 12), r_entry("LOGS", 0x10, 0)];
+    let directory: Vec<u8> = [&2u16.to_le_bytes()[..], &entries[0], &entries[1]].concat();
+    assert_eq!(directory.len(), 2 + (2 + 2 * 6 + 10) + (2 + 2 * 4 + 10));
+
+    let files = list_canned_directory(CPU::R, drive, directory.clone()).await.unwrap();
+    assert_eq!(files, [
+        FileInfo { name: String::from("設定.DAT"), size: 12, modified: modified(), attribute: 0x20 },
+        FileInfo { name: String::from("LOGS"), size: 0, modified: modified(), attribute: 0x10 },
+    ]);
+
+    // Cut in the size, the name, and the name length.
+    let second = 2 + entries[0].len();
+    for len in [directory.len() - 1, second + 5, second + 1, second] {
+        let e = list_canned_directory(CPU::R, drive, directory[..len].to_vec()).await.unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData, "{len}");
+    }
+
+    // A name length past the data.
+    let mut directory = directory;
+    directory[second..second + 2].copy_from_slice(&0xFFFFu16.to_le_bytes());
+    let e = list_canned_directory(CPU::R, drive, directory).await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,52 @@
+use slmp::*;
+use slmp::frame::{DeviceSpecification, SlmpCommandPacket, SlmpReturnPacket};
+
+fn command_frame(command: &[u8]) -> Vec<u8> {
+    let mut frame: Vec<u8> = vec![0x54, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0x03, 0x00];
+    frame.extend(((command.len() + 2) as u16).to_le_bytes());
+    frame.extend([0x10, 0x00]);
+    frame.extend(command);
+    frame
+}
+
+#[test]
+fn decode_random_write() {
+    // Random write (R): D100 = 1 (single word), W1A = 2 (double word)
+    let frame = command_frame(&[
+        0x02, 0x14, 0x02, 0x00, 0x01, 0x01,
+        0x64, 0x00, 0x00, 0x00, 0xa8, 0x00, 0x01, 0x00,
+        0x1a, 0x00, 0x00, 0x00, 0xb4, 0x00, 0x02, 0x00, 0x00, 0x00,
+    ]);
+    let packet = SlmpCommandPacket::try_from(&frame[..]).unwrap();
+
+    assert_eq!(packet.command_name(), "RandomWrite");
+    assert_eq!(packet.devices().unwrap(), [
+        DeviceSpecification { device: Device { device_type: DeviceType::D, address: 100 }, points: 1 },
+        DeviceSpecification { device: Device { device_type: DeviceType::W, address: 0x1a }, points: 2 },
+    ]);
+
+    let text = packet.to_string();
+    assert!(text.contains("Command: 0x1402 (RandomWrite)"));
+    assert!(text.contains("D100 (1 points)"));
+    assert!(text.contains("W1A (2 points)"));
+}
+
+#[test]
+fn decode_block_read_and_response() {
+    // Block read (Q/L): D0 x 2 words, M16 x 1 word
+    let frame = command_frame(&[
+        0x06, 0x04, 0x00, 0x00, 0x01, 0x01,
+        0x00, 0x00, 0x00, 0xa8, 0x02, 0x00,
+        0x10, 0x00, 0x00, 0x90, 0x01, 0x00,
+    ]);
+    let packet = SlmpCommandPacket::try_from(&frame[..]).unwrap();
+    assert_eq!(packet.devices().unwrap()[1], DeviceSpecification { device: Device { device_type: DeviceType::M, address: 16 }, points: 16 });
+
+    // Truncated frame
+    assert!(SlmpCommandPacket::try_from(&frame[..frame.len() - 1]).is_err());
+
+    let response: [u8; 15] = [0xd4, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0x03, 0x00, 0x02, 0x00, 0x61, 0xc0];
+    let packet = SlmpReturnPacket::try_from(&response[..]).unwrap();
+    assert_eq!(packet.error, SLMPEndCode::WRONG_LENGTH.0);
+    assert!(packet.to_string().contains("WrongLength"));
+}
@@ -0,0 +1,241 @@
+//! MELSEC-A (`CPU::A`) through the 1E frame.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use slmp::*;
+use slmp::testing::MockServer;
+
+fn d(address: usize) -> Device {
+    Device { device_type: DeviceType::D, address }
+}
+
+fn m(address: usize) -> Device {
+    Device { device_type: DeviceType::M, address }
+}
+
+/// A server answering the requests in `script` in turn, each read as many bytes as the request there.
+/// The requests received are kept in the returned list.
+async fn scripted_server(script: Vec<(Vec<u8>, Vec<u8>)>) -> (SLMPClient, Arc<Mutex<Vec<Vec<u8>>>>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let requests = received.clone();
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        for (request, response) in script {
+            let mut buf = vec![0u8; request.len()];
+            if stream.read_exact(&mut buf).await.is_err() {
+                return;
+            }
+            requests.lock().unwrap().push(buf);
+            stream.write_all(&response).await.unwrap();
+        }
+    });
+
+    let props = SLMP4EConnectionProps {
+        ip: addr.ip().to_string(),
+        port: addr.port(),
+        cpu: CPU::A,
+        serial_id: 0x0001,
+        network_id: 0x00,
+        pc_id: 0xff,
+        io_id: 0x03ff,
+        area_id: 0x00,
+        cpu_timer: 0x0010,
+        remote_password: None,
+    };
+    let client = SLMPClient::new(props);
+    client.connect().await.unwrap();
+    (client, received)
+}
+
+#[tokio::test]
+async fn a_series_frames() {
+    let script: Vec<(Vec<u8>, Vec<u8>)> = vec![
+        // Batch read in word units: D100, 3 points.
+        (vec![0x01, 0xff, 0x10, 0x00, 0x64, 0x00, 0x00, 0x00, 0x20, 0x44, 0x03, 0x00], vec![0x81, 0x00, 0x01, 0x00, 0x02, 0x00, 0x03, 0x00]),
+        // Batch read in bit units: M10, 3 points (2 points per byte).
+        (vec![0x00, 0xff, 0x10, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x20, 0x4d, 0x03, 0x00], vec![0x80, 0x00, 0x10, 0x10]),
+        // Batch write in word units: D0, 256 points as 0.
+        ([vec![0x03, 0xff, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x44, 0x00, 0x00], vec![0x00; 512]].concat(), vec![0x83, 0x00]),
+        // Batch write in bit units: TS5, 2 points.
+        (vec![0x02, 0xff, 0x10, 0x00, 0x05, 0x00, 0x00, 0x00, 0x53, 0x54, 0x02, 0x00, 0x01], vec![0x82, 0x00]),
+        // Model read.
+        (vec![0x15, 0xff, 0x10, 0x00], vec![0x95, 0x00, 0x83]),
+        // Loopback.
+        (vec![0x16, 0xff, 0x10, 0x00, 0x04, 0x41, 0x31, 0x47, 0x35], vec![0x96, 0x00, 0x04, 0x41, 0x31, 0x47, 0x35]),
+        // Remote RUN and STOP.
+        (vec![0x13, 0xff, 0x10, 0x00], vec![0x93, 0x00]),
+        (vec![0x14, 0xff, 0x10, 0x00], vec![0x94, 0x00]),
+        // Errors: a completion code, and 0x5B with an abnormal code.
+        (vec![0x01, 0xff, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x44, 0x01, 0x00], vec![0x81, 0x56]),
+        (vec![0x01, 0xff, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x44, 0x01, 0x00], vec![0x81, 0x5b, 0x10]),
+        // Back in step after the errors.
+        (vec![0x01, 0xff, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x44, 0x01, 0x00], vec![0x81, 0x00, 0x34, 0x12]),
+    ];
+    let requests: Vec<Vec<u8>> = script.iter().map(|(request, _)| request.clone()).collect();
+    let (client, received) = scripted_server(script).await;
+
+    assert_eq!(client.bulk_read_words(d(100), 3).await.unwrap(), [1, 2, 3]);
+    let bits = client.bulk_read(m(10), 3, DataType::Bool).await.unwrap();
+    assert_eq!(bits.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), [true, false, true].map(TypedData::Bool));
+    client.bulk_write_words(d(0), &[0; 256]).await.unwrap();
+    client.bulk_write(Device { device_type: DeviceType::TS, address: 5 }, &[false, true].map(TypedData::Bool)).await.unwrap();
+
+    let cpu_type = client.get_cpu_type().await.unwrap();
+    assert_eq!((cpu_type.name.as_str(), cpu_type.code, cpu_type.family), ("ACPU", 0x83, Some(CPU::A)));
+    client.echo().await.unwrap();
+    client.run_cpu().await.unwrap();
+    client.stop_cpu().await.unwrap();
+
+    let e = client.bulk_read_words(d(0), 1).await.unwrap_err();
+    assert_eq!(SLMPEndCode::from_io_error(&e), Some(SLMPEndCode(0x56)));
+    let e = client.bulk_read_words(d(0), 1).await.unwrap_err();
+    assert_eq!(SLMPEndCode::from_io_error(&e), Some(SLMPEndCode(0x5B10)));
+    assert_eq!(client.bulk_read_words(d(0), 1).await.unwrap(), [0x1234]);
+
+    assert_eq!(*received.lock().unwrap(), requests);
+}
+
+#[tokio::test]
+async fn a_series_with_mock() {
+    let (server, client) = MockServer::start_connected(CPU::A).await.unwrap();
+    server.set_word(d(10), 0x1234);
+
+    client.bulk_write(d(0), &[TypedData::U16(1), TypedData::I32(-2)]).await.unwrap();
+    client.bulk_write(m(9000), &[true, false, true].map(TypedData::Bool)).await.unwrap();
+    assert_eq!(server.word(d(1)), 0xfffe);
+    assert!(server.bit(m(9002)).unwrap());
+
+    assert_eq!(client.bulk_read_words(d(0), 3).await.unwrap(), [1, 0xfffe, 0xffff]);
+    let data = client.bulk_read(d(10), 1, DataType::U16).await.unwrap();
+    assert_eq!(data[0].data, TypedData::U16(0x1234));
+    let bits = client.bulk_read(m(9000), 3, DataType::Bool).await.unwrap();
+    assert_eq!(bits.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), [true, false, true].map(TypedData::Bool));
+
+    // Requests over the batch limit of 256 points are split.
+    client.fill(d(100), 600, TypedData::U16(7)).await.unwrap();
+    assert_eq!(server.word(d(699)), 7);
+    let snapshot = client.snapshot(&[DeviceBlock { access_type: AccessType::Word, start_device: d(100), size: 600 }]).await.unwrap();
+    assert_eq!(snapshot.areas[0].words, [7; 600]);
+    let e = client.bulk_read_words(d(0), 257).await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+
+    let cpu_type = client.verify_cpu_type().await.unwrap();
+    assert_eq!(cpu_type.family, Some(CPU::A));
+    client.echo().await.unwrap();
+    client.loopback(Some(b"ABC")).await.unwrap();
+}
+
+#[tokio::test]
+async fn a_series_unsupported() {
+    let (server, client) = MockServer::start_connected(CPU::A).await.unwrap();
+    let sent = client.transferred_bytes().0;
+
+    // Commands without a 1E frame are refused before anything is sent.
+    let d0 = TypedDevice { device: d(0), data_type: DataType::U16 };
+    assert_eq!(client.random_read(&[d0]).await.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+    assert_eq!(client.monitor_register(&[d0]).await.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+    assert_eq!(client.read_clock().await.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+    assert_eq!(client.pause_cpu().await.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+    assert_eq!(client.read_labels(&["Label"]).await.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+    assert_eq!(client.unlock_cpu("1234").await.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+    let drive = FileDrive::QL(FileDriveForQL::ProgramMemory);
+    assert_eq!(client.list_files(drive, 0, 16).await.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+    assert_eq!(client.transferred_bytes().0, sent);
+
+    // Devices MELSEC-A does not have.
+    let sd0 = Device { device_type: DeviceType::SD, address: 0 };
+    assert_eq!(client.bulk_read_words(sd0, 1).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(client.bulk_read_words(d(9256), 1).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+    // The connection is still in step.
+    server.set_word(d(0), 5);
+    assert_eq!(client.bulk_read_words(d(0), 1).await.unwrap(), [5]);
+}
+
+#[tokio::test]
+async fn a_series_late_response() {
+    let (server, mut client) = MockServer::start_connected(CPU::A).await.unwrap();
+    server.set_word(d(0), 1);
+    server.set_word(d(1), 2);
+
+    // A 1E response has no serial No., so a request timed out closes the connection instead of waiting for its late response.
+    client.set_recv_timeout(Duration::from_millis(50));
+    server.set_response_delay(Duration::from_millis(100));
+    assert_eq!(client.bulk_read_words(d(0), 1).await.unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+    server.set_response_delay(Duration::ZERO);
+    client.set_recv_timeout(Duration::from_secs(1));
+    assert_eq!(client.bulk_read_words(d(1), 1).await.unwrap_err().kind(), std::io::ErrorKind::NotConnected);
+    client.connect().await.unwrap();
+    assert_eq!(client.bulk_read_words(d(1), 1).await.unwrap(), [2]);
+}
+
+/// A transport passing 4E frames through as they are.
+struct Passthrough(TcpTransport);
+
+impl SlmpTransport for Passthrough {
+    async fn connect(&mut self) -> std::io::Result<()> {
+        self.0.connect().await
+    }
+
+    async fn close(&mut self) {
+        self.0.close().await
+    }
+
+    async fn send(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        self.0.send(frame).await
+    }
+
+    async fn recv(&mut self) -> std::io::Result<Vec<u8>> {
+        self.0.recv().await
+    }
+}
+
+#[tokio::test]
+async fn a_series_transports() {
+    let server = MockServer::start(CPU::A).await.unwrap();
+    let props = server.connection_props();
+    server.set_word(d(0), 3);
+
+    // A transport which does not translate to the 1E frame is refused before anything is sent.
+    let client = SLMPClient::with_transport(props.clone(), Passthrough(TcpTransport::new(&props)));
+    assert_eq!(client.connect().await.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+    assert_eq!(client.bulk_read_words(d(0), 1).await.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+    assert_eq!(client.transferred_bytes().0, 0);
+
+    // Wrapping `TcpTransport` keeps its translation.
+    let transport = RecordingTransport::new(TcpTransport::new(&props));
+    let client = SLMPClient::with_transport(props, transport);
+    client.connect().await.unwrap();
+    assert_eq!(client.bulk_read_words(d(0), 1).await.unwrap(), [3]);
+}
+
+#[tokio::test]
+async fn a_series_blocking() {
+    let server = MockServer::start(CPU::A).await.unwrap();
+    let props = server.connection_props();
+    server.set_word(d(10), 0x1234);
+
+    let ret = tokio::task::spawn_blocking(move || {
+        let mut client = slmp::blocking::SLMPClient::new(props);
+        client.connect()?;
+        client.bulk_write(d(0), &[TypedData::U16(1), TypedData::U16(2)])?;
+        let words = client.bulk_read_words(d(0), 2)?;
+        let data = client.bulk_read(d(10), 1, DataType::U16)?;
+        let cpu_type = client.get_cpu_type()?;
+        let unsupported = client.pause_cpu().unwrap_err().kind();
+        client.close();
+        std::io::Result::Ok((words, data[0].data.clone(), cpu_type.code, unsupported))
+    }).await.unwrap().unwrap();
+
+    assert_eq!(ret, (vec![1, 2], TypedData::U16(0x1234), 0x83, std::io::ErrorKind::Unsupported));
+}
+
+#[test]
+fn a_series_devices() {
+    assert!(DeviceLimits::new(CPU::A).is_available(DeviceType::D));
+    assert!(!DeviceLimits::new(CPU::A).is_available(DeviceType::ZR));
+}
@@ -0,0 +1,263 @@
+use std::io::{Read, Write};
+use slmp::*;
+use slmp::testing::MockServer;
+
+/// Send a frame over a plain socket and return the whole response frame.
+fn exchange(stream: &mut std::net::TcpStream, frame: &[u8]) -> Vec<u8> {
+    stream.write_all(frame).unwrap();
+    let mut response = Vec::new();
+    let mut buf = [0u8; 16];
+    loop {
+        if let Some(len) = frames::frame_len(&response, 2048).unwrap() && response.len() >= len {
+            response.truncate(len);
+            return response;
+        }
+        let n = stream.read(&mut buf).unwrap();
+        assert!(n > 0, "connection closed");
+        response.extend_from_slice(&buf[..n]);
+    }
+}
+
+#[test]
+fn frame_len() {
+    let response: [u8; 17] = [
+        0xd4, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0x03, 0x00, 0x04, 0x00,
+        0x00, 0x00, 0x0a, 0x00,
+    ];
+    assert_eq!(frames::frame_len(&response[..12], 2048).unwrap(), None);
+    assert_eq!(frames::frame_len(&response[..13], 2048).unwrap(), Some(17));
+    assert_eq!(frames::frame_len(&response, 16).unwrap_err().kind(), io::ErrorKind::InvalidData);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn offline_frames() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let mut props = server.connection_props();
+    let d = |address| Device { device_type: DeviceType::D, address };
+
+    // The frames are built without a client; a plain socket carries them.
+    tokio::task::spawn_blocking(move || {
+        let mut stream = std::net::TcpStream::connect((props.ip.as_str(), props.port)).unwrap();
+
+        let frame = frames::build_bulk_write(&props, d(100), &[TypedData::U16(10), TypedData::U32(70000)]).unwrap();
+        frames::check_response(&props, &exchange(&mut stream, &frame)).unwrap();
+
+        let frame = frames::build_bulk_read(&props, d(100), 3, DataType::U16).unwrap();
+        let values = frames::parse_bulk_read_response(&props, d(100), 3, DataType::U16, &exchange(&mut stream, &frame)).unwrap();
+        assert_eq!(values.into_iter().map(|x| x.data).collect::<Vec<_>>(), [TypedData::U16(10), TypedData::U16((70000u32 & 0xffff) as u16), TypedData::U16(1)]);
+
+        let devices = [TypedDevice { device: d(101), data_type: DataType::U32 }, TypedDevice { device: d(100), data_type: DataType::U16 }];
+        let frame = frames::build_random_read(&props, &devices).unwrap();
+        let values = frames::parse_random_read_response(&props, &devices, &exchange(&mut stream, &frame)).unwrap();
+        assert_eq!(values.into_iter().map(|x| x.data).collect::<Vec<_>>(), [TypedData::U32(70000), TypedData::U16(10)]);
+
+        // Word and bit devices go in two frames, numbered from `serial_id`.
+        let data = [
+            DeviceData { device: d(200), data: TypedData::I16(-1) },
+            DeviceData { device: Device { device_type: DeviceType::M, address: 5 }, data: TypedData::Bool(true) },
+        ];
+        let serial_id = props.serial_id;
+        for frame in frames::build_random_write(&props, &data).unwrap() {
+            frames::check_response(&props, &exchange(&mut stream, &frame)).unwrap();
+            props.serial_id = props.serial_id.wrapping_add(1);
+        }
+        assert_eq!(props.serial_id, serial_id.wrapping_add(2));
+
+        let blocks = [DeviceBlock { access_type: AccessType::Bit, start_device: Device { device_type: DeviceType::M, address: 0 }, size: 16 }];
+        let frame = frames::build_block_read(&props, &blocks).unwrap();
+        let values = frames::parse_block_read_response(&props, &blocks, &exchange(&mut stream, &frame)).unwrap();
+        assert_eq!(values[5].data, TypedData::Bool(true));
+
+        let frame = frames::build_echo(&props).unwrap();
+        frames::parse_echo_response(&props, &exchange(&mut stream, &frame)).unwrap();
+
+        // A response to another serial No. is rejected.
+        let frame = frames::build_get_cpu_type(&props).unwrap();
+        let response = exchange(&mut stream, &frame);
+        let mut other = props.clone();
+        other.serial_id = other.serial_id.wrapping_add(1);
+        assert_eq!(frames::parse_get_cpu_type_response(&other, &response).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+        assert!(frames::parse_get_cpu_type_response(&props, &response).is_ok());
+
+        // Checks run before a frame is built.
+        assert_eq!(frames::build_bulk_read(&props, d(0), 0, DataType::U16).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    }).await.unwrap();
+}
+
+/// TCP transport keeping the frames sent.
+struct RecordingTransport {
+    inner: TcpTransport,
+    sent: std::sync::Arc<std::sync::Mutex<Vec<Vec<u8>>>>,
+}
+
+impl SlmpTransport for RecordingTransport {
+    async fn connect(&mut self) -> std::io::Result<()> {
+        self.inner.connect().await
+    }
+
+    async fn close(&mut self) {
+        self.inner.close().await
+    }
+
+    async fn send(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        self.sent.lock().unwrap().push(frame.to_vec());
+        self.inner.send(frame).await
+    }
+
+    async fn recv(&mut self) -> std::io::Result<Vec<u8>> {
+        self.inner.recv().await
+    }
+}
+
+#[tokio::test]
+async fn frames_match_client() {
+    let server = MockServer::start(CPU::Q).await.unwrap();
+    let mut props = server.connection_props();
+    let sent = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let transport = RecordingTransport { inner: TcpTransport::new(&props), sent: sent.clone() };
+    let client = SLMPClient::with_transport(props.clone(), transport);
+    client.connect().await.unwrap();
+
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+    client.bulk_write(d0, &[TypedData::F32(1.5)]).await.unwrap();
+    client.bulk_read(d0, 4, DataType::U16).await.unwrap();
+    client.echo().await.unwrap();
+
+    // The client sends the frames `frames` builds, with its serial No. counting up.
+    let expected = [
+        frames::build_bulk_write(&props, d0, &[TypedData::F32(1.5)]).unwrap(),
+        { props.serial_id += 1; frames::build_bulk_read(&props, d0, 4, DataType::U16).unwrap() },
+        { props.serial_id += 1; frames::build_echo(&props).unwrap() },
+    ];
+    assert_eq!(*sent.lock().unwrap(), expected);
+}
+
+#[test]
+fn route() {
+    let own = Route::own_station();
+    assert_eq!((own.network_id(), own.pc_id(), own.io_id(), own.area_id()), (0x00, 0xFF, 0x03FF, 0x00));
+
+    let route = Route::cc_link_ie(2, 5).unwrap().with_cpu(CpuNo::No2);
+    assert_eq!((route.network_id(), route.pc_id(), route.io_id()), (2, 5, 0x03E1));
+    assert_eq!(Route::multi_cpu(CpuNo::No4).io_id(), 0x03E3);
+    let multidrop = Route::multidrop(0x0040, 3).unwrap();
+    assert_eq!((multidrop.io_id(), multidrop.area_id()), (0x0004, 3));
+
+    for invalid in [
+        Route::cc_link_ie(0, 5),
+        Route::cc_link_ie(0xF0, 5),
+        Route::cc_link_ie(1, 0),
+        Route::cc_link_ie(1, 0xFF),
+        Route::multidrop(0x0041, 3),
+        Route::multidrop(0x0040, 32),
+        Route::new(0x00, 0xFF, 0x03FF, 1),
+        Route::new(0x00, 0xFF, 0x0300, 0),
+    ] {
+        assert_eq!(invalid.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    let props = SLMP4EConnectionProps {
+        ip: "127.0.0.1".into(), port: 5000, cpu: CPU::R, serial_id: 1,
+        network_id: 0, pc_id: 0xFF, io_id: 0x03FF, area_id: 0, cpu_timer: 4, remote_password: None,
+    }.with_route(route);
+    assert_eq!(props.route().unwrap(), route);
+    assert_eq!((props.network_id, props.pc_id, props.io_id, props.area_id), (2, 5, 0x03E1, 0));
+
+    // A relay failure carries the route; other end codes do not.
+    let response = |end_code: u16| {
+        let mut frame = vec![0xD4, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x05, 0xE1, 0x03, 0x00, 0x02, 0x00];
+        frame.extend(end_code.to_le_bytes());
+        frame
+    };
+    let error = frames::check_response(&props, &response(0xCF70)).unwrap_err();
+    assert_eq!(SLMPEndCode::from_io_error(&error), Some(SLMPEndCode::RELAY_FAILURE));
+    assert_eq!(RelayError::from_io_error(&error).unwrap().route, route);
+    assert!(error.to_string().contains("network 0x02, station 0x05"));
+    let error = frames::check_response(&props, &response(0xC059)).unwrap_err();
+    assert_eq!(SLMPEndCode::from_io_error(&error), Some(SLMPEndCode::WRONG_COMMAND));
+    assert!(RelayError::from_io_error(&error).is_none());
+}
+
+#[test]
+fn unaligned_bit_blocks() {
+    let m = |address| Device { device_type: DeviceType::M, address };
+    let response = |data: &[u8]| {
+        let mut frame = vec![0xD4, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0x03, 0x00];
+        frame.extend((2 + data.len() as u16).to_le_bytes());
+        frame.extend([0x00, 0x00]);
+        frame.extend(data);
+        frame
+    };
+
+    for cpu in [CPU::Q, CPU::R] {
+        let props = SLMP4EConnectionProps {
+            ip: "127.0.0.1".into(), port: 5000, cpu, serial_id: 1,
+            network_id: 0, pc_id: 0xFF, io_id: 0x03FF, area_id: 0, cpu_timer: 4, remote_password: None,
+        };
+        let device_code: &[u8] = match cpu {
+            CPU::R => &[0x00, 0x00, 0x00, 0x00, 0x90, 0x00],
+            _ => &[0x00, 0x00, 0x00, 0x90],
+        };
+
+        // M8..M20 is requested as 2 words from M0, and M8..M20 are taken from bits 8..20 of them.
+        let blocks = [DeviceBlock { access_type: AccessType::Bit, start_device: m(8), size: 13 }];
+        let frame = frames::build_block_read(&props, &blocks).unwrap();
+        assert!(frame.ends_with(&[&[0x00, 0x01][..], device_code, &[0x02, 0x00]].concat()));
+
+        let data = frames::parse_block_read_response(&props, &blocks, &response(&[0xFF, 0x01, 0x10, 0x80])).unwrap();
+        assert_eq!(data.len(), 13);
+        assert_eq!(data.iter().map(|x| x.device).collect::<Vec<_>>(), (8..21).map(m).collect::<Vec<_>>());
+        let set: Vec<usize> = data.iter().filter(|x| x.data == TypedData::Bool(true)).map(|x| x.device.address).collect();
+        assert_eq!(set, [8, 20]);
+
+        // An aligned start reads only the words it covers.
+        let blocks = [DeviceBlock { access_type: AccessType::Bit, start_device: m(32), size: 16 }];
+        let frame = frames::build_block_read(&props, &blocks).unwrap();
+        assert!(frame.ends_with(&[0x01, 0x00]));
+        assert_eq!(frames::parse_block_read_response(&props, &blocks, &response(&[0x00, 0x80])).unwrap()[15], DeviceData { device: m(47), data: TypedData::Bool(true) });
+        assert_eq!(frames::parse_block_read_response(&props, &blocks, &response(&[0x00, 0x80, 0x00, 0x00])).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // M15..M16 crosses a word boundary.
+        let blocks = [DeviceBlock { access_type: AccessType::Bit, start_device: m(15), size: 2 }];
+        let data = frames::parse_block_read_response(&props, &blocks, &response(&[0x00, 0x80, 0x01, 0x00])).unwrap();
+        assert_eq!(data, [DeviceData { device: m(15), data: TypedData::Bool(true) }, DeviceData { device: m(16), data: TypedData::Bool(true) }]);
+    }
+}
+
+#[test]
+fn bulk_read_addresses() {
+    let props = SLMP4EConnectionProps {
+        ip: "127.0.0.1".into(), port: 5000, cpu: CPU::Q, serial_id: 1,
+        network_id: 0, pc_id: 0xFF, io_id: 0x03FF, area_id: 0, cpu_timer: 4, remote_password: None,
+    };
+    let response = |data: &[u8]| {
+        let mut frame = vec![0xD4, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0x03, 0x00];
+        frame.extend((2 + data.len() as u16).to_le_bytes());
+        frame.extend([0x00, 0x00]);
+        frame.extend(data);
+        frame
+    };
+    let data: Vec<u8> = (0..24).collect();
+
+    // A word device steps by the words of the type, a bit device by 16 points per word.
+    for (device_type, start, steps) in [
+        (DeviceType::D, 100, [1, 2, 4]),
+        (DeviceType::W, 0x100, [1, 2, 4]),
+        (DeviceType::X, 0x20, [16, 32, 64]),
+        (DeviceType::M, 32, [16, 32, 64]),
+    ] {
+        for ((data_type, device_num), step) in [(DataType::U16, 12), (DataType::U32, 6), (DataType::F64, 3)].into_iter().zip(steps) {
+            let start_device = Device { device_type, address: start };
+            let values = frames::parse_bulk_read_response(&props, start_device, device_num, data_type, &response(&data)).unwrap();
+            let addresses: Vec<usize> = values.iter().map(|x| x.device.address).collect();
+            assert_eq!(addresses, (0..device_num).map(|i| start + step * i).collect::<Vec<_>>(), "{device_type:?} {data_type}");
+        }
+    }
+
+    let x = Device { device_type: DeviceType::X, address: 0 };
+    let values = frames::parse_bulk_read_response(&props, x, 2, DataType::U32, &response(&[0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00])).unwrap();
+    assert_eq!(values, [
+        DeviceData { device: x, data: TypedData::U32(1) },
+        DeviceData { device: Device { device_type: DeviceType::X, address: 0x20 }, data: TypedData::U32(2) },
+    ]);
+}
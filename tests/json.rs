@@ -0,0 +1,183 @@
+use serde_json::json;
+use slmp::*;
+use slmp::json::{ErrorResponse, LabelData, LabelResult, ReadRequest, ReadResponse, Request, Response, WriteRequest};
+use slmp::testing::MockServer;
+
+fn d(address: usize) -> Device {
+    Device { device_type: DeviceType::D, address }
+}
+
+/// Serialized as `expected`, and deserialized back to `value`.
+fn assert_shape<T>(value: &T, expected: serde_json::Value)
+where
+    T: serde::Serialize + serde::de::DeserializeOwned + PartialEq + std::fmt::Debug
+{
+    assert_eq!(serde_json::to_value(value).unwrap(), expected);
+    assert_eq!(&serde_json::from_value::<T>(expected).unwrap(), value);
+}
+
+#[test]
+fn read_request_shape() {
+    assert_shape(&ReadRequest::Bulk { start_device: d(100), device_num: 2, data_type: DataType::U16 }, json!({
+        "type": "Bulk", "startDevice": {"deviceType": "D", "address": 100}, "deviceNum": 2, "dataType": "U16",
+    }));
+    assert_shape(&ReadRequest::Random { devices: vec![TypedDevice { device: d(0), data_type: DataType::String(4) }] }, json!({
+        "type": "Random", "devices": [{"device": {"deviceType": "D", "address": 0}, "dataType": {"String": 4}}],
+    }));
+    let block = TypedDeviceBlock { access_type: AccessType::Word, start_device: d(10), size: 4, data_type: Some(DataType::F32) };
+    assert_shape(&ReadRequest::Block { blocks: vec![block] }, json!({
+        "type": "Block",
+        "blocks": [{"accessType": "Word", "startDevice": {"deviceType": "D", "address": 10}, "size": 4, "dataType": "F32"}],
+    }));
+    // A block without a data type reads U16s.
+    let request: ReadRequest = serde_json::from_value(json!({
+        "type": "Block", "blocks": [{"accessType": "Bit", "startDevice": {"deviceType": "M", "address": 0}, "size": 16}],
+    })).unwrap();
+    assert!(matches!(&request, ReadRequest::Block { blocks } if blocks[0].data_type.is_none()));
+}
+
+#[test]
+fn write_request_shape() {
+    assert_shape(&WriteRequest::Bulk { start_device: d(0), data: vec![TypedData::I16(-1), TypedData::F32(0.5)] }, json!({
+        "type": "Bulk",
+        "startDevice": {"deviceType": "D", "address": 0},
+        "data": [{"type": "I16", "value": -1}, {"type": "F32", "value": 0.5}],
+    }));
+    let data = vec![
+        DeviceData { device: Device { device_type: DeviceType::M, address: 5 }, data: TypedData::Bool(true) },
+        DeviceData { device: d(200), data: TypedData::from(("ABC", 3)) },
+    ];
+    assert_shape(&WriteRequest::Random { data }, json!({
+        "type": "Random",
+        "data": [
+            {"device": {"deviceType": "M", "address": 5}, "data": {"type": "Bool", "value": true}},
+            {"device": {"deviceType": "D", "address": 200}, "data": {"type": "String", "value": {"text": "ABC", "deviceSize": 3, "encoding": "ShiftJis"}}},
+        ],
+    }));
+    let blocks = vec![BlockedDeviceDataOwned { access_type: AccessType::Word, start_device: d(0), data: vec![TypedData::U32(70000)] }];
+    assert_shape(&WriteRequest::Block { blocks }, json!({
+        "type": "Block",
+        "blocks": [{"accessType": "Word", "startDevice": {"deviceType": "D", "address": 0}, "data": [{"type": "U32", "value": 70000}]}],
+    }));
+}
+
+#[test]
+fn string_values() {
+    // Encodings other than Shift-JIS round-trip, and the query string form is still accepted.
+    assert_shape(&TypedData::from(("Aé", 2, StringEncoding::Utf16Le)), json!({
+        "type": "String", "value": {"text": "Aé", "deviceSize": 2, "encoding": "Utf16Le"},
+    }));
+    let value: TypedData = serde_json::from_value(json!({"type": "String", "value": "4#|#ABC"})).unwrap();
+    assert_eq!(value, TypedData::from(("ABC", 4)));
+    let value: TypedData = serde_json::from_value(json!({"type": "String", "value": {"text": "ABC", "deviceSize": 4}})).unwrap();
+    assert_eq!(value.get_type(), DataType::String(4));
+    assert!(serde_json::from_value::<TypedData>(json!({"type": "String", "value": {"text": "ABCDE", "deviceSize": 2}})).is_err());
+}
+
+#[test]
+fn field_and_variant_names() {
+    // Fields in camelCase, variants as named.
+    assert_shape(&ChangeFilter::OnChange { deadband: 0.5 }, json!({"OnChange": {"deadband": 0.5}}));
+    assert_shape(&CPU::F, json!("F"));
+    assert_shape(&WordOrder::HighLow, json!("HighLow"));
+    let response = ReadResponse { data: vec![DeviceData { device: d(1), data: TypedData::BitArray16([false; 16]) }] };
+    assert_eq!(serde_json::to_value(&response).unwrap()["data"][0]["data"]["type"], "BitArray16");
+}
+
+#[tokio::test]
+async fn execute() {
+    let (server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+
+    let request: WriteRequest = serde_json::from_value(json!({
+        "type": "Bulk", "startDevice": {"deviceType": "D", "address": 0}, "data": [{"type": "U16", "value": 7}, {"type": "I32", "value": -2}],
+    })).unwrap();
+    client.execute_write(&request).await.unwrap();
+    assert_eq!(server.word(d(0)), 7);
+
+    let request: ReadRequest = serde_json::from_value(json!({
+        "type": "Random", "devices": [{"device": {"deviceType": "D", "address": 1}, "dataType": "I32"}, {"device": {"deviceType": "D", "address": 0}, "dataType": "U16"}],
+    })).unwrap();
+    let response = client.execute_read(&request).await.unwrap();
+    assert_eq!(serde_json::to_value(&response).unwrap(), json!({"data": [
+        {"device": {"deviceType": "D", "address": 1}, "data": {"type": "I32", "value": -2}},
+        {"device": {"deviceType": "D", "address": 0}, "data": {"type": "U16", "value": 7}},
+    ]}));
+}
+
+#[test]
+fn request_shape() {
+    assert_shape(&Request::ReadClock, json!({"type": "ReadClock"}));
+    assert_shape(&Request::Read { request: ReadRequest::Bulk { start_device: d(100), device_num: 2, data_type: DataType::U16 } }, json!({
+        "type": "Read",
+        "request": {"type": "Bulk", "startDevice": {"deviceType": "D", "address": 100}, "deviceNum": 2, "dataType": "U16"},
+    }));
+    assert_shape(&Request::WriteLabels { labels: vec![LabelData { name: "Speed".to_string(), data: TypedData::I16(-5) }] }, json!({
+        "type": "WriteLabels", "labels": [{"name": "Speed", "data": {"type": "I16", "value": -5}}],
+    }));
+    assert_shape(&Request::ReadStruct { start_device: d(0), fields: vec![DataType::U16, DataType::F32] }, json!({
+        "type": "ReadStruct", "startDevice": {"deviceType": "D", "address": 0}, "fields": ["U16", "F32"],
+    }));
+    assert_shape(&Request::ListFiles { drive: FileDrive::R(FileDriveForR::SDMemory), start_file_no: 0, count: 16 }, json!({
+        "type": "ListFiles", "drive": {"R": "SDMemory"}, "startFileNo": 0, "count": 16,
+    }));
+    assert_shape(&Request::ReadErrorHistory { max_entries: 4 }, json!({"type": "ReadErrorHistory", "maxEntries": 4}));
+}
+
+#[test]
+fn response_shape() {
+    assert_shape(&Response::Done, json!({"type": "Done"}));
+    assert_shape(&Response::Read(ReadResponse { data: vec![DeviceData { device: d(0), data: TypedData::U16(1) }] }), json!({
+        "type": "Read", "data": [{"device": {"deviceType": "D", "address": 0}, "data": {"type": "U16", "value": 1}}],
+    }));
+    assert_shape(&Response::CpuType(CpuTypeInfo { name: "R08CPU".to_string(), code: 0x4801, family: Some(CPU::R) }), json!({
+        "type": "CpuType", "name": "R08CPU", "code": 0x4801, "family": "R",
+    }));
+    let error = ErrorResponse { error: "SLMP Returns Error: Busy (0xCEE0)".to_string(), kind: "InvalidData".to_string(), end_code: Some(0xCEE0) };
+    assert_shape(&Response::Error(error.clone()), json!({
+        "type": "Error", "error": "SLMP Returns Error: Busy (0xCEE0)", "kind": "InvalidData", "endCode": 0xCEE0,
+    }));
+    assert_shape(&Response::Labels { labels: vec![LabelResult { name: "Speed".to_string(), data: None, error: Some(error) }] }, json!({
+        "type": "Labels",
+        "labels": [{"name": "Speed", "data": null, "error": {"error": "SLMP Returns Error: Busy (0xCEE0)", "kind": "InvalidData", "endCode": 0xCEE0}}],
+    }));
+}
+
+#[tokio::test]
+async fn execute_requests() {
+    let (server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let request = |value: serde_json::Value| serde_json::from_value::<Request>(value).unwrap();
+
+    let response = client.execute(&request(json!({"type": "WriteString", "device": {"deviceType": "D", "address": 0}, "text": "ABC", "wordLen": 4}))).await;
+    assert_eq!(response, Response::Done);
+    let response = client.execute(&request(json!({"type": "ReadString", "device": {"deviceType": "D", "address": 0}, "wordLen": 4}))).await;
+    assert_eq!(response, Response::String { text: "ABC".to_string() });
+
+    let fields = vec![TypedData::U16(7), TypedData::F32(1.5)];
+    assert_eq!(client.execute(&Request::WriteStruct { start_device: d(10), fields: fields.clone() }).await, Response::Done);
+    let response = client.execute(&Request::ReadStruct { start_device: d(10), fields: vec![DataType::U16, DataType::F32] }).await;
+    assert_eq!(response, Response::Struct { fields });
+
+    // A label the CPU does not have carries its own error.
+    server.set_label("Speed", TypedData::I16(-5)).unwrap();
+    let response = client.execute(&request(json!({"type": "ReadLabels", "labels": ["Speed", "Missing"]}))).await;
+    let Response::Labels { labels } = response else { panic!("{response:?}") };
+    assert_eq!(labels[0], LabelResult { name: "Speed".to_string(), data: Some(TypedData::I16(-5)), error: None });
+    assert!(labels[1].data.is_none() && labels[1].error.as_ref().unwrap().end_code.is_some());
+
+    let response = client.execute(&Request::GetCpuType).await;
+    assert!(matches!(response, Response::CpuType(CpuTypeInfo { family: Some(CPU::R), .. })));
+    assert_eq!(client.execute(&request(json!({"type": "RunCpu"}))).await, Response::Done);
+
+    let drive = FileDrive::R(FileDriveForR::SDMemory);
+    server.insert_file(drive, "LOG.CSV", b"1,2");
+    let response = client.execute(&request(json!({"type": "DownloadFile", "drive": {"R": "SDMemory"}, "fileName": "LOG.CSV"}))).await;
+    assert_eq!(response, Response::File { data: b"1,2".to_vec() });
+
+    // Failures are answered with an error response, with the end code if the CPU returned one.
+    let response = client.execute(&Request::DeleteFile { drive, file_name: "NONE.CSV".to_string() }).await;
+    let Response::Error(error) = response else { panic!("{response:?}") };
+    assert_eq!((error.kind.as_str(), error.end_code), ("NotFound", Some(SLMPEndCode::FILE_NOT_FOUND.0)));
+    let response = client.execute(&Request::ReadString { device: d(0), word_len: 0 }).await;
+    let Response::Error(error) = response else { panic!("{response:?}") };
+    assert_eq!((error.kind.as_str(), error.end_code), ("InvalidInput", None));
+}
@@ -0,0 +1,1204 @@
+use std::time::Duration;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use slmp::*;
+use slmp::testing::MockServer;
+
+async fn wait_until<F, Fut>(timeout: Duration, f: F) -> bool
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = bool>,
+{
+    let deadline = tokio::time::Instant::now() + timeout;
+    while tokio::time::Instant::now() < deadline {
+        if f().await {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn keep_alive_reconnects() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let addr = server.local_addr();
+    let props = server.connection_props();
+
+    let manager = SLMPConnectionManager::new();
+    let cyclic_task = async |_| Ok(());
+    manager.connect_with_keep_alive(&props, cyclic_task, 1000, Duration::from_millis(50)).await.unwrap();
+    assert!(manager.is_healthy(&props).await.unwrap());
+
+    // The PLC goes away: the echo fails and reconnecting fails.
+    drop(server);
+    assert!(wait_until(Duration::from_secs(3), || async { !manager.is_healthy(&props).await.unwrap() }).await);
+
+    // The PLC comes back on the same port.
+    let _server = MockServer::bind(&addr.to_string(), CPU::R).await.unwrap();
+    assert!(wait_until(Duration::from_secs(3), || async { manager.is_healthy(&props).await.unwrap() }).await);
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn lifecycle_events() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let addr = server.local_addr();
+    let props = server.connection_props();
+    let key = ConnectionKey::try_from(&props).unwrap();
+
+    let manager = SLMPConnectionManager::new();
+    let mut events = manager.events();
+    let cyclic_task = async |_| Ok(());
+    manager.connect_with_keep_alive(&props, cyclic_task, 20, Duration::from_millis(50)).await.unwrap();
+    let targets = [MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device: Device { device_type: DeviceType::D, address: 0 }, data_type: DataType::U16 }, change_filter: ChangeFilter::Always, cycle_ms: None }];
+    manager.register_monitor_targets(&targets).await.unwrap();
+
+    // The PLC goes away, then comes back on the same port.
+    assert!(wait_until(Duration::from_secs(3), || async { manager.status(&props).await.unwrap().last_success.is_some() }).await);
+    drop(server);
+    assert!(wait_until(Duration::from_secs(3), || async { !manager.is_healthy(&props).await.unwrap() }).await);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let _server = MockServer::bind(&addr.to_string(), CPU::R).await.unwrap();
+    assert!(wait_until(Duration::from_secs(3), || async { manager.is_healthy(&props).await.unwrap() }).await);
+    manager.disconnect(&props).await.unwrap();
+
+    let mut sequence: Vec<String> = Vec::new();
+    let mut attempts: Vec<u32> = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        let name = match event {
+            ManagerEvent::Connected { key: x } => { assert_eq!(x, key); "Connected" },
+            ManagerEvent::Disconnected { .. } => "Disconnected",
+            ManagerEvent::ReconnectAttempt { attempt, .. } => { attempts.push(attempt); "ReconnectAttempt" },
+            ManagerEvent::Reconnected { .. } => "Reconnected",
+            ManagerEvent::MonitorRegistered { points, .. } => { assert_eq!(points, 1); "MonitorRegistered" },
+            // Every failed read while the PLC is away.
+            ManagerEvent::CyclicError { .. } => continue,
+            ManagerEvent::OutputError { .. } => unreachable!(),
+        };
+        if sequence.last().map(String::as_str) != Some(name) {
+            sequence.push(name.to_string());
+        }
+    }
+    assert_eq!(sequence, ["Connected", "MonitorRegistered", "Disconnected", "ReconnectAttempt", "Reconnected", "MonitorRegistered", "Disconnected"]);
+    assert!(attempts.len() >= 2);
+    assert_eq!(attempts, (1..=attempts.len() as u32).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn connection_status() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+
+    let manager = SLMPConnectionManager::new();
+    let cyclic_task = async |_| Ok(());
+    manager.connect(&props, cyclic_task, 20).await.unwrap();
+
+    let status = manager.status(&props).await.unwrap();
+    assert!(status.connected);
+    assert_eq!(status.poll_cycle_ms, 20);
+    assert_eq!(status.registered_monitor_points, 0);
+
+    let targets = [
+        MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device: Device { device_type: DeviceType::D, address: 0 }, data_type: DataType::U16 }, change_filter: ChangeFilter::Always, cycle_ms: None },
+        MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device: Device { device_type: DeviceType::D, address: 1 }, data_type: DataType::U32 }, change_filter: ChangeFilter::Always, cycle_ms: None },
+    ];
+    manager.register_monitor_targets(&targets).await.unwrap();
+    assert!(wait_until(Duration::from_secs(3), || async {
+        let status = manager.status(&props).await.unwrap();
+        status.registered_monitor_points == 2 && status.last_success.is_some()
+    }).await);
+
+    // The PLC goes away: the cyclic read fails.
+    drop(server);
+    assert!(wait_until(Duration::from_secs(3), || async {
+        let status = manager.status(&props).await.unwrap();
+        !status.connected && status.last_error.is_some()
+    }).await);
+
+    let all = manager.status_all().await;
+    assert_eq!(all.len(), 1);
+    assert!(!all[&ConnectionKey::try_from(&props).unwrap()].connected);
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn monitor_errors_are_reported() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+
+    let manager = SLMPConnectionManager::new();
+    let cyclic_task = async |_| Ok(());
+    manager.connect(&props, cyclic_task, 20).await.unwrap();
+    let mut errors = manager.subscribe_errors(&props).await.unwrap();
+
+    let targets = [
+        MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device: Device { device_type: DeviceType::D, address: 0 }, data_type: DataType::U16 }, change_filter: ChangeFilter::Always, cycle_ms: None },
+    ];
+    manager.register_monitor_targets(&targets).await.unwrap();
+    assert!(wait_until(Duration::from_secs(3), || async { manager.status(&props).await.unwrap().last_success.is_some() }).await);
+
+    drop(server);
+    let first = tokio::time::timeout(Duration::from_secs(3), errors.recv()).await.unwrap().unwrap();
+    let second = tokio::time::timeout(Duration::from_secs(3), errors.recv()).await.unwrap().unwrap();
+    assert_eq!(first.socket_addr, SocketAddr::try_from(&props).unwrap());
+    assert_eq!(first.route, Route::own_station());
+    assert_eq!(first.consecutive_failures, 1);
+    assert_eq!(second.consecutive_failures, 2);
+    assert!(manager.status(&props).await.unwrap().consecutive_failures >= 2);
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn stream_api() {
+    for back_pressure in [BackPressure::Block, BackPressure::DropOldest, BackPressure::DropNewest] {
+        let server = MockServer::start(CPU::Q).await.unwrap();
+        let props = server.connection_props();
+        let device = Device { device_type: DeviceType::D, address: 10 };
+        server.set_word(device, 1234);
+
+        let manager = SLMPConnectionManager::new();
+        let mut receiver = manager.connect_stream(&props, 10, 1, back_pressure).await.unwrap();
+
+        let targets = [MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device, data_type: DataType::U16 }, change_filter: ChangeFilter::Always, cycle_ms: None }];
+        manager.register_monitor_targets(&targets).await.unwrap();
+
+        let data = tokio::time::timeout(Duration::from_secs(3), receiver.recv()).await.unwrap().unwrap();
+        assert_eq!(data[0].device_data.data, TypedData::U16(1234));
+
+        // Leave the receiver behind; the worker must still close.
+        server.set_word(device, 5678);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let data = tokio::time::timeout(Duration::from_secs(3), receiver.recv()).await.unwrap().unwrap();
+        if back_pressure == BackPressure::DropOldest {
+            assert_eq!(data[0].device_data.data, TypedData::U16(5678));
+        }
+
+        tokio::time::timeout(Duration::from_secs(3), manager.clear()).await.unwrap();
+        while receiver.recv().await.is_some() {}
+    }
+}
+
+#[tokio::test]
+async fn on_change_filter() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let word = Device { device_type: DeviceType::D, address: 0 };
+    let real = Device { device_type: DeviceType::D, address: 2 };
+    let set_real = |value: f32| {
+        let bytes = value.to_le_bytes();
+        server.set_word(real, u16::from_le_bytes([bytes[0], bytes[1]]));
+        server.set_word(Device { device_type: DeviceType::D, address: 3 }, u16::from_le_bytes([bytes[2], bytes[3]]));
+    };
+    set_real(1.0);
+
+    let manager = SLMPConnectionManager::new();
+    let mut receiver = manager.connect_stream(&props, 10, 16, BackPressure::Block).await.unwrap();
+    let targets = [
+        MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device: word, data_type: DataType::U16 }, change_filter: ChangeFilter::OnChange { deadband: 0.0 }, cycle_ms: None },
+        MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device: real, data_type: DataType::F32 }, change_filter: ChangeFilter::OnChange { deadband: 0.5 }, cycle_ms: None },
+    ];
+    manager.register_monitor_targets(&targets).await.unwrap();
+
+    let recv = async |receiver: &mut MonitorReceiver| tokio::time::timeout(Duration::from_millis(300), receiver.recv()).await.ok().flatten();
+
+    // The first read passes every device.
+    assert_eq!(recv(&mut receiver).await.unwrap().len(), 2);
+    // Nothing changed.
+    assert!(recv(&mut receiver).await.is_none());
+
+    // A change within the deadband is suppressed.
+    set_real(1.3);
+    server.set_word(word, 7);
+    let data = recv(&mut receiver).await.unwrap();
+    assert_eq!(data.len(), 1);
+    assert_eq!(data[0].device_data.data, TypedData::U16(7));
+
+    // Compared with the last passed value (1.0), not the last read one.
+    set_real(1.6);
+    let data = recv(&mut receiver).await.unwrap();
+    assert_eq!(data.len(), 1);
+    assert_eq!(data[0].device_data.data, TypedData::F32(1.6));
+
+    // Snapshots pass everything.
+    manager.set_snapshot_interval(&props, 1).await.unwrap();
+    assert_eq!(recv(&mut receiver).await.unwrap().len(), 2);
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn interval_groups() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let fast = Device { device_type: DeviceType::D, address: 0 };
+    let slow = Device { device_type: DeviceType::D, address: 100 };
+
+    let manager = SLMPConnectionManager::new();
+    let mut receiver = manager.connect_stream(&props, 20, 256, BackPressure::Block).await.unwrap();
+    let targets = [
+        MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device: fast, data_type: DataType::U16 }, change_filter: ChangeFilter::Always, cycle_ms: None },
+        MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device: slow, data_type: DataType::U16 }, change_filter: ChangeFilter::Always, cycle_ms: Some(200) },
+    ];
+    manager.register_monitor_targets(&targets).await.unwrap();
+
+    let (mut fast_reads, mut slow_reads) = (0, 0);
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(500);
+    while let Ok(Some(data)) = tokio::time::timeout_at(deadline, receiver.recv()).await {
+        // Each group is delivered separately.
+        assert_eq!(data.len(), 1);
+        if data[0].device_data.device == fast { fast_reads += 1 } else { slow_reads += 1 }
+    }
+
+    assert!(fast_reads >= 10, "fast group read {fast_reads} times");
+    assert!((2..=4).contains(&slow_reads), "slow group read {slow_reads} times");
+    assert_eq!(manager.status(&props).await.unwrap().registered_monitor_points, 2);
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn incremental_targets() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let socket_addr = SocketAddr::try_from(&props).unwrap();
+    let word = |address| TypedDevice { device: Device { device_type: DeviceType::D, address }, data_type: DataType::U16 };
+    let request = |address| MonitorRequest { connection_props: &props, monitor_device: word(address), change_filter: ChangeFilter::Always, cycle_ms: None };
+
+    let manager = SLMPConnectionManager::new();
+    let cyclic_task = async |_| Ok(());
+    manager.connect(&props, cyclic_task, 20).await.unwrap();
+
+    let ret = manager.add_monitor_targets(&[request(0)]).await.unwrap();
+    assert_eq!(ret, [MonitoredDevice { socket_addr, route: Route::own_station(), monitor_device: word(0) }]);
+    let ret = manager.add_monitor_targets(&[request(1), request(0)]).await.unwrap();
+    assert_eq!(ret.iter().map(|x| x.monitor_device).collect::<Vec<_>>(), [word(0), word(1)]);
+
+    // Removing a device not registered is a no-op.
+    let ret = manager.remove_monitor_targets(&[MonitoredDevice { socket_addr, route: Route::own_station(), monitor_device: word(5) }]).await.unwrap();
+    assert_eq!(ret.len(), 2);
+    let ret = manager.remove_monitor_targets(&[MonitoredDevice { socket_addr, route: Route::own_station(), monitor_device: word(0) }]).await.unwrap();
+    assert_eq!(ret.iter().map(|x| x.monitor_device).collect::<Vec<_>>(), [word(1)]);
+
+    manager.clear_monitor_targets(&props).await.unwrap();
+    assert_eq!(manager.status(&props).await.unwrap().registered_monitor_points, 0);
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn targets_over_monitor_limit() {
+    // 192 points per monitor register and random read on Q, 96 on iQ-R.
+    for cpu in [CPU::Q, CPU::R] {
+        targets_over_monitor_limit_on(cpu).await;
+    }
+}
+
+async fn targets_over_monitor_limit_on(cpu: CPU) {
+    let server = MockServer::start(cpu).await.unwrap();
+    let props = server.connection_props();
+    let word = |address| TypedDevice { device: Device { device_type: DeviceType::D, address }, data_type: DataType::U16 };
+    for address in 0..300 {
+        server.set_word(word(address).device, address as u16);
+    }
+
+    let manager = SLMPConnectionManager::new();
+    let mut receiver = manager.connect_stream(&props, 20, 16, BackPressure::Block).await.unwrap();
+
+    // 300 points are read over a monitor register and a random read.
+    let requests: Vec<MonitorRequest> = (0..300)
+        .map(|address| MonitorRequest { connection_props: &props, monitor_device: word(address), change_filter: ChangeFilter::Always, cycle_ms: None })
+        .collect();
+    assert_eq!(manager.add_monitor_targets(&requests).await.unwrap().len(), 300);
+
+    let data = tokio::time::timeout(Duration::from_secs(3), receiver.recv()).await.unwrap().unwrap();
+    assert_eq!(data.len(), 300);
+    assert!(data.iter().enumerate().all(|(i, x)| x.device_data.device == word(i).device && x.device_data.data == TypedData::U16(i as u16)));
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn write_and_read_through_manager() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let d = |address| Device { device_type: DeviceType::D, address };
+
+    let manager = SLMPConnectionManager::new();
+    let cyclic_task = async |_| Ok(());
+    manager.connect(&props, cyclic_task, 10).await.unwrap();
+    let targets = [MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device: d(0), data_type: DataType::U16 }, change_filter: ChangeFilter::Always, cycle_ms: None }];
+    manager.register_monitor_targets(&targets).await.unwrap();
+
+    manager.write(&props, &[DeviceData { device: d(0), data: TypedData::U16(10) }, DeviceData { device: d(10), data: TypedData::I32(-5) }]).await.unwrap();
+    manager.bulk_write(&props, d(20), &[TypedData::U16(1), TypedData::U16(2)]).await.unwrap();
+    assert_eq!(server.word(d(0)), 10);
+    assert_eq!(server.word(d(21)), 2);
+
+    let ret = manager.read(&props, &[TypedDevice { device: d(10), data_type: DataType::I32 }, TypedDevice { device: d(20), data_type: DataType::U16 }]).await.unwrap();
+    assert_eq!(ret.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), [TypedData::I32(-5), TypedData::U16(1)]);
+
+    // Bits of a word, between monitor reads.
+    server.set_word(d(30), 0x00F0);
+    let previous = manager.set_word_bit(&props, d(30), 0, true).await.unwrap();
+    assert!(previous[4] && !previous[0]);
+    manager.modify_word(&props, d(30), 0xFF0F, 0x8000).await.unwrap();
+    assert_eq!(server.word(d(30)), 0x8001);
+    assert_eq!(manager.set_word_bit(&props, d(30), 16, true).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+    // The task gets the client itself; its methods take &self.
+    let ret = manager.operate_worker(&props, async move |client| client.bulk_read(d(20), 2, DataType::U16).await).await.unwrap();
+    assert_eq!(ret[1].data, TypedData::U16(2));
+
+    // Same error as operate_worker for an unknown connection.
+    let mut unknown = props.clone();
+    unknown.port += 1;
+    assert_eq!(manager.read(&unknown, &[]).await.unwrap_err().kind(), std::io::ErrorKind::AddrNotAvailable);
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn reconnect_preserves_targets() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let device = Device { device_type: DeviceType::D, address: 3 };
+    server.set_word(device, 33);
+    let targets = [MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device, data_type: DataType::U16 }, change_filter: ChangeFilter::Always, cycle_ms: None }];
+
+    let manager = SLMPConnectionManager::new();
+    manager.connect_stream(&props, 10, 16, BackPressure::DropOldest).await.unwrap();
+    manager.register_monitor_targets(&targets).await.unwrap();
+    assert!(wait_until(Duration::from_secs(3), || async { manager.status(&props).await.unwrap().registered_monitor_points == 1 }).await);
+    manager.disconnect(&props).await.unwrap();
+
+    // The targets are registered again without the application resending them.
+    let mut receiver = manager.connect_stream(&props, 10, 16, BackPressure::DropOldest).await.unwrap();
+    let data = tokio::time::timeout(Duration::from_secs(3), receiver.recv()).await.unwrap().unwrap();
+    assert_eq!(data[0].device_data.data, TypedData::U16(33));
+    manager.disconnect(&props).await.unwrap();
+
+    // Opting out starts from a clean slate.
+    manager.set_preserve_monitor_targets(false).await;
+    manager.connect_stream(&props, 10, 16, BackPressure::DropOldest).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(manager.status(&props).await.unwrap().registered_monitor_points, 0);
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn worker_stats() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+
+    let manager = SLMPConnectionManager::new();
+    let cyclic_task = async |_| Ok(());
+    manager.connect(&props, cyclic_task, 10).await.unwrap();
+    let targets = [MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device: Device { device_type: DeviceType::D, address: 0 }, data_type: DataType::U16 }, change_filter: ChangeFilter::Always, cycle_ms: None }];
+    manager.register_monitor_targets(&targets).await.unwrap();
+
+    assert!(wait_until(Duration::from_secs(3), || async { manager.stats(&props).await.unwrap().total_requests >= 5 }).await);
+    let stats = manager.stats(&props).await.unwrap();
+    assert_eq!(stats.failed_requests, 0);
+    assert!(stats.bytes_sent > 0 && stats.bytes_received > 0);
+    assert!(stats.min_latency.unwrap() <= stats.average_latency.unwrap());
+    assert!(stats.average_latency.unwrap() <= stats.max_latency.unwrap());
+
+    drop(server);
+    assert!(wait_until(Duration::from_secs(3), || async { manager.stats(&props).await.unwrap().consecutive_failures >= 2 }).await);
+    let failed = manager.stats(&props).await.unwrap();
+    assert!(failed.failed_requests >= 2);
+    assert!(failed.total_requests > stats.total_requests);
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn bounded_close() {
+    let manager = SLMPConnectionManager::new();
+    manager.set_close_timeout(Duration::from_millis(100));
+
+    let mut servers = Vec::new();
+    for _ in 0..3 {
+        let server = MockServer::start(CPU::R).await.unwrap();
+        let props = server.connection_props();
+        let cyclic_task = async |_| Ok(());
+        manager.connect(&props, cyclic_task, 10).await.unwrap();
+        let targets = [MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device: Device { device_type: DeviceType::D, address: 0 }, data_type: DataType::U16 }, change_filter: ChangeFilter::Always, cycle_ms: None }];
+        manager.register_monitor_targets(&targets).await.unwrap();
+        assert!(wait_until(Duration::from_secs(3), || async { manager.stats(&props).await.unwrap().total_requests > 0 }).await);
+        servers.push(server);
+    }
+
+    // Every monitor loop gets stuck waiting for a response (1 s receive timeout).
+    for server in &servers {
+        server.set_response_delay(Duration::from_secs(5));
+    }
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let start = tokio::time::Instant::now();
+    manager.clear().await;
+    assert!(start.elapsed() < Duration::from_millis(500), "clear took {:?}", start.elapsed());
+    assert!(manager.connections.read().await.is_empty());
+}
+
+#[tokio::test]
+async fn for_each_connection() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let manager = Arc::new(SLMPConnectionManager::new());
+    let mut servers = Vec::new();
+    for _ in 0..5 {
+        let server = MockServer::start(CPU::R).await.unwrap();
+        manager.connect(&server.connection_props(), async |_| Ok(()), 100).await.unwrap();
+        servers.push(server);
+    }
+
+    // At most 2 at a time, each request going through the manager while the others run.
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let ret = {
+        let (manager_ref, running, max_running) = (manager.clone(), running.clone(), max_running.clone());
+        manager.for_each_connection(2, move |key, worker| {
+            let (manager, running, max_running) = (manager_ref.clone(), running.clone(), max_running.clone());
+            async move {
+                max_running.fetch_max(running.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                let ret = worker.request(async |client| client.echo().await).await;
+                assert!(manager.get_by_socket_addr(&key.socket_addr).await.len() == 1);
+                running.fetch_sub(1, Ordering::SeqCst);
+                ret
+            }
+        }).await
+    };
+    assert_eq!(ret.len(), 5);
+    assert!(ret.values().all(|x| x.is_ok()));
+    assert_eq!(max_running.load(Ordering::SeqCst), 2);
+
+    assert_eq!(manager.status_all().await.len(), 5);
+    assert_eq!(manager.get_connections_with_elapsed_time().await.len(), 5);
+    manager.clear().await;
+    assert!(manager.for_each_connection(1, |_, _| async {}).await.is_empty());
+}
+
+#[tokio::test]
+async fn write_queue() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let manager = SLMPConnectionManager::new();
+    manager.connect(&props, async |_| Ok(()), 1000).await.unwrap();
+    let worker = manager.get(&props).await.unwrap();
+    let d = |address| Device { device_type: DeviceType::D, address };
+    let word = |address, value| DeviceData { device: d(address), data: TypedData::U16(value) };
+
+    // By default a batch takes the access points of a random write of double words only: 960 / 14 on iQ-R.
+    assert_eq!(worker.write_coalescing(), (Duration::ZERO, 68));
+
+    // Nothing is sent within the window until the flush.
+    worker.set_write_coalescing(Duration::from_secs(10), 192);
+    assert_eq!(worker.write_coalescing(), (Duration::from_secs(10), 192));
+    let mut receivers = Vec::new();
+    for address in 0..5 {
+        receivers.push(worker.queue_write(word(address, address as u16 + 1)).await.unwrap());
+    }
+    receivers.push(worker.queue_write(word(10, 1)).await.unwrap());
+    receivers.push(worker.queue_write(DeviceData { device: Device { device_type: DeviceType::M, address: 5 }, data: TypedData::Bool(true) }).await.unwrap());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(server.word(d(0)), 0);
+
+    // A write overlapping a queued one sends the batch before it.
+    receivers.push(worker.queue_write(DeviceData { device: d(9), data: TypedData::U32(0x0002_0003) }).await.unwrap());
+    assert!(wait_until(Duration::from_secs(1), || async { server.word(d(0)) == 1 }).await);
+    assert_eq!(server.word(d(9)), 0);
+    worker.flush().await.unwrap();
+    for receiver in receivers {
+        receiver.await.unwrap().unwrap();
+    }
+    assert_eq!((0..5).map(|x| server.word(d(x))).collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+    assert_eq!((server.word(d(9)), server.word(d(10))), (3, 2));
+    assert!(server.bit(Device { device_type: DeviceType::M, address: 5 }).unwrap());
+
+    // A batch is sent when the window closes.
+    worker.set_write_coalescing(Duration::from_millis(20), 192);
+    let receiver = worker.queue_write(word(20, 7)).await.unwrap();
+    tokio::time::timeout(Duration::from_secs(1), receiver).await.unwrap().unwrap().unwrap();
+    assert_eq!(server.word(d(20)), 7);
+
+    // Batches within the default take any mix of word sizes in a random write.
+    worker.set_write_coalescing(Duration::from_millis(20), 68);
+    let mut receivers = Vec::new();
+    for i in 0..100 {
+        receivers.push(worker.queue_write(DeviceData { device: d(100 + 2 * i), data: TypedData::U32(i as u32) }).await.unwrap());
+    }
+    worker.flush().await.unwrap();
+    for receiver in receivers {
+        receiver.await.unwrap().unwrap();
+    }
+    assert_eq!(server.word(d(298)), 99);
+
+    // A write the CPU would reject fails before it is queued; a failed batch reaches every write in it.
+    let e = worker.queue_write(DeviceData { device: d(0), data: TypedData::Bool(true) }).await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+    server.set_response_delay(Duration::from_secs(2));
+    let (first, second) = (worker.queue_write(word(0, 1)).await.unwrap(), worker.queue_write(word(1, 1)).await.unwrap());
+    let (first, second) = (first.await.unwrap().unwrap_err(), second.await.unwrap().unwrap_err());
+    assert_eq!((first.kind(), second.kind()), (std::io::ErrorKind::TimedOut, std::io::ErrorKind::TimedOut));
+    server.set_response_delay(Duration::ZERO);
+
+    manager.clear().await;
+    assert_eq!(worker.queue_write(word(0, 1)).await.unwrap_err().kind(), std::io::ErrorKind::NotConnected);
+    assert!(worker.flush().await.is_err());
+}
+
+#[tokio::test]
+async fn subscribe() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let manager = SLMPConnectionManager::new();
+    let received: Arc<std::sync::Mutex<Vec<PLCData>>> = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let task_received = received.clone();
+    let cyclic_task = move |data: Vec<PLCData>| {
+        let received = task_received.clone();
+        async move {
+            received.lock().unwrap().extend(data);
+            Ok(())
+        }
+    };
+    manager.connect(&props, cyclic_task, 10).await.unwrap();
+    let d100 = Device { device_type: DeviceType::D, address: 100 };
+    let device = TypedDevice { device: d100, data_type: DataType::U16 };
+    server.set_word(d100, 5);
+
+    // The receiver starts with the current value and sees its changes; the subscribers of a device share it.
+    let mut first = manager.subscribe(&props, device).await.unwrap();
+    assert_eq!(*first.borrow_and_update(), TypedData::U16(5));
+    let mut second = manager.subscribe(&props, device).await.unwrap();
+    server.set_word(d100, 6);
+    tokio::time::timeout(Duration::from_secs(1), first.changed()).await.unwrap().unwrap();
+    assert_eq!(*first.borrow_and_update(), TypedData::U16(6));
+    tokio::time::timeout(Duration::from_secs(1), second.wait_for(|x| *x == TypedData::U16(6))).await.unwrap().unwrap();
+
+    // Another data type of a subscribed device is rejected, and subscribed devices do not reach the cyclic task.
+    let e = manager.subscribe(&props, TypedDevice { device: d100, data_type: DataType::I16 }).await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(received.lock().unwrap().iter().all(|x| x.device_data.device != d100));
+
+    // The device is released with the last receiver, then can be subscribed with another data type.
+    drop(first);
+    drop(second);
+    let i16_device = TypedDevice { device: d100, data_type: DataType::I16 };
+    assert!(wait_until(Duration::from_secs(1), || async { manager.subscribe(&props, i16_device).await.is_ok() }).await);
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn worker_diagnostics_and_reconnect() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let manager = SLMPConnectionManager::new();
+    let reads = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let task_reads = reads.clone();
+    let cyclic_task = move |_| {
+        let reads = task_reads.clone();
+        async move {
+            reads.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Ok(())
+        }
+    };
+    manager.connect(&props, cyclic_task, 10).await.unwrap();
+    let device = TypedDevice { device: Device { device_type: DeviceType::D, address: 0 }, data_type: DataType::U16 };
+    let targets = [MonitorRequest { connection_props: &props, monitor_device: device, change_filter: ChangeFilter::Always, cycle_ms: None }];
+    manager.register_monitor_targets(&targets).await.unwrap();
+
+    let worker = manager.worker(&props).await.unwrap();
+    assert!(worker.is_running().await);
+    assert_eq!(worker.monitor_devices().await, [device]);
+    let connected_at = worker.connected_at();
+
+    // The targets are registered again on the new connection, and the cyclic reads go on.
+    worker.reconnect().await.unwrap();
+    assert!(worker.connected_at() > connected_at);
+    let status = worker.status().await;
+    assert!(status.connected && status.last_error.is_none());
+    let count = reads.load(std::sync::atomic::Ordering::Relaxed);
+    assert!(wait_until(Duration::from_secs(1), || async { reads.load(std::sync::atomic::Ordering::Relaxed) > count + 2 }).await);
+    assert_eq!(worker.status().await.consecutive_failures, 0);
+
+    manager.clear().await;
+    assert!(!worker.is_running().await);
+    assert!(manager.worker(&props).await.is_none());
+    assert_eq!(worker.reconnect().await.unwrap_err().kind(), std::io::ErrorKind::NotConnected);
+}
+
+#[tokio::test]
+async fn connect_lazy() {
+    // The PLC is offline when the connection is made.
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let addr = server.local_addr();
+    let props = server.connection_props();
+    drop(server);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let manager = SLMPConnectionManager::new();
+    manager.set_connect_backoff(Duration::from_millis(10), Duration::from_millis(40));
+    assert_eq!(manager.connect_backoff(), (Duration::from_millis(10), Duration::from_millis(40)));
+    let received: Arc<std::sync::Mutex<Vec<PLCData>>> = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let task_received = received.clone();
+    let cyclic_task = move |data: Vec<PLCData>| {
+        let received = task_received.clone();
+        async move {
+            received.lock().unwrap().extend(data);
+            Ok(())
+        }
+    };
+    manager.connect_lazy(&props, cyclic_task, 10).await.unwrap();
+    let mut errors = manager.subscribe_errors(&props).await.unwrap();
+
+    // Failed attempts are reported, requests are refused, and the targets are kept for later.
+    let error = tokio::time::timeout(Duration::from_secs(1), errors.recv()).await.unwrap().unwrap();
+    assert!(error.consecutive_failures >= 1);
+    assert!(!manager.is_healthy(&props).await.unwrap());
+    let d0 = TypedDevice { device: Device { device_type: DeviceType::D, address: 0 }, data_type: DataType::U16 };
+    let e = manager.read(&props, &[d0]).await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::NotConnected);
+    assert!(NotConnectedYet::from_io_error(&e).is_some());
+    let targets = [MonitorRequest { connection_props: &props, monitor_device: d0, change_filter: ChangeFilter::Always, cycle_ms: None }];
+    manager.register_monitor_targets(&targets).await.unwrap();
+    assert_eq!(manager.worker(&props).await.unwrap().monitor_devices().await, [d0]);
+
+    // The PLC comes up: the targets are registered and read.
+    let server = MockServer::bind(&addr.to_string(), CPU::R).await.unwrap();
+    server.set_word(d0.device, 42);
+    assert!(wait_until(Duration::from_secs(3), || async {
+        received.lock().unwrap().iter().any(|x| x.device_data.data == TypedData::U16(42))
+    }).await);
+    assert!(manager.is_healthy(&props).await.unwrap());
+    assert_eq!(manager.status(&props).await.unwrap().registered_monitor_points, 1);
+    assert_eq!(manager.read(&props, &[d0]).await.unwrap()[0].data, TypedData::U16(42));
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn read_only_manager() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+    server.set_word(d0, 7);
+
+    let manager = SLMPConnectionManager::new();
+    manager.set_read_only(true);
+    assert!(manager.is_read_only());
+    manager.connect(&props, async |_| Ok(()), 100).await.unwrap();
+    let denied = |e: std::io::Error| assert_eq!(e.kind(), std::io::ErrorKind::PermissionDenied);
+
+    denied(manager.write(&props, &[DeviceData { device: d0, data: TypedData::U16(1) }]).await.unwrap_err());
+    denied(manager.bulk_write(&props, d0, &[TypedData::U16(1)]).await.unwrap_err());
+    denied(manager.get(&props).await.unwrap().queue_write(DeviceData { device: d0, data: TypedData::U16(1) }).await.unwrap_err());
+    // A closure cannot get around it with a clone of the client.
+    let e = manager.operate_worker(&props, async move |client| {
+        let mut client = (*client).clone();
+        client.set_read_only(false)?;
+        client.bulk_write(d0, &[TypedData::U16(1)]).await
+    }).await.unwrap_err();
+    denied(e);
+    assert_eq!(server.word(d0), 7);
+    assert_eq!(manager.read(&props, &[TypedDevice { device: d0, data_type: DataType::U16 }]).await.unwrap()[0].data, TypedData::U16(7));
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn named_connections_with_routes() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let cpu1 = SLMP4EConnectionProps { io_id: 0x03E0, ..server.connection_props() };
+    let cpu2 = SLMP4EConnectionProps { io_id: 0x03E1, ..server.connection_props() };
+
+    let manager = SLMPConnectionManager::new();
+    let cyclic_task = async |_| Ok(());
+    manager.connect_named("press_line_1", &cpu1, cyclic_task, 10).await.unwrap();
+    manager.connect_named("press_line_2", &cpu2, cyclic_task, 10).await.unwrap();
+
+    // Two routes behind one address are kept apart.
+    assert_eq!(manager.connections.read().await.len(), 2);
+    assert_eq!(manager.get_by_socket_addr(&server.local_addr()).await.len(), 2);
+    assert!(Arc::ptr_eq(&manager.get_by_name("press_line_2").await.unwrap(), &manager.get(&cpu2).await.unwrap()));
+    assert!(manager.get_by_name("press_line_3").await.is_none());
+
+    let e = manager.set_name("press_line_1", &cpu2).await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::AlreadyExists);
+
+    // Monitored devices carry the route of their connection, so that each goes back to its own.
+    let word = |address| TypedDevice { device: Device { device_type: DeviceType::D, address }, data_type: DataType::U16 };
+    let request = |props, address| MonitorRequest { connection_props: props, monitor_device: word(address), change_filter: ChangeFilter::Always, cycle_ms: None };
+    let ret = manager.add_monitor_targets(&[request(&cpu1, 0), request(&cpu2, 1)]).await.unwrap();
+    assert_eq!(ret.iter().map(|x| (x.route, x.monitor_device)).collect::<Vec<_>>(), [
+        (Route::multi_cpu(CpuNo::No1), word(0)),
+        (Route::multi_cpu(CpuNo::No2), word(1)),
+    ]);
+    let ret = manager.remove_monitor_targets(&ret[1..]).await.unwrap();
+    assert!(ret.is_empty());
+    assert_eq!(manager.get(&cpu1).await.unwrap().add_monitor_targets(&[]).await.unwrap(), [word(0)]);
+
+    // A device of the own station route has no connection.
+    let device = MonitoredDevice { socket_addr: server.local_addr(), route: Route::own_station(), monitor_device: word(0) };
+    assert_eq!(manager.remove_monitor_targets(&[device]).await.unwrap_err().kind(), std::io::ErrorKind::AddrNotAvailable);
+
+    manager.disconnect(&cpu1).await.unwrap();
+    assert!(manager.get_by_name("press_line_1").await.is_none());
+    assert!(manager.get_by_name("press_line_2").await.is_some());
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn manager_from_config() {
+    let server1 = MockServer::start(CPU::R).await.unwrap();
+    let server2 = MockServer::start(CPU::Q).await.unwrap();
+    server2.set_word(Device { device_type: DeviceType::D, address: 100 }, 7);
+
+    let config = ManagerConfig {
+        connections: vec![
+            ConnectionConfig {
+                name: "line_1".to_string(),
+                connection_props: server1.connection_props(),
+                cycle_ms: 10,
+                monitor_devices: vec!["D100:U16".to_string(), "X1F:Bool".to_string()],
+            },
+            ConnectionConfig {
+                name: "line_2".to_string(),
+                connection_props: server2.connection_props(),
+                cycle_ms: 10,
+                monitor_devices: vec!["D100:U16".to_string()],
+            },
+            ConnectionConfig {
+                name: "line_3".to_string(),
+                connection_props: server2.connection_props(),
+                cycle_ms: 10,
+                monitor_devices: vec!["Q100:U16".to_string()],
+            },
+        ],
+    };
+
+    let (manager, results) = SLMPConnectionManager::from_config(&config, async |_| Ok(())).await;
+    let names: Vec<&str> = results.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["line_1", "line_2", "line_3"]);
+    assert!(results[0].1.is_ok());
+    assert!(results[1].1.is_ok());
+    assert_eq!(results[2].1.as_ref().unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+    assert!(manager.get_by_name("line_1").await.is_some());
+    assert!(manager.get_by_name("line_3").await.is_none());
+    assert_eq!(manager.status(&server1.connection_props()).await.unwrap().registered_monitor_points, 2);
+
+    manager.clear().await;
+}
+
+#[test]
+fn parse_device_notation() {
+    let device: Device = "X1F".parse().unwrap();
+    assert_eq!(device, Device { device_type: DeviceType::X, address: 0x1f });
+    assert_eq!(device.to_string(), "X1F");
+
+    let device: Device = "SD100".parse().unwrap();
+    assert_eq!(device, Device { device_type: DeviceType::SD, address: 100 });
+
+    let typed: TypedDevice = "D200:String(10)".parse().unwrap();
+    assert_eq!(typed.data_type, DataType::String(10));
+    assert_eq!(typed.to_string().parse::<TypedDevice>().unwrap(), typed);
+
+    assert!("D100".parse::<TypedDevice>().is_err());
+    assert!("ZZ1:U16".parse::<TypedDevice>().is_err());
+}
+
+#[tokio::test]
+async fn one_shot_reads_under_load() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    server.set_response_delay(Duration::from_millis(5));
+    let props = server.connection_props();
+    let d = |address| TypedDevice { device: Device { device_type: DeviceType::D, address }, data_type: DataType::U16 };
+
+    // The monitor loop is always busy: three groups read every cycle, each taking the response delay.
+    let manager = Arc::new(SLMPConnectionManager::new());
+    manager.connect(&props, async |_| Ok(()), 5).await.unwrap();
+    let targets = [
+        MonitorRequest { connection_props: &props, monitor_device: d(0), change_filter: ChangeFilter::Always, cycle_ms: None },
+        MonitorRequest { connection_props: &props, monitor_device: d(1), change_filter: ChangeFilter::Always, cycle_ms: Some(6) },
+        MonitorRequest { connection_props: &props, monitor_device: d(2), change_filter: ChangeFilter::Always, cycle_ms: Some(7) },
+    ];
+    manager.register_monitor_targets(&targets).await.unwrap();
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for address in 10..20 {
+        let manager = manager.clone();
+        let props = props.clone();
+        tasks.spawn(async move {
+            let start = tokio::time::Instant::now();
+            manager.read(&props, &[d(address)]).await.unwrap();
+            start.elapsed()
+        });
+    }
+
+    // Each read waits for at most one monitor read, plus the reads queued before it.
+    while let Some(elapsed) = tasks.join_next().await {
+        assert!(elapsed.unwrap() < Duration::from_millis(500));
+    }
+    assert!(manager.stats(&props).await.unwrap().total_requests > 0);
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn monitor_loss_is_recovered() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let d = |address| Device { device_type: DeviceType::D, address };
+    server.set_word(d(0), 1);
+    server.set_word(d(10), 2);
+
+    let manager = SLMPConnectionManager::new();
+    let mut receiver = manager.connect_stream(&props, 20, 16, BackPressure::DropOldest).await.unwrap();
+    let mut errors = manager.subscribe_errors(&props).await.unwrap();
+
+    // Registration errors reach the caller.
+    let invalid = [MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device: d(0), data_type: DataType::String(0) }, change_filter: ChangeFilter::Always, cycle_ms: None }];
+    let ret = manager.register_monitor_targets(&invalid).await.unwrap();
+    assert_eq!(ret[0].key, ConnectionKey::try_from(&props).unwrap());
+    assert_eq!(ret[0].monitored_devices.as_ref().unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+    let targets = [
+        MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device: d(10), data_type: DataType::U32 }, change_filter: ChangeFilter::Always, cycle_ms: None },
+        MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device: d(0), data_type: DataType::U16 }, change_filter: ChangeFilter::Always, cycle_ms: None },
+    ];
+    manager.register_monitor_targets(&targets).await.unwrap();
+    let data = tokio::time::timeout(Duration::from_secs(3), receiver.recv()).await.unwrap().unwrap();
+    assert_eq!(data.iter().map(|x| x.device_data.data.clone()).collect::<Vec<_>>(), [TypedData::U32(2), TypedData::U16(1)]);
+
+    let mut events = manager.events();
+
+    // The CPU loses the registration, as on a power cycle.
+    let client = SLMPClient::new(props.clone());
+    client.connect().await.unwrap();
+    client.monitor_clear().await.unwrap();
+    assert_eq!(SLMPEndCode::from_io_error(&client.monitor_read(&MonitorList::new()).await.unwrap_err()), Some(SLMPEndCode::MONITOR_NOT_REGISTERED));
+    client.close().await;
+
+    let e = tokio::time::timeout(Duration::from_secs(3), errors.recv()).await.unwrap().unwrap();
+    assert_eq!(SLMPEndCode::from_io_error(&e.error), Some(SLMPEndCode::MONITOR_NOT_REGISTERED));
+    let registered = async {
+        while let Ok(event) = events.recv().await {
+            if let ManagerEvent::MonitorRegistered { points, .. } = event {
+                return points;
+            }
+        }
+        0
+    };
+    assert_eq!(tokio::time::timeout(Duration::from_secs(3), registered).await.unwrap(), 2);
+
+    // The devices are registered again in the same order.
+    server.set_word(d(0), 3);
+    let recovered = async {
+        while let Some(data) = receiver.recv().await {
+            if data.iter().map(|x| x.device_data.data.clone()).collect::<Vec<_>>() == [TypedData::U32(2), TypedData::U16(3)] {
+                return true;
+            }
+        }
+        false
+    };
+    assert!(tokio::time::timeout(Duration::from_secs(3), recovered).await.unwrap());
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn registration_results_per_connection() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let cpu1 = SLMP4EConnectionProps { io_id: 0x03E0, ..server.connection_props() };
+    let cpu2 = SLMP4EConnectionProps { io_id: 0x03E1, ..server.connection_props() };
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+
+    let manager = SLMPConnectionManager::new();
+    let cyclic_task = async |_| Ok(());
+    manager.connect(&cpu1, cyclic_task, 10).await.unwrap();
+    manager.connect(&cpu2, cyclic_task, 10).await.unwrap();
+
+    let targets = [
+        MonitorRequest { connection_props: &cpu2, monitor_device: TypedDevice { device: d0, data_type: DataType::String(0) }, change_filter: ChangeFilter::Always, cycle_ms: None },
+        MonitorRequest { connection_props: &cpu1, monitor_device: TypedDevice { device: d0, data_type: DataType::U16 }, change_filter: ChangeFilter::Always, cycle_ms: None },
+        MonitorRequest { connection_props: &cpu1, monitor_device: TypedDevice { device: d0, data_type: DataType::U32 }, change_filter: ChangeFilter::Always, cycle_ms: None },
+    ];
+    let ret = manager.register_monitor_targets(&targets).await.unwrap();
+    assert_eq!(ret.iter().map(|x| x.key).collect::<Vec<_>>(), [ConnectionKey::try_from(&cpu2).unwrap(), ConnectionKey::try_from(&cpu1).unwrap()]);
+    assert!(ret[0].monitored_devices.is_err());
+    assert_eq!(ret[1].monitored_devices.as_ref().unwrap().len(), 2);
+
+    // The status reflects the registration as soon as the call returns.
+    assert_eq!(manager.status(&cpu1).await.unwrap().registered_monitor_points, 2);
+    assert_eq!(manager.status(&cpu2).await.unwrap().registered_monitor_points, 0);
+
+    manager.register_monitor_targets(&targets[1..2]).await.unwrap();
+    assert_eq!(manager.status(&cpu1).await.unwrap().registered_monitor_points, 1);
+
+    // An unknown connection fails the whole request.
+    let cpu3 = SLMP4EConnectionProps { io_id: 0x03E2, ..server.connection_props() };
+    let unknown = [MonitorRequest { connection_props: &cpu3, ..targets[1].clone() }];
+    assert_eq!(manager.register_monitor_targets(&unknown).await.unwrap_err().kind(), std::io::ErrorKind::AddrNotAvailable);
+
+    manager.clear().await;
+}
+
+#[test]
+fn host_name_props() {
+    let props = SLMP4EConnectionProps {
+        ip: String::from("localhost"),
+        port: 5007,
+        cpu: CPU::R,
+        serial_id: 0x0001,
+        network_id: 0x00,
+        pc_id: 0xff,
+        io_id: 0x03ff,
+        area_id: 0x00,
+        cpu_timer: 0x0010,
+        remote_password: None,
+    };
+    let key = ConnectionKey::try_from(&props).unwrap();
+    assert!(key.socket_addr.ip().is_loopback());
+    assert_eq!(key.socket_addr.port(), 5007);
+
+    let props = SLMP4EConnectionProps { ip: String::from("192.168.3.10"), ..props };
+    assert_eq!(SocketAddr::try_from(&props).unwrap(), "192.168.3.10:5007".parse().unwrap());
+}
+
+#[tokio::test]
+async fn lookup_without_resolving() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let manager = SLMPConnectionManager::new();
+    manager.connect(&props, async |_| Ok(()), 1000).await.unwrap();
+    let key = ConnectionKey::new(server.local_addr(), Route::own_station());
+    assert!(manager.status_all().await.contains_key(&key));
+
+    // The lookups go by the host as given, and do not resolve it again.
+    let unknown = SLMP4EConnectionProps { ip: String::from("plc.invalid"), ..props.clone() };
+    assert_eq!(manager.status(&unknown).await.unwrap_err().kind(), std::io::ErrorKind::AddrNotAvailable);
+    assert!(manager.get(&unknown).await.is_none());
+    assert!(!manager.disconnect(&unknown).await.unwrap());
+    assert!(manager.status(&props).await.unwrap().connected);
+
+    // Connecting again with the same props replaces the connection.
+    manager.connect(&props, async |_| Ok(()), 1000).await.unwrap();
+    assert_eq!(manager.status_all().await.len(), 1);
+    assert!(manager.disconnect(&props).await.unwrap());
+    assert_eq!(manager.status(&props).await.unwrap_err().kind(), std::io::ErrorKind::AddrNotAvailable);
+}
+
+#[tokio::test]
+async fn retry_policy() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+
+    let manager = SLMPConnectionManager::new();
+    let policy = RetryPolicy::new(3, Duration::from_millis(20));
+    manager.set_retry_policy(policy);
+    assert_eq!(manager.retry_policy(), policy);
+
+    // The clients of the following connections take the policy.
+    manager.connect(&props, async |_| Ok(()), 1000).await.unwrap();
+    let ret = manager.operate_worker(&props, async move |client| Ok(*client.retry_policy())).await.unwrap();
+    assert_eq!(ret, policy);
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn slow_cyclic_task() {
+    for back_pressure in [BackPressure::DropOldest, BackPressure::DropNewest] {
+        let server = MockServer::start(CPU::R).await.unwrap();
+        let props = server.connection_props();
+
+        let manager = SLMPConnectionManager::new();
+        manager.set_task_queue(1, back_pressure);
+        manager.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        assert_eq!(manager.task_queue(), (1, back_pressure));
+
+        let calls = Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let cyclic_task = {
+            let calls = calls.clone();
+            move |_| {
+                calls.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                async { tokio::time::sleep(Duration::from_millis(500)).await; Ok(()) }
+            }
+        };
+        manager.connect(&props, cyclic_task, 10).await.unwrap();
+        let targets = [MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device: Device { device_type: DeviceType::D, address: 0 }, data_type: DataType::U16 }, change_filter: ChangeFilter::Always, cycle_ms: None }];
+        manager.register_monitor_targets(&targets).await.unwrap();
+
+        // The reads keep their cycle while the task sleeps, and the batches it cannot take are dropped.
+        assert!(wait_until(Duration::from_secs(3), || async { manager.stats(&props).await.unwrap().total_requests >= 10 }).await);
+        assert!(calls.load(std::sync::atomic::Ordering::Relaxed) <= 2);
+        assert!(manager.stats(&props).await.unwrap().dropped_batches > 0);
+
+        tokio::time::timeout(Duration::from_secs(3), manager.clear()).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn repeated_monitor_targets() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let manager = SLMPConnectionManager::new();
+    manager.connect(&props, async |_| Ok(()), 20).await.unwrap();
+
+    let d100 = |data_type| TypedDevice { device: Device { device_type: DeviceType::D, address: 100 }, data_type };
+    let target = |monitor_device, change_filter| MonitorRequest { connection_props: &props, monitor_device, change_filter, cycle_ms: None };
+    let targets = [
+        target(d100(DataType::U16), ChangeFilter::Always),
+        target(d100(DataType::F32), ChangeFilter::Always),
+        target(d100(DataType::U16), ChangeFilter::OnChange { deadband: 0.0 }),
+    ];
+
+    // The repeated target is registered once; another type at the same address is a target of its own.
+    let result = manager.register_monitor_targets(&targets).await.unwrap();
+    let registered: Vec<TypedDevice> = result[0].monitored_devices.as_ref().unwrap().iter().map(|x| x.monitor_device).collect();
+    assert_eq!(registered, [d100(DataType::U16), d100(DataType::F32)]);
+    assert_eq!(manager.status(&props).await.unwrap().registered_monitor_points, 2);
+
+    manager.clear().await;
+}
+
+#[tokio::test]
+async fn latest_values() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let manager = SLMPConnectionManager::new();
+    manager.connect(&props, async |_| Ok(()), 10).await.unwrap();
+
+    let d = |address| Device { device_type: DeviceType::D, address };
+    let u16_device = |address| TypedDevice { device: d(address), data_type: DataType::U16 };
+    let target = |monitor_device| MonitorRequest { connection_props: &props, monitor_device, change_filter: ChangeFilter::OnChange { deadband: 0.0 }, cycle_ms: None };
+    server.set_word(d(4001), 7);
+    server.set_word(d(4002), 8);
+    assert!(manager.latest(&props, &u16_device(4001)).await.is_none());
+
+    let before = std::time::SystemTime::now();
+    manager.register_monitor_targets(&[target(u16_device(4001)), target(u16_device(4002))]).await.unwrap();
+    assert!(wait_until(Duration::from_secs(1), || async { manager.latest_all(&props).await.unwrap().len() == 2 }).await);
+    let (value, read_at) = manager.latest(&props, &u16_device(4001)).await.unwrap();
+    assert_eq!(value, TypedData::U16(7));
+    assert!(read_at >= before);
+
+    // The time moves on with each read, even when the value does not change.
+    assert!(wait_until(Duration::from_secs(1), || async { manager.latest(&props, &u16_device(4001)).await.unwrap().1 > read_at }).await);
+    server.set_word(d(4001), 9);
+    assert!(wait_until(Duration::from_secs(1), || async { manager.latest(&props, &u16_device(4001)).await.unwrap().0 == TypedData::U16(9) }).await);
+
+    // A device removed from the targets is forgotten.
+    manager.register_monitor_targets(&[target(u16_device(4002))]).await.unwrap();
+    assert!(manager.latest(&props, &u16_device(4001)).await.is_none());
+    assert_eq!(manager.latest_all(&props).await.unwrap().keys().collect::<Vec<_>>(), [&u16_device(4002)]);
+
+    manager.clear().await;
+    assert!(manager.latest(&props, &u16_device(4002)).await.is_none());
+    assert_eq!(manager.latest_all(&props).await.unwrap_err().kind(), std::io::ErrorKind::AddrNotAvailable);
+}
+
+#[tokio::test]
+async fn read_now() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let manager = SLMPConnectionManager::new();
+    manager.connect(&props, async |_| Ok(()), 20).await.unwrap();
+
+    let d = |address| Device { device_type: DeviceType::D, address };
+    let target = MonitorRequest { connection_props: &props, monitor_device: TypedDevice { device: d(0), data_type: DataType::U16 }, change_filter: ChangeFilter::Always, cycle_ms: None };
+    manager.register_monitor_targets(&[target]).await.unwrap();
+
+    // Read on the connection of the monitor loop, between its reads.
+    server.set_word(d(4001), 0x1234);
+    let value = manager.read_now(&props, TypedDevice { device: d(4001), data_type: DataType::U16 }, Duration::from_secs(1)).await.unwrap();
+    assert_eq!(value, TypedData::U16(0x1234));
+    let bit = manager.read_now(&props, TypedDevice { device: Device { device_type: DeviceType::M, address: 3 }, data_type: DataType::Bool }, Duration::from_secs(1)).await.unwrap();
+    assert_eq!(bit, TypedData::Bool(false));
+
+    // A PLC slower than the timeout.
+    server.set_response_delay(Duration::from_millis(200));
+    let e = manager.read_now(&props, TypedDevice { device: d(4001), data_type: DataType::U16 }, Duration::from_millis(50)).await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::TimedOut);
+    server.set_response_delay(Duration::ZERO);
+
+    manager.clear().await;
+    let e = manager.read_now(&props, TypedDevice { device: d(4001), data_type: DataType::U16 }, Duration::from_secs(1)).await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::AddrNotAvailable);
+}
+
+#[tokio::test]
+async fn output_image() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let addr = server.local_addr();
+    let props = server.connection_props();
+    let manager = SLMPConnectionManager::new();
+    let mut events = manager.events();
+    manager.connect_with_keep_alive(&props, async |_| Ok(()), 20, Duration::from_millis(50)).await.unwrap();
+
+    let d = |address| Device { device_type: DeviceType::D, address };
+    let m5 = Device { device_type: DeviceType::M, address: 5 };
+    let devices = [
+        TypedDevice { device: d(200), data_type: DataType::U16 },
+        TypedDevice { device: d(201), data_type: DataType::U32 },
+        TypedDevice { device: d(203), data_type: DataType::I16 },
+        TypedDevice { device: d(300), data_type: DataType::U16 },
+        TypedDevice { device: m5, data_type: DataType::Bool },
+    ];
+
+    // The image starts with the current values, so registering it changes nothing.
+    server.set_word(d(300), 42);
+    manager.register_output_image(&props, &devices, 20).await.unwrap();
+    let image = manager.worker(&props).await.unwrap().output_image();
+    assert_eq!(image[3].data, TypedData::U16(42));
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert_eq!(server.word(d(300)), 42);
+
+    manager.update_output(&props, DeviceData { device: d(201), data: TypedData::U32(0x0001_0002) }).await.unwrap();
+    manager.update_output(&props, DeviceData { device: d(300), data: TypedData::U16(7) }).await.unwrap();
+    manager.update_output(&props, DeviceData { device: m5, data: TypedData::Bool(true) }).await.unwrap();
+    assert!(wait_until(Duration::from_secs(1), || async {
+        server.word(d(201)) == 2 && server.word(d(202)) == 1 && server.word(d(300)) == 7 && server.bit(m5).unwrap()
+    }).await);
+
+    // The image wins over a change made by another writer on the next cycle.
+    server.set_word(d(300), 0);
+    assert!(wait_until(Duration::from_secs(1), || async { server.word(d(300)) == 7 }).await);
+
+    let invalid = |ret: std::io::Result<()>| ret.unwrap_err().kind() == std::io::ErrorKind::InvalidInput;
+    assert!(invalid(manager.update_output(&props, DeviceData { device: d(400), data: TypedData::U16(1) }).await));
+    assert!(invalid(manager.update_output(&props, DeviceData { device: d(300), data: TypedData::I16(1) }).await));
+    let overlapping = [TypedDevice { device: d(200), data_type: DataType::U32 }, TypedDevice { device: d(201), data_type: DataType::U16 }];
+    assert!(invalid(manager.register_output_image(&props, &overlapping, 20).await));
+
+    // The failed writes go to the events, and the image is written again on the PLC which comes back.
+    drop(server);
+    let error = tokio::time::timeout(Duration::from_secs(3), async {
+        loop {
+            if let Ok(ManagerEvent::OutputError { error, .. }) = events.recv().await {
+                break error;
+            }
+        }
+    }).await.unwrap();
+    assert!(SLMPEndCode::from_io_error(&error).is_none());
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let server = MockServer::bind(&addr.to_string(), CPU::R).await.unwrap();
+    assert!(wait_until(Duration::from_secs(3), || async { server.word(d(300)) == 7 && server.bit(m5).unwrap() }).await);
+
+    // No devices stop the writes.
+    manager.register_output_image(&props, &[], 20).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    server.set_word(d(300), 0);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(server.word(d(300)), 0);
+
+    manager.clear().await;
+}
@@ -0,0 +1,87 @@
+//! On-demand frames pushed to `SLMPOnDemandServer` over TCP and UDP on the loopback.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+use slmp::{DataType, OnDemandProtocol, SLMPOnDemandServer, TypedData, WordOrder, parse_on_demand_frame};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+
+fn on_demand_frame(serial_id: u16, words: &[u16]) -> Vec<u8> {
+    let mut frame: Vec<u8> = vec![0xD4, 0x00];
+    frame.extend(serial_id.to_le_bytes());
+    frame.extend([0x00, 0x00, 0x00, 0xFF, 0xFF, 0x03, 0x00]);
+    frame.extend(((words.len() * 2 + 6) as u16).to_le_bytes());
+    frame.extend([0xFF, 0xFF, 0x01, 0x21, 0x00, 0x00]);
+    frame.extend(words.iter().flat_map(|x| x.to_le_bytes()));
+    frame
+}
+
+fn loopback() -> SocketAddr {
+    "127.0.0.1:0".parse().unwrap()
+}
+
+#[test]
+fn parse() {
+    let source = loopback();
+    let data = parse_on_demand_frame(source, &on_demand_frame(7, &[0x1234, 0xABCD])).unwrap();
+    assert_eq!(data.serial_id, 7);
+    assert_eq!((data.network_id, data.pc_id, data.io_id), (0x00, 0xFF, 0x03FF));
+    assert_eq!(data.words, vec![0x1234, 0xABCD]);
+
+    let values = data.decode(&[DataType::U32], WordOrder::default()).unwrap();
+    assert_eq!(values, vec![TypedData::U32(0xABCD1234)]);
+    assert!(data.decode(&[DataType::U16], WordOrder::default()).is_err());
+
+    // Another command, an odd data length and a broken length field.
+    let mut frame = on_demand_frame(7, &[0x0001]);
+    frame[15] = 0x02;
+    assert!(parse_on_demand_frame(source, &frame).is_err());
+    let mut frame = on_demand_frame(7, &[0x0001]);
+    frame.push(0x00);
+    frame[11] += 1;
+    assert!(parse_on_demand_frame(source, &frame).is_err());
+    let mut frame = on_demand_frame(7, &[0x0001]);
+    frame[11] += 2;
+    assert!(parse_on_demand_frame(source, &frame).is_err());
+}
+
+#[tokio::test]
+async fn tcp() {
+    let (server, mut receiver) = SLMPOnDemandServer::bind(loopback(), OnDemandProtocol::Tcp, 8, true).await.unwrap();
+    let mut stream = TcpStream::connect(server.local_addr()).await.unwrap();
+
+    // Two frames in one segment, a frame of another command between them.
+    let mut bytes = on_demand_frame(1, &[10, 20, 30]);
+    let mut other = on_demand_frame(2, &[0]);
+    other[15] = 0x01;
+    other[16] = 0x04;
+    bytes.extend(other);
+    bytes.extend(on_demand_frame(3, &[40]));
+    stream.write_all(&bytes).await.unwrap();
+
+    let first = tokio::time::timeout(Duration::from_secs(1), receiver.recv()).await.unwrap().unwrap();
+    assert_eq!((first.serial_id, first.words), (1, vec![10, 20, 30]));
+    let second = tokio::time::timeout(Duration::from_secs(1), receiver.recv()).await.unwrap().unwrap();
+    assert_eq!((second.serial_id, second.words), (3, vec![40]));
+
+    let mut ack = [0u8; 15];
+    stream.read_exact(&mut ack).await.unwrap();
+    assert_eq!(ack, [0xD4, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0x03, 0x00, 0x02, 0x00, 0x00, 0x00]);
+
+    server.close();
+    drop(server);
+    assert!(tokio::time::timeout(Duration::from_secs(1), receiver.recv()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn udp() {
+    let (server, mut receiver) = SLMPOnDemandServer::bind(loopback(), OnDemandProtocol::Udp, 8, false).await.unwrap();
+    let socket = UdpSocket::bind(loopback()).await.unwrap();
+
+    socket.send_to(&[0x50, 0x00, 0x00], server.local_addr()).await.unwrap();
+    socket.send_to(&on_demand_frame(5, &[0x0000, 0x3F80]), server.local_addr()).await.unwrap();
+
+    let data = tokio::time::timeout(Duration::from_secs(1), receiver.recv()).await.unwrap().unwrap();
+    assert_eq!(data.source, socket.local_addr().unwrap());
+    assert_eq!(data.decode(&[DataType::F32], WordOrder::default()).unwrap(), vec![TypedData::F32(1.0)]);
+}
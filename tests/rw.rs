@@ -0,0 +1,1952 @@
+use slmp::*;
+use slmp::testing::MockServer;
+
+/// A server answering every request with `body` (end code 0), for responses a CPU is not expected to send.
+async fn canned_response(cpu: CPU, body: Vec<u8>) -> SLMPClient {
+    canned_frame(cpu, move |header| {
+        let mut response: Vec<u8> = vec![0xd4, 0x00];
+        response.extend(&header[2..11]);
+        response.extend(((body.len() + 2) as u16).to_le_bytes());
+        response.extend([0x00, 0x00]);
+        response.extend(&body);
+        response
+    }).await
+}
+
+/// A server answering every request with the whole frame `respond` builds from the request header.
+async fn canned_frame<F>(cpu: CPU, respond: F) -> SLMPClient
+    where F: Fn(&[u8; 13]) -> Vec<u8> + Send + 'static
+{
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut header = [0u8; 13];
+        while stream.read_exact(&mut header).await.is_ok() {
+            let mut data = vec![0u8; u16::from_le_bytes([header[11], header[12]]) as usize];
+            stream.read_exact(&mut data).await.unwrap();
+            stream.write_all(&respond(&header)).await.unwrap();
+        }
+    });
+
+    let props = SLMP4EConnectionProps {
+        ip: addr.ip().to_string(),
+        port: addr.port(),
+        cpu,
+        serial_id: 0x0001,
+        network_id: 0x00,
+        pc_id: 0xff,
+        io_id: 0x03ff,
+        area_id: 0x00,
+        cpu_timer: 0x0010,
+        remote_password: None,
+    };
+    let client = SLMPClient::new(props);
+    client.connect().await.unwrap();
+    client
+}
+
+#[tokio::test]
+async fn bulk_read_response_length() {
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+
+    // 3 x U32 from D0
+    let client = canned_response(CPU::R, (1..=6).flat_map(|x: u16| x.to_le_bytes()).collect()).await;
+    let ret = client.bulk_read(d0, 3, DataType::U32).await.unwrap();
+    assert_eq!(ret.iter().map(|x| x.device.address).collect::<Vec<_>>(), [0, 2, 4]);
+    assert_eq!(ret[2].data, TypedData::U32(0x0006_0005));
+
+    // A short or padded response
+    for len in [10, 14] {
+        let client = canned_response(CPU::R, vec![0; len]).await;
+        let e = client.bulk_read(d0, 3, DataType::U32).await.unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    // 3 bits take 2 bytes.
+    let client = canned_response(CPU::Q, vec![0x10, 0x01]).await;
+    let ret = client.bulk_read(Device { device_type: DeviceType::M, address: 0 }, 3, DataType::Bool).await.unwrap();
+    assert_eq!(ret.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), [true, false, false].map(TypedData::Bool));
+    let client = canned_response(CPU::Q, vec![0x10]).await;
+    assert!(client.bulk_read(Device { device_type: DeviceType::M, address: 0 }, 3, DataType::Bool).await.is_err());
+
+    // A word of a bit device holds 16 points.
+    let client = canned_response(CPU::R, vec![0x01, 0x00, 0x02, 0x00]).await;
+    let ret = client.bulk_read(Device { device_type: DeviceType::M, address: 0 }, 2, DataType::U16).await.unwrap();
+    assert_eq!(ret.iter().map(|x| x.device.address).collect::<Vec<_>>(), [0, 16]);
+}
+
+#[tokio::test]
+async fn random_and_block_read_response_length() {
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+    let m0 = Device { device_type: DeviceType::M, address: 0 };
+
+    // U16 + U32 take 6 bytes.
+    let devices = [
+        TypedDevice { device: d0, data_type: DataType::U16 },
+        TypedDevice { device: Device { device_type: DeviceType::D, address: 10 }, data_type: DataType::U32 },
+    ];
+    for len in [0, 5, 7] {
+        let client = canned_response(CPU::R, vec![0; len]).await;
+        let e = client.random_read(&devices).await.unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    // 2 words + 9 bits take 4 + 2 bytes.
+    let blocks = [
+        DeviceBlock { access_type: AccessType::Word, start_device: d0, size: 2 },
+        DeviceBlock { access_type: AccessType::Bit, start_device: m0, size: 9 },
+    ];
+    let client = canned_response(CPU::R, vec![0; 6]).await;
+    assert_eq!(client.block_read(&blocks).await.unwrap().len(), 2 + 9);
+    for len in [0, 5, 7] {
+        let client = canned_response(CPU::R, vec![0; len]).await;
+        let e = client.block_read(&blocks).await.unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
+
+#[tokio::test]
+async fn bulk_word_access() {
+    for cpu in [CPU::Q, CPU::R, CPU::F] {
+        let (server, client) = MockServer::start_connected(cpu).await.unwrap();
+        let start_device = Device { device_type: DeviceType::D, address: 0 };
+
+        let data: Vec<TypedData> = (0..120).map(TypedData::U16).collect();
+        client.bulk_write(start_device, &data).await.unwrap();
+
+        let ret = client.bulk_read(start_device, 120, DataType::U16).await.unwrap();
+        assert_eq!(ret.len(), 120);
+        for (i, x) in ret.iter().enumerate() {
+            assert_eq!(x.device, Device { device_type: DeviceType::D, address: i });
+            assert_eq!(x.data, TypedData::U16(i as u16));
+        }
+        assert_eq!(server.word(Device { device_type: DeviceType::D, address: 119 }), 119);
+    }
+}
+
+#[tokio::test]
+async fn bulk_typed_access() {
+    let (server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+
+    let start_device = Device { device_type: DeviceType::D, address: 100 };
+    let data = [TypedData::from(100.0f64), TypedData::from(-200.5f64)];
+    client.bulk_write(start_device, &data).await.unwrap();
+    let ret = client.bulk_read(start_device, 2, DataType::F64).await.unwrap();
+    assert_eq!(ret.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), data);
+    assert_eq!(ret[1].device.address, 104);
+
+    let start_device = Device { device_type: DeviceType::D, address: 200 };
+    let data = [TypedData::I32(-123456), TypedData::F32(1.5), TypedData::U32(0xDEADBEEF)];
+    client.bulk_write(start_device, &data).await.unwrap();
+    let ret = client.bulk_read(start_device, 1, DataType::I32).await.unwrap();
+    assert_eq!(ret[0].data, TypedData::I32(-123456));
+
+    // Bit 0 is the LSB of the word.
+    let mut bits = [false; 16];
+    bits[0] = true;
+    bits[9] = true;
+    let start_device = Device { device_type: DeviceType::D, address: 300 };
+    client.bulk_write(start_device, &[TypedData::BitArray16(bits)]).await.unwrap();
+    assert_eq!(server.word(start_device), 0x0201);
+    let ret = client.bulk_read(start_device, 1, DataType::BitArray16).await.unwrap();
+    assert_eq!(ret[0].data, TypedData::BitArray16(bits));
+
+    let start_device = Device { device_type: DeviceType::D, address: 10 };
+    let data = [TypedData::from(("ABcd", 10)), TypedData::from(("character", 10)), TypedData::from(("日本語", 10))];
+    client.bulk_write(start_device, &data).await.unwrap();
+    let ret = client.bulk_read(start_device, 3, DataType::String(10)).await.unwrap();
+    assert_eq!(ret.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), data);
+}
+
+#[tokio::test]
+async fn bulk_bit_access() {
+    for cpu in [CPU::Q, CPU::R, CPU::F] {
+        let (server, client) = MockServer::start_connected(cpu).await.unwrap();
+        let start_device = Device { device_type: DeviceType::M, address: 0 };
+
+        let data = [true, false, false, true, true].map(TypedData::Bool);
+        client.bulk_write(start_device, &data).await.unwrap();
+
+        let ret = client.bulk_read(start_device, data.len(), DataType::Bool).await.unwrap();
+        assert_eq!(ret.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), data);
+        assert!(server.bit(Device { device_type: DeviceType::M, address: 4 }).unwrap());
+    }
+}
+
+#[tokio::test]
+async fn bulk_bit_nibble_order() {
+    let m = |address| Device { device_type: DeviceType::M, address };
+
+    for n in 1..=9 {
+        // Written by the client, decoded and encoded again by the server.
+        let (server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+        let data: Vec<TypedData> = (0..n).map(|i| TypedData::Bool(i % 3 != 1)).collect();
+        client.bulk_write(m(0), &data).await.unwrap();
+        for i in 0..n {
+            assert_eq!(server.bit(m(i)).unwrap(), i % 3 != 1, "M{i} of {n} points");
+        }
+        assert!(!server.bit(m(n)).unwrap());
+
+        let ret = client.bulk_read(m(0), n, DataType::Bool).await.unwrap();
+        assert_eq!(ret.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), data);
+
+        // Only the first point on: 0x10, then 0x00 per further pair.
+        let mut body = vec![0x00; n.div_ceil(2)];
+        body[0] = 0x10;
+        let client = canned_response(CPU::R, body).await;
+        let ret = client.bulk_read(m(0), n, DataType::Bool).await.unwrap();
+        assert_eq!(ret.len(), n);
+        assert!(ret.iter().enumerate().all(|(i, x)| x.data == TypedData::Bool(i == 0)));
+    }
+
+    // The last point of an odd range is in the upper nibble of the last byte.
+    let client = canned_response(CPU::Q, vec![0x00, 0x10]).await;
+    let ret = client.bulk_read(m(0), 3, DataType::Bool).await.unwrap();
+    assert_eq!(ret.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), [false, false, true].map(TypedData::Bool));
+}
+
+#[tokio::test]
+async fn empty_requests() {
+    // Not connected: the requests must fail before reaching the network.
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let client = SLMPClient::new(server.connection_props());
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+    let invalid = |ret: std::io::Result<_>| ret.is_err_and(|e| e.kind() == std::io::ErrorKind::InvalidInput);
+
+    assert!(invalid(client.bulk_write(d0, &[]).await));
+    assert!(invalid(client.bulk_read(d0, 0, DataType::U16).await.map(|_| ())));
+    assert!(invalid(client.bulk_read(d0, 1, DataType::String(0)).await.map(|_| ())));
+    assert!(invalid(client.random_write(&[]).await));
+    assert!(invalid(client.random_read(&[]).await.map(|_| ())));
+    assert!(invalid(client.random_read(&[TypedDevice { device: d0, data_type: DataType::String(0) }]).await.map(|_| ())));
+    assert!(invalid(client.block_write(&[]).await));
+    assert!(invalid(client.block_write(&[BlockedDeviceData { access_type: AccessType::Word, start_device: d0, data: &[] }]).await));
+    assert!(invalid(client.block_read(&[]).await.map(|_| ())));
+    assert!(invalid(client.block_read(&[DeviceBlock { access_type: AccessType::Word, start_device: d0, size: 0 }]).await.map(|_| ())));
+    assert!(invalid(client.monitor_register(&[]).await.map(|_| ())));
+
+    // The connection is not affected.
+    client.connect().await.unwrap();
+    assert!(invalid(client.bulk_read(d0, 0, DataType::U16).await.map(|_| ())));
+    client.bulk_read(d0, 1, DataType::U16).await.unwrap();
+}
+
+#[tokio::test]
+async fn request_limits() {
+    let (_server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let d = |address| Device { device_type: DeviceType::D, address };
+    let m = |address| Device { device_type: DeviceType::M, address };
+    let message = |e: std::io::Error| {
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+        e.to_string()
+    };
+
+    // iQ-R takes half the random write of Q/L.
+    let bits: Vec<DeviceData> = (0..300).map(|i| DeviceData { device: m(i), data: TypedData::Bool(true) }).collect();
+    assert!(message(client.random_write(&bits).await.unwrap_err()).contains("300 > 94"));
+    assert!(message(client.random_write(&bits[..95]).await.unwrap_err()).contains("95 > 94"));
+    client.random_write(&bits[..94]).await.unwrap();
+
+    // 80 x 12 = 960
+    let words: Vec<DeviceData> = (0..300).map(|i| DeviceData { device: d(i), data: TypedData::U16(i as u16) }).collect();
+    assert!(message(client.random_write(&words).await.unwrap_err()).contains("3600 > 960"));
+    assert!(message(client.random_write(&words[..81]).await.unwrap_err()).contains("972 > 960"));
+    client.random_write(&words[..80]).await.unwrap();
+
+    {
+        let (_server, client) = MockServer::start_connected(CPU::Q).await.unwrap();
+        assert!(message(client.random_write(&bits).await.unwrap_err()).contains("300 > 188"));
+        client.random_write(&bits[..188]).await.unwrap();
+        // 160 x 12 = 1920
+        assert!(message(client.random_write(&words).await.unwrap_err()).contains("3600 > 1920"));
+        client.random_write(&words[..160]).await.unwrap();
+    }
+    let strings: Vec<DeviceData> = (0..6).map(|i| DeviceData { device: d(100 * i), data: TypedData::from(("ABC", 100)) }).collect();
+    assert!(client.random_write(&strings).await.is_err());
+
+    assert!(message(client.bulk_read(d(0), 961, DataType::U16).await.unwrap_err()).contains("961 > 960"));
+    assert!(client.bulk_read(d(0), 481, DataType::U32).await.is_err());
+    assert_eq!(client.bulk_read(d(0), 960, DataType::U16).await.unwrap().len(), 960);
+    let data = vec![TypedData::U16(0); 961];
+    assert!(client.bulk_write(d(0), &data).await.is_err());
+
+    let blocks: Vec<DeviceBlock> = (0..121).map(|i| DeviceBlock { access_type: AccessType::Word, start_device: d(10 * i), size: 1 }).collect();
+    assert!(message(client.block_read(&blocks).await.unwrap_err()).contains("121 > 60"));
+    let blocks = [DeviceBlock { access_type: AccessType::Word, start_device: d(0), size: 961 }];
+    assert!(client.block_read(&blocks).await.is_err());
+
+    // The connection is still usable.
+    client.bulk_read(d(0), 1, DataType::U16).await.unwrap();
+}
+
+#[tokio::test]
+async fn random_access() {
+    for cpu in [CPU::Q, CPU::R, CPU::F] {
+        let (_server, client) = MockServer::start_connected(cpu).await.unwrap();
+
+        let data = [
+            DeviceData { device: Device { device_type: DeviceType::D, address: 20 }, data: TypedData::U16(10) },
+            DeviceData { device: Device { device_type: DeviceType::D, address: 30 }, data: TypedData::I32(-20) },
+            DeviceData { device: Device { device_type: DeviceType::D, address: 40 }, data: TypedData::F64(0.25) },
+            DeviceData { device: Device { device_type: DeviceType::W, address: 0x10 }, data: TypedData::from(("slmp", 4)) },
+            DeviceData { device: Device { device_type: DeviceType::M, address: 7 }, data: TypedData::Bool(true) },
+            DeviceData { device: Device { device_type: DeviceType::Y, address: 0x20 }, data: TypedData::Bool(true) },
+        ];
+        client.random_write(&data).await.unwrap();
+
+        let devices: Vec<TypedDevice> = data.iter()
+            .filter(|x| !matches!(x.data, TypedData::Bool(_)))
+            .map(|x| TypedDevice { device: x.device, data_type: x.data.get_type() })
+            .collect();
+        let ret = client.random_read(&devices).await.unwrap();
+        assert_eq!(ret, data[..4]);
+
+        let ret = client.bulk_read(Device { device_type: DeviceType::M, address: 7 }, 1, DataType::Bool).await.unwrap();
+        assert_eq!(ret[0].data, TypedData::Bool(true));
+    }
+}
+
+#[tokio::test]
+async fn optimized_read() {
+    let (server, mut client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let device = |device_type, address, data_type| TypedDevice { device: Device { device_type, address }, data_type };
+
+    // D100 to D130 in a scrambled order, a far D, a string of W, and M bits between them.
+    let mut devices: Vec<TypedDevice> = (100..=130).rev().step_by(2).map(|x| device(DeviceType::D, x, DataType::U16)).collect();
+    devices.insert(3, device(DeviceType::M, 7, DataType::Bool));
+    devices.insert(8, device(DeviceType::D, 5000, DataType::I32));
+    devices.push(device(DeviceType::W, 0x10, DataType::String(4)));
+    devices.push(device(DeviceType::D, 101, DataType::U32));
+    devices.push(device(DeviceType::M, 100, DataType::Bool));
+    for x in 0..200 {
+        server.set_word(Device { device_type: DeviceType::D, address: x }, x as u16 * 3);
+    }
+    server.set_word(Device { device_type: DeviceType::D, address: 5000 }, 0xFFFE);
+    server.set_word(Device { device_type: DeviceType::D, address: 5001 }, 0xFFFF);
+    server.set_bit(Device { device_type: DeviceType::M, address: 7 }, true).unwrap();
+    client.bulk_write(Device { device_type: DeviceType::W, address: 0x10 }, &[TypedData::from(("slmp", 4))]).await.unwrap();
+
+    for order in [WordOrder::LowHigh, WordOrder::HighLow] {
+        client.set_word_order(order);
+        let ret = client.optimized_read(&devices).await.unwrap();
+        assert_eq!(ret, client.random_read(&devices).await.unwrap());
+        assert_eq!(ret.iter().map(|x| x.device).collect::<Vec<_>>(), devices.iter().map(|x| x.device).collect::<Vec<_>>());
+    }
+    assert_eq!(client.optimized_read(&devices).await.unwrap()[8].data, TypedData::I32(-2).with_word_order(WordOrder::HighLow));
+
+    // A bulk read of D100 to D130, a random read of the rest.
+    assert_eq!(client.dry_run().optimized_read(&devices).unwrap().frames.len(), 2);
+    // No read-through of the unused odd words: D100 to D102 only, D104 to D130 by the random read.
+    client.set_read_coalescing(ReadCoalescing { max_gap: 0, ..ReadCoalescing::DEFAULT });
+    let packets = client.dry_run().optimized_read(&devices).unwrap().packets();
+    assert_eq!(packets.iter().map(|x| x.command_name()).collect::<Vec<_>>(), ["BulkRead", "RandomRead"]);
+    assert_eq!(packets[0].data, [0x64, 0x00, 0x00, 0x00, 0xA8, 0x00, 0x03, 0x00]);
+    assert_eq!(client.optimized_read(&devices).await.unwrap(), client.random_read(&devices).await.unwrap());
+    client.set_read_coalescing(ReadCoalescing { max_words: 8, ..ReadCoalescing::DEFAULT });
+    assert_eq!(client.dry_run().optimized_read(&devices).unwrap().frames.len(), 4 + 1);
+    assert_eq!(client.optimized_read(&devices).await.unwrap(), client.random_read(&devices).await.unwrap());
+    client.set_read_coalescing(ReadCoalescing { max_random_points: 2, ..ReadCoalescing::DEFAULT });
+    assert_eq!(client.optimized_read(&devices).await.unwrap(), client.random_read(&devices).await.unwrap());
+
+    // More devices than a random read takes.
+    let devices: Vec<TypedDevice> = (0..1000).map(|x| device(DeviceType::D, x * 2, DataType::U16)).collect();
+    client.set_read_coalescing(ReadCoalescing::DEFAULT);
+    assert!(client.random_read(&devices).await.is_err());
+    let ret = client.optimized_read(&devices).await.unwrap();
+    assert_eq!(ret[99].data, TypedData::U16(99 * 2 * 3));
+    assert_eq!(client.dry_run().optimized_read(&devices).unwrap().frames.len(), 3);
+    assert!(client.optimized_read(&[]).await.is_err());
+}
+
+#[tokio::test]
+async fn block_access() {
+    for cpu in [CPU::Q, CPU::R, CPU::F] {
+        let (_server, client) = MockServer::start_connected(cpu).await.unwrap();
+
+        let words = [TypedData::U16(1), TypedData::U16(2), TypedData::U16(3)];
+        let bits = [true, false, true, true].map(TypedData::Bool);
+        let data = [
+            BlockedDeviceData { access_type: AccessType::Word, start_device: Device { device_type: DeviceType::D, address: 500 }, data: &words },
+            BlockedDeviceData { access_type: AccessType::Bit, start_device: Device { device_type: DeviceType::M, address: 32 }, data: &bits },
+        ];
+        client.block_write(&data).await.unwrap();
+
+        let blocks = [
+            DeviceBlock { access_type: AccessType::Word, start_device: Device { device_type: DeviceType::D, address: 500 }, size: 3 },
+            DeviceBlock { access_type: AccessType::Bit, start_device: Device { device_type: DeviceType::M, address: 32 }, size: 4 },
+        ];
+        let ret = client.block_read(&blocks).await.unwrap();
+        assert_eq!(ret.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), [&words[..], &bits[..]].concat());
+        assert_eq!(ret[4].device, Device { device_type: DeviceType::M, address: 33 });
+    }
+}
+
+#[tokio::test]
+async fn owned_block_write() {
+    let (server, client) = MockServer::start_connected(CPU::Q).await.unwrap();
+    let d = |address| Device { device_type: DeviceType::D, address };
+    let m = |address| Device { device_type: DeviceType::M, address };
+
+    let words = [TypedData::U16(1), TypedData::I32(-2)];
+    let bits = [TypedData::Bool(true), TypedData::Bool(false), TypedData::Bool(true)];
+    let blocks: Vec<BlockedDeviceDataOwned> = [
+        BlockedDeviceData { access_type: AccessType::Word, start_device: d(10), data: &words },
+        BlockedDeviceData { access_type: AccessType::Bit, start_device: m(3), data: &bits },
+    ].into_iter().map(BlockedDeviceDataOwned::from).collect();
+    assert_eq!(blocks[0].as_blocked().data, words);
+
+    // Prepared writes can be stored and received as JSON.
+    let blocks: Vec<BlockedDeviceDataOwned> = serde_json::from_str(&serde_json::to_string(&blocks).unwrap()).unwrap();
+    client.block_write_owned(&blocks).await.unwrap();
+    assert_eq!((server.word(d(10)), server.word(d(11)), server.word(d(12))), (1, 0xfffe, 0xffff));
+    assert!(server.bit(m(3)).unwrap() && !server.bit(m(4)).unwrap() && server.bit(m(5)).unwrap());
+}
+
+#[tokio::test]
+async fn monitor_access() {
+    for cpu in [CPU::Q, CPU::R, CPU::F] {
+        let (server, client) = MockServer::start_connected(cpu).await.unwrap();
+
+        let devices = [
+            TypedDevice { device: Device { device_type: DeviceType::D, address: 0 }, data_type: DataType::U16 },
+            TypedDevice { device: Device { device_type: DeviceType::D, address: 2 }, data_type: DataType::U32 },
+            TypedDevice { device: Device { device_type: DeviceType::D, address: 4 }, data_type: DataType::F64 },
+        ];
+        let monitor_list = client.monitor_register(&devices).await.unwrap();
+
+        client.bulk_write(Device { device_type: DeviceType::D, address: 0 }, &[TypedData::U16(7), TypedData::U16(0), TypedData::U32(70000), TypedData::F64(3.0)]).await.unwrap();
+        let ret = client.monitor_read(&monitor_list).await.unwrap();
+        assert_eq!(ret.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), [TypedData::U16(7), TypedData::U32(70000), TypedData::F64(3.0)]);
+
+        server.set_word(Device { device_type: DeviceType::D, address: 0 }, 8);
+        let ret = client.monitor_read(&monitor_list).await.unwrap();
+        assert_eq!(ret[0].data, TypedData::U16(8));
+    }
+}
+
+#[tokio::test]
+async fn monitor_point_limit() {
+    let (_server, client) = MockServer::start_connected(CPU::Q).await.unwrap();
+
+    // 190 words + F64 (4 words) exceeds the 192 points without wrapping the byte-sized counts.
+    let mut devices: Vec<TypedDevice> = (0..190)
+        .map(|address| TypedDevice { device: Device { device_type: DeviceType::D, address }, data_type: DataType::U16 })
+        .collect();
+    devices.push(TypedDevice { device: Device { device_type: DeviceType::D, address: 1000 }, data_type: DataType::F64 });
+
+    let e = client.monitor_register(&devices).await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(e.to_string().contains("194"));
+    assert_eq!(client.random_read(&devices).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+    devices.drain(..2);
+    assert_eq!(client.monitor_register(&devices).await.unwrap().access_points(), 192);
+
+    // iQ-R takes half as many points.
+    let (_server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let e = client.monitor_register(&devices[..97]).await.unwrap_err();
+    assert_eq!(MonitorListFull::from_io_error(&e).map(|x| (x.access_points, x.max_access_points)), Some((97, 96)));
+    assert_eq!(client.random_read(&devices[..97]).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(client.monitor_register(&devices[..96]).await.unwrap().access_points(), 96);
+    assert_eq!(client.random_read(&devices[..96]).await.unwrap().len(), 96);
+
+    // Optimized read splits its random reads within the limit of the CPU.
+    let scattered: Vec<TypedDevice> = (0..150)
+        .map(|i| TypedDevice { device: Device { device_type: DeviceType::M, address: 100 * i }, data_type: DataType::Bool })
+        .collect();
+    assert_eq!(client.optimized_read(&scattered).await.unwrap().len(), 150);
+}
+
+#[tokio::test]
+async fn unit_control() {
+    let (server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+
+    client.echo().await.unwrap();
+    assert_eq!(client.get_cpu_type().await.unwrap(), CpuTypeInfo { name: "R04CPU".to_string(), code: 0x4800, family: Some(CPU::R) });
+    server.set_cpu_type("R08CPU");
+    assert_eq!(client.verify_cpu_type().await.unwrap(), CpuTypeInfo { name: "R08CPU".to_string(), code: 0x4801, family: Some(CPU::R) });
+
+    // A family other than the configured one is reported; an unknown model code is not.
+    server.set_cpu_type("L02CPU");
+    let e = client.verify_cpu_type().await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(e.to_string().contains("L02CPU"));
+    server.set_cpu_type("Q172DCPU");
+    assert_eq!(client.verify_cpu_type().await.unwrap().family, None);
+    server.set_cpu_type("L08HCPU");
+    assert_eq!(client.verify_cpu_type().await.unwrap().family, Some(CPU::R));
+
+    client.stop_cpu().await.unwrap();
+    client.run_cpu().await.unwrap();
+    client.pause_cpu().await.unwrap();
+    client.unlock_cpu("password").await.unwrap();
+    client.lock_cpu("password").await.unwrap();
+}
+
+#[tokio::test]
+async fn end_codes() {
+    // An end code with its error information.
+    let client = canned_frame(CPU::Q, |header| {
+        let mut response: Vec<u8> = vec![0xd4, 0x00];
+        response.extend(&header[2..11]);
+        response.extend(11u16.to_le_bytes());
+        response.extend(0xC05Bu16.to_le_bytes());
+        response.extend([0x00, 0xff, 0xff, 0x03, 0x00, 0x01, 0x04, 0x00, 0x00]);
+        response
+    }).await;
+    let ret = client.bulk_read(Device { device_type: DeviceType::D, address: 0 }, 1, DataType::U16).await;
+    assert_eq!(SLMPEndCode::from_io_error(&ret.unwrap_err()), Some(SLMPEndCode(0xC05B)));
+
+    let (_server, client) = MockServer::start_connected(CPU::Q).await.unwrap();
+
+    // Too many points are rejected before a request is sent.
+    let e = client.bulk_read(Device { device_type: DeviceType::D, address: 0 }, 961, DataType::U16).await.unwrap_err();
+    assert_eq!(SLMPEndCode::from_io_error(&e), None);
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+
+    // The connection is still usable after an error response.
+    client.echo().await.unwrap();
+}
+
+#[tokio::test]
+async fn malformed_requests() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let server = MockServer::start(CPU::Q).await.unwrap();
+    let mut stream = tokio::net::TcpStream::connect(server.local_addr()).await.unwrap();
+
+    async fn request(stream: &mut tokio::net::TcpStream, data: &[u8]) -> u16 {
+        let mut frame: Vec<u8> = vec![0x54, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0x03, 0x00];
+        frame.extend((data.len() as u16).to_le_bytes());
+        frame.extend(data);
+        stream.write_all(&frame).await.unwrap();
+
+        let mut header = [0u8; 13];
+        stream.read_exact(&mut header).await.unwrap();
+        assert_eq!(header[0..2], [0xD4, 0x00]);
+        let mut body = vec![0u8; u16::from_le_bytes([header[11], header[12]]) as usize];
+        stream.read_exact(&mut body).await.unwrap();
+        u16::from_le_bytes([body[0], body[1]])
+    }
+
+    // Bulk read D0, 1 point
+    assert_eq!(request(&mut stream, &[0x10, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xa8, 0x01, 0x00]).await, 0);
+    // Missing number of points
+    assert_eq!(request(&mut stream, &[0x10, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xa8]).await, SLMPEndCode::WRONG_LENGTH.0);
+    // Trailing bytes
+    assert_eq!(request(&mut stream, &[0x10, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xa8, 0x01, 0x00, 0x00]).await, SLMPEndCode::WRONG_LENGTH.0);
+    // Unknown command
+    assert_eq!(request(&mut stream, &[0x10, 0x00, 0xff, 0xff, 0x00, 0x00]).await, SLMPEndCode::WRONG_COMMAND.0);
+    // Unknown subcommand
+    assert_eq!(request(&mut stream, &[0x10, 0x00, 0x01, 0x04, 0x80, 0x00, 0x00, 0x00, 0x00, 0xa8, 0x01, 0x00]).await, SLMPEndCode::WRONG_COMMAND.0);
+    // Unknown device code
+    assert_eq!(request(&mut stream, &[0x10, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01, 0x00]).await, SLMPEndCode::WRONG_FORMAT.0);
+    // No command
+    assert_eq!(request(&mut stream, &[0x10, 0x00]).await, SLMPEndCode::WRONG_LENGTH.0);
+}
+
+#[tokio::test]
+async fn per_request_timeout() {
+    use std::time::Duration;
+
+    let (server, mut client) = MockServer::start_connected(CPU::R).await.unwrap();
+    client.set_recv_timeout(Duration::from_millis(50));
+    server.set_response_delay(Duration::from_millis(200));
+
+    let start_device = Device { device_type: DeviceType::D, address: 0 };
+    let err = client.bulk_read(start_device, 1, DataType::U16).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+    // The late response of the timed-out request is skipped.
+    let ret = client.bulk_read_with_timeout(start_device, 1, DataType::U16, Duration::from_secs(1)).await.unwrap();
+    assert_eq!(ret[0].data, TypedData::U16(0));
+    client.bulk_write_with_timeout(start_device, &[TypedData::U16(5)], Duration::from_secs(1)).await.unwrap();
+}
+
+#[tokio::test]
+async fn late_responses_are_skipped() {
+    use std::time::Duration;
+
+    let (server, mut client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let d = |address| Device { device_type: DeviceType::D, address };
+    server.set_word(d(0), 5);
+    server.set_word(d(1), 7);
+    server.set_response_delay(Duration::from_millis(100));
+
+    client.set_recv_timeout(Duration::from_millis(30));
+    assert_eq!(client.bulk_read(d(0), 1, DataType::U16).await.unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+
+    // The response for D0 arrives first on the same connection.
+    let ret = client.bulk_read_with_timeout(d(1), 1, DataType::U16, Duration::from_secs(1)).await.unwrap();
+    assert_eq!(ret[0].data, TypedData::U16(7));
+
+    // The serial No. wraps around.
+    let props = SLMP4EConnectionProps { serial_id: u16::MAX, ..server.connection_props() };
+    server.set_response_delay(Duration::ZERO);
+    let client = SLMPClient::new(props);
+    client.connect().await.unwrap();
+    for _ in 0..3 {
+        client.echo().await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn resync_after_timeout_mid_frame() {
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // The first response stops halfway for a while; the others are sent at once. Each answers its request No. as a word.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut header = [0u8; 13];
+        let mut n: u16 = 0;
+        while stream.read_exact(&mut header).await.is_ok() {
+            let mut data = vec![0u8; u16::from_le_bytes([header[11], header[12]]) as usize];
+            stream.read_exact(&mut data).await.unwrap();
+
+            let mut response: Vec<u8> = vec![0xd4, 0x00];
+            response.extend(&header[2..11]);
+            response.extend([0x04, 0x00, 0x00, 0x00]);
+            response.extend(n.to_le_bytes());
+            if n == 0 {
+                stream.write_all(&response[..8]).await.unwrap();
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                stream.write_all(&response[8..]).await.unwrap();
+            } else {
+                stream.write_all(&response).await.unwrap();
+            }
+            n += 1;
+        }
+    });
+
+    let props = SLMP4EConnectionProps {
+        ip: addr.ip().to_string(),
+        port: addr.port(),
+        cpu: CPU::R,
+        serial_id: 0x0001,
+        network_id: 0x00,
+        pc_id: 0xff,
+        io_id: 0x03ff,
+        area_id: 0x00,
+        cpu_timer: 0x0010,
+        remote_password: None,
+    };
+    let mut client = SLMPClient::new(props);
+    client.connect().await.unwrap();
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+
+    client.set_recv_timeout(Duration::from_millis(30));
+    assert_eq!(client.bulk_read(d0, 1, DataType::U16).await.unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+
+    // The rest of the first response is skipped, and the following requests get their own responses.
+    client.set_recv_timeout(Duration::from_secs(1));
+    for n in 1..4 {
+        let ret = client.bulk_read(d0, 1, DataType::U16).await.unwrap();
+        assert_eq!(ret[0].data, TypedData::U16(n));
+    }
+}
+
+#[tokio::test]
+async fn concurrent_clones() {
+    let (server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let d = |address| Device { device_type: DeviceType::D, address };
+    for i in 0..20 {
+        server.set_word(d(i), i as u16 * 3);
+    }
+    server.set_response_delay(std::time::Duration::from_millis(2));
+
+    // The clones share the connection and take turns on it.
+    let mut tasks = tokio::task::JoinSet::new();
+    for i in 0..20 {
+        let client = client.clone();
+        tasks.spawn(async move {
+            let ret = client.bulk_read(d(i), 1, DataType::U16).await.unwrap();
+            assert_eq!(ret[0].device, d(i));
+            assert_eq!(ret[0].data, TypedData::U16(i as u16 * 3));
+        });
+    }
+    while let Some(ret) = tasks.join_next().await {
+        ret.unwrap();
+    }
+
+    let (sent, received) = client.transferred_bytes();
+    assert!(sent > 0 && received > 0);
+}
+
+#[tokio::test]
+async fn cpu_timer_consistency() {
+    use std::time::Duration;
+
+    let server = MockServer::start(CPU::R).await.unwrap();
+
+    // 0x0010 x 250 ms = 4 s exceeds the default receive timeout (1 s).
+    assert!(SLMPClient::try_new(server.connection_props()).is_err());
+
+    let props = server.connection_props().with_timeouts(Duration::from_secs(1)).unwrap();
+    assert_eq!(props.cpu_timer, 4);
+    assert_eq!(props.cpu_timer_duration(), Some(Duration::from_secs(1)));
+    assert!(server.connection_props().with_timeouts(Duration::from_millis(100)).is_err());
+
+    let mut client = SLMPClient::try_new(props).unwrap();
+    client.set_recv_timeout(Duration::from_millis(500));
+    assert!(client.validate_timeouts().is_err());
+    client.set_cpu_timer(2);
+    assert!(client.validate_timeouts().is_ok());
+
+    client.connect().await.unwrap();
+    client.echo().await.unwrap();
+}
+
+#[tokio::test]
+async fn response_header_validation() {
+    use std::time::Duration;
+    type Tamper = fn(&mut Vec<u8>);
+
+    // A response of D0 = 0x4241, with `tamper` applied
+    let frame = |header: &[u8; 13], tamper: Tamper| {
+        let mut response: Vec<u8> = vec![0xd4, 0x00];
+        response.extend(&header[2..11]);
+        response.extend([0x04, 0x00, 0x00, 0x00, 0x41, 0x42]);
+        tamper(&mut response);
+        response
+    };
+    let request = async |tamper: Tamper| {
+        let mut client = canned_frame(CPU::R, move |header| frame(header, tamper)).await;
+        client.set_recv_timeout(Duration::from_millis(100));
+        client.bulk_read(Device { device_type: DeviceType::D, address: 0 }, 1, DataType::U16).await
+    };
+    let message = |e: std::io::Error| e.into_inner().unwrap().to_string();
+
+    assert_eq!(request(|_| ()).await.unwrap()[0].data, TypedData::U16(0x4241));
+
+    let cases: [(Tamper, &str); 7] = [
+        (|x| x[0] = 0xd0, "Response Data"),
+        (|x| x[4] = 0x01, "Blank Code"),
+        (|x| x[6] = 0x01, "Network ID"),
+        (|x| x[7] = 0x00, "PC ID"),
+        (|x| x[8] = 0x00, "IO ID"),
+        (|x| x[9] = 0x00, "IO ID"),
+        (|x| x[10] = 0x01, "Area ID"),
+    ];
+    for (tamper, field) in cases {
+        let e = request(tamper).await.unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+        assert!(message(e).contains(field), "{field}");
+    }
+
+    // A frame of another serial No. is not the response.
+    let e = request(|x| x[3] ^= 0x80).await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::TimedOut);
+
+    // The data block is too short for the end code, or does not match its length field.
+    for tamper in [
+        (|x| { x.truncate(13); x[11] = 0x00; }) as Tamper,
+        |x| { x.truncate(14); x[11] = 0x01; },
+        |x| { x[11] = 0x03; x.truncate(16); },
+    ] {
+        let e = request(tamper).await.unwrap_err();
+        assert!(matches!(e.kind(), std::io::ErrorKind::InvalidData | std::io::ErrorKind::TimedOut), "{e}");
+    }
+
+    // The end code of a valid header
+    let e = request(|x| { x[13] = 0x51; x[14] = 0xc0; }).await.unwrap_err();
+    assert_eq!(SLMPEndCode::from_io_error(&e), Some(SLMPEndCode(0xc051)));
+}
+
+/// Transport counting the frames it carries over TCP.
+struct CountingTransport {
+    inner: TcpTransport,
+    frames: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl SlmpTransport for CountingTransport {
+    async fn connect(&mut self) -> std::io::Result<()> {
+        self.inner.connect().await
+    }
+
+    async fn close(&mut self) {
+        self.inner.close().await
+    }
+
+    async fn send(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        self.inner.send(frame).await?;
+        self.frames.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn recv(&mut self) -> std::io::Result<Vec<u8>> {
+        self.inner.recv().await
+    }
+}
+
+#[tokio::test]
+async fn custom_transport() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let frames = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let transport = CountingTransport { inner: TcpTransport::new(&props), frames: frames.clone() };
+
+    let client: SLMPClient<CountingTransport> = SLMPClient::with_transport(props, transport);
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+    assert_eq!(client.get_cpu_type().await.unwrap_err().kind(), std::io::ErrorKind::NotConnected);
+
+    client.connect().await.unwrap();
+    client.bulk_write(d0, &[TypedData::U16(0x1234)]).await.unwrap();
+    let data = client.bulk_read(d0, 1, DataType::U16).await.unwrap();
+    assert_eq!(data[0].data, TypedData::U16(0x1234));
+    assert_eq!(frames.load(std::sync::atomic::Ordering::Relaxed), 2);
+
+    client.close().await;
+    assert_eq!(client.get_cpu_type().await.unwrap_err().kind(), std::io::ErrorKind::NotConnected);
+}
+
+device_struct! {
+    #[derive(Clone, Debug, PartialEq)]
+    struct Recipe {
+        speed: u16 => U16,
+        temperature: f32 => F32,
+        name: PLCString => String(3),
+        enabled: bool => Bool,
+        offset: i32 => I32,
+    }
+}
+
+#[tokio::test]
+async fn struct_mapping() {
+    let (_server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let d100 = Device { device_type: DeviceType::D, address: 100 };
+
+    assert_eq!(Recipe::word_size(), 9);
+    assert_eq!(Recipe::word_offsets(), vec![0, 1, 3, 6, 7]);
+
+    let recipe = Recipe {
+        speed: 1500,
+        temperature: 62.5,
+        name: PLCString::from(("ABCDE", 3)),
+        enabled: true,
+        offset: -40,
+    };
+    client.write_struct(d100, &recipe).await.unwrap();
+    assert_eq!(client.read_struct::<Recipe>(d100).await.unwrap(), recipe);
+
+    // The fields follow each other word by word.
+    let words: Vec<TypedData> = client.bulk_read(d100, 9, DataType::U16).await.unwrap().into_iter().map(|x| x.data).collect();
+    assert_eq!(words[0], TypedData::U16(1500));
+    assert_eq!(words[1..3], [TypedData::U16(0x0000), TypedData::U16(0x427a)]);
+    assert_eq!(words[3..6], [TypedData::U16(0x4241), TypedData::U16(0x4443), TypedData::U16(0x0045)]);
+    assert_eq!(words[6], TypedData::U16(1));
+    assert_eq!(words[7..9], [TypedData::U16(0xffd8), TypedData::U16(0xffff)]);
+
+    // A shorter string is padded to the size of its field.
+    let short = Recipe { name: PLCString::from(("X", 1)), ..recipe.clone() };
+    client.write_struct(d100, &short).await.unwrap();
+    assert_eq!(client.read_struct::<Recipe>(d100).await.unwrap().name.as_str(), "X");
+    assert_eq!(client.read_struct::<Recipe>(d100).await.unwrap().offset, -40);
+}
+
+/// Values not matching the layout.
+struct Mismatch;
+
+impl SlmpLayout for Mismatch {
+    const FIELDS: &'static [DataType] = &[DataType::U16, DataType::F32];
+}
+
+impl SlmpWritable for Mismatch {
+    fn to_fields(&self) -> Vec<TypedData> {
+        vec![TypedData::U16(1), TypedData::U32(2)]
+    }
+}
+
+#[tokio::test]
+async fn struct_mapping_mismatch() {
+    let (_server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+
+    let err = client.write_struct(d0, &Mismatch).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[tokio::test]
+async fn raw_word_access() {
+    let (_server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let d10 = Device { device_type: DeviceType::D, address: 10 };
+    let d11 = Device { device_type: DeviceType::D, address: 11 };
+
+    client.bulk_write_words(d10, &[0x1234, 0xabcd, 0x0001]).await.unwrap();
+    assert_eq!(client.bulk_read_words(d10, 3).await.unwrap(), vec![0x1234, 0xabcd, 0x0001]);
+    assert_eq!(client.bulk_read_bytes(d11, 2).await.unwrap(), vec![0xcd, 0xab, 0x01, 0x00]);
+
+    // Same words as the typed access.
+    let data = client.bulk_read(d10, 1, DataType::U32).await.unwrap();
+    assert_eq!(data[0].data, TypedData::U32(0xabcd1234));
+
+    assert_eq!(client.bulk_write_words(d10, &[]).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(client.bulk_read_words(d10, 0).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[tokio::test]
+async fn string_access() {
+    let (_server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+
+    // "あ" (0x82 0xa0) straddles D0 and D1.
+    client.write_string(d0, "aあい", 3).await.unwrap();
+    assert_eq!(client.bulk_read_words(d0, 3).await.unwrap(), vec![0x8261, 0x82a0, 0x00a2]);
+    assert_eq!(client.read_string(d0, 3).await.unwrap(), "aあい");
+
+    // The rest of the words is padded with NULs, which are stripped on read.
+    client.write_string(d0, "b", 3).await.unwrap();
+    assert_eq!(client.bulk_read_words(d0, 3).await.unwrap(), vec![0x0062, 0x0000, 0x0000]);
+    assert_eq!(client.read_string(d0, 3).await.unwrap(), "b");
+
+    let invalid_input = |ret: std::io::Result<()>| ret.unwrap_err().kind() == std::io::ErrorKind::InvalidInput;
+    assert!(invalid_input(client.write_string(d0, "aあい", 2).await));
+    assert!(invalid_input(client.write_string(d0, "\u{1F600}", 4).await));
+    assert!(invalid_input(client.write_string(d0, "", 0).await));
+    assert_eq!(client.read_string(d0, 0).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(client.read_string(d0, 3).await.unwrap(), "b");
+}
+
+#[tokio::test]
+async fn long_string_access() {
+    let (_server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+    let text: String = "fault ".repeat(20);
+
+    client.write_string(d0, &text, 64).await.unwrap();
+    assert_eq!(client.read_string(d0, 64).await.unwrap(), text);
+
+    let data = client.bulk_read(d0, 1, DataType::String(64)).await.unwrap();
+    assert_eq!(data[0].data, TypedData::from((text.as_str(), 64)));
+}
+
+#[tokio::test]
+async fn string_encoding_access() {
+    let (_server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+    let d10 = Device { device_type: DeviceType::D, address: 10 };
+
+    client.bulk_write(d0, &[TypedData::from(("Füllstand", 10, StringEncoding::Utf16Le))]).await.unwrap();
+    client.bulk_write(d10, &[TypedData::from(("PUMP-1", 4, StringEncoding::Ascii))]).await.unwrap();
+
+    let data = client.random_read(&[
+        TypedDevice { device: d0, data_type: DataType::WString(10) },
+        TypedDevice { device: d10, data_type: DataType::AsciiString(4) },
+    ]).await.unwrap();
+    let text = |data: &DeviceData| PLCString::try_from(data.data.clone()).unwrap().to_string();
+    assert_eq!(text(&data[0]), "Füllstand");
+    assert_eq!(text(&data[1]), "PUMP-1");
+}
+
+#[tokio::test]
+async fn word_order() {
+    let (_server, mut client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let d = |address| Device { device_type: DeviceType::D, address };
+    client.set_word_order(WordOrder::HighLow);
+
+    client.bulk_write(d(0), &[TypedData::U32(0x1234_5678), TypedData::U16(0xabcd), TypedData::F32(1.5)]).await.unwrap();
+    assert_eq!(client.bulk_read_words(d(0), 5).await.unwrap(), vec![0x1234, 0x5678, 0xabcd, 0x3fc0, 0x0000]);
+
+    client.random_write(&[DeviceData { device: d(10), data: TypedData::I32(-2) }]).await.unwrap();
+    assert_eq!(client.bulk_read_words(d(10), 2).await.unwrap(), vec![0xffff, 0xfffe]);
+
+    let f64_words = [TypedData::F64(-2.0)];
+    client.block_write(&[BlockedDeviceData { access_type: AccessType::Word, start_device: d(20), data: &f64_words }]).await.unwrap();
+    assert_eq!(client.bulk_read_words(d(20), 4).await.unwrap(), vec![0xc000, 0x0000, 0x0000, 0x0000]);
+
+    // Reads take the same order back.
+    let data = client.bulk_read(d(0), 1, DataType::U32).await.unwrap();
+    assert_eq!(data[0].data, TypedData::U32(0x1234_5678));
+    let data = client.random_read(&[
+        TypedDevice { device: d(3), data_type: DataType::F32 },
+        TypedDevice { device: d(10), data_type: DataType::I32 },
+        TypedDevice { device: d(20), data_type: DataType::F64 },
+    ]).await.unwrap();
+    assert_eq!(data.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), [TypedData::F32(1.5), TypedData::I32(-2), TypedData::F64(-2.0)]);
+
+    // A clone keeps its own order.
+    let mut native = client.clone();
+    native.set_word_order(WordOrder::LowHigh);
+    let data = native.bulk_read(d(0), 1, DataType::U32).await.unwrap();
+    assert_eq!(data[0].data, TypedData::U32(0x5678_1234));
+    assert_eq!(client.word_order(), WordOrder::HighLow);
+}
+
+#[tokio::test]
+async fn sixty_four_bit_integers() {
+    let (_server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let d = |address| Device { device_type: DeviceType::D, address };
+    let total: i64 = -9_007_199_254_740_993; // Not representable in f64.
+
+    client.bulk_write(d(0), &[TypedData::I64(total), TypedData::U64(u64::MAX - 1)]).await.unwrap();
+    assert_eq!(client.bulk_read_words(d(0), 4).await.unwrap(), vec![0xffff, 0xffff, 0xffff, 0xffdf]);
+
+    let data = client.bulk_read(d(0), 2, DataType::I64).await.unwrap();
+    assert_eq!(data[0].data, TypedData::I64(total));
+    assert_eq!(data[1].device, d(4));
+
+    let devices = [
+        TypedDevice { device: d(0), data_type: DataType::I64 },
+        TypedDevice { device: d(4), data_type: DataType::U64 },
+        TypedDevice { device: d(8), data_type: DataType::U16 },
+    ];
+    let data = client.random_read(&devices).await.unwrap();
+    assert_eq!(data.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), [TypedData::I64(total), TypedData::U64(u64::MAX - 1), TypedData::U16(0)]);
+
+    let monitor_list = client.monitor_register(&devices).await.unwrap();
+    let data = client.monitor_read(&monitor_list).await.unwrap();
+    assert_eq!(data[0].data, TypedData::I64(total));
+    assert_eq!(data[1].data, TypedData::U64(u64::MAX - 1));
+
+    client.random_write(&[DeviceData { device: d(20), data: TypedData::U64(1 << 40) }]).await.unwrap();
+    assert_eq!(client.bulk_read_words(d(20), 4).await.unwrap(), vec![0, 0, 0x0100, 0]);
+}
+
+#[tokio::test]
+async fn bcd_access() {
+    let (_server, client) = MockServer::start_connected(CPU::Q).await.unwrap();
+    let d = |address| Device { device_type: DeviceType::D, address };
+
+    client.bulk_write(d(0), &[TypedData::Bcd16(1234), TypedData::Bcd32(20_251_016)]).await.unwrap();
+    assert_eq!(client.bulk_read_words(d(0), 3).await.unwrap(), vec![0x1234, 0x1016, 0x2025]);
+
+    let data = client.bulk_read(d(0), 1, DataType::Bcd16).await.unwrap();
+    assert_eq!(data[0].data, TypedData::Bcd16(1234));
+
+    let devices = [
+        TypedDevice { device: d(0), data_type: DataType::Bcd16 },
+        TypedDevice { device: d(1), data_type: DataType::Bcd32 },
+    ];
+    let data = client.random_read(&devices).await.unwrap();
+    assert_eq!(data.iter().map(|x| x.data.clone()).collect::<Vec<_>>(), [TypedData::Bcd16(1234), TypedData::Bcd32(20_251_016)]);
+    let monitor_list = client.monitor_register(&devices).await.unwrap();
+    assert_eq!(client.monitor_read(&monitor_list).await.unwrap().len(), 2);
+
+    // A digit over 9 in the response.
+    client.bulk_write_words(d(0), &[0x12a4]).await.unwrap();
+    let invalid_data = |ret: std::io::Result<Vec<DeviceData>>| ret.unwrap_err().kind() == std::io::ErrorKind::InvalidData;
+    assert!(invalid_data(client.bulk_read(d(0), 1, DataType::Bcd16).await));
+    assert!(invalid_data(client.random_read(&devices).await));
+    assert!(invalid_data(client.monitor_read(&monitor_list).await));
+
+    let invalid_input = |ret: std::io::Result<()>| ret.unwrap_err().kind() == std::io::ErrorKind::InvalidInput;
+    assert!(invalid_input(client.bulk_write(d(0), &[TypedData::Bcd16(10_000)]).await));
+    assert!(invalid_input(client.random_write(&[DeviceData { device: d(1), data: TypedData::Bcd32(100_000_000) }]).await));
+    assert_eq!(client.bulk_read_words(d(0), 1).await.unwrap(), vec![0x12a4]);
+}
+
+#[tokio::test]
+async fn device_limits() {
+    let (_server, mut client) = MockServer::start_connected(CPU::Q).await.unwrap();
+    let d = |address| Device { device_type: DeviceType::D, address };
+    let zr = |address| Device { device_type: DeviceType::ZR, address };
+    let out_of_range = |ret: std::io::Result<()>| ret.unwrap_err().kind() == std::io::ErrorKind::InvalidInput;
+
+    // Rejected before anything is sent.
+    let e = client.bulk_write(d(9_000_000), &[TypedData::U16(1)]).await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(e.to_string(), "D9000000 is out of the device range (max: D4910079)");
+    // The last device of a request counts, e.g. the second word of a U32.
+    let e = client.bulk_read(d(4_910_079), 1, DataType::U32).await.unwrap_err();
+    assert_eq!(e.to_string(), "D4910079 to D4910080 is out of the device range (max: D4910079)");
+    let x = Device { device_type: DeviceType::X, address: 0x1ff0 };
+    assert!(out_of_range(client.bulk_read(x, 2, DataType::U16).await.map(|_| ())));
+    assert!(out_of_range(client.random_read(&[TypedDevice { device: d(5_000_000), data_type: DataType::U16 }]).await.map(|_| ())));
+    assert!(out_of_range(client.block_read(&[DeviceBlock { access_type: AccessType::Word, start_device: d(4_910_079), size: 2 }]).await.map(|_| ())));
+    assert!(out_of_range(client.random_write(&[DeviceData { device: d(5_000_000), data: TypedData::U16(1) }]).await));
+    assert!(out_of_range(client.monitor_register(&[TypedDevice { device: d(5_000_000), data_type: DataType::U16 }]).await.map(|_| ())));
+    assert!(out_of_range(client.write_string(d(4_910_079), "AB", 2).await));
+    assert_eq!(client.transferred_bytes(), (0, 0));
+
+    // File registers follow the parameters of the CPU.
+    client.set_device_limits(DeviceLimits::new(CPU::Q).with_max_address(DeviceType::ZR, 32767));
+    assert!(out_of_range(client.bulk_write(zr(32767), &[TypedData::U32(1)]).await));
+    client.bulk_write(zr(32766), &[TypedData::U32(0x12345678)]).await.unwrap();
+    assert_eq!(client.bulk_read(zr(32766), 1, DataType::U32).await.unwrap()[0].data, TypedData::U32(0x12345678));
+
+    // iQ-R takes larger devices.
+    let (_server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    client.bulk_write(d(9_000_000), &[TypedData::U16(1)]).await.unwrap();
+    assert!(out_of_range(client.bulk_write(d(Device::MAX_ADDRESS), &[TypedData::U32(1)]).await));
+}
+
+#[tokio::test]
+async fn fx5_device_set() {
+    let (server, mut client) = MockServer::start_connected(CPU::F).await.unwrap();
+    assert_eq!(client.verify_cpu_type().await.unwrap().family, Some(CPU::F));
+
+    // X and Y are numbered in octal: X1777 is the last input.
+    let x = Device { device_type: DeviceType::X, address: 0o1777 };
+    server.set_bit(x, true).unwrap();
+    assert_eq!(client.bulk_read(x, 1, DataType::Bool).await.unwrap()[0].data, TypedData::Bool(true));
+    let e = client.bulk_read(x + 1, 1, DataType::Bool).await.unwrap_err();
+    assert_eq!(e.to_string(), "X400 is out of the device range (max: X3FF)");
+    let e = client.bulk_read(Device { device_type: DeviceType::D, address: 8000 }, 1, DataType::U16).await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+
+    // No file registers ZR nor link direct devices.
+    let sent = client.transferred_bytes().0;
+    for device_type in [DeviceType::ZR, DeviceType::V, DeviceType::DX, DeviceType::DY] {
+        let e = client.bulk_read(Device { device_type, address: 0 }, 1, DataType::U16).await.unwrap_err();
+        assert_eq!(e.to_string(), format!("{device_type:?} is not available on this CPU"));
+    }
+    assert_eq!(client.transferred_bytes().0, sent);
+    client.set_device_limits(DeviceLimits::new(CPU::F).with_max_address(DeviceType::ZR, 32767));
+    client.bulk_write(Device { device_type: DeviceType::ZR, address: 0 }, &[TypedData::U16(1)]).await.unwrap();
+
+    // The remote password takes 6 to 32 characters as on iQ-R.
+    assert_eq!(client.unlock_cpu("pass").await.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+    client.unlock_cpu("password").await.unwrap();
+}
+
+#[tokio::test]
+async fn device_access_types() {
+    let (_server, client) = MockServer::start_connected(CPU::Q).await.unwrap();
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+    let ts0 = Device { device_type: DeviceType::TS, address: 0 };
+    let tn0 = Device { device_type: DeviceType::TN, address: 0 };
+    let invalid_input = |ret: std::io::Result<()>| ret.unwrap_err().kind() == std::io::ErrorKind::InvalidInput;
+
+    // Rejected before anything is sent.
+    let e = client.bulk_read(d0, 1, DataType::Bool).await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(e.to_string(), "Bit access to D0: D is a word device (access it in words, e.g. as U16)");
+    let e = client.bulk_read(ts0, 1, DataType::U16).await.unwrap_err();
+    assert_eq!(e.to_string(), "Word access to TS0: TS is a timer or counter contact accessed in bits (Bool)");
+    assert!(invalid_input(client.bulk_write(d0, &[TypedData::Bool(true)]).await));
+    assert!(invalid_input(client.random_write(&[DeviceData { device: tn0, data: TypedData::Bool(true) }]).await));
+    assert!(invalid_input(client.random_read(&[TypedDevice { device: ts0, data_type: DataType::Bool }]).await.map(|_| ())));
+    assert!(invalid_input(client.monitor_register(&[TypedDevice { device: ts0, data_type: DataType::U16 }]).await.map(|_| ())));
+    assert!(invalid_input(client.block_read(&[DeviceBlock { access_type: AccessType::Bit, start_device: d0, size: 16 }]).await.map(|_| ())));
+    let data = [TypedData::U16(1)];
+    assert!(invalid_input(client.block_write(&[BlockedDeviceData { access_type: AccessType::Word, start_device: ts0, data: &data }]).await));
+    assert_eq!(client.transferred_bytes(), (0, 0));
+
+    // Contacts in bits and current values in words are accepted.
+    client.bulk_write(ts0, &[TypedData::Bool(true)]).await.unwrap();
+    assert_eq!(client.bulk_read(ts0, 1, DataType::Bool).await.unwrap()[0].data, TypedData::Bool(true));
+    client.bulk_write(tn0, &[TypedData::U16(30)]).await.unwrap();
+    assert_eq!(client.bulk_read(tn0, 1, DataType::U16).await.unwrap()[0].data, TypedData::U16(30));
+}
+
+/// End code response to the request of `header`.
+fn end_code_frame(header: &[u8; 13], end_code: u16) -> Vec<u8> {
+    let mut response: Vec<u8> = vec![0xd4, 0x00];
+    response.extend(&header[2..11]);
+    response.extend(11u16.to_le_bytes());
+    response.extend(end_code.to_le_bytes());
+    response.extend([0x00, 0xff, 0xff, 0x03, 0x00, 0x01, 0x04, 0x00, 0x00]);
+    response
+}
+
+/// Transport failing its first `broken` sends with a broken pipe.
+struct BrokenTransport {
+    inner: TcpTransport,
+    broken: usize,
+    connects: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl SlmpTransport for BrokenTransport {
+    async fn connect(&mut self) -> std::io::Result<()> {
+        self.connects.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.inner.connect().await
+    }
+
+    async fn close(&mut self) {
+        self.inner.close().await
+    }
+
+    async fn send(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        if self.broken > 0 {
+            self.broken -= 1;
+            self.inner.close().await;
+            return Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "Broken pipe"));
+        }
+        self.inner.send(frame).await
+    }
+
+    async fn recv(&mut self) -> std::io::Result<Vec<u8>> {
+        self.inner.recv().await
+    }
+}
+
+#[tokio::test]
+async fn retry_policy() {
+    use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+    use std::time::Duration;
+
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+    let policy = RetryPolicy::new(3, Duration::from_millis(10));
+
+    // Busy twice, then a response, then busy forever.
+    let requests = Arc::new(AtomicUsize::new(0));
+    let count = requests.clone();
+    let mut client = canned_frame(CPU::Q, move |header| {
+        match count.fetch_add(1, Ordering::Relaxed) {
+            2 => {
+                let mut response: Vec<u8> = vec![0xd4, 0x00];
+                response.extend(&header[2..11]);
+                response.extend(4u16.to_le_bytes());
+                response.extend([0x00, 0x00, 0x0a, 0x00]);
+                response
+            },
+            _ => end_code_frame(header, 0xCEE0),
+        }
+    }).await;
+    let err = client.bulk_read(d0, 1, DataType::U16).await.unwrap_err();
+    assert_eq!(SLMPEndCode::from_io_error(&err), Some(SLMPEndCode::BUSY));
+    assert!(RetriesExhausted::from_io_error(&err).is_none());
+
+    client.set_retry_policy(policy);
+    assert_eq!(client.bulk_read(d0, 1, DataType::U16).await.unwrap()[0].data, TypedData::U16(10));
+    assert_eq!(requests.load(Ordering::Relaxed), 3);
+
+    // The final error carries the attempt count and the last error.
+    let err = client.bulk_read(d0, 1, DataType::U16).await.unwrap_err();
+    assert_eq!(requests.load(Ordering::Relaxed), 6);
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(SLMPEndCode::from_io_error(&err), Some(SLMPEndCode::BUSY));
+    let retries = RetriesExhausted::from_io_error(&err).unwrap();
+    assert_eq!(retries.attempts, 3);
+    assert_eq!(SLMPEndCode::from_io_error(&retries.last_error), Some(SLMPEndCode::BUSY));
+
+    // WrongCommand and WrongFormat are not retried.
+    for end_code in [SLMPEndCode::WRONG_COMMAND, SLMPEndCode::WRONG_FORMAT] {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let count = requests.clone();
+        let mut client = canned_frame(CPU::Q, move |header| {
+            count.fetch_add(1, Ordering::Relaxed);
+            end_code_frame(header, end_code.0)
+        }).await;
+        client.set_retry_policy(policy);
+        let err = client.bulk_read(d0, 1, DataType::U16).await.unwrap_err();
+        assert_eq!(SLMPEndCode::from_io_error(&err), Some(end_code));
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+    }
+
+    // Timeouts are retried, and a late response to a previous attempt is skipped.
+    let (server, mut client) = MockServer::start_connected(CPU::R).await.unwrap();
+    client.set_recv_timeout(Duration::from_millis(50));
+    client.set_retry_policy(RetryPolicy { max_attempts: 2, ..policy });
+    server.set_response_delay(Duration::from_millis(200));
+    let err = client.bulk_read(d0, 1, DataType::U16).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    assert_eq!(RetriesExhausted::from_io_error(&err).unwrap().attempts, 2);
+    client.set_retry_policy(RetryPolicy { retry_on: RetryOn { timeout: false, ..RetryOn::ALL }, ..policy });
+    let err = client.bulk_read(d0, 1, DataType::U16).await.unwrap_err();
+    assert!(RetriesExhausted::from_io_error(&err).is_none());
+
+    // The client reconnects on a broken pipe before the retry.
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let connects = Arc::new(AtomicUsize::new(0));
+    let transport = BrokenTransport { inner: TcpTransport::new(&props), broken: 1, connects: connects.clone() };
+    let mut client = SLMPClient::with_transport(props, transport);
+    client.set_retry_policy(policy);
+    client.connect().await.unwrap();
+    client.bulk_write(d0, &[TypedData::U16(7)]).await.unwrap();
+    assert_eq!(connects.load(Ordering::Relaxed), 2);
+    assert_eq!(server.word(d0), 7);
+}
+
+#[tokio::test]
+async fn verified_writes() {
+    let d = |address| Device { device_type: DeviceType::D, address };
+    let m = |address| Device { device_type: DeviceType::M, address };
+
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let frames = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let transport = CountingTransport { inner: TcpTransport::new(&props), frames: frames.clone() };
+    let mut client = SLMPClient::with_transport(props, transport);
+    client.connect().await.unwrap();
+
+    // The read back is sent only by the verified writes.
+    client.bulk_write(d(0), &[TypedData::U16(1)]).await.unwrap();
+    assert_eq!(frames.load(std::sync::atomic::Ordering::Relaxed), 1);
+    let data = [TypedData::U16(1), TypedData::F32(-0.0), TypedData::U32(70000), TypedData::F64(f64::NAN)];
+    client.bulk_write_verified(d(0), &data).await.unwrap();
+    assert_eq!(frames.load(std::sync::atomic::Ordering::Relaxed), 3);
+    client.bulk_write_verified(m(3), &[TypedData::Bool(true), TypedData::Bool(false), TypedData::Bool(true)]).await.unwrap();
+
+    client.set_word_order(WordOrder::HighLow);
+    client.bulk_write_verified(d(10), &[TypedData::I32(-2), TypedData::Bcd32(12345678)]).await.unwrap();
+    assert_eq!(server.word(d(10)), 0xffff);
+
+    let data = [
+        DeviceData { device: m(20), data: TypedData::Bool(true) },
+        DeviceData { device: d(20), data: TypedData::F64(1.25) },
+        DeviceData { device: d(30), data: TypedData::Bcd16(1234) },
+        DeviceData { device: d(31), data: TypedData::U32(0x12345678) },
+    ];
+    client.random_write_verified(&data).await.unwrap();
+
+    let words = [TypedData::U16(7), TypedData::F32(2.5)];
+    let bits = [TypedData::Bool(false), TypedData::Bool(true)];
+    let blocks = [
+        BlockedDeviceData { access_type: AccessType::Bit, start_device: m(40), data: &bits },
+        BlockedDeviceData { access_type: AccessType::Word, start_device: d(50), data: &words },
+        BlockedDeviceData { access_type: AccessType::Word, start_device: d(40), data: &words },
+    ];
+    client.block_write_verified(&blocks).await.unwrap();
+
+    // A request rejected before the write sends nothing.
+    let sent = frames.load(std::sync::atomic::Ordering::Relaxed);
+    let ts = Device { device_type: DeviceType::TS, address: 0 };
+    assert!(client.random_write_verified(&[DeviceData { device: ts, data: TypedData::Bool(true) }]).await.is_err());
+    assert_eq!(frames.load(std::sync::atomic::Ordering::Relaxed), sent);
+
+    // A CPU reading back zeros: the values are compared bit-exact.
+    let client = canned_response(CPU::Q, vec![0x00; 12]).await;
+    let err = client.bulk_write_verified(d(0), &[TypedData::F32(-0.0), TypedData::F32(0.0), TypedData::U32(5)]).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let failed = VerificationFailed::from_io_error(&err).unwrap();
+    assert_eq!(failed.mismatches, [
+        DeviceMismatch { device: d(0), expected: TypedData::F32(-0.0), actual: TypedData::F32(0.0) },
+        DeviceMismatch { device: d(4), expected: TypedData::U32(5), actual: TypedData::U32(0) },
+    ]);
+
+    // A CPU reading back 0x000A for Bcd16(10) (0x0010): a word that is not BCD is not taken for the value written.
+    let client = canned_response(CPU::Q, vec![0x0a, 0x00]).await;
+    let err = client.bulk_write_verified(d(0), &[TypedData::Bcd16(10)]).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let err = client.random_write_verified(&[DeviceData { device: d(0), data: TypedData::Bcd16(10) }]).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[tokio::test]
+async fn fill() {
+    let d = |address| Device { device_type: DeviceType::D, address };
+    let m = |address| Device { device_type: DeviceType::M, address };
+
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let frames = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let transport = CountingTransport { inner: TcpTransport::new(&props), frames: frames.clone() };
+    let mut client = SLMPClient::with_transport(props, transport);
+    client.connect().await.unwrap();
+
+    // 2000 words in 960 + 960 + 80.
+    let mut progress: Vec<(usize, usize)> = Vec::new();
+    client.fill_with_progress(d(0), 2000, TypedData::U16(0xABCD), |written, total| progress.push((written, total))).await.unwrap();
+    assert_eq!(progress, [(960, 2000), (1920, 2000), (2000, 2000)]);
+    assert_eq!(frames.load(std::sync::atomic::Ordering::Relaxed), 3);
+    assert_eq!(server.word(d(0)), 0xABCD);
+    assert_eq!(server.word(d(1999)), 0xABCD);
+    assert_eq!(server.word(d(2000)), 0);
+
+    // Double words do not straddle the requests.
+    client.set_word_order(WordOrder::HighLow);
+    client.fill(d(3000), 500, TypedData::U32(0x12345678)).await.unwrap();
+    assert_eq!((server.word(d(3000)), server.word(d(3001))), (0x1234, 0x5678));
+    assert_eq!((server.word(d(3998)), server.word(d(3999))), (0x1234, 0x5678));
+    assert_eq!(server.word(d(4000)), 0);
+
+    // Bits, in an odd count.
+    client.fill(m(0), 7169, TypedData::Bool(true)).await.unwrap();
+    assert!(server.bit(m(0)).unwrap() && server.bit(m(7168)).unwrap());
+    assert!(!server.bit(m(7169)).unwrap());
+    client.clear_bits(m(1), 7167).await.unwrap();
+    assert!(server.bit(m(0)).unwrap() && server.bit(m(7168)).unwrap());
+    assert!(!server.bit(m(1)).unwrap() && !server.bit(m(7167)).unwrap());
+
+    // The whole range is checked before anything is sent.
+    let sent = frames.load(std::sync::atomic::Ordering::Relaxed);
+    let limit = DeviceLimits::new(CPU::R).max_address(DeviceType::D);
+    assert_eq!(client.fill(d(0), limit + 2, TypedData::U16(0)).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(client.fill(d(0), 0, TypedData::U16(0)).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(frames.load(std::sync::atomic::Ordering::Relaxed), sent);
+}
+
+#[tokio::test]
+async fn word_bits() {
+    let d = |address| Device { device_type: DeviceType::D, address };
+    let m = |address| Device { device_type: DeviceType::M, address };
+    let (server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+
+    server.set_word(d(100), 0x1234);
+    let previous = client.set_word_bit(d(100), 5, true).await.unwrap();
+    assert_eq!(TypedData::BitArray16(previous), TypedData::from((&0x1234u16.to_le_bytes()[..], DataType::BitArray16)));
+    assert_eq!(server.word(d(100)), 0x1234 | 0x20);
+    client.set_word_bit(d(100), 15, false).await.unwrap();
+    client.set_word_bit(d(100), 4, false).await.unwrap();
+    assert_eq!(server.word(d(100)), 0x1224);
+
+    let previous = client.modify_word(d(100), 0x00FF, 0xA000).await.unwrap();
+    assert!(previous[2] && !previous[3]);
+    assert_eq!(server.word(d(100)), 0xA024);
+
+    // 16 points of a bit device.
+    client.set_word_bit(m(16), 3, true).await.unwrap();
+    assert!(server.bit(m(19)).unwrap());
+
+    // Checked before the read.
+    assert_eq!(client.set_word_bit(d(100), 16, true).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    let ts = Device { device_type: DeviceType::TS, address: 0 };
+    assert_eq!(client.modify_word(ts, 0, 1).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[tokio::test]
+async fn snapshot_and_restore() {
+    let d = |address| Device { device_type: DeviceType::D, address };
+    let m = |address| Device { device_type: DeviceType::M, address };
+    let (server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+
+    for i in 0..1000 {
+        server.set_word(d(i), i as u16);
+    }
+    server.set_bit(m(3), true).unwrap();
+    server.set_bit(m(7999), true).unwrap();
+    let blocks = [
+        DeviceBlock { access_type: AccessType::Word, start_device: d(0), size: 1000 },
+        DeviceBlock { access_type: AccessType::Bit, start_device: m(0), size: 8000 },
+    ];
+    let snapshot = client.snapshot(&blocks).await.unwrap();
+    assert_eq!(snapshot.cpu, CPU::R);
+    assert_eq!(snapshot.areas[0].words[999], 999);
+    assert_eq!(snapshot.areas[1].words.len(), 500);
+    assert_eq!((snapshot.areas[1].words[0], snapshot.areas[1].words[499]), (0x0008, 0x8000));
+
+    // Through a file.
+    let json = serde_json::to_string(&snapshot).unwrap();
+    let snapshot: DeviceSnapshot = serde_json::from_str(&json).unwrap();
+
+    client.fill(d(0), 1000, TypedData::U16(0)).await.unwrap();
+    client.clear_bits(m(0), 8000).await.unwrap();
+    server.set_bit(m(8000), true).unwrap();
+    client.restore(&snapshot).await.unwrap();
+    assert_eq!((server.word(d(1)), server.word(d(999))), (1, 999));
+    assert!(server.bit(m(3)).unwrap() && server.bit(m(7999)).unwrap() && !server.bit(m(4)).unwrap());
+    assert!(server.bit(m(8000)).unwrap());
+    assert_eq!(client.snapshot(&blocks).await.unwrap(), snapshot);
+
+    // D5000000 is past the D of a Q CPU: nothing is written.
+    let area = SnapshotArea { access_type: AccessType::Word, start_device: d(5_000_000), size: 1, words: vec![1] };
+    let snapshot = DeviceSnapshot { cpu: CPU::R, areas: vec![SnapshotArea { access_type: AccessType::Word, start_device: d(0), size: 1, words: vec![7] }, area] };
+    let (server, client) = MockServer::start_connected(CPU::Q).await.unwrap();
+    assert_eq!(client.restore(&snapshot).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(server.word(d(0)), 0);
+
+    // Words not matching the size.
+    let snapshot = DeviceSnapshot { cpu: CPU::Q, areas: vec![SnapshotArea { access_type: AccessType::Bit, start_device: m(0), size: 17, words: vec![1] }] };
+    assert_eq!(client.restore(&snapshot).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(client.snapshot(&[]).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[tokio::test]
+async fn dry_run() {
+    let d = |address| Device { device_type: DeviceType::D, address };
+    let m = |address| Device { device_type: DeviceType::M, address };
+
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let frames = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let transport = CountingTransport { inner: TcpTransport::new(&props), frames: frames.clone() };
+    let client = SLMPClient::with_transport(props.clone(), transport);
+    client.connect().await.unwrap();
+    client.bulk_write(d(0), &[TypedData::U16(1)]).await.unwrap();
+
+    // The frames the client sends, numbered from its next serial No.
+    let dry_run = client.dry_run();
+    let ret = dry_run.bulk_write(d(100), &[TypedData::U16(10)]).unwrap();
+    let next = SLMP4EConnectionProps { serial_id: props.serial_id + 1, ..props.clone() };
+    assert_eq!(ret.frames, [frames::build_bulk_write(&next, d(100), &[TypedData::U16(10)]).unwrap()]);
+
+    // Chunked and split requests return every frame in order.
+    let ret = dry_run.fill(d(0), 2000, TypedData::U16(7)).unwrap();
+    let packets = ret.packets();
+    assert_eq!(packets.iter().map(|x| x.serial_id).collect::<Vec<_>>(), [props.serial_id + 1, props.serial_id + 2, props.serial_id + 3]);
+    assert_eq!(packets.iter().map(|x| x.devices().unwrap()[0].points).collect::<Vec<_>>(), [960, 960, 80]);
+    assert_eq!(packets[2].devices().unwrap()[0].device, d(1920));
+
+    let data = [DeviceData { device: m(0), data: TypedData::Bool(true) }, DeviceData { device: d(0), data: TypedData::U32(1) }];
+    assert_eq!(dry_run.random_write(&data).unwrap().frames.len(), 2);
+    let ret = dry_run.bulk_write_verified(d(0), &[TypedData::U16(1)]).unwrap();
+    assert_eq!(ret.packets().iter().map(|x| x.command_name()).collect::<Vec<_>>(), ["BulkWrite", "BulkRead"]);
+    assert!(ret.to_string().contains("Command: 0x1401 (BulkWrite)"));
+    assert_eq!(dry_run.set_clock(PLCDateTime { year: 2026, month: 10, day: 16, hour: 9, minute: 0, second: 0, day_of_week: 5 }).unwrap().frames.len(), 3);
+
+    // Nothing was sent, and invalid requests fail as they do on the client.
+    assert_eq!(frames.load(std::sync::atomic::Ordering::Relaxed), 1);
+    assert_eq!(server.word(d(100)), 0);
+    assert_eq!(dry_run.bulk_write(d(0), &[]).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+    // The actual fill sends the same number of frames.
+    client.fill(d(0), 2000, TypedData::U16(7)).await.unwrap();
+    assert_eq!(frames.load(std::sync::atomic::Ordering::Relaxed), 4);
+}
+
+#[tokio::test]
+async fn record_and_replay() {
+    let d = |address| Device { device_type: DeviceType::D, address };
+
+    // Record a session with the mock server.
+    let server = MockServer::start(CPU::R).await.unwrap();
+    let props = server.connection_props();
+    let transport = RecordingTransport::new(TcpTransport::new(&props));
+    let log = transport.log();
+    let client = SLMPClient::with_transport(props.clone(), transport);
+    client.connect().await.unwrap();
+    client.bulk_write(d(0), &[TypedData::U16(5), TypedData::U16(6)]).await.unwrap();
+    assert_eq!(client.bulk_read_words(d(0), 2).await.unwrap(), [5, 6]);
+    client.bulk_write(d(0), &[TypedData::U16(7), TypedData::U16(8)]).await.unwrap();
+    assert_eq!(client.bulk_read_words(d(0), 2).await.unwrap(), [7, 8]);
+    let log: SessionLog = log.lock().unwrap().clone();
+    assert_eq!(log.exchanges.len(), 4);
+    assert!(log.exchanges.iter().all(|x| x.response.is_some()));
+    drop(server);
+
+    // Through a file.
+    let log: SessionLog = serde_json::from_str(&serde_json::to_string(&log).unwrap()).unwrap();
+
+    // The same requests get the recorded responses without a server.
+    let client = SLMPClient::with_transport(props.clone(), ReplayTransport::new(log.clone(), ReplayMatch::Exact));
+    client.connect().await.unwrap();
+    client.bulk_write(d(0), &[TypedData::U16(5), TypedData::U16(6)]).await.unwrap();
+    assert_eq!(client.bulk_read_words(d(0), 2).await.unwrap(), [5, 6]);
+    // A request not in the log (out of order here, by its serial No.).
+    assert_eq!(client.bulk_read_words(d(0), 2).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+    // Matching the commands only: another serial No., and a repeated read gets the responses in turn.
+    let props = SLMP4EConnectionProps { serial_id: 0x1234, ..props };
+    let mut client = SLMPClient::with_transport(props.clone(), ReplayTransport::new(log, ReplayMatch::Command));
+    client.set_recv_timeout(std::time::Duration::from_millis(50));
+    client.connect().await.unwrap();
+    assert_eq!(client.bulk_read_words(d(0), 2).await.unwrap(), [5, 6]);
+    assert_eq!(client.bulk_read_words(d(0), 2).await.unwrap(), [7, 8]);
+    assert_eq!(client.bulk_read_words(d(0), 2).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+    // A request recorded without a response times out.
+    let request = frames::build_bulk_read(&props, d(0), 1, DataType::U16).unwrap();
+    let log = SessionLog { exchanges: vec![RecordedExchange { request, response: None }] };
+    let mut client = SLMPClient::with_transport(props, ReplayTransport::new(log, ReplayMatch::Exact));
+    client.set_recv_timeout(std::time::Duration::from_millis(50));
+    client.connect().await.unwrap();
+    assert_eq!(client.bulk_read(d(0), 1, DataType::U16).await.unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+}
+
+#[tokio::test]
+async fn label_round_trip() {
+    let (server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let values = [
+        ("Speed", TypedData::U16(1500)),
+        ("Position", TypedData::I32(-70000)),
+        ("Total", TypedData::U32(70000)),
+        ("Ready", TypedData::Bool(true)),
+        ("Recipe[3].Name", TypedData::from(("ABC", 4))),
+        ("Title", TypedData::from(("Aé", 4, StringEncoding::Utf16Le))),
+    ];
+    for (name, value) in &values {
+        let initial = TypedData::from((&vec![0u8; value.to_bytes().len().max(2)][..], value.get_type()));
+        server.set_label(name, initial).unwrap();
+    }
+    assert_eq!(server.set_label("Count", TypedData::U64(0)).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+    client.write_labels(&values).await.unwrap();
+    for (name, value) in &values {
+        assert_eq!(server.label(name).as_ref(), Some(value));
+    }
+
+    let names: Vec<&str> = values.iter().map(|x| x.0).collect();
+    let read = client.read_labels(&names).await.unwrap();
+    for ((name, value), (read_name, read_value)) in values.iter().zip(read) {
+        assert_eq!(read_name, *name);
+        assert_eq!(&read_value.unwrap(), value);
+    }
+}
+
+#[tokio::test]
+async fn label_write_not_split() {
+    let (server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    server.set_label("Speed", TypedData::U16(1)).unwrap();
+
+    // A batch with a missing label is rejected as a whole, and nothing of it is written.
+    let sent = client.transferred_bytes().0;
+    let e = client.write_labels(&[("Speed", TypedData::U16(2)), ("Missing", TypedData::U16(3))]).await.unwrap_err();
+    assert_eq!(SLMPEndCode::from_io_error(&e), Some(SLMPEndCode(0x40C0)));
+    let single = client.dry_run().write_labels(&[("Speed", TypedData::U16(2)), ("Missing", TypedData::U16(3))]).unwrap();
+    assert_eq!(client.transferred_bytes().0 - sent, single.frames[0].len() as u64);
+    assert_eq!(server.label("Speed"), Some(TypedData::U16(1)));
+
+    // Reads are retried label by label, so that only the missing one fails.
+    let read = client.read_labels(&["Speed", "Missing"]).await.unwrap();
+    assert_eq!(read[0].1.as_ref().unwrap(), &TypedData::U16(1));
+    assert!(SLMPEndCode::from_io_error(read[1].1.as_ref().unwrap_err()).is_some());
+}
+
+#[tokio::test]
+async fn label_data_types() {
+    // A response of random label read with one label of `type_id`.
+    async fn read(type_id: u8, data: &[u8]) -> std::io::Result<TypedData> {
+        let mut body: Vec<u8> = 1u16.to_le_bytes().to_vec();
+        body.extend([type_id, 0x00]);
+        body.extend((data.len() as u16).to_le_bytes());
+        body.extend(data);
+        let client = canned_response(CPU::R, body).await;
+        client.read_labels(&["Label"]).await.unwrap().pop().unwrap().1
+    }
+
+    assert_eq!(read(0x01, &[0x01, 0x00]).await.unwrap(), TypedData::Bool(true));
+    assert_eq!(read(0x01, &[0x01]).await.unwrap(), TypedData::Bool(true));
+    assert_eq!(read(0x02, &[0x34, 0x12]).await.unwrap(), TypedData::U16(0x1234));
+    assert_eq!(read(0x03, &70000u32.to_le_bytes()).await.unwrap(), TypedData::U32(70000));
+    assert_eq!(read(0x04, &0.5f32.to_le_bytes()).await.unwrap(), TypedData::F32(0.5));
+    assert_eq!(read(0x05, &0.25f64.to_le_bytes()).await.unwrap(), TypedData::F64(0.25));
+    assert_eq!(read(0x06, b"ABC").await.unwrap(), TypedData::from(("ABC", 2)));
+    assert_eq!(read(0x07, &[0x41, 0x00, 0xE9, 0x00]).await.unwrap(), TypedData::from(("Aé", 2, StringEncoding::Utf16Le)));
+    assert_eq!(read(0x08, &[0xFF, 0xFF]).await.unwrap(), TypedData::I16(-1));
+    assert_eq!(read(0x09, &(-2i32).to_le_bytes()).await.unwrap(), TypedData::I32(-2));
+
+    // Lengths a type cannot have are not truncated to fit.
+    let invalid = |x: std::io::Result<TypedData>| assert_eq!(x.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    invalid(read(0x06, &[0x41; 512]).await);
+    invalid(read(0x07, &[0x41; 3]).await);
+    invalid(read(0x06, &[]).await);
+    invalid(read(0x02, &[0x00; 4]).await);
+    invalid(read(0x03, &[0x00; 2]).await);
+    invalid(read(0x20, &[0x00; 2]).await);
+    // Timers and counters are structures.
+    assert_eq!(read(0x0A, &[0x00; 4]).await.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+}
+
+#[tokio::test]
+async fn recorded_password_masked() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    server.set_remote_password(Some("secret"));
+    let mut props = server.connection_props();
+    props.remote_password = Some(RemotePassword::new("secret"));
+
+    let transport = RecordingTransport::new(TcpTransport::new(&props));
+    let log = transport.log();
+    let client = SLMPClient::with_transport(props.clone(), transport);
+    client.connect().await.unwrap();
+    client.close().await;
+    let log: SessionLog = log.lock().unwrap().clone();
+    assert_eq!(log.exchanges.len(), 2);
+
+    // Neither the frames, their Debug, their JSON nor their decoded form holds the password.
+    let contains_password = |bytes: &[u8]| bytes.windows(6).any(|x| x == b"secret");
+    assert!(log.exchanges.iter().all(|x| !contains_password(&x.request)));
+    assert!(!format!("{log:?}").contains("115, 101, 99, 114, 101, 116"));
+    assert!(!serde_json::to_string(&log).unwrap().contains("115,101,99,114,101,116"));
+    for x in &log.exchanges {
+        let packet = frame::SlmpCommandPacket::try_from(&x.request[..]).unwrap();
+        assert!(packet.to_string().contains("2A, 2A, 2A, 2A, 2A, 2A"));
+    }
+
+    // The masked session is still replayed with the password.
+    let client = SLMPClient::with_transport(props, ReplayTransport::new(log, ReplayMatch::Exact));
+    client.connect().await.unwrap();
+    client.close().await;
+}
+
+#[tokio::test]
+async fn read_only() {
+    let (server, mut client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+    server.set_word(d0, 7);
+    client.set_read_only(true).unwrap();
+    assert!(client.is_read_only());
+
+    // Writes are refused before anything is sent; reads work.
+    let sent = client.transferred_bytes().0;
+    let denied = |e: std::io::Error| assert_eq!(e.kind(), std::io::ErrorKind::PermissionDenied);
+    denied(client.bulk_write(d0, &[TypedData::U16(1)]).await.unwrap_err());
+    denied(client.random_write(&[DeviceData { device: d0, data: TypedData::U16(1) }]).await.unwrap_err());
+    denied(client.write_string(d0, "AB", 2).await.unwrap_err());
+    denied(client.write_labels(&[("Speed", TypedData::U16(1))]).await.unwrap_err());
+    denied(client.run_cpu().await.unwrap_err());
+    denied(client.stop_cpu().await.unwrap_err());
+    denied(client.clear_latch().await.unwrap_err());
+    denied(client.reset_cpu().await.unwrap_err());
+    denied(client.set_clock(PLCDateTime { year: 2026, month: 10, day: 16, hour: 9, minute: 0, second: 0, day_of_week: 5 }).await.unwrap_err());
+    denied(client.upload_file(FileDrive::R(FileDriveForR::DataMemory), "RECIPE.CSV", b"data", true).await.unwrap_err());
+    denied(client.delete_file(FileDrive::R(FileDriveForR::DataMemory), "RECIPE.CSV").await.unwrap_err());
+    assert_eq!(client.transferred_bytes().0, sent);
+    assert_eq!(server.word(d0), 7);
+    assert_eq!(client.bulk_read(d0, 1, DataType::U16).await.unwrap()[0].data, TypedData::U16(7));
+
+    // A clone shares the mode and cannot lift it; the client can once it has no clones.
+    let mut clone = client.clone();
+    assert!(clone.is_read_only());
+    denied(clone.set_read_only(false).unwrap_err());
+    drop(clone);
+    client.set_read_only(false).unwrap();
+    client.bulk_write(d0, &[TypedData::U16(1)]).await.unwrap();
+    assert_eq!(server.word(d0), 1);
+}
+
+#[tokio::test]
+async fn remote_password() {
+    let server = MockServer::start(CPU::R).await.unwrap();
+    server.set_remote_password(Some("secret"));
+    let d0 = Device { device_type: DeviceType::D, address: 0 };
+
+    // A scope locks again on success, on an error and when it is dropped halfway.
+    let client = SLMPClient::new(server.connection_props());
+    client.connect().await.unwrap();
+    let value = client.unlock_scope("secret", async |client| {
+        assert!(server.is_remote_unlocked());
+        client.bulk_read(d0, 1, DataType::U16).await
+    }).await.unwrap();
+    assert_eq!(value[0].data, TypedData::U16(0));
+    assert!(!server.is_remote_unlocked());
+
+    let e = client.unlock_scope("secret", async |_| -> std::io::Result<()> {
+        Err(std::io::Error::other("failed"))
+    }).await.unwrap_err();
+    assert_eq!(e.to_string(), "failed");
+    assert!(!server.is_remote_unlocked());
+
+    let scope = client.unlock_scope("secret", async |_| {
+        tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+        Ok(())
+    });
+    tokio::time::timeout(std::time::Duration::from_millis(100), scope).await.unwrap_err();
+    for _ in 0..100 {
+        if !server.is_remote_unlocked() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert!(!server.is_remote_unlocked());
+
+    // A wrong password is an end code, and one of a wrong length is refused before anything is sent.
+    assert!(client.unlock_scope("wrong!", async |_| Ok(())).await.is_err());
+    let sent = client.transferred_bytes().0;
+    let e = client.unlock_scope("abc", async |_| Ok(())).await.unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::Unsupported);
+    assert_eq!(client.transferred_bytes().0, sent);
+    client.close().await;
+
+    // The props unlock on connect and lock on close, without showing the password.
+    let mut props = server.connection_props();
+    props.remote_password = Some(RemotePassword::new("secret"));
+    assert!(!format!("{props:?}").contains("secret"));
+    let client = SLMPClient::new(props.clone());
+    client.connect().await.unwrap();
+    assert!(server.is_remote_unlocked());
+    client.close().await;
+    assert!(!server.is_remote_unlocked());
+
+    props.remote_password = Some(RemotePassword::new("wrong!"));
+    let client = SLMPClient::new(props);
+    assert!(client.connect().await.is_err());
+    assert!(client.bulk_read(d0, 1, DataType::U16).await.is_err());
+}
+
+#[tokio::test]
+async fn loopback() {
+    let (_server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+
+    client.loopback(None).await.unwrap();
+    let data: Vec<u8> = (0..960).map(|i| b' ' + (i % 95) as u8).collect();
+    client.loopback(Some(&data)).await.unwrap();
+    client.loopback(Some(b"0")).await.unwrap();
+
+    // Data out of range is refused before anything is sent.
+    let sent = client.transferred_bytes().0;
+    for data in [&b""[..], &[b'A'; 961], b"AB\r\n", &[0x80]] {
+        assert_eq!(client.loopback(Some(data)).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    }
+    assert_eq!(client.transferred_bytes().0, sent);
+
+    // Anything but the data sent is a mismatch.
+    let client = canned_response(CPU::R, [&4u16.to_le_bytes()[..], b"ABCE"].concat()).await;
+    assert_eq!(client.loopback(Some(b"ABCD")).await.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    let client = canned_response(CPU::R, [&4u16.to_le_bytes()[..], b"ABCDE"].concat()).await;
+    assert_eq!(client.loopback(Some(b"ABCD")).await.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[tokio::test]
+async fn typed_block_read() {
+    let (_server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let d = |address| Device { device_type: DeviceType::D, address };
+    let m = |address| Device { device_type: DeviceType::M, address };
+    client.bulk_write(d(0), &[TypedData::F32(1.5), TypedData::F32(-2.25)]).await.unwrap();
+    client.bulk_write(d(100), &[TypedData::U32(0x12345678), TypedData::U16(7)]).await.unwrap();
+    client.bulk_write(d(200), &[TypedData::F64(0.125)]).await.unwrap();
+    client.bulk_write(m(0), &[TypedData::I32(-2)]).await.unwrap();
+    client.bulk_write(m(100), &[TypedData::Bool(true)]).await.unwrap();
+
+    let block = |access_type, start_device, size, data_type| TypedDeviceBlock { access_type, start_device, size, data_type };
+    let data = client.block_read_typed(&[
+        block(AccessType::Word, d(100), 3, None),
+        block(AccessType::Word, d(0), 4, Some(DataType::F32)),
+        block(AccessType::Word, d(200), 4, Some(DataType::F64)),
+        block(AccessType::Word, m(0), 2, Some(DataType::I32)),
+        block(AccessType::Bit, m(100), 1, None),
+    ]).await.unwrap();
+    assert_eq!(data, [
+        DeviceData { device: d(0), data: TypedData::F32(1.5) },
+        DeviceData { device: d(2), data: TypedData::F32(-2.25) },
+        DeviceData { device: m(0), data: TypedData::I32(-2) },
+        DeviceData { device: d(100), data: TypedData::U16(0x5678) },
+        DeviceData { device: d(101), data: TypedData::U16(0x1234) },
+        DeviceData { device: d(102), data: TypedData::U16(7) },
+        DeviceData { device: d(200), data: TypedData::F64(0.125) },
+        DeviceData { device: m(100), data: TypedData::Bool(true) },
+    ]);
+
+    // BCD blocks are checked digit by digit: D1 holds 0x3fc0 (the high word of 1.5).
+    let data = client.block_read_typed(&[block(AccessType::Word, d(101), 1, Some(DataType::Bcd16))]).await.unwrap();
+    assert_eq!(data, [DeviceData { device: d(101), data: TypedData::Bcd16(1234) }]);
+    for blocks in [
+        [block(AccessType::Word, d(1), 1, Some(DataType::Bcd16))],
+        [block(AccessType::Word, d(0), 2, Some(DataType::Bcd32))],
+    ] {
+        assert_eq!(client.block_read_typed(&blocks).await.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    // Sizes must divide into the type, and only word blocks take a type.
+    let sent = client.transferred_bytes().0;
+    for blocks in [
+        [block(AccessType::Word, d(0), 3, Some(DataType::F32))],
+        [block(AccessType::Word, d(0), 2, Some(DataType::F64))],
+        [block(AccessType::Bit, m(0), 32, Some(DataType::U32))],
+        [block(AccessType::Word, d(0), 1, Some(DataType::Bool))],
+        [block(AccessType::Word, d(0), 1, Some(DataType::String(0)))],
+    ] {
+        assert_eq!(client.block_read_typed(&blocks).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    }
+    assert_eq!(client.transferred_bytes().0, sent);
+}
+
+#[tokio::test]
+async fn block_limits() {
+    for (cpu, max_blocks) in [(CPU::Q, 120), (CPU::R, 60)] {
+        let (server, client) = MockServer::start_connected(cpu).await.unwrap();
+        let d = |address| Device { device_type: DeviceType::D, address };
+        let m = |address| Device { device_type: DeviceType::M, address };
+        let word_blocks = |n: usize| (0..n).map(|i| DeviceBlock { access_type: AccessType::Word, start_device: d(10 * i), size: 1 }).collect::<Vec<_>>();
+        let too_many = |e: std::io::Error| {
+            assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+            assert!(e.to_string().contains(&format!("{} > {max_blocks}", max_blocks + 1)), "{e}");
+        };
+
+        // Blocks at the limit are sent, one over is refused before anything is sent.
+        assert_eq!(client.block_read(&word_blocks(max_blocks)).await.unwrap().len(), max_blocks);
+        let sent = client.transferred_bytes().0;
+        too_many(client.block_read(&word_blocks(max_blocks + 1)).await.unwrap_err());
+        let values = vec![TypedData::U16(1)];
+        let data: Vec<BlockedDeviceData> = (0..=max_blocks).map(|i| BlockedDeviceData { access_type: AccessType::Word, start_device: d(10 * i), data: &values }).collect();
+        too_many(client.block_write(&data).await.unwrap_err());
+        assert_eq!(client.transferred_bytes().0, sent);
+        client.block_write(&data[..max_blocks]).await.unwrap();
+
+        // 960 words at most, counting a bit block in words of 16 points.
+        let blocks = [
+            DeviceBlock { access_type: AccessType::Word, start_device: d(0), size: 959 },
+            DeviceBlock { access_type: AccessType::Bit, start_device: m(0), size: 16 },
+        ];
+        assert_eq!(client.block_read(&blocks).await.unwrap().len(), 959 + 16);
+        let blocks = [blocks[0], DeviceBlock { access_type: AccessType::Bit, start_device: m(0), size: 17 }];
+        assert!(client.block_read(&blocks).await.unwrap_err().to_string().contains("961 > 960"));
+
+        // Split requests take any number of blocks, and return them as a single request would.
+        server.set_bit(m(5), true).unwrap();
+        let mut blocks = word_blocks(2 * max_blocks + 1);
+        blocks.insert(0, DeviceBlock { access_type: AccessType::Bit, start_device: m(0), size: 8 });
+        blocks.push(DeviceBlock { access_type: AccessType::Word, start_device: d(5000), size: 900 });
+        let split = client.block_read_split(&blocks).await.unwrap();
+        assert_eq!(split.len(), 2 * max_blocks + 1 + 900 + 8);
+        assert_eq!(split[0].device, d(0));
+        assert_eq!(split[2 * max_blocks].device, d(10 * 2 * max_blocks));
+        assert_eq!(split[2 * max_blocks + 1].device, d(5000));
+        assert_eq!(split[2 * max_blocks + 1 + 900 + 5], DeviceData { device: m(5), data: TypedData::Bool(true) });
+
+        let data: Vec<BlockedDeviceData> = (0..2 * max_blocks).map(|i| BlockedDeviceData { access_type: AccessType::Word, start_device: d(10 * i), data: &values }).collect();
+        client.block_write_split(&data).await.unwrap();
+        assert_eq!(server.word(d(10 * (2 * max_blocks - 1))), 1);
+
+        // A block too large for a request fails the split before anything is sent.
+        let sent = client.transferred_bytes().0;
+        let blocks = [DeviceBlock { access_type: AccessType::Word, start_device: d(0), size: 1 }, DeviceBlock { access_type: AccessType::Word, start_device: d(10), size: 961 }];
+        assert!(client.block_read_split(&blocks).await.unwrap_err().to_string().contains("961 > 960"));
+        assert_eq!(client.block_read_split(&[]).await.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(client.transferred_bytes().0, sent);
+    }
+}
+
+#[tokio::test]
+async fn unaligned_bit_block_read() {
+    for cpu in [CPU::Q, CPU::R, CPU::F] {
+        let (server, client) = MockServer::start_connected(cpu).await.unwrap();
+        let m = |address| Device { device_type: DeviceType::M, address };
+        for address in [7, 8, 20, 21] {
+            server.set_bit(m(address), true).unwrap();
+        }
+
+        let data = client.block_read(&[DeviceBlock { access_type: AccessType::Bit, start_device: m(8), size: 13 }]).await.unwrap();
+        assert_eq!(data.first().unwrap().device, m(8));
+        assert_eq!(data.last().unwrap().device, m(20));
+        let set: Vec<usize> = data.iter().filter(|x| x.data == TypedData::Bool(true)).map(|x| x.device.address).collect();
+        assert_eq!(set, [8, 20]);
+    }
+}
+
+#[tokio::test]
+async fn bit_array_writes() {
+    for cpu in [CPU::Q, CPU::R, CPU::F] {
+        let (server, client) = MockServer::start_connected(cpu).await.unwrap();
+        let d = |address| Device { device_type: DeviceType::D, address };
+        let m = |address| Device { device_type: DeviceType::M, address };
+        let mut bits = [false; 16];
+        bits[0] = true;
+        bits[3] = true;
+        bits[15] = true;
+
+        // Bit 0 is the least significant bit of the word.
+        client.write_bits16(d(100), bits).await.unwrap();
+        assert_eq!(server.word(d(100)), 0x8009);
+        assert_eq!(client.bulk_read(d(100), 1, DataType::BitArray16).await.unwrap()[0].data, TypedData::BitArray16(bits));
+        assert_eq!(client.bulk_read(d(100), 1, DataType::U16).await.unwrap()[0].data, TypedData::U16(0x8009));
+
+        // Mixed with other types in bulk and word block writes; a word of M covers 16 points.
+        client.bulk_write(d(200), &[TypedData::U16(7), TypedData::BitArray16(bits), TypedData::Bool(true)]).await.unwrap();
+        assert_eq!((server.word(d(200)), server.word(d(201)), server.word(d(202))), (7, 0x8009, 1));
+        let values = [TypedData::BitArray16(bits), TypedData::U32(0x12345678)];
+        client.block_write(&[
+            BlockedDeviceData { access_type: AccessType::Word, start_device: d(300), data: &values },
+            BlockedDeviceData { access_type: AccessType::Word, start_device: m(16), data: &values[..1] },
+        ]).await.unwrap();
+        assert_eq!((server.word(d(300)), server.word(d(301)), server.word(d(302))), (0x8009, 0x5678, 0x1234));
+        assert!(server.bit(m(16)).unwrap() && server.bit(m(19)).unwrap() && server.bit(m(31)).unwrap() && !server.bit(m(17)).unwrap());
+
+        // A bit block takes only Bool.
+        let e = client.block_write(&[BlockedDeviceData { access_type: AccessType::Bit, start_device: m(0), data: &values[..1] }]).await.unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(!server.bit(m(0)).unwrap());
+    }
+}
+
+#[tokio::test]
+async fn repeated_monitor_devices() {
+    let (server, client) = MockServer::start_connected(CPU::R).await.unwrap();
+    let d = |address| Device { device_type: DeviceType::D, address };
+    client.bulk_write(d(100), &[TypedData::F32(1.5)]).await.unwrap();
+    server.set_word(d(200), 9);
+
+    // A repeated device takes its points once, and each entry gets its value.
+    let u16_100 = TypedDevice { device: d(100), data_type: DataType::U16 };
+    let f32_100 = TypedDevice { device: d(100), data_type: DataType::F32 };
+    let u16_200 = TypedDevice { device: d(200), data_type: DataType::U16 };
+    let devices = [f32_100, u16_200, u16_100, f32_100, u16_200];
+    let list = client.monitor_register(&devices).await.unwrap();
+    assert_eq!(list.sorted_devices.len(), 3);
+    assert_eq!(list.access_points(), 3);
+    assert_eq!(list.devices(), devices);
+
+    let expected: Vec<DeviceData> = [TypedData::F32(1.5), TypedData::U16(9), TypedData::U16(0), TypedData::F32(1.5), TypedData::U16(9)]
+        .into_iter()
+        .zip(devices)
+        .map(|(data, x)| DeviceData { device: x.device, data })
+        .collect();
+    assert_eq!(client.monitor_read(&list).await.unwrap(), expected);
+    assert_eq!(client.random_read(&devices).await.unwrap(), expected);
+}